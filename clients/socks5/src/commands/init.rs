@@ -77,6 +77,11 @@ pub(crate) struct Init {
     #[clap(long)]
     host: Option<IpAddr>,
 
+    /// The address on which the client will be serving DNS queries, resolving them through
+    /// the service provider instead of the local resolver
+    #[clap(long)]
+    dns_address: Option<SocketAddr>,
+
     #[clap(short, long, default_value_t = OutputFormat::default())]
     output: OutputFormat,
 }
@@ -93,6 +98,7 @@ impl From<Init> for OverrideConfig {
             nym_apis: init_config.common_args.nym_apis,
             ip: init_config.host,
             port: init_config.port,
+            dns_address: init_config.dns_address,
             use_anonymous_replies: init_config.use_reply_surbs,
             fastmode: init_config.common_args.fastmode,
             no_cover: init_config.common_args.no_cover,
@@ -22,7 +22,7 @@ use nym_client_core::error::ClientCoreError;
 use nym_config::OptionalSet;
 use nym_sphinx::params::{PacketSize, PacketType};
 use std::error::Error;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::OnceLock;
 
 pub(crate) mod build_info;
@@ -76,6 +76,7 @@ pub(crate) struct OverrideConfig {
     nym_apis: Option<Vec<url::Url>>,
     ip: Option<IpAddr>,
     port: Option<u16>,
+    dns_address: Option<SocketAddr>,
     use_anonymous_replies: Option<bool>,
     fastmode: bool,
     no_cover: bool,
@@ -150,6 +151,7 @@ pub(crate) fn override_config(config: Config, args: OverrideConfig) -> Config {
         .with_optional(Config::with_anonymous_replies, args.use_anonymous_replies)
         .with_optional(Config::with_port, args.port)
         .with_optional(Config::with_ip, args.ip)
+        .with_optional(Config::with_dns_bind_address, args.dns_address)
         .with_optional_base_custom_env(
             BaseClientConfig::with_custom_nym_apis,
             args.nym_apis,
@@ -15,7 +15,7 @@ use nym_client_core::client::base_client::storage::OnDiskPersistent;
 use nym_client_core::client::topology_control::geo_aware_provider::CountryGroup;
 use nym_socks5_client_core::NymClient;
 use nym_sphinx::addressing::clients::Recipient;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 
 #[derive(Args, Clone)]
 pub(crate) struct Run {
@@ -43,6 +43,11 @@ pub(crate) struct Run {
     #[clap(long)]
     host: Option<IpAddr>,
 
+    /// The address on which the client will be serving DNS queries, resolving them through
+    /// the service provider instead of the local resolver
+    #[clap(long)]
+    dns_address: Option<SocketAddr>,
+
     /// Set geo-aware mixnode selection when sending mixnet traffic, for experiments only.
     #[clap(long, hide = true, value_parser = validate_country_group, group="routing")]
     geo_routing: Option<CountryGroup>,
@@ -62,6 +67,7 @@ impl From<Run> for OverrideConfig {
             nym_apis: run_config.common_args.nym_apis,
             ip: run_config.host,
             port: run_config.port,
+            dns_address: run_config.dns_address,
             use_anonymous_replies: run_config.use_anonymous_replies,
             fastmode: run_config.common_args.fastmode,
             no_cover: run_config.common_args.no_cover,
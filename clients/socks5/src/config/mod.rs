@@ -12,7 +12,7 @@ use nym_config::{
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use std::io;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -136,6 +136,12 @@ impl Config {
         self
     }
 
+    #[must_use]
+    pub fn with_dns_bind_address(mut self, dns_bind_address: SocketAddr) -> Self {
+        self.core = self.core.with_dns_bind_address(dns_bind_address);
+        self
+    }
+
     pub fn with_anonymous_replies(mut self, anonymous_replies: bool) -> Self {
         self.core.socks5.send_anonymously = anonymous_replies;
         self
@@ -86,6 +86,11 @@ bind_adddress = '{{ core.socks5.bind_adddress }}'
 # Note that some service providers might not support this.
 send_anonymously = {{ core.socks5.send_anonymously }}
 
+# The address on which the client will be serving DNS queries (both over UDP and TCP),
+# resolving the A and AAAA records through the service provider rather than the local resolver.
+# If left empty, the DNS stub is not started.
+dns_bind_address = '{{ core.socks5.dns_bind_address }}'
+
 ##### logging configuration options #####
 
 [logging]
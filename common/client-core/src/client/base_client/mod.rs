@@ -4,11 +4,17 @@
 use super::packet_statistics_control::PacketStatisticsReporter;
 use super::received_buffer::ReceivedBufferMessage;
 use super::topology_control::geo_aware_provider::GeoAwareTopologyProvider;
-use crate::client::base_client::storage::gateway_details::GatewayDetailsStore;
+use crate::client::base_client::storage::gateway_details::{
+    GatewayDetailsStore, PersistedStandbyGateway,
+};
 use crate::client::base_client::storage::MixnetClientStorage;
 use crate::client::cover_traffic_stream::LoopCoverTrafficStream;
 use crate::client::inbound_messages::{InputMessage, InputMessageReceiver, InputMessageSender};
 use crate::client::key_manager::persistence::KeyStore;
+use crate::client::key_manager::ManagedKeys;
+use crate::client::mix_traffic::failover::{
+    FailoverGateway, GatewayClientFactory, RegisteredGateway,
+};
 use crate::client::mix_traffic::transceiver::{GatewayReceiver, GatewayTransceiver, RemoteGateway};
 use crate::client::mix_traffic::{BatchMixMessageSender, MixTrafficController};
 use crate::client::packet_statistics_control::PacketStatisticsControl;
@@ -22,24 +28,27 @@ use crate::client::replies::reply_controller::{ReplyControllerReceiver, ReplyCon
 use crate::client::replies::reply_storage::{
     CombinedReplyStorage, PersistentReplyStorage, ReplyStorageBackend, SentReplyKeys,
 };
+use crate::client::self_address::SelfAddress;
 use crate::client::topology_control::nym_api_provider::NymApiTopologyProvider;
 use crate::client::topology_control::{
     TopologyAccessor, TopologyRefresher, TopologyRefresherConfig,
 };
-use crate::config::{Config, DebugConfig};
+use crate::config::{Config, DebugConfig, GatewayEndpointConfig};
 use crate::error::ClientCoreError;
 use crate::init::{
-    setup_gateway,
+    setup_gateway, setup_standby_gateways,
     types::{GatewayDetails, GatewaySetup, InitialisationResult},
 };
 use crate::{config, spawn_future};
 use futures::channel::mpsc;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use nym_bandwidth_controller::BandwidthController;
 use nym_credential_storage::storage::Storage as CredentialStorage;
 use nym_crypto::asymmetric::encryption;
+use nym_gateway_client::client::InitGatewayClient;
 use nym_gateway_client::{
-    AcknowledgementReceiver, GatewayClient, MixnetMessageReceiver, PacketRouter,
+    AcknowledgementReceiver, BandwidthReportReceiver, BandwidthReportSender, GatewayClient,
+    MixnetMessageReceiver, PacketRouter,
 };
 use nym_sphinx::acknowledgements::AckKey;
 use nym_sphinx::addressing::clients::Recipient;
@@ -260,7 +269,7 @@ where
     fn start_cover_traffic_stream(
         debug_config: &DebugConfig,
         ack_key: Arc<AckKey>,
-        self_address: Recipient,
+        self_address: SelfAddress,
         topology_accessor: TopologyAccessor,
         mix_tx: BatchMixMessageSender,
         stats_tx: PacketStatisticsReporter,
@@ -340,11 +349,42 @@ where
         controller.start_with_shutdown(shutdown)
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn new_gateway_client(
+        config: &Config,
+        managed_keys: &ManagedKeys,
+        gateway_config: GatewayEndpointConfig,
+        authenticated_ephemeral_client: Option<InitGatewayClient>,
+        bandwidth_controller: Option<BandwidthController<C, S::CredentialStore>>,
+        packet_router: PacketRouter,
+        bandwidth_report_sender: BandwidthReportSender,
+        shutdown: TaskClient,
+    ) -> Result<GatewayClient<C, S::CredentialStore>, ClientCoreError> {
+        let gateway_client = if let Some(existing_client) = authenticated_ephemeral_client {
+            existing_client.upgrade(packet_router, bandwidth_controller, shutdown)
+        } else {
+            let cfg = gateway_config.try_into()?;
+            GatewayClient::new(
+                cfg,
+                managed_keys.identity_keypair(),
+                Some(managed_keys.must_get_gateway_shared_key()),
+                packet_router,
+                bandwidth_controller,
+                shutdown,
+            )
+            .with_disabled_credentials_mode(config.client.disabled_credentials_mode)
+            .with_response_timeout(config.debug.gateway_connection.gateway_response_timeout)
+        };
+
+        Ok(gateway_client.with_bandwidth_report_sender(bandwidth_report_sender))
+    }
+
     async fn start_gateway_client(
         config: &Config,
         initialisation_result: InitialisationResult,
         bandwidth_controller: Option<BandwidthController<C, S::CredentialStore>>,
         packet_router: PacketRouter,
+        bandwidth_report_sender: BandwidthReportSender,
         shutdown: TaskClient,
     ) -> Result<GatewayClient<C, S::CredentialStore>, ClientCoreError>
     where
@@ -357,22 +397,16 @@ where
             return Err(ClientCoreError::UnexpectedPersistedCustomGatewayDetails);
         };
 
-        let mut gateway_client =
-            if let Some(existing_client) = initialisation_result.authenticated_ephemeral_client {
-                existing_client.upgrade(packet_router, bandwidth_controller, shutdown)
-            } else {
-                let cfg = gateway_config.try_into()?;
-                GatewayClient::new(
-                    cfg,
-                    managed_keys.identity_keypair(),
-                    Some(managed_keys.must_get_gateway_shared_key()),
-                    packet_router,
-                    bandwidth_controller,
-                    shutdown,
-                )
-                .with_disabled_credentials_mode(config.client.disabled_credentials_mode)
-                .with_response_timeout(config.debug.gateway_connection.gateway_response_timeout)
-            };
+        let mut gateway_client = Self::new_gateway_client(
+            config,
+            &managed_keys,
+            gateway_config,
+            initialisation_result.authenticated_ephemeral_client,
+            bandwidth_controller,
+            packet_router,
+            bandwidth_report_sender,
+            shutdown,
+        )?;
 
         let gateway_id = gateway_client.gateway_identity();
 
@@ -392,12 +426,99 @@ where
         Ok(gateway_client)
     }
 
+    #[allow(clippy::too_many_arguments)]
+    async fn start_failover_gateway(
+        config: &Config,
+        initialisation_result: InitialisationResult,
+        standby_gateways: Vec<PersistedStandbyGateway>,
+        bandwidth_controller: Option<BandwidthController<C, S::CredentialStore>>,
+        packet_router: PacketRouter,
+        bandwidth_report_sender: BandwidthReportSender,
+        self_address: SelfAddress,
+        reply_controller_sender: ReplyControllerSender,
+        mut shutdown: TaskClient,
+    ) -> Result<FailoverGateway<GatewayClientFactory<C, S::CredentialStore>>, ClientCoreError>
+    where
+        <S::KeyStore as KeyStore>::StorageError: Send + Sync + 'static,
+        <S::CredentialStore as CredentialStorage>::StorageError: Send + Sync + 'static,
+    {
+        let managed_keys = initialisation_result.managed_keys;
+        let GatewayDetails::Configured(gateway_config) = initialisation_result.gateway_details
+        else {
+            return Err(ClientCoreError::UnexpectedPersistedCustomGatewayDetails);
+        };
+
+        let standby_gateways = standby_gateways
+            .into_iter()
+            .map(|standby| {
+                let shared_key = standby.shared_key()?;
+                Ok(RegisteredGateway::new(
+                    standby.details,
+                    Arc::new(shared_key),
+                ))
+            })
+            .collect::<Result<Vec<_>, ClientCoreError>>()?;
+
+        // losing connection to a gateway is no longer fatal, so make sure none of the gateway clients
+        // would cause premature shutdown
+        shutdown.mark_as_success();
+
+        let mut gateway_client = Self::new_gateway_client(
+            config,
+            &managed_keys,
+            gateway_config.clone(),
+            initialisation_result.authenticated_ephemeral_client,
+            bandwidth_controller,
+            packet_router.clone(),
+            bandwidth_report_sender.clone(),
+            shutdown.fork("primary_gateway"),
+        )?;
+
+        let authentication_result = gateway_client.authenticate_and_start().await;
+        if let Ok(shared_key) = &authentication_result {
+            managed_keys.ensure_gateway_key(Some(Arc::clone(shared_key)));
+        }
+
+        let primary_gateway =
+            RegisteredGateway::new(gateway_config, managed_keys.must_get_gateway_shared_key());
+
+        let client_factory = GatewayClientFactory::new(
+            managed_keys.identity_keypair(),
+            packet_router,
+            config.client.disabled_credentials_mode,
+            config.debug.gateway_connection.gateway_response_timeout,
+            shutdown,
+        )
+        .with_bandwidth_report_sender(bandwidth_report_sender);
+
+        let mut failover_gateway = FailoverGateway::new(
+            gateway_client,
+            primary_gateway,
+            standby_gateways,
+            client_factory,
+            self_address,
+            reply_controller_sender,
+        );
+
+        if let Err(err) = authentication_result {
+            warn!("Could not authenticate and start up the primary gateway connection - {err}");
+            failover_gateway.try_failover().await?;
+        }
+
+        Ok(failover_gateway)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn setup_gateway_transceiver(
         custom_gateway_transceiver: Option<Box<dyn GatewayTransceiver + Send>>,
         config: &Config,
         initialisation_result: InitialisationResult,
+        standby_gateways: Vec<PersistedStandbyGateway>,
         bandwidth_controller: Option<BandwidthController<C, S::CredentialStore>>,
         packet_router: PacketRouter,
+        bandwidth_report_sender: BandwidthReportSender,
+        self_address: SelfAddress,
+        reply_controller_sender: ReplyControllerSender,
         mut shutdown: TaskClient,
     ) -> Result<Box<dyn GatewayTransceiver + Send>, ClientCoreError>
     where
@@ -416,12 +537,30 @@ where
             };
         }
 
+        // if we have registered with any standby gateways, make sure we could switch to them
+        if !standby_gateways.is_empty() {
+            let failover_gateway = Self::start_failover_gateway(
+                config,
+                initialisation_result,
+                standby_gateways,
+                bandwidth_controller,
+                packet_router,
+                bandwidth_report_sender,
+                self_address,
+                reply_controller_sender,
+                shutdown,
+            )
+            .await?;
+            return Ok(Box::new(failover_gateway));
+        }
+
         // otherwise, setup normal gateway client, etc
         let gateway_client = Self::start_gateway_client(
             config,
             initialisation_result,
             bandwidth_controller,
             packet_router,
+            bandwidth_report_sender,
             shutdown,
         )
         .await?;
@@ -431,14 +570,16 @@ where
 
     fn setup_topology_provider(
         custom_provider: Option<Box<dyn TopologyProvider + Send + Sync>>,
-        provider_from_config: config::TopologyStructure,
+        topology_config: config::Topology,
         nym_api_urls: Vec<Url>,
     ) -> Box<dyn TopologyProvider + Send + Sync> {
         // if no custom provider was ... provided ..., create one using nym-api
-        custom_provider.unwrap_or_else(|| match provider_from_config {
+        custom_provider.unwrap_or_else(|| match topology_config.topology_structure {
             config::TopologyStructure::NymApi => Box::new(NymApiTopologyProvider::new(
                 nym_api_urls,
                 env!("CARGO_PKG_VERSION").to_string(),
+                topology_config.route_selection_performance_bias,
+                topology_config.route_selection_measure_rtt,
             )),
             config::TopologyStructure::GeoAware(group_by) => {
                 Box::new(GeoAwareTopologyProvider::new(
@@ -455,13 +596,18 @@ where
     async fn start_topology_refresher(
         topology_provider: Box<dyn TopologyProvider + Send + Sync>,
         topology_config: config::Topology,
+        nym_api_urls: Vec<Url>,
         topology_accessor: TopologyAccessor,
         local_gateway: &NodeIdentity,
         wait_for_gateway: bool,
         mut shutdown: TaskClient,
     ) -> Result<(), ClientCoreError> {
-        let topology_refresher_config =
+        let mut topology_refresher_config =
             TopologyRefresherConfig::new(topology_config.topology_refresh_rate);
+        if topology_config.subscribe_to_network_events {
+            topology_refresher_config =
+                topology_refresher_config.with_network_events_sources(nym_api_urls);
+        }
 
         let mut topology_refresher = TopologyRefresher::new(
             topology_refresher_config,
@@ -530,11 +676,14 @@ where
 
     fn start_mix_traffic_controller(
         gateway_transceiver: Box<dyn GatewayTransceiver + Send>,
+        bandwidth_reports: BandwidthReportReceiver,
         shutdown: TaskClient,
     ) -> BatchMixMessageSender {
         info!("Starting mix traffic controller...");
         let (mix_traffic_controller, mix_tx) = MixTrafficController::new(gateway_transceiver);
-        mix_traffic_controller.start_with_shutdown(shutdown);
+        mix_traffic_controller
+            .with_bandwidth_reports(bandwidth_reports)
+            .start_with_shutdown(shutdown);
         mix_tx
     }
 
@@ -578,6 +727,44 @@ where
         setup_gateway(setup_method, key_store, details_store).await
     }
 
+    async fn register_standby_gateways(
+        config: &Config,
+        initialisation_result: &InitialisationResult,
+        uses_custom_transceiver: bool,
+        details_store: &S::GatewayDetailsStore,
+        topology_accessor: &TopologyAccessor,
+    ) -> Vec<PersistedStandbyGateway>
+    where
+        <S::GatewayDetailsStore as GatewayDetailsStore>::StorageError: Sync + Send,
+    {
+        let count = config.debug.gateway_connection.standby_gateways;
+        if count == 0
+            || uses_custom_transceiver
+            || initialisation_result.gateway_details.is_custom()
+        {
+            return Vec::new();
+        }
+
+        let available_gateways = topology_accessor
+            .current_topology()
+            .await
+            .map(|topology| topology.gateways().to_vec())
+            .unwrap_or_default();
+
+        setup_standby_gateways(
+            details_store,
+            initialisation_result.managed_keys.identity_keypair(),
+            &available_gateways,
+            count,
+        )
+        .await
+        .unwrap_or_else(|err| {
+            // we can still carry on with just the primary gateway
+            warn!("failed to set up the standby gateways: {err}");
+            Vec::new()
+        })
+    }
+
     pub async fn start_base(mut self) -> Result<BaseClient, ClientCoreError>
     where
         S::ReplyStore: Send + Sync,
@@ -596,8 +783,6 @@ where
         )
         .await?;
 
-        let (reply_storage_backend, credential_store) = self.client_store.into_runtime_stores();
-
         // channels for inter-component communication
         // TODO: make the channels be internally created by the relevant components
         // rather than creating them here, so say for example the buffer controller would create the request channels
@@ -628,19 +813,16 @@ where
         let (reply_controller_sender, reply_controller_receiver) =
             reply_controller::requests::new_control_channels();
 
-        let self_address = Self::mix_address(&init_res);
+        let self_address = SelfAddress::new(Self::mix_address(&init_res));
+        let initial_address = self_address.get();
         let ack_key = init_res.managed_keys.ack_key();
         let encryption_keys = init_res.managed_keys.encryption_keypair();
 
         // the components are started in very specific order. Unless you know what you are doing,
         // do not change that.
-        let bandwidth_controller = self
-            .dkg_query_client
-            .map(|client| BandwidthController::new(credential_store, client));
-
         let topology_provider = Self::setup_topology_provider(
             self.custom_topology_provider.take(),
-            self.config.debug.topology.topology_structure,
+            self.config.debug.topology,
             self.config.get_nym_api_endpoints(),
         );
 
@@ -648,13 +830,28 @@ where
         Self::start_topology_refresher(
             topology_provider,
             self.config.debug.topology,
+            self.config.get_nym_api_endpoints(),
             shared_topology_accessor.clone(),
-            self_address.gateway(),
+            initial_address.gateway(),
             self.wait_for_gateway,
             shutdown.fork("topology_refresher"),
         )
         .await?;
 
+        let standby_gateways = Self::register_standby_gateways(
+            self.config,
+            &init_res,
+            self.custom_gateway_transceiver.is_some(),
+            self.client_store.gateway_details_store(),
+            &shared_topology_accessor,
+        )
+        .await;
+
+        let (reply_storage_backend, credential_store) = self.client_store.into_runtime_stores();
+        let bandwidth_controller = self
+            .dkg_query_client
+            .map(|client| BandwidthController::new(credential_store, client));
+
         let packet_stats_reporter =
             Self::start_packet_statistics_control(shutdown.fork("packet_statistics_control"));
 
@@ -664,12 +861,19 @@ where
             shutdown.get_handle().named("gateway-packet-router"),
         );
 
+        // reports of the remaining bandwidth pushed by the gateway, so that we could top it up in time
+        let (bandwidth_report_sender, bandwidth_report_receiver) = mpsc::unbounded();
+
         let gateway_transceiver = Self::setup_gateway_transceiver(
             self.custom_gateway_transceiver,
             self.config,
             init_res,
+            standby_gateways,
             bandwidth_controller,
             gateway_packet_router,
+            bandwidth_report_sender,
+            self_address.clone(),
+            reply_controller_sender.clone(),
             shutdown.fork("gateway_transceiver"),
         )
         .await?;
@@ -697,6 +901,7 @@ where
         // The MixTrafficController then sends the actual traffic
         let message_sender = Self::start_mix_traffic_controller(
             gateway_transceiver,
+            bandwidth_report_receiver,
             shutdown.fork("mix_traffic_controller"),
         );
 
@@ -711,7 +916,7 @@ where
         let controller_config = real_messages_control::Config::new(
             &self.config.debug,
             Arc::clone(&ack_key),
-            self_address,
+            self_address.clone(),
         );
 
         Self::start_real_traffic_controller(
@@ -748,10 +953,10 @@ where
         }
 
         debug!("Core client startup finished!");
-        debug!("The address of this client is: {self_address}");
+        debug!("The address of this client is: {initial_address}");
 
         Ok(BaseClient {
-            address: self_address,
+            address: initial_address,
             client_input: ClientInputStatus::AwaitingProducer {
                 client_input: ClientInput {
                     connection_command_sender: client_connection_tx,
@@ -17,6 +17,9 @@ use std::{fs, io};
 use time::OffsetDateTime;
 use url::Url;
 
+#[cfg(feature = "encrypted-surb-storage")]
+use crate::client::replies::reply_storage::encrypted_fs_backend;
+
 async fn setup_fresh_backend<P: AsRef<Path>>(
     db_path: P,
     surb_config: &config::ReplySurbs,
@@ -101,6 +104,67 @@ pub async fn setup_fs_reply_surb_backend<P: AsRef<Path>>(
     }
 }
 
+#[cfg(feature = "encrypted-surb-storage")]
+async fn setup_fresh_encrypted_backend<P: AsRef<Path>>(
+    storage_path: P,
+    passphrase: Option<&[u8]>,
+    surb_config: &config::ReplySurbs,
+) -> Result<encrypted_fs_backend::Backend, ClientCoreError> {
+    info!("creating fresh surb storage file");
+    let mut storage_backend =
+        match encrypted_fs_backend::Backend::init(storage_path, passphrase).await {
+            Ok(backend) => backend,
+            Err(err) => {
+                error!("failed to setup persistent storage backend for our reply needs: {err}");
+                return Err(ClientCoreError::SurbStorageError {
+                    source: Box::new(err),
+                });
+            }
+        };
+
+    let mem_store = CombinedReplyStorage::new(
+        surb_config.minimum_reply_surb_storage_threshold,
+        surb_config.maximum_reply_surb_storage_threshold,
+    );
+    storage_backend
+        .init_fresh(&mem_store)
+        .await
+        .map_err(|err| ClientCoreError::SurbStorageError {
+            source: Box::new(err),
+        })?;
+
+    Ok(storage_backend)
+}
+
+#[cfg(feature = "encrypted-surb-storage")]
+pub async fn setup_encrypted_fs_reply_surb_backend<P: AsRef<Path>>(
+    storage_path: P,
+    passphrase: Option<&[u8]>,
+    surb_config: &config::ReplySurbs,
+) -> Result<encrypted_fs_backend::Backend, ClientCoreError> {
+    // if the storage file doesnt exist, initialise fresh storage, otherwise attempt to load
+    // the existing one
+    let storage_path = storage_path.as_ref();
+    if storage_path.exists() {
+        info!("loading existing surb storage file");
+        match encrypted_fs_backend::Backend::try_load(storage_path, passphrase).await {
+            Ok(backend) => Ok(backend),
+            // don't throw away perfectly valid data just because the passphrase didn't match
+            Err(err) if err.is_passphrase_error() => Err(ClientCoreError::SurbStorageError {
+                source: Box::new(err),
+            }),
+            Err(err) => {
+                error!("failed to setup persistent storage backend for our reply needs: {err}. We're going to create a fresh storage instead. This behaviour might change in the future");
+
+                archive_corrupted_database(storage_path)?;
+                setup_fresh_encrypted_backend(storage_path, passphrase, surb_config).await
+            }
+        }
+    } else {
+        setup_fresh_encrypted_backend(storage_path, passphrase, surb_config).await
+    }
+}
+
 pub fn create_bandwidth_controller<St: CredentialStorage>(
     config: &Config,
     storage: St,
@@ -0,0 +1,494 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! File-based [`KeyStore`] and [`GatewayDetailsStore`] with optional encryption of the stored data.
+//!
+//! Both the client keys and the details of its gateway are kept in a single file that is fully
+//! rewritten whenever any of them changes. If a passphrase is provided, the data is encrypted with
+//! a [`StoreCipher`] derived from it, in the same way as the reply data of the
+//! [encrypted reply storage](crate::client::replies::reply_storage::encrypted_fs_backend).
+
+use crate::client::base_client::storage::gateway_details::{
+    GatewayDetailsStore, PersistedGatewayDetails,
+};
+use crate::client::key_manager::persistence::KeyStore;
+use crate::client::key_manager::KeyManager;
+use async_trait::async_trait;
+use log::info;
+use nym_crypto::asymmetric::{encryption, identity};
+use nym_gateway_requests::registration::handshake::SharedKeys;
+use nym_pemstore::traits::{PemStorableKey, PemStorableKeyPair};
+use nym_sphinx::acknowledgements::AckKey;
+use nym_store_cipher::{Aes256Gcm, EncryptedData, ExportedStoreCipher, StoreCipher};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+const CURRENT_STORAGE_VERSION: u8 = 1;
+const TEMPORARY_EXTENSION: &str = "tmp";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientDetailsStorageError {
+    #[error("failed to access the client details file - {source}")]
+    IoError {
+        #[from]
+        source: io::Error,
+    },
+
+    #[error("failed to serialize/deserialize the client details: {source}")]
+    SerializationError {
+        #[from]
+        source: serde_json::Error,
+    },
+
+    #[error("encountered issue with our storage encryption layer: {source}")]
+    CryptoStorageError {
+        #[from]
+        source: nym_store_cipher::Error,
+    },
+
+    #[error(
+        "attempted to unlock existing encrypted client details without providing a passphrase"
+    )]
+    NoPassphraseProvided,
+
+    #[error("attempted to access existing unencrypted client details with a passphrase")]
+    UnexpectedPassphraseProvided,
+
+    #[error("the client details file has been created with an unsupported version {received}. The current version is {current}")]
+    UnsupportedVersion { received: u8, current: u8 },
+
+    #[error("the stored client details are corrupted: {details}")]
+    CorruptedData { details: String },
+
+    #[error("the client keys haven't been stored yet")]
+    MissingKeys,
+
+    #[error("the gateway details haven't been stored yet")]
+    MissingGatewayDetails,
+}
+
+impl ClientDetailsStorageError {
+    /// Indicates whether the error was caused by an invalid (or missing) passphrase rather than
+    /// by the state of the stored data, in which case the data must not be discarded.
+    pub fn is_passphrase_error(&self) -> bool {
+        matches!(
+            self,
+            ClientDetailsStorageError::NoPassphraseProvided
+                | ClientDetailsStorageError::UnexpectedPassphraseProvided
+                | ClientDetailsStorageError::CryptoStorageError {
+                    source: nym_store_cipher::Error::InvalidImportPassphrase
+                        | nym_store_cipher::Error::VerificationPhraseMismatch
+                }
+        )
+    }
+}
+
+/// The content of the client details file.
+#[derive(Serialize, Deserialize)]
+struct StoredClientDetails {
+    version: u8,
+
+    /// Information required to re-derive the store cipher if the data is encrypted.
+    cipher: Option<ExportedStoreCipher>,
+
+    data: StoredClientData,
+}
+
+#[derive(Serialize, Deserialize)]
+enum StoredClientData {
+    Plaintext(ClientDetailsSnapshot),
+    Encrypted(EncryptedData),
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+struct ClientDetailsSnapshot {
+    keys: Option<StoredKeys>,
+    gateway_details: Option<PersistedGatewayDetails>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredKeys {
+    identity_private_key: Vec<u8>,
+    identity_public_key: Vec<u8>,
+    encryption_private_key: Vec<u8>,
+    encryption_public_key: Vec<u8>,
+    ack_key: Vec<u8>,
+    gateway_shared_key: Option<Vec<u8>>,
+}
+
+impl StoredKeys {
+    fn new(keys: &KeyManager) -> Self {
+        let (identity_private_key, identity_public_key) =
+            keypair_bytes(keys.identity_keypair().as_ref());
+        let (encryption_private_key, encryption_public_key) =
+            keypair_bytes(keys.encryption_keypair().as_ref());
+
+        StoredKeys {
+            identity_private_key,
+            identity_public_key,
+            encryption_private_key,
+            encryption_public_key,
+            ack_key: keys.ack_key().to_bytes(),
+            gateway_shared_key: keys
+                .gateway_shared_key()
+                .map(|shared_keys| PemStorableKey::to_bytes(shared_keys.as_ref())),
+        }
+    }
+
+    fn recover(&self) -> Result<KeyManager, ClientDetailsStorageError> {
+        let identity_keypair: identity::KeyPair = recover_keypair(
+            &self.identity_private_key,
+            &self.identity_public_key,
+            "identity",
+        )?;
+        let encryption_keypair: encryption::KeyPair = recover_keypair(
+            &self.encryption_private_key,
+            &self.encryption_public_key,
+            "encryption",
+        )?;
+        let ack_key: AckKey = recover_key(&self.ack_key, "ack")?;
+        let gateway_shared_key: Option<SharedKeys> = self
+            .gateway_shared_key
+            .as_ref()
+            .map(|raw| recover_key(raw, "gateway shared"))
+            .transpose()?;
+
+        Ok(KeyManager::from_keys(
+            identity_keypair,
+            encryption_keypair,
+            gateway_shared_key,
+            ack_key,
+        ))
+    }
+}
+
+fn keypair_bytes<T: PemStorableKeyPair>(keypair: &T) -> (Vec<u8>, Vec<u8>) {
+    (
+        keypair.private_key().to_bytes(),
+        keypair.public_key().to_bytes(),
+    )
+}
+
+fn recover_key<T: PemStorableKey>(raw: &[u8], name: &str) -> Result<T, ClientDetailsStorageError> {
+    T::from_bytes(raw).map_err(|err| ClientDetailsStorageError::CorruptedData {
+        details: format!("failed to recover the {name} key: {err}"),
+    })
+}
+
+fn recover_keypair<T: PemStorableKeyPair>(
+    private_key: &[u8],
+    public_key: &[u8],
+    name: &str,
+) -> Result<T, ClientDetailsStorageError> {
+    let private_key = recover_key(private_key, &format!("{name} private"))?;
+    let public_key = recover_key(public_key, &format!("{name} public"))?;
+    Ok(T::from_keys(private_key, public_key))
+}
+
+/// Persistent storage of the client keys and the details of its gateway.
+pub struct EncryptedClientDetails {
+    storage_path: PathBuf,
+    store_cipher: Option<StoreCipher<Aes256Gcm>>,
+    details: Mutex<ClientDetailsSnapshot>,
+}
+
+impl EncryptedClientDetails {
+    /// Loads the existing client details from the provided path or creates new, empty, storage
+    /// if the file doesn't exist yet. The passphrase must match the one used when
+    /// the storage was created.
+    pub fn open<P: AsRef<Path>>(
+        storage_path: P,
+        passphrase: Option<&[u8]>,
+    ) -> Result<Self, ClientDetailsStorageError> {
+        if storage_path.as_ref().exists() {
+            info!("loading existing client details");
+            Self::try_load(storage_path, passphrase)
+        } else {
+            Self::init(storage_path, passphrase)
+        }
+    }
+
+    /// Creates new storage at the provided path. Nothing is written to the disk until
+    /// either the keys or the gateway details are stored.
+    pub fn init<P: AsRef<Path>>(
+        storage_path: P,
+        passphrase: Option<&[u8]>,
+    ) -> Result<Self, ClientDetailsStorageError> {
+        let storage_path = storage_path.as_ref().to_path_buf();
+        if let Some(parent) = storage_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let store_cipher = match passphrase {
+            Some(passphrase) => {
+                info!("deriving new encryption key for the client details");
+                Some(StoreCipher::new_with_default_kdf(passphrase)?)
+            }
+            None => {
+                info!("the client details will not use any encryption");
+                None
+            }
+        };
+
+        Ok(EncryptedClientDetails {
+            storage_path,
+            store_cipher,
+            details: Mutex::new(Default::default()),
+        })
+    }
+
+    /// Attempts to load the existing client details from the provided path.
+    pub fn try_load<P: AsRef<Path>>(
+        storage_path: P,
+        passphrase: Option<&[u8]>,
+    ) -> Result<Self, ClientDetailsStorageError> {
+        let storage_path = storage_path.as_ref().to_path_buf();
+        let stored: StoredClientDetails = serde_json::from_slice(&fs::read(&storage_path)?)?;
+
+        if stored.version != CURRENT_STORAGE_VERSION {
+            return Err(ClientDetailsStorageError::UnsupportedVersion {
+                received: stored.version,
+                current: CURRENT_STORAGE_VERSION,
+            });
+        }
+
+        let store_cipher = match (stored.cipher, passphrase) {
+            (Some(exported), Some(passphrase)) => {
+                Some(StoreCipher::import_aes256gcm(passphrase, exported)?)
+            }
+            (Some(_), None) => return Err(ClientDetailsStorageError::NoPassphraseProvided),
+            (None, Some(_)) => return Err(ClientDetailsStorageError::UnexpectedPassphraseProvided),
+            (None, None) => None,
+        };
+
+        let details = match (stored.data, &store_cipher) {
+            (StoredClientData::Plaintext(details), None) => details,
+            (StoredClientData::Encrypted(encrypted), Some(cipher)) => {
+                cipher.decrypt_json_value(encrypted)?
+            }
+            _ => {
+                return Err(ClientDetailsStorageError::CorruptedData {
+                    details: "the stored data does not match the declared encryption".to_string(),
+                })
+            }
+        };
+
+        Ok(EncryptedClientDetails {
+            storage_path,
+            store_cipher,
+            details: Mutex::new(details),
+        })
+    }
+
+    /// Atomically replaces the content of the storage file with the provided details.
+    fn write_storage(
+        &self,
+        details: &ClientDetailsSnapshot,
+    ) -> Result<(), ClientDetailsStorageError> {
+        let (cipher, data) = match &self.store_cipher {
+            Some(cipher) => (
+                Some(cipher.export_aes256gcm()?),
+                StoredClientData::Encrypted(cipher.encrypt_json_value(details)?),
+            ),
+            None => (None, StoredClientData::Plaintext(details.clone())),
+        };
+
+        let stored = StoredClientDetails {
+            version: CURRENT_STORAGE_VERSION,
+            cipher,
+            data,
+        };
+
+        let new_extension = if let Some(existing_extension) =
+            self.storage_path.extension().and_then(|ext| ext.to_str())
+        {
+            format!("{existing_extension}.{TEMPORARY_EXTENSION}")
+        } else {
+            TEMPORARY_EXTENSION.to_string()
+        };
+        let mut temp_path = self.storage_path.clone();
+        temp_path.set_extension(new_extension);
+
+        fs::write(&temp_path, serde_json::to_vec(&stored)?)?;
+        fs::rename(&temp_path, &self.storage_path)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl KeyStore for EncryptedClientDetails {
+    type StorageError = ClientDetailsStorageError;
+
+    async fn load_keys(&self) -> Result<KeyManager, Self::StorageError> {
+        self.details
+            .lock()
+            .await
+            .keys
+            .as_ref()
+            .ok_or(ClientDetailsStorageError::MissingKeys)?
+            .recover()
+    }
+
+    async fn store_keys(&self, keys: &KeyManager) -> Result<(), Self::StorageError> {
+        let mut details = self.details.lock().await;
+        let mut updated = details.clone();
+        updated.keys = Some(StoredKeys::new(keys));
+
+        // only update the in-memory state if we managed to persist the change
+        self.write_storage(&updated)?;
+        *details = updated;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GatewayDetailsStore for EncryptedClientDetails {
+    type StorageError = ClientDetailsStorageError;
+
+    async fn load_gateway_details(&self) -> Result<PersistedGatewayDetails, Self::StorageError> {
+        self.details
+            .lock()
+            .await
+            .gateway_details
+            .clone()
+            .ok_or(ClientDetailsStorageError::MissingGatewayDetails)
+    }
+
+    async fn store_gateway_details(
+        &self,
+        gateway_details: &PersistedGatewayDetails,
+    ) -> Result<(), Self::StorageError> {
+        let mut details = self.details.lock().await;
+        let mut updated = details.clone();
+        updated.gateway_details = Some(gateway_details.clone());
+
+        self.write_storage(&updated)?;
+        *details = updated;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::base_client::storage::gateway_details::PersistedGatewayConfig;
+    use crate::config::GatewayEndpointConfig;
+    use rand::rngs::OsRng;
+
+    fn gateway_details(shared_keys: &SharedKeys) -> PersistedGatewayDetails {
+        PersistedGatewayDetails::Default(PersistedGatewayConfig::new(
+            GatewayEndpointConfig {
+                gateway_id: "gateway".to_string(),
+                gateway_owner: "owner".to_string(),
+                gateway_listener: "ws://1.2.3.4:9000".to_string(),
+            },
+            shared_keys,
+        ))
+    }
+
+    fn keys_with_gateway() -> KeyManager {
+        let mut rng = OsRng;
+        let shared_keys = SharedKeys::try_from_bytes(&[42u8; 32]).unwrap();
+        KeyManager::from_keys(
+            identity::KeyPair::new(&mut rng),
+            encryption::KeyPair::new(&mut rng),
+            Some(shared_keys),
+            AckKey::new(&mut rng),
+        )
+    }
+
+    fn assert_same_keys(a: &KeyManager, b: &KeyManager) {
+        assert_eq!(
+            a.identity_keypair().private_key().to_bytes(),
+            b.identity_keypair().private_key().to_bytes()
+        );
+        assert_eq!(
+            a.encryption_keypair().private_key().to_bytes(),
+            b.encryption_keypair().private_key().to_bytes()
+        );
+        assert_eq!(a.ack_key().to_bytes(), b.ack_key().to_bytes());
+        assert_eq!(
+            a.gateway_shared_key().map(|k| k.to_bytes()),
+            b.gateway_shared_key().map(|k| k.to_bytes())
+        );
+    }
+
+    #[tokio::test]
+    async fn details_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("client_details.json");
+        let passphrase = Some(b"secret passphrase".as_slice());
+
+        let keys = keys_with_gateway();
+        let details = gateway_details(&keys.gateway_shared_key().unwrap());
+
+        let store = EncryptedClientDetails::open(&path, passphrase).unwrap();
+        assert!(store.load_keys().await.is_err());
+        assert!(store.load_gateway_details().await.is_err());
+        store.store_keys(&keys).await.unwrap();
+        store.store_gateway_details(&details).await.unwrap();
+        drop(store);
+
+        let reopened = EncryptedClientDetails::open(&path, passphrase).unwrap();
+        let loaded_keys = reopened.load_keys().await.unwrap();
+        assert_same_keys(&keys, &loaded_keys);
+
+        let PersistedGatewayDetails::Default(loaded) =
+            reopened.load_gateway_details().await.unwrap()
+        else {
+            panic!("unexpected gateway details")
+        };
+        assert!(loaded.verify(&loaded_keys.gateway_shared_key().unwrap()));
+        assert_eq!(loaded.details.gateway_id, "gateway");
+    }
+
+    #[tokio::test]
+    async fn nothing_is_stored_in_plaintext_with_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("client_details.json");
+
+        let keys = keys_with_gateway();
+        let store = EncryptedClientDetails::open(&path, Some(b"passphrase")).unwrap();
+        store.store_keys(&keys).await.unwrap();
+        store
+            .store_gateway_details(&gateway_details(&keys.gateway_shared_key().unwrap()))
+            .await
+            .unwrap();
+
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("ws://1.2.3.4:9000"));
+        let stored: StoredClientDetails = serde_json::from_str(&raw).unwrap();
+        assert!(stored.cipher.is_some());
+        assert!(matches!(stored.data, StoredClientData::Encrypted(_)));
+    }
+
+    #[tokio::test]
+    async fn passphrase_must_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let encrypted_path = dir.path().join("encrypted.json");
+        let plaintext_path = dir.path().join("plaintext.json");
+
+        let keys = keys_with_gateway();
+        let encrypted = EncryptedClientDetails::open(&encrypted_path, Some(b"passphrase")).unwrap();
+        encrypted.store_keys(&keys).await.unwrap();
+        let plaintext = EncryptedClientDetails::open(&plaintext_path, None).unwrap();
+        plaintext.store_keys(&keys).await.unwrap();
+
+        for (path, passphrase) in [
+            (&encrypted_path, Some(b"wrong passphrase".as_slice())),
+            (&encrypted_path, None),
+            (&plaintext_path, Some(b"passphrase".as_slice())),
+        ] {
+            let Err(err) = EncryptedClientDetails::open(path, passphrase) else {
+                panic!("managed to open the storage with invalid passphrase")
+            };
+            assert!(err.is_passphrase_error());
+        }
+
+        let plaintext = EncryptedClientDetails::open(&plaintext_path, None).unwrap();
+        assert_same_keys(&keys, &plaintext.load_keys().await.unwrap());
+    }
+}
@@ -78,6 +78,23 @@ pub struct PersistedGatewayConfig {
 
     /// Actual gateway details being persisted.
     pub details: GatewayEndpointConfig,
+
+    /// Gateways the client has registered with in addition to the primary one,
+    /// so that it could switch to them should the primary become unavailable.
+    #[serde(default)]
+    pub standby_gateways: Vec<PersistedStandbyGateway>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PersistedStandbyGateway {
+    /// The shared keys derived during registration with this gateway.
+    // unlike the primary gateway, whose keys are kept in the key store,
+    // the standby keys are not used until the failover actually happens
+    #[serde(with = "base64")]
+    shared_key: Vec<u8>,
+
+    /// Details of the standby gateway.
+    pub details: GatewayEndpointConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,7 +114,11 @@ impl PersistedGatewayConfig {
         key_hasher.update(&key_bytes);
         let key_hash = key_hasher.finalize().to_vec();
 
-        PersistedGatewayConfig { key_hash, details }
+        PersistedGatewayConfig {
+            key_hash,
+            details,
+            standby_gateways: Vec::new(),
+        }
     }
 
     pub fn verify(&self, shared_key: &SharedKeys) -> bool {
@@ -111,6 +132,24 @@ impl PersistedGatewayConfig {
     }
 }
 
+impl PersistedStandbyGateway {
+    pub fn new(details: GatewayEndpointConfig, shared_key: &SharedKeys) -> Self {
+        PersistedStandbyGateway {
+            shared_key: shared_key.to_bytes(),
+            details,
+        }
+    }
+
+    pub fn shared_key(&self) -> Result<SharedKeys, ClientCoreError> {
+        SharedKeys::try_from_bytes(&self.shared_key).map_err(|source| {
+            ClientCoreError::MalformedStandbyGatewayKey {
+                gateway_id: self.details.gateway_id.clone(),
+                source,
+            }
+        })
+    }
+}
+
 impl<T> PersistedGatewayDetails<T> {
     pub fn new(
         details: GatewayDetails<T>,
@@ -15,10 +15,14 @@ use nym_credential_storage::storage::Storage as CredentialStorage;
 
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
 use crate::client::base_client::non_wasm_helpers;
+#[cfg(all(not(target_arch = "wasm32"), feature = "encrypted-surb-storage"))]
+use crate::client::base_client::storage::encrypted_client_details::EncryptedClientDetails;
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
 use crate::client::base_client::storage::gateway_details::OnDiskGatewayDetails;
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
 use crate::client::key_manager::persistence::OnDiskKeys;
+#[cfg(all(not(target_arch = "wasm32"), feature = "encrypted-surb-storage"))]
+use crate::client::replies::reply_storage::encrypted_fs_backend;
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
 use crate::client::replies::reply_storage::fs_backend;
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
@@ -28,6 +32,8 @@ use crate::error::ClientCoreError;
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
 use nym_credential_storage::persistent_storage::PersistentStorage as PersistentCredentialStorage;
 
+#[cfg(all(not(target_arch = "wasm32"), feature = "encrypted-surb-storage"))]
+pub mod encrypted_client_details;
 pub mod gateway_details;
 
 // TODO: ideally this should be changed into
@@ -87,6 +93,90 @@ impl MixnetClientStorage for Ephemeral {
     }
 }
 
+/// Client storage that persists its keys, the details of its gateway and all the reply-related data,
+/// such as reply SURBs received from anonymous senders, in files optionally encrypted with
+/// a passphrase. This way the client keeps its address across restarts and can keep replying
+/// to anonymous senders with the SURBs it has already received.
+/// Note that the credentials are only ever kept in memory.
+#[cfg(all(not(target_arch = "wasm32"), feature = "encrypted-surb-storage"))]
+pub struct EncryptedPersistent {
+    client_details_store: EncryptedClientDetails,
+    reply_store: encrypted_fs_backend::Backend,
+    credential_store: EphemeralCredentialStorage,
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "encrypted-surb-storage"))]
+impl EncryptedPersistent {
+    pub const CLIENT_DETAILS_FILENAME: &'static str = "client_details.json";
+    pub const REPLY_STORE_FILENAME: &'static str = "reply_store.json";
+
+    pub fn new(
+        client_details_store: EncryptedClientDetails,
+        reply_store: encrypted_fs_backend::Backend,
+    ) -> Self {
+        EncryptedPersistent {
+            client_details_store,
+            reply_store,
+            credential_store: Default::default(),
+        }
+    }
+
+    /// Opens (or creates) the storage files inside the provided directory.
+    /// If the passphrase is specified, it must match the one used when the storage was created.
+    pub async fn from_directory<P: AsRef<std::path::Path>>(
+        storage_directory: P,
+        passphrase: Option<&[u8]>,
+        debug_config: &config::DebugConfig,
+    ) -> Result<Self, ClientCoreError> {
+        let storage_directory = storage_directory.as_ref();
+
+        let client_details_store = EncryptedClientDetails::open(
+            storage_directory.join(Self::CLIENT_DETAILS_FILENAME),
+            passphrase,
+        )
+        .map_err(|source| ClientCoreError::KeyStoreError {
+            source: Box::new(source),
+        })?;
+
+        let reply_store = non_wasm_helpers::setup_encrypted_fs_reply_surb_backend(
+            storage_directory.join(Self::REPLY_STORE_FILENAME),
+            passphrase,
+            &debug_config.reply_surbs,
+        )
+        .await?;
+
+        Ok(Self::new(client_details_store, reply_store))
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "encrypted-surb-storage"))]
+impl MixnetClientStorage for EncryptedPersistent {
+    type KeyStore = EncryptedClientDetails;
+    type ReplyStore = encrypted_fs_backend::Backend;
+    type CredentialStore = EphemeralCredentialStorage;
+    type GatewayDetailsStore = EncryptedClientDetails;
+
+    fn into_runtime_stores(self) -> (Self::ReplyStore, Self::CredentialStore) {
+        (self.reply_store, self.credential_store)
+    }
+
+    fn key_store(&self) -> &Self::KeyStore {
+        &self.client_details_store
+    }
+
+    fn reply_store(&self) -> &Self::ReplyStore {
+        &self.reply_store
+    }
+
+    fn credential_store(&self) -> &Self::CredentialStore {
+        &self.credential_store
+    }
+
+    fn gateway_details_store(&self) -> &Self::GatewayDetailsStore {
+        &self.client_details_store
+    }
+}
+
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
 pub struct OnDiskPersistent {
     pub(crate) key_store: OnDiskKeys,
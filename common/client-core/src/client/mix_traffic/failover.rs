@@ -0,0 +1,633 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::client::mix_traffic::transceiver::{
+    erase_err, ErasedGatewayError, GatewayReceiver, GatewaySender, GatewayTransceiver,
+};
+use crate::client::replies::reply_controller::ReplyControllerSender;
+use crate::client::self_address::SelfAddress;
+use crate::config::GatewayEndpointConfig;
+use crate::error::ClientCoreError;
+use async_trait::async_trait;
+use log::{error, info, warn};
+use nym_bandwidth_controller::BandwidthController;
+use nym_credential_storage::storage::Storage as CredentialStorage;
+use nym_crypto::asymmetric::identity;
+use nym_gateway_client::error::GatewayClientError;
+use nym_gateway_client::{BandwidthReportSender, GatewayClient, PacketRouter};
+use nym_gateway_requests::registration::handshake::SharedKeys;
+use nym_sphinx::forwarding::packet::MixPacket;
+use nym_task::TaskClient;
+use nym_validator_client::nyxd::contract_traits::DkgQueryClient;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::os::raw::c_int as RawFd;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Gateway the client has registered with, alongside the keys it has derived with it.
+pub struct RegisteredGateway {
+    details: GatewayEndpointConfig,
+    shared_key: Arc<SharedKeys>,
+}
+
+impl RegisteredGateway {
+    pub fn new(details: GatewayEndpointConfig, shared_key: Arc<SharedKeys>) -> Self {
+        RegisteredGateway {
+            details,
+            shared_key,
+        }
+    }
+}
+
+/// Functionalities of a gateway client required for switching between the gateways.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait FailoverClient: Send {
+    type BandwidthController: Send;
+
+    fn gateway_identity(&self) -> identity::PublicKey;
+
+    fn ws_fd(&self) -> Option<RawFd>;
+
+    fn take_bandwidth_controller(&mut self) -> Option<Self::BandwidthController>;
+
+    async fn authenticate_and_start(&mut self) -> Result<(), GatewayClientError>;
+
+    async fn send_mix_packet(&mut self, packet: MixPacket) -> Result<(), GatewayClientError>;
+
+    async fn batch_send_mix_packets(
+        &mut self,
+        packets: Vec<MixPacket>,
+    ) -> Result<(), GatewayClientError>;
+
+    /// Acquire more bandwidth at the gateway, unless it has already been done since the gateway
+    /// reported it was running low.
+    async fn top_up_bandwidth(&mut self) -> Result<(), GatewayClientError>;
+}
+
+/// Creates the clients for the standby gateways.
+pub trait FailoverClientFactory: Send {
+    type Client: FailoverClient;
+
+    fn new_client(
+        &mut self,
+        gateway: &RegisteredGateway,
+        bandwidth_controller: Option<<Self::Client as FailoverClient>::BandwidthController>,
+    ) -> Result<Self::Client, ClientCoreError>;
+}
+
+type BandwidthControllerOf<F> =
+    <<F as FailoverClientFactory>::Client as FailoverClient>::BandwidthController;
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C, St> FailoverClient for GatewayClient<C, St>
+where
+    C: DkgQueryClient + Send + Sync,
+    St: CredentialStorage,
+    <St as CredentialStorage>::StorageError: Send + Sync + 'static,
+{
+    type BandwidthController = BandwidthController<C, St>;
+
+    fn gateway_identity(&self) -> identity::PublicKey {
+        GatewayClient::gateway_identity(self)
+    }
+
+    fn ws_fd(&self) -> Option<RawFd> {
+        GatewayClient::ws_fd(self)
+    }
+
+    fn take_bandwidth_controller(&mut self) -> Option<Self::BandwidthController> {
+        GatewayClient::take_bandwidth_controller(self)
+    }
+
+    async fn authenticate_and_start(&mut self) -> Result<(), GatewayClientError> {
+        GatewayClient::authenticate_and_start(self)
+            .await
+            .map(|_| ())
+    }
+
+    async fn send_mix_packet(&mut self, packet: MixPacket) -> Result<(), GatewayClientError> {
+        GatewayClient::send_mix_packet(self, packet).await
+    }
+
+    async fn batch_send_mix_packets(
+        &mut self,
+        packets: Vec<MixPacket>,
+    ) -> Result<(), GatewayClientError> {
+        GatewayClient::batch_send_mix_packets(self, packets).await
+    }
+
+    async fn top_up_bandwidth(&mut self) -> Result<(), GatewayClientError> {
+        GatewayClient::claim_bandwidth_if_running_low(self).await
+    }
+}
+
+/// Creates websocket clients of the standby gateways using the same configuration
+/// as the client of the primary gateway.
+pub struct GatewayClientFactory<C, St> {
+    local_identity: Arc<identity::KeyPair>,
+    packet_router: PacketRouter,
+    bandwidth_report_sender: Option<BandwidthReportSender>,
+    disabled_credentials_mode: bool,
+    gateway_response_timeout: Duration,
+
+    // note: it must not signal on drop, as the gateway clients dying is what we're trying to deal with
+    shutdown: TaskClient,
+
+    _phantom: PhantomData<fn() -> (C, St)>,
+}
+
+impl<C, St> GatewayClientFactory<C, St> {
+    pub(crate) fn new(
+        local_identity: Arc<identity::KeyPair>,
+        packet_router: PacketRouter,
+        disabled_credentials_mode: bool,
+        gateway_response_timeout: Duration,
+        mut shutdown: TaskClient,
+    ) -> Self {
+        shutdown.mark_as_success();
+
+        GatewayClientFactory {
+            local_identity,
+            packet_router,
+            bandwidth_report_sender: None,
+            disabled_credentials_mode,
+            gateway_response_timeout,
+            shutdown,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Make sure the clients of the standby gateways also forward their bandwidth reports.
+    #[must_use]
+    pub(crate) fn with_bandwidth_report_sender(
+        mut self,
+        bandwidth_report_sender: BandwidthReportSender,
+    ) -> Self {
+        self.bandwidth_report_sender = Some(bandwidth_report_sender);
+        self
+    }
+}
+
+impl<C, St> FailoverClientFactory for GatewayClientFactory<C, St>
+where
+    C: DkgQueryClient + Send + Sync,
+    St: CredentialStorage,
+    <St as CredentialStorage>::StorageError: Send + Sync + 'static,
+{
+    type Client = GatewayClient<C, St>;
+
+    fn new_client(
+        &mut self,
+        gateway: &RegisteredGateway,
+        bandwidth_controller: Option<BandwidthController<C, St>>,
+    ) -> Result<Self::Client, ClientCoreError> {
+        let mut gateway_client = GatewayClient::new(
+            gateway.details.clone().try_into()?,
+            Arc::clone(&self.local_identity),
+            Some(Arc::clone(&gateway.shared_key)),
+            self.packet_router.clone(),
+            bandwidth_controller,
+            self.shutdown.fork("standby_gateway"),
+        )
+        .with_disabled_credentials_mode(self.disabled_credentials_mode)
+        .with_response_timeout(self.gateway_response_timeout);
+
+        if let Some(bandwidth_report_sender) = &self.bandwidth_report_sender {
+            gateway_client =
+                gateway_client.with_bandwidth_report_sender(bandwidth_report_sender.clone());
+        }
+        Ok(gateway_client)
+    }
+}
+
+/// Gateway connection that, once the current gateway becomes unreachable (i.e. the underlying
+/// client gives up on reconnecting to it), transparently switches over to one of the standby gateways.
+///
+/// Upon successful switch, the address of this client is updated and all correspondents that hold
+/// our reply SURBs are sent fresh ones pointing to the new gateway.
+pub struct FailoverGateway<F: FailoverClientFactory> {
+    active_client: F::Client,
+    active_gateway: RegisteredGateway,
+
+    /// Gateways to try, in order, if the active one fails. The failed gateways are put at the back,
+    /// so that they could be retried if everything else fails too.
+    standby_gateways: VecDeque<RegisteredGateway>,
+
+    /// Bandwidth controller recovered from the gateway client that we failed to switch to.
+    spare_bandwidth_controller: Option<BandwidthControllerOf<F>>,
+
+    client_factory: F,
+
+    self_address: SelfAddress,
+    reply_controller_sender: ReplyControllerSender,
+}
+
+impl<F: FailoverClientFactory> FailoverGateway<F> {
+    pub(crate) fn new(
+        active_client: F::Client,
+        active_gateway: RegisteredGateway,
+        standby_gateways: Vec<RegisteredGateway>,
+        client_factory: F,
+        self_address: SelfAddress,
+        reply_controller_sender: ReplyControllerSender,
+    ) -> Self {
+        FailoverGateway {
+            active_client,
+            active_gateway,
+            standby_gateways: standby_gateways.into(),
+            spare_bandwidth_controller: None,
+            client_factory,
+            self_address,
+            reply_controller_sender,
+        }
+    }
+
+    fn requires_failover(err: &GatewayClientError) -> bool {
+        // we only get `NotAuthenticated` if we have previously failed to reconnect
+        matches!(
+            err,
+            GatewayClientError::ReconnectionFailure { .. } | GatewayClientError::NotAuthenticated
+        )
+    }
+
+    fn announce_gateway_change(&self) {
+        let new_address = self
+            .self_address
+            .change_gateway(self.active_client.gateway_identity());
+        info!("our new address is {new_address}");
+
+        self.reply_controller_sender.announce_gateway_change()
+    }
+
+    fn new_gateway_client(
+        &mut self,
+        gateway: &RegisteredGateway,
+    ) -> Result<F::Client, ClientCoreError> {
+        let bandwidth_controller = self
+            .active_client
+            .take_bandwidth_controller()
+            .or_else(|| self.spare_bandwidth_controller.take());
+
+        self.client_factory
+            .new_client(gateway, bandwidth_controller)
+    }
+
+    /// Attempts to switch to the first standby gateway that we manage to authenticate with.
+    pub(crate) async fn try_failover(&mut self) -> Result<(), ClientCoreError> {
+        for _ in 0..self.standby_gateways.len() {
+            let Some(candidate) = self.standby_gateways.pop_front() else {
+                break;
+            };
+            let gateway_id = candidate.details.gateway_id.clone();
+            info!("attempting to switch over to the standby gateway {gateway_id}");
+
+            let mut gateway_client = match self.new_gateway_client(&candidate) {
+                Ok(gateway_client) => gateway_client,
+                Err(err) => {
+                    warn!("the details of the standby gateway {gateway_id} are invalid: {err}");
+                    self.standby_gateways.push_back(candidate);
+                    continue;
+                }
+            };
+
+            if let Err(err) = gateway_client.authenticate_and_start().await {
+                warn!("failed to switch over to the standby gateway {gateway_id}: {err}");
+                self.spare_bandwidth_controller = gateway_client.take_bandwidth_controller();
+                self.standby_gateways.push_back(candidate);
+                continue;
+            }
+
+            info!("switched over to the standby gateway {gateway_id}");
+            let previous_gateway = std::mem::replace(&mut self.active_gateway, candidate);
+            self.standby_gateways.push_back(previous_gateway);
+
+            // note: this drops the old client alongside its (already dead) connection
+            self.active_client = gateway_client;
+            self.announce_gateway_change();
+            return Ok(());
+        }
+
+        Err(ClientCoreError::NoStandbyGatewaysAvailable)
+    }
+
+    async fn handle_send_result(
+        &mut self,
+        result: Result<(), GatewayClientError>,
+    ) -> Result<(), ErasedGatewayError> {
+        if let Err(err) = &result {
+            if Self::requires_failover(err) {
+                warn!(
+                    "the gateway {} has become unreachable: {err}",
+                    self.active_gateway.details.gateway_id
+                );
+                if let Err(failover_err) = self.try_failover().await {
+                    error!("failed to switch to any of the standby gateways: {failover_err}")
+                }
+            }
+        }
+
+        result.map_err(erase_err)
+    }
+}
+
+impl<F: FailoverClientFactory> GatewayTransceiver for FailoverGateway<F> {
+    fn gateway_identity(&self) -> identity::PublicKey {
+        self.active_client.gateway_identity()
+    }
+    fn ws_fd(&self) -> Option<RawFd> {
+        self.active_client.ws_fd()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<F: FailoverClientFactory> GatewaySender for FailoverGateway<F> {
+    async fn send_mix_packet(&mut self, packet: MixPacket) -> Result<(), ErasedGatewayError> {
+        // if the gateway is gone, the packet is lost, but it will get retransmitted
+        // once we fail to receive an ack for it
+        let result = self.active_client.send_mix_packet(packet).await;
+        self.handle_send_result(result).await
+    }
+
+    async fn batch_send_mix_packets(
+        &mut self,
+        packets: Vec<MixPacket>,
+    ) -> Result<(), ErasedGatewayError> {
+        let result = self.active_client.batch_send_mix_packets(packets).await;
+        self.handle_send_result(result).await
+    }
+
+    async fn top_up_bandwidth(&mut self) -> Result<(), ErasedGatewayError> {
+        let result = self.active_client.top_up_bandwidth().await;
+        self.handle_send_result(result).await
+    }
+}
+
+impl<F: FailoverClientFactory> GatewayReceiver for FailoverGateway<F> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::replies::reply_controller::requests::new_control_channels;
+    use crate::client::replies::reply_controller::{
+        ReplyControllerMessage, ReplyControllerReceiver,
+    };
+    use nym_crypto::asymmetric::encryption;
+    use nym_sphinx::addressing::clients::Recipient;
+    use std::collections::HashSet;
+
+    type MockBandwidthController = u32;
+
+    struct MockClient {
+        identity: identity::PublicKey,
+        reachable: bool,
+        send_error: Option<fn() -> GatewayClientError>,
+        bandwidth_controller: Option<MockBandwidthController>,
+    }
+
+    #[async_trait]
+    impl FailoverClient for MockClient {
+        type BandwidthController = MockBandwidthController;
+
+        fn gateway_identity(&self) -> identity::PublicKey {
+            self.identity
+        }
+
+        fn ws_fd(&self) -> Option<RawFd> {
+            None
+        }
+
+        fn take_bandwidth_controller(&mut self) -> Option<Self::BandwidthController> {
+            self.bandwidth_controller.take()
+        }
+
+        async fn authenticate_and_start(&mut self) -> Result<(), GatewayClientError> {
+            if self.reachable {
+                Ok(())
+            } else {
+                Err(GatewayClientError::ConnectionNotEstablished)
+            }
+        }
+
+        async fn send_mix_packet(&mut self, _: MixPacket) -> Result<(), GatewayClientError> {
+            self.send_error.map_or(Ok(()), |err| Err(err()))
+        }
+
+        async fn batch_send_mix_packets(
+            &mut self,
+            _: Vec<MixPacket>,
+        ) -> Result<(), GatewayClientError> {
+            self.send_error.map_or(Ok(()), |err| Err(err()))
+        }
+
+        async fn top_up_bandwidth(&mut self) -> Result<(), GatewayClientError> {
+            self.send_error.map_or(Ok(()), |err| Err(err()))
+        }
+    }
+
+    #[derive(Default)]
+    struct MockClientFactory {
+        reachable: HashSet<String>,
+
+        /// Gateways the clients have been created for alongside the bandwidth controllers they got.
+        created: Vec<(String, Option<MockBandwidthController>)>,
+    }
+
+    impl FailoverClientFactory for MockClientFactory {
+        type Client = MockClient;
+
+        fn new_client(
+            &mut self,
+            gateway: &RegisteredGateway,
+            bandwidth_controller: Option<MockBandwidthController>,
+        ) -> Result<Self::Client, ClientCoreError> {
+            let gateway_id = gateway.details.gateway_id.clone();
+            self.created
+                .push((gateway_id.clone(), bandwidth_controller));
+            Ok(MockClient {
+                identity: identity::PublicKey::from_base58_string(&gateway_id).unwrap(),
+                reachable: self.reachable.contains(&gateway_id),
+                send_error: None,
+                bandwidth_controller,
+            })
+        }
+    }
+
+    fn new_gateway() -> (RegisteredGateway, identity::PublicKey) {
+        let identity = *identity::KeyPair::new(&mut rand::thread_rng()).public_key();
+        let details = GatewayEndpointConfig {
+            gateway_id: identity.to_base58_string(),
+            gateway_owner: "n1gatewayowner".to_string(),
+            gateway_listener: "ws://127.0.0.1:9000".to_string(),
+        };
+        let shared_key = SharedKeys::try_from_bytes(&[42; 32]).unwrap();
+        (
+            RegisteredGateway::new(details, Arc::new(shared_key)),
+            identity,
+        )
+    }
+
+    struct TestSetup {
+        failover: FailoverGateway<MockClientFactory>,
+        primary: identity::PublicKey,
+        standby: Vec<identity::PublicKey>,
+        self_address: SelfAddress,
+        reply_controller_receiver: ReplyControllerReceiver,
+    }
+
+    impl TestSetup {
+        fn new(
+            standby_gateways: usize,
+            active_error: fn() -> GatewayClientError,
+            bandwidth_controller: Option<MockBandwidthController>,
+        ) -> Self {
+            let mut rng = rand::thread_rng();
+            let (primary_gateway, primary) = new_gateway();
+            let (standby_gateways, standby): (Vec<_>, Vec<_>) =
+                (0..standby_gateways).map(|_| new_gateway()).unzip();
+
+            let self_address = SelfAddress::new(Recipient::new(
+                *identity::KeyPair::new(&mut rng).public_key(),
+                *encryption::KeyPair::new(&mut rng).public_key(),
+                primary,
+            ));
+            let (reply_controller_sender, reply_controller_receiver) = new_control_channels();
+
+            let active_client = MockClient {
+                identity: primary,
+                reachable: true,
+                send_error: Some(active_error),
+                bandwidth_controller,
+            };
+
+            TestSetup {
+                failover: FailoverGateway::new(
+                    active_client,
+                    primary_gateway,
+                    standby_gateways,
+                    MockClientFactory::default(),
+                    self_address.clone(),
+                    reply_controller_sender,
+                ),
+                primary,
+                standby,
+                self_address,
+                reply_controller_receiver,
+            }
+        }
+
+        fn make_reachable(&mut self, gateway: identity::PublicKey) {
+            self.failover
+                .client_factory
+                .reachable
+                .insert(gateway.to_base58_string());
+        }
+
+        fn gateway_change_got_announced(&mut self) -> bool {
+            matches!(
+                self.reply_controller_receiver.try_next(),
+                Ok(Some(ReplyControllerMessage::AnnounceGatewayChange))
+            )
+        }
+    }
+
+    fn reconnection_failure() -> GatewayClientError {
+        GatewayClientError::ReconnectionFailure {
+            attempts: 10,
+            source: Box::new(GatewayClientError::ConnectionNotEstablished),
+        }
+    }
+
+    #[tokio::test]
+    async fn switches_to_standby_gateway_once_the_active_one_becomes_unreachable() {
+        let errors: [fn() -> GatewayClientError; 2] = [reconnection_failure, || {
+            GatewayClientError::NotAuthenticated
+        }];
+
+        for error in errors {
+            let mut setup = TestSetup::new(1, error, None);
+            setup.make_reachable(setup.standby[0]);
+
+            // the packets sent to the dead gateway are lost
+            assert!(setup
+                .failover
+                .batch_send_mix_packets(Vec::new())
+                .await
+                .is_err());
+
+            assert_eq!(setup.failover.gateway_identity(), setup.standby[0]);
+            assert_eq!(setup.self_address.get().gateway(), &setup.standby[0]);
+            assert!(setup.gateway_change_got_announced());
+
+            // and the new gateway is used from now on
+            assert!(setup
+                .failover
+                .batch_send_mix_packets(Vec::new())
+                .await
+                .is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn does_not_switch_gateways_on_other_errors() {
+        let mut setup = TestSetup::new(1, || GatewayClientError::Timeout, None);
+        setup.make_reachable(setup.standby[0]);
+
+        assert!(setup
+            .failover
+            .batch_send_mix_packets(Vec::new())
+            .await
+            .is_err());
+        assert!(setup.failover.top_up_bandwidth().await.is_err());
+
+        assert_eq!(setup.failover.gateway_identity(), setup.primary);
+        assert_eq!(setup.self_address.get().gateway(), &setup.primary);
+        assert!(setup.failover.client_factory.created.is_empty());
+        assert!(!setup.gateway_change_got_announced());
+    }
+
+    #[tokio::test]
+    async fn bandwidth_controller_is_handed_over_to_the_standby_gateway() {
+        let mut setup = TestSetup::new(2, || GatewayClientError::NotAuthenticated, Some(42));
+        // the first standby gateway is also down
+        setup.make_reachable(setup.standby[1]);
+
+        assert!(setup.failover.top_up_bandwidth().await.is_err());
+
+        let created = &setup.failover.client_factory.created;
+        assert_eq!(
+            created,
+            &vec![
+                (setup.standby[0].to_base58_string(), Some(42)),
+                (setup.standby[1].to_base58_string(), Some(42)),
+            ]
+        );
+        assert_eq!(setup.failover.gateway_identity(), setup.standby[1]);
+        assert_eq!(setup.failover.active_client.bandwidth_controller, Some(42));
+        assert!(setup.failover.spare_bandwidth_controller.is_none());
+    }
+
+    #[tokio::test]
+    async fn unreachable_standby_gateways_are_retried() {
+        let mut setup = TestSetup::new(1, || GatewayClientError::NotAuthenticated, Some(42));
+
+        assert!(matches!(
+            setup.failover.try_failover().await,
+            Err(ClientCoreError::NoStandbyGatewaysAvailable)
+        ));
+        assert_eq!(setup.failover.gateway_identity(), setup.primary);
+        assert!(!setup.gateway_change_got_announced());
+        // the controller got recovered from the client of the gateway we failed to switch to
+        assert_eq!(setup.failover.spare_bandwidth_controller, Some(42));
+
+        setup.make_reachable(setup.standby[0]);
+        setup.failover.try_failover().await.unwrap();
+
+        assert_eq!(setup.failover.gateway_identity(), setup.standby[0]);
+        assert_eq!(setup.failover.active_client.bandwidth_controller, Some(42));
+        assert!(setup.gateway_change_got_announced());
+    }
+}
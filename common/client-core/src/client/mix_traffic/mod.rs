@@ -1,20 +1,31 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client::helpers::{get_time_now, Instant};
 use crate::client::mix_traffic::transceiver::GatewayTransceiver;
+use crate::error::ClientCoreStatusMessage;
 use crate::spawn_future;
+use futures::StreamExt;
 use log::*;
+use nym_gateway_client::{BandwidthReport, BandwidthReportReceiver};
 use nym_sphinx::forwarding::packet::MixPacket;
+use nym_task::TaskClient;
+use std::time::Duration;
 
 pub type BatchMixMessageSender = tokio::sync::mpsc::Sender<Vec<MixPacket>>;
 pub type BatchMixMessageReceiver = tokio::sync::mpsc::Receiver<Vec<MixPacket>>;
 
+pub mod failover;
 pub mod transceiver;
 
 // We remind ourselves that 32 x 32kb = 1024kb, a reasonable size for a network buffer.
 pub const MIX_MESSAGE_RECEIVER_BUFFER_SIZE: usize = 32;
 const MAX_FAILURE_COUNT: usize = 100;
 
+// acquiring bandwidth blocks sending any packets, so make sure we don't attempt it on every single
+// report, for example if the credential preparation keeps failing
+const MIN_BANDWIDTH_TOP_UP_INTERVAL: Duration = Duration::from_secs(30);
+
 // that's also disgusting.
 pub struct Empty;
 
@@ -23,6 +34,11 @@ pub struct MixTrafficController {
 
     mix_rx: BatchMixMessageReceiver,
 
+    /// Reports of the remaining bandwidth pushed by the gateway, if it supports them.
+    bandwidth_reports: Option<BandwidthReportReceiver>,
+
+    last_bandwidth_top_up: Option<Instant>,
+
     // TODO: this is temporary work-around.
     // in long run `gateway_client` will be moved away from `MixTrafficController` anyway.
     consecutive_gateway_failure_count: usize,
@@ -39,6 +55,8 @@ impl MixTrafficController {
             MixTrafficController {
                 gateway_transceiver: Box::new(gateway_transceiver),
                 mix_rx: message_receiver,
+                bandwidth_reports: None,
+                last_bandwidth_top_up: None,
                 consecutive_gateway_failure_count: 0,
             },
             message_sender,
@@ -54,12 +72,67 @@ impl MixTrafficController {
             MixTrafficController {
                 gateway_transceiver,
                 mix_rx: message_receiver,
+                bandwidth_reports: None,
+                last_bandwidth_top_up: None,
                 consecutive_gateway_failure_count: 0,
             },
             message_sender,
         )
     }
 
+    #[must_use]
+    pub fn with_bandwidth_reports(mut self, bandwidth_reports: BandwidthReportReceiver) -> Self {
+        self.bandwidth_reports = Some(bandwidth_reports);
+        self
+    }
+
+    async fn next_bandwidth_report(
+        bandwidth_reports: &mut Option<BandwidthReportReceiver>,
+    ) -> Option<BandwidthReport> {
+        match bandwidth_reports {
+            Some(bandwidth_reports) => bandwidth_reports.next().await,
+            None => None,
+        }
+    }
+
+    async fn on_bandwidth_report(&mut self, report: BandwidthReport, shutdown: &mut TaskClient) {
+        if !report.running_low {
+            trace!(
+                "the gateway reported {} bytes of bandwidth remaining",
+                report.available_total
+            );
+            return;
+        }
+
+        let now = get_time_now();
+        if let Some(last_top_up) = self.last_bandwidth_top_up {
+            if now.duration_since(last_top_up) < MIN_BANDWIDTH_TOP_UP_INTERVAL {
+                debug!(
+                    "the gateway reported our bandwidth is running low ({} bytes remaining), but we have just attempted to acquire more",
+                    report.available_total
+                );
+                return;
+            }
+        }
+
+        warn!(
+            "the gateway reported our bandwidth is running low ({} bytes remaining). Attempting to acquire more",
+            report.available_total
+        );
+        shutdown.send_status_msg(Box::new(
+            ClientCoreStatusMessage::GatewayBandwidthRunningLow {
+                available_total: report.available_total,
+            },
+        ));
+
+        // note: the transceiver is going to skip the top up if more bandwidth has already been
+        // acquired since this report got generated
+        self.last_bandwidth_top_up = Some(now);
+        if let Err(err) = self.gateway_transceiver.top_up_bandwidth().await {
+            error!("Failed to acquire more bandwidth at the gateway: {err}");
+        }
+    }
+
     async fn on_messages(&mut self, mut mix_packets: Vec<MixPacket>) {
         debug_assert!(!mix_packets.is_empty());
 
@@ -89,7 +162,7 @@ impl MixTrafficController {
         }
     }
 
-    pub fn start_with_shutdown(mut self, mut shutdown: nym_task::TaskClient) {
+    pub fn start_with_shutdown(mut self, mut shutdown: TaskClient) {
         spawn_future(async move {
             debug!("Started MixTrafficController with graceful shutdown support");
 
@@ -104,6 +177,15 @@ impl MixTrafficController {
                             break;
                         }
                     },
+                    report = Self::next_bandwidth_report(&mut self.bandwidth_reports), if self.bandwidth_reports.is_some() => match report {
+                        Some(report) => {
+                            self.on_bandwidth_report(report, &mut shutdown).await;
+                        },
+                        None => {
+                            log::trace!("MixTrafficController: bandwidth reports channel closed");
+                            self.bandwidth_reports = None;
+                        }
+                    },
                     _ = shutdown.recv_with_delay() => {
                         log::trace!("MixTrafficController: Received shutdown");
                         break;
@@ -115,3 +197,82 @@ impl MixTrafficController {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mix_traffic::transceiver::{
+        ErasedGatewayError, GatewayReceiver, GatewaySender,
+    };
+    use async_trait::async_trait;
+    use nym_crypto::asymmetric::identity;
+    use std::os::raw::c_int as RawFd;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct CountingGateway {
+        identity: identity::PublicKey,
+        top_ups: Arc<AtomicUsize>,
+    }
+
+    impl GatewayTransceiver for CountingGateway {
+        fn gateway_identity(&self) -> identity::PublicKey {
+            self.identity
+        }
+        fn ws_fd(&self) -> Option<RawFd> {
+            None
+        }
+    }
+
+    #[async_trait]
+    impl GatewaySender for CountingGateway {
+        async fn send_mix_packet(&mut self, _: MixPacket) -> Result<(), ErasedGatewayError> {
+            Ok(())
+        }
+
+        async fn top_up_bandwidth(&mut self) -> Result<(), ErasedGatewayError> {
+            self.top_ups.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    impl GatewayReceiver for CountingGateway {}
+
+    fn report(running_low: bool) -> BandwidthReport {
+        BandwidthReport {
+            available_total: 1000,
+            running_low,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bandwidth_top_ups_are_rate_limited() {
+        let top_ups = Arc::new(AtomicUsize::new(0));
+        let gateway = CountingGateway {
+            identity: *identity::KeyPair::new(&mut rand::thread_rng()).public_key(),
+            top_ups: Arc::clone(&top_ups),
+        };
+        let (mut controller, _) = MixTrafficController::new(gateway);
+        let mut shutdown = TaskClient::dummy();
+
+        controller
+            .on_bandwidth_report(report(false), &mut shutdown)
+            .await;
+        assert_eq!(top_ups.load(Ordering::SeqCst), 0);
+
+        // the threshold crossing report immediately followed by the periodic one
+        controller
+            .on_bandwidth_report(report(true), &mut shutdown)
+            .await;
+        controller
+            .on_bandwidth_report(report(true), &mut shutdown)
+            .await;
+        assert_eq!(top_ups.load(Ordering::SeqCst), 1);
+
+        tokio::time::advance(MIN_BANDWIDTH_TOP_UP_INTERVAL).await;
+        controller
+            .on_bandwidth_report(report(true), &mut shutdown)
+            .await;
+        assert_eq!(top_ups.load(Ordering::SeqCst), 2);
+    }
+}
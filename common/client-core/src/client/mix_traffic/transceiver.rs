@@ -3,10 +3,12 @@
 
 use async_trait::async_trait;
 use log::{debug, error};
+use nym_credential_storage::storage::Storage as CredentialStorage;
 use nym_crypto::asymmetric::identity;
 use nym_gateway_client::GatewayClient;
 pub use nym_gateway_client::{GatewayPacketRouter, PacketRouter};
 use nym_sphinx::forwarding::packet::MixPacket;
+use nym_validator_client::nyxd::contract_traits::DkgQueryClient;
 use std::fmt::Debug;
 use std::os::raw::c_int as RawFd;
 use thiserror::Error;
@@ -19,7 +21,9 @@ use futures::channel::{mpsc, oneshot};
 #[error(transparent)]
 pub struct ErasedGatewayError(Box<dyn std::error::Error + Send + Sync>);
 
-fn erase_err<E: std::error::Error + Send + Sync + 'static>(err: E) -> ErasedGatewayError {
+pub(crate) fn erase_err<E: std::error::Error + Send + Sync + 'static>(
+    err: E,
+) -> ErasedGatewayError {
     ErasedGatewayError(Box::new(err))
 }
 
@@ -46,6 +50,12 @@ pub trait GatewaySender {
         }
         Ok(())
     }
+
+    /// Acquire more bandwidth at the gateway before it starts rejecting our packets.
+    async fn top_up_bandwidth(&mut self) -> Result<(), ErasedGatewayError> {
+        debug!("no-op bandwidth top up");
+        Ok(())
+    }
 }
 
 /// this trait defines the functionality of being able to correctly route
@@ -88,6 +98,11 @@ impl<G: GatewaySender + ?Sized + Send> GatewaySender for Box<G> {
     ) -> Result<(), ErasedGatewayError> {
         (**self).batch_send_mix_packets(packets).await
     }
+
+    #[inline]
+    async fn top_up_bandwidth(&mut self) -> Result<(), ErasedGatewayError> {
+        (**self).top_up_bandwidth().await
+    }
 }
 
 impl<G: GatewayReceiver + ?Sized> GatewayReceiver for Box<G> {
@@ -111,8 +126,9 @@ impl<C, St> RemoteGateway<C, St> {
 
 impl<C, St> GatewayTransceiver for RemoteGateway<C, St>
 where
-    C: Send,
-    St: Send,
+    C: DkgQueryClient + Send + Sync,
+    St: CredentialStorage,
+    <St as CredentialStorage>::StorageError: Send + Sync + 'static,
 {
     fn gateway_identity(&self) -> identity::PublicKey {
         self.gateway_client.gateway_identity()
@@ -126,8 +142,9 @@ where
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 impl<C, St> GatewaySender for RemoteGateway<C, St>
 where
-    C: Send,
-    St: Send,
+    C: DkgQueryClient + Send + Sync,
+    St: CredentialStorage,
+    <St as CredentialStorage>::StorageError: Send + Sync + 'static,
 {
     async fn send_mix_packet(&mut self, packet: MixPacket) -> Result<(), ErasedGatewayError> {
         self.gateway_client
@@ -145,6 +162,13 @@ where
             .await
             .map_err(erase_err)
     }
+
+    async fn top_up_bandwidth(&mut self) -> Result<(), ErasedGatewayError> {
+        self.gateway_client
+            .claim_bandwidth_if_running_low()
+            .await
+            .map_err(erase_err)
+    }
 }
 
 impl<C, St> GatewayReceiver for RemoteGateway<C, St> {}
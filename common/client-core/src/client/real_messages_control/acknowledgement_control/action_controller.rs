@@ -9,7 +9,7 @@ use log::*;
 use nym_nonexhaustive_delayqueue::{Expired, NonExhaustiveDelayQueue, QueueKey};
 use nym_sphinx::chunking::fragment::FragmentIdentifier;
 use nym_sphinx::Delay as SphinxDelay;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -62,6 +62,18 @@ impl Action {
     }
 }
 
+/// Acknowledgement state of an erasure coded set of `Fragment`s.
+struct ErasureCodedSetAcks {
+    /// Number of `Fragment`s the recipient needs to receive in order to reconstruct the set.
+    data_fragments: u8,
+
+    /// Number of `Fragment`s of the set the recipient has already acknowledged.
+    acknowledged: u8,
+
+    /// `Fragment`s of the set that are still waiting for their acknowledgements.
+    pending: HashSet<FragmentIdentifier>,
+}
+
 /// Configurable parameters of the `ActionController`
 pub(super) struct Config {
     /// Given ack timeout in the form a * BASE_DELAY + b, it specifies the additive part `b`
@@ -95,6 +107,11 @@ pub(super) struct ActionController {
     /// retransmitted if their timer fires up.
     pending_acks_timers: NonExhaustiveDelayQueue<FragmentIdentifier>,
 
+    /// Acknowledgement state of all erasure coded sets that still have some pending `Fragment`s.
+    /// Once the recipient has acknowledged enough of them to reconstruct the set, the remaining
+    /// ones no longer need to be retransmitted.
+    erasure_coded_sets: HashMap<i32, ErasureCodedSetAcks>,
+
     /// Channel for receiving `Action`s from other modules.
     incoming_actions: AckActionReceiver,
 
@@ -112,6 +129,7 @@ impl ActionController {
             config,
             pending_acks_data: HashMap::new(),
             pending_acks_timers: NonExhaustiveDelayQueue::new(),
+            erasure_coded_sets: HashMap::new(),
             incoming_actions,
             retransmission_sender,
         }
@@ -122,6 +140,18 @@ impl ActionController {
             let frag_id = pending_ack.message_chunk.fragment_identifier();
             trace!("{} is inserted", frag_id);
 
+            if let Some(erasure_coding) = pending_ack.message_chunk.erasure_coding() {
+                self.erasure_coded_sets
+                    .entry(pending_ack.message_chunk.id())
+                    .or_insert_with(|| ErasureCodedSetAcks {
+                        data_fragments: erasure_coding.data_fragments(),
+                        acknowledged: 0,
+                        pending: HashSet::new(),
+                    })
+                    .pending
+                    .insert(frag_id);
+            }
+
             if self
                 .pending_acks_data
                 .insert(frag_id, (Arc::new(pending_ack), None))
@@ -172,7 +202,11 @@ impl ActionController {
                     frag_id
                 );
             }
-            Some((_, queue_key)) => {
+            Some((pending_ack, queue_key)) => {
+                if pending_ack.message_chunk.erasure_coding().is_some() {
+                    self.handle_erasure_coded_ack(pending_ack.message_chunk.id(), frag_id)
+                }
+
                 if let Some(queue_key) = queue_key {
                     // there are no possible checks here, we must GUARANTEE that we NEVER try
                     // to remove an entry that doesn't exist (and we MUST GUARANTEE that
@@ -191,6 +225,31 @@ impl ActionController {
         }
     }
 
+    // once the recipient has acknowledged enough fragments of an erasure coded set to reconstruct it,
+    // the remaining ones are redundant and there's no point in retransmitting them
+    fn handle_erasure_coded_ack(&mut self, set_id: i32, frag_id: FragmentIdentifier) {
+        let Some(set_acks) = self.erasure_coded_sets.get_mut(&set_id) else {
+            return;
+        };
+
+        set_acks.pending.remove(&frag_id);
+        set_acks.acknowledged = set_acks.acknowledged.saturating_add(1);
+        if set_acks.acknowledged < set_acks.data_fragments && !set_acks.pending.is_empty() {
+            return;
+        }
+
+        let Some(set_acks) = self.erasure_coded_sets.remove(&set_id) else {
+            return;
+        };
+
+        for redundant in set_acks.pending {
+            trace!("{redundant} is no longer needed to reconstruct its set");
+            if let Some((_, Some(queue_key))) = self.pending_acks_data.remove(&redundant) {
+                self.pending_acks_timers.remove(&queue_key);
+            }
+        }
+    }
+
     // initiated basically as a first step of retransmission. At first data has its delay updated
     // (as new sphinx packet was created with new expected delivery time)
     fn handle_update_delay(&mut self, frag_id: FragmentIdentifier, delay: SphinxDelay) {
@@ -293,3 +352,103 @@ impl ActionController {
         log::debug!("ActionController: Exiting");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+    use nym_sphinx::chunking::fragment::{erasure_coded_fragment_payload_max_len, Fragment};
+    use rand::rngs::OsRng;
+
+    // just some arbitrary value to use in tests
+    const AVAILABLE_PLAINTEXT_SIZE: usize = 1024;
+
+    fn erasure_coded_fragments(data_fragments: usize, parity_fragments: u8) -> Vec<Fragment> {
+        let message = vec![
+            42u8;
+            erasure_coded_fragment_payload_max_len(AVAILABLE_PLAINTEXT_SIZE)
+                * data_fragments
+        ];
+
+        nym_sphinx::chunking::split_into_sets_with_parity(
+            &mut OsRng,
+            &message,
+            AVAILABLE_PLAINTEXT_SIZE,
+            parity_fragments,
+        )
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn action_controller() -> ActionController {
+        let (retransmission_sender, _) = mpsc::unbounded();
+        let (_, incoming_actions) = mpsc::unbounded();
+        ActionController::new(
+            Config::new(Duration::from_secs(1), 1.5),
+            retransmission_sender,
+            incoming_actions,
+        )
+    }
+
+    fn insert_and_start_timers(controller: &mut ActionController, fragments: &[Fragment]) {
+        let tag = AnonymousSenderTag::new_random(&mut OsRng);
+        controller.handle_insert(
+            fragments
+                .iter()
+                .map(|f| {
+                    PendingAcknowledgement::new_anonymous(
+                        f.clone(),
+                        SphinxDelay::new_from_nanos(100_000_000),
+                        tag,
+                        false,
+                    )
+                })
+                .collect(),
+        );
+        for fragment in fragments {
+            controller.handle_start_timer(fragment.fragment_identifier())
+        }
+    }
+
+    #[tokio::test]
+    async fn redundant_erasure_coded_fragments_are_dropped_once_enough_are_acknowledged() {
+        let data_fragments = 3;
+        let fragments = erasure_coded_fragments(data_fragments, 2);
+        assert_eq!(fragments.len(), data_fragments + 2);
+
+        let mut controller = action_controller();
+        insert_and_start_timers(&mut controller, &fragments);
+        assert_eq!(controller.pending_acks_data.len(), fragments.len());
+
+        for fragment in fragments.iter().take(data_fragments - 1) {
+            controller.handle_remove(fragment.fragment_identifier());
+        }
+        assert_eq!(controller.pending_acks_data.len(), 3);
+
+        controller.handle_remove(fragments[data_fragments - 1].fragment_identifier());
+        assert!(controller.pending_acks_data.is_empty());
+        assert!(controller.erasure_coded_sets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn regular_fragments_are_not_affected_by_erasure_coded_acks() {
+        let erasure_coded = erasure_coded_fragments(1, 1);
+        let regular: Vec<_> = nym_sphinx::chunking::split_into_sets(
+            &mut OsRng,
+            &[42u8; 10000],
+            AVAILABLE_PLAINTEXT_SIZE,
+        )
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let mut controller = action_controller();
+        insert_and_start_timers(&mut controller, &regular);
+        insert_and_start_timers(&mut controller, &erasure_coded);
+
+        controller.handle_remove(erasure_coded[0].fragment_identifier());
+        assert_eq!(controller.pending_acks_data.len(), regular.len());
+        assert!(controller.erasure_coded_sets.is_empty());
+    }
+}
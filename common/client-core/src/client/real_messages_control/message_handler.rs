@@ -7,6 +7,7 @@ use crate::client::real_messages_control::real_traffic_stream::{
 };
 use crate::client::real_messages_control::{AckActionSender, Action};
 use crate::client::replies::reply_storage::{ReceivedReplySurbsMap, SentReplyKeys, UsedSenderTags};
+use crate::client::self_address::SelfAddress;
 use crate::client::topology_control::{TopologyAccessor, TopologyReadPermit};
 use log::{debug, error, info, trace, warn};
 use nym_sphinx::acknowledgements::AckKey;
@@ -89,7 +90,7 @@ pub(crate) struct Config {
 
     /// Address of this client which also represent an address to which all acknowledgements
     /// and surb-based are going to be sent.
-    sender_address: Recipient,
+    sender_address: SelfAddress,
 
     /// Average delay a data packet is going to get delay at a single mixnode.
     average_packet_delay: Duration,
@@ -106,12 +107,15 @@ pub(crate) struct Config {
 
     /// Optional secondary predefined packet size used for the encapsulated messages.
     secondary_packet_size: Option<PacketSize>,
+
+    /// Number of additional parity fragments sent alongside messages to known recipients.
+    erasure_coding_parity_fragments: u8,
 }
 
 impl Config {
     pub fn new(
         ack_key: Arc<AckKey>,
-        sender_address: Recipient,
+        sender_address: SelfAddress,
         average_packet_delay: Duration,
         average_ack_delay: Duration,
     ) -> Self {
@@ -123,6 +127,7 @@ impl Config {
             num_mix_hops: DEFAULT_NUM_MIX_HOPS,
             primary_packet_size: PacketSize::default(),
             secondary_packet_size: None,
+            erasure_coding_parity_fragments: 0,
         }
     }
 
@@ -144,6 +149,12 @@ impl Config {
         self.secondary_packet_size = packet_size;
         self
     }
+
+    /// Allows protecting messages sent to known recipients with additional parity fragments.
+    pub fn with_erasure_coding_parity_fragments(mut self, parity_fragments: u8) -> Self {
+        self.erasure_coding_parity_fragments = parity_fragments;
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -176,7 +187,7 @@ where
     {
         let message_preparer = MessagePreparer::new(
             rng,
-            config.sender_address,
+            config.sender_address.get(),
             config.average_packet_delay,
             config.average_ack_delay,
         )
@@ -194,6 +205,12 @@ where
         }
     }
 
+    // the address might have changed since the last packet if we switched to a different gateway
+    fn refresh_sender_address(&mut self) {
+        self.message_preparer
+            .set_sender_address(self.config.sender_address.get())
+    }
+
     fn get_or_create_sender_tag(&mut self, recipient: &Recipient) -> AnonymousSenderTag {
         if let Some(existing) = self.tag_storage.try_get_existing(recipient) {
             trace!("we already had sender tag for {recipient}");
@@ -211,7 +228,8 @@ where
         &self,
         permit: &'a TopologyReadPermit<'a>,
     ) -> Result<&'a NymTopology, PreparationError> {
-        match permit.try_get_valid_topology_ref(&self.config.sender_address, None) {
+        let sender_address = self.config.sender_address.get();
+        match permit.try_get_valid_topology_ref(&sender_address, None) {
             Ok(topology_ref) => Ok(topology_ref),
             Err(err) => {
                 warn!("Could not process the packet - the network topology is invalid - {err}");
@@ -246,6 +264,7 @@ where
         &mut self,
         amount: usize,
     ) -> Result<(Vec<ReplySurb>, Vec<SurbEncryptionKey>), PreparationError> {
+        self.refresh_sender_address();
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = self.get_topology(&topology_permit)?;
 
@@ -319,7 +338,7 @@ where
         debug!("requesting {amount} reply SURBs from {from}");
 
         let surbs_request =
-            ReplyMessage::new_surb_request_message(self.config.sender_address, amount);
+            ReplyMessage::new_surb_request_message(self.config.sender_address.get(), amount);
         self.try_send_single_surb_message(from, surbs_request, reply_surb, true)
             .await
     }
@@ -437,6 +456,8 @@ where
         // TODO: I really dislike existence of this assertion, it implies code has to be re-organised
         debug_assert!(!matches!(message, NymMessage::Reply(_)));
 
+        self.refresh_sender_address();
+
         // TODO2: it's really annoying we have to get topology permit again here due to borrow-checker
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = self.get_topology(&topology_permit)?;
@@ -447,9 +468,16 @@ where
             self.optimal_packet_size(&message)
         };
         debug!("Using {packet_size} packets for {message}");
-        let fragments = self
-            .message_preparer
-            .pad_and_split_message(message, packet_size);
+        let fragments = if self.config.erasure_coding_parity_fragments > 0 {
+            self.message_preparer.pad_and_split_message_with_parity(
+                message,
+                packet_size,
+                self.config.erasure_coding_parity_fragments,
+            )
+        } else {
+            self.message_preparer
+                .pad_and_split_message(message, packet_size)
+        };
 
         let mut pending_acks = Vec::with_capacity(fragments.len());
         let mut real_messages = Vec::with_capacity(fragments.len());
@@ -516,6 +544,38 @@ where
         Ok(())
     }
 
+    pub(crate) async fn try_announce_gateway_change(
+        &mut self,
+        recipient: Recipient,
+        amount: u32,
+        packet_type: PacketType,
+        mix_hops: Option<u8>,
+    ) -> Result<(), PreparationError> {
+        debug!("Announcing our gateway change to {recipient} with packet type {packet_type}");
+        let sender_tag = self.get_or_create_sender_tag(&recipient);
+        let (reply_surbs, reply_keys) =
+            self.generate_reply_surbs_with_keys(amount as usize).await?;
+
+        let message = NymMessage::new_repliable(RepliableMessage::new_gateway_change(
+            sender_tag,
+            reply_surbs,
+        ));
+
+        self.try_split_and_send_non_reply_message(
+            message,
+            recipient,
+            TransmissionLane::AdditionalReplySurbs,
+            packet_type,
+            mix_hops,
+        )
+        .await?;
+
+        log::trace!("storing {} reply keys", reply_keys.len());
+        self.reply_key_storage.insert_multiple(reply_keys);
+
+        Ok(())
+    }
+
     pub(crate) async fn try_send_message_with_reply_surbs(
         &mut self,
         recipient: Recipient,
@@ -551,6 +611,7 @@ where
         mix_hops: Option<u8>,
     ) -> Result<PreparedFragment, PreparationError> {
         debug!("Sending single chunk with packet type {packet_type}");
+        self.refresh_sender_address();
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = self.get_topology(&topology_permit)?;
 
@@ -582,6 +643,7 @@ where
             reply_surbs.len()
         );
 
+        self.refresh_sender_address();
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = match self.get_topology(&topology_permit) {
             Ok(topology) => topology,
@@ -611,6 +673,7 @@ where
         reply_surb: ReplySurb,
         chunk: Fragment,
     ) -> Result<PreparedFragment, SurbWrappedPreparationError> {
+        self.refresh_sender_address();
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = match self.get_topology(&topology_permit) {
             Ok(topology) => topology,
@@ -259,6 +259,16 @@ impl<R: MessageReceiver> ReceivedMessagesBuffer<R> {
                     error!("received a repliable heartbeat message - we don't know how to handle it yet (and we won't know until future PRs)");
                     (additional_reply_surbs, false)
                 }
+                RepliableMessageContent::GatewayChange { reply_surbs } => {
+                    debug!(
+                        "{:?} has changed its gateway and sent us {} new reply surbs",
+                        msg.sender_tag,
+                        reply_surbs.len()
+                    );
+                    self.reply_controller_sender
+                        .send_gateway_change(msg.sender_tag, reply_surbs);
+                    continue;
+                }
             };
 
             self.reply_controller_sender.send_additional_surbs(
@@ -479,6 +479,53 @@ where
         }
     }
 
+    async fn handle_gateway_change(
+        &mut self,
+        from: AnonymousSenderTag,
+        reply_surbs: Vec<ReplySurb>,
+    ) {
+        info!(
+            "{from} has switched to a different gateway and sent us {} new reply surbs",
+            reply_surbs.len()
+        );
+
+        // whatever surbs we still had would have been sent to the old gateway
+        self.full_reply_storage
+            .surbs_storage_ref()
+            .replace_surbs(&from, reply_surbs);
+
+        self.try_clear_pending_retransmission(from).await;
+        self.try_clear_pending_queue(from).await;
+
+        if self.should_request_more_surbs(&from) {
+            self.request_reply_surbs_for_queue_clearing(from).await;
+        }
+    }
+
+    async fn handle_gateway_change_announcement(&mut self) {
+        let recipients = self.full_reply_storage.tags_storage_ref().recipients();
+        info!(
+            "announcing our gateway change to {} correspondents",
+            recipients.len()
+        );
+
+        let amount = self.config.reply_surbs.minimum_reply_surb_request_size;
+        for recipient in recipients {
+            if let Err(err) = self
+                .message_handler
+                .try_announce_gateway_change(
+                    recipient,
+                    amount,
+                    nym_sphinx::params::PacketType::Mix,
+                    self.config.reply_surbs.surb_mix_hops,
+                )
+                .await
+            {
+                warn!("failed to announce our gateway change to {recipient} - {err}");
+            }
+        }
+    }
+
     async fn handle_surb_request(&mut self, recipient: Recipient, mut amount: u32) {
         // 1. check whether we sent any surbs in the past to this recipient, otherwise
         // they have no business in asking for more
@@ -667,6 +714,13 @@ where
                 self.handle_received_surbs(sender_tag, reply_surbs, from_surb_request)
                     .await
             }
+            ReplyControllerMessage::GatewayChange {
+                sender_tag,
+                reply_surbs,
+            } => self.handle_gateway_change(sender_tag, reply_surbs).await,
+            ReplyControllerMessage::AnnounceGatewayChange => {
+                self.handle_gateway_change_announcement().await
+            }
             ReplyControllerMessage::LaneQueueLength {
                 connection_id,
                 response_channel,
@@ -70,6 +70,25 @@ impl ReplyControllerSender {
             .expect("ReplyControllerReceiver has died!")
     }
 
+    pub(crate) fn send_gateway_change(
+        &self,
+        sender_tag: AnonymousSenderTag,
+        reply_surbs: Vec<ReplySurb>,
+    ) {
+        self.0
+            .unbounded_send(ReplyControllerMessage::GatewayChange {
+                sender_tag,
+                reply_surbs,
+            })
+            .expect("ReplyControllerReceiver has died!")
+    }
+
+    pub(crate) fn announce_gateway_change(&self) {
+        self.0
+            .unbounded_send(ReplyControllerMessage::AnnounceGatewayChange)
+            .expect("ReplyControllerReceiver has died!")
+    }
+
     pub(crate) fn send_additional_surbs_request(&self, recipient: Recipient, amount: u32) {
         self.0
             .unbounded_send(ReplyControllerMessage::AdditionalSurbsRequest {
@@ -139,6 +158,17 @@ pub(crate) enum ReplyControllerMessage {
         from_surb_request: bool,
     },
 
+    /// The remote party has switched to a different gateway, so all reply surbs we had received
+    /// from it before are no longer usable.
+    GatewayChange {
+        sender_tag: AnonymousSenderTag,
+        reply_surbs: Vec<ReplySurb>,
+    },
+
+    /// We have switched to a different gateway, so all reply surbs we have given out before
+    /// are no longer usable and have to be replaced.
+    AnnounceGatewayChange,
+
     // this one doesn't belong here either...
     LaneQueueLength {
         connection_id: ConnectionId,
@@ -0,0 +1,60 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("the provided storage path doesn't have a filename defined")]
+    StoragePathWithoutFilename { provided_path: PathBuf },
+
+    #[error("failed to access the storage file - {source}")]
+    IoError {
+        #[from]
+        source: io::Error,
+    },
+
+    #[error("failed to serialize/deserialize the stored data: {source}")]
+    SerializationError {
+        #[from]
+        source: serde_json::Error,
+    },
+
+    #[error("encountered issue with our storage encryption layer: {source}")]
+    CryptoStorageError {
+        #[from]
+        source: nym_store_cipher::Error,
+    },
+
+    #[error(
+        "attempted to unlock an existing encrypted reply storage without providing a passphrase"
+    )]
+    NoPassphraseProvided,
+
+    #[error("attempted to access an existing unencrypted reply storage with a passphrase")]
+    UnexpectedPassphraseProvided,
+
+    #[error("the storage file has been created with an unsupported version {received}. The current version is {current}")]
+    UnsupportedVersion { received: u8, current: u8 },
+
+    #[error("data retrieved from the underlying storage is corrupted: {details}")]
+    CorruptedData { details: String },
+}
+
+impl StorageError {
+    /// Indicates whether the error was caused by an invalid (or missing) passphrase rather than
+    /// by the state of the stored data, in which case the data must not be discarded.
+    pub fn is_passphrase_error(&self) -> bool {
+        matches!(
+            self,
+            StorageError::NoPassphraseProvided
+                | StorageError::UnexpectedPassphraseProvided
+                | StorageError::CryptoStorageError {
+                    source: nym_store_cipher::Error::InvalidImportPassphrase
+                        | nym_store_cipher::Error::VerificationPhraseMismatch
+                }
+        )
+    }
+}
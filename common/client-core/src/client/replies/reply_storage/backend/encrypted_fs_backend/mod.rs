@@ -0,0 +1,445 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! File-based [`ReplyStorageBackend`] with optional encryption of the stored data.
+//!
+//! All the reply-related data (received reply SURBs, sent reply keys and used sender tags)
+//! is kept in a single file that is fully rewritten on every flush. If a passphrase is provided,
+//! the data is encrypted with a [`StoreCipher`] derived from it, so that none of the SURBs
+//! (or keys) could be recovered by anyone having access to the disk.
+//!
+//! Since reply SURBs must never be used more than once, the data is removed from the file
+//! for the duration of the client session and is only written back on graceful shutdown.
+
+use crate::client::replies::reply_storage::backend::encrypted_fs_backend::models::{
+    ReplyDataSnapshot, ReplySurbStorageMetadata, StoredReplyData, StoredReplyKey,
+    StoredReplyStorage, StoredSenderTag, StoredSurbSender, CURRENT_STORAGE_VERSION,
+};
+use crate::client::replies::reply_storage::surb_storage::ReceivedReplySurbs;
+use crate::client::replies::reply_storage::{
+    CombinedReplyStorage, ReceivedReplySurbsMap, ReplyStorageBackend, SentReplyKeys, UsedSenderTags,
+};
+use async_trait::async_trait;
+use log::{error, info};
+use nym_store_cipher::{Aes256Gcm, StoreCipher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use time::OffsetDateTime;
+
+pub use self::error::StorageError;
+
+mod error;
+mod models;
+
+pub struct Backend {
+    storage_path: PathBuf,
+    store_cipher: Option<StoreCipher<Aes256Gcm>>,
+    metadata: ReplySurbStorageMetadata,
+
+    // data retrieved from the storage file that is yet to be loaded into the client
+    retrieved: Mutex<Option<ReplyDataSnapshot>>,
+}
+
+impl Backend {
+    const TEMPORARY_EXTENSION: &'static str = "tmp";
+
+    /// Creates new storage at the provided path. If the passphrase is specified,
+    /// all the stored data is going to be encrypted with a key derived from it.
+    pub async fn init<P: AsRef<Path>>(
+        storage_path: P,
+        passphrase: Option<&[u8]>,
+    ) -> Result<Self, StorageError> {
+        let storage_path = Self::validate_path(storage_path)?;
+        if let Some(parent) = storage_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let store_cipher = match passphrase {
+            Some(passphrase) => {
+                info!("deriving new encryption key for the reply storage");
+                Some(StoreCipher::new_with_default_kdf(passphrase)?)
+            }
+            None => {
+                info!("the reply storage will not use any encryption");
+                None
+            }
+        };
+
+        Ok(Backend {
+            storage_path,
+            store_cipher,
+            // those are going to get overwritten in `init_fresh`
+            metadata: ReplySurbStorageMetadata::new(0, 0),
+            retrieved: Mutex::new(None),
+        })
+    }
+
+    /// Attempts to load the existing storage from the provided path. The passphrase must match
+    /// the one used when the storage was created.
+    pub async fn try_load<P: AsRef<Path>>(
+        storage_path: P,
+        passphrase: Option<&[u8]>,
+    ) -> Result<Self, StorageError> {
+        let storage_path = Self::validate_path(storage_path)?;
+        let stored: StoredReplyStorage = serde_json::from_slice(&fs::read(&storage_path)?)?;
+
+        if stored.version != CURRENT_STORAGE_VERSION {
+            return Err(StorageError::UnsupportedVersion {
+                received: stored.version,
+                current: CURRENT_STORAGE_VERSION,
+            });
+        }
+
+        let store_cipher = match (stored.cipher, passphrase) {
+            (Some(exported), Some(passphrase)) => {
+                Some(StoreCipher::import_aes256gcm(passphrase, exported)?)
+            }
+            (Some(_), None) => return Err(StorageError::NoPassphraseProvided),
+            (None, Some(_)) => return Err(StorageError::UnexpectedPassphraseProvided),
+            (None, None) => None,
+        };
+
+        let mut data = match (stored.data, &store_cipher) {
+            (StoredReplyData::Plaintext(data), None) => data,
+            (StoredReplyData::Encrypted(encrypted), Some(cipher)) => {
+                cipher.decrypt_json_value(encrypted)?
+            }
+            _ => {
+                return Err(StorageError::CorruptedData {
+                    details: "the stored data does not match the declared encryption".to_string(),
+                })
+            }
+        };
+
+        // the process has gone down without full graceful shutdown,
+        // the data has been removed at the beginning of the session so there's nothing to recover
+        if stored.client_in_use {
+            error!("the client hasn't undergone through graceful shutdown the last time it's gone down - its reply surbs and stored encryption keys are lost");
+        }
+
+        let last_flush =
+            OffsetDateTime::from_unix_timestamp(stored.last_flush_timestamp).map_err(|err| {
+                StorageError::CorruptedData {
+                    details: format!("failed to parse stored timestamp - {err}"),
+                }
+            })?;
+
+        // apply the same invalidation policy as the sqlite-based backend
+        let since_last_flush = OffsetDateTime::now_utc() - last_flush;
+        let days = since_last_flush.whole_days();
+        let hours = since_last_flush.whole_hours() % 24;
+
+        if days > 0 {
+            info!("it's been over {days} days and {hours} hours since we last used our data store. our reply surbs are already outdated - we're going to purge them now.");
+            data.surb_senders.clear();
+        }
+
+        if days > 1 {
+            info!("it's been over {days} days and {hours} hours since we last used our data store. our reply keys are already outdated - we're going to purge them now.");
+            data.reply_keys.clear();
+        }
+
+        if days > 2 {
+            info!("it's been over {days} days and {hours} hours since we last used our data store. our used sender tags are already outdated - we're going to purge them now.");
+            data.sender_tags.clear();
+        }
+
+        Ok(Backend {
+            storage_path,
+            store_cipher,
+            metadata: stored.metadata,
+            retrieved: Mutex::new(Some(data)),
+        })
+    }
+
+    fn validate_path<P: AsRef<Path>>(storage_path: P) -> Result<PathBuf, StorageError> {
+        let owned_path: PathBuf = storage_path.as_ref().into();
+        if owned_path.file_name().is_none() {
+            return Err(StorageError::StoragePathWithoutFilename {
+                provided_path: owned_path,
+            });
+        }
+        Ok(owned_path)
+    }
+
+    /// Writes the provided data to the storage file. The write is atomic, i.e. either all of the
+    /// data is going to be stored or the previous content of the file is going to remain intact.
+    fn write_storage(
+        &self,
+        data: ReplyDataSnapshot,
+        client_in_use: bool,
+    ) -> Result<(), StorageError> {
+        let (cipher, data) = match &self.store_cipher {
+            Some(cipher) => (
+                Some(cipher.export_aes256gcm()?),
+                StoredReplyData::Encrypted(cipher.encrypt_json_value(&data)?),
+            ),
+            None => (None, StoredReplyData::Plaintext(data)),
+        };
+
+        let stored = StoredReplyStorage {
+            version: CURRENT_STORAGE_VERSION,
+            cipher,
+            client_in_use,
+            last_flush_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            metadata: self.metadata,
+            data,
+        };
+
+        let new_extension = if let Some(existing_extension) =
+            self.storage_path.extension().and_then(|ext| ext.to_str())
+        {
+            format!("{existing_extension}.{}", Self::TEMPORARY_EXTENSION)
+        } else {
+            Self::TEMPORARY_EXTENSION.to_string()
+        };
+        let mut temp_path = self.storage_path.clone();
+        temp_path.set_extension(new_extension);
+
+        fs::write(&temp_path, serde_json::to_vec(&stored)?)?;
+        fs::rename(&temp_path, &self.storage_path)?;
+        Ok(())
+    }
+
+    fn snapshot(storage: &CombinedReplyStorage) -> ReplyDataSnapshot {
+        let sender_tags = storage
+            .tags_storage_ref()
+            .as_raw_iter()
+            .map(|map_ref| {
+                let (recipient, tag) = map_ref.pair();
+                StoredSenderTag::new(*recipient, *tag)
+            })
+            .collect();
+
+        let reply_keys = storage
+            .key_storage_ref()
+            .as_raw_iter()
+            .map(|map_ref| {
+                let (digest, key) = map_ref.pair();
+                StoredReplyKey::new(*digest, *key)
+            })
+            .collect();
+
+        let surb_senders = storage
+            .surbs_storage_ref()
+            .as_raw_iter()
+            .map(|map_ref| {
+                let (tag, received_surbs) = map_ref.pair();
+                StoredSurbSender::new(
+                    *tag,
+                    received_surbs.surbs_last_received_at(),
+                    received_surbs.surbs_ref(),
+                )
+            })
+            .collect();
+
+        ReplyDataSnapshot {
+            sender_tags,
+            reply_keys,
+            surb_senders,
+        }
+    }
+
+    fn restore(&self, data: ReplyDataSnapshot) -> Result<CombinedReplyStorage, StorageError> {
+        // stop at the first instance of corruption. if even a single entry is malformed,
+        // something weird has happened and we can't trust the rest of the data
+        let tags = data
+            .sender_tags
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<_, _>>()?;
+
+        let reply_keys = data
+            .reply_keys
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<_, _>>()?;
+
+        let mut received_surbs = Vec::with_capacity(data.surb_senders.len());
+        for sender in data.surb_senders {
+            let (sender_tag, surbs_last_received_at_timestamp, surbs) = sender.try_into()?;
+            received_surbs.push((
+                sender_tag,
+                ReceivedReplySurbs::new_retrieved(surbs, surbs_last_received_at_timestamp),
+            ))
+        }
+
+        Ok(CombinedReplyStorage::load(
+            SentReplyKeys::from_raw(reply_keys),
+            ReceivedReplySurbsMap::from_raw(
+                self.metadata.min_reply_surb_threshold as usize,
+                self.metadata.max_reply_surb_threshold as usize,
+                received_surbs,
+            ),
+            UsedSenderTags::from_raw(tags),
+        ))
+    }
+}
+
+#[async_trait]
+impl ReplyStorageBackend for Backend {
+    type StorageError = StorageError;
+
+    async fn start_storage_session(&self) -> Result<(), Self::StorageError> {
+        // the data now lives in the memory of the client.
+        // remove it from the disk so that the same surbs could never be used twice
+        // if the client were to crash before flushing its state
+        self.write_storage(ReplyDataSnapshot::default(), true)
+    }
+
+    async fn flush_surb_storage(
+        &mut self,
+        storage: &CombinedReplyStorage,
+    ) -> Result<(), Self::StorageError> {
+        let surbs_ref = storage.surbs_storage_ref();
+        self.metadata = ReplySurbStorageMetadata::new(
+            surbs_ref.min_surb_threshold(),
+            surbs_ref.max_surb_threshold(),
+        );
+        self.write_storage(Self::snapshot(storage), false)
+    }
+
+    async fn init_fresh(&mut self, fresh: &CombinedReplyStorage) -> Result<(), Self::StorageError> {
+        // for now nothing more to do apart from dumping the metadata
+        self.flush_surb_storage(fresh).await
+    }
+
+    async fn load_surb_storage(&self) -> Result<CombinedReplyStorage, Self::StorageError> {
+        let retrieved = self
+            .retrieved
+            .lock()
+            .map_err(|_| StorageError::CorruptedData {
+                details: "the retrieved data lock got poisoned".to_string(),
+            })?
+            .take()
+            .unwrap_or_default();
+
+        self.restore(retrieved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::{encryption, identity};
+    use nym_sphinx::addressing::clients::Recipient;
+    use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+    use nym_sphinx::anonymous_replies::SurbEncryptionKey;
+    use rand::rngs::OsRng;
+
+    const PASSPHRASE: &[u8] = b"my secret passphrase";
+
+    fn recipient() -> Recipient {
+        let mut rng = OsRng;
+        Recipient::new(
+            *identity::KeyPair::new(&mut rng).public_key(),
+            *encryption::KeyPair::new(&mut rng).public_key(),
+            *identity::KeyPair::new(&mut rng).public_key(),
+        )
+    }
+
+    async fn fresh_backend(path: &Path, passphrase: Option<&[u8]>) -> Backend {
+        let mut backend = Backend::init(path, passphrase).await.unwrap();
+        backend
+            .init_fresh(&CombinedReplyStorage::new(10, 100))
+            .await
+            .unwrap();
+        backend
+    }
+
+    #[tokio::test]
+    async fn data_survives_graceful_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reply_store.json");
+
+        let recipient = recipient();
+        let tag = AnonymousSenderTag::new_random(&mut OsRng);
+        let reply_key = SurbEncryptionKey::new(&mut OsRng);
+
+        let mut backend = fresh_backend(&path, Some(PASSPHRASE)).await;
+        backend.start_storage_session().await.unwrap();
+        let storage = backend.load_surb_storage().await.unwrap();
+        storage.tags_storage_ref().insert_new(&recipient, tag);
+        storage.key_storage_ref().insert_multiple(vec![reply_key]);
+        backend.flush_surb_storage(&storage).await.unwrap();
+        drop(backend);
+
+        let backend = Backend::try_load(&path, Some(PASSPHRASE)).await.unwrap();
+        let restored = backend.load_surb_storage().await.unwrap();
+        assert_eq!(
+            restored.tags_storage_ref().try_get_existing(&recipient),
+            Some(tag)
+        );
+        let restored_key = restored
+            .key_storage_ref()
+            .try_pop(reply_key.compute_digest())
+            .unwrap();
+        assert_eq!(restored_key.to_bytes(), reply_key.to_bytes());
+        assert_eq!(restored.surbs_storage_ref().min_surb_threshold(), 10);
+        assert_eq!(restored.surbs_storage_ref().max_surb_threshold(), 100);
+    }
+
+    #[tokio::test]
+    async fn data_is_not_kept_on_disk_during_the_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reply_store.json");
+        let recipient = recipient();
+
+        let mut backend = fresh_backend(&path, Some(PASSPHRASE)).await;
+        let storage = backend.load_surb_storage().await.unwrap();
+        storage
+            .tags_storage_ref()
+            .insert_new(&recipient, AnonymousSenderTag::new_random(&mut OsRng));
+        backend.flush_surb_storage(&storage).await.unwrap();
+
+        // the client starts using the data, but crashes before flushing it back
+        let backend = Backend::try_load(&path, Some(PASSPHRASE)).await.unwrap();
+        backend.start_storage_session().await.unwrap();
+        drop(backend);
+
+        let backend = Backend::try_load(&path, Some(PASSPHRASE)).await.unwrap();
+        let restored = backend.load_surb_storage().await.unwrap();
+        assert!(!restored.tags_storage_ref().exists(&recipient));
+    }
+
+    #[tokio::test]
+    async fn encrypted_data_is_not_readable_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reply_store.json");
+
+        let mut backend = fresh_backend(&path, Some(PASSPHRASE)).await;
+        let storage = backend.load_surb_storage().await.unwrap();
+        storage
+            .tags_storage_ref()
+            .insert_new(&recipient(), AnonymousSenderTag::new_random(&mut OsRng));
+        backend.flush_surb_storage(&storage).await.unwrap();
+
+        let stored: StoredReplyStorage = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert!(stored.cipher.is_some());
+        assert!(matches!(stored.data, StoredReplyData::Encrypted(_)));
+    }
+
+    #[tokio::test]
+    async fn passphrase_must_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let encrypted_path = dir.path().join("encrypted.json");
+        let plaintext_path = dir.path().join("plaintext.json");
+
+        fresh_backend(&encrypted_path, Some(PASSPHRASE)).await;
+        fresh_backend(&plaintext_path, None).await;
+
+        for (path, passphrase) in [
+            (&encrypted_path, Some(b"wrong passphrase".as_slice())),
+            (&encrypted_path, None),
+            (&plaintext_path, Some(PASSPHRASE)),
+        ] {
+            let Err(err) = Backend::try_load(path, passphrase).await else {
+                panic!("managed to load the storage with invalid passphrase")
+            };
+            assert!(err.is_passphrase_error());
+        }
+
+        assert!(Backend::try_load(&plaintext_path, None).await.is_ok());
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::client::replies::reply_storage::backend::encrypted_fs_backend::error::StorageError;
+use crate::client::replies::reply_storage::key_storage::UsedReplyKey;
+use nym_crypto::generic_array::typenum::Unsigned;
+use nym_crypto::Digest;
+use nym_sphinx::addressing::clients::{Recipient, RecipientBytes};
+use nym_sphinx::anonymous_replies::encryption_key::EncryptionKeyDigest;
+use nym_sphinx::anonymous_replies::requests::{AnonymousSenderTag, SENDER_TAG_SIZE};
+use nym_sphinx::anonymous_replies::{ReplySurb, SurbEncryptionKey, SurbEncryptionKeySize};
+use nym_sphinx::params::ReplySurbKeyDigestAlgorithm;
+use nym_store_cipher::{EncryptedData, ExportedStoreCipher};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const CURRENT_STORAGE_VERSION: u8 = 1;
+
+/// The content of the storage file.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredReplyStorage {
+    pub(crate) version: u8,
+
+    /// Information required to re-derive the store cipher if the data is encrypted.
+    pub(crate) cipher: Option<ExportedStoreCipher>,
+
+    pub(crate) client_in_use: bool,
+    pub(crate) last_flush_timestamp: i64,
+    pub(crate) metadata: ReplySurbStorageMetadata,
+    pub(crate) data: StoredReplyData,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) enum StoredReplyData {
+    Plaintext(ReplyDataSnapshot),
+    Encrypted(EncryptedData),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ReplyDataSnapshot {
+    pub(crate) sender_tags: Vec<StoredSenderTag>,
+    pub(crate) reply_keys: Vec<StoredReplyKey>,
+    pub(crate) surb_senders: Vec<StoredSurbSender>,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub(crate) struct ReplySurbStorageMetadata {
+    pub(crate) min_reply_surb_threshold: u32,
+    pub(crate) max_reply_surb_threshold: u32,
+}
+
+impl ReplySurbStorageMetadata {
+    pub(crate) fn new(min_reply_surb_threshold: usize, max_reply_surb_threshold: usize) -> Self {
+        Self {
+            min_reply_surb_threshold: min_reply_surb_threshold as u32,
+            max_reply_surb_threshold: max_reply_surb_threshold as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StoredSenderTag {
+    pub(crate) recipient: Vec<u8>,
+    pub(crate) tag: Vec<u8>,
+}
+
+impl StoredSenderTag {
+    pub(crate) fn new(recipient: RecipientBytes, tag: AnonymousSenderTag) -> StoredSenderTag {
+        StoredSenderTag {
+            recipient: recipient.to_vec(),
+            tag: tag.to_bytes().to_vec(),
+        }
+    }
+}
+
+fn recover_sender_tag(raw: Vec<u8>) -> Result<AnonymousSenderTag, StorageError> {
+    let tag_len = raw.len();
+    let Ok(sender_tag_bytes) = raw.try_into() else {
+        return Err(StorageError::CorruptedData {
+            details: format!(
+                "the retrieved sender tag has length of {tag_len} while {SENDER_TAG_SIZE} was expected",
+            ),
+        });
+    };
+    Ok(AnonymousSenderTag::from_bytes(sender_tag_bytes))
+}
+
+impl TryFrom<StoredSenderTag> for (RecipientBytes, AnonymousSenderTag) {
+    type Error = StorageError;
+
+    fn try_from(value: StoredSenderTag) -> Result<Self, Self::Error> {
+        let recipient_len = value.recipient.len();
+        let Ok(recipient_bytes) = value.recipient.try_into() else {
+            return Err(StorageError::CorruptedData {
+                details: format!(
+                    "the retrieved recipient has length of {recipient_len} while {} was expected",
+                    Recipient::LEN
+                ),
+            });
+        };
+
+        Ok((recipient_bytes, recover_sender_tag(value.tag)?))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StoredReplyKey {
+    pub(crate) key_digest: Vec<u8>,
+    pub(crate) reply_key: Vec<u8>,
+    pub(crate) sent_at_timestamp: i64,
+}
+
+impl StoredReplyKey {
+    pub(crate) fn new(key_digest: EncryptionKeyDigest, reply_key: UsedReplyKey) -> StoredReplyKey {
+        StoredReplyKey {
+            key_digest: key_digest.to_vec(),
+            reply_key: (*reply_key).to_bytes(),
+            sent_at_timestamp: reply_key.sent_at_timestamp,
+        }
+    }
+}
+
+impl TryFrom<StoredReplyKey> for (EncryptionKeyDigest, UsedReplyKey) {
+    type Error = StorageError;
+
+    fn try_from(value: StoredReplyKey) -> Result<Self, Self::Error> {
+        let expected_reply_key_digest_size = ReplySurbKeyDigestAlgorithm::output_size();
+        let reply_key_digest_size = value.key_digest.len();
+
+        let Some(digest) = EncryptionKeyDigest::from_exact_iter(value.key_digest) else {
+            return Err(StorageError::CorruptedData {
+                details: format!(
+                    "the reply surb digest has length of {reply_key_digest_size} while {expected_reply_key_digest_size} was expected",
+                ),
+            });
+        };
+
+        let reply_key_len = value.reply_key.len();
+        let Ok(reply_key) = SurbEncryptionKey::try_from_bytes(&value.reply_key) else {
+            return Err(StorageError::CorruptedData {
+                details: format!(
+                    "the reply key has length of {reply_key_len} while {} was expected",
+                    SurbEncryptionKeySize::USIZE
+                ),
+            });
+        };
+
+        Ok((
+            digest,
+            UsedReplyKey::new(reply_key, value.sent_at_timestamp),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StoredSurbSender {
+    pub(crate) tag: Vec<u8>,
+    pub(crate) last_sent_timestamp: i64,
+    pub(crate) reply_surbs: Vec<Vec<u8>>,
+}
+
+impl StoredSurbSender {
+    pub(crate) fn new<'a, I>(tag: AnonymousSenderTag, last_sent_timestamp: i64, surbs: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ReplySurb>,
+    {
+        StoredSurbSender {
+            tag: tag.to_bytes().to_vec(),
+            last_sent_timestamp,
+            reply_surbs: surbs.into_iter().map(|surb| surb.to_bytes()).collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredSurbSender> for (AnonymousSenderTag, i64, Vec<ReplySurb>) {
+    type Error = StorageError;
+
+    fn try_from(value: StoredSurbSender) -> Result<Self, Self::Error> {
+        let sender_tag = recover_sender_tag(value.tag)?;
+        let reply_surbs = value
+            .reply_surbs
+            .iter()
+            .map(|raw| {
+                ReplySurb::from_bytes(raw).map_err(|err| StorageError::CorruptedData {
+                    details: format!("failed to recover the reply surb: {err}"),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok((sender_tag, value.last_sent_timestamp, reply_surbs))
+    }
+}
@@ -12,6 +12,9 @@ pub mod browser_backend;
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
 pub mod fs_backend;
 
+#[cfg(all(not(target_arch = "wasm32"), feature = "encrypted-surb-storage"))]
+pub mod encrypted_fs_backend;
+
 // #[cfg(all(test, feature = "std"))]
 // third case: node with actual filesystem
 
@@ -186,6 +186,22 @@ impl ReceivedReplySurbsMap {
             self.inner.data.insert(*target, new_entry);
         }
     }
+
+    /// Discards all previously received reply surbs from the target and replaces them
+    /// with the provided ones.
+    pub(crate) fn replace_surbs<I: IntoIterator<Item = ReplySurb>>(
+        &self,
+        target: &AnonymousSenderTag,
+        surbs: I,
+    ) {
+        if let Some(mut existing_data) = self.inner.data.get_mut(target) {
+            existing_data.clear_reply_surbs();
+            existing_data.insert_reply_surbs(surbs)
+        } else {
+            let new_entry = ReceivedReplySurbs::new(surbs.into_iter().collect());
+            self.inner.data.insert(*target, new_entry);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -267,6 +283,11 @@ impl ReceivedReplySurbs {
         self.data.len()
     }
 
+    fn clear_reply_surbs(&mut self) {
+        trace!("discarding {} surbs from the storage", self.data.len());
+        self.data.clear();
+    }
+
     // realistically we're always going to be getting multiple surbs at once
     pub(crate) fn insert_reply_surbs<I: IntoIterator<Item = ReplySurb>>(&mut self, surbs: I) {
         let mut v = surbs.into_iter().collect::<VecDeque<_>>();
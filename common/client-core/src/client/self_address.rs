@@ -0,0 +1,32 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nym_sphinx::addressing::clients::Recipient;
+use nym_sphinx::addressing::nodes::NodeIdentity;
+use std::sync::{Arc, RwLock};
+
+/// Address of this client shared between all the components embedding it in the packets they
+/// create, such as acknowledgements, reply surbs or loop cover messages.
+/// Its gateway part changes whenever the client fails over to one of its standby gateways.
+#[derive(Debug, Clone)]
+pub struct SelfAddress {
+    inner: Arc<RwLock<Recipient>>,
+}
+
+impl SelfAddress {
+    pub fn new(address: Recipient) -> Self {
+        SelfAddress {
+            inner: Arc::new(RwLock::new(address)),
+        }
+    }
+
+    pub fn get(&self) -> Recipient {
+        *self.inner.read().expect("self address lock got poisoned")
+    }
+
+    pub(crate) fn change_gateway(&self, gateway: NodeIdentity) -> Recipient {
+        let mut address = self.inner.write().expect("self address lock got poisoned");
+        *address = Recipient::new(*address.identity(), *address.encryption_key(), gateway);
+        *address
+    }
+}
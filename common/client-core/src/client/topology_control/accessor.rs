@@ -1,13 +1,17 @@
 // Copyright 2021-2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use log::debug;
 use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::params::DEFAULT_NUM_MIX_HOPS;
-use nym_topology::{NymTopology, NymTopologyError};
+use nym_topology::{NymTopology, NymTopologyDiff, NymTopologyError};
 use std::ops::Deref;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::{Notify, RwLock, RwLockReadGuard};
+use tokio::sync::{broadcast, Notify, RwLock, RwLockReadGuard};
+
+// the number of topology changes that can be buffered for a lagging subscriber
+const TOPOLOGY_CHANGES_CHANNEL_CAPACITY: usize = 16;
 
 #[derive(Debug)]
 pub struct TopologyAccessorInner {
@@ -18,6 +22,9 @@ pub struct TopologyAccessorInner {
     // However, proper benchmarks will be needed to determine if `RwLock` is indeed a better
     // approach than a `Mutex`
     topology: RwLock<Option<NymTopology>>,
+
+    // notifies all subscribers about any changes to the network topology
+    topology_changes: broadcast::Sender<Arc<NymTopologyDiff>>,
 }
 
 impl TopologyAccessorInner {
@@ -26,11 +33,30 @@ impl TopologyAccessorInner {
             controlled_manually: AtomicBool::new(false),
             released_manual_control: Notify::new(),
             topology: RwLock::new(None),
+            topology_changes: broadcast::channel(TOPOLOGY_CHANGES_CHANNEL_CAPACITY).0,
         }
     }
 
     async fn update(&self, new: Option<NymTopology>) {
-        *self.topology.write().await = new;
+        let mut topology = self.topology.write().await;
+        let diff = NymTopologyDiff::compute(topology.as_ref(), new.as_ref());
+        *topology = new;
+        drop(topology);
+
+        if diff.is_empty() {
+            return;
+        }
+        debug!(
+            "the network topology has changed: {} mixnodes added, {} mixnodes removed, {} mixnodes changed layers, {} gateways added, {} gateways removed",
+            diff.added_mixnodes.len(),
+            diff.removed_mixnodes.len(),
+            diff.mixnode_layer_changes.len(),
+            diff.added_gateways.len(),
+            diff.removed_gateways.len(),
+        );
+
+        // the send can only fail if there are no subscribers, which is fine
+        let _ = self.topology_changes.send(Arc::new(diff));
     }
 }
 
@@ -121,6 +147,13 @@ impl TopologyAccessor {
         self.inner.released_manual_control.notified().await
     }
 
+    /// Subscribe to the changes of the network topology. A [`NymTopologyDiff`] is published
+    /// every time the topology gets updated, either by the refresher or manually,
+    /// and it's different from the previous one.
+    pub fn subscribe_to_changes(&self) -> broadcast::Receiver<Arc<NymTopologyDiff>> {
+        self.inner.topology_changes.subscribe()
+    }
+
     pub async fn current_topology(&self) -> Option<NymTopology> {
         self.inner.topology.read().await.clone()
     }
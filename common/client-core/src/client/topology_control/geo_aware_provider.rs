@@ -14,6 +14,7 @@ use serde::{Deserialize, Serialize};
 use tap::TapOptional;
 use url::Url;
 
+use crate::client::topology_control::get_announced_sphinx_keys;
 use crate::config::GroupBy;
 
 const MIN_NODES_PER_LAYER: usize = 1;
@@ -338,7 +339,9 @@ impl GeoAwareTopologyProvider {
             .filter(|m| filtered_mixnode_ids.contains(&m.mix_id()))
             .collect::<Vec<_>>();
 
-        let topology = nym_topology_from_detailed(mixnodes, gateways)
+        let announced_sphinx_keys = get_announced_sphinx_keys(&self.validator_client).await;
+
+        let topology = nym_topology_from_detailed(mixnodes, gateways, &announced_sphinx_keys)
             .filter_system_version(&self.client_version);
 
         // TODO: return real error type
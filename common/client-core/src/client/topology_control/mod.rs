@@ -8,7 +8,10 @@ use log::*;
 use nym_sphinx::addressing::nodes::NodeIdentity;
 use nym_topology::provider_trait::TopologyProvider;
 use nym_topology::NymTopologyError;
+use nym_validator_client::models::MixnodeSphinxKeys;
+use nym_validator_client::NymApiClient;
 use std::time::Duration;
+use url::Url;
 
 #[cfg(not(target_arch = "wasm32"))]
 use tokio::time::sleep;
@@ -19,17 +22,54 @@ use wasmtimer::tokio::sleep;
 mod accessor;
 pub mod geo_aware_provider;
 pub(crate) mod nym_api_provider;
+#[cfg(not(target_arch = "wasm32"))]
+mod rtt;
 
 // TODO: move it to config later
 const MAX_FAILURE_COUNT: usize = 10;
 
+// while subscribed to the network events, only every n-th periodic refresh is actually performed
+#[cfg(not(target_arch = "wasm32"))]
+const SUBSCRIBED_REFRESH_RATE_MULTIPLIER: u32 = 10;
+
+// the network events tend to come in bursts (e.g. on epoch transitions),
+// so wait a bit after receiving one to refresh the topology only once
+#[cfg(not(target_arch = "wasm32"))]
+const NETWORK_EVENTS_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Retrieves the rotated sphinx keys announced by the mixnodes, so that they could be attached
+/// to the constructed topology. Failure is not fatal as the bonded keys are going to be used instead.
+pub(crate) async fn get_announced_sphinx_keys(
+    validator_client: &NymApiClient,
+) -> Vec<MixnodeSphinxKeys> {
+    match validator_client.get_cached_mixnodes_sphinx_keys().await {
+        Ok(announced) => announced,
+        Err(err) => {
+            debug!("failed to get rotated mixnode sphinx keys - {err}. the bonded keys are going to be used instead");
+            Vec::new()
+        }
+    }
+}
+
 pub struct TopologyRefresherConfig {
     refresh_rate: Duration,
+    network_events_sources: Vec<Url>,
 }
 
 impl TopologyRefresherConfig {
     pub fn new(refresh_rate: Duration) -> Self {
-        TopologyRefresherConfig { refresh_rate }
+        TopologyRefresherConfig {
+            refresh_rate,
+            network_events_sources: Vec::new(),
+        }
+    }
+
+    /// Refresh the topology whenever any of the provided nym-apis announces a network change
+    /// rather than purely relying on the periodic refresh.
+    #[must_use]
+    pub fn with_network_events_sources(mut self, nym_apis: Vec<Url>) -> Self {
+        self.network_events_sources = nym_apis;
+        self
     }
 }
 
@@ -39,6 +79,9 @@ pub struct TopologyRefresher {
 
     refresh_rate: Duration,
     consecutive_failure_count: usize,
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    network_events_sources: Vec<Url>,
 }
 
 impl TopologyRefresher {
@@ -52,6 +95,7 @@ impl TopologyRefresher {
             topology_accessor,
             refresh_rate: cfg.refresh_rate,
             consecutive_failure_count: 0,
+            network_events_sources: cfg.network_events_sources,
         }
     }
 
@@ -141,10 +185,115 @@ impl TopologyRefresher {
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn subscribe_to_network_events(
+        &self,
+        attempt: usize,
+    ) -> Option<nym_validator_client::nym_api::events::NetworkEventsSubscription> {
+        use nym_validator_client::nym_api::events::NetworkEventsSubscription;
+
+        if self.network_events_sources.is_empty() {
+            return None;
+        }
+
+        // rotate through the available nym-apis on subsequent attempts
+        let nym_api = &self.network_events_sources[attempt % self.network_events_sources.len()];
+        match NetworkEventsSubscription::new(nym_api).await {
+            Ok(subscription) => {
+                info!("subscribed to the network events of {nym_api}");
+                Some(subscription)
+            }
+            Err(err) => {
+                warn!("failed to subscribe to the network events of {nym_api}: {err}");
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn run_with_network_events(&mut self, shutdown: &mut nym_task::TaskClient) {
+        use nym_validator_client::nym_api::events::NetworkEvent;
+        use std::pin::Pin;
+        use tokio::time::{interval, Sleep};
+
+        let mut interval = interval(self.refresh_rate);
+        // the initial topology has already been obtained
+        interval.reset();
+
+        let mut subscription_attempt = 0;
+        let mut subscription = self.subscribe_to_network_events(subscription_attempt).await;
+        let mut skipped_refreshes = 0;
+        let mut pending_refresh: Option<Pin<Box<Sleep>>> = None;
+
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => {
+                    log::trace!("TopologyRefresher: Received shutdown");
+                },
+                _ = async { pending_refresh.as_mut().unwrap().await }, if pending_refresh.is_some() => {
+                    pending_refresh = None;
+                    self.try_refresh().await;
+                }
+                event = async { subscription.as_mut().unwrap().next().await }, if subscription.is_some() => {
+                    match event {
+                        Some(Ok(NetworkEvent::Unknown)) => {
+                            debug!("ignoring network event unknown to this client");
+                        }
+                        Some(Ok(event)) => {
+                            debug!("received network event: {}", event.name());
+                            if pending_refresh.is_none() {
+                                pending_refresh = Some(Box::pin(sleep(NETWORK_EVENTS_DEBOUNCE)));
+                            }
+                        }
+                        Some(Err(err)) => {
+                            // the connection might have silently died, so try to resubscribe straight away
+                            // rather than relying on the reduced refresh rate until the next attempt
+                            warn!("the network events subscription has failed: {err}. attempting to resubscribe");
+                            self.try_refresh().await;
+                            subscription_attempt += 1;
+                            subscription = self.subscribe_to_network_events(subscription_attempt).await;
+                            if subscription.is_none() {
+                                warn!("falling back to the periodic refresh");
+                            }
+                        }
+                        None => {
+                            warn!("the network events subscription has been closed. falling back to the periodic refresh");
+                            subscription = None;
+                        }
+                    }
+                }
+                _ = interval.tick() => {
+                    if subscription.is_some() {
+                        skipped_refreshes += 1;
+                        if skipped_refreshes < SUBSCRIBED_REFRESH_RATE_MULTIPLIER {
+                            continue
+                        }
+                    }
+                    skipped_refreshes = 0;
+                    self.try_refresh().await;
+
+                    if subscription.is_none() {
+                        subscription_attempt += 1;
+                        subscription = self.subscribe_to_network_events(subscription_attempt).await;
+                    }
+                }
+            }
+        }
+    }
+
     pub fn start_with_shutdown(mut self, mut shutdown: nym_task::TaskClient) {
         spawn_future(async move {
             debug!("Started TopologyRefresher with graceful shutdown support");
 
+            #[cfg(not(target_arch = "wasm32"))]
+            if !self.network_events_sources.is_empty() {
+                self.run_with_network_events(&mut shutdown).await;
+                shutdown.recv_timeout().await;
+                log::debug!("TopologyRefresher: Exiting");
+                return;
+            }
+
             #[cfg(not(target_arch = "wasm32"))]
             let mut interval = tokio_stream::wrappers::IntervalStream::new(tokio::time::interval(
                 self.refresh_rate,
@@ -1,10 +1,14 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client::topology_control::get_announced_sphinx_keys;
 use async_trait::async_trait;
 use log::{error, warn};
 use nym_topology::provider_trait::TopologyProvider;
-use nym_topology::{nym_topology_from_detailed, NymTopology, NymTopologyError};
+use nym_topology::{
+    nym_topology_from_detailed, NymTopology, NymTopologyError, WeightedRouteSelector,
+};
+use nym_validator_client::models::MixNodeBondAnnotated;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 use url::Url;
@@ -15,10 +19,23 @@ pub(crate) struct NymApiTopologyProvider {
 
     client_version: String,
     currently_used_api: usize,
+
+    // specifies how strongly the route selection is biased towards reliable nodes.
+    // if it's 0, the node performance is not going to be retrieved at all
+    route_selection_performance_bias: f64,
+
+    // specifies whether the route selection should also take the locally measured rtt into account
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    route_selection_measure_rtt: bool,
 }
 
 impl NymApiTopologyProvider {
-    pub(crate) fn new(mut nym_api_urls: Vec<Url>, client_version: String) -> Self {
+    pub(crate) fn new(
+        mut nym_api_urls: Vec<Url>,
+        client_version: String,
+        route_selection_performance_bias: f64,
+        route_selection_measure_rtt: bool,
+    ) -> Self {
         nym_api_urls.shuffle(&mut thread_rng());
 
         NymApiTopologyProvider {
@@ -28,6 +45,8 @@ impl NymApiTopologyProvider {
             nym_api_urls,
             client_version,
             currently_used_api: 0,
+            route_selection_performance_bias,
+            route_selection_measure_rtt,
         }
     }
 
@@ -60,13 +79,58 @@ impl NymApiTopologyProvider {
         active_topology.ensure_even_layer_distribution(lower_threshold, upper_threshold)
     }
 
+    fn build_route_selector(&self, annotated: &[MixNodeBondAnnotated]) -> WeightedRouteSelector {
+        let mut selector = WeightedRouteSelector::new(self.route_selection_performance_bias);
+        for node in annotated {
+            let performance = node.node_performance.last_hour.round_to_integer() as f64 / 100.0;
+            selector.set_performance(node.mix_id(), performance);
+        }
+        selector
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn with_measured_rtt(
+        &self,
+        mut selector: WeightedRouteSelector,
+        topology: &NymTopology,
+    ) -> WeightedRouteSelector {
+        if self.route_selection_measure_rtt {
+            for (mix_id, rtt) in super::rtt::measure_mixnodes_rtt(topology).await {
+                selector.set_rtt(mix_id, rtt)
+            }
+        }
+        selector
+    }
+
     async fn get_current_compatible_topology(&mut self) -> Option<NymTopology> {
-        let mixnodes = match self.validator_client.get_cached_active_mixnodes().await {
-            Err(err) => {
-                error!("failed to get network mixnodes - {err}");
-                return None;
+        // only bother retrieving the (much bigger) detailed response if we're going to use it
+        let (mixnodes, route_selector) = if self.route_selection_performance_bias > 0.0 {
+            match self
+                .validator_client
+                .get_cached_active_mixnodes_detailed()
+                .await
+            {
+                Err(err) => {
+                    error!("failed to get network mixnodes - {err}");
+                    return None;
+                }
+                Ok(annotated) => {
+                    let selector = self.build_route_selector(&annotated);
+                    let mixes = annotated
+                        .into_iter()
+                        .map(|node| node.mixnode_details)
+                        .collect();
+                    (mixes, Some(selector))
+                }
+            }
+        } else {
+            match self.validator_client.get_cached_active_mixnodes().await {
+                Err(err) => {
+                    error!("failed to get network mixnodes - {err}");
+                    return None;
+                }
+                Ok(mixes) => (mixes, None),
             }
-            Ok(mixes) => mixes,
         };
 
         let gateways = match self.validator_client.get_cached_described_gateways().await {
@@ -77,8 +141,15 @@ impl NymApiTopologyProvider {
             Ok(gateways) => gateways,
         };
 
-        let topology = nym_topology_from_detailed(mixnodes, gateways)
+        let announced_sphinx_keys = get_announced_sphinx_keys(&self.validator_client).await;
+
+        let mut topology = nym_topology_from_detailed(mixnodes, gateways, &announced_sphinx_keys)
             .filter_system_version(&self.client_version);
+        if let Some(route_selector) = route_selector {
+            #[cfg(not(target_arch = "wasm32"))]
+            let route_selector = self.with_measured_rtt(route_selector, &topology).await;
+            topology.set_route_selector(route_selector)
+        }
 
         if let Err(err) = self.check_layer_distribution(&topology) {
             warn!("The current filtered active topology has extremely skewed layer distribution. It cannot be used: {err}");
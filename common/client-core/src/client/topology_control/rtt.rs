@@ -0,0 +1,54 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::{stream, StreamExt};
+use log::{debug, trace};
+use nym_topology::NymTopology;
+use nym_validator_client::client::MixId;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+// Maximum amount of time we're willing to wait for a single node to accept the connection
+const RTT_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Maximum number of the nodes being probed at the same time
+const MAX_CONCURRENT_PROBES: usize = 32;
+
+/// Estimates the round-trip time to every mixnode in the topology by timing the establishment
+/// of a TCP connection to its mix port. Nodes that could not be reached in time are omitted.
+pub(crate) async fn measure_mixnodes_rtt(topology: &NymTopology) -> HashMap<MixId, Duration> {
+    let targets = topology
+        .mixes()
+        .values()
+        .flatten()
+        .map(|node| (node.mix_id, node.mix_host))
+        .collect::<Vec<_>>();
+
+    let measured = stream::iter(targets)
+        .map(|(mix_id, mix_host)| async move {
+            let start = Instant::now();
+            match tokio::time::timeout(RTT_PROBE_TIMEOUT, TcpStream::connect(mix_host)).await {
+                Ok(Ok(_)) => Some((mix_id, start.elapsed())),
+                Ok(Err(err)) => {
+                    trace!("failed to probe mixnode {mix_id} at {mix_host}: {err}");
+                    None
+                }
+                Err(_) => {
+                    trace!("timed out while probing mixnode {mix_id} at {mix_host}");
+                    None
+                }
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_PROBES)
+        .filter_map(|measured| async move { measured })
+        .collect::<HashMap<_, _>>()
+        .await;
+
+    debug!(
+        "measured the round-trip time to {} mixnodes",
+        measured.len()
+    );
+    measured
+}
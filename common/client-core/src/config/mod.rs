@@ -38,6 +38,10 @@ const DEFAULT_MAX_STARTUP_GATEWAY_WAITING_PERIOD: Duration = Duration::from_secs
 // bandwidth bridging protocol, we can come back to a smaller timeout value
 const DEFAULT_GATEWAY_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
 
+// standby gateways are opt-in so that the existing clients wouldn't register with additional,
+// random, gateways upon upgrading
+const DEFAULT_STANDBY_GATEWAYS: usize = 0;
+
 const DEFAULT_COVER_TRAFFIC_PRIMARY_SIZE_RATIO: f64 = 0.70;
 
 // reply-surbs related:
@@ -180,6 +184,11 @@ impl Config {
         self
     }
 
+    pub fn with_network_events_subscription(mut self, subscribe: bool) -> Self {
+        self.debug.topology.subscribe_to_network_events = subscribe;
+        self
+    }
+
     pub fn with_topology_structure(mut self, topology_structure: TopologyStructure) -> Self {
         self.set_topology_structure(topology_structure);
         self
@@ -189,6 +198,24 @@ impl Config {
         self.debug.topology.topology_structure = topology_structure;
     }
 
+    pub fn with_route_selection_performance_bias(mut self, performance_bias: f64) -> Self {
+        self.set_route_selection_performance_bias(performance_bias);
+        self
+    }
+
+    pub fn set_route_selection_performance_bias(&mut self, performance_bias: f64) {
+        self.debug.topology.route_selection_performance_bias = performance_bias;
+    }
+
+    pub fn with_route_selection_rtt_measurement(mut self, measure_rtt: bool) -> Self {
+        self.set_route_selection_rtt_measurement(measure_rtt);
+        self
+    }
+
+    pub fn set_route_selection_rtt_measurement(&mut self, measure_rtt: bool) {
+        self.debug.topology.route_selection_measure_rtt = measure_rtt;
+    }
+
     pub fn with_no_per_hop_delays(mut self, no_per_hop_delays: bool) -> Self {
         if no_per_hop_delays {
             self.set_no_per_hop_delays()
@@ -401,6 +428,12 @@ pub struct Traffic {
     pub secondary_packet_size: Option<PacketSize>,
 
     pub packet_type: PacketType,
+
+    /// Number of additional Reed-Solomon parity fragments sent alongside each message to a known
+    /// recipient, so that it could be reconstructed even if up to that many of its packets got lost.
+    /// Messages that do not fit in a single erasure coded set are sent without any parity data.
+    /// Note that the recipient has to understand erasure coded messages. Set to 0 to disable it.
+    pub erasure_coding_parity_fragments: u8,
 }
 
 impl Traffic {
@@ -425,6 +458,7 @@ impl Default for Traffic {
             primary_packet_size: PacketSize::RegularPacket,
             secondary_packet_size: None,
             packet_type: PacketType::Mix,
+            erasure_coding_parity_fragments: 0,
         }
     }
 }
@@ -463,12 +497,18 @@ pub struct GatewayConnection {
     /// before giving up on it.
     #[serde(with = "humantime_serde")]
     pub gateway_response_timeout: Duration,
+
+    /// Number of additional gateways the client is going to register with,
+    /// so that it could switch to one of them if its primary gateway became unavailable.
+    /// By default, no standby gateways are used.
+    pub standby_gateways: usize,
 }
 
 impl Default for GatewayConnection {
     fn default() -> Self {
         GatewayConnection {
             gateway_response_timeout: DEFAULT_GATEWAY_RESPONSE_TIMEOUT,
+            standby_gateways: DEFAULT_STANDBY_GATEWAYS,
         }
     }
 }
@@ -531,6 +571,25 @@ pub struct Topology {
 
     /// Specifies the mixnode topology to be used for sending packets.
     pub topology_structure: TopologyStructure,
+
+    /// Specifies how strongly the mix route selection should be biased towards more reliable nodes,
+    /// as reported by the nym-api. The value must be in range `[0, 1]`.
+    /// With `0` all nodes in given layer are equally likely to be chosen, which maximises
+    /// the anonymity set, while `1` selects nodes purely based on their performance.
+    pub route_selection_performance_bias: f64,
+
+    /// Specifies whether the client should measure the round-trip time to every mixnode
+    /// (by timing the establishment of a TCP connection to its mix port) on each topology refresh
+    /// and bias the route selection towards the closer ones as well.
+    /// It has no effect unless `route_selection_performance_bias` is non-zero. Note that the probes
+    /// reveal the address of the client to all mixnodes. Not supported in wasm.
+    pub route_selection_measure_rtt: bool,
+
+    /// Specifies whether the client should subscribe to the network events published by the nym-api
+    /// and refresh the topology as soon as they arrive (such as on epoch transitions)
+    /// rather than relying purely on the periodic refresh. The periodic refresh still happens,
+    /// albeit much less frequently, as a fallback.
+    pub subscribe_to_network_events: bool,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -565,6 +624,9 @@ impl Default for Topology {
             disable_refreshing: false,
             max_startup_gateway_waiting_period: DEFAULT_MAX_STARTUP_GATEWAY_WAITING_PERIOD,
             topology_structure: TopologyStructure::default(),
+            route_selection_performance_bias: 0.0,
+            route_selection_measure_rtt: false,
+            subscribe_to_network_events: false,
         }
     }
 }
@@ -88,6 +88,7 @@ impl From<ConfigV1_1_30> for Config {
                     primary_packet_size: value.debug.traffic.primary_packet_size,
                     secondary_packet_size: value.debug.traffic.secondary_packet_size,
                     packet_type: value.debug.traffic.packet_type,
+                    erasure_coding_parity_fragments: 0,
                 },
                 cover_traffic: CoverTraffic {
                     loop_cover_traffic_average_delay: value
@@ -108,6 +109,7 @@ impl From<ConfigV1_1_30> for Config {
                         .debug
                         .gateway_connection
                         .gateway_response_timeout,
+                    ..Default::default()
                 },
                 acknowledgements: Acknowledgements {
                     average_ack_delay: value.debug.acknowledgements.average_ack_delay,
@@ -123,6 +125,7 @@ impl From<ConfigV1_1_30> for Config {
                         .topology
                         .max_startup_gateway_waiting_period,
                     topology_structure: value.debug.topology.topology_structure.into(),
+                    ..Default::default()
                 },
                 reply_surbs: ReplySurbs {
                     minimum_reply_surb_storage_threshold: value
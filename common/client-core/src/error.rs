@@ -4,6 +4,7 @@
 use crate::client::mix_traffic::transceiver::ErasedGatewayError;
 use nym_crypto::asymmetric::identity::Ed25519RecoveryError;
 use nym_gateway_client::error::GatewayClientError;
+use nym_gateway_requests::registration::handshake::shared_key::SharedKeyConversionError;
 use nym_topology::gateway::GatewayConversionError;
 use nym_topology::NymTopologyError;
 use nym_validator_client::ValidatorClientError;
@@ -119,6 +120,16 @@ pub enum ClientCoreError {
     #[error("the provided gateway details don't much the stored data")]
     MismatchedStoredGatewayDetails,
 
+    #[error("the persisted shared key of the standby gateway {gateway_id} is malformed: {source}")]
+    MalformedStandbyGatewayKey {
+        gateway_id: String,
+        #[source]
+        source: SharedKeyConversionError,
+    },
+
+    #[error("there are no standby gateways available to switch to")]
+    NoStandbyGatewaysAvailable,
+
     #[error("custom selection of gateway was expected")]
     CustomGatewaySelectionExpected,
 
@@ -164,4 +175,6 @@ pub enum ClientCoreStatusMessage {
     // NOTE: The nym-connect frontend listens for these strings, so don't change them until we have a more robust mechanism in place
     #[error("The connected gateway is very slow, or the connection to it is very slow")]
     GatewayIsVerySlow,
+    #[error("The bandwidth available at the connected gateway is running low ({available_total} bytes remaining)")]
+    GatewayBandwidthRunningLow { available_total: i64 },
 }
@@ -4,7 +4,7 @@
 //! Collection of initialization steps used by client implementations
 
 use crate::client::base_client::storage::gateway_details::{
-    GatewayDetailsStore, PersistedGatewayDetails,
+    GatewayDetailsStore, PersistedGatewayConfig, PersistedGatewayDetails, PersistedStandbyGateway,
 };
 use crate::client::key_manager::persistence::KeyStore;
 use crate::client::key_manager::ManagedKeys;
@@ -17,6 +17,7 @@ use crate::init::types::{
     CustomGatewayDetails, GatewayDetails, GatewaySelectionSpecification, GatewaySetup,
     InitialisationResult,
 };
+use nym_crypto::asymmetric::identity;
 use nym_gateway_client::client::InitGatewayClient;
 use nym_topology::gateway;
 use rand::rngs::OsRng;
@@ -241,6 +242,91 @@ where
     }
 }
 
+/// Returns all the gateways the client is not yet registered with.
+fn standby_candidates(
+    persisted_config: &PersistedGatewayConfig,
+    available_gateways: &[gateway::Node],
+) -> Vec<gateway::Node> {
+    available_gateways
+        .iter()
+        .filter(|node| {
+            let id = node.identity().to_base58_string();
+            id != persisted_config.details.gateway_id
+                && !persisted_config
+                    .standby_gateways
+                    .iter()
+                    .any(|standby| standby.details.gateway_id == id)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Ensures the client is registered with (up to) the specified number of standby gateways,
+/// in addition to its primary one, and persists their details alongside the shared keys.
+/// Returns all the standby gateways the client is registered with.
+pub async fn setup_standby_gateways<T, D>(
+    details_store: &D,
+    our_identity: Arc<identity::KeyPair>,
+    available_gateways: &[gateway::Node],
+    count: usize,
+) -> Result<Vec<PersistedStandbyGateway>, ClientCoreError>
+where
+    D: GatewayDetailsStore<T>,
+    D::StorageError: Send + Sync + 'static,
+    T: DeserializeOwned + Serialize + Send + Sync,
+{
+    let mut details = _load_gateway_details(details_store).await?;
+    let PersistedGatewayDetails::Default(persisted_config) = &mut details else {
+        // we can't fail over from custom gateway setups
+        return Ok(Vec::new());
+    };
+
+    let registered = persisted_config.standby_gateways.len();
+    if registered >= count {
+        return Ok(persisted_config.standby_gateways.clone());
+    }
+
+    let must_use_tls = persisted_config.details.gateway_listener.starts_with("wss");
+    let mut candidates = standby_candidates(persisted_config, available_gateways);
+
+    let mut rng = OsRng;
+    while persisted_config.standby_gateways.len() < count {
+        let Ok(gateway) = uniformly_random_gateway(&mut rng, &candidates, must_use_tls) else {
+            log::warn!("there are no more gateways available to use as standby");
+            break;
+        };
+        candidates.retain(|node| node.identity() != gateway.identity());
+
+        let gateway_config = match GatewayEndpointConfig::from_node(gateway, must_use_tls) {
+            Ok(gateway_config) => gateway_config,
+            Err(err) => {
+                log::warn!("could not use the gateway as standby: {err}");
+                continue;
+            }
+        };
+
+        match helpers::register_with_gateway(&gateway_config, Arc::clone(&our_identity)).await {
+            // we don't need the connection itself, we only wanted to derive the keys
+            Ok(registration) => {
+                persisted_config
+                    .standby_gateways
+                    .push(PersistedStandbyGateway::new(
+                        gateway_config,
+                        &registration.shared_keys,
+                    ))
+            }
+            Err(err) => log::warn!("failed to register with the standby gateway: {err}"),
+        }
+    }
+
+    let standby_gateways = persisted_config.standby_gateways.clone();
+    if standby_gateways.len() > registered {
+        _store_gateway_details(details_store, &details).await?;
+    }
+
+    Ok(standby_gateways)
+}
+
 pub fn output_to_json<T: Serialize>(init_results: &T, output_file: &str) {
     match std::fs::File::create(output_file) {
         Ok(file) => match serde_json::to_writer_pretty(file, init_results) {
@@ -250,3 +336,137 @@ pub fn output_to_json<T: Serialize>(init_results: &T, output_file: &str) {
         Err(err) => eprintln!("Could not save {output_file}: {err}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::base_client::storage::gateway_details::{
+        InMemGatewayDetails, PersistedCustomGatewayDetails,
+    };
+    use crate::init::types::EmptyCustomDetails;
+    use nym_crypto::asymmetric::encryption;
+    use nym_gateway_requests::registration::handshake::SharedKeys;
+    use nym_topology::NodeVersion;
+
+    fn shared_key() -> SharedKeys {
+        SharedKeys::try_from_bytes(&[42; 32]).unwrap()
+    }
+
+    fn new_gateway() -> gateway::Node {
+        let mut rng = OsRng;
+        gateway::Node {
+            owner: "n1gatewayowner".to_string(),
+            host: "127.0.0.1".parse().unwrap(),
+            mix_host: "127.0.0.1:1789".parse().unwrap(),
+            clients_ws_port: 9000,
+            clients_wss_port: None,
+            identity_key: *identity::KeyPair::new(&mut rng).public_key(),
+            sphinx_key: *encryption::KeyPair::new(&mut rng).public_key(),
+            version: NodeVersion::Unknown,
+        }
+    }
+
+    fn endpoint(node: &gateway::Node) -> GatewayEndpointConfig {
+        GatewayEndpointConfig::from_node(node.clone(), false).unwrap()
+    }
+
+    async fn persisted_store(
+        primary: &gateway::Node,
+        standby: &[gateway::Node],
+    ) -> InMemGatewayDetails {
+        let mut config = PersistedGatewayConfig::new(endpoint(primary), &shared_key());
+        config.standby_gateways = standby
+            .iter()
+            .map(|node| PersistedStandbyGateway::new(endpoint(node), &shared_key()))
+            .collect();
+
+        let store = InMemGatewayDetails::default();
+        store
+            .store_gateway_details(&PersistedGatewayDetails::Default(config))
+            .await
+            .unwrap();
+        store
+    }
+
+    fn our_identity() -> Arc<identity::KeyPair> {
+        Arc::new(identity::KeyPair::new(&mut OsRng))
+    }
+
+    #[test]
+    fn standby_candidates_exclude_gateways_we_are_registered_with() {
+        let primary = new_gateway();
+        let standby = new_gateway();
+        let other = new_gateway();
+
+        let mut config = PersistedGatewayConfig::new(endpoint(&primary), &shared_key());
+        config.standby_gateways.push(PersistedStandbyGateway::new(
+            endpoint(&standby),
+            &shared_key(),
+        ));
+
+        let candidates = standby_candidates(&config, &[primary, standby, other.clone()]);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].identity(), other.identity());
+    }
+
+    #[tokio::test]
+    async fn existing_standby_gateways_are_reused() {
+        let primary = new_gateway();
+        let standby = vec![new_gateway(), new_gateway()];
+        let store = persisted_store(&primary, &standby).await;
+
+        // there's no need to register with any of the other available gateways
+        let available = vec![new_gateway(), new_gateway()];
+        for count in [0, 1, 2] {
+            let registered = setup_standby_gateways(&store, our_identity(), &available, count)
+                .await
+                .unwrap();
+            assert_eq!(registered.len(), 2);
+            assert_eq!(
+                registered[0].details.gateway_id,
+                endpoint(&standby[0]).gateway_id
+            );
+            assert_eq!(
+                registered[1].details.gateway_id,
+                endpoint(&standby[1]).gateway_id
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn nothing_is_registered_without_available_gateways() {
+        let primary = new_gateway();
+        let store = persisted_store(&primary, &[]).await;
+
+        let registered = setup_standby_gateways(&store, our_identity(), &[primary], 2)
+            .await
+            .unwrap();
+        assert!(registered.is_empty());
+
+        let PersistedGatewayDetails::Default(persisted) =
+            store.load_gateway_details().await.unwrap()
+        else {
+            panic!("unexpected custom gateway details")
+        };
+        assert!(persisted.standby_gateways.is_empty());
+    }
+
+    #[tokio::test]
+    async fn custom_gateway_setups_have_no_standby_gateways() {
+        let store = InMemGatewayDetails::default();
+        store
+            .store_gateway_details(&PersistedGatewayDetails::Custom(
+                PersistedCustomGatewayDetails {
+                    gateway_id: new_gateway().identity().to_base58_string(),
+                    additional_data: EmptyCustomDetails {},
+                },
+            ))
+            .await
+            .unwrap();
+
+        let registered = setup_standby_gateways(&store, our_identity(), &[new_gateway()], 1)
+            .await
+            .unwrap();
+        assert!(registered.is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::channel::mpsc;
+use log::{debug, warn};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+
+pub type BandwidthReportSender = mpsc::UnboundedSender<BandwidthReport>;
+pub type BandwidthReportReceiver = mpsc::UnboundedReceiver<BandwidthReport>;
+
+/// Authoritative amount of bandwidth available to the client, as reported by its gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BandwidthReport {
+    pub available_total: i64,
+
+    /// Indicates more bandwidth should be acquired before the gateway starts rejecting our packets.
+    pub running_low: bool,
+}
+
+/// Bandwidth remaining at the gateway, shared between the client and the task listening
+/// on the socket for the reports pushed by the gateway.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClientBandwidth {
+    remaining: Arc<AtomicI64>,
+
+    /// Indicates the gateway has reported our bandwidth is running low since we last claimed more of it.
+    running_low: Arc<AtomicBool>,
+    report_sender: Option<BandwidthReportSender>,
+}
+
+impl ClientBandwidth {
+    pub(crate) fn with_report_sender(mut self, report_sender: BandwidthReportSender) -> Self {
+        self.report_sender = Some(report_sender);
+        self
+    }
+
+    pub(crate) fn remaining(&self) -> i64 {
+        self.remaining.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn update_remaining(&self, remaining: i64) {
+        self.remaining.store(remaining, Ordering::Release)
+    }
+
+    pub(crate) fn running_low(&self) -> bool {
+        self.running_low.load(Ordering::Acquire)
+    }
+
+    /// Updates the remaining bandwidth after successfully claiming more of it. Any reports
+    /// generated by the gateway before it has processed the claim are going to be ignored.
+    pub(crate) fn bandwidth_claimed(&self, available_total: i64) {
+        self.update_remaining(available_total);
+        self.running_low.store(false, Ordering::Release)
+    }
+
+    pub(crate) fn handle_report(&self, report: BandwidthReport) {
+        debug!(
+            "the gateway reported {} bytes of bandwidth remaining",
+            report.available_total
+        );
+        self.update_remaining(report.available_total);
+        self.running_low
+            .store(report.running_low, Ordering::Release);
+
+        if let Some(report_sender) = &self.report_sender {
+            if report_sender.unbounded_send(report).is_err() {
+                warn!("failed to forward the bandwidth report - the receiver has been dropped")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(available_total: i64, running_low: bool) -> BandwidthReport {
+        BandwidthReport {
+            available_total,
+            running_low,
+        }
+    }
+
+    #[test]
+    fn reports_update_the_shared_state() {
+        let bandwidth = ClientBandwidth::default();
+        let listener_copy = bandwidth.clone();
+
+        listener_copy.handle_report(report(1000, false));
+        assert_eq!(bandwidth.remaining(), 1000);
+        assert!(!bandwidth.running_low());
+
+        listener_copy.handle_report(report(10, true));
+        assert_eq!(bandwidth.remaining(), 10);
+        assert!(bandwidth.running_low());
+    }
+
+    #[test]
+    fn claiming_bandwidth_clears_the_running_low_flag() {
+        let bandwidth = ClientBandwidth::default();
+        bandwidth.handle_report(report(10, true));
+
+        bandwidth.bandwidth_claimed(1_000_000);
+        assert_eq!(bandwidth.remaining(), 1_000_000);
+        assert!(!bandwidth.running_low());
+    }
+
+    #[test]
+    fn reports_are_forwarded_in_order() {
+        let (report_sender, mut report_receiver) = mpsc::unbounded();
+        let bandwidth = ClientBandwidth::default().with_report_sender(report_sender);
+
+        bandwidth.handle_report(report(10, true));
+        bandwidth.handle_report(report(5, true));
+
+        assert_eq!(report_receiver.try_next().unwrap(), Some(report(10, true)));
+        assert_eq!(report_receiver.try_next().unwrap(), Some(report(5, true)));
+
+        // the reports are not forwarded if nobody is listening for them, but they're still applied
+        drop(report_receiver);
+        bandwidth.handle_report(report(1, true));
+        assert_eq!(bandwidth.remaining(), 1);
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2021-2024 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::bandwidth::{BandwidthReport, BandwidthReportSender, ClientBandwidth};
 use crate::error::GatewayClientError;
 use crate::packet_router::PacketRouter;
 pub use crate::packet_router::{
@@ -17,11 +18,12 @@ use nym_credential_storage::storage::Storage as CredentialStorage;
 use nym_credentials::CredentialSpendingData;
 use nym_crypto::asymmetric::identity;
 use nym_gateway_requests::authentication::encrypted_address::EncryptedAddressBytes;
+use nym_gateway_requests::capabilities::{GatewayCapabilities, GatewayCapability};
 use nym_gateway_requests::iv::IV;
 use nym_gateway_requests::registration::handshake::{client_handshake, SharedKeys};
 use nym_gateway_requests::{
     BinaryRequest, ClientControlRequest, ServerResponse, CREDENTIAL_UPDATE_V2_PROTOCOL_VERSION,
-    CURRENT_PROTOCOL_VERSION,
+    CURRENT_PROTOCOL_VERSION, MAX_MIX_PACKET_BATCH_SIZE,
 };
 use nym_network_defaults::{REMAINING_BANDWIDTH_THRESHOLD, TOKENS_TO_BURN};
 use nym_sphinx::forwarding::packet::MixPacket;
@@ -67,7 +69,7 @@ pub struct GatewayConfig {
 pub struct GatewayClient<C, St = EphemeralCredentialStorage> {
     authenticated: bool,
     disabled_credentials_mode: bool,
-    bandwidth_remaining: i64,
+    bandwidth: ClientBandwidth,
     gateway_address: String,
     gateway_identity: identity::PublicKey,
     local_identity: Arc<identity::KeyPair>,
@@ -89,6 +91,9 @@ pub struct GatewayClient<C, St = EphemeralCredentialStorage> {
     // currently unused (but populated)
     negotiated_protocol: Option<u8>,
 
+    /// Protocol extensions that both this client and the gateway support.
+    negotiated_capabilities: GatewayCapabilities,
+
     /// Listen to shutdown messages.
     shutdown: TaskClient,
 }
@@ -106,7 +111,7 @@ impl<C, St> GatewayClient<C, St> {
         GatewayClient {
             authenticated: false,
             disabled_credentials_mode: true,
-            bandwidth_remaining: 0,
+            bandwidth: ClientBandwidth::default(),
             gateway_address: config.gateway_listener,
             gateway_identity: config.gateway_identity,
             local_identity,
@@ -119,6 +124,7 @@ impl<C, St> GatewayClient<C, St> {
             reconnection_attempts: DEFAULT_RECONNECTION_ATTEMPTS,
             reconnection_backoff: DEFAULT_RECONNECTION_BACKOFF,
             negotiated_protocol: None,
+            negotiated_capabilities: GatewayCapabilities::default(),
             shutdown,
         }
     }
@@ -129,6 +135,14 @@ impl<C, St> GatewayClient<C, St> {
         self
     }
 
+    /// Forward all bandwidth reports pushed by the gateway into the provided channel.
+    /// Note: it has to be set before the client starts listening for mixnet messages.
+    #[must_use]
+    pub fn with_bandwidth_report_sender(mut self, report_sender: BandwidthReportSender) -> Self {
+        self.bandwidth = self.bandwidth.with_report_sender(report_sender);
+        self
+    }
+
     #[must_use]
     pub fn with_reconnection_on_failure(mut self, should_reconnect_on_failure: bool) -> Self {
         self.should_reconnect_on_failure = should_reconnect_on_failure;
@@ -153,6 +167,12 @@ impl<C, St> GatewayClient<C, St> {
         self
     }
 
+    /// Takes the bandwidth controller away from this client, so that it could be used
+    /// by a client connected to a different gateway.
+    pub fn take_bandwidth_controller(&mut self) -> Option<BandwidthController<C, St>> {
+        self.bandwidth_controller.take()
+    }
+
     pub fn gateway_identity(&self) -> identity::PublicKey {
         self.gateway_identity
     }
@@ -166,7 +186,12 @@ impl<C, St> GatewayClient<C, St> {
     }
 
     pub fn remaining_bandwidth(&self) -> i64 {
-        self.bandwidth_remaining
+        self.bandwidth.remaining()
+    }
+
+    /// Protocol extensions negotiated with the gateway during the registration or authentication.
+    pub fn negotiated_capabilities(&self) -> &GatewayCapabilities {
+        &self.negotiated_capabilities
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -255,7 +280,10 @@ impl<C, St> GatewayClient<C, St> {
                     "failed to reconnect after {} attempts",
                     self.reconnection_attempts
                 );
-                Err(err)
+                Err(GatewayClientError::ReconnectionFailure {
+                    attempts: self.reconnection_attempts,
+                    source: Box::new(err),
+                })
             }
         }
     }
@@ -302,7 +330,13 @@ impl<C, St> GatewayClient<C, St> {
                             }
                         }
                         Message::Text(txt_msg) => {
-                            break ServerResponse::try_from(txt_msg).map_err(|_| GatewayClientError::MalformedResponse);
+                            match ServerResponse::try_from(txt_msg) {
+                                // the gateway might push a report at any point, it's not a response to our request
+                                Ok(ServerResponse::BandwidthReport { available_total, running_low }) => {
+                                    self.bandwidth.handle_report(BandwidthReport { available_total, running_low });
+                                }
+                                res => break res.map_err(|_| GatewayClientError::MalformedResponse),
+                            }
                         }
                         _ => (),
                     }
@@ -420,6 +454,17 @@ impl<C, St> GatewayClient<C, St> {
         }
     }
 
+    fn set_negotiated_capabilities(&mut self, gateway_capabilities: GatewayCapabilities) {
+        // the gateway should have only responded with the subset of what we have advertised,
+        // but make sure we're not going to use anything we don't understand
+        self.negotiated_capabilities =
+            GatewayCapabilities::supported().negotiate(&gateway_capabilities);
+        debug!(
+            "negotiated gateway capabilities: {}",
+            self.negotiated_capabilities
+        );
+    }
+
     async fn register(&mut self) -> Result<(), GatewayClientError> {
         if !self.connection.is_established() {
             return Err(GatewayClientError::ConnectionNotEstablished);
@@ -439,21 +484,24 @@ impl<C, St> GatewayClient<C, St> {
                 self.local_identity.as_ref(),
                 self.gateway_identity,
                 !self.disabled_credentials_mode,
+                GatewayCapabilities::supported(),
             )
             .await
             .map_err(GatewayClientError::RegistrationFailure),
             _ => unreachable!(),
         }?;
-        let (authentication_status, gateway_protocol) = match self.read_control_response().await? {
-            ServerResponse::Register {
-                protocol_version,
-                status,
-            } => (status, protocol_version),
-            ServerResponse::Error { message } => {
-                return Err(GatewayClientError::GatewayError(message))
-            }
-            _ => return Err(GatewayClientError::UnexpectedResponse),
-        };
+        let (authentication_status, gateway_protocol, capabilities) =
+            match self.read_control_response().await? {
+                ServerResponse::Register {
+                    protocol_version,
+                    capabilities,
+                    status,
+                } => (status, protocol_version, capabilities),
+                ServerResponse::Error { message } => {
+                    return Err(GatewayClientError::GatewayError(message))
+                }
+                _ => return Err(GatewayClientError::UnexpectedResponse),
+            };
 
         self.check_gateway_protocol(gateway_protocol)?;
         self.authenticated = authentication_status;
@@ -464,6 +512,7 @@ impl<C, St> GatewayClient<C, St> {
 
         // populate the negotiated protocol for future uses
         self.negotiated_protocol = gateway_protocol;
+        self.set_negotiated_capabilities(capabilities);
 
         Ok(())
     }
@@ -500,19 +549,22 @@ impl<C, St> GatewayClient<C, St> {
             encrypted_address,
             iv,
             !self.disabled_credentials_mode,
+            GatewayCapabilities::supported(),
         )
         .into();
 
         match self.send_websocket_message(msg).await? {
             ServerResponse::Authenticate {
                 protocol_version,
+                capabilities,
                 status,
                 bandwidth_remaining,
             } => {
                 self.check_gateway_protocol(protocol_version)?;
                 self.authenticated = status;
-                self.bandwidth_remaining = bandwidth_remaining;
+                self.bandwidth.update_remaining(bandwidth_remaining);
                 self.negotiated_protocol = protocol_version;
+                self.set_negotiated_capabilities(capabilities);
                 Ok(())
             }
             ServerResponse::Error { message } => Err(GatewayClientError::GatewayError(message)),
@@ -558,25 +610,44 @@ impl<C, St> GatewayClient<C, St> {
             iv,
         )
         .into();
-        self.bandwidth_remaining = match self.send_websocket_message(msg).await? {
+        let available_total = match self.send_websocket_message(msg).await? {
             ServerResponse::Bandwidth { available_total } => Ok(available_total),
             ServerResponse::Error { message } => Err(GatewayClientError::GatewayError(message)),
             _ => Err(GatewayClientError::UnexpectedResponse),
         }?;
+        self.bandwidth.bandwidth_claimed(available_total);
         Ok(())
     }
 
     async fn try_claim_testnet_bandwidth(&mut self) -> Result<(), GatewayClientError> {
         let msg = ClientControlRequest::ClaimFreeTestnetBandwidth.into();
-        self.bandwidth_remaining = match self.send_websocket_message(msg).await? {
+        let available_total = match self.send_websocket_message(msg).await? {
             ServerResponse::Bandwidth { available_total } => Ok(available_total),
             ServerResponse::Error { message } => Err(GatewayClientError::GatewayError(message)),
             _ => Err(GatewayClientError::UnexpectedResponse),
         }?;
+        self.bandwidth.bandwidth_claimed(available_total);
 
         Ok(())
     }
 
+    /// Claims more bandwidth, unless it has already been done since the gateway reported it was running low,
+    /// for example when handling an earlier report.
+    pub async fn claim_bandwidth_if_running_low(&mut self) -> Result<(), GatewayClientError>
+    where
+        C: DkgQueryClient + Send + Sync,
+        St: CredentialStorage,
+        <St as CredentialStorage>::StorageError: Send + Sync + 'static,
+    {
+        if !self.bandwidth.running_low() {
+            debug!(
+                "we have already claimed more bandwidth since it was reported to be running low"
+            );
+            return Ok(());
+        }
+        self.claim_bandwidth().await
+    }
+
     pub async fn claim_bandwidth(&mut self) -> Result<(), GatewayClientError>
     where
         C: DkgQueryClient + Send + Sync,
@@ -645,26 +716,43 @@ impl<C, St> GatewayClient<C, St> {
         if !self.authenticated {
             return Err(GatewayClientError::NotAuthenticated);
         }
-        if self.estimate_required_bandwidth(&packets) > self.bandwidth_remaining {
+        if self.estimate_required_bandwidth(&packets) > self.bandwidth.remaining() {
             return Err(GatewayClientError::NotEnoughBandwidth(
                 self.estimate_required_bandwidth(&packets),
-                self.bandwidth_remaining,
+                self.bandwidth.remaining(),
             ));
         }
         if !self.connection.is_established() {
             return Err(GatewayClientError::ConnectionNotEstablished);
         }
 
-        let messages: Vec<_> = packets
-            .into_iter()
-            .map(|mix_packet| {
-                BinaryRequest::new_forward_request(mix_packet).into_ws_message(
-                    self.shared_key
-                        .as_ref()
-                        .expect("no shared key present even though we're authenticated!"),
-                )
-            })
-            .collect();
+        let shared_key = self
+            .shared_key
+            .as_ref()
+            .expect("no shared key present even though we're authenticated!");
+
+        // if the gateway understands it, send the packets in as few websocket messages as possible
+        let messages = if self
+            .negotiated_capabilities
+            .supports(GatewayCapability::ForwardSphinxBatch)
+        {
+            let mut packets = packets.into_iter().peekable();
+            let mut messages = Vec::new();
+            while packets.peek().is_some() {
+                let batch = packets.by_ref().take(MAX_MIX_PACKET_BATCH_SIZE).collect();
+                messages.push(
+                    BinaryRequest::new_forward_batch_request(batch).into_ws_message(shared_key)?,
+                );
+            }
+            messages
+        } else {
+            packets
+                .into_iter()
+                .map(|mix_packet| {
+                    BinaryRequest::new_forward_request(mix_packet).into_ws_message(shared_key)
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
 
         if let Err(err) = self
             .batch_send_websocket_messages_without_response(messages)
@@ -716,10 +804,10 @@ impl<C, St> GatewayClient<C, St> {
         if !self.authenticated {
             return Err(GatewayClientError::NotAuthenticated);
         }
-        if (mix_packet.packet().len() as i64) > self.bandwidth_remaining {
+        if (mix_packet.packet().len() as i64) > self.bandwidth.remaining() {
             return Err(GatewayClientError::NotEnoughBandwidth(
                 mix_packet.packet().len() as i64,
-                self.bandwidth_remaining,
+                self.bandwidth.remaining(),
             ));
         }
         if !self.connection.is_established() {
@@ -731,7 +819,7 @@ impl<C, St> GatewayClient<C, St> {
             self.shared_key
                 .as_ref()
                 .expect("no shared key present even though we're authenticated!"),
-        );
+        )?;
         self.send_with_reconnection_on_failure(msg).await
     }
 
@@ -775,6 +863,7 @@ impl<C, St> GatewayClient<C, St> {
                                 .as_ref()
                                 .expect("no shared key present even though we're authenticated!"),
                         ),
+                        self.bandwidth.clone(),
                         self.shutdown.clone(),
                     )
                 }
@@ -816,7 +905,7 @@ impl<C, St> GatewayClient<C, St> {
         }
         let shared_key = self.perform_initial_authentication().await?;
 
-        if self.bandwidth_remaining < REMAINING_BANDWIDTH_THRESHOLD {
+        if self.bandwidth.remaining() < REMAINING_BANDWIDTH_THRESHOLD {
             info!("Claiming more bandwidth for your tokens. This will use {} token(s) from your wallet. \
             Stop the process now if you don't want that to happen.", TOKENS_TO_BURN);
             self.claim_bandwidth().await?;
@@ -850,7 +939,7 @@ impl GatewayClient<InitOnly, EphemeralCredentialStorage> {
         GatewayClient {
             authenticated: false,
             disabled_credentials_mode: true,
-            bandwidth_remaining: 0,
+            bandwidth: ClientBandwidth::default(),
             gateway_address: config.gateway_listener,
             gateway_identity: config.gateway_identity,
             local_identity,
@@ -863,6 +952,7 @@ impl GatewayClient<InitOnly, EphemeralCredentialStorage> {
             reconnection_attempts: DEFAULT_RECONNECTION_ATTEMPTS,
             reconnection_backoff: DEFAULT_RECONNECTION_BACKOFF,
             negotiated_protocol: None,
+            negotiated_capabilities: GatewayCapabilities::default(),
             shutdown,
         }
     }
@@ -882,7 +972,7 @@ impl GatewayClient<InitOnly, EphemeralCredentialStorage> {
         GatewayClient {
             authenticated: self.authenticated,
             disabled_credentials_mode: self.disabled_credentials_mode,
-            bandwidth_remaining: self.bandwidth_remaining,
+            bandwidth: self.bandwidth,
             gateway_address: self.gateway_address,
             gateway_identity: self.gateway_identity,
             local_identity: self.local_identity,
@@ -895,6 +985,7 @@ impl GatewayClient<InitOnly, EphemeralCredentialStorage> {
             reconnection_attempts: self.reconnection_attempts,
             reconnection_backoff: self.reconnection_backoff,
             negotiated_protocol: self.negotiated_protocol,
+            negotiated_capabilities: self.negotiated_capabilities,
             shutdown,
         }
     }
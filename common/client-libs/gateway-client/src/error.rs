@@ -4,6 +4,7 @@
 #[cfg(target_arch = "wasm32")]
 use gloo_utils::errors::JsError;
 use nym_gateway_requests::registration::handshake::error::HandshakeError;
+use nym_gateway_requests::GatewayRequestsError;
 use std::io;
 use thiserror::Error;
 use tungstenite::Error as WsError;
@@ -44,6 +45,9 @@ pub enum GatewayClientError {
     #[error("Received response was malformed")]
     MalformedResponse,
 
+    #[error("Failed to prepare the request: {0}")]
+    MalformedRequest(#[from] GatewayRequestsError),
+
     #[error("Credential could not be serialized")]
     SerializeCredential,
 
@@ -62,6 +66,13 @@ pub enum GatewayClientError {
     #[error("Received an unexpected response")]
     UnexpectedResponse,
 
+    #[error("Failed to reconnect to the gateway after {attempts} attempts: {source}")]
+    ReconnectionFailure {
+        attempts: usize,
+        #[source]
+        source: Box<GatewayClientError>,
+    },
+
     #[error("Connection is in an invalid state - please send a bug report")]
     ConnectionInInvalidState,
 
@@ -6,6 +6,7 @@ use log::warn;
 use nym_gateway_requests::BinaryResponse;
 use tungstenite::{protocol::Message, Error as WsError};
 
+pub use bandwidth::{BandwidthReport, BandwidthReportReceiver, BandwidthReportSender};
 pub use client::{GatewayClient, GatewayConfig};
 pub use nym_gateway_requests::registration::handshake::SharedKeys;
 pub use packet_router::{
@@ -14,6 +15,7 @@ pub use packet_router::{
 };
 pub use traits::GatewayPacketRouter;
 
+pub mod bandwidth;
 pub mod client;
 pub mod error;
 pub mod packet_router;
@@ -1,6 +1,7 @@
 // Copyright 2021-2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::bandwidth::{BandwidthReport, ClientBandwidth};
 use crate::error::GatewayClientError;
 use crate::packet_router::PacketRouter;
 use crate::traits::GatewayPacketRouter;
@@ -10,6 +11,7 @@ use futures::stream::{SplitSink, SplitStream};
 use futures::{SinkExt, StreamExt};
 use log::*;
 use nym_gateway_requests::registration::handshake::SharedKeys;
+use nym_gateway_requests::ServerResponse;
 use nym_task::TaskClient;
 use std::os::raw::c_int as RawFd;
 use std::sync::Arc;
@@ -59,7 +61,11 @@ pub(crate) struct PartiallyDelegated {
 }
 
 impl PartiallyDelegated {
-    fn recover_received_plaintexts(ws_msgs: Vec<Message>, shared_key: &SharedKeys) -> Vec<Vec<u8>> {
+    fn recover_received_plaintexts(
+        ws_msgs: Vec<Message>,
+        shared_key: &SharedKeys,
+        bandwidth: &ClientBandwidth,
+    ) -> Vec<Vec<u8>> {
         let mut plaintexts = Vec::with_capacity(ws_msgs.len());
         for ws_msg in ws_msgs {
             match ws_msg {
@@ -75,13 +81,21 @@ impl PartiallyDelegated {
                 // This would also require NOT discarding any text responses here.
 
                 // TODO: those can return the "send confirmations" - perhaps it should be somehow worked around?
-                Message::Text(text) => {
-                    trace!(
-                    "received a text message - probably a response to some previous query! - {}",
-                    text
-                );
-                    continue;
-                }
+                Message::Text(text) => match ServerResponse::try_from(text) {
+                    Ok(ServerResponse::BandwidthReport {
+                        available_total,
+                        running_low,
+                    }) => bandwidth.handle_report(BandwidthReport {
+                        available_total,
+                        running_low,
+                    }),
+                    Ok(response) => {
+                        trace!(
+                            "received a text message - probably a response to some previous query! - {response:?}"
+                        );
+                    }
+                    Err(err) => warn!("received a malformed text message from the gateway - {err}"),
+                },
                 _ => continue,
             }
         }
@@ -92,8 +106,9 @@ impl PartiallyDelegated {
         ws_msgs: Vec<Message>,
         packet_router: &PacketRouter,
         shared_key: &SharedKeys,
+        bandwidth: &ClientBandwidth,
     ) -> Result<(), GatewayClientError> {
-        let plaintexts = Self::recover_received_plaintexts(ws_msgs, shared_key);
+        let plaintexts = Self::recover_received_plaintexts(ws_msgs, shared_key, bandwidth);
         packet_router.route_received(plaintexts)
     }
 
@@ -101,6 +116,7 @@ impl PartiallyDelegated {
         conn: WsConn,
         packet_router: PacketRouter,
         shared_key: Arc<SharedKeys>,
+        bandwidth: ClientBandwidth,
         mut shutdown: TaskClient,
     ) -> Self {
         // when called for, it NEEDS TO yield back the stream so that we could merge it and
@@ -132,7 +148,7 @@ impl PartiallyDelegated {
                             Ok(msgs) => msgs
                         };
 
-                        if let Err(err) = Self::route_socket_messages(ws_msgs, &packet_router, shared_key.as_ref()) {
+                        if let Err(err) = Self::route_socket_messages(ws_msgs, &packet_router, shared_key.as_ref(), &bandwidth) {
                             log::warn!("Route socket messages failed: {err}");
                         }
                     }
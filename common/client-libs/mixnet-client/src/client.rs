@@ -4,6 +4,7 @@
 use futures::channel::mpsc;
 use futures::StreamExt;
 use log::*;
+use nym_noise::{upgrade_noise_initiator, Connection, NoiseConfig};
 use nym_sphinx::addressing::nodes::NymNodeRoutingAddress;
 use nym_sphinx::framing::codec::NymCodec;
 use nym_sphinx::framing::packet::FramedNymPacket;
@@ -25,6 +26,7 @@ pub struct Config {
     initial_connection_timeout: Duration,
     maximum_connection_buffer_size: usize,
     use_legacy_version: bool,
+    noise: Option<NoiseConfig>,
 }
 
 impl Config {
@@ -41,8 +43,16 @@ impl Config {
             initial_connection_timeout,
             maximum_connection_buffer_size,
             use_legacy_version,
+            noise: None,
         }
     }
+
+    /// Attempt to establish noise links with the nodes whose keys are known.
+    #[must_use]
+    pub fn with_noise(mut self, noise: NoiseConfig) -> Self {
+        self.noise = Some(noise);
+        self
+    }
 }
 
 pub trait SendWithoutResponse {
@@ -83,28 +93,25 @@ impl Client {
         }
     }
 
-    async fn manage_connection(
+    async fn connect(
         address: SocketAddr,
-        receiver: mpsc::Receiver<FramedNymPacket>,
         connection_timeout: Duration,
         current_reconnection: &AtomicU32,
-    ) {
+    ) -> Option<TcpStream> {
         let connection_fut = TcpStream::connect(address);
 
-        let conn = match tokio::time::timeout(connection_timeout, connection_fut).await {
+        match tokio::time::timeout(connection_timeout, connection_fut).await {
             Ok(stream_res) => match stream_res {
                 Ok(stream) => {
                     debug!("Managed to establish connection to {}", address);
-                    // if we managed to connect, reset the reconnection count (whatever it might have been)
-                    current_reconnection.store(0, Ordering::Release);
-                    Framed::new(stream, NymCodec)
+                    Some(stream)
                 }
                 Err(err) => {
                     debug!(
                         "failed to establish connection to {} (err: {})",
                         address, err
                     );
-                    return;
+                    None
                 }
             },
             Err(_) => {
@@ -115,10 +122,51 @@ impl Client {
 
                 // we failed to connect - increase reconnection attempt
                 current_reconnection.fetch_add(1, Ordering::SeqCst);
-                return;
+                None
             }
+        }
+    }
+
+    async fn manage_connection(
+        address: SocketAddr,
+        receiver: mpsc::Receiver<FramedNymPacket>,
+        connection_timeout: Duration,
+        noise: Option<NoiseConfig>,
+        current_reconnection: &AtomicU32,
+    ) {
+        let Some(stream) = Self::connect(address, connection_timeout, current_reconnection).await
+        else {
+            return;
         };
 
+        let conn = match noise {
+            Some(noise) => match upgrade_noise_initiator(stream, &noise, address).await {
+                Ok(conn) => conn,
+                Err(err) if !noise.requires_encryption() => {
+                    // the remote has most likely not been upgraded yet and dropped the connection
+                    // upon receiving the noise preface, so carry on using the plaintext link
+                    debug!("failed to establish noise link with {address}: {err}. falling back to the plaintext link");
+                    noise.peer_keys().mark_unsupported(&address);
+                    let Some(stream) =
+                        Self::connect(address, connection_timeout, current_reconnection).await
+                    else {
+                        return;
+                    };
+                    Connection::Raw(stream)
+                }
+                Err(err) => {
+                    warn!("failed to establish noise link with {address}: {err}");
+                    current_reconnection.fetch_add(1, Ordering::SeqCst);
+                    return;
+                }
+            },
+            None => Connection::Raw(stream),
+        };
+
+        // if we managed to connect, reset the reconnection count (whatever it might have been)
+        current_reconnection.store(0, Ordering::Release);
+        let conn = Framed::new(conn, NymCodec);
+
         // Take whatever the receiver channel produces and put it on the connection.
         // We could have as well used conn.send_all(receiver.map(Ok)), but considering we don't care
         // about neither receiver nor the connection, it doesn't matter which one gets consumed
@@ -172,8 +220,9 @@ impl Client {
         let reconnection_attempt = current_reconnection_attempt.load(Ordering::Acquire);
         let backoff = self.determine_backoff(reconnection_attempt);
 
-        // copy the value before moving into another task
+        // copy the values before moving into another task
         let initial_connection_timeout = self.config.initial_connection_timeout;
+        let noise = self.config.noise.clone();
 
         tokio::spawn(async move {
             // before executing the manager, wait for what was specified, if anything
@@ -186,6 +235,7 @@ impl Client {
                 address.into(),
                 receiver,
                 initial_connection_timeout,
+                noise,
                 &current_reconnection_attempt,
             )
             .await
@@ -261,6 +311,7 @@ mod tests {
             initial_connection_timeout: Duration::from_millis(1_500),
             maximum_connection_buffer_size: 128,
             use_legacy_version: false,
+            noise: None,
         })
     }
 
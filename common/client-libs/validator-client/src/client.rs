@@ -13,7 +13,7 @@ use nym_api_requests::coconut::{
     BlindSignRequestBody, BlindedSignatureResponse, FreePassRequest, VerifyCredentialBody,
     VerifyCredentialResponse,
 };
-use nym_api_requests::models::{DescribedGateway, MixNodeBondAnnotated};
+use nym_api_requests::models::{DescribedGateway, MixNodeBondAnnotated, MixnodeSphinxKeys};
 use nym_api_requests::models::{
     GatewayCoreStatusResponse, MixnodeCoreStatusResponse, MixnodeStatusResponse,
     RewardEstimationResponse, StakeSaturationResponse,
@@ -271,6 +271,12 @@ impl NymApiClient {
         Ok(self.nym_api.get_active_mixnodes().await?)
     }
 
+    pub async fn get_cached_active_mixnodes_detailed(
+        &self,
+    ) -> Result<Vec<MixNodeBondAnnotated>, ValidatorClientError> {
+        Ok(self.nym_api.get_active_mixnodes_detailed().await?)
+    }
+
     pub async fn get_cached_rewarded_mixnodes(
         &self,
     ) -> Result<Vec<MixNodeDetails>, ValidatorClientError> {
@@ -291,6 +297,12 @@ impl NymApiClient {
         Ok(self.nym_api.get_gateways_described().await?)
     }
 
+    pub async fn get_cached_mixnodes_sphinx_keys(
+        &self,
+    ) -> Result<Vec<MixnodeSphinxKeys>, ValidatorClientError> {
+        Ok(self.nym_api.get_mixnodes_sphinx_keys().await?)
+    }
+
     pub async fn get_gateway_core_status_count(
         &self,
         identity: IdentityKeyRef<'_>,
@@ -0,0 +1,150 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::nym_api::error::NymAPIError;
+use crate::nym_api::routes;
+use http_api_client::{sanitize_url, NO_PARAMS};
+pub use nym_api_requests::events::NetworkEvent;
+use nym_api_requests::events::NETWORK_EVENTS_HEARTBEAT_INTERVAL;
+use reqwest::header::ACCEPT;
+use std::time::Duration;
+use url::Url;
+
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::time::timeout;
+
+#[cfg(target_arch = "wasm32")]
+use wasmtimer::tokio::timeout;
+
+/// If nothing, not even a heartbeat, has been received for this long, the connection is assumed
+/// to have silently died.
+pub const NETWORK_EVENTS_IDLE_TIMEOUT: Duration =
+    Duration::from_secs(2 * NETWORK_EVENTS_HEARTBEAT_INTERVAL.as_secs());
+
+/// Subscription to the stream of the network events (as server-sent events) published by a nym-api.
+pub struct NetworkEventsSubscription {
+    response: reqwest::Response,
+    parser: EventStreamParser,
+}
+
+impl NetworkEventsSubscription {
+    pub async fn new(nym_api: &Url) -> Result<Self, NymAPIError> {
+        let url = sanitize_url(nym_api, &[routes::API_VERSION, routes::EVENTS], NO_PARAMS);
+
+        // note: we can't reuse the standard api client as its request timeout would have
+        // terminated the stream
+        let response = reqwest::Client::new()
+            .get(url)
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(NymAPIError::RequestFailure { status });
+        }
+
+        Ok(NetworkEventsSubscription {
+            response,
+            parser: EventStreamParser::default(),
+        })
+    }
+
+    /// Waits for the next network event. Returns `None` if the stream has been closed.
+    /// Returns an error if the stream has been idle for longer than [`NETWORK_EVENTS_IDLE_TIMEOUT`].
+    pub async fn next(&mut self) -> Option<Result<NetworkEvent, NymAPIError>> {
+        loop {
+            if let Some(data) = self.parser.next_event_data() {
+                return Some(serde_json::from_str(&data).map_err(|err| {
+                    NymAPIError::GenericRequestFailure(format!("malformed network event: {err}"))
+                }));
+            }
+
+            match timeout(NETWORK_EVENTS_IDLE_TIMEOUT, self.response.chunk()).await {
+                Ok(Ok(Some(chunk))) => self.parser.push(&chunk),
+                Ok(Ok(None)) => return None,
+                Ok(Err(err)) => return Some(Err(err.into())),
+                Err(_) => {
+                    return Some(Err(NymAPIError::GenericRequestFailure(format!(
+                        "nothing has been received on the network events stream for {NETWORK_EVENTS_IDLE_TIMEOUT:?}"
+                    ))))
+                }
+            }
+        }
+    }
+}
+
+/// Minimal parser of the `text/event-stream` format that only cares about the data of the events.
+#[derive(Default)]
+struct EventStreamParser {
+    buffer: Vec<u8>,
+    data: Option<String>,
+}
+
+impl EventStreamParser {
+    fn push(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk)
+    }
+
+    fn next_event_data(&mut self) -> Option<String> {
+        while let Some(line_end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let raw_line = self.buffer.drain(..=line_end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&raw_line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            // an empty line dispatches the event
+            if line.is_empty() {
+                match self.data.take() {
+                    Some(data) => return Some(data),
+                    None => continue,
+                }
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+
+            // we ignore all other fields (and comments, i.e. lines starting with ':')
+            if field == "data" {
+                match &mut self.data {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value)
+                    }
+                    None => self.data = Some(value.to_owned()),
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_event_stream() {
+        let mut parser = EventStreamParser::default();
+        assert!(parser.next_event_data().is_none());
+
+        parser.push(b":\n\nevent: resync\ndata: {\"type\"");
+        assert!(parser.next_event_data().is_none());
+
+        parser.push(b":\"resync\"}\r\n\r\nevent: foo\ndata: a\ndata:b\n\n");
+        let event = parser.next_event_data().unwrap();
+        assert_eq!(
+            serde_json::from_str::<NetworkEvent>(&event).unwrap(),
+            NetworkEvent::Resync
+        );
+        assert_eq!(parser.next_event_data().unwrap(), "a\nb");
+        assert!(parser.next_event_data().is_none());
+    }
+
+    #[test]
+    fn events_from_newer_apis_are_not_errors() {
+        assert_eq!(
+            serde_json::from_str::<NetworkEvent>(r#"{"type":"something_new","foo":42}"#).unwrap(),
+            NetworkEvent::Unknown
+        );
+    }
+}
@@ -17,9 +17,9 @@ pub use nym_api_requests::{
     models::{
         ComputeRewardEstParam, DescribedGateway, GatewayBondAnnotated, GatewayCoreStatusResponse,
         GatewayStatusReportResponse, GatewayUptimeHistoryResponse, InclusionProbabilityResponse,
-        MixNodeBondAnnotated, MixnodeCoreStatusResponse, MixnodeStatusReportResponse,
-        MixnodeStatusResponse, MixnodeUptimeHistoryResponse, RewardEstimationResponse,
-        StakeSaturationResponse, UptimeResponse,
+        MixNodeBondAnnotated, MixnodeCoreStatusResponse, MixnodeSphinxKeys,
+        MixnodeStatusReportResponse, MixnodeStatusResponse, MixnodeUptimeHistoryResponse,
+        RewardEstimationResponse, StakeSaturationResponse, UptimeResponse,
     },
 };
 pub use nym_coconut_dkg_common::types::EpochId;
@@ -29,6 +29,8 @@ use nym_name_service_common::response::NamesListResponse;
 use nym_service_provider_directory_common::response::ServicesListResponse;
 
 pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
 pub mod routes;
 
 pub use http_api_client::Client;
@@ -97,6 +99,14 @@ pub trait NymApiClientExt: ApiClient {
         .await
     }
 
+    async fn get_mixnodes_sphinx_keys(&self) -> Result<Vec<MixnodeSphinxKeys>, NymAPIError> {
+        self.get_json(
+            &[routes::API_VERSION, routes::MIXNODES, routes::SPHINX_KEYS],
+            NO_PARAMS,
+        )
+        .await
+    }
+
     async fn get_active_mixnodes(&self) -> Result<Vec<MixNodeDetails>, NymAPIError> {
         self.get_json(
             &[routes::API_VERSION, routes::MIXNODES, routes::ACTIVE],
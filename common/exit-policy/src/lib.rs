@@ -7,8 +7,8 @@ pub mod policy;
 pub mod client;
 
 pub use crate::policy::{
-    AddressPolicy, AddressPolicyAction, AddressPolicyRule, AddressPortPattern, PolicyError,
-    PortRange,
+    is_reserved_address, AddressPolicy, AddressPolicyAction, AddressPolicyRule, AddressPortPattern,
+    PolicyError, PortRange,
 };
 
 pub(crate) const EXIT_POLICY_FIELD_NAME: &str = "ExitPolicy";
@@ -7,8 +7,10 @@
 
 mod address_policy;
 mod error;
+mod reserved;
 
 pub use address_policy::{
     AddressPolicy, AddressPolicyAction, AddressPolicyRule, AddressPortPattern, IpPattern, PortRange,
 };
 pub use error::PolicyError;
+pub use reserved::is_reserved_address;
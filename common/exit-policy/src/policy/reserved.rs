@@ -0,0 +1,157 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Checks whether the address belongs to any of the private, local or otherwise reserved ranges
+/// that should never be reachable through an exit node, regardless of what its exit policy says.
+/// This protects against names that (re)resolve to internal addresses, i.e. DNS rebinding.
+///
+/// Based on the IANA special-purpose address registries:
+/// https://www.iana.org/assignments/iana-ipv4-special-registry
+/// https://www.iana.org/assignments/iana-ipv6-special-registry
+pub fn is_reserved_address(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => is_reserved_ipv4(addr),
+        IpAddr::V6(addr) => is_reserved_ipv6(addr),
+    }
+}
+
+fn is_reserved_ipv4(addr: &Ipv4Addr) -> bool {
+    let octets = addr.octets();
+
+    addr.is_unspecified()
+        || addr.is_loopback()
+        || addr.is_private()
+        || addr.is_link_local()
+        || addr.is_multicast()
+        || addr.is_broadcast()
+        || addr.is_documentation()
+        // "this network": 0.0.0.0/8
+        || octets[0] == 0
+        // shared address space (carrier-grade NAT): 100.64.0.0/10
+        || (octets[0] == 100 && (octets[1] & 0b1100_0000) == 64)
+        // ietf protocol assignments: 192.0.0.0/24
+        || (octets[0] == 192 && octets[1] == 0 && octets[2] == 0)
+        // benchmarking: 198.18.0.0/15
+        || (octets[0] == 198 && (octets[1] & 0xfe) == 18)
+        // reserved for future use: 240.0.0.0/4
+        || octets[0] >= 240
+}
+
+fn is_reserved_ipv6(addr: &Ipv6Addr) -> bool {
+    // check the embedded ipv4 address of ipv4-mapped (::ffff:0:0/96)
+    // and NAT64 (64:ff9b::/96) addresses
+    if let Some(mapped) = addr.to_ipv4_mapped() {
+        return is_reserved_ipv4(&mapped);
+    }
+    let segments = addr.segments();
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [a, b] = segments[6].to_be_bytes();
+        let [c, d] = segments[7].to_be_bytes();
+        return is_reserved_ipv4(&Ipv4Addr::new(a, b, c, d));
+    }
+
+    addr.is_unspecified()
+        || addr.is_loopback()
+        || addr.is_multicast()
+        // unique local: fc00::/7
+        || (segments[0] & 0xfe00) == 0xfc00
+        // link-local unicast: fe80::/10
+        || (segments[0] & 0xffc0) == 0xfe80
+        // deprecated site-local: fec0::/10
+        || (segments[0] & 0xffc0) == 0xfec0
+        // discard-only: 100::/64
+        || segments[..4] == [0x100, 0, 0, 0]
+        // documentation: 2001:db8::/32
+        || (segments[0] == 0x2001 && segments[1] == 0xdb8)
+        // ietf protocol assignments: 2001::/23 (which includes teredo and benchmarking)
+        || (segments[0] == 0x2001 && segments[1] < 0x200)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reserved(raw: &str) -> bool {
+        is_reserved_address(&raw.parse().unwrap())
+    }
+
+    #[test]
+    fn reserved_ipv4_ranges() {
+        for addr in [
+            "0.0.0.0",
+            "0.1.2.3",
+            "10.0.0.1",
+            "100.64.0.1",
+            "100.127.255.255",
+            "127.0.0.1",
+            "169.254.169.254",
+            "172.16.0.1",
+            "172.31.255.255",
+            "192.0.0.8",
+            "192.0.2.1",
+            "192.168.1.1",
+            "198.18.0.1",
+            "198.19.255.255",
+            "198.51.100.1",
+            "203.0.113.1",
+            "224.0.0.1",
+            "240.0.0.1",
+            "255.255.255.255",
+        ] {
+            assert!(reserved(addr), "{addr} should be reserved");
+        }
+    }
+
+    #[test]
+    fn public_ipv4_addresses() {
+        for addr in [
+            "1.1.1.1",
+            "8.8.8.8",
+            "100.63.255.255",
+            "100.128.0.1",
+            "172.15.255.255",
+            "172.32.0.1",
+            "198.17.255.255",
+            "198.20.0.1",
+            "223.255.255.255",
+        ] {
+            assert!(!reserved(addr), "{addr} should not be reserved");
+        }
+    }
+
+    #[test]
+    fn reserved_ipv6_ranges() {
+        for addr in [
+            "::",
+            "::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.1.2.3",
+            "64:ff9b::192.168.0.1",
+            "100::1",
+            "2001::1",
+            "2001:db8::1",
+            "fc00::1",
+            "fd12:3456::1",
+            "fe80::1",
+            "fec0::1",
+            "ff02::1",
+        ] {
+            assert!(reserved(addr), "{addr} should be reserved");
+        }
+    }
+
+    #[test]
+    fn public_ipv6_addresses() {
+        for addr in [
+            "2606:4700:4700::1111",
+            "2a00:1450:4001::200e",
+            "::ffff:1.1.1.1",
+            "64:ff9b::8.8.8.8",
+            "2001:4860:4860::8888",
+        ] {
+            assert!(!reserved(addr), "{addr} should not be reserved");
+        }
+    }
+}
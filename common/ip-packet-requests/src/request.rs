@@ -76,6 +76,20 @@ impl IpPacketRequest {
         )
     }
 
+    pub fn new_lease_query_request(reply_to: Recipient) -> (Self, u64) {
+        let request_id = generate_random();
+        (
+            Self {
+                version: CURRENT_VERSION,
+                data: IpPacketRequestData::LeaseQuery(LeaseQueryRequest {
+                    request_id,
+                    reply_to,
+                }),
+            },
+            request_id,
+        )
+    }
+
     pub fn new_data_request(ip_packets: bytes::Bytes) -> Self {
         Self {
             version: CURRENT_VERSION,
@@ -91,6 +105,7 @@ impl IpPacketRequest {
             IpPacketRequestData::Data(_) => None,
             IpPacketRequestData::Ping(request) => Some(request.request_id),
             IpPacketRequestData::Health(request) => Some(request.request_id),
+            IpPacketRequestData::LeaseQuery(request) => Some(request.request_id),
         }
     }
 
@@ -102,6 +117,7 @@ impl IpPacketRequest {
             IpPacketRequestData::Data(_) => None,
             IpPacketRequestData::Ping(request) => Some(&request.reply_to),
             IpPacketRequestData::Health(request) => Some(&request.reply_to),
+            IpPacketRequestData::LeaseQuery(request) => Some(&request.reply_to),
         }
     }
 
@@ -127,6 +143,7 @@ pub enum IpPacketRequestData {
     Data(DataRequest),
     Ping(PingRequest),
     Health(HealthRequest),
+    LeaseQuery(LeaseQueryRequest),
 }
 
 // A static connect request is when the client provides the internal IP address it will use on the
@@ -205,6 +222,15 @@ pub struct HealthRequest {
     pub reply_to: Recipient,
 }
 
+// A lease query request is when the operator of the ip packet router wants to inspect the
+// addresses currently leased to the clients.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LeaseQueryRequest {
+    pub request_id: u64,
+    // The nym-address the response should be sent back to
+    pub reply_to: Recipient,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -218,7 +244,7 @@ mod tests {
             data: IpPacketRequestData::StaticConnect(
                 StaticConnectRequest {
                     request_id: 123,
-                    ips: IpPair::new(Ipv4Addr::from_str("10.0.0.1").unwrap(), Ipv6Addr::from_str("2001:db8:a160::1").unwrap()),
+                    ips: IpPair::new(Ipv4Addr::from_str("10.0.0.1").unwrap(), Ipv6Addr::from_str("fd00:a160::1").unwrap()),
                     reply_to: Recipient::try_from_base58_string("D1rrpsysCGCYXy9saP8y3kmNpGtJZUXN9SvFoUcqAsM9.9Ssso1ea5NfkbMASdiseDSjTN1fSWda5SgEVjdSN4CvV@GJqd3ZxpXWSNxTfx7B1pPtswpetH4LnJdFeLeuY5KUuN").unwrap(),
                     reply_to_hops: None,
                     reply_to_avg_mix_delays: None,
@@ -101,6 +101,36 @@ impl IpPacketResponse {
         }
     }
 
+    pub fn new_lease_query_success(
+        request_id: u64,
+        reply_to: Recipient,
+        leases: Vec<IpLease>,
+    ) -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            data: IpPacketResponseData::LeaseQuery(LeaseQueryResponse {
+                request_id,
+                reply_to,
+                reply: LeaseQueryResponseReply::Success(leases),
+            }),
+        }
+    }
+
+    pub fn new_lease_query_failure(
+        request_id: u64,
+        reply_to: Recipient,
+        reason: LeaseQueryFailureReason,
+    ) -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            data: IpPacketResponseData::LeaseQuery(LeaseQueryResponse {
+                request_id,
+                reply_to,
+                reply: LeaseQueryResponseReply::Failure(reason),
+            }),
+        }
+    }
+
     pub fn new_ip_packet(ip_packet: bytes::Bytes) -> Self {
         Self {
             version: CURRENT_VERSION,
@@ -154,6 +184,7 @@ impl IpPacketResponse {
             IpPacketResponseData::Pong(response) => Some(response.request_id),
             IpPacketResponseData::Health(response) => Some(response.request_id),
             IpPacketResponseData::Info(response) => Some(response.request_id),
+            IpPacketResponseData::LeaseQuery(response) => Some(response.request_id),
         }
     }
 
@@ -167,6 +198,7 @@ impl IpPacketResponse {
             IpPacketResponseData::Pong(response) => Some(&response.reply_to),
             IpPacketResponseData::Health(response) => Some(&response.reply_to),
             IpPacketResponseData::Info(response) => Some(&response.reply_to),
+            IpPacketResponseData::LeaseQuery(response) => Some(&response.reply_to),
         }
     }
 
@@ -209,6 +241,9 @@ pub enum IpPacketResponseData {
 
     // Info response. This can be anything from informative messages to errors
     Info(InfoResponse),
+
+    // Response for a lease query request
+    LeaseQuery(LeaseQueryResponse),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -343,6 +378,40 @@ pub struct HealthResponseReply {
     pub routable: Option<bool>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeaseQueryResponse {
+    pub request_id: u64,
+    pub reply_to: Recipient,
+    pub reply: LeaseQueryResponseReply,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum LeaseQueryResponseReply {
+    Success(Vec<IpLease>),
+    Failure(LeaseQueryFailureReason),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct IpLease {
+    // The client the addresses are leased to
+    pub nym_address: Recipient,
+    pub ips: IpPair,
+    // Unix timestamp of when the lease expires, if the client has disconnected
+    pub expires_at: Option<i64>,
+    // Unix timestamp of the last time the client was seen sending anything
+    pub last_activity: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, thiserror::Error)]
+pub enum LeaseQueryFailureReason {
+    #[error("the requesting nym-address is not authorized to query the leases")]
+    NotAuthorized,
+    #[error("persistent lease storage is not enabled")]
+    StorageDisabled,
+    #[error("{0}")]
+    Other(String),
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InfoResponse {
     pub request_id: u64,
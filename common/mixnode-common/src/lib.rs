@@ -1,4 +1,6 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
+pub mod noise_keys;
 pub mod packet_processor;
+pub mod replay_protection;
 pub mod verloc;
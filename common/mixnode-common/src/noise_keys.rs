@@ -0,0 +1,170 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::{stream, StreamExt};
+use log::{debug, error, info, warn};
+use nym_crypto::asymmetric::encryption;
+use nym_noise::NoisePeerKeys;
+use nym_task::TaskClient;
+use nym_validator_client::ValidatorClientError;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::net::lookup_host;
+use tokio::time::sleep;
+use url::Url;
+
+// Maximum number of the node hosts being resolved at the same time
+const MAX_CONCURRENT_RESOLUTIONS: usize = 64;
+
+#[derive(Debug, Error)]
+#[error("there are no nym API endpoints available")]
+pub struct NoNymApisAvailable;
+
+/// Periodically retrieves sphinx keys of all bonded mixnodes and gateways from the nym-api,
+/// so that they could be used as static keys of the noise links.
+pub struct NoiseKeysRefresher {
+    peer_keys: NoisePeerKeys,
+    refresh_interval: Duration,
+
+    nym_api_urls: Vec<Url>,
+    currently_used_api: usize,
+    validator_client: nym_validator_client::NymApiClient,
+
+    shutdown: TaskClient,
+}
+
+impl NoiseKeysRefresher {
+    pub fn new(
+        peer_keys: NoisePeerKeys,
+        refresh_interval: Duration,
+        mut nym_api_urls: Vec<Url>,
+        shutdown: TaskClient,
+    ) -> Result<Self, NoNymApisAvailable> {
+        nym_api_urls.shuffle(&mut thread_rng());
+        let first_api = nym_api_urls.first().ok_or(NoNymApisAvailable)?.clone();
+
+        Ok(NoiseKeysRefresher {
+            peer_keys,
+            refresh_interval,
+            validator_client: nym_validator_client::NymApiClient::new(first_api),
+            nym_api_urls,
+            currently_used_api: 0,
+            shutdown,
+        })
+    }
+
+    fn use_next_nym_api(&mut self) {
+        if self.nym_api_urls.len() == 1 {
+            return;
+        }
+
+        self.currently_used_api = (self.currently_used_api + 1) % self.nym_api_urls.len();
+        self.validator_client
+            .change_nym_api(self.nym_api_urls[self.currently_used_api].clone())
+    }
+
+    async fn resolve(host: String, port: u16) -> Option<SocketAddr> {
+        match lookup_host((host.as_str(), port)).await {
+            Ok(mut addresses) => addresses.next(),
+            Err(err) => {
+                debug!("failed to resolve {host}:{port}: {err}");
+                None
+            }
+        }
+    }
+
+    async fn refresh(&mut self) -> Result<(), ValidatorClientError> {
+        let mixnodes = self.validator_client.get_cached_mixnodes().await?;
+        let gateways = self.validator_client.get_cached_gateways().await?;
+
+        let nodes = mixnodes
+            .into_iter()
+            .map(|node| {
+                let mix_node = node.bond_information.mix_node;
+                (mix_node.host, mix_node.mix_port, mix_node.sphinx_key)
+            })
+            .chain(gateways.into_iter().map(|node| {
+                let gateway = node.gateway;
+                (gateway.host, gateway.mix_port, gateway.sphinx_key)
+            }));
+
+        let nodes = nodes.filter_map(|(host, port, sphinx_key)| {
+            match encryption::PublicKey::from_base58_string(&sphinx_key) {
+                Ok(key) => Some((host, port, key)),
+                Err(_) => {
+                    debug!("{host} has announced malformed sphinx key");
+                    None
+                }
+            }
+        });
+
+        // resolve the hosts concurrently, so that the misconfigured ones wouldn't hold up the rest
+        let keys: HashMap<_, _> = stream::iter(nodes)
+            .map(|(host, port, key)| async move {
+                Self::resolve(host, port)
+                    .await
+                    .map(|address| (address, key))
+            })
+            .buffer_unordered(MAX_CONCURRENT_RESOLUTIONS)
+            .filter_map(|resolved| async move { resolved })
+            .collect()
+            .await;
+
+        info!("retrieved noise keys of {} nodes", keys.len());
+        self.peer_keys.update(keys);
+        Ok(())
+    }
+
+    pub async fn run(&mut self) {
+        while !self.shutdown.is_shutdown() {
+            let next_refresh = match self.refresh().await {
+                Ok(_) => self.refresh_interval,
+                Err(err) => {
+                    error!("failed to obtain the noise keys of the network nodes: {err}");
+                    self.use_next_nym_api();
+                    if self.peer_keys.is_empty() {
+                        warn!("the noise keys of the other nodes are still not known");
+                    }
+                    // retry sooner if we failed
+                    self.refresh_interval / 10
+                }
+            };
+
+            tokio::select! {
+                _ = sleep(next_refresh) => {},
+                _ = self.shutdown.recv() => {
+                    log::trace!("NoiseKeysRefresher: Received shutdown");
+                }
+            }
+        }
+        log::trace!("NoiseKeysRefresher: Exiting");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresher_requires_nym_api_endpoints() {
+        let refresher = NoiseKeysRefresher::new(
+            NoisePeerKeys::new(),
+            Duration::from_secs(60),
+            Vec::new(),
+            TaskClient::dummy(),
+        );
+        assert!(refresher.is_err());
+
+        let refresher = NoiseKeysRefresher::new(
+            NoisePeerKeys::new(),
+            Duration::from_secs(60),
+            vec!["http://localhost:8080".parse().unwrap()],
+            TaskClient::dummy(),
+        );
+        assert!(refresher.is_ok());
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nym_sphinx_types::PrivateKey;
+use std::sync::{Arc, RwLock};
+
+/// Identifies the period during which a sphinx key is accepted, so that any state associated
+/// with the packets created for that key (such as their replay tags) could be discarded together with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyEpoch {
+    /// The bonded sphinx key of the node which does not have a predetermined expiration.
+    Legacy,
+
+    /// Key used for the specified rotation.
+    Rotation(u32),
+}
+
+/// Sphinx key alongside the epoch it belongs to.
+#[derive(Clone)]
+pub struct EpochKey {
+    pub epoch: KeyEpoch,
+    pub key: Arc<PrivateKey>,
+}
+
+impl EpochKey {
+    pub fn new(epoch: KeyEpoch, key: Arc<PrivateKey>) -> Self {
+        EpochKey { epoch, key }
+    }
+}
+
+/// Set of sphinx keys currently accepted by the node, shared between all the packet processors.
+///
+/// The primary key is always tried first. Any additional keys (such as the previous key
+/// during the rotation grace period or the upcoming one announced in advance)
+/// are only used if the packet couldn't have been processed with the primary key.
+#[derive(Clone)]
+pub struct SphinxKeyRing {
+    inner: Arc<RwLock<KeyRingInner>>,
+}
+
+struct KeyRingInner {
+    primary: EpochKey,
+    additional: Vec<EpochKey>,
+}
+
+impl SphinxKeyRing {
+    /// Creates a key ring consisting only of the legacy (bonded) key.
+    pub fn new(primary: PrivateKey) -> Self {
+        SphinxKeyRing {
+            inner: Arc::new(RwLock::new(KeyRingInner {
+                primary: EpochKey::new(KeyEpoch::Legacy, Arc::new(primary)),
+                additional: Vec::new(),
+            })),
+        }
+    }
+
+    /// Replaces all the keys in the ring.
+    pub fn update(&self, primary: EpochKey, additional: Vec<EpochKey>) {
+        let mut guard = self
+            .inner
+            .write()
+            .expect("sphinx key ring lock got poisoned");
+        guard.primary = primary;
+        guard.additional = additional;
+    }
+
+    /// Returns all the currently accepted keys in the order they should be tried in.
+    pub fn keys(&self) -> Vec<EpochKey> {
+        let guard = self
+            .inner
+            .read()
+            .expect("sphinx key ring lock got poisoned");
+        std::iter::once(&guard.primary)
+            .chain(guard.additional.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Returns epochs of all the currently accepted keys.
+    pub fn epochs(&self) -> Vec<KeyEpoch> {
+        self.keys().into_iter().map(|key| key.epoch).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        let guard = self
+            .inner
+            .read()
+            .expect("sphinx key ring lock got poisoned");
+        1 + guard.additional.len()
+    }
+
+    /// The key ring always contains at least the primary key.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl From<PrivateKey> for SphinxKeyRing {
+    fn from(primary: PrivateKey) -> Self {
+        SphinxKeyRing::new(primary)
+    }
+}
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::packet_processor::error::MixProcessingError;
+use crate::packet_processor::key_ring::{KeyEpoch, SphinxKeyRing};
+use crate::replay_protection::ReplayProtection;
 use log::*;
 use nym_metrics::nanos;
 use nym_sphinx_acknowledgements::surb_ack::SurbAck;
@@ -14,7 +16,6 @@ use nym_sphinx_types::{
     PrivateKey, ProcessedPacket,
 };
 use std::convert::TryFrom;
-use std::sync::Arc;
 
 type ForwardAck = MixPacket;
 
@@ -36,39 +37,100 @@ pub enum MixProcessingResult {
 
 #[derive(Clone)]
 pub struct SphinxPacketProcessor {
-    /// Private sphinx key of this node required to unwrap received sphinx packet.
-    sphinx_key: Arc<PrivateKey>,
+    /// Private sphinx key(s) of this node required to unwrap received sphinx packet.
+    sphinx_keys: SphinxKeyRing,
+
+    /// If specified, used for rejecting packets that have already been processed before.
+    replay_protection: Option<ReplayProtection>,
 }
 
 impl SphinxPacketProcessor {
     /// Creates new instance of `CachedPacketProcessor`
     pub fn new(sphinx_key: PrivateKey) -> Self {
+        Self::new_with_key_ring(SphinxKeyRing::new(sphinx_key))
+    }
+
+    /// Creates new instance of `SphinxPacketProcessor` using the shared set of keys
+    /// that might get updated whenever the node rotates its sphinx key.
+    pub fn new_with_key_ring(sphinx_keys: SphinxKeyRing) -> Self {
         SphinxPacketProcessor {
-            sphinx_key: Arc::new(sphinx_key),
+            sphinx_keys,
+            replay_protection: None,
         }
     }
 
-    /// Performs a fresh sphinx unwrapping using no cache.
-    fn perform_initial_packet_processing(
+    #[must_use]
+    pub fn with_replay_protection(mut self, replay_protection: ReplayProtection) -> Self {
+        self.replay_protection = Some(replay_protection);
+        self
+    }
+
+    /// Checks whether the received packet has already been seen before and returns its replay tag,
+    /// if applicable. Note that the tag is not recorded at this point.
+    fn check_replay(
         &self,
-        packet: NymPacket,
-    ) -> Result<NymProcessedPacket, MixProcessingError> {
-        nanos!("perform_initial_packet_processing", {
-            packet.process(&self.sphinx_key).map_err(|err| {
-                debug!("Failed to unwrap NymPacket packet: {err}");
-                MixProcessingError::NymPacketProcessingError(err)
-            })
-        })
+        received: &FramedNymPacket,
+    ) -> Result<Option<[u8; 32]>, MixProcessingError> {
+        let Some(replay_protection) = &self.replay_protection else {
+            return Ok(None);
+        };
+
+        let replay_tag = received.packet().replay_tag();
+        if let Some(replay_tag) = &replay_tag {
+            if replay_protection.contains(replay_tag) {
+                return Err(MixProcessingError::ReplayedPacket);
+            }
+        }
+        Ok(replay_tag)
+    }
+
+    /// Records the replay tag of a successfully unwrapped packet. It's only done after the unwrapping
+    /// so that nobody could insert tags of packets that haven't yet been received.
+    fn record_replay_tag(
+        &self,
+        replay_tag: Option<[u8; 32]>,
+        epoch: KeyEpoch,
+    ) -> Result<(), MixProcessingError> {
+        let (Some(replay_protection), Some(replay_tag)) = (&self.replay_protection, replay_tag)
+        else {
+            return Ok(());
+        };
+
+        // the same packet might have been concurrently processed by another connection
+        if replay_protection.check_and_insert(epoch, &replay_tag) {
+            return Err(MixProcessingError::ReplayedPacket);
+        }
+        Ok(())
     }
 
     /// Takes the received framed packet and tries to unwrap it from the sphinx encryption.
     fn perform_initial_unwrapping(
         &self,
         received: FramedNymPacket,
-    ) -> Result<NymProcessedPacket, MixProcessingError> {
+    ) -> Result<(NymProcessedPacket, KeyEpoch), MixProcessingError> {
         nanos!("perform_initial_unwrapping", {
-            let packet = received.into_inner();
-            self.perform_initial_packet_processing(packet)
+            let keys = self.sphinx_keys.keys();
+
+            // attempt the processing with every key but the last one on a copy of the packet
+            // so that we could retry it if we happened to use a wrong key
+            let (last_key, other_keys) = keys
+                .split_last()
+                .expect("the key ring always contains at least a single key");
+            for key in other_keys {
+                match received.packet_copy()?.process(&key.key) {
+                    Ok(processed) => return Ok((processed, key.epoch)),
+                    Err(err) => trace!("failed to unwrap NymPacket with one of the keys: {err}"),
+                }
+            }
+
+            received
+                .into_inner()
+                .process(&last_key.key)
+                .map(|processed| (processed, last_key.epoch))
+                .map_err(|err| {
+                    debug!("Failed to unwrap NymPacket packet: {err}");
+                    MixProcessingError::NymPacketProcessingError(err)
+                })
         })
     }
 
@@ -221,8 +283,12 @@ impl SphinxPacketProcessor {
             let packet_size = received.packet_size();
             let packet_type = received.packet_type();
 
+            // reject the packet before doing any expensive processing if we've already seen it
+            let replay_tag = self.check_replay(&received)?;
+
             // unwrap the sphinx packet and if possible and appropriate, cache keys
-            let processed_packet = self.perform_initial_unwrapping(received)?;
+            let (processed_packet, epoch) = self.perform_initial_unwrapping(received)?;
+            self.record_replay_tag(replay_tag, epoch)?;
 
             // for forward packets, extract next hop and set delay (but do NOT delay here)
             // for final packets, extract SURBAck
@@ -242,13 +308,149 @@ impl SphinxPacketProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::packet_processor::key_ring::EpochKey;
+    use crate::replay_protection::ReplayProtectionConfig;
     use nym_sphinx_types::crypto::keygen;
+    use nym_sphinx_types::{
+        Destination, Node, PublicKey, DESTINATION_ADDRESS_LENGTH, IDENTIFIER_LENGTH,
+        NODE_ADDRESS_LENGTH,
+    };
+    use std::time::Duration;
 
     fn fixture() -> SphinxPacketProcessor {
         let local_keys = keygen();
         SphinxPacketProcessor::new(local_keys.0)
     }
 
+    fn make_sphinx_packet_bytes(first_hop_key: &PublicKey) -> Vec<u8> {
+        let node1 = Node::new(
+            NodeAddressBytes::from_bytes([5u8; NODE_ADDRESS_LENGTH]),
+            PublicKey::from(*first_hop_key.as_bytes()),
+        );
+        let (_, node2_pk) = keygen();
+        let node2 = Node::new(
+            NodeAddressBytes::from_bytes([4u8; NODE_ADDRESS_LENGTH]),
+            node2_pk,
+        );
+
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![
+            SphinxDelay::new_from_nanos(42),
+            SphinxDelay::new_from_nanos(42),
+        ];
+        NymPacket::sphinx_build(
+            PacketSize::RegularPacket.payload_size(),
+            b"foomp",
+            &[node1, node2],
+            &destination,
+            &delays,
+        )
+        .unwrap()
+        .to_bytes()
+        .unwrap()
+    }
+
+    fn framed(bytes: &[u8]) -> FramedNymPacket {
+        FramedNymPacket::new(
+            NymPacket::sphinx_from_bytes(bytes).unwrap(),
+            PacketType::Mix,
+            false,
+        )
+    }
+
+    fn test_replay_protection_config() -> ReplayProtectionConfig {
+        ReplayProtectionConfig {
+            expected_packet_rate: 10,
+            false_positive_rate: 1e-9,
+            key_lifetime: Duration::from_secs(10),
+            legacy_partition_duration: Duration::from_secs(10),
+        }
+    }
+
+    #[tokio::test]
+    async fn replayed_packets_are_rejected() {
+        let (private_key, public_key) = keygen();
+        let processor = SphinxPacketProcessor::new(private_key)
+            .with_replay_protection(ReplayProtection::new(test_replay_protection_config()));
+
+        let packet = make_sphinx_packet_bytes(&public_key);
+        let other_packet = make_sphinx_packet_bytes(&public_key);
+
+        assert!(!matches!(
+            processor.process_received(framed(&packet)),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+        assert!(!matches!(
+            processor.process_received(framed(&other_packet)),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+        assert!(matches!(
+            processor.process_received(framed(&packet)),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+    }
+
+    #[tokio::test]
+    async fn replay_tags_of_invalid_packets_are_not_recorded() {
+        let (private_key, public_key) = keygen();
+        let (other_private_key, _) = keygen();
+        let replay_protection = ReplayProtection::new(test_replay_protection_config());
+
+        let processor = SphinxPacketProcessor::new(private_key)
+            .with_replay_protection(replay_protection.clone());
+        let wrong_key_processor =
+            SphinxPacketProcessor::new(other_private_key).with_replay_protection(replay_protection);
+
+        // the packet can't be unwrapped with the wrong key, so its tag must not poison the filter
+        let packet = make_sphinx_packet_bytes(&public_key);
+        assert!(matches!(
+            wrong_key_processor.process_received(framed(&packet)),
+            Err(MixProcessingError::NymPacketProcessingError(_))
+        ));
+        assert!(processor.process_received(framed(&packet)).is_ok());
+        assert!(matches!(
+            processor.process_received(framed(&packet)),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+    }
+
+    #[tokio::test]
+    async fn packets_are_processed_with_any_of_the_accepted_keys() {
+        let (old_private, old_public) = keygen();
+        let (new_private, new_public) = keygen();
+        let (_, unknown_public) = keygen();
+
+        let key_ring = SphinxKeyRing::new(old_private);
+        let processor = SphinxPacketProcessor::new_with_key_ring(key_ring.clone());
+        assert!(processor
+            .process_received(framed(&make_sphinx_packet_bytes(&old_public)))
+            .is_ok());
+
+        // rotate the key, but keep accepting the old one
+        key_ring.update(
+            EpochKey::new(KeyEpoch::Rotation(1), std::sync::Arc::new(new_private)),
+            key_ring.keys(),
+        );
+        assert!(processor
+            .process_received(framed(&make_sphinx_packet_bytes(&new_public)))
+            .is_ok());
+        assert!(processor
+            .process_received(framed(&make_sphinx_packet_bytes(&old_public)))
+            .is_ok());
+        assert!(processor
+            .process_received(framed(&make_sphinx_packet_bytes(&unknown_public)))
+            .is_err());
+
+        // and finally drop the old key
+        key_ring.update(key_ring.keys()[0].clone(), Vec::new());
+        assert!(processor
+            .process_received(framed(&make_sphinx_packet_bytes(&old_public)))
+            .is_err());
+    }
+
     #[tokio::test]
     async fn splitting_hop_data_works_for_sufficiently_long_payload() {
         let processor = fixture();
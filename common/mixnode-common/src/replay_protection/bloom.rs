@@ -0,0 +1,139 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+const WORD_BITS: u64 = u64::BITS as u64;
+
+/// Simple bloom filter with fixed memory footprint.
+///
+/// The bit indices are derived with a randomly keyed hasher so that a malicious party
+/// couldn't deliberately craft values colliding with the ones inserted by honest users.
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    items: usize,
+    hasher: RandomState,
+}
+
+impl BloomFilter {
+    /// Creates a new filter sized for storing `capacity` items with the specified false positive rate.
+    pub(crate) fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1) as f64;
+        let false_positive_rate = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+
+        let num_bits = (-capacity * false_positive_rate.ln() / (ln2 * ln2)).ceil() as u64;
+        let num_bits = num_bits.max(WORD_BITS);
+        let num_hashes = ((num_bits as f64 / capacity) * ln2).round().max(1.0) as u32;
+
+        let words = num_bits.div_ceil(WORD_BITS) as usize;
+
+        BloomFilter {
+            bits: vec![0; words],
+            num_bits: words as u64 * WORD_BITS,
+            num_hashes,
+            items: 0,
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub(crate) fn items(&self) -> usize {
+        self.items
+    }
+
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.bits.len() * std::mem::size_of::<u64>()
+    }
+
+    fn base_hashes(&self, item: &[u8]) -> (u64, u64) {
+        let h1 = self.hasher.hash_one((0u8, item));
+        let h2 = self.hasher.hash_one((1u8, item));
+
+        // make sure the second hash is odd so that all indices are distinct
+        (h1, h2 | 1)
+    }
+
+    fn bit_position(&self, h1: u64, h2: u64, i: u64) -> (usize, u64) {
+        let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+        ((bit / WORD_BITS) as usize, 1 << (bit % WORD_BITS))
+    }
+
+    pub(crate) fn contains(&self, item: &[u8]) -> bool {
+        let (h1, h2) = self.base_hashes(item);
+        (0..self.num_hashes as u64).all(|i| {
+            let (word, mask) = self.bit_position(h1, h2, i);
+            self.bits[word] & mask != 0
+        })
+    }
+
+    /// Inserts the item into the filter and returns whether it (probably) has already been present.
+    pub(crate) fn check_and_insert(&mut self, item: &[u8]) -> bool {
+        let (h1, h2) = self.base_hashes(item);
+
+        let mut present = true;
+        for i in 0..self.num_hashes as u64 {
+            let (word, mask) = self.bit_position(h1, h2, i);
+            if self.bits[word] & mask == 0 {
+                present = false;
+                self.bits[word] |= mask;
+            }
+        }
+
+        if !present {
+            self.items += 1;
+        }
+        present
+    }
+
+    /// Removes all items from the filter whilst keeping the underlying allocation.
+    pub(crate) fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|word| *word = 0);
+        self.items = 0;
+        self.hasher = RandomState::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_inserted_items() {
+        let mut filter = BloomFilter::new(1000, 1e-9);
+        for i in 0u32..1000 {
+            assert!(!filter.check_and_insert(&i.to_be_bytes()));
+        }
+        for i in 0u32..1000 {
+            assert!(filter.contains(&i.to_be_bytes()));
+            assert!(filter.check_and_insert(&i.to_be_bytes()));
+        }
+        assert_eq!(filter.items(), 1000);
+    }
+
+    #[test]
+    fn false_positive_rate_is_roughly_respected() {
+        let mut filter = BloomFilter::new(10000, 0.001);
+        for i in 0u32..10000 {
+            filter.check_and_insert(&i.to_be_bytes());
+        }
+
+        let false_positives = (10000u32..110000)
+            .filter(|i| filter.contains(&i.to_be_bytes()))
+            .count();
+
+        // expected ~100, leave plenty of headroom
+        assert!(false_positives < 300);
+    }
+
+    #[test]
+    fn clearing_removes_items() {
+        let mut filter = BloomFilter::new(100, 0.001);
+        filter.check_and_insert(b"foo");
+        filter.clear();
+        assert!(!filter.contains(b"foo"));
+        assert_eq!(filter.items(), 0);
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detection of replayed packets.
+//!
+//! Every processed packet has its replay tag (i.e. the public element of the header that is unique
+//! for each packet and hop) recorded in a bloom filter associated with the sphinx key the packet
+//! has been processed with. A replayed packet could only ever be processed with the very same key,
+//! so the tags are remembered for exactly as long as the key is accepted and the filter is dropped
+//! once the key expires.
+//!
+//! Each filter is sized for the expected packet rate sustained over the period it covers, i.e. the
+//! lifetime of the key (or the duration of a legacy partition, see below).
+//! If a filter ends up receiving more packets than it has been sized for, its false positive rate
+//! is going to increase rather than any tags getting evicted, so that flooding the node could not
+//! make it forget the packets it has already seen.
+//!
+//! The legacy (bonded) key does not expire, so the tags of the packets created for it are split into
+//! time-based partitions instead, each with its own filter. Once the current partition expires,
+//! the oldest filter gets cleared and becomes the new current one.
+
+use crate::packet_processor::key_ring::KeyEpoch;
+use crate::replay_protection::bloom::BloomFilter;
+use log::{debug, warn};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+mod bloom;
+
+const LEGACY_PARTITIONS: usize = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayProtectionConfig {
+    /// Expected average number of packets processed by the node per second. Together with the
+    /// key lifetime (or the legacy partition duration) it determines the capacity,
+    /// and thus the memory usage, of the filters.
+    pub expected_packet_rate: u32,
+
+    /// Desired false positive rate of each filter, as long as it does not exceed its capacity.
+    pub false_positive_rate: f64,
+
+    /// For how long each rotated sphinx key is accepted, i.e. for how long the tags of the packets
+    /// processed with it have to be remembered.
+    pub key_lifetime: Duration,
+
+    /// Specifies for how long each partition of the legacy key filter is going to be used
+    /// for recording new packets. Replays of packets created for the legacy key are going to
+    /// be detected for at least this long.
+    pub legacy_partition_duration: Duration,
+}
+
+impl Default for ReplayProtectionConfig {
+    fn default() -> Self {
+        ReplayProtectionConfig {
+            expected_packet_rate: 500,
+            false_positive_rate: 1e-6,
+            key_lifetime: Duration::from_secs(26 * 60 * 60),
+            legacy_partition_duration: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl ReplayProtectionConfig {
+    fn capacity_for(&self, period: Duration) -> usize {
+        (self.expected_packet_rate as f64 * period.as_secs_f64()).ceil() as usize
+    }
+
+    fn rotation_capacity(&self) -> usize {
+        self.capacity_for(self.key_lifetime)
+    }
+
+    fn legacy_partition_capacity(&self) -> usize {
+        self.capacity_for(self.legacy_partition_duration)
+    }
+}
+
+/// Thread-safe handle to the shared replay detection filters.
+#[derive(Clone)]
+pub struct ReplayProtection {
+    inner: Arc<Mutex<EpochFilters>>,
+}
+
+impl ReplayProtection {
+    pub fn new(config: ReplayProtectionConfig) -> Self {
+        ReplayProtection {
+            inner: Arc::new(Mutex::new(EpochFilters::new(config))),
+        }
+    }
+
+    /// Checks whether the provided replay tag has already been seen with any of the keys,
+    /// without recording it.
+    pub fn contains(&self, replay_tag: &[u8]) -> bool {
+        let guard = self.inner.lock().expect("replay filter lock got poisoned");
+        guard.contains(replay_tag)
+    }
+
+    /// Records the replay tag of a packet successfully processed with the key of the specified
+    /// epoch and returns whether it has already been seen before.
+    pub fn check_and_insert(&self, epoch: KeyEpoch, replay_tag: &[u8]) -> bool {
+        let mut guard = self.inner.lock().expect("replay filter lock got poisoned");
+        guard.check_and_insert(epoch, replay_tag, Instant::now())
+    }
+
+    /// Drops the filters of all the epochs whose keys are no longer accepted.
+    pub fn retain_epochs(&self, accepted: &[KeyEpoch]) {
+        let mut guard = self.inner.lock().expect("replay filter lock got poisoned");
+        guard.retain_epochs(accepted)
+    }
+}
+
+struct LegacyFilter {
+    // the front partition is the current one
+    partitions: VecDeque<BloomFilter>,
+    current_started_at: Instant,
+}
+
+struct EpochFilters {
+    config: ReplayProtectionConfig,
+
+    rotations: HashMap<u32, BloomFilter>,
+
+    // only allocated once the first packet for the legacy key is processed
+    legacy: Option<LegacyFilter>,
+}
+
+impl EpochFilters {
+    fn new(config: ReplayProtectionConfig) -> Self {
+        EpochFilters {
+            config,
+            rotations: HashMap::new(),
+            legacy: None,
+        }
+    }
+
+    fn capacity(&self, epoch: KeyEpoch) -> usize {
+        match epoch {
+            KeyEpoch::Legacy => self.config.legacy_partition_capacity(),
+            KeyEpoch::Rotation(_) => self.config.rotation_capacity(),
+        }
+    }
+
+    fn new_filter(&self, epoch: KeyEpoch) -> BloomFilter {
+        let filter = BloomFilter::new(self.capacity(epoch), self.config.false_positive_rate);
+        debug!(
+            "allocated {} bytes for a new replay detection filter for {epoch:?}",
+            filter.memory_usage()
+        );
+        filter
+    }
+
+    fn warn_if_saturated(&self, filter: &BloomFilter, epoch: KeyEpoch) {
+        let capacity = self.capacity(epoch);
+        // only log it the moment the capacity gets exceeded
+        if filter.items() == capacity + 1 {
+            warn!(
+                "the replay detection filter for {epoch:?} has exceeded its capacity of {capacity} packets - its false positive rate is going to increase. consider increasing the expected packet rate"
+            )
+        }
+    }
+
+    fn contains(&self, replay_tag: &[u8]) -> bool {
+        self.rotations
+            .values()
+            .chain(
+                self.legacy
+                    .iter()
+                    .flat_map(|legacy| legacy.partitions.iter()),
+            )
+            .any(|filter| filter.contains(replay_tag))
+    }
+
+    fn check_and_insert_legacy(&mut self, replay_tag: &[u8], now: Instant) -> bool {
+        if self.legacy.is_none() {
+            let partitions = (0..LEGACY_PARTITIONS)
+                .map(|_| self.new_filter(KeyEpoch::Legacy))
+                .collect();
+            self.legacy = Some(LegacyFilter {
+                partitions,
+                current_started_at: now,
+            })
+        }
+        let partition_duration = self.config.legacy_partition_duration;
+        let Some(legacy) = self.legacy.as_mut() else {
+            return false;
+        };
+
+        // note that, unlike the capacity, only the time is taken into consideration for rotating
+        // the partitions as otherwise anyone could flush the filter by flooding the node
+        if now.saturating_duration_since(legacy.current_started_at) >= partition_duration {
+            // reuse the allocation of the oldest partition
+            if let Some(mut oldest) = legacy.partitions.pop_back() {
+                oldest.clear();
+                legacy.partitions.push_front(oldest);
+            }
+            legacy.current_started_at = now;
+        }
+
+        if legacy
+            .partitions
+            .iter()
+            .skip(1)
+            .any(|partition| partition.contains(replay_tag))
+        {
+            return true;
+        }
+
+        let Some(current) = legacy.partitions.front_mut() else {
+            return false;
+        };
+        current.check_and_insert(replay_tag)
+    }
+
+    fn check_and_insert(&mut self, epoch: KeyEpoch, replay_tag: &[u8], now: Instant) -> bool {
+        match epoch {
+            KeyEpoch::Legacy => {
+                let seen = self.check_and_insert_legacy(replay_tag, now);
+                if let Some(current) = self
+                    .legacy
+                    .as_ref()
+                    .and_then(|legacy| legacy.partitions.front())
+                {
+                    self.warn_if_saturated(current, epoch)
+                }
+                seen
+            }
+            KeyEpoch::Rotation(rotation_id) => {
+                if !self.rotations.contains_key(&rotation_id) {
+                    let filter = self.new_filter(epoch);
+                    self.rotations.insert(rotation_id, filter);
+                }
+                let Some(filter) = self.rotations.get_mut(&rotation_id) else {
+                    return false;
+                };
+                let seen = filter.check_and_insert(replay_tag);
+                if let Some(filter) = self.rotations.get(&rotation_id) {
+                    self.warn_if_saturated(filter, epoch)
+                }
+                seen
+            }
+        }
+    }
+
+    fn retain_epochs(&mut self, accepted: &[KeyEpoch]) {
+        self.rotations
+            .retain(|rotation_id, _| accepted.contains(&KeyEpoch::Rotation(*rotation_id)));
+        if !accepted.contains(&KeyEpoch::Legacy) {
+            self.legacy = None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> ReplayProtectionConfig {
+        ReplayProtectionConfig {
+            expected_packet_rate: 10,
+            false_positive_rate: 1e-9,
+            key_lifetime: Duration::from_secs(10),
+            legacy_partition_duration: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn filters_are_sized_for_their_periods() {
+        let config = ReplayProtectionConfig {
+            expected_packet_rate: 500,
+            false_positive_rate: 1e-6,
+            key_lifetime: Duration::from_secs(26 * 60 * 60),
+            legacy_partition_duration: Duration::from_secs(60 * 60),
+        };
+        let filters = EpochFilters::new(config);
+        assert_eq!(filters.capacity(KeyEpoch::Rotation(1)), 46_800_000);
+        assert_eq!(filters.capacity(KeyEpoch::Legacy), 1_800_000);
+    }
+
+    #[test]
+    fn detects_replays() {
+        let protection = ReplayProtection::new(test_config());
+        let epoch = KeyEpoch::Rotation(1);
+        assert!(!protection.check_and_insert(epoch, b"foo"));
+        assert!(!protection.check_and_insert(epoch, b"bar"));
+        assert!(protection.contains(b"foo"));
+        assert!(protection.check_and_insert(epoch, b"foo"));
+        assert!(!protection.contains(b"baz"));
+    }
+
+    #[test]
+    fn tags_are_remembered_for_as_long_as_the_key_is_accepted() {
+        let start = Instant::now();
+        let mut filters = EpochFilters::new(test_config());
+        assert!(!filters.check_and_insert(KeyEpoch::Rotation(1), b"foo", start));
+        assert!(!filters.check_and_insert(KeyEpoch::Rotation(2), b"bar", start));
+
+        // the passage of time on its own does not affect the rotated keys
+        let much_later = start + Duration::from_secs(60 * 60 * 24);
+        assert!(filters.check_and_insert(KeyEpoch::Rotation(1), b"foo", much_later));
+
+        filters.retain_epochs(&[KeyEpoch::Rotation(2)]);
+        assert!(!filters.contains(b"foo"));
+        assert!(filters.contains(b"bar"));
+    }
+
+    #[test]
+    fn full_filters_do_not_forget_tags() {
+        let start = Instant::now();
+        let mut filters = EpochFilters::new(test_config());
+        for i in 0u32..300 {
+            filters.check_and_insert(KeyEpoch::Rotation(1), &i.to_be_bytes(), start);
+            filters.check_and_insert(KeyEpoch::Legacy, &i.to_be_bytes(), start);
+        }
+        for i in 0u32..300 {
+            assert!(filters.check_and_insert(KeyEpoch::Rotation(1), &i.to_be_bytes(), start));
+            assert!(filters.check_and_insert(KeyEpoch::Legacy, &i.to_be_bytes(), start));
+        }
+    }
+
+    #[test]
+    fn legacy_replays_are_detected_in_previous_partition() {
+        let start = Instant::now();
+        let mut filters = EpochFilters::new(test_config());
+        assert!(!filters.check_and_insert(KeyEpoch::Legacy, b"foo", start));
+
+        let later = start + Duration::from_secs(61);
+        assert!(filters.check_and_insert(KeyEpoch::Legacy, b"foo", later));
+
+        // the tag has only been inserted in the old partition, so after another rotation it's gone
+        let much_later = later + Duration::from_secs(61);
+        assert!(!filters.check_and_insert(KeyEpoch::Legacy, b"foo", much_later));
+    }
+
+    #[test]
+    fn legacy_filter_is_dropped_once_the_key_is_no_longer_accepted() {
+        let start = Instant::now();
+        let mut filters = EpochFilters::new(test_config());
+        filters.check_and_insert(KeyEpoch::Legacy, b"foo", start);
+
+        filters.retain_epochs(&[KeyEpoch::Legacy, KeyEpoch::Rotation(1)]);
+        assert!(filters.contains(b"foo"));
+
+        filters.retain_epochs(&[KeyEpoch::Rotation(1)]);
+        assert!(filters.legacy.is_none());
+        assert!(!filters.contains(b"foo"));
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nym_crypto::asymmetric::encryption;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct NoiseConfig {
+    pub(crate) local_key: Arc<encryption::KeyPair>,
+    pub(crate) peer_keys: NoisePeerKeys,
+    pub(crate) handshake_timeout: Duration,
+    pub(crate) require_encryption: bool,
+}
+
+impl NoiseConfig {
+    /// Creates new configuration using the provided sphinx keys as the static noise keys
+    /// of this node and the remotes.
+    pub fn new(local_key: Arc<encryption::KeyPair>, peer_keys: NoisePeerKeys) -> Self {
+        NoiseConfig {
+            local_key,
+            peer_keys,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            require_encryption: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Refuse any plaintext links, i.e. incoming connections not initiating the noise handshake
+    /// and outgoing connections to nodes with unknown keys. Furthermore, the incoming handshakes
+    /// are only accepted from the nodes with known keys.
+    #[must_use]
+    pub fn with_required_encryption(mut self, require_encryption: bool) -> Self {
+        self.require_encryption = require_encryption;
+        self
+    }
+
+    pub fn peer_keys(&self) -> &NoisePeerKeys {
+        &self.peer_keys
+    }
+
+    pub fn requires_encryption(&self) -> bool {
+        self.require_encryption
+    }
+}
+
+/// Static noise keys of the known mixnet nodes, i.e. their sphinx keys as present in the topology.
+#[derive(Clone, Default)]
+pub struct NoisePeerKeys {
+    inner: Arc<RwLock<PeerKeysInner>>,
+}
+
+#[derive(Default)]
+struct PeerKeysInner {
+    by_address: HashMap<SocketAddr, encryption::PublicKey>,
+    known: HashSet<encryption::PublicKey>,
+}
+
+impl NoisePeerKeys {
+    pub fn new() -> Self {
+        NoisePeerKeys::default()
+    }
+
+    /// Replaces all the known keys. This also gives another chance to the nodes
+    /// that previously failed to complete the handshake.
+    pub fn update(&self, keys: HashMap<SocketAddr, encryption::PublicKey>) {
+        let mut guard = self.inner.write().expect("noise keys lock got poisoned");
+        guard.known = keys.values().copied().collect();
+        guard.by_address = keys;
+    }
+
+    /// Stops initiating the handshakes with the specified node until the keys are next updated,
+    /// for example because it has not been upgraded to support the noise links yet.
+    /// Its key is still going to be accepted for the incoming handshakes.
+    pub fn mark_unsupported(&self, address: &SocketAddr) {
+        let mut guard = self.inner.write().expect("noise keys lock got poisoned");
+        guard.by_address.remove(address);
+    }
+
+    pub fn get(&self, address: &SocketAddr) -> Option<encryption::PublicKey> {
+        let guard = self.inner.read().expect("noise keys lock got poisoned");
+        guard.by_address.get(address).copied()
+    }
+
+    pub fn is_known(&self, key: &encryption::PublicKey) -> bool {
+        let guard = self.inner.read().expect("noise keys lock got poisoned");
+        guard.known.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        let guard = self.inner.read().expect("noise keys lock got poisoned");
+        guard.by_address.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
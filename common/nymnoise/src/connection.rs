@@ -0,0 +1,80 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::stream::NoiseStream;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Link between two mixnet nodes that might or might not be protected with noise,
+/// depending on the outcome of the negotiation.
+pub enum Connection<C> {
+    Raw(C),
+    Noise(Box<NoiseStream<C>>),
+}
+
+impl<C> Connection<C> {
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, Connection::Noise(..))
+    }
+
+    pub fn get_ref(&self) -> &C {
+        match self {
+            Connection::Raw(conn) => conn,
+            Connection::Noise(stream) => stream.get_ref(),
+        }
+    }
+}
+
+impl<C> From<NoiseStream<C>> for Connection<C> {
+    fn from(stream: NoiseStream<C>) -> Self {
+        Connection::Noise(Box::new(stream))
+    }
+}
+
+impl<C> AsyncRead for Connection<C>
+where
+    C: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Raw(conn) => Pin::new(conn).poll_read(cx, buf),
+            Connection::Noise(stream) => Pin::new(stream.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<C> AsyncWrite for Connection<C>
+where
+    C: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Connection::Raw(conn) => Pin::new(conn).poll_write(cx, buf),
+            Connection::Noise(stream) => Pin::new(stream.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Raw(conn) => Pin::new(conn).poll_flush(cx),
+            Connection::Noise(stream) => Pin::new(stream.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Raw(conn) => Pin::new(conn).poll_shutdown(cx),
+            Connection::Noise(stream) => Pin::new(stream.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nym_crypto::asymmetric::encryption::KeyRecoveryError;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum NoiseError {
+    #[error("experienced an io failure: {0}")]
+    IoError(#[from] io::Error),
+
+    #[error("noise protocol failure: {0}")]
+    ProtocolError(#[from] snow::Error),
+
+    #[error("the noise handshake has not completed within {0:?}")]
+    HandshakeTimeout(Duration),
+
+    #[error("received an unexpected noise preface marker: {0:#04x}")]
+    UnexpectedPreface(u8),
+
+    #[error("the remote uses an unsupported noise link version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("the remote has not revealed its static key during the handshake")]
+    MissingRemoteStaticKey,
+
+    #[error("the remote static key is malformed: {0}")]
+    MalformedRemoteStaticKey(#[from] KeyRecoveryError),
+
+    #[error("the remote static key {0} does not belong to any known node")]
+    UnknownRemoteStaticKey(String),
+
+    #[error("the noise key of {0} is not known and plaintext links are not allowed")]
+    UnknownPeerKey(SocketAddr),
+
+    #[error("the remote attempted to establish a plaintext link which is not allowed")]
+    PlaintextLinkRefused,
+}
@@ -0,0 +1,338 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optional noise-based encryption and authentication of the links between the mixnet nodes.
+//!
+//! The initiator of the connection, if it knows the sphinx key of the remote (as published in the
+//! topology), sends a 2-byte preface consisting of [`NOISE_PREFACE_MARKER`] and the version of
+//! the protocol, followed by the `XK` handshake using its own sphinx key as the static key.
+//! The marker is chosen so that it can never be the first byte of a framed sphinx packet,
+//! thus the responder can distinguish noise links from the legacy plaintext ones
+//! and (unless configured otherwise) keep accepting the latter.
+//!
+//! Nodes that have not been upgraded yet are unable to parse the preface and drop the connection,
+//! in which case (unless configured otherwise) the initiator is expected to reconnect using
+//! the plaintext link and to stop attempting the handshakes with that node
+//! until the keys are next refreshed, see [`NoisePeerKeys::mark_unsupported`].
+
+use crate::stream::NoiseStream;
+use log::debug;
+use nym_crypto::asymmetric::encryption;
+use snow::HandshakeState;
+use std::net::SocketAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+pub mod config;
+pub mod connection;
+pub mod error;
+pub mod stream;
+
+pub use config::{NoiseConfig, NoisePeerKeys};
+pub use connection::Connection;
+pub use error::NoiseError;
+
+pub const NOISE_PATTERN: &str = "Noise_XK_25519_ChaChaPoly_BLAKE2s";
+
+/// The first byte sent by the initiator of the noise link. It does not correspond to any valid
+/// packet version nor packet size and thus could not have been sent by a plaintext link.
+pub const NOISE_PREFACE_MARKER: u8 = 0xff;
+
+pub const NOISE_VERSION: u8 = 1;
+
+const NOISE_PROLOGUE: &[u8] = b"NYM_MIXNET_NOISE_LINK";
+
+pub(crate) const MAX_NOISE_MESSAGE_LEN: usize = 65535;
+pub(crate) const NOISE_TAG_LEN: usize = 16;
+
+// the longest `XK` handshake message (`-> s, se`) with an empty payload is 64 bytes long
+const MAX_HANDSHAKE_MESSAGE_LEN: usize = 128;
+
+/// Attempts to establish noise link with the remote node, if its key is known.
+/// Otherwise, unless it's explicitly disallowed, the plaintext connection is returned.
+pub async fn upgrade_noise_initiator(
+    conn: TcpStream,
+    config: &NoiseConfig,
+    remote: SocketAddr,
+) -> Result<Connection<TcpStream>, NoiseError> {
+    let Some(remote_key) = config.peer_keys.get(&remote) else {
+        if config.require_encryption {
+            return Err(NoiseError::UnknownPeerKey(remote));
+        }
+        debug!("the noise key of {remote} is not known - using plaintext link");
+        return Ok(Connection::Raw(conn));
+    };
+
+    let stream = timeout(
+        config.handshake_timeout,
+        initiator_handshake(conn, config.local_key.private_key(), &remote_key),
+    )
+    .await
+    .map_err(|_| NoiseError::HandshakeTimeout(config.handshake_timeout))??;
+
+    debug!("established noise link with {remote}");
+    Ok(stream.into())
+}
+
+/// Completes the noise handshake if the remote has initiated it.
+/// Otherwise, unless it's explicitly disallowed, the plaintext connection is returned.
+pub async fn upgrade_noise_responder(
+    conn: TcpStream,
+    config: &NoiseConfig,
+) -> Result<Connection<TcpStream>, NoiseError> {
+    let handshake_timeout = config.handshake_timeout;
+    timeout(handshake_timeout, async {
+        let mut marker = [0u8; 1];
+        if conn.peek(&mut marker).await? == 0 {
+            // the remote has already closed the connection, there's nothing to negotiate
+            return Ok(Connection::Raw(conn));
+        }
+
+        if marker[0] != NOISE_PREFACE_MARKER {
+            if config.require_encryption {
+                return Err(NoiseError::PlaintextLinkRefused);
+            }
+            return Ok(Connection::Raw(conn));
+        }
+
+        let (stream, remote_key) =
+            responder_handshake(conn, config.local_key.private_key()).await?;
+        if config.require_encryption && !config.peer_keys.is_known(&remote_key) {
+            return Err(NoiseError::UnknownRemoteStaticKey(
+                remote_key.to_base58_string(),
+            ));
+        }
+
+        debug!("established noise link with node using key {remote_key}");
+        Ok(stream.into())
+    })
+    .await
+    .map_err(|_| NoiseError::HandshakeTimeout(handshake_timeout))?
+}
+
+pub(crate) async fn initiator_handshake<C>(
+    mut conn: C,
+    local_key: &encryption::PrivateKey,
+    remote_key: &encryption::PublicKey,
+) -> Result<NoiseStream<C>, NoiseError>
+where
+    C: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut handshake = snow::Builder::new(NOISE_PATTERN.parse()?)
+        .local_private_key(&local_key.to_bytes())
+        .remote_public_key(&remote_key.to_bytes())
+        .prologue(NOISE_PROLOGUE)
+        .build_initiator()?;
+
+    conn.write_all(&[NOISE_PREFACE_MARKER, NOISE_VERSION])
+        .await?;
+
+    // -> e, es
+    send_handshake_message(&mut conn, &mut handshake).await?;
+    // <- e, ee
+    recv_handshake_message(&mut conn, &mut handshake).await?;
+    // -> s, se
+    send_handshake_message(&mut conn, &mut handshake).await?;
+
+    Ok(NoiseStream::new(conn, handshake.into_transport_mode()?))
+}
+
+pub(crate) async fn responder_handshake<C>(
+    mut conn: C,
+    local_key: &encryption::PrivateKey,
+) -> Result<(NoiseStream<C>, encryption::PublicKey), NoiseError>
+where
+    C: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut preface = [0u8; 2];
+    conn.read_exact(&mut preface).await?;
+    if preface[0] != NOISE_PREFACE_MARKER {
+        return Err(NoiseError::UnexpectedPreface(preface[0]));
+    }
+    if preface[1] != NOISE_VERSION {
+        return Err(NoiseError::UnsupportedVersion(preface[1]));
+    }
+
+    let mut handshake = snow::Builder::new(NOISE_PATTERN.parse()?)
+        .local_private_key(&local_key.to_bytes())
+        .prologue(NOISE_PROLOGUE)
+        .build_responder()?;
+
+    // -> e, es
+    recv_handshake_message(&mut conn, &mut handshake).await?;
+    // <- e, ee
+    send_handshake_message(&mut conn, &mut handshake).await?;
+    // -> s, se
+    recv_handshake_message(&mut conn, &mut handshake).await?;
+
+    let remote_key = handshake
+        .get_remote_static()
+        .ok_or(NoiseError::MissingRemoteStaticKey)?;
+    let remote_key = encryption::PublicKey::from_bytes(remote_key)?;
+
+    Ok((
+        NoiseStream::new(conn, handshake.into_transport_mode()?),
+        remote_key,
+    ))
+}
+
+async fn send_handshake_message<C>(
+    conn: &mut C,
+    handshake: &mut HandshakeState,
+) -> Result<(), NoiseError>
+where
+    C: AsyncWrite + Unpin,
+{
+    let mut buf = [0u8; MAX_HANDSHAKE_MESSAGE_LEN];
+    let len = handshake.write_message(&[], &mut buf)?;
+
+    conn.write_u16(len as u16).await?;
+    conn.write_all(&buf[..len]).await?;
+    conn.flush().await?;
+    Ok(())
+}
+
+async fn recv_handshake_message<C>(
+    conn: &mut C,
+    handshake: &mut HandshakeState,
+) -> Result<(), NoiseError>
+where
+    C: AsyncRead + Unpin,
+{
+    let len = conn.read_u16().await? as usize;
+    if len > MAX_HANDSHAKE_MESSAGE_LEN {
+        return Err(NoiseError::ProtocolError(snow::Error::Input));
+    }
+    let mut message = [0u8; MAX_HANDSHAKE_MESSAGE_LEN];
+    conn.read_exact(&mut message[..len]).await?;
+
+    let mut payload = [0u8; MAX_HANDSHAKE_MESSAGE_LEN];
+    handshake.read_message(&message[..len], &mut payload)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use std::sync::Arc;
+    use tokio::io::duplex;
+
+    #[tokio::test]
+    async fn noise_link_is_established_and_carries_data_both_ways() {
+        let initiator_keys = encryption::KeyPair::new(&mut OsRng);
+        let responder_keys = encryption::KeyPair::new(&mut OsRng);
+
+        let (initiator_conn, responder_conn) = duplex(1024);
+
+        let responder_private =
+            encryption::PrivateKey::from_bytes(&responder_keys.private_key().to_bytes()).unwrap();
+        let responder = tokio::spawn(async move {
+            let (mut stream, remote_key) = responder_handshake(responder_conn, &responder_private)
+                .await
+                .unwrap();
+
+            // large enough to be split into multiple noise messages
+            let mut received = vec![0u8; 100_000];
+            stream.read_exact(&mut received).await.unwrap();
+            stream.write_all(b"pong").await.unwrap();
+            stream.flush().await.unwrap();
+            (received, remote_key)
+        });
+
+        let mut stream = initiator_handshake(
+            initiator_conn,
+            initiator_keys.private_key(),
+            responder_keys.public_key(),
+        )
+        .await
+        .unwrap();
+
+        let sent = (0..100_000).map(|i| i as u8).collect::<Vec<_>>();
+        stream.write_all(&sent).await.unwrap();
+        stream.flush().await.unwrap();
+
+        let mut response = [0u8; 4];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(&response, b"pong");
+
+        let (received, remote_key) = responder.await.unwrap();
+        assert_eq!(received, sent);
+        assert_eq!(&remote_key, initiator_keys.public_key());
+    }
+
+    #[tokio::test]
+    async fn handshake_fails_with_wrong_responder_key() {
+        let initiator_keys = encryption::KeyPair::new(&mut OsRng);
+        let responder_keys = encryption::KeyPair::new(&mut OsRng);
+        let unrelated_keys = encryption::KeyPair::new(&mut OsRng);
+
+        let (initiator_conn, responder_conn) = duplex(1024);
+
+        let responder_private =
+            encryption::PrivateKey::from_bytes(&responder_keys.private_key().to_bytes()).unwrap();
+        let responder = tokio::spawn(async move {
+            responder_handshake(responder_conn, &responder_private)
+                .await
+                .is_ok()
+        });
+
+        let initiator_res = initiator_handshake(
+            initiator_conn,
+            initiator_keys.private_key(),
+            unrelated_keys.public_key(),
+        )
+        .await;
+
+        assert!(initiator_res.is_err());
+        assert!(!responder.await.unwrap());
+    }
+
+    #[test]
+    fn unsupported_peers_are_skipped_until_next_update() {
+        let key = *encryption::KeyPair::new(&mut OsRng).public_key();
+        let address: SocketAddr = "1.2.3.4:1789".parse().unwrap();
+
+        let peer_keys = NoisePeerKeys::new();
+        peer_keys.update([(address, key)].into_iter().collect());
+
+        peer_keys.mark_unsupported(&address);
+        assert!(peer_keys.get(&address).is_none());
+        // the node might still initiate the handshake on its own
+        assert!(peer_keys.is_known(&key));
+
+        peer_keys.update([(address, key)].into_iter().collect());
+        assert_eq!(peer_keys.get(&address), Some(key));
+    }
+
+    #[tokio::test]
+    async fn plaintext_links_are_accepted_unless_required() {
+        let keys = Arc::new(encryption::KeyPair::new(&mut OsRng));
+
+        for require_encryption in [false, true] {
+            let config = NoiseConfig::new(Arc::clone(&keys), NoisePeerKeys::new())
+                .with_required_encryption(require_encryption);
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+
+            let client = tokio::spawn(async move {
+                let mut conn = TcpStream::connect(address).await.unwrap();
+                // first byte of a versioned framed packet
+                conn.write_all(&[7, 1, 0]).await.unwrap();
+                conn
+            });
+
+            let (conn, _) = listener.accept().await.unwrap();
+            let _client_conn = client.await.unwrap();
+
+            let res = upgrade_noise_responder(conn, &config).await;
+            if require_encryption {
+                assert!(matches!(res, Err(NoiseError::PlaintextLinkRefused)));
+            } else {
+                assert!(!res.unwrap().is_encrypted());
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{MAX_NOISE_MESSAGE_LEN, NOISE_TAG_LEN};
+use bytes::{Buf, BytesMut};
+use snow::TransportState;
+use std::cmp::min;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+const MAX_PLAINTEXT_LEN: usize = MAX_NOISE_MESSAGE_LEN - NOISE_TAG_LEN;
+const LENGTH_PREFIX_LEN: usize = 2;
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+// once we accumulate that much ciphertext, we won't accept any new data before it's written
+const MAX_PENDING_WRITE: usize = 4 * MAX_NOISE_MESSAGE_LEN;
+
+/// Encrypted stream established after completing the noise handshake.
+///
+/// Each transport message is prefixed with its length encoded as big endian u16.
+pub struct NoiseStream<C> {
+    inner: C,
+    transport: TransportState,
+
+    // raw bytes received from the underlying connection
+    read_buf: BytesMut,
+
+    // decrypted bytes not yet consumed by the reader
+    plaintext: BytesMut,
+
+    // encrypted messages not yet written to the underlying connection
+    write_buf: BytesMut,
+}
+
+impl<C> NoiseStream<C> {
+    pub(crate) fn new(inner: C, transport: TransportState) -> Self {
+        NoiseStream {
+            inner,
+            transport,
+            read_buf: BytesMut::new(),
+            plaintext: BytesMut::new(),
+            write_buf: BytesMut::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn remote_static_key(&self) -> Option<&[u8]> {
+        self.transport.get_remote_static()
+    }
+
+    // attempts to decrypt the next complete message from the read buffer
+    fn decrypt_next_message(&mut self) -> io::Result<bool> {
+        if self.read_buf.len() < LENGTH_PREFIX_LEN {
+            return Ok(false);
+        }
+        let len = u16::from_be_bytes([self.read_buf[0], self.read_buf[1]]) as usize;
+        if self.read_buf.len() < LENGTH_PREFIX_LEN + len {
+            self.read_buf
+                .reserve(LENGTH_PREFIX_LEN + len - self.read_buf.len());
+            return Ok(false);
+        }
+
+        self.read_buf.advance(LENGTH_PREFIX_LEN);
+        let ciphertext = self.read_buf.split_to(len);
+
+        let start = self.plaintext.len();
+        self.plaintext.resize(start + len, 0);
+        let plaintext_len = self
+            .transport
+            .read_message(&ciphertext, &mut self.plaintext[start..])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.plaintext.truncate(start + plaintext_len);
+
+        Ok(true)
+    }
+
+    fn encrypt_message(&mut self, plaintext: &[u8]) -> io::Result<()> {
+        let start = self.write_buf.len();
+        self.write_buf.resize(
+            start + LENGTH_PREFIX_LEN + plaintext.len() + NOISE_TAG_LEN,
+            0,
+        );
+        let len = self
+            .transport
+            .write_message(plaintext, &mut self.write_buf[start + LENGTH_PREFIX_LEN..])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        // this can't fail as we never encrypt more than `MAX_PLAINTEXT_LEN` bytes at once
+        let len_prefix = (len as u16).to_be_bytes();
+        self.write_buf[start..start + LENGTH_PREFIX_LEN].copy_from_slice(&len_prefix);
+        self.write_buf.truncate(start + LENGTH_PREFIX_LEN + len);
+        Ok(())
+    }
+}
+
+impl<C> NoiseStream<C>
+where
+    C: AsyncWrite + Unpin,
+{
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.write_buf.is_empty() {
+            let written = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.write_buf))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.write_buf.advance(written);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<C> AsyncRead for NoiseStream<C>
+where
+    C: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if !this.plaintext.is_empty() {
+                let n = min(buf.remaining(), this.plaintext.len());
+                buf.put_slice(&this.plaintext.split_to(n));
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.decrypt_next_message()? {
+                continue;
+            }
+
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut chunk_buf))?;
+
+            let received = chunk_buf.filled();
+            if received.is_empty() {
+                // the connection got closed
+                return if this.read_buf.is_empty() {
+                    Poll::Ready(Ok(()))
+                } else {
+                    Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()))
+                };
+            }
+            this.read_buf.extend_from_slice(received);
+        }
+    }
+}
+
+impl<C> AsyncWrite for NoiseStream<C>
+where
+    C: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let this = self.get_mut();
+        if this.write_buf.len() >= MAX_PENDING_WRITE {
+            ready!(this.poll_write_pending(cx))?;
+        }
+
+        let n = min(buf.len(), MAX_PLAINTEXT_LEN);
+        this.encrypt_message(&buf[..n])?;
+
+        // opportunistically push the data through, we'll try again on flush if it's not possible
+        if let Poll::Ready(Err(err)) = this.poll_write_pending(cx) {
+            return Poll::Ready(Err(err));
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_pending(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_pending(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
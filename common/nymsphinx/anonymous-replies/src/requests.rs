@@ -129,6 +129,12 @@ impl Display for RepliableMessage {
                     self.sender_tag,
                 )
             }
+            RepliableMessageContent::GatewayChange { reply_surbs } => write!(
+                f,
+                "repliable gateway change message ({} reply surbs attached) from {}",
+                reply_surbs.len(),
+                self.sender_tag,
+            ),
         }
     }
 }
@@ -158,6 +164,13 @@ impl RepliableMessage {
         }
     }
 
+    pub fn new_gateway_change(sender_tag: AnonymousSenderTag, reply_surbs: Vec<ReplySurb>) -> Self {
+        RepliableMessage {
+            sender_tag,
+            content: RepliableMessageContent::GatewayChange { reply_surbs },
+        }
+    }
+
     pub fn into_bytes(self) -> Vec<u8> {
         let content_tag = self.content.tag();
 
@@ -230,6 +243,7 @@ enum RepliableMessageContentTag {
     Data = 0,
     AdditionalSurbs = 1,
     Heartbeat = 2,
+    GatewayChange = 3,
 }
 
 impl TryFrom<u8> for RepliableMessageContentTag {
@@ -242,6 +256,9 @@ impl TryFrom<u8> for RepliableMessageContentTag {
                 Ok(Self::AdditionalSurbs)
             }
             _ if value == (RepliableMessageContentTag::Heartbeat as u8) => Ok(Self::Heartbeat),
+            _ if value == (RepliableMessageContentTag::GatewayChange as u8) => {
+                Ok(Self::GatewayChange)
+            }
             val => Err(InvalidReplyRequestError::InvalidRepliableContentTag { received: val }),
         }
     }
@@ -260,6 +277,12 @@ pub enum RepliableMessageContent {
     Heartbeat {
         additional_reply_surbs: Vec<ReplySurb>,
     },
+    /// Sent once the original sender has switched to a different gateway.
+    /// Any reply surbs received from it before are no longer usable and should be replaced
+    /// with the attached ones.
+    GatewayChange {
+        reply_surbs: Vec<ReplySurb>,
+    },
 }
 
 impl RepliableMessageContent {
@@ -302,6 +325,15 @@ impl RepliableMessageContent {
                     )
                     .collect()
             }
+            RepliableMessageContent::GatewayChange { reply_surbs } => {
+                let num_surbs = reply_surbs.len() as u32;
+
+                num_surbs
+                    .to_be_bytes()
+                    .into_iter()
+                    .chain(reply_surbs.into_iter().flat_map(|s| s.to_bytes()))
+                    .collect()
+            }
         }
     }
 
@@ -327,6 +359,9 @@ impl RepliableMessageContent {
             RepliableMessageContentTag::Heartbeat => Ok(RepliableMessageContent::Heartbeat {
                 additional_reply_surbs: reply_surbs,
             }),
+            RepliableMessageContentTag::GatewayChange => {
+                Ok(RepliableMessageContent::GatewayChange { reply_surbs })
+            }
         }
     }
 
@@ -337,6 +372,9 @@ impl RepliableMessageContent {
                 RepliableMessageContentTag::AdditionalSurbs
             }
             RepliableMessageContent::Heartbeat { .. } => RepliableMessageContentTag::Heartbeat,
+            RepliableMessageContent::GatewayChange { .. } => {
+                RepliableMessageContentTag::GatewayChange
+            }
         }
     }
 
@@ -362,6 +400,10 @@ impl RepliableMessageContent {
                 num_reply_surbs_tag
                     + additional_reply_surbs.len() * ReplySurb::serialized_len(num_mix_hops)
             }
+            RepliableMessageContent::GatewayChange { reply_surbs } => {
+                let num_reply_surbs_tag = mem::size_of::<u32>();
+                num_reply_surbs_tag + reply_surbs.len() * ReplySurb::serialized_len(num_mix_hops)
+            }
         }
     }
 }
@@ -649,6 +691,16 @@ mod tests {
             }
         }
 
+        pub(super) fn repliable_content_gateway_change(
+            rng: &mut ChaCha20Rng,
+            num_mix_hops: u8,
+            surbs: usize,
+        ) -> RepliableMessageContent {
+            RepliableMessageContent::GatewayChange {
+                reply_surbs: reply_surbs(rng, num_mix_hops, surbs),
+            }
+        }
+
         pub(super) fn reply_content_data(
             rng: &mut ChaCha20Rng,
             msg_len: usize,
@@ -740,6 +792,37 @@ mod tests {
                 heartbeat2.serialized_size(num_mix_hops),
                 heartbeat2.into_bytes().len()
             );
+
+            let gateway_change = RepliableMessage {
+                sender_tag: fixtures::sender_tag(&mut rng),
+                content: fixtures::repliable_content_gateway_change(&mut rng, num_mix_hops, 100),
+            };
+            assert_eq!(
+                gateway_change.serialized_size(num_mix_hops),
+                gateway_change.into_bytes().len()
+            );
+        }
+
+        #[test]
+        fn gateway_change_survives_serialization_roundtrip() {
+            let mut rng = fixtures::test_rng();
+            let num_mix_hops = 3;
+
+            let sender_tag = fixtures::sender_tag(&mut rng);
+            let gateway_change = RepliableMessage {
+                sender_tag,
+                content: fixtures::repliable_content_gateway_change(&mut rng, num_mix_hops, 10),
+            };
+
+            let bytes = gateway_change.into_bytes();
+            let recovered = RepliableMessage::try_from_bytes(&bytes, num_mix_hops).unwrap();
+            assert_eq!(recovered.sender_tag, sender_tag);
+            match recovered.content {
+                RepliableMessageContent::GatewayChange { reply_surbs } => {
+                    assert_eq!(reply_surbs.len(), 10)
+                }
+                _ => panic!("unexpected content"),
+            }
         }
     }
 
@@ -793,6 +876,13 @@ mod tests {
                 heartbeat2.serialized_size(num_mix_hops),
                 heartbeat2.into_bytes().len()
             );
+
+            let gateway_change =
+                fixtures::repliable_content_gateway_change(&mut rng, num_mix_hops, 100);
+            assert_eq!(
+                gateway_change.serialized_size(num_mix_hops),
+                gateway_change.into_bytes().len()
+            );
         }
     }
 
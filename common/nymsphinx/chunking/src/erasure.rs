@@ -0,0 +1,270 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::fragment::{erasure_coded_fragment_payload_max_len, ErasureCodingInfo, Fragment};
+use crate::set::FragmentSet;
+use crate::ChunkingError;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+// Note: erasure coding is only ever applied to messages fitting in a single, unlinked, set.
+// Protecting linked sets would require every single set to be recoverable independently
+// and, more importantly, messages of that size are better served by the retransmission mechanism.
+
+/// Returns number of data fragments the message would be split to if it was erasure coded.
+/// Note that the returned value does not include the parity fragments.
+pub fn number_of_erasure_coded_data_fragments(
+    message_len: usize,
+    max_plaintext_size: usize,
+) -> usize {
+    let max_payload = erasure_coded_fragment_payload_max_len(max_plaintext_size);
+    let pre_casted_frags = (message_len as f64 / max_payload as f64).ceil() as usize;
+    usize::max(1, pre_casted_frags)
+}
+
+/// Checks whether message of given length can be put in a single erasure coded set
+/// alongside the specified number of parity fragments.
+pub fn can_be_erasure_coded(
+    message_len: usize,
+    max_plaintext_size: usize,
+    parity_fragments: u8,
+) -> bool {
+    parity_fragments > 0
+        && erasure_coded_fragment_payload_max_len(max_plaintext_size) <= u16::MAX as usize
+        && number_of_erasure_coded_data_fragments(message_len, max_plaintext_size)
+            + parity_fragments as usize
+            <= u8::MAX as usize
+}
+
+fn erasure_coder(
+    data_fragments: usize,
+    parity_fragments: usize,
+) -> Result<ReedSolomon, ChunkingError> {
+    ReedSolomon::new(data_fragments, parity_fragments).map_err(|err| {
+        ChunkingError::ErasureCodingFailure {
+            reason: err.to_string(),
+        }
+    })
+}
+
+/// Splits underlying message into multiple equally sized data `Fragment`s and extends them with
+/// `parity_fragments` Reed-Solomon parity `Fragment`s, so that the whole message could be recovered
+/// from any subset of the `Fragment`s that is at least as big as the number of data fragments.
+pub(crate) fn prepare_erasure_coded_set(
+    message: &[u8],
+    id: i32,
+    parity_fragments: u8,
+    max_plaintext_size: usize,
+) -> Result<FragmentSet, ChunkingError> {
+    if !can_be_erasure_coded(message.len(), max_plaintext_size, parity_fragments) {
+        return Err(ChunkingError::ErasureCodingFailure {
+            reason: format!(
+                "message of length {} can't be erasure coded with {parity_fragments} parity fragments",
+                message.len()
+            ),
+        });
+    }
+
+    let fragment_len = erasure_coded_fragment_payload_max_len(max_plaintext_size);
+    let data_fragments = number_of_erasure_coded_data_fragments(message.len(), max_plaintext_size);
+    let total_fragments = data_fragments + parity_fragments as usize;
+
+    // all shards must have identical length, so the final data fragment is zero-padded
+    let mut shards = Vec::with_capacity(total_fragments);
+    for i in 0..data_fragments {
+        let lb = usize::min(message.len(), i * fragment_len);
+        let ub = usize::min(message.len(), (i + 1) * fragment_len);
+        let mut shard = message[lb..ub].to_vec();
+        shard.resize(fragment_len, 0);
+        shards.push(shard);
+    }
+    shards.resize(total_fragments, vec![0; fragment_len]);
+
+    erasure_coder(data_fragments, parity_fragments as usize)?
+        .encode(&mut shards)
+        .map_err(|err| ChunkingError::ErasureCodingFailure {
+            reason: err.to_string(),
+        })?;
+
+    // the cast is fine as we have checked the fragment length fits in u16
+    let final_data_fragment_len = (message.len() - (data_fragments - 1) * fragment_len) as u16;
+    let erasure_coding = ErasureCodingInfo::new(data_fragments as u8, final_data_fragment_len);
+
+    shards
+        .iter()
+        .enumerate()
+        .map(|(i, shard)| {
+            Fragment::try_new_erasure_coded(
+                shard,
+                id,
+                total_fragments as u8,
+                (i + 1) as u8,
+                erasure_coding,
+                max_plaintext_size,
+            )
+        })
+        .collect()
+}
+
+/// Given (possibly incomplete) ordered `Fragment`s of an erasure coded set, attempts to recover
+/// the original data. It requires at least `data_fragments` of the `Fragment`s to be present.
+pub(crate) fn reconstruct_erasure_coded_set(
+    fragments: &[Option<Fragment>],
+    erasure_coding: ErasureCodingInfo,
+) -> Result<Vec<u8>, ChunkingError> {
+    let data_fragments = erasure_coding.data_fragments() as usize;
+    if data_fragments >= fragments.len() {
+        return Err(ChunkingError::MalformedHeaderError);
+    }
+
+    let mut shards: Vec<_> = fragments
+        .iter()
+        .map(|fragment| fragment.as_ref().map(|frag| frag.payload().to_vec()))
+        .collect();
+
+    let fragment_len = shards
+        .iter()
+        .flatten()
+        .map(|shard| shard.len())
+        .next()
+        .unwrap_or_default();
+    let final_data_fragment_len = erasure_coding.final_data_fragment_len() as usize;
+    if final_data_fragment_len > fragment_len {
+        return Err(ChunkingError::ErasureCodingFailure {
+            reason: format!(
+                "final data fragment length ({final_data_fragment_len}) is greater than the fragment length ({fragment_len})"
+            ),
+        });
+    }
+
+    erasure_coder(data_fragments, fragments.len() - data_fragments)?
+        .reconstruct_data(&mut shards)
+        .map_err(|err| ChunkingError::ErasureCodingFailure {
+            reason: err.to_string(),
+        })?;
+
+    let mut data: Vec<_> = shards
+        .into_iter()
+        .take(data_fragments)
+        .flatten()
+        .flatten()
+        .collect();
+    data.truncate((data_fragments - 1) * fragment_len + final_data_fragment_len);
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, RngCore};
+
+    // just some arbitrary value to use in tests
+    const AVAILABLE_PLAINTEXT_SIZE: usize = 1024;
+
+    fn random_message(len: usize) -> Vec<u8> {
+        let mut message = vec![0u8; len];
+        thread_rng().fill_bytes(&mut message);
+        message
+    }
+
+    fn into_received(set: FragmentSet) -> Vec<Option<Fragment>> {
+        set.into_iter().map(Some).collect()
+    }
+
+    #[test]
+    fn determining_whether_message_can_be_erasure_coded() {
+        let max_payload = erasure_coded_fragment_payload_max_len(AVAILABLE_PLAINTEXT_SIZE);
+
+        assert!(!can_be_erasure_coded(42, AVAILABLE_PLAINTEXT_SIZE, 0));
+        assert!(can_be_erasure_coded(42, AVAILABLE_PLAINTEXT_SIZE, 1));
+        assert!(can_be_erasure_coded(
+            max_payload * 250,
+            AVAILABLE_PLAINTEXT_SIZE,
+            5
+        ));
+        assert!(!can_be_erasure_coded(
+            max_payload * 250 + 1,
+            AVAILABLE_PLAINTEXT_SIZE,
+            5
+        ));
+    }
+
+    #[test]
+    fn set_contains_expected_number_of_fragments() {
+        let max_payload = erasure_coded_fragment_payload_max_len(AVAILABLE_PLAINTEXT_SIZE);
+        let message = random_message(max_payload * 3 + 42);
+
+        let set = prepare_erasure_coded_set(&message, 12345, 2, AVAILABLE_PLAINTEXT_SIZE).unwrap();
+        assert_eq!(set.len(), 6);
+        for (i, fragment) in set.iter().enumerate() {
+            assert_eq!(fragment.total_fragments(), 6);
+            assert_eq!(fragment.current_fragment() as usize, i + 1);
+            assert_eq!(
+                fragment.erasure_coding(),
+                Some(ErasureCodingInfo::new(4, 42))
+            );
+        }
+    }
+
+    #[test]
+    fn reconstructs_data_from_complete_set() {
+        let message = random_message(3000);
+        let set = prepare_erasure_coded_set(&message, 12345, 3, AVAILABLE_PLAINTEXT_SIZE).unwrap();
+        let erasure_coding = set[0].erasure_coding().unwrap();
+
+        let reconstructed =
+            reconstruct_erasure_coded_set(&into_received(set), erasure_coding).unwrap();
+        assert_eq!(reconstructed, message);
+    }
+
+    #[test]
+    fn reconstructs_data_with_missing_fragments() {
+        let message = random_message(5000);
+        let set = prepare_erasure_coded_set(&message, 12345, 3, AVAILABLE_PLAINTEXT_SIZE).unwrap();
+        let erasure_coding = set[0].erasure_coding().unwrap();
+
+        // lose data fragments
+        let mut received = into_received(set.clone());
+        received[0] = None;
+        received[2] = None;
+        received[4] = None;
+        let reconstructed = reconstruct_erasure_coded_set(&received, erasure_coding).unwrap();
+        assert_eq!(reconstructed, message);
+
+        // lose parity fragments
+        let mut received = into_received(set);
+        let total = received.len();
+        received[total - 1] = None;
+        received[total - 2] = None;
+        let reconstructed = reconstruct_erasure_coded_set(&received, erasure_coding).unwrap();
+        assert_eq!(reconstructed, message);
+    }
+
+    #[test]
+    fn reconstructs_empty_and_single_fragment_messages() {
+        for len in [0, 1, 100] {
+            let message = random_message(len);
+            let set =
+                prepare_erasure_coded_set(&message, 12345, 1, AVAILABLE_PLAINTEXT_SIZE).unwrap();
+            assert_eq!(set.len(), 2);
+            let erasure_coding = set[0].erasure_coding().unwrap();
+
+            let mut received = into_received(set);
+            received[0] = None;
+            let reconstructed = reconstruct_erasure_coded_set(&received, erasure_coding).unwrap();
+            assert_eq!(reconstructed, message);
+        }
+    }
+
+    #[test]
+    fn reconstruction_fails_with_insufficient_fragments() {
+        let message = random_message(5000);
+        let set = prepare_erasure_coded_set(&message, 12345, 2, AVAILABLE_PLAINTEXT_SIZE).unwrap();
+        let erasure_coding = set[0].erasure_coding().unwrap();
+
+        let mut received = into_received(set);
+        received[0] = None;
+        received[1] = None;
+        received[2] = None;
+        assert!(reconstruct_erasure_coded_set(&received, erasure_coding).is_err());
+    }
+}
@@ -34,6 +34,17 @@ pub const UNLINKED_FRAGMENTED_HEADER_LEN: usize = 7;
 /// `Fragment` in a `FragmentSet`.
 pub const LINKED_FRAGMENTED_HEADER_LEN: usize = 10;
 
+/// Erasure coded `Fragment`s use the same first 6 bytes as the unlinked ones, however, instead of
+/// the byte indicating lack of linking, they have a marker byte followed by the number of data
+/// fragments in the set and two bytes representing length of the final data fragment,
+/// so that the original data could be recovered from any sufficiently large subset of the set.
+pub const ERASURE_CODED_FRAGMENTED_HEADER_LEN: usize = 10;
+
+/// Value of the 7th byte of the header indicating the `Fragment` is erasure coded. Note that
+/// any value in the range [1, 127] would have previously been rejected as it would represent
+/// a linked fragment without the linking flag set.
+const ERASURE_CODED_MARKER: u8 = 1;
+
 /// Maximum size of payload of each fragment is always the maximum amount of plaintext data
 /// we can put into a sphinx packet minus length of respective fragment header.
 pub const fn unlinked_fragment_payload_max_len(max_plaintext_size: usize) -> usize {
@@ -46,6 +57,42 @@ pub const fn linked_fragment_payload_max_len(max_plaintext_size: usize) -> usize
     max_plaintext_size - LINKED_FRAGMENTED_HEADER_LEN
 }
 
+/// Since all fragments in an erasure coded set have to be of equal length, the maximum size of
+/// each payload is the maximum amount of plaintext data we can put into a sphinx packet minus
+/// the length of the erasure coded header.
+pub const fn erasure_coded_fragment_payload_max_len(max_plaintext_size: usize) -> usize {
+    max_plaintext_size - ERASURE_CODED_FRAGMENTED_HEADER_LEN
+}
+
+/// Additional metadata attached to each `Fragment` in a set protected with Reed-Solomon
+/// erasure coding. The first `data_fragments` `Fragment`s in the set contain the original data
+/// whilst the remaining ones contain the parity information.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ErasureCodingInfo {
+    data_fragments: u8,
+    final_data_fragment_len: u16,
+}
+
+impl ErasureCodingInfo {
+    pub(crate) fn new(data_fragments: u8, final_data_fragment_len: u16) -> Self {
+        ErasureCodingInfo {
+            data_fragments,
+            final_data_fragment_len,
+        }
+    }
+
+    /// Number of `Fragment`s required in order to reconstruct the whole set.
+    pub fn data_fragments(&self) -> u8 {
+        self.data_fragments
+    }
+
+    /// Length of the actual data in the final data `Fragment` as opposed to the zero padding
+    /// required to make all fragments of equal length.
+    pub fn final_data_fragment_len(&self) -> u16 {
+        self.final_data_fragment_len
+    }
+}
+
 // TODO: should this be defined in this module or in `cover`? I can see arguments for both options...
 /// A special `FragmentIdentifier` that is not valid in all cases unless if it's used in a loop
 /// cover message.
@@ -191,6 +238,38 @@ impl Fragment {
         })
     }
 
+    /// Tries to encapsulate provided payload slice and metadata into an erasure coded `Fragment`.
+    /// Unlike regular `Fragment`s, each payload in the set must have exactly the same length
+    /// as otherwise the set could not be reconstructed.
+    pub(crate) fn try_new_erasure_coded(
+        payload: &[u8],
+        id: i32,
+        total_fragments: u8,
+        current_fragment: u8,
+        erasure_coding: ErasureCodingInfo,
+        max_plaintext_size: usize,
+    ) -> Result<Self, ChunkingError> {
+        let header = FragmentHeader::try_new_erasure_coded(
+            id,
+            total_fragments,
+            current_fragment,
+            erasure_coding,
+        )?;
+
+        let expected_len = erasure_coded_fragment_payload_max_len(max_plaintext_size);
+        if payload.len() != expected_len {
+            return Err(ChunkingError::InvalidPayloadLengthError {
+                received: payload.len(),
+                expected: expected_len,
+            });
+        }
+
+        Ok(Fragment {
+            header,
+            payload: payload.to_vec(),
+        })
+    }
+
     /// based on the size of the embedded data, determines which predefined `PacketSize`
     /// was used for construction of this `Fragment`
     pub fn serialized_size(&self) -> usize {
@@ -247,6 +326,17 @@ impl Fragment {
         self.header.next_fragments_set_id
     }
 
+    /// Extracts information regarding erasure coding of the `FragmentSet`, if it was used.
+    pub fn erasure_coding(&self) -> Option<ErasureCodingInfo> {
+        self.header.erasure_coding
+    }
+
+    /// Gets the payload (i.e. part of original message or the parity data) associated with this
+    /// `Fragment`.
+    pub(crate) fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
     /// Consumes `self` to obtain payload (i.e. part of original message) associated with this
     /// `Fragment`.
     pub(crate) fn extract_payload(self) -> Vec<u8> {
@@ -292,6 +382,11 @@ impl Fragment {
 /// where the set is linked to either preceding data (TF == 1) or proceeding data (TF == CF == 255)
 /// '1'bit || 31-bit ID || 1-byte TF || 1 byte CF || '1'bit || 31-bit LID
 ///
+/// 10 byte sequence representing a fragment in an unlinked set protected with erasure coding,
+/// where TF includes the parity fragments, DF is the number of data fragments
+/// and FL is the length of the final data fragment:
+/// '1'bit || 31-bit ID || 1-byte TF || 1 byte CF || 0x01 byte || 1-byte DF || 2-byte FL
+///
 /// And hence for messages larger than `max_plaintext_size` but small enough
 /// to avoid set division (which happens if message has to be fragmented into more than 255 fragments)
 /// there is 7 bytes of overhead inside each sphinx packet sent
@@ -321,6 +416,10 @@ pub(crate) struct FragmentHeader {
     /// Optional ID of next `FragmentSet` into which the original message was split.
     /// Note, this option is only valid of `current_fragment == total_fragments == u8::max_value()`
     next_fragments_set_id: Option<i32>,
+
+    /// Optional erasure coding metadata of the `FragmentSet`.
+    /// Note, this option is only valid for unlinked sets
+    erasure_coding: Option<ErasureCodingInfo>,
 }
 
 impl FragmentHeader {
@@ -363,9 +462,28 @@ impl FragmentHeader {
             current_fragment,
             previous_fragments_set_id,
             next_fragments_set_id,
+            erasure_coding: None,
         })
     }
 
+    /// Tries to create a new erasure coded `FragmentHeader` using provided metadata.
+    /// On top of the regular checks, it's also verified that the set contains at least a single
+    /// data and a single parity fragment.
+    fn try_new_erasure_coded(
+        id: i32,
+        total_fragments: u8,
+        current_fragment: u8,
+        erasure_coding: ErasureCodingInfo,
+    ) -> Result<Self, ChunkingError> {
+        let mut header = Self::try_new(id, total_fragments, current_fragment, None, None)?;
+        if erasure_coding.data_fragments == 0 || erasure_coding.data_fragments >= total_fragments {
+            return Err(ChunkingError::MalformedHeaderError);
+        }
+
+        header.erasure_coding = Some(erasure_coding);
+        Ok(header)
+    }
+
     /// Tries to recover `FragmentHeader` from slice of bytes extracted from received sphinx packet.
     /// If successful, returns `Self` and number of bytes used, as those can differ based on the
     /// type of header (unlinked or linked).
@@ -391,6 +509,24 @@ impl FragmentHeader {
             return Err(ChunkingError::MalformedHeaderError);
         }
 
+        if b[6] == ERASURE_CODED_MARKER {
+            if b.len() < ERASURE_CODED_FRAGMENTED_HEADER_LEN {
+                return Err(ChunkingError::TooShortFragmentHeader {
+                    received: b.len(),
+                    expected: ERASURE_CODED_FRAGMENTED_HEADER_LEN,
+                });
+            }
+            let erasure_coding = ErasureCodingInfo {
+                data_fragments: b[7],
+                final_data_fragment_len: u16::from_be_bytes([b[8], b[9]]),
+            };
+
+            return Ok((
+                Self::try_new_erasure_coded(id, total_fragments, current_fragment, erasure_coding)?,
+                ERASURE_CODED_FRAGMENTED_HEADER_LEN,
+            ));
+        }
+
         let mut previous_fragments_set_id = None;
         let mut next_fragments_set_id = None;
 
@@ -446,6 +582,14 @@ impl FragmentHeader {
             .chain(std::iter::once(self.total_fragments))
             .chain(std::iter::once(self.current_fragment));
 
+        if let Some(erasure_coding) = self.erasure_coding {
+            return bytes_prefix_iter
+                .chain(std::iter::once(ERASURE_CODED_MARKER))
+                .chain(std::iter::once(erasure_coding.data_fragments))
+                .chain(erasure_coding.final_data_fragment_len.to_be_bytes())
+                .collect();
+        }
+
         let is_linked =
             self.previous_fragments_set_id.is_some() || self.next_fragments_set_id.is_some();
         if is_linked {
@@ -1027,6 +1171,7 @@ mod fragment_header {
                 current_fragment: 11,
                 previous_fragments_set_id: None,
                 next_fragments_set_id: None,
+                erasure_coding: None,
             };
             let header_bytes = header.to_bytes();
             assert!(FragmentHeader::try_from_bytes(&header_bytes).is_err());
@@ -1041,6 +1186,7 @@ mod fragment_header {
                 current_fragment: 0,
                 previous_fragments_set_id: None,
                 next_fragments_set_id: None,
+                erasure_coding: None,
             };
             let header_bytes = header.to_bytes();
             assert!(FragmentHeader::try_from_bytes(&header_bytes).is_err());
@@ -1142,4 +1288,96 @@ mod fragment_header {
             assert_eq!(LINKED_FRAGMENTED_HEADER_LEN, bytes_used);
         }
     }
+
+    #[cfg(test)]
+    mod erasure_coded_fragmented_payload {
+        use super::*;
+
+        #[test]
+        fn can_be_converted_to_and_from_bytes_for_exact_number_of_bytes_provided() {
+            let fragmented_header =
+                FragmentHeader::try_new_erasure_coded(12345, 10, 5, ErasureCodingInfo::new(8, 42))
+                    .unwrap();
+
+            let header_bytes = fragmented_header.to_bytes();
+            let (recovered_header, bytes_used) =
+                FragmentHeader::try_from_bytes(&header_bytes).unwrap();
+            assert_eq!(fragmented_header, recovered_header);
+            assert_eq!(ERASURE_CODED_FRAGMENTED_HEADER_LEN, bytes_used);
+        }
+
+        #[test]
+        fn can_be_converted_to_and_from_bytes_for_more_than_required_number_of_bytes() {
+            let fragmented_header = FragmentHeader::try_new_erasure_coded(
+                12345,
+                u8::MAX,
+                u8::MAX,
+                ErasureCodingInfo::new(200, 1234),
+            )
+            .unwrap();
+
+            let mut header_bytes = fragmented_header.to_bytes();
+            header_bytes.append(vec![1, 2, 3, 4, 5].as_mut());
+
+            let (recovered_header, bytes_used) =
+                FragmentHeader::try_from_bytes(&header_bytes).unwrap();
+            assert_eq!(fragmented_header, recovered_header);
+            assert_eq!(ERASURE_CODED_FRAGMENTED_HEADER_LEN, bytes_used);
+        }
+
+        #[test]
+        fn retrieval_from_bytes_fail_for_insufficient_number_of_bytes_provided() {
+            let fragmented_header =
+                FragmentHeader::try_new_erasure_coded(12345, 10, 5, ErasureCodingInfo::new(8, 42))
+                    .unwrap();
+
+            let header_bytes = fragmented_header.to_bytes();
+            let header_bytes = &header_bytes[..header_bytes.len() - 1];
+            assert!(FragmentHeader::try_from_bytes(header_bytes).is_err())
+        }
+
+        #[test]
+        fn creation_of_header_fails_without_data_or_parity_fragments() {
+            assert!(FragmentHeader::try_new_erasure_coded(
+                12345,
+                10,
+                5,
+                ErasureCodingInfo::new(0, 42)
+            )
+            .is_err());
+            assert!(FragmentHeader::try_new_erasure_coded(
+                12345,
+                10,
+                5,
+                ErasureCodingInfo::new(10, 42)
+            )
+            .is_err());
+        }
+
+        #[test]
+        fn fragment_requires_payload_of_exact_length() {
+            let max_plaintext_size = 1024;
+            let info = ErasureCodingInfo::new(8, 42);
+            let payload_len = erasure_coded_fragment_payload_max_len(max_plaintext_size);
+
+            assert!(Fragment::try_new_erasure_coded(
+                &vec![1u8; payload_len],
+                12345,
+                10,
+                10,
+                info,
+                max_plaintext_size
+            )
+            .is_ok());
+            assert!(Fragment::try_new_erasure_coded(
+                &vec![1u8; payload_len - 1],
+                12345,
+                10,
+                10,
+                info,
+                max_plaintext_size
+            )
+            .is_err());
+        }
+    }
 }
@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::fragment::{linked_fragment_payload_max_len, unlinked_fragment_payload_max_len};
-pub use set::split_into_sets;
+pub use erasure::{can_be_erasure_coded, number_of_erasure_coded_data_fragments};
+pub use set::{split_into_sets, split_into_sets_with_parity};
 use thiserror::Error;
 
 pub const MIN_PADDING_OVERHEAD: usize = 1;
@@ -18,6 +19,7 @@ pub const MIN_PADDING_OVERHEAD: usize = 1;
 // they should definitely be revisited.
 // For instance there are not tests for the cases when we are padding the message
 
+pub mod erasure;
 pub mod fragment;
 pub mod reconstruction;
 pub mod set;
@@ -68,6 +70,9 @@ pub enum ChunkingError {
 
     #[error("Received fragment identifier ({received}) is not a valid value!")]
     MalformedFragmentIdentifier { received: i32 },
+
+    #[error("Failed to perform erasure coding on the fragment set: {reason}")]
+    ErasureCodingFailure { reason: String },
 }
 
 /// Returns number of fragments the message will be split to as well as number of available
@@ -1,9 +1,14 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
-use crate::fragment::Fragment;
+use crate::erasure::reconstruct_erasure_coded_set;
+use crate::fragment::{ErasureCodingInfo, Fragment};
 use crate::ChunkingError;
 use log::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
+/// Maximum number of already reconstructed erasure coded sets that are remembered in order to
+/// discard any of their `Fragment`s that might still arrive afterwards.
+const MAX_TRACKED_COMPLETED_SETS: usize = 1024;
 
 // TODO: perhaps a more sophisticated approach with writing to disk periodically in case
 // we're receiving fast & furious in uncompressed 4K - we don't want to keep that in memory;
@@ -31,6 +36,10 @@ struct ReconstructionBuffer {
     /// appropriately resized and all missing fragments are set to a `None`, thus keeping
     /// everything in order the whole time, allowing for O(1) insertions and O(n) reconstruction.
     fragments: Vec<Option<Fragment>>,
+
+    /// If the set was erasure coded, the original data is recovered as soon as sufficient
+    /// number of `Fragment`s is received and it's kept here until the set is consumed.
+    erasure_coded_data: Option<Vec<u8>>,
 }
 
 /// Type alias representing fully reconstructed message - its original data and list of all
@@ -56,6 +65,7 @@ impl ReconstructionBuffer {
             previous_fragments_set_id: None,
             next_fragments_set_id: None,
             fragments: fragments_buffer,
+            erasure_coded_data: None,
         }
     }
 
@@ -66,6 +76,10 @@ impl ReconstructionBuffer {
         // if the set is complete.
         debug_assert!(self.is_complete);
 
+        if let Some(erasure_coded_data) = self.erasure_coded_data {
+            return erasure_coded_data;
+        }
+
         self.fragments
             .into_iter()
             .map(|fragment| fragment.unwrap().extract_payload())
@@ -82,6 +96,49 @@ impl ReconstructionBuffer {
         !self.fragments.contains(&None)
     }
 
+    /// For a completed erasure coded set, determines how many of its `Fragment`s might still
+    /// arrive after it has already been reconstructed.
+    fn outstanding_erasure_coded_fragments(&self) -> Option<usize> {
+        if !self.is_complete || self.erasure_coded_data.is_none() {
+            return None;
+        }
+        Some(self.fragments.iter().filter(|f| f.is_none()).count())
+    }
+
+    /// Gets the erasure coding information of the set based on any of the received `Fragment`s.
+    fn erasure_coding(&self) -> Option<ErasureCodingInfo> {
+        self.fragments
+            .iter()
+            .flatten()
+            .next()
+            .and_then(|fragment| fragment.erasure_coding())
+    }
+
+    /// Attempts to recover the data of an erasure coded set. This is only possible if at least
+    /// `data_fragments` `Fragment`s were received.
+    fn try_recover_erasure_coded_data(&mut self, erasure_coding: ErasureCodingInfo) {
+        let received = self.fragments.iter().flatten().count();
+        if received < erasure_coding.data_fragments() as usize {
+            return;
+        }
+
+        match reconstruct_erasure_coded_set(&self.fragments, erasure_coding) {
+            Ok(data) => {
+                self.erasure_coded_data = Some(data);
+                self.is_complete = true;
+            }
+            Err(err) => warn!(
+                "failed to recover erasure coded set {} - {err}",
+                self.fragments
+                    .iter()
+                    .flatten()
+                    .next()
+                    .map(|fragment| fragment.id())
+                    .unwrap_or_default()
+            ),
+        }
+    }
+
     /// Inserts new `Fragment` data into an appropriate position in the buffer.
     ///
     /// (Note: currently there is no defined behaviour for dealing with duplicate
@@ -104,6 +161,17 @@ impl ReconstructionBuffer {
             }
         });
 
+        if let Some(existing_fragment) = self.fragments.iter().flatten().next() {
+            if existing_fragment.erasure_coding() != fragment.erasure_coding() {
+                warn!(
+                    "received fragment with inconsistent erasure coding information! - frag - {} (set id: {})",
+                    fragment.current_fragment(),
+                    fragment.id()
+                );
+                return;
+            }
+        }
+
         let fragment_index = fragment.current_fragment() as usize - 1;
         if self.fragments[fragment_index].is_some() {
             // TODO: what to do in that case? give up on the message? overwrite it? panic?
@@ -116,6 +184,15 @@ impl ReconstructionBuffer {
             );
         }
         self.fragments[fragment_index] = Some(fragment);
+
+        // erasure coded sets are never linked, so there's no need to set the linking ids
+        if let Some(erasure_coding) = self.erasure_coding() {
+            if !self.is_complete {
+                self.try_recover_erasure_coded_data(erasure_coding);
+            }
+            return;
+        }
+
         if self.is_done_receiving() {
             self.is_complete = true;
             self.previous_fragments_set_id = self.fragments[0]
@@ -147,6 +224,15 @@ pub struct MessageReconstructor {
     // maximum sized sets but without one of required fragments. All of the received
     // data will be kept on the heap indefinitely in the current implementation.
     reconstructed_sets: HashMap<i32, ReconstructionBuffer>,
+
+    /// Erasure coded sets that have already been reconstructed alongside the number of their
+    /// `Fragment`s that have not been received yet. Any of those arriving later on are discarded
+    /// rather than being used to create a new buffer that could deliver the message again.
+    completed_erasure_coded_sets: HashMap<i32, usize>,
+
+    /// Order in which the erasure coded sets got completed, so that the oldest entries could be
+    /// forgotten if the remaining `Fragment`s never arrive.
+    completed_erasure_coded_order: VecDeque<i32>,
 }
 
 impl MessageReconstructor {
@@ -263,6 +349,39 @@ impl MessageReconstructor {
         (message_content, set_id_sequence)
     }
 
+    /// Remembers the erasure coded set of given `id` has been reconstructed, so that any of its
+    /// `outstanding` `Fragment`s received later on would get discarded.
+    fn mark_erasure_coded_set_completed(&mut self, set_id: i32, outstanding: usize) {
+        if outstanding == 0 {
+            return;
+        }
+
+        self.completed_erasure_coded_sets
+            .insert(set_id, outstanding);
+        self.completed_erasure_coded_order.push_back(set_id);
+        while self.completed_erasure_coded_order.len() > MAX_TRACKED_COMPLETED_SETS {
+            if let Some(oldest) = self.completed_erasure_coded_order.pop_front() {
+                self.completed_erasure_coded_sets.remove(&oldest);
+            }
+        }
+    }
+
+    /// Checks whether the `Fragment` belongs to an already reconstructed erasure coded set
+    /// and if so, updates the number of its `Fragment`s that might still arrive.
+    fn is_late_erasure_coded_fragment(&mut self, set_id: i32) -> bool {
+        let Some(outstanding) = self.completed_erasure_coded_sets.get_mut(&set_id) else {
+            return false;
+        };
+
+        *outstanding -= 1;
+        if *outstanding == 0 {
+            self.completed_erasure_coded_sets.remove(&set_id);
+            self.completed_erasure_coded_order
+                .retain(|id| *id != set_id);
+        }
+        true
+    }
+
     /// Given recovered `Fragment`, tries to insert it into an appropriate `ReconstructionBuffer`.
     /// If a buffer does not exist, a new instance is created.
     /// If it was last remaining `Fragment` for the original message, the message is reconstructed
@@ -271,13 +390,23 @@ impl MessageReconstructor {
         let set_id = fragment.id();
         let set_len = fragment.total_fragments();
 
+        if self.is_late_erasure_coded_fragment(set_id) {
+            trace!("discarding fragment of already reconstructed erasure coded set {set_id}");
+            return None;
+        }
+
         let buf = self
             .reconstructed_sets
             .entry(set_id)
             .or_insert_with(|| ReconstructionBuffer::new(set_len));
 
         buf.insert_fragment(fragment);
+        let outstanding = buf.outstanding_erasure_coded_fragments();
+
         if self.is_message_fully_received(set_id) {
+            if let Some(outstanding) = outstanding {
+                self.mark_erasure_coded_set_completed(set_id, outstanding);
+            }
             Some(self.reconstruct_message(set_id))
         } else {
             None
@@ -865,6 +994,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: None,
                 next_fragments_set_id: None,
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
 
@@ -875,6 +1005,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: None,
                 next_fragments_set_id: None,
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
 
@@ -1002,6 +1133,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: None,
                 next_fragments_set_id: Some(1234),
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
 
@@ -1012,6 +1144,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: Some(12345),
                 next_fragments_set_id: Some(123),
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
 
@@ -1022,6 +1155,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: Some(1234),
                 next_fragments_set_id: Some(12),
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
 
@@ -1032,6 +1166,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: Some(123),
                 next_fragments_set_id: None,
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
 
@@ -1079,6 +1214,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: None,
                 next_fragments_set_id: Some(1234),
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
         reconstructor.reconstructed_sets.insert(
@@ -1088,6 +1224,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: Some(12345),
                 next_fragments_set_id: None,
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
         assert_eq!(reconstructor.previous_linked_set_id(12345), None);
@@ -1135,6 +1272,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: None,
                 next_fragments_set_id: Some(1234),
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
         reconstructor.reconstructed_sets.insert(
@@ -1144,6 +1282,7 @@ mod message_reconstructor {
                 previous_fragments_set_id: Some(12345),
                 next_fragments_set_id: None,
                 fragments: vec![],
+                erasure_coded_data: None,
             },
         );
         assert_eq!(reconstructor.next_linked_set_id(12345), Some(1234));
@@ -1662,6 +1801,184 @@ mod message_reconstruction {
         }
     }
 
+    #[cfg(test)]
+    mod erasure_coded_set {
+        use super::*;
+
+        fn erasure_coded_fragments(message: &[u8], parity_fragments: u8) -> Vec<Vec<u8>> {
+            crate::split_into_sets_with_parity(
+                &mut rand::rngs::OsRng,
+                message,
+                AVAILABLE_PLAINTEXT_SIZE,
+                parity_fragments,
+            )
+            .into_iter()
+            .flat_map(|fragment_set| fragment_set.into_iter())
+            .map(|x| x.into_bytes())
+            .collect()
+        }
+
+        #[test]
+        fn it_reconstructs_message_from_all_fragments() {
+            let mut rng = thread_rng();
+
+            let mut message = vec![0u8; 10000];
+            rng.fill_bytes(&mut message);
+
+            let mut fragments = erasure_coded_fragments(&message, 5);
+            fragments.shuffle(&mut rng);
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            let mut reconstructed_message = None;
+            for fragment in fragments {
+                let fragment = message_reconstructor.recover_fragment(fragment).unwrap();
+                if let Some(reconstructed) = message_reconstructor.insert_new_fragment(fragment) {
+                    reconstructed_message = Some(reconstructed);
+                    break;
+                }
+            }
+
+            let reconstructed_message = reconstructed_message.unwrap();
+            assert_eq!(reconstructed_message.0, message);
+            assert_eq!(reconstructed_message.1.len(), 1);
+        }
+
+        #[test]
+        fn it_reconstructs_message_with_as_many_lost_fragments_as_parity_fragments() {
+            let mut rng = thread_rng();
+
+            let mut message = vec![0u8; 10000];
+            rng.fill_bytes(&mut message);
+
+            let mut fragments = erasure_coded_fragments(&message, 5);
+            fragments.shuffle(&mut rng);
+            fragments.truncate(fragments.len() - 5);
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            for fragment in fragments.iter().take(fragments.len() - 1) {
+                assert!(message_reconstructor
+                    .insert_new_fragment(
+                        message_reconstructor
+                            .recover_fragment(fragment.clone())
+                            .unwrap()
+                    )
+                    .is_none());
+            }
+
+            let reconstructed_message = message_reconstructor
+                .insert_new_fragment(
+                    message_reconstructor
+                        .recover_fragment(fragments.last().unwrap().clone())
+                        .unwrap(),
+                )
+                .unwrap();
+
+            assert_eq!(reconstructed_message.0, message);
+            assert_eq!(reconstructed_message.1.len(), 1);
+        }
+
+        #[test]
+        fn it_does_not_reconstruct_message_with_more_lost_fragments_than_parity_fragments() {
+            let mut rng = thread_rng();
+
+            let mut message = vec![0u8; 10000];
+            rng.fill_bytes(&mut message);
+
+            let mut fragments = erasure_coded_fragments(&message, 5);
+            fragments.shuffle(&mut rng);
+            fragments.truncate(fragments.len() - 6);
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            for fragment in fragments {
+                assert!(message_reconstructor
+                    .insert_new_fragment(message_reconstructor.recover_fragment(fragment).unwrap())
+                    .is_none());
+            }
+        }
+
+        #[test]
+        fn it_delivers_message_only_once_if_remaining_fragments_arrive_afterwards() {
+            let mut rng = thread_rng();
+
+            // a single data fragment means any of the fragments is sufficient for the reconstruction
+            let message = vec![42u8; 100];
+            let mut fragments = erasure_coded_fragments(&message, 3);
+            assert_eq!(fragments.len(), 4);
+            fragments.shuffle(&mut rng);
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            let mut reconstructed = Vec::new();
+            for fragment in fragments {
+                let fragment = message_reconstructor.recover_fragment(fragment).unwrap();
+                if let Some(message) = message_reconstructor.insert_new_fragment(fragment) {
+                    reconstructed.push(message)
+                }
+            }
+
+            assert_eq!(reconstructed.len(), 1);
+            assert_eq!(reconstructed[0].0, message);
+        }
+
+        #[test]
+        fn it_frees_all_buffers_once_all_fragments_of_reconstructed_set_arrive() {
+            let mut rng = thread_rng();
+
+            let mut message = vec![0u8; 10000];
+            rng.fill_bytes(&mut message);
+
+            let mut fragments = erasure_coded_fragments(&message, 5);
+            fragments.shuffle(&mut rng);
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            let mut reconstructed = 0;
+            for fragment in fragments {
+                let fragment = message_reconstructor.recover_fragment(fragment).unwrap();
+                if message_reconstructor
+                    .insert_new_fragment(fragment)
+                    .is_some()
+                {
+                    reconstructed += 1;
+                    assert_eq!(message_reconstructor.completed_erasure_coded_sets.len(), 1);
+                }
+                assert!(message_reconstructor.reconstructed_sets.len() <= 1);
+            }
+
+            assert_eq!(reconstructed, 1);
+            assert_eq!(message_reconstructor, MessageReconstructor::default());
+        }
+
+        #[test]
+        fn it_forgets_oldest_completed_sets_whose_fragments_never_arrive() {
+            let mut message_reconstructor = MessageReconstructor::default();
+            let message = vec![42u8; 100];
+
+            let mut first_set_id = None;
+            for _ in 0..MAX_TRACKED_COMPLETED_SETS + 1 {
+                // the parity fragment is never delivered
+                let fragments = erasure_coded_fragments(&message, 1);
+                let fragment = message_reconstructor
+                    .recover_fragment(fragments[0].clone())
+                    .unwrap();
+                first_set_id.get_or_insert(fragment.id());
+                assert!(message_reconstructor
+                    .insert_new_fragment(fragment)
+                    .is_some());
+            }
+
+            assert_eq!(
+                message_reconstructor.completed_erasure_coded_sets.len(),
+                MAX_TRACKED_COMPLETED_SETS
+            );
+            assert_eq!(
+                message_reconstructor.completed_erasure_coded_order.len(),
+                MAX_TRACKED_COMPLETED_SETS
+            );
+            assert!(!message_reconstructor
+                .completed_erasure_coded_sets
+                .contains_key(&first_set_id.unwrap()));
+        }
+    }
+
     #[cfg(test)]
     mod multiple_sets_split {
         use super::*;
@@ -1,6 +1,7 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::erasure::{can_be_erasure_coded, prepare_erasure_coded_set};
 use crate::fragment::{
     linked_fragment_payload_max_len, unlinked_fragment_payload_max_len, Fragment,
     LINKED_FRAGMENTED_HEADER_LEN, UNLINKED_FRAGMENTED_HEADER_LEN,
@@ -295,6 +296,29 @@ pub fn split_into_sets<R: Rng>(
     }
 }
 
+/// Entry point for splitting whole message into a single erasure coded [`Set`] consisting of
+/// the data `Fragment`s and additional `parity_fragments` that allow the recipient to reconstruct
+/// the message without having received all of them.
+/// If no parity was requested or the message is too long to fit in a single erasure coded set,
+/// it falls back to the regular [`split_into_sets`].
+pub fn split_into_sets_with_parity<R: Rng>(
+    rng: &mut R,
+    message: &[u8],
+    max_plaintext_size: usize,
+    parity_fragments: u8,
+) -> Vec<FragmentSet> {
+    if !can_be_erasure_coded(message.len(), max_plaintext_size, parity_fragments) {
+        return split_into_sets(rng, message, max_plaintext_size);
+    }
+
+    let set_id = generate_set_id(rng);
+    // the only failure case is if the message could not have been erasure coded,
+    // which we have explicitly checked
+    let set =
+        prepare_erasure_coded_set(message, set_id, parity_fragments, max_plaintext_size).unwrap();
+    vec![set]
+}
+
 // reason for top level tests module is to be able to use the helper functions to verify sets payloads
 #[cfg(test)]
 mod tests {
@@ -800,6 +824,53 @@ mod tests {
     }
 
     #[cfg(test)]
+    mod splitting_into_sets_with_parity {
+        use super::*;
+        use rand::{thread_rng, RngCore};
+
+        #[test]
+        fn creates_single_erasure_coded_set_when_possible() {
+            let mut rng = thread_rng();
+            let mut message = vec![0u8; 10000];
+            rng.fill_bytes(&mut message);
+
+            let sets = split_into_sets_with_parity(&mut rng, &message, max_plaintext_size(), 3);
+            assert_eq!(1, sets.len());
+            let data_fragments =
+                crate::number_of_erasure_coded_data_fragments(message.len(), max_plaintext_size());
+            assert_eq!(data_fragments + 3, sets[0].len());
+            assert!(sets[0]
+                .iter()
+                .all(|fragment| fragment.erasure_coding().is_some()));
+        }
+
+        #[test]
+        fn falls_back_to_regular_split_without_parity() {
+            let mut rng = thread_rng();
+            let mut message = vec![0u8; 10000];
+            rng.fill_bytes(&mut message);
+
+            let mut sets = split_into_sets_with_parity(&mut rng, &message, max_plaintext_size(), 0);
+            assert_eq!(1, sets.len());
+            verify_unlinked_set_payload(sets.pop().unwrap(), &message);
+        }
+
+        #[test]
+        fn falls_back_to_regular_split_for_messages_requiring_multiple_sets() {
+            let mut rng = thread_rng();
+            let mut message =
+                vec![0u8; max_unlinked_set_payload_length(max_plaintext_size()) + 1234];
+            rng.fill_bytes(&mut message);
+
+            let sets = split_into_sets_with_parity(&mut rng, &message, max_plaintext_size(), 3);
+            assert_eq!(2, sets.len());
+            assert!(sets
+                .iter()
+                .flatten()
+                .all(|fragment| fragment.erasure_coding().is_none()));
+        }
+    }
+
     mod helpers {
         use super::*;
 
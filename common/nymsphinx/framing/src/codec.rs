@@ -92,8 +92,11 @@ impl Decoder for NymCodec {
             return Ok(None);
         };
 
-        // let packet = SphinxPacket::from_bytes(&sphinx_packet_bytes)?;
-        let nymsphinx_packet = FramedNymPacket { header, packet };
+        let nymsphinx_packet = FramedNymPacket {
+            header,
+            packet,
+            raw: Some(packet_bytes.freeze()),
+        };
 
         // As per docs:
         // Before returning from the function, implementations should ensure that the buffer
@@ -209,6 +212,7 @@ mod packet_encoding {
         let packet = FramedNymPacket {
             header,
             packet: sphinx_packet,
+            raw: None,
         };
 
         let mut bytes = BytesMut::new();
@@ -227,7 +231,11 @@ mod packet_encoding {
 
         NymPacket::outfox_from_bytes(packet_bytes.as_slice()).unwrap();
 
-        let packet = FramedNymPacket { header, packet };
+        let packet = FramedNymPacket {
+            header,
+            packet,
+            raw: None,
+        };
 
         let mut bytes = BytesMut::new();
         NymCodec.encode(packet, &mut bytes).unwrap();
@@ -237,6 +245,28 @@ mod packet_encoding {
         assert_eq!(decoded.packet.to_bytes().unwrap(), packet_bytes)
     }
 
+    #[test]
+    fn decoded_packet_can_be_copied_without_reserialization() {
+        let sphinx_packet = make_valid_sphinx_packet(Default::default());
+        let sphinx_bytes = sphinx_packet.to_bytes().unwrap();
+
+        let mut bytes = BytesMut::new();
+        NymCodec
+            .encode(
+                FramedNymPacket::new(sphinx_packet, PacketType::Mix, false),
+                &mut bytes,
+            )
+            .unwrap();
+        let decoded = NymCodec.decode(&mut bytes).unwrap().unwrap();
+
+        assert_eq!(decoded.raw.as_deref(), Some(sphinx_bytes.as_slice()));
+        assert_eq!(
+            decoded.packet_copy().unwrap().to_bytes().unwrap(),
+            sphinx_bytes
+        );
+        assert_eq!(decoded.into_inner().to_bytes().unwrap(), sphinx_bytes)
+    }
+
     #[cfg(test)]
     mod decode_will_allocate_enough_bytes_for_next_call {
         use super::*;
@@ -314,6 +344,7 @@ mod packet_encoding {
                     ..Default::default()
                 },
                 packet: make_valid_sphinx_packet(Default::default()),
+                raw: None,
             };
 
             let mut bytes = BytesMut::new();
@@ -331,6 +362,7 @@ mod packet_encoding {
             let packet = FramedNymPacket {
                 header: Header::default(),
                 packet: make_valid_sphinx_packet(Default::default()),
+                raw: None,
             };
 
             let mut bytes = BytesMut::new();
@@ -360,6 +392,7 @@ mod packet_encoding {
                         ..Default::default()
                     },
                     packet: make_valid_sphinx_packet(Default::default()),
+                    raw: None,
                 };
 
                 let mut bytes = BytesMut::new();
@@ -387,6 +420,7 @@ mod packet_encoding {
                 let first_packet = FramedNymPacket {
                     header: Header::default(),
                     packet: make_valid_sphinx_packet(Default::default()),
+                    raw: None,
                 };
 
                 let mut bytes = BytesMut::new();
@@ -406,11 +440,13 @@ mod packet_encoding {
         let packet1 = FramedNymPacket {
             header: Header::default(),
             packet: make_valid_sphinx_packet(Default::default()),
+            raw: None,
         };
 
         let packet2 = FramedNymPacket {
             header: Header::default(),
             packet: make_valid_sphinx_packet(Default::default()),
+            raw: None,
         };
 
         let mut bytes = BytesMut::new();
@@ -428,11 +464,13 @@ mod packet_encoding {
         let packet1 = FramedNymPacket {
             header: Header::default(),
             packet: make_valid_sphinx_packet(Default::default()),
+            raw: None,
         };
 
         let packet2 = FramedNymPacket {
             header: Header::default(),
             packet: make_valid_sphinx_packet(Default::default()),
+            raw: None,
         };
 
         let mut bytes = BytesMut::new();
@@ -2,11 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::codec::NymCodecError;
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 use nym_sphinx_params::packet_sizes::PacketSize;
 use nym_sphinx_params::packet_version::PacketVersion;
 use nym_sphinx_params::PacketType;
-use nym_sphinx_types::NymPacket;
+use nym_sphinx_types::{NymPacket, NymPacketError};
 use std::convert::TryFrom;
 
 #[derive(Debug)]
@@ -16,6 +16,10 @@ pub struct FramedNymPacket {
 
     /// The actual SphinxPacket being sent.
     pub(crate) packet: NymPacket,
+
+    /// Bytes of the packet as received from the wire, if it has been decoded from them.
+    /// They allow creating copies of the packet without having to serialize it again.
+    pub(crate) raw: Option<Bytes>,
 }
 
 impl FramedNymPacket {
@@ -36,7 +40,11 @@ impl FramedNymPacket {
             packet_type,
         };
 
-        FramedNymPacket { header, packet }
+        FramedNymPacket {
+            header,
+            packet,
+            raw: None,
+        }
     }
 
     pub fn header(&self) -> Header {
@@ -51,6 +59,24 @@ impl FramedNymPacket {
         self.header.packet_type
     }
 
+    pub fn packet(&self) -> &NymPacket {
+        &self.packet
+    }
+
+    /// Creates an independent copy of the underlying packet, for example so that its processing
+    /// could be retried with a different key.
+    pub fn packet_copy(&self) -> Result<NymPacket, NymPacketError> {
+        match &self.raw {
+            Some(raw) => match self.header.packet_type {
+                PacketType::Outfox => NymPacket::outfox_from_bytes(raw),
+                PacketType::Mix => NymPacket::sphinx_from_bytes(raw),
+                #[allow(deprecated)]
+                PacketType::Vpn => NymPacket::sphinx_from_bytes(raw),
+            },
+            None => self.packet.try_clone(),
+        }
+    }
+
     pub fn into_inner(self) -> NymPacket {
         self.packet
     }
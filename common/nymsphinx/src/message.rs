@@ -278,6 +278,21 @@ impl PaddedMessage {
             .collect()
     }
 
+    /// Splits the padded message into [`Fragment`] alongside additional `parity_fragments`,
+    /// allowing the recipient to reconstruct the message even if some of them got lost.
+    /// If the message is too long to be erasure coded, it's split as normal.
+    pub fn split_into_fragments_with_parity<R: Rng>(
+        self,
+        rng: &mut R,
+        plaintext_per_packet: usize,
+        parity_fragments: u8,
+    ) -> Vec<Fragment> {
+        chunking::split_into_sets_with_parity(rng, &self.0, plaintext_per_packet, parity_fragments)
+            .into_iter()
+            .flat_map(|fragment_set| fragment_set.into_iter())
+            .collect()
+    }
+
     // reverse of NymMessage::pad_to_full_packet_lengths
     pub fn remove_padding(self, num_mix_hops: u8) -> Result<NymMessage, NymMessageError> {
         // we are looking for first occurrence of 1 in the tail and we get its index
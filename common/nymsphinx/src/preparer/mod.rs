@@ -289,6 +289,19 @@ pub trait FragmentPreparer {
             .pad_to_full_packet_lengths(plaintext_per_packet)
             .split_into_fragments(self.rng(), plaintext_per_packet)
     }
+
+    fn pad_and_split_message_with_parity(
+        &mut self,
+        message: NymMessage,
+        packet_size: PacketSize,
+        parity_fragments: u8,
+    ) -> Vec<Fragment> {
+        let plaintext_per_packet = message.available_sphinx_plaintext_per_packet(packet_size);
+
+        message
+            .pad_to_full_packet_lengths(plaintext_per_packet)
+            .split_into_fragments_with_parity(self.rng(), plaintext_per_packet, parity_fragments)
+    }
 }
 
 /// Prepares the message that is to be sent through the mix network by attaching
@@ -434,6 +447,20 @@ where
     ) -> Vec<Fragment> {
         <Self as FragmentPreparer>::pad_and_split_message(self, message, packet_size)
     }
+
+    pub fn pad_and_split_message_with_parity(
+        &mut self,
+        message: NymMessage,
+        packet_size: PacketSize,
+        parity_fragments: u8,
+    ) -> Vec<Fragment> {
+        <Self as FragmentPreparer>::pad_and_split_message_with_parity(
+            self,
+            message,
+            packet_size,
+            parity_fragments,
+        )
+    }
 }
 
 impl<R: CryptoRng + Rng> FragmentPreparer for MessagePreparer<R> {
@@ -142,6 +142,31 @@ impl NymPacket {
         }
     }
 
+    /// Creates an independent copy of this packet by going through its byte representation.
+    pub fn try_clone(&self) -> Result<NymPacket, NymPacketError> {
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "sphinx")]
+            NymPacket::Sphinx(_) => NymPacket::sphinx_from_bytes(&self.to_bytes()?),
+            #[cfg(feature = "outfox")]
+            NymPacket::Outfox(_) => NymPacket::outfox_from_bytes(&self.to_bytes()?),
+            _ => unreachable!("no packet format has been enabled"),
+        }
+    }
+
+    /// Returns the public element of the packet header that is unique for each packet
+    /// and each of its hops, allowing to detect any replays.
+    pub fn replay_tag(&self) -> Option<[u8; 32]> {
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "sphinx")]
+            NymPacket::Sphinx(packet) => Some(*packet.header.shared_secret.as_bytes()),
+            #[cfg(feature = "outfox")]
+            NymPacket::Outfox(packet) => packet.replay_tag(),
+            _ => None,
+        }
+    }
+
     #[cfg(feature = "sphinx")]
     pub fn process(
         self,
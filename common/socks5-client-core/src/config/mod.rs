@@ -3,6 +3,7 @@
 
 pub use nym_client_core::config::Config as BaseClientConfig;
 use nym_config::defaults::DEFAULT_SOCKS5_LISTENING_PORT;
+use nym_config::serde_helpers::de_maybe_stringified;
 use nym_config::OptionalSet;
 use nym_sphinx::addressing::clients::Recipient;
 use serde::{Deserialize, Serialize};
@@ -62,6 +63,12 @@ impl Config {
         self
     }
 
+    #[must_use]
+    pub fn with_dns_bind_address(mut self, dns_bind_address: SocketAddr) -> Self {
+        self.socks5.dns_bind_address = Some(dns_bind_address);
+        self
+    }
+
     // poor man's 'builder' method
     pub fn with_base<F, T>(mut self, f: F, val: T) -> Self
     where
@@ -134,6 +141,12 @@ pub struct Socks5 {
     #[serde(default)]
     pub send_anonymously: bool,
 
+    /// The address on which the client will be listening for plain DNS queries (over both UDP and TCP)
+    /// that are going to get resolved by the service provider.
+    /// If not specified, the DNS stub is disabled.
+    #[serde(default, deserialize_with = "de_maybe_stringified")]
+    pub dns_bind_address: Option<SocketAddr>,
+
     #[serde(default)]
     pub socks5_debug: Socks5Debug,
 }
@@ -149,6 +162,7 @@ impl Socks5 {
             provider_interface_version: ProviderInterfaceVersion::Legacy,
             socks5_protocol_version: Socks5ProtocolVersion::Legacy,
             send_anonymously: false,
+            dns_bind_address: None,
             socks5_debug: Default::default(),
         }
     }
@@ -71,6 +71,7 @@ impl From<Socks5V1_1_30> for Socks5 {
             provider_interface_version: value.provider_interface_version,
             socks5_protocol_version: value.socks5_protocol_version,
             send_anonymously: value.send_anonymously,
+            dns_bind_address: None,
             socks5_debug: value.socks5_debug.into(),
         }
     }
@@ -138,7 +138,8 @@ where
             ),
             shutdown.clone(),
             packet_type,
-        );
+        )
+        .with_dns_stub(socks5_config.dns_bind_address);
         nym_task::spawn_with_report_error(
             async move {
                 sphinx_socks
@@ -3,19 +3,23 @@
 use super::authentication::{AuthenticationMethods, Authenticator, User};
 use super::request::{SocksCommand, SocksRequest};
 use super::types::{ResponseCodeV4, ResponseCodeV5, SocksProxyError};
+use super::udp::{self, MAX_UDP_DATAGRAM_SIZE};
+use super::utils as socks_utils;
 use super::{SocksVersion, RESERVED, SOCKS4_VERSION, SOCKS5_VERSION};
 use crate::config;
 use futures::channel::mpsc;
 use futures::task::{Context, Poll};
+use futures::StreamExt;
 use log::*;
 use nym_client_core::client::inbound_messages::{InputMessage, InputMessageSender};
 use nym_service_providers_common::interface::{ProviderInterfaceVersion, RequestVersion};
 use nym_socks5_proxy_helpers::connection_controller::{
-    ConnectionReceiver, ControllerCommand, ControllerSender,
+    BindReceiver, ConnectionReceiver, ControllerCommand, ControllerSender, DatagramReceiver,
 };
 use nym_socks5_proxy_helpers::proxy_runner::ProxyRunner;
 use nym_socks5_requests::{
-    ConnectionId, RemoteAddress, Socks5ProtocolVersion, Socks5ProviderRequest, Socks5Request,
+    BindEvent, ConnectionId, Datagram, RemoteAddress, Socks5ProtocolVersion, Socks5ProviderRequest,
+    Socks5Request,
 };
 use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::params::PacketSize;
@@ -25,11 +29,17 @@ use nym_task::TaskClient;
 use pin_project::pin_project;
 use rand::RngCore;
 use std::io;
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::pin::Pin;
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::UdpSocket;
 use tokio::{self, net::TcpStream};
 
+/// Maximum time we're willing to wait for the service provider to report progress of a BIND request,
+/// i.e. either to start listening or to accept the inbound connection.
+const BIND_EVENT_TIMEOUT: Duration = Duration::from_secs(180);
+
 #[pin_project(project = StateProject)]
 enum StreamState {
     Available(TcpStream),
@@ -69,6 +79,17 @@ impl StreamState {
         }
     }
 
+    /// Returns the local address that this stream is bound to.
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self {
+            StreamState::RunningProxy => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "stream is being used to run the proxy",
+            )),
+            StreamState::Available(ref stream) => stream.local_addr(),
+        }
+    }
+
     async fn shutdown(&mut self) -> io::Result<()> {
         // shutdown should only be called if proxy is not being run. If it is, there's some bug
         // somewhere
@@ -160,10 +181,26 @@ impl Config {
         }
     }
 
+    pub(crate) fn provider_interface_version(&self) -> ProviderInterfaceVersion {
+        self.provider_interface_version
+    }
+
+    pub(crate) fn socks5_protocol_version(&self) -> Socks5ProtocolVersion {
+        self.socks5_protocol_version
+    }
+
+    pub(crate) fn use_surbs_for_responses(&self) -> bool {
+        self.use_surbs_for_responses
+    }
+
+    pub(crate) fn per_request_surbs(&self) -> u32 {
+        self.per_request_surbs
+    }
+
     fn request_version(&self) -> RequestVersion<Socks5Request> {
         RequestVersion {
             provider_interface: self.provider_interface_version,
-            provider_protocol: self.socks5_protocol_version,
+            provider_protocol: self.socks5_protocol_version.for_basic_requests(),
         }
     }
 }
@@ -340,7 +377,7 @@ impl SocksClient {
     async fn send_anonymous_connect_to_mixnet(&mut self, remote_address: RemoteAddress) {
         // TODO: simplify by using `request_version`
         let req = Socks5Request::new_connect(
-            self.config.socks5_protocol_version,
+            self.config.socks5_protocol_version.for_basic_requests(),
             self.connection_id,
             remote_address,
             None,
@@ -364,7 +401,7 @@ impl SocksClient {
     async fn send_connect_to_mixnet_with_return_address(&mut self, remote_address: RemoteAddress) {
         // TODO: simplify by using `request_version`
         let req = Socks5Request::new_connect(
-            self.config.socks5_protocol_version,
+            self.config.socks5_protocol_version.for_basic_requests(),
             self.connection_id,
             remote_address,
             Some(self.self_address),
@@ -394,9 +431,6 @@ impl SocksClient {
     }
 
     async fn run_proxy(&mut self, conn_receiver: ConnectionReceiver, remote_proxy_target: String) {
-        self.send_connect_to_mixnet(remote_proxy_target.clone())
-            .await;
-
         let stream = self.stream.run_proxy();
         let peer_addr = match stream.peer_addr() {
             Ok(peer_addr) => peer_addr,
@@ -459,6 +493,230 @@ impl SocksClient {
         self.stream.finish_proxy(stream)
     }
 
+    fn new_provider_input_message(&self, request: Socks5Request, reply_surbs: u32) -> InputMessage {
+        let msg = Socks5ProviderRequest::new_provider_data(
+            self.config.provider_interface_version,
+            request,
+        );
+        let lane = TransmissionLane::ConnectionId(self.connection_id);
+
+        if self.config.use_surbs_for_responses {
+            InputMessage::new_anonymous(
+                self.service_provider,
+                msg.into_bytes(),
+                reply_surbs,
+                lane,
+                self.packet_type,
+            )
+        } else {
+            InputMessage::new_regular(
+                self.service_provider,
+                msg.into_bytes(),
+                lane,
+                self.packet_type,
+            )
+        }
+    }
+
+    async fn send_provider_request(&mut self, request: Socks5Request, reply_surbs: u32) {
+        let input_message = self.new_provider_input_message(request, reply_surbs);
+        self.input_sender
+            .send(input_message)
+            .await
+            .expect("InputMessageReceiver has stopped receiving!");
+    }
+
+    async fn send_udp_associate_to_mixnet(&mut self) {
+        let return_address = if self.config.use_surbs_for_responses {
+            None
+        } else {
+            Some(self.self_address)
+        };
+        let req = Socks5Request::new_udp_associate(
+            self.config.socks5_protocol_version,
+            self.connection_id,
+            return_address,
+        );
+        self.send_provider_request(req, self.config.connection_start_surbs)
+            .await
+    }
+
+    async fn send_udp_disassociate_to_mixnet(&mut self) {
+        let req = Socks5Request::new_udp_disassociate(
+            self.config.socks5_protocol_version,
+            self.connection_id,
+        );
+        // we don't expect any responses to this one
+        self.send_provider_request(req, 0).await
+    }
+
+    async fn send_bind_to_mixnet(&mut self, expected_peer: RemoteAddress) {
+        let return_address = if self.config.use_surbs_for_responses {
+            None
+        } else {
+            Some(self.self_address)
+        };
+        let req = Socks5Request::new_bind(
+            self.config.socks5_protocol_version,
+            self.connection_id,
+            expected_peer,
+            return_address,
+        );
+        self.send_provider_request(req, self.config.connection_start_surbs)
+            .await
+    }
+
+    async fn wait_for_bind_event(
+        &mut self,
+        bind_receiver: &mut BindReceiver,
+    ) -> Result<BindEvent, SocksProxyError> {
+        tokio::select! {
+            event = bind_receiver.next() => event.ok_or_else(|| SocksProxyError::BindFailure {
+                message: "the bind channel got closed".to_string(),
+            }),
+            _ = tokio::time::sleep(BIND_EVENT_TIMEOUT) => Err(SocksProxyError::BindTimeout),
+            _ = self.shutdown_listener.recv() => Err(SocksProxyError::BindFailure {
+                message: "the client is shutting down".to_string(),
+            }),
+        }
+    }
+
+    /// Completes the BIND request by sending both of the replies, as described in
+    /// https://www.rfc-editor.org/rfc/rfc1928#section-6, and then proxies the accepted connection.
+    async fn run_bind(
+        &mut self,
+        mut bind_receiver: BindReceiver,
+        conn_receiver: ConnectionReceiver,
+        expected_peer: RemoteAddress,
+    ) -> Result<(), SocksProxyError> {
+        // the first reply is sent once the service provider has started listening
+        let listening_address = match self.wait_for_bind_event(&mut bind_receiver).await? {
+            BindEvent::Listening { address } => address,
+            BindEvent::Failed { message } => return Err(SocksProxyError::BindFailure { message }),
+            BindEvent::Accepted { peer } => {
+                return Err(SocksProxyError::BindFailure {
+                    message: format!("received an inbound connection from {peer} before the listener has been set up"),
+                })
+            }
+        };
+        debug!(
+            "the service provider is listening on {listening_address} for a connection from {expected_peer} (id: {})",
+            self.connection_id
+        );
+        self.acknowledge_socks5_with_address(reply_address(&listening_address))
+            .await?;
+
+        // and the second one once the inbound connection got accepted
+        let peer = match self.wait_for_bind_event(&mut bind_receiver).await? {
+            BindEvent::Accepted { peer } => peer,
+            BindEvent::Failed { message } => return Err(SocksProxyError::BindFailure { message }),
+            BindEvent::Listening { address } => {
+                return Err(SocksProxyError::BindFailure {
+                    message: format!("received a duplicate listening notification for {address}"),
+                })
+            }
+        };
+        self.acknowledge_socks5_with_address(reply_address(&peer))
+            .await?;
+
+        info!(
+            "Starting proxy for inbound connection from {peer} (id: {})",
+            self.connection_id
+        );
+        self.run_proxy(conn_receiver, peer.clone()).await;
+        info!(
+            "Proxy for inbound connection from {peer} is finished (id: {})",
+            self.connection_id
+        );
+        Ok(())
+    }
+
+    /// Binds the UDP relay socket on the same interface the client has connected to.
+    async fn bind_udp_relay(&self) -> Result<UdpSocket, SocksProxyError> {
+        let local_ip = self
+            .stream
+            .local_addr()
+            .map_err(|source| SocksProxyError::UdpRelayBindFailure { source })?
+            .ip();
+        UdpSocket::bind(SocketAddr::new(local_ip, 0))
+            .await
+            .map_err(|source| SocksProxyError::UdpRelayBindFailure { source })
+    }
+
+    /// Relays the datagrams between the local client and the mixnet for as long as the TCP
+    /// connection the UDP ASSOCIATE request arrived on stays open.
+    async fn run_udp_relay(
+        &mut self,
+        socket: UdpSocket,
+        mut datagram_receiver: DatagramReceiver,
+    ) -> Result<(), SocksProxyError> {
+        // only accept datagrams coming from the same host as the one that made the request
+        let client_ip = self
+            .stream
+            .peer_addr()
+            .map_err(|source| SocksProxyError::PeerAddrExtractionFailure { source })?
+            .ip();
+        let mut client_udp_address = None;
+
+        let mut buf = vec![0u8; MAX_UDP_DATAGRAM_SIZE];
+        let mut tcp_buf = [0u8; 64];
+
+        while !self.shutdown_listener.is_shutdown() {
+            tokio::select! {
+                _ = self.shutdown_listener.recv() => {
+                    log::trace!("UDP relay {}: Received shutdown", self.connection_id);
+                }
+                read = self.stream.read(&mut tcp_buf) => match read {
+                    // the association terminates when the TCP connection it arrived on terminates
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => trace!("ignoring data received on the UDP ASSOCIATE control connection"),
+                },
+                received = socket.recv_from(&mut buf) => {
+                    let (n, source) = received.map_err(|source| SocksProxyError::SocketReadError { source })?;
+                    if source.ip() != client_ip {
+                        debug!("dropping datagram from unexpected source {source}");
+                        continue;
+                    }
+                    client_udp_address = Some(source);
+
+                    let (remote_address, data) = match udp::parse_udp_datagram(&buf[..n]) {
+                        Ok(parsed) => parsed,
+                        Err(err) => {
+                            debug!("dropping datagram from {source}: {err}");
+                            continue;
+                        }
+                    };
+                    trace!("relaying {} bytes to {remote_address}", data.len());
+
+                    let req = Socks5Request::new_datagram(
+                        self.config.socks5_protocol_version,
+                        Datagram::new(self.connection_id, remote_address, data.to_vec()),
+                    );
+                    self.send_provider_request(req, self.config.per_request_surbs).await;
+                },
+                datagram = datagram_receiver.next() => {
+                    let Some(datagram) = datagram else {
+                        log::trace!("UDP relay {}: Stopping since channel closed", self.connection_id);
+                        break;
+                    };
+                    let Some(client_udp_address) = client_udp_address else {
+                        debug!("received a datagram before the client has sent anything - dropping it");
+                        continue;
+                    };
+                    match udp::encode_udp_datagram(&datagram.remote_addr, &datagram.data) {
+                        Ok(encoded) => {
+                            if let Err(err) = socket.send_to(&encoded, client_udp_address).await {
+                                warn!("failed to forward datagram to {client_udp_address}: {err}")
+                            }
+                        }
+                        Err(err) => warn!("failed to encode datagram from {}: {err}", datagram.remote_addr),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Handles a client request.
     async fn handle_request(&mut self) -> Result<(), SocksProxyError> {
         debug!("Handling CONNECT Command");
@@ -500,6 +758,7 @@ impl SocksClient {
                     remote_address.clone(),
                     self.connection_id
                 );
+                self.send_connect_to_mixnet(remote_address.clone()).await;
                 self.run_proxy(mix_receiver, remote_address.clone()).await;
                 info!(
                     "Proxy for {} is finished (id: {})",
@@ -507,8 +766,84 @@ impl SocksClient {
                 );
             }
 
-            SocksCommand::Bind => return Err(SocksProxyError::BindNotSupported), // not handled
-            SocksCommand::UdpAssociate => return Err(SocksProxyError::UdpNotSupported),
+            SocksCommand::Bind => {
+                // TODO: add support for SOCKS4 BIND
+                if *version != SocksVersion::V5 {
+                    return Err(ResponseCodeV5::CommandNotSupported.into());
+                }
+                if !self
+                    .config
+                    .socks5_protocol_version
+                    .supports_extended_requests()
+                {
+                    warn!(
+                        "BIND is not supported by the service provider protocol version {}",
+                        self.config.socks5_protocol_version
+                    );
+                    return Err(ResponseCodeV5::CommandNotSupported.into());
+                }
+
+                let (bind_sender, bind_receiver) = mpsc::unbounded();
+                self.started_proxy = true;
+                self.controller_sender
+                    .unbounded_send(ControllerCommand::Insert {
+                        connection_id: self.connection_id,
+                        connection_sender: mix_sender,
+                    })
+                    .unwrap();
+                self.controller_sender
+                    .unbounded_send(ControllerCommand::InsertBind {
+                        connection_id: self.connection_id,
+                        bind_sender,
+                    })
+                    .unwrap();
+
+                self.send_bind_to_mixnet(remote_address.clone()).await;
+                self.run_bind(bind_receiver, mix_receiver, remote_address)
+                    .await?
+            }
+            SocksCommand::UdpAssociate => {
+                // there's no UDP in SOCKS4
+                if *version != SocksVersion::V5 {
+                    return Err(ResponseCodeV5::CommandNotSupported.into());
+                }
+                if !self
+                    .config
+                    .socks5_protocol_version
+                    .supports_extended_requests()
+                {
+                    warn!(
+                        "UDP ASSOCIATE is not supported by the service provider protocol version {}",
+                        self.config.socks5_protocol_version
+                    );
+                    return Err(ResponseCodeV5::CommandNotSupported.into());
+                }
+
+                let socket = self.bind_udp_relay().await?;
+                let relay_address = socket
+                    .local_addr()
+                    .map_err(|source| SocksProxyError::UdpRelayBindFailure { source })?;
+                self.acknowledge_socks5_with_address(relay_address).await?;
+
+                let (datagram_sender, datagram_receiver) = mpsc::unbounded();
+                self.started_proxy = true;
+                self.controller_sender
+                    .unbounded_send(ControllerCommand::InsertAssociation {
+                        connection_id: self.connection_id,
+                        datagram_sender,
+                    })
+                    .unwrap();
+                self.send_udp_associate_to_mixnet().await;
+
+                info!(
+                    "Starting UDP relay on {relay_address} (id: {})",
+                    self.connection_id
+                );
+                let res = self.run_udp_relay(socket, datagram_receiver).await;
+                self.send_udp_disassociate_to_mixnet().await;
+                info!("UDP relay is finished (id: {})", self.connection_id);
+                res?
+            }
         };
 
         Ok(())
@@ -534,6 +869,22 @@ impl SocksClient {
             .unwrap();
     }
 
+    /// Writes a Socks5 header back to the requesting client's TCP stream including
+    /// the specified bound address, such as the address of the UDP relay.
+    async fn acknowledge_socks5_with_address(
+        &mut self,
+        address: SocketAddr,
+    ) -> Result<(), SocksProxyError> {
+        let response: Vec<_> = [SOCKS5_VERSION, ResponseCodeV5::Success as u8, RESERVED]
+            .into_iter()
+            .chain(socks_utils::encode_socket_address(address))
+            .collect();
+        self.stream
+            .write_all(&response)
+            .await
+            .map_err(|source| SocksProxyError::SocketWriteError { source })
+    }
+
     /// Writes a Socks4 header back to the requesting client's TCP stream,
     async fn acknowledge_socks4(&mut self) {
         self.stream
@@ -693,3 +1044,12 @@ impl SocksClient {
         Ok(methods)
     }
 }
+
+/// Attempts to parse the address reported by the service provider, so that it could be included
+/// in the reply, falling back to the unspecified address if it's malformed.
+fn reply_address(address: &str) -> SocketAddr {
+    address.parse().unwrap_or_else(|_| {
+        warn!("received malformed address {address}");
+        SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0)
+    })
+}
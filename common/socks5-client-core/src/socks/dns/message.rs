@@ -0,0 +1,372 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Minimal decoding of DNS queries and encoding of the responses to them, as exchanged with
+//! the local applications. Only standard queries with a single question are supported.
+//! From: https://www.rfc-editor.org/rfc/rfc1035#section-4
+//!
+//! +---------------------+
+//! |        Header       |
+//! +---------------------+
+//! |       Question      | the question for the name server
+//! +---------------------+
+//! |        Answer       | RRs answering the question
+//! +---------------------+
+//! |      Authority      | RRs pointing toward an authority
+//! +---------------------+
+//! |      Additional     | RRs holding additional information
+//! +---------------------+
+
+use nym_socks5_requests::DnsRecordType;
+use std::net::IpAddr;
+use thiserror::Error;
+
+/// The biggest message we're allowed to send over UDP without relying on EDNS.
+pub(crate) const MAX_UDP_MESSAGE_SIZE: usize = 512;
+
+/// TTL attached to all the answers. The service provider resolves the names with
+/// the system resolver, which doesn't tell us the actual value, so keep it short.
+const ANSWER_TTL: u32 = 60;
+
+const HEADER_LEN: usize = 12;
+const MAX_NAME_LEN: usize = 255;
+
+const FLAG_RESPONSE: u16 = 0x8000;
+const FLAG_TRUNCATED: u16 = 0x0200;
+const FLAG_RECURSION_DESIRED: u16 = 0x0100;
+const FLAG_RECURSION_AVAILABLE: u16 = 0x0080;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+// pointer to the name in the question section, which always starts right after the header
+const QUESTION_NAME_POINTER: u16 = 0xC000 | HEADER_LEN as u16;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ResponseCode {
+    NoError = 0,
+    FormatError = 1,
+    ServerFailure = 2,
+    NotImplemented = 4,
+    Refused = 5,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(crate) enum DnsMessageError {
+    #[error("the message is too short to contain the dns header")]
+    HeaderTooShort,
+
+    #[error("the message is a response rather than a query")]
+    NotAQuery,
+
+    #[error("opcode {opcode} is not supported")]
+    UnsupportedOpcode { opcode: u8 },
+
+    #[error("expected exactly one question, got {count}")]
+    UnsupportedQuestionCount { count: u16 },
+
+    #[error("the question is malformed")]
+    MalformedQuestion,
+}
+
+impl DnsMessageError {
+    fn response_code(&self) -> ResponseCode {
+        match self {
+            DnsMessageError::UnsupportedOpcode { .. } => ResponseCode::NotImplemented,
+            _ => ResponseCode::FormatError,
+        }
+    }
+}
+
+/// A standard query for a single name received from a local application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DnsQuery {
+    pub(crate) id: u16,
+    pub(crate) name: String,
+    pub(crate) qtype: u16,
+    qclass: u16,
+    recursion_desired: bool,
+
+    // the raw question section that has to be repeated in the response
+    question: Vec<u8>,
+}
+
+impl DnsQuery {
+    pub(crate) fn try_from_bytes(b: &[u8]) -> Result<DnsQuery, DnsMessageError> {
+        if b.len() < HEADER_LEN {
+            return Err(DnsMessageError::HeaderTooShort);
+        }
+
+        let id = u16::from_be_bytes([b[0], b[1]]);
+        let flags = u16::from_be_bytes([b[2], b[3]]);
+        if flags & FLAG_RESPONSE != 0 {
+            return Err(DnsMessageError::NotAQuery);
+        }
+        let opcode = ((flags >> 11) & 0x0F) as u8;
+        if opcode != 0 {
+            return Err(DnsMessageError::UnsupportedOpcode { opcode });
+        }
+        let question_count = u16::from_be_bytes([b[4], b[5]]);
+        if question_count != 1 {
+            return Err(DnsMessageError::UnsupportedQuestionCount {
+                count: question_count,
+            });
+        }
+
+        // QNAME is a sequence of length-prefixed labels terminated by an empty label.
+        // queries don't use compression, so we don't have to follow any pointers
+        let mut labels = Vec::new();
+        let mut offset = HEADER_LEN;
+        loop {
+            let label_len = *b.get(offset).ok_or(DnsMessageError::MalformedQuestion)? as usize;
+            offset += 1;
+            if label_len == 0 {
+                break;
+            }
+            if label_len > 63 {
+                return Err(DnsMessageError::MalformedQuestion);
+            }
+            let label = b
+                .get(offset..offset + label_len)
+                .ok_or(DnsMessageError::MalformedQuestion)?;
+            labels.push(String::from_utf8_lossy(label).to_string());
+            offset += label_len;
+            if offset - HEADER_LEN > MAX_NAME_LEN {
+                return Err(DnsMessageError::MalformedQuestion);
+            }
+        }
+
+        // QTYPE (2) || QCLASS (2)
+        let tail = b
+            .get(offset..offset + 4)
+            .ok_or(DnsMessageError::MalformedQuestion)?;
+        let qtype = u16::from_be_bytes([tail[0], tail[1]]);
+        let qclass = u16::from_be_bytes([tail[2], tail[3]]);
+
+        // anything after the question, such as the EDNS record, is ignored
+        Ok(DnsQuery {
+            id,
+            name: labels.join("."),
+            qtype,
+            qclass,
+            recursion_desired: flags & FLAG_RECURSION_DESIRED != 0,
+            question: b[HEADER_LEN..offset + 4].to_vec(),
+        })
+    }
+
+    /// Returns the type of the requested records if it's something the service provider
+    /// is able to resolve.
+    pub(crate) fn record_type(&self) -> Option<DnsRecordType> {
+        if self.qclass != CLASS_IN || self.name.is_empty() {
+            return None;
+        }
+        match self.qtype {
+            TYPE_A => Some(DnsRecordType::A),
+            TYPE_AAAA => Some(DnsRecordType::Aaaa),
+            _ => None,
+        }
+    }
+
+    /// Creates the response to this query answering it with the provided addresses.
+    /// If they don't all fit in `max_size`, the response is marked as truncated.
+    pub(crate) fn response(
+        &self,
+        code: ResponseCode,
+        addresses: &[IpAddr],
+        max_size: usize,
+    ) -> Vec<u8> {
+        let mut answers = Vec::new();
+        let mut answer_count = 0u16;
+        let mut truncated = false;
+
+        for address in addresses {
+            let (rtype, rdata) = match address {
+                IpAddr::V4(ip) if self.qtype == TYPE_A => (TYPE_A, ip.octets().to_vec()),
+                IpAddr::V6(ip) if self.qtype == TYPE_AAAA => (TYPE_AAAA, ip.octets().to_vec()),
+                _ => continue,
+            };
+            let answer: Vec<u8> = QUESTION_NAME_POINTER
+                .to_be_bytes()
+                .into_iter()
+                .chain(rtype.to_be_bytes())
+                .chain(CLASS_IN.to_be_bytes())
+                .chain(ANSWER_TTL.to_be_bytes())
+                .chain((rdata.len() as u16).to_be_bytes())
+                .chain(rdata)
+                .collect();
+
+            if HEADER_LEN + self.question.len() + answers.len() + answer.len() > max_size {
+                truncated = true;
+                break;
+            }
+            answers.extend(answer);
+            answer_count += 1;
+        }
+
+        let mut flags = FLAG_RESPONSE | FLAG_RECURSION_AVAILABLE | code as u16;
+        if self.recursion_desired {
+            flags |= FLAG_RECURSION_DESIRED;
+        }
+        if truncated {
+            flags |= FLAG_TRUNCATED;
+        }
+
+        // ID || FLAGS || QDCOUNT || ANCOUNT || NSCOUNT || ARCOUNT || QUESTION || ANSWERS
+        self.id
+            .to_be_bytes()
+            .into_iter()
+            .chain(flags.to_be_bytes())
+            .chain(1u16.to_be_bytes())
+            .chain(answer_count.to_be_bytes())
+            .chain(0u16.to_be_bytes())
+            .chain(0u16.to_be_bytes())
+            .chain(self.question.iter().copied())
+            .chain(answers)
+            .collect()
+    }
+}
+
+/// Creates the response to a message that couldn't be parsed as a supported query.
+/// Returns `None` if the message should be ignored instead.
+pub(crate) fn error_response(b: &[u8], err: &DnsMessageError) -> Option<Vec<u8>> {
+    if matches!(
+        err,
+        DnsMessageError::HeaderTooShort | DnsMessageError::NotAQuery
+    ) {
+        return None;
+    }
+
+    // echo the id and the opcode back, with no sections attached
+    let flags = FLAG_RESPONSE | (u16::from_be_bytes([b[2], b[3]]) & 0x7800);
+    Some(
+        [b[0], b[1]]
+            .into_iter()
+            .chain((flags | err.response_code() as u16).to_be_bytes())
+            .chain([0u8; 8])
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // query for the A records of "nym.com" with recursion desired and an EDNS record attached
+    fn query_bytes(qtype: u16) -> Vec<u8> {
+        let mut query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 1];
+        query.extend_from_slice(&[3, b'n', b'y', b'm', 3, b'c', b'o', b'm', 0]);
+        query.extend_from_slice(&qtype.to_be_bytes());
+        query.extend_from_slice(&CLASS_IN.to_be_bytes());
+        query.extend_from_slice(&[0, 0, 41, 16, 0, 0, 0, 0, 0, 0, 0]);
+        query
+    }
+
+    #[test]
+    fn parsing_query() {
+        let query = DnsQuery::try_from_bytes(&query_bytes(TYPE_A)).unwrap();
+        assert_eq!(query.id, 0x1234);
+        assert_eq!(query.name, "nym.com");
+        assert_eq!(query.record_type(), Some(DnsRecordType::A));
+        assert!(query.recursion_desired);
+
+        let query = DnsQuery::try_from_bytes(&query_bytes(TYPE_AAAA)).unwrap();
+        assert_eq!(query.record_type(), Some(DnsRecordType::Aaaa));
+
+        // MX
+        let query = DnsQuery::try_from_bytes(&query_bytes(15)).unwrap();
+        assert_eq!(query.record_type(), None);
+    }
+
+    #[test]
+    fn parsing_invalid_queries() {
+        let query = query_bytes(TYPE_A);
+        assert_eq!(
+            DnsQuery::try_from_bytes(&query[..11]),
+            Err(DnsMessageError::HeaderTooShort)
+        );
+        assert_eq!(
+            DnsQuery::try_from_bytes(&query[..20]),
+            Err(DnsMessageError::MalformedQuestion)
+        );
+
+        let mut response = query.clone();
+        response[2] |= 0x80;
+        assert_eq!(
+            DnsQuery::try_from_bytes(&response),
+            Err(DnsMessageError::NotAQuery)
+        );
+        assert!(error_response(&response, &DnsMessageError::NotAQuery).is_none());
+
+        let mut multiple_questions = query.clone();
+        multiple_questions[5] = 2;
+        let err = DnsQuery::try_from_bytes(&multiple_questions).unwrap_err();
+        assert_eq!(err, DnsMessageError::UnsupportedQuestionCount { count: 2 });
+        let response = error_response(&multiple_questions, &err).unwrap();
+        assert_eq!(
+            response,
+            vec![0x12, 0x34, 0x80, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let mut compressed = query;
+        compressed[12] = 0xC0;
+        assert_eq!(
+            DnsQuery::try_from_bytes(&compressed),
+            Err(DnsMessageError::MalformedQuestion)
+        );
+    }
+
+    #[test]
+    fn encoding_response() {
+        let query = DnsQuery::try_from_bytes(&query_bytes(TYPE_A)).unwrap();
+        let addresses = [
+            "1.2.3.4".parse().unwrap(),
+            "2001:db8::1".parse().unwrap(),
+            "5.6.7.8".parse().unwrap(),
+        ];
+        let response = query.response(ResponseCode::NoError, &addresses, MAX_UDP_MESSAGE_SIZE);
+
+        // header: same id, response with RD and RA set, 1 question and 2 answers
+        assert_eq!(
+            response[..HEADER_LEN],
+            [0x12, 0x34, 0x81, 0x80, 0, 1, 0, 2, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            response[HEADER_LEN..HEADER_LEN + 13],
+            query_bytes(TYPE_A)[12..25]
+        );
+        let answers = &response[HEADER_LEN + 13..];
+        assert_eq!(
+            answers[..16],
+            [0xC0, 0x0C, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 1, 2, 3, 4]
+        );
+        assert_eq!(
+            answers[16..],
+            [0xC0, 0x0C, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 5, 6, 7, 8]
+        );
+    }
+
+    #[test]
+    fn truncating_response() {
+        let query = DnsQuery::try_from_bytes(&query_bytes(TYPE_A)).unwrap();
+        let addresses = ["1.2.3.4".parse().unwrap(), "5.6.7.8".parse().unwrap()];
+
+        // only enough space for a single answer
+        let response = query.response(ResponseCode::NoError, &addresses, 45);
+        assert_eq!(response.len(), 41);
+        assert_eq!(response[2] & 0x02, 0x02);
+        assert_eq!(response[6..8], [0, 1]);
+    }
+
+    #[test]
+    fn encoding_failure() {
+        let query = DnsQuery::try_from_bytes(&query_bytes(TYPE_A)).unwrap();
+        let response = query.response(ResponseCode::Refused, &[], MAX_UDP_MESSAGE_SIZE);
+        assert_eq!(
+            response[..HEADER_LEN],
+            [0x12, 0x34, 0x81, 0x85, 0, 1, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(response.len(), HEADER_LEN + 13);
+    }
+}
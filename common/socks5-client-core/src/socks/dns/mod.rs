@@ -0,0 +1,314 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! A local DNS stub resolver that forwards the A and AAAA queries of the local applications
+//! to the service provider, so that the names would get resolved on the other side of the mixnet
+//! rather than leaking to the local resolver.
+
+use self::message::{DnsQuery, ResponseCode, MAX_UDP_MESSAGE_SIZE};
+use crate::socks::client;
+use futures::channel::oneshot;
+use log::*;
+use nym_client_core::client::inbound_messages::{InputMessage, InputMessageSender};
+use nym_socks5_requests::{
+    DnsQueryId, DnsQueryResult, DnsRecordType, Socks5ProviderRequest, Socks5Request,
+};
+use nym_sphinx::addressing::clients::Recipient;
+use nym_sphinx::params::PacketType;
+use nym_task::connections::TransmissionLane;
+use nym_task::TaskClient;
+use rand::RngCore;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+mod message;
+
+/// Maximum time we're willing to wait for the service provider to answer a query.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum time a TCP connection is kept open without receiving any queries.
+const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Queries sent to the service provider that are still waiting for their answers.
+#[derive(Clone, Default)]
+pub(crate) struct PendingDnsQueries {
+    inner: Arc<Mutex<HashMap<DnsQueryId, oneshot::Sender<DnsQueryResult>>>>,
+}
+
+impl PendingDnsQueries {
+    fn register(&self) -> (DnsQueryId, oneshot::Receiver<DnsQueryResult>) {
+        let (sender, receiver) = oneshot::channel();
+        let mut pending = self
+            .inner
+            .lock()
+            .expect("pending dns queries lock is poisoned");
+        let mut rng = rand::rngs::OsRng;
+        let mut query_id = rng.next_u64();
+        while pending.contains_key(&query_id) {
+            query_id = rng.next_u64();
+        }
+        pending.insert(query_id, sender);
+        (query_id, receiver)
+    }
+
+    fn remove(&self, query_id: DnsQueryId) {
+        self.inner
+            .lock()
+            .expect("pending dns queries lock is poisoned")
+            .remove(&query_id);
+    }
+
+    /// Passes the answer received from the service provider to whoever is waiting for it.
+    pub(crate) fn complete(&self, query_id: DnsQueryId, result: DnsQueryResult) {
+        let sender = self
+            .inner
+            .lock()
+            .expect("pending dns queries lock is poisoned")
+            .remove(&query_id);
+        match sender {
+            Some(sender) => {
+                // the receiver might have already timed out
+                let _ = sender.send(result);
+            }
+            None => debug!("received a response to an unknown dns query {query_id}"),
+        }
+    }
+}
+
+/// Forwards the queries to the service provider.
+#[derive(Clone)]
+struct MixnetResolver {
+    input_sender: InputMessageSender,
+    service_provider: Recipient,
+    self_address: Recipient,
+    client_config: client::Config,
+    packet_type: PacketType,
+    pending: PendingDnsQueries,
+}
+
+impl MixnetResolver {
+    fn new_provider_input_message(&self, request: Socks5Request) -> InputMessage {
+        let msg = Socks5ProviderRequest::new_provider_data(
+            self.client_config.provider_interface_version(),
+            request,
+        );
+
+        if self.client_config.use_surbs_for_responses() {
+            InputMessage::new_anonymous(
+                self.service_provider,
+                msg.into_bytes(),
+                self.client_config.per_request_surbs(),
+                TransmissionLane::General,
+                Some(self.packet_type),
+            )
+        } else {
+            InputMessage::new_regular(
+                self.service_provider,
+                msg.into_bytes(),
+                TransmissionLane::General,
+                Some(self.packet_type),
+            )
+        }
+    }
+
+    async fn resolve(&self, name: String, record_type: DnsRecordType) -> DnsQueryResult {
+        let protocol_version = self.client_config.socks5_protocol_version();
+        if !protocol_version.supports_extended_requests() {
+            return DnsQueryResult::Failed {
+                message: format!(
+                    "dns queries are not supported by the service provider protocol version {protocol_version}"
+                ),
+            };
+        }
+
+        let return_address = if self.client_config.use_surbs_for_responses() {
+            None
+        } else {
+            Some(self.self_address)
+        };
+
+        let (query_id, receiver) = self.pending.register();
+        let request = Socks5Request::new_dns_query(
+            protocol_version,
+            query_id,
+            name,
+            record_type,
+            return_address,
+        );
+        let input_message = self.new_provider_input_message(request);
+        if self.input_sender.send(input_message).await.is_err() {
+            self.pending.remove(query_id);
+            return DnsQueryResult::Failed {
+                message: "the client is shutting down".to_string(),
+            };
+        }
+
+        match tokio::time::timeout(QUERY_TIMEOUT, receiver).await {
+            Ok(Ok(result)) => result,
+            _ => {
+                self.pending.remove(query_id);
+                DnsQueryResult::Failed {
+                    message: "timed out while waiting for the service provider".to_string(),
+                }
+            }
+        }
+    }
+
+    /// Answers the raw query received from the local application. Returns `None` if the message
+    /// should be ignored.
+    async fn answer(&self, raw_query: &[u8], max_response_size: usize) -> Option<Vec<u8>> {
+        let query = match DnsQuery::try_from_bytes(raw_query) {
+            Ok(query) => query,
+            Err(err) => {
+                debug!("received an unsupported dns message: {err}");
+                return message::error_response(raw_query, &err);
+            }
+        };
+
+        let Some(record_type) = query.record_type() else {
+            // the service provider can only resolve addresses, so pretend there are no other records
+            return Some(query.response(ResponseCode::NoError, &[], max_response_size));
+        };
+
+        debug!(
+            "resolving {} ({record_type:?}) through the mixnet",
+            query.name
+        );
+        let (code, addresses): (_, Vec<IpAddr>) =
+            match self.resolve(query.name.clone(), record_type).await {
+                DnsQueryResult::Resolved { addresses } => (ResponseCode::NoError, addresses),
+                DnsQueryResult::Refused => {
+                    info!("the service provider refused to resolve {}", query.name);
+                    (ResponseCode::Refused, Vec::new())
+                }
+                DnsQueryResult::Failed { message } => {
+                    debug!("failed to resolve {}: {message}", query.name);
+                    (ResponseCode::ServerFailure, Vec::new())
+                }
+            };
+        Some(query.response(code, &addresses, max_response_size))
+    }
+}
+
+/// Local DNS server listening for queries over both UDP and TCP on the same address.
+pub(crate) struct DnsStub {
+    udp_socket: Arc<UdpSocket>,
+    tcp_listener: TcpListener,
+    resolver: MixnetResolver,
+    shutdown: TaskClient,
+}
+
+impl DnsStub {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn bind(
+        bind_address: SocketAddr,
+        input_sender: InputMessageSender,
+        service_provider: Recipient,
+        self_address: Recipient,
+        client_config: client::Config,
+        packet_type: PacketType,
+        pending: PendingDnsQueries,
+        shutdown: TaskClient,
+    ) -> io::Result<Self> {
+        let udp_socket = UdpSocket::bind(bind_address).await?;
+        let tcp_listener = TcpListener::bind(bind_address).await?;
+        info!("Serving DNS queries on {bind_address}");
+
+        Ok(DnsStub {
+            udp_socket: Arc::new(udp_socket),
+            tcp_listener,
+            resolver: MixnetResolver {
+                input_sender,
+                service_provider,
+                self_address,
+                client_config,
+                packet_type,
+                pending,
+            },
+            shutdown,
+        })
+    }
+
+    pub(crate) async fn run(mut self) {
+        let mut buf = vec![0u8; MAX_UDP_MESSAGE_SIZE * 8];
+
+        loop {
+            tokio::select! {
+                received = self.udp_socket.recv_from(&mut buf) => {
+                    match received {
+                        Ok((len, peer)) => self.handle_datagram(buf[..len].to_vec(), peer),
+                        Err(err) => warn!("failed to receive dns query: {err}"),
+                    }
+                }
+                accepted = self.tcp_listener.accept() => {
+                    match accepted {
+                        Ok((stream, peer)) => self.handle_connection(stream, peer),
+                        Err(err) => warn!("failed to accept dns connection: {err}"),
+                    }
+                }
+                _ = self.shutdown.recv() => {
+                    log::trace!("DnsStub: Received shutdown");
+                    log::debug!("DnsStub: Exiting");
+                    return;
+                }
+            }
+        }
+    }
+
+    fn handle_datagram(&self, raw_query: Vec<u8>, peer: SocketAddr) {
+        let socket = Arc::clone(&self.udp_socket);
+        let resolver = self.resolver.clone();
+
+        // answering a query takes a round trip through the mixnet, so don't block the others
+        tokio::spawn(async move {
+            if let Some(response) = resolver.answer(&raw_query, MAX_UDP_MESSAGE_SIZE).await {
+                if let Err(err) = socket.send_to(&response, peer).await {
+                    debug!("failed to send dns response to {peer}: {err}");
+                }
+            }
+        });
+    }
+
+    fn handle_connection(&self, mut stream: TcpStream, peer: SocketAddr) {
+        let resolver = self.resolver.clone();
+        let mut shutdown = self.shutdown.clone();
+        shutdown.mark_as_success();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                res = serve_tcp_connection(&resolver, &mut stream) => {
+                    if let Err(err) = res {
+                        debug!("dns connection from {peer} failed: {err}");
+                    }
+                }
+                _ = shutdown.recv() => {}
+            }
+        });
+    }
+}
+
+/// Answers the queries sent over a single TCP connection, each of them prefixed with its length.
+/// From: https://www.rfc-editor.org/rfc/rfc1035#section-4.2.2
+async fn serve_tcp_connection(resolver: &MixnetResolver, stream: &mut TcpStream) -> io::Result<()> {
+    loop {
+        let len = match tokio::time::timeout(TCP_IDLE_TIMEOUT, stream.read_u16()).await {
+            Ok(Ok(len)) => len as usize,
+            Ok(Err(err)) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Ok(Err(err)) => return Err(err),
+            Err(_) => return Ok(()),
+        };
+        let mut raw_query = vec![0u8; len];
+        stream.read_exact(&mut raw_query).await?;
+
+        let Some(response) = resolver.answer(&raw_query, u16::MAX as usize).await else {
+            return Ok(());
+        };
+        stream.write_u16(response.len() as u16).await?;
+        stream.write_all(&response).await?;
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::Socks5ClientCoreError;
+use crate::socks::dns::PendingDnsQueries;
 use futures::channel::mpsc;
 use futures::StreamExt;
 use log::*;
@@ -19,6 +20,7 @@ pub(crate) struct MixnetResponseListener {
     buffer_requester: ReceivedBufferRequestSender,
     mix_response_receiver: ReconstructedMessagesReceiver,
     controller_sender: ControllerSender,
+    pending_dns_queries: PendingDnsQueries,
     shutdown: TaskClient,
 }
 
@@ -41,6 +43,7 @@ impl MixnetResponseListener {
     pub(crate) fn new(
         buffer_requester: ReceivedBufferRequestSender,
         controller_sender: ControllerSender,
+        pending_dns_queries: PendingDnsQueries,
         shutdown: TaskClient,
     ) -> Self {
         let (mix_response_sender, mix_response_receiver) = mpsc::unbounded();
@@ -52,6 +55,7 @@ impl MixnetResponseListener {
             buffer_requester,
             mix_response_receiver,
             controller_sender,
+            pending_dns_queries,
             shutdown,
         }
     }
@@ -86,6 +90,23 @@ impl MixnetResponseListener {
                     .unwrap();
                 Ok(())
             }
+            Socks5ResponseContent::Datagram(datagram) => {
+                self.controller_sender
+                    .unbounded_send(ControllerCommand::new_send_datagram(datagram))
+                    .unwrap();
+                Ok(())
+            }
+            Socks5ResponseContent::Bind(response) => {
+                self.controller_sender
+                    .unbounded_send(ControllerCommand::SendBindResponse { response })
+                    .unwrap();
+                Ok(())
+            }
+            Socks5ResponseContent::DnsQuery(response) => {
+                self.pending_dns_queries
+                    .complete(response.query_id, response.result);
+                Ok(())
+            }
             Socks5ResponseContent::Query(response) => {
                 error!("received a query response which we don't know how to handle yet!");
                 error!("got: {:?}", response);
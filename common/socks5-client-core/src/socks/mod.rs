@@ -6,10 +6,12 @@ use self::types::SocksProxyError;
 
 pub mod authentication;
 pub(crate) mod client;
+pub(crate) mod dns;
 pub(crate) mod mixnet_responses;
 mod request;
 pub mod server;
 pub mod types;
+mod udp;
 pub mod utils;
 
 /// Version of socks
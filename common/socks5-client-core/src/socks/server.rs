@@ -1,7 +1,10 @@
 use crate::error::Socks5ClientCoreError;
 
 use super::{
-    authentication::Authenticator, client::SocksClient, mixnet_responses::MixnetResponseListener,
+    authentication::Authenticator,
+    client::SocksClient,
+    dns::{DnsStub, PendingDnsQueries},
+    mixnet_responses::MixnetResponseListener,
 };
 use crate::socks::client;
 use log::*;
@@ -21,6 +24,7 @@ use tokio::net::TcpListener;
 pub struct NymSocksServer {
     authenticator: Authenticator,
     listening_address: SocketAddr,
+    dns_bind_address: Option<SocketAddr>,
     service_provider: Recipient,
     self_address: Recipient,
     client_config: client::Config,
@@ -46,6 +50,7 @@ impl NymSocksServer {
         NymSocksServer {
             authenticator,
             listening_address: bind_adddress,
+            dns_bind_address: None,
             service_provider,
             self_address,
             client_config,
@@ -55,6 +60,13 @@ impl NymSocksServer {
         }
     }
 
+    /// Additionally serve DNS queries on the provided address, resolving them through the mixnet.
+    #[must_use]
+    pub(crate) fn with_dns_stub(mut self, dns_bind_address: Option<SocketAddr>) -> Self {
+        self.dns_bind_address = dns_bind_address;
+        self
+    }
+
     /// Set up the listener and initiate connection handling when something
     /// connects to the server.
     pub(crate) async fn serve(
@@ -79,15 +91,36 @@ impl NymSocksServer {
         });
 
         // listener for mix messages
+        let pending_dns_queries = PendingDnsQueries::default();
         let mut mixnet_response_listener = MixnetResponseListener::new(
             buffer_requester,
             controller_sender.clone(),
+            pending_dns_queries.clone(),
             self.shutdown.clone(),
         );
         tokio::spawn(async move {
             mixnet_response_listener.run().await;
         });
 
+        // local resolver forwarding dns queries to the service provider
+        if let Some(dns_bind_address) = self.dns_bind_address {
+            let dns_stub = DnsStub::bind(
+                dns_bind_address,
+                input_sender.clone(),
+                self.service_provider,
+                self.self_address,
+                self.client_config,
+                self.packet_type,
+                pending_dns_queries,
+                self.shutdown.clone(),
+            )
+            .await
+            .tap_err(|err| {
+                log::error!("Failed to bind the dns stub to {dns_bind_address}: {err}")
+            })?;
+            tokio::spawn(dns_stub.run());
+        }
+
         // TODO:, if required, there should be another task here responsible for control requests.
         // it should get `input_sender` to send actual requests into the mixnet
         // and some channel that connects it from `MixnetResponseListener` to receive
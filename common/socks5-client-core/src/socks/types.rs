@@ -84,11 +84,23 @@ pub enum SocksProxyError {
         source: Socks5RequestError,
     },
 
-    #[error("SOCKS5 UDP not (yet) supported")]
-    UdpNotSupported,
+    #[error("failed to bind the UDP relay socket: {source}")]
+    UdpRelayBindFailure {
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("received malformed UDP datagram")]
+    MalformedUdpDatagram,
+
+    #[error("received fragmented UDP datagram - fragmentation is not supported")]
+    FragmentedUdpDatagram,
+
+    #[error("the service provider failed to complete the BIND request: {message}")]
+    BindFailure { message: String },
 
-    #[error("SOCKS5 BIND not (yet) supported")]
-    BindNotSupported,
+    #[error("timed out while waiting for the service provider to complete the BIND request")]
+    BindTimeout,
 }
 
 /// DST.addr variant types
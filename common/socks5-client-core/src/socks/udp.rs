@@ -0,0 +1,176 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encoding and decoding of the UDP request header used by the SOCKS5 UDP ASSOCIATE command.
+//! From: https://www.rfc-editor.org/rfc/rfc1928#section-7
+//!
+//! +----+------+------+----------+----------+----------+
+//! |RSV | FRAG | ATYP | DST.ADDR | DST.PORT |   DATA   |
+//! +----+------+------+----------+----------+----------+
+//! | 2  |  1   |  1   | Variable |    2     | Variable |
+//! +----+------+------+----------+----------+----------+
+
+use super::types::{AddrType, SocksProxyError};
+use super::utils as socks_utils;
+use nym_socks5_requests::RemoteAddress;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// The biggest possible UDP datagram we might receive from the local client.
+pub(crate) const MAX_UDP_DATAGRAM_SIZE: usize = 65535;
+
+/// Parses the UDP request header of a datagram received from the local client returning
+/// the destination address alongside the actual payload.
+pub(crate) fn parse_udp_datagram(b: &[u8]) -> Result<(RemoteAddress, &[u8]), SocksProxyError> {
+    // RSV (2) || FRAG (1) || ATYP (1)
+    if b.len() < 4 {
+        return Err(SocksProxyError::MalformedUdpDatagram);
+    }
+
+    // we don't support fragmentation, as permitted by the RFC, so drop anything that is fragmented
+    if b[2] != 0 {
+        return Err(SocksProxyError::FragmentedUdpDatagram);
+    }
+
+    let addr_type = AddrType::from(b[3] as usize).ok_or(SocksProxyError::MalformedUdpDatagram)?;
+    let rest = &b[4..];
+
+    let (host, rest) = match addr_type {
+        AddrType::V4 => {
+            let addr: [u8; 4] = rest
+                .get(..4)
+                .and_then(|addr| addr.try_into().ok())
+                .ok_or(SocksProxyError::MalformedUdpDatagram)?;
+            (Ipv4Addr::from(addr).to_string(), &rest[4..])
+        }
+        AddrType::V6 => {
+            let addr: [u8; 16] = rest
+                .get(..16)
+                .and_then(|addr| addr.try_into().ok())
+                .ok_or(SocksProxyError::MalformedUdpDatagram)?;
+            (format!("[{}]", Ipv6Addr::from(addr)), &rest[16..])
+        }
+        AddrType::Domain => {
+            let domain_length =
+                *rest.first().ok_or(SocksProxyError::MalformedUdpDatagram)? as usize;
+            let domain = rest
+                .get(1..1 + domain_length)
+                .ok_or(SocksProxyError::MalformedUdpDatagram)?;
+            (
+                String::from_utf8_lossy(domain).to_string(),
+                &rest[1 + domain_length..],
+            )
+        }
+    };
+
+    if rest.len() < 2 {
+        return Err(SocksProxyError::MalformedUdpDatagram);
+    }
+    let port = u16::from_be_bytes([rest[0], rest[1]]);
+
+    Ok((format!("{host}:{port}"), &rest[2..]))
+}
+
+/// Attaches the UDP request header to the datagram received from the specified source
+/// so that it could be forwarded to the local client.
+pub(crate) fn encode_udp_datagram(
+    source: &RemoteAddress,
+    data: &[u8],
+) -> Result<Vec<u8>, SocksProxyError> {
+    let encoded_address = match source.parse::<SocketAddr>() {
+        Ok(addr) => socks_utils::encode_socket_address(addr),
+        Err(_) => {
+            // if it's not an ip address, it must have been a domain
+            let (host, port) = source
+                .rsplit_once(':')
+                .ok_or(SocksProxyError::MalformedUdpDatagram)?;
+            let port: u16 = port
+                .parse()
+                .map_err(|_| SocksProxyError::MalformedUdpDatagram)?;
+            if host.len() > u8::MAX as usize {
+                return Err(SocksProxyError::MalformedUdpDatagram);
+            }
+            [AddrType::Domain as u8, host.len() as u8]
+                .into_iter()
+                .chain(host.bytes())
+                .chain(port.to_be_bytes())
+                .collect()
+        }
+    };
+
+    // RSV || FRAG || ATYP || ADDR || PORT || DATA
+    Ok([0u8, 0, 0]
+        .into_iter()
+        .chain(encoded_address)
+        .chain(data.iter().copied())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_ipv4_datagram() {
+        let datagram = [0, 0, 0, 1, 1, 1, 1, 1, 0, 53, 42, 42];
+        let (remote, data) = parse_udp_datagram(&datagram).unwrap();
+        assert_eq!(remote, "1.1.1.1:53");
+        assert_eq!(data, &[42, 42]);
+    }
+
+    #[test]
+    fn parsing_ipv6_datagram() {
+        let mut datagram = vec![0, 0, 0, 4];
+        datagram.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        datagram.extend_from_slice(&[0, 53, 42]);
+
+        let (remote, data) = parse_udp_datagram(&datagram).unwrap();
+        assert_eq!(remote, "[2001:db8::1]:53");
+        assert_eq!(remote.parse::<SocketAddr>().unwrap().port(), 53);
+        assert_eq!(data, &[42]);
+    }
+
+    #[test]
+    fn parsing_domain_datagram() {
+        let mut datagram = vec![0, 0, 0, 3, 11];
+        datagram.extend_from_slice(b"example.com");
+        datagram.extend_from_slice(&[1, 187]);
+
+        let (remote, data) = parse_udp_datagram(&datagram).unwrap();
+        assert_eq!(remote, "example.com:443");
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn parsing_invalid_datagrams() {
+        assert!(matches!(
+            parse_udp_datagram(&[0, 0, 0]),
+            Err(SocksProxyError::MalformedUdpDatagram)
+        ));
+        assert!(matches!(
+            parse_udp_datagram(&[0, 0, 1, 1, 1, 1, 1, 1, 0, 53]),
+            Err(SocksProxyError::FragmentedUdpDatagram)
+        ));
+        assert!(matches!(
+            parse_udp_datagram(&[0, 0, 0, 2, 1, 1, 1, 1, 0, 53]),
+            Err(SocksProxyError::MalformedUdpDatagram)
+        ));
+        assert!(matches!(
+            parse_udp_datagram(&[0, 0, 0, 1, 1, 1, 1, 1, 0]),
+            Err(SocksProxyError::MalformedUdpDatagram)
+        ));
+        assert!(matches!(
+            parse_udp_datagram(&[0, 0, 0, 3, 11, 1, 2]),
+            Err(SocksProxyError::MalformedUdpDatagram)
+        ));
+    }
+
+    #[test]
+    fn encoding_is_inverse_of_parsing() {
+        for source in ["1.1.1.1:53", "[2001:db8::1]:1234", "example.com:443"] {
+            let encoded = encode_udp_datagram(&source.to_string(), &[1, 2, 3]).unwrap();
+            let (remote, data) = parse_udp_datagram(&encoded).unwrap();
+            assert_eq!(remote, source);
+            assert_eq!(data, &[1, 2, 3]);
+        }
+    }
+}
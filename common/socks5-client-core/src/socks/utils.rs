@@ -1,4 +1,5 @@
 use super::types::AddrType;
+use std::net::SocketAddr;
 
 /// Convert an AddrType and address to String
 pub(crate) fn pretty_print_addr(addr_type: &AddrType, addr: &[u8]) -> String {
@@ -22,3 +23,17 @@ pub(crate) fn pretty_print_addr(addr_type: &AddrType, addr: &[u8]) -> String {
         }
     }
 }
+
+/// Encode the socket address as ATYP || ADDR || PORT
+pub(crate) fn encode_socket_address(addr: SocketAddr) -> Vec<u8> {
+    match addr {
+        SocketAddr::V4(addr) => std::iter::once(AddrType::V4 as u8)
+            .chain(addr.ip().octets())
+            .chain(addr.port().to_be_bytes())
+            .collect(),
+        SocketAddr::V6(addr) => std::iter::once(AddrType::V6 as u8)
+            .chain(addr.ip().octets())
+            .chain(addr.port().to_be_bytes())
+            .collect(),
+    }
+}
@@ -5,10 +5,12 @@ use futures::channel::mpsc;
 use futures::StreamExt;
 use log::*;
 use nym_ordered_buffer::{OrderedMessageBuffer, ReadContiguousData};
-use nym_socks5_requests::{ConnectionId, SocketData};
+use nym_socks5_requests::{BindEvent, BindResponse, ConnectionId, Datagram, SocketData};
 use nym_task::connections::{ConnectionCommand, ConnectionCommandSender};
 use nym_task::TaskClient;
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::time::Instant;
 
 /// A generic message produced after reading from a socket/connection. It includes data that was
 /// actually read alongside boolean indicating whether the connection got closed so that
@@ -28,6 +30,29 @@ pub type ConnectionSender = mpsc::UnboundedSender<ConnectionMessage>;
 /// Receiver part of the [`ConnectionSender`]
 pub type ConnectionReceiver = mpsc::UnboundedReceiver<ConnectionMessage>;
 
+/// Channel responsible for sending datagrams that were received from mix network into particular UDP association.
+/// Unlike [`ConnectionSender`], the datagrams are not reordered nor buffered.
+pub type DatagramSender = mpsc::UnboundedSender<Datagram>;
+
+/// Receiver part of the [`DatagramSender`]
+pub type DatagramReceiver = mpsc::UnboundedReceiver<Datagram>;
+
+/// Channel responsible for notifying particular connection about the progress of its BIND request.
+pub type BindSender = mpsc::UnboundedSender<BindEvent>;
+
+/// Receiver part of the [`BindSender`]
+pub type BindReceiver = mpsc::UnboundedReceiver<BindEvent>;
+
+/// Maximum number of datagrams buffered for a single association that has not been established yet.
+const MAX_PENDING_DATAGRAMS: usize = 16;
+
+/// Maximum number of datagrams buffered across all associations that have not been established yet.
+const MAX_TOTAL_PENDING_DATAGRAMS: usize = 256;
+
+/// Datagrams buffered for an association that does not get established within this period
+/// are assumed to be unclaimed and get dropped.
+const PENDING_DATAGRAMS_TTL: Duration = Duration::from_secs(30);
+
 pub type ControllerSender = mpsc::UnboundedSender<ControllerCommand>;
 pub type ControllerReceiver = mpsc::UnboundedReceiver<ControllerCommand>;
 
@@ -42,12 +67,30 @@ pub enum ControllerCommand {
     Send {
         data: SocketData,
     },
+    InsertAssociation {
+        connection_id: ConnectionId,
+        datagram_sender: DatagramSender,
+    },
+    SendDatagram {
+        datagram: Datagram,
+    },
+    InsertBind {
+        connection_id: ConnectionId,
+        bind_sender: BindSender,
+    },
+    SendBindResponse {
+        response: BindResponse,
+    },
 }
 
 impl ControllerCommand {
     pub fn new_send(data: SocketData) -> Self {
         ControllerCommand::Send { data }
     }
+
+    pub fn new_send_datagram(datagram: Datagram) -> Self {
+        ControllerCommand::SendDatagram { datagram }
+    }
 }
 
 struct ActiveConnection {
@@ -72,6 +115,24 @@ impl ActiveConnection {
     }
 }
 
+struct PendingDatagrams {
+    first_received: Instant,
+    datagrams: Vec<Datagram>,
+}
+
+impl PendingDatagrams {
+    fn new() -> Self {
+        PendingDatagrams {
+            first_received: Instant::now(),
+            datagrams: Vec::new(),
+        }
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        now.duration_since(self.first_received) > PENDING_DATAGRAMS_TTL
+    }
+}
+
 #[derive(PartialEq, Eq)]
 pub enum BroadcastActiveConnections {
     On,
@@ -82,6 +143,8 @@ pub enum BroadcastActiveConnections {
 /// proxy.
 pub struct Controller {
     active_connections: HashMap<ConnectionId, ActiveConnection>,
+    active_associations: HashMap<ConnectionId, DatagramSender>,
+    pending_binds: HashMap<ConnectionId, BindSender>,
     receiver: ControllerReceiver,
 
     // TODO: this will need to be either completely removed (from code) or periodically cleaned
@@ -98,6 +161,12 @@ pub struct Controller {
     // un-order messages. Note we don't ever expect to have more than 1-2 messages per connection here
     pending_messages: HashMap<ConnectionId, Vec<SocketData>>,
 
+    // similarly, buffer for datagrams received before the udp association was established.
+    // since anyone can send datagrams for arbitrary connection ids, it's bounded both per association
+    // and overall, and the entries that never get claimed are periodically removed
+    pending_datagrams: HashMap<ConnectionId, PendingDatagrams>,
+    total_pending_datagrams: usize,
+
     shutdown: TaskClient,
 }
 
@@ -110,10 +179,14 @@ impl Controller {
         (
             Controller {
                 active_connections: HashMap::new(),
+                active_associations: HashMap::new(),
+                pending_binds: HashMap::new(),
                 receiver,
                 recently_closed: HashSet::new(),
                 client_connection_tx,
                 pending_messages: HashMap::new(),
+                pending_datagrams: HashMap::new(),
+                total_pending_datagrams: 0,
                 shutdown,
             },
             sender,
@@ -140,9 +213,85 @@ impl Controller {
         }
     }
 
+    fn insert_association(&mut self, conn_id: ConnectionId, datagram_sender: DatagramSender) {
+        if self
+            .active_associations
+            .insert(conn_id, datagram_sender)
+            .is_some()
+        {
+            error!("Received a duplicate 'UdpAssociate'!")
+        } else if let Some(pending) = self.remove_pending_datagrams(conn_id) {
+            debug!("There were some pending datagrams for {conn_id}");
+            for datagram in pending.datagrams {
+                self.send_to_association(datagram)
+            }
+        }
+    }
+
+    fn remove_pending_datagrams(&mut self, conn_id: ConnectionId) -> Option<PendingDatagrams> {
+        let pending = self.pending_datagrams.remove(&conn_id)?;
+        self.total_pending_datagrams -= pending.datagrams.len();
+        Some(pending)
+    }
+
+    fn remove_expired_pending_datagrams(&mut self) {
+        let now = Instant::now();
+        let total_pending_datagrams = &mut self.total_pending_datagrams;
+        self.pending_datagrams.retain(|conn_id, pending| {
+            if pending.is_expired(now) {
+                debug!(
+                    "association {conn_id} has never been established - dropping {} pending datagrams",
+                    pending.datagrams.len()
+                );
+                *total_pending_datagrams -= pending.datagrams.len();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    fn buffer_pending_datagram(&mut self, datagram: Datagram) {
+        let conn_id = datagram.connection_id;
+        if self.total_pending_datagrams >= MAX_TOTAL_PENDING_DATAGRAMS {
+            self.remove_expired_pending_datagrams();
+        }
+        if self.total_pending_datagrams >= MAX_TOTAL_PENDING_DATAGRAMS {
+            debug!("Too many datagrams are waiting for their associations - dropping the one for {conn_id}");
+            return;
+        }
+
+        let pending = self
+            .pending_datagrams
+            .entry(conn_id)
+            .or_insert_with(PendingDatagrams::new);
+        // datagrams are inherently unreliable, so don't bother buffering too many of them
+        if pending.datagrams.len() < MAX_PENDING_DATAGRAMS {
+            debug!("Received a datagram before 'UdpAssociate' - going to buffer it");
+            pending.datagrams.push(datagram);
+            self.total_pending_datagrams += 1;
+        }
+    }
+
+    fn insert_bind(&mut self, conn_id: ConnectionId, bind_sender: BindSender) {
+        if self.pending_binds.insert(conn_id, bind_sender).is_some() {
+            error!("Received a duplicate 'Bind'!")
+        }
+    }
+
     fn remove_connection(&mut self, conn_id: ConnectionId) {
         debug!("Removing {conn_id} from controller");
-        if self.active_connections.remove(&conn_id).is_none() {
+        self.pending_binds.remove(&conn_id);
+        self.remove_pending_datagrams(conn_id);
+        if self.active_connections.remove(&conn_id).is_none()
+            && self.active_associations.remove(&conn_id).is_none()
+        {
+            // udp associations might get removed both by the explicit request and by their
+            // own termination
+            if self.recently_closed.contains(&conn_id) {
+                debug!("connection with id {conn_id} has already been removed");
+                return;
+            }
             error!("tried to remove non-existing connection with id: {conn_id}",)
         }
         self.recently_closed.insert(conn_id);
@@ -209,9 +358,39 @@ impl Controller {
         }
     }
 
+    fn send_to_association(&mut self, datagram: Datagram) {
+        let conn_id = datagram.connection_id;
+        if let Some(datagram_sender) = self.active_associations.get(&conn_id) {
+            if let Err(err) = datagram_sender.unbounded_send(datagram) {
+                error!("failed to send on the active association channel: {err}");
+            }
+        } else if !self.recently_closed.contains(&conn_id) {
+            self.buffer_pending_datagram(datagram)
+        } else {
+            debug!(
+                "Received a datagram for closed association {conn_id} ({} bytes were dropped)",
+                datagram.data.len()
+            )
+        }
+    }
+
+    fn send_bind_response(&mut self, response: BindResponse) {
+        let conn_id = response.connection_id;
+        if let Some(bind_sender) = self.pending_binds.get(&conn_id) {
+            if let Err(err) = bind_sender.unbounded_send(response.event) {
+                error!("failed to send on the bind channel: {err}");
+            }
+        } else {
+            warn!("Received a bind response for unknown connection {conn_id}")
+        }
+    }
+
     pub async fn run(&mut self) {
+        let mut pending_datagrams_cleanup = tokio::time::interval(PENDING_DATAGRAMS_TTL);
+
         loop {
             tokio::select! {
+                _ = pending_datagrams_cleanup.tick() => self.remove_expired_pending_datagrams(),
                 command = self.receiver.next() => match command {
                     Some(ControllerCommand::Send{data}) => {
                         self.send_to_connection(data)
@@ -220,6 +399,18 @@ impl Controller {
                         self.insert_connection(connection_id, connection_sender)
                     }
                     Some(ControllerCommand::Remove{ connection_id }) => self.remove_connection(connection_id),
+                    Some(ControllerCommand::InsertAssociation{connection_id, datagram_sender}) => {
+                        self.insert_association(connection_id, datagram_sender)
+                    }
+                    Some(ControllerCommand::SendDatagram{datagram}) => {
+                        self.send_to_association(datagram)
+                    }
+                    Some(ControllerCommand::InsertBind{connection_id, bind_sender}) => {
+                        self.insert_bind(connection_id, bind_sender)
+                    }
+                    Some(ControllerCommand::SendBindResponse{response}) => {
+                        self.send_bind_response(response)
+                    }
                     None => {
                         log::trace!("SOCKS5 Controller: Stopping since channel closed");
                         break;
@@ -231,3 +422,122 @@ impl Controller {
         log::debug!("SOCKS5 Controller: Exiting");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> Controller {
+        let (client_connection_tx, _) = mpsc::unbounded();
+        Controller::new(client_connection_tx, TaskClient::dummy()).0
+    }
+
+    fn datagram(connection_id: ConnectionId, content: u8) -> Datagram {
+        Datagram::new(connection_id, "1.1.1.1:53".to_string(), vec![content])
+    }
+
+    fn received(receiver: &mut DatagramReceiver) -> Vec<Datagram> {
+        let mut received = Vec::new();
+        while let Ok(Some(datagram)) = receiver.try_next() {
+            received.push(datagram)
+        }
+        received
+    }
+
+    #[test]
+    fn datagrams_are_forwarded_to_established_associations() {
+        let mut controller = controller();
+        let (datagram_sender, mut datagram_receiver) = mpsc::unbounded();
+        controller.insert_association(42, datagram_sender);
+
+        controller.send_to_association(datagram(42, 1));
+        controller.send_to_association(datagram(42, 2));
+        assert_eq!(
+            received(&mut datagram_receiver),
+            vec![datagram(42, 1), datagram(42, 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn datagrams_received_before_association_are_delivered_once_its_established() {
+        let mut controller = controller();
+        controller.send_to_association(datagram(42, 1));
+        controller.send_to_association(datagram(42, 2));
+        assert_eq!(controller.total_pending_datagrams, 2);
+
+        let (datagram_sender, mut datagram_receiver) = mpsc::unbounded();
+        controller.insert_association(42, datagram_sender);
+        assert_eq!(
+            received(&mut datagram_receiver),
+            vec![datagram(42, 1), datagram(42, 2)]
+        );
+        assert!(controller.pending_datagrams.is_empty());
+        assert_eq!(controller.total_pending_datagrams, 0);
+    }
+
+    #[tokio::test]
+    async fn pending_datagrams_are_bounded_per_association() {
+        let mut controller = controller();
+        for i in 0..MAX_PENDING_DATAGRAMS + 5 {
+            controller.send_to_association(datagram(42, i as u8));
+        }
+        assert_eq!(controller.total_pending_datagrams, MAX_PENDING_DATAGRAMS);
+
+        let (datagram_sender, mut datagram_receiver) = mpsc::unbounded();
+        controller.insert_association(42, datagram_sender);
+        assert_eq!(
+            received(&mut datagram_receiver).len(),
+            MAX_PENDING_DATAGRAMS
+        );
+    }
+
+    #[tokio::test]
+    async fn pending_datagrams_are_bounded_overall() {
+        let mut controller = controller();
+        for conn_id in 0..(MAX_TOTAL_PENDING_DATAGRAMS * 2) as ConnectionId {
+            controller.send_to_association(datagram(conn_id, 0));
+        }
+        assert_eq!(
+            controller.total_pending_datagrams,
+            MAX_TOTAL_PENDING_DATAGRAMS
+        );
+        assert_eq!(
+            controller.pending_datagrams.len(),
+            MAX_TOTAL_PENDING_DATAGRAMS
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unclaimed_pending_datagrams_expire() {
+        let mut controller = controller();
+        for conn_id in 0..MAX_TOTAL_PENDING_DATAGRAMS as ConnectionId {
+            controller.send_to_association(datagram(conn_id, 0));
+        }
+
+        // nothing can be buffered until the existing entries expire
+        let late = MAX_TOTAL_PENDING_DATAGRAMS as ConnectionId;
+        controller.send_to_association(datagram(late, 0));
+        assert!(!controller.pending_datagrams.contains_key(&late));
+
+        tokio::time::advance(PENDING_DATAGRAMS_TTL + Duration::from_secs(1)).await;
+        controller.send_to_association(datagram(late, 0));
+        assert_eq!(controller.pending_datagrams.len(), 1);
+        assert_eq!(controller.total_pending_datagrams, 1);
+
+        tokio::time::advance(PENDING_DATAGRAMS_TTL + Duration::from_secs(1)).await;
+        controller.remove_expired_pending_datagrams();
+        assert!(controller.pending_datagrams.is_empty());
+        assert_eq!(controller.total_pending_datagrams, 0);
+    }
+
+    #[tokio::test]
+    async fn datagrams_for_removed_associations_are_dropped() {
+        let mut controller = controller();
+        controller.send_to_association(datagram(42, 1));
+        controller.remove_connection(42);
+        assert_eq!(controller.total_pending_datagrams, 0);
+
+        controller.send_to_association(datagram(42, 2));
+        assert!(controller.pending_datagrams.is_empty());
+    }
+}
@@ -192,6 +192,80 @@ impl SocketData {
     }
 }
 
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MalformedDatagramError {
+    #[error("not enough bytes to recover the connection id")]
+    ConnectionIdTooShort,
+
+    #[error("not enough bytes to recover the length of the address")]
+    AddressLengthTooShort,
+
+    #[error("not enough bytes to recover the address")]
+    AddressTooShort,
+}
+
+/// A single UDP datagram exchanged within a UDP association.
+/// In requests the `remote_addr` is the destination of the datagram,
+/// while in responses it is the address the datagram has been received from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Datagram {
+    pub connection_id: ConnectionId,
+    pub remote_addr: RemoteAddress,
+    pub data: Vec<u8>,
+}
+
+impl Datagram {
+    pub fn new(connection_id: ConnectionId, remote_addr: RemoteAddress, data: Vec<u8>) -> Self {
+        Datagram {
+            connection_id,
+            remote_addr,
+            data,
+        }
+    }
+
+    pub fn try_from_bytes(b: &[u8]) -> Result<Datagram, MalformedDatagramError> {
+        if b.len() < mem::size_of::<ConnectionId>() {
+            return Err(MalformedDatagramError::ConnectionIdTooShort);
+        }
+        // the unwrap here is fine as we just ensured we have enough bytes
+        let connection_id = ConnectionId::from_be_bytes(b[..8].try_into().unwrap());
+
+        if b.len() < 10 {
+            return Err(MalformedDatagramError::AddressLengthTooShort);
+        }
+        let address_length = u16::from_be_bytes([b[8], b[9]]) as usize;
+        let address_end = 10 + address_length;
+        if b.len() < address_end {
+            return Err(MalformedDatagramError::AddressTooShort);
+        }
+        let remote_addr = String::from_utf8_lossy(&b[10..address_end]).to_string();
+
+        Ok(Datagram {
+            connection_id,
+            remote_addr,
+            data: b[address_end..].to_vec(),
+        })
+    }
+
+    // the serialization of the datagram looks as follows:
+    // CONNECTION_ID (8B) || ADDR_LEN (2B) || ADDR || DATA
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.into_bytes_iter().collect()
+    }
+
+    pub fn into_bytes_iter(self) -> impl Iterator<Item = u8> {
+        let remote_address_bytes = self.remote_addr.into_bytes();
+        let remote_address_bytes_len = remote_address_bytes.len() as u16;
+
+        self.connection_id
+            .to_be_bytes()
+            .into_iter()
+            .chain(remote_address_bytes_len.to_be_bytes())
+            .chain(remote_address_bytes)
+            .chain(self.data)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Socks5RequestError {
     #[error("failed to deserialize received request: {source}")]
@@ -213,6 +287,11 @@ pub enum Socks5RequestError {
     UnsupportedProtocolVersion {
         protocol_version: <Socks5Request as interface::ServiceProviderRequest>::ProtocolVersion,
     },
+
+    #[error("received a request that is not supported by the protocol version {protocol_version}")]
+    UnsupportedRequestForProtocolVersion {
+        protocol_version: <Socks5Request as interface::ServiceProviderRequest>::ProtocolVersion,
+    },
 }
 
 fn make_bincode_serializer() -> impl bincode::Options {
@@ -324,6 +403,38 @@ mod tests {
         }
     }
 
+    #[cfg(test)]
+    mod datagram_serialization {
+        use super::*;
+
+        #[test]
+        fn there_and_back() {
+            let datagram = Datagram::new(12345, "1.1.1.1:53".to_string(), vec![1, 2, 3]);
+            let serialized = datagram.clone().into_bytes();
+            assert_eq!(datagram, Datagram::try_from_bytes(&serialized).unwrap());
+
+            let empty = Datagram::new(42, "example.com:1234".to_string(), Vec::new());
+            let serialized = empty.clone().into_bytes();
+            assert_eq!(empty, Datagram::try_from_bytes(&serialized).unwrap());
+        }
+
+        #[test]
+        fn deserialization_errors() {
+            assert_eq!(
+                MalformedDatagramError::ConnectionIdTooShort,
+                Datagram::try_from_bytes(&[1, 2, 3, 4, 5, 6, 7]).unwrap_err()
+            );
+            assert_eq!(
+                MalformedDatagramError::AddressLengthTooShort,
+                Datagram::try_from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 0]).unwrap_err()
+            );
+            assert_eq!(
+                MalformedDatagramError::AddressTooShort,
+                Datagram::try_from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 0, 3, 1, 2]).unwrap_err()
+            );
+        }
+    }
+
     #[cfg(test)]
     mod interface_backwards_compatibility {
         use super::*;
@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    make_bincode_serializer, InsufficientSocketDataError, SocketData, Socks5ProtocolVersion,
-    Socks5RequestError, Socks5Response,
+    make_bincode_serializer, Datagram, InsufficientSocketDataError, MalformedDatagramError,
+    SocketData, Socks5ProtocolVersion, Socks5RequestError, Socks5Response,
 };
 use nym_service_providers_common::interface::{Serializable, ServiceProviderRequest};
 use nym_sphinx_addressing::clients::{Recipient, RecipientFormattingError};
@@ -15,6 +15,7 @@ use thiserror::Error;
 
 pub type ConnectionId = u64;
 pub type RemoteAddress = String;
+pub type DnsQueryId = u64;
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug)]
@@ -22,6 +23,11 @@ pub enum RequestFlag {
     Connect = 0,
     Send = 1,
     Query = 2,
+    UdpAssociate = 3,
+    Datagram = 4,
+    UdpDisassociate = 5,
+    Bind = 6,
+    DnsQuery = 7,
 }
 
 impl TryFrom<u8> for RequestFlag {
@@ -32,6 +38,11 @@ impl TryFrom<u8> for RequestFlag {
             _ if value == (RequestFlag::Connect as u8) => Ok(Self::Connect),
             _ if value == (RequestFlag::Send as u8) => Ok(Self::Send),
             _ if value == (RequestFlag::Query as u8) => Ok(Self::Query),
+            _ if value == (RequestFlag::UdpAssociate as u8) => Ok(Self::UdpAssociate),
+            _ if value == (RequestFlag::Datagram as u8) => Ok(Self::Datagram),
+            _ if value == (RequestFlag::UdpDisassociate as u8) => Ok(Self::UdpDisassociate),
+            _ if value == (RequestFlag::Bind as u8) => Ok(Self::Bind),
+            _ if value == (RequestFlag::DnsQuery as u8) => Ok(Self::DnsQuery),
             value => Err(RequestDeserializationError::UnknownRequestFlag { value }),
         }
     }
@@ -68,6 +79,15 @@ pub enum RequestDeserializationError {
 
     #[error(transparent)]
     InvalidSocketData(#[from] InsufficientSocketDataError),
+
+    #[error("malformed datagram: {0}")]
+    MalformedDatagram(#[from] MalformedDatagramError),
+
+    #[error("not enough bytes to recover the dns record type")]
+    DnsRecordTypeTooShort,
+
+    #[error("{value} is not a supported dns record type")]
+    UnknownDnsRecordType { value: u8 },
 }
 
 impl RequestDeserializationError {
@@ -102,6 +122,67 @@ pub struct SendRequest {
     pub data: SocketData,
 }
 
+#[derive(Clone, PartialEq, Eq)]
+pub struct UdpAssociateRequest {
+    pub conn_id: ConnectionId,
+    pub return_address: Option<Recipient>,
+}
+
+impl Debug for UdpAssociateRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UdpAssociateRequest")
+            .field("conn_id", &self.conn_id)
+            .field(
+                "return_address",
+                &self.return_address.map(|r| r.to_string()),
+            )
+            .finish()
+    }
+}
+
+/// Type of the records requested in a DNS query.
+/// Only the address records are supported as that's all the system resolver can give us.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DnsRecordType {
+    A = 0,
+    Aaaa = 1,
+}
+
+impl TryFrom<u8> for DnsRecordType {
+    type Error = RequestDeserializationError;
+
+    fn try_from(value: u8) -> Result<DnsRecordType, RequestDeserializationError> {
+        match value {
+            _ if value == (DnsRecordType::A as u8) => Ok(Self::A),
+            _ if value == (DnsRecordType::Aaaa as u8) => Ok(Self::Aaaa),
+            value => Err(RequestDeserializationError::UnknownDnsRecordType { value }),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct DnsQueryRequest {
+    pub query_id: DnsQueryId,
+    pub name: String,
+    pub record_type: DnsRecordType,
+    pub return_address: Option<Recipient>,
+}
+
+impl Debug for DnsQueryRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DnsQueryRequest")
+            .field("query_id", &self.query_id)
+            .field("name", &self.name)
+            .field("record_type", &self.record_type)
+            .field(
+                "return_address",
+                &self.return_address.map(|r| r.to_string()),
+            )
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum QueryRequest {
@@ -151,9 +232,16 @@ impl Serializable for Socks5Request {
         // TODO: handle the case then protocol version if less then the current one. Then we should
         // make sure to only respond with the same version
 
+        let content = Socks5RequestContent::try_from_bytes(&b[1..])?;
+        if content.requires_extended_interface() && !protocol_version.supports_extended_requests() {
+            return Err(Socks5RequestError::UnsupportedRequestForProtocolVersion {
+                protocol_version,
+            });
+        }
+
         Ok(Socks5Request {
             protocol_version,
-            content: Socks5RequestContent::try_from_bytes(&b[1..])?,
+            content,
         })
     }
 }
@@ -214,6 +302,67 @@ impl Socks5Request {
             content: Socks5RequestContent::Query(query),
         }
     }
+
+    pub fn new_udp_associate(
+        protocol_version: Socks5ProtocolVersion,
+        conn_id: ConnectionId,
+        return_address: Option<Recipient>,
+    ) -> Socks5Request {
+        Socks5Request {
+            protocol_version,
+            content: Socks5RequestContent::new_udp_associate(conn_id, return_address),
+        }
+    }
+
+    pub fn new_datagram(
+        protocol_version: Socks5ProtocolVersion,
+        datagram: Datagram,
+    ) -> Socks5Request {
+        Socks5Request {
+            protocol_version,
+            content: Socks5RequestContent::Datagram(datagram),
+        }
+    }
+
+    pub fn new_udp_disassociate(
+        protocol_version: Socks5ProtocolVersion,
+        conn_id: ConnectionId,
+    ) -> Socks5Request {
+        Socks5Request {
+            protocol_version,
+            content: Socks5RequestContent::UdpDisassociate(conn_id),
+        }
+    }
+
+    pub fn new_bind(
+        protocol_version: Socks5ProtocolVersion,
+        conn_id: ConnectionId,
+        expected_peer: RemoteAddress,
+        return_address: Option<Recipient>,
+    ) -> Socks5Request {
+        Socks5Request {
+            protocol_version,
+            content: Socks5RequestContent::new_bind(conn_id, expected_peer, return_address),
+        }
+    }
+
+    pub fn new_dns_query(
+        protocol_version: Socks5ProtocolVersion,
+        query_id: DnsQueryId,
+        name: String,
+        record_type: DnsRecordType,
+        return_address: Option<Recipient>,
+    ) -> Socks5Request {
+        Socks5Request {
+            protocol_version,
+            content: Socks5RequestContent::new_dns_query(
+                query_id,
+                name,
+                record_type,
+                return_address,
+            ),
+        }
+    }
 }
 
 /// A request from a SOCKS5 client that a Nym Socks5 service provider should
@@ -229,9 +378,42 @@ pub enum Socks5RequestContent {
     Send(SendRequest),
 
     Query(QueryRequest),
+
+    /// Open a new UDP association, i.e. a UDP socket used for relaying datagrams
+    /// to arbitrary remote addresses.
+    /// All datagrams received on this `ConnectionId` should come back to the specified `Recipient`
+    UdpAssociate(Box<UdpAssociateRequest>),
+
+    /// Send a single datagram to the specified remote through an existing UDP association.
+    Datagram(Datagram),
+
+    /// Close an existing UDP association.
+    UdpDisassociate(ConnectionId),
+
+    /// Start listening for a single inbound TCP connection coming from the `RemoteAddress`
+    /// (i.e. the expected peer) and proxy it once accepted.
+    /// All responses produced on this `ConnectionId` should come back to the specified `Recipient`
+    Bind(Box<ConnectRequest>),
+
+    /// Resolve the records of the specified type for the provided domain name.
+    /// The answer should come back to the specified `Recipient`
+    DnsQuery(Box<DnsQueryRequest>),
 }
 
 impl Socks5RequestContent {
+    /// Checks whether this request has been introduced alongside the extended interface, and thus
+    /// is not understood by the network requesters running the older ones.
+    pub fn requires_extended_interface(&self) -> bool {
+        matches!(
+            self,
+            Socks5RequestContent::UdpAssociate(_)
+                | Socks5RequestContent::Datagram(_)
+                | Socks5RequestContent::UdpDisassociate(_)
+                | Socks5RequestContent::Bind(_)
+                | Socks5RequestContent::DnsQuery(_)
+        )
+    }
+
     /// Construct a new Request::Connect instance
     pub fn new_connect(
         conn_id: ConnectionId,
@@ -250,6 +432,45 @@ impl Socks5RequestContent {
         Socks5RequestContent::Send(SendRequest { data })
     }
 
+    /// Construct a new Request::Bind instance
+    pub fn new_bind(
+        conn_id: ConnectionId,
+        expected_peer: RemoteAddress,
+        return_address: Option<Recipient>,
+    ) -> Socks5RequestContent {
+        Socks5RequestContent::Bind(Box::new(ConnectRequest {
+            conn_id,
+            remote_addr: expected_peer,
+            return_address,
+        }))
+    }
+
+    /// Construct a new Request::UdpAssociate instance
+    pub fn new_udp_associate(
+        conn_id: ConnectionId,
+        return_address: Option<Recipient>,
+    ) -> Socks5RequestContent {
+        Socks5RequestContent::UdpAssociate(Box::new(UdpAssociateRequest {
+            conn_id,
+            return_address,
+        }))
+    }
+
+    /// Construct a new Request::DnsQuery instance
+    pub fn new_dns_query(
+        query_id: DnsQueryId,
+        name: String,
+        record_type: DnsRecordType,
+        return_address: Option<Recipient>,
+    ) -> Socks5RequestContent {
+        Socks5RequestContent::DnsQuery(Box::new(DnsQueryRequest {
+            query_id,
+            name,
+            record_type,
+            return_address,
+        }))
+    }
+
     /// Deserialize the request type, connection id, destination address and port,
     /// and the request body from bytes.
     ///
@@ -271,6 +492,21 @@ impl Socks5RequestContent {
     // send:
     // RequestFlag::Send || CONN_ID || LOCAL_CLOSED || DATA
     // where DATA: SEQ || TRUE_DATA
+    //
+    // udp associate:
+    // RequestFlag::UdpAssociate || CONN_ID || <RETURN_ADDR>
+    //
+    // datagram:
+    // RequestFlag::Datagram || CONN_ID || ADDR_LEN || ADDR || DATA
+    //
+    // udp disassociate:
+    // RequestFlag::UdpDisassociate || CONN_ID
+    //
+    // bind (uses the same format as connect):
+    // RequestFlag::Bind || CONN_ID || ADDR_LEN || ADDR || <RETURN_ADDR>
+    //
+    // dns query:
+    // RequestFlag::DnsQuery || QUERY_ID || RECORD_TYPE || NAME_LEN || NAME || <RETURN_ADDR>
 
     pub fn try_from_bytes(b: &[u8]) -> Result<Socks5RequestContent, RequestDeserializationError> {
         // each request needs to at least contain flag and ConnectionId
@@ -279,7 +515,7 @@ impl Socks5RequestContent {
         }
 
         match RequestFlag::try_from(b[0])? {
-            RequestFlag::Connect => {
+            flag @ (RequestFlag::Connect | RequestFlag::Bind) => {
                 if b.len() < 9 {
                     return Err(RequestDeserializationError::ConnectionIdTooShort);
                 }
@@ -308,26 +544,21 @@ impl Socks5RequestContent {
                 // just a temporary reference to mid-slice for ease of use
                 let recipient_data_bytes = &connect_request_bytes[address_end..];
 
-                let return_address = if recipient_data_bytes.is_empty() {
-                    None
+                let return_address = parse_return_address(recipient_data_bytes)?;
+
+                if matches!(flag, RequestFlag::Bind) {
+                    Ok(Socks5RequestContent::new_bind(
+                        conn_id,
+                        remote_address,
+                        return_address,
+                    ))
                 } else {
-                    if recipient_data_bytes.len() != Recipient::LEN {
-                        return Err(RequestDeserializationError::ReturnAddressTooShort);
-                    }
-
-                    let mut return_bytes = [0u8; Recipient::LEN];
-                    return_bytes.copy_from_slice(&recipient_data_bytes[..Recipient::LEN]);
-                    Some(
-                        Recipient::try_from_bytes(return_bytes)
-                            .map_err(RequestDeserializationError::MalformedReturnAddress)?,
-                    )
-                };
-
-                Ok(Socks5RequestContent::new_connect(
-                    conn_id,
-                    remote_address,
-                    return_address,
-                ))
+                    Ok(Socks5RequestContent::new_connect(
+                        conn_id,
+                        remote_address,
+                        return_address,
+                    ))
+                }
             }
             RequestFlag::Send => Ok(Socks5RequestContent::Send(SendRequest {
                 data: SocketData::try_from_request_bytes(&b[1..])?,
@@ -337,6 +568,47 @@ impl Socks5RequestContent {
                 let query = make_bincode_serializer().deserialize(&b[1..])?;
                 Ok(Socks5RequestContent::Query(query))
             }
+            RequestFlag::UdpAssociate => {
+                let conn_id = parse_connection_id(b)?;
+                let return_address = parse_return_address(&b[9..])?;
+                Ok(Socks5RequestContent::new_udp_associate(
+                    conn_id,
+                    return_address,
+                ))
+            }
+            RequestFlag::Datagram => Ok(Socks5RequestContent::Datagram(Datagram::try_from_bytes(
+                &b[1..],
+            )?)),
+            RequestFlag::UdpDisassociate => Ok(Socks5RequestContent::UdpDisassociate(
+                parse_connection_id(b)?,
+            )),
+            RequestFlag::DnsQuery => {
+                // the query id is serialized the same way as the connection id
+                let query_id = parse_connection_id(b)?;
+                let record_type = b
+                    .get(9)
+                    .ok_or(RequestDeserializationError::DnsRecordTypeTooShort)?;
+                let record_type = DnsRecordType::try_from(*record_type)?;
+
+                let name_bytes = &b[10..];
+                if name_bytes.len() < 2 {
+                    return Err(RequestDeserializationError::AddressLengthTooShort);
+                }
+                let name_length = u16::from_be_bytes([name_bytes[0], name_bytes[1]]) as usize;
+                let name_end = 2 + name_length;
+                if name_bytes.len() < name_end {
+                    return Err(RequestDeserializationError::AddressTooShort);
+                }
+                let name = String::from_utf8_lossy(&name_bytes[2..name_end]).to_string();
+                let return_address = parse_return_address(&name_bytes[name_end..])?;
+
+                Ok(Socks5RequestContent::new_dns_query(
+                    query_id,
+                    name,
+                    record_type,
+                    return_address,
+                ))
+            }
         }
     }
 
@@ -346,21 +618,9 @@ impl Socks5RequestContent {
     pub fn into_bytes(self) -> Vec<u8> {
         match self {
             // connect is: CONN_FLAG || CONN_ID || REMOTE_LEN || REMOTE || RETURN
-            Socks5RequestContent::Connect(req) => {
-                let remote_address_bytes = req.remote_addr.into_bytes();
-                let remote_address_bytes_len = remote_address_bytes.len() as u16;
-
-                let iter = std::iter::once(RequestFlag::Connect as u8)
-                    .chain(req.conn_id.to_be_bytes())
-                    .chain(remote_address_bytes_len.to_be_bytes())
-                    .chain(remote_address_bytes);
-
-                if let Some(return_address) = req.return_address {
-                    iter.chain(return_address.to_bytes()).collect()
-                } else {
-                    iter.collect()
-                }
-            }
+            Socks5RequestContent::Connect(req) => connect_request_bytes(RequestFlag::Connect, *req),
+            // bind is: BIND_FLAG || CONN_ID || REMOTE_LEN || REMOTE || RETURN
+            Socks5RequestContent::Bind(req) => connect_request_bytes(RequestFlag::Bind, *req),
             Socks5RequestContent::Send(req) => std::iter::once(RequestFlag::Send as u8)
                 .chain(req.data.into_request_bytes_iter())
                 .collect(),
@@ -377,10 +637,89 @@ impl Socks5RequestContent {
                     .chain(query_bytes)
                     .collect()
             }
+            // udp associate is: UDP_ASSOCIATE_FLAG || CONN_ID || RETURN
+            Socks5RequestContent::UdpAssociate(req) => {
+                let iter = std::iter::once(RequestFlag::UdpAssociate as u8)
+                    .chain(req.conn_id.to_be_bytes());
+
+                if let Some(return_address) = req.return_address {
+                    iter.chain(return_address.to_bytes()).collect()
+                } else {
+                    iter.collect()
+                }
+            }
+            Socks5RequestContent::Datagram(datagram) => {
+                std::iter::once(RequestFlag::Datagram as u8)
+                    .chain(datagram.into_bytes_iter())
+                    .collect()
+            }
+            Socks5RequestContent::UdpDisassociate(conn_id) => {
+                std::iter::once(RequestFlag::UdpDisassociate as u8)
+                    .chain(conn_id.to_be_bytes())
+                    .collect()
+            }
+            // dns query is: DNS_QUERY_FLAG || QUERY_ID || RECORD_TYPE || NAME_LEN || NAME || RETURN
+            Socks5RequestContent::DnsQuery(req) => {
+                let name_bytes = req.name.into_bytes();
+                let name_bytes_len = name_bytes.len() as u16;
+
+                let iter = std::iter::once(RequestFlag::DnsQuery as u8)
+                    .chain(req.query_id.to_be_bytes())
+                    .chain(std::iter::once(req.record_type as u8))
+                    .chain(name_bytes_len.to_be_bytes())
+                    .chain(name_bytes);
+
+                if let Some(return_address) = req.return_address {
+                    iter.chain(return_address.to_bytes()).collect()
+                } else {
+                    iter.collect()
+                }
+            }
         }
     }
 }
 
+fn connect_request_bytes(flag: RequestFlag, req: ConnectRequest) -> Vec<u8> {
+    let remote_address_bytes = req.remote_addr.into_bytes();
+    let remote_address_bytes_len = remote_address_bytes.len() as u16;
+
+    let iter = std::iter::once(flag as u8)
+        .chain(req.conn_id.to_be_bytes())
+        .chain(remote_address_bytes_len.to_be_bytes())
+        .chain(remote_address_bytes);
+
+    if let Some(return_address) = req.return_address {
+        iter.chain(return_address.to_bytes()).collect()
+    } else {
+        iter.collect()
+    }
+}
+
+// note: `b` includes the request flag
+fn parse_connection_id(b: &[u8]) -> Result<ConnectionId, RequestDeserializationError> {
+    if b.len() < 9 {
+        return Err(RequestDeserializationError::ConnectionIdTooShort);
+    }
+    Ok(u64::from_be_bytes([
+        b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8],
+    ]))
+}
+
+fn parse_return_address(b: &[u8]) -> Result<Option<Recipient>, RequestDeserializationError> {
+    if b.is_empty() {
+        return Ok(None);
+    }
+    if b.len() != Recipient::LEN {
+        return Err(RequestDeserializationError::ReturnAddressTooShort);
+    }
+
+    let mut return_bytes = [0u8; Recipient::LEN];
+    return_bytes.copy_from_slice(&b[..Recipient::LEN]);
+    Recipient::try_from_bytes(return_bytes)
+        .map(Some)
+        .map_err(RequestDeserializationError::MalformedReturnAddress)
+}
+
 #[cfg(test)]
 mod request_deserialization_tests {
     use super::*;
@@ -715,4 +1054,182 @@ mod request_deserialization_tests {
             assert_eq!(description, description2);
         }
     }
+    #[cfg(test)]
+    mod udp_requests {
+        use super::*;
+
+        #[test]
+        fn udp_associate_serialize_there_and_back() {
+            let recipient = Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap();
+
+            let anonymous = Socks5RequestContent::new_udp_associate(42, None);
+            let bytes = anonymous.clone().into_bytes();
+            assert_eq!(bytes, vec![3, 0, 0, 0, 0, 0, 0, 0, 42]);
+            assert_eq!(
+                anonymous,
+                Socks5RequestContent::try_from_bytes(&bytes).unwrap()
+            );
+
+            let with_return = Socks5RequestContent::new_udp_associate(42, Some(recipient));
+            let bytes = with_return.clone().into_bytes();
+            assert_eq!(
+                with_return,
+                Socks5RequestContent::try_from_bytes(&bytes).unwrap()
+            );
+
+            match Socks5RequestContent::try_from_bytes(&bytes[..bytes.len() - 1]).unwrap_err() {
+                RequestDeserializationError::ReturnAddressTooShort => {}
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn datagram_serialize_there_and_back() {
+            let datagram = Socks5RequestContent::Datagram(Datagram::new(
+                42,
+                "1.1.1.1:53".to_string(),
+                vec![1, 2, 3],
+            ));
+            let bytes = datagram.clone().into_bytes();
+            assert_eq!(bytes[0], RequestFlag::Datagram as u8);
+            assert_eq!(
+                datagram,
+                Socks5RequestContent::try_from_bytes(&bytes).unwrap()
+            );
+
+            match Socks5RequestContent::try_from_bytes(&bytes[..10]).unwrap_err() {
+                RequestDeserializationError::MalformedDatagram(
+                    MalformedDatagramError::AddressLengthTooShort,
+                ) => {}
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn udp_disassociate_serialize_there_and_back() {
+            let disassociate = Socks5RequestContent::UdpDisassociate(12345);
+            let bytes = disassociate.clone().into_bytes();
+            assert_eq!(
+                disassociate,
+                Socks5RequestContent::try_from_bytes(&bytes).unwrap()
+            );
+
+            match Socks5RequestContent::try_from_bytes(&bytes[..8]).unwrap_err() {
+                RequestDeserializationError::ConnectionIdTooShort => {}
+                _ => unreachable!(),
+            }
+        }
+    }
+    #[cfg(test)]
+    mod bind_requests {
+        use super::*;
+        use crate::INITIAL_INTERFACE_VERSION;
+
+        #[test]
+        fn serialize_there_and_back() {
+            let recipient = Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap();
+
+            for return_address in [None, Some(recipient)] {
+                let bind =
+                    Socks5RequestContent::new_bind(42, "1.2.3.4:20".to_string(), return_address);
+                let bytes = bind.clone().into_bytes();
+                assert_eq!(bytes[0], RequestFlag::Bind as u8);
+                assert_eq!(bind, Socks5RequestContent::try_from_bytes(&bytes).unwrap());
+            }
+
+            // bind and connect only differ by the flag
+            let connect =
+                Socks5RequestContent::new_connect(42, "1.2.3.4:20".to_string(), None).into_bytes();
+            let bind =
+                Socks5RequestContent::new_bind(42, "1.2.3.4:20".to_string(), None).into_bytes();
+            assert_eq!(connect[1..], bind[1..]);
+        }
+
+        #[test]
+        fn requires_the_extended_interface() {
+            let bind = |protocol_version| {
+                Socks5Request::new_bind(protocol_version, 42, "1.2.3.4:20".to_string(), None)
+                    .into_bytes()
+            };
+
+            let current = Socks5ProtocolVersion::new_current();
+            let request = Socks5Request::try_from_bytes(&bind(current)).unwrap();
+            assert_eq!(request.protocol_version, current);
+
+            let initial = Socks5ProtocolVersion::new_versioned(INITIAL_INTERFACE_VERSION);
+            match Socks5Request::try_from_bytes(&bind(initial)).unwrap_err() {
+                Socks5RequestError::UnsupportedRequestForProtocolVersion { protocol_version } => {
+                    assert_eq!(protocol_version, initial)
+                }
+                _ => unreachable!(),
+            }
+
+            // while the basic requests are still understood with the initial version
+            let connect = Socks5Request::new_connect(initial, 42, "1.2.3.4:20".to_string(), None);
+            assert!(Socks5Request::try_from_bytes(&connect.into_bytes()).is_ok());
+        }
+    }
+    #[cfg(test)]
+    mod dns_query_requests {
+        use super::*;
+
+        #[test]
+        fn serialize_there_and_back() {
+            let recipient = Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap();
+
+            let anonymous = Socks5RequestContent::new_dns_query(
+                42,
+                "nym.com".to_string(),
+                DnsRecordType::A,
+                None,
+            );
+            let bytes = anonymous.clone().into_bytes();
+            assert_eq!(
+                bytes,
+                vec![7, 0, 0, 0, 0, 0, 0, 0, 42, 0, 0, 7, 110, 121, 109, 46, 99, 111, 109]
+            );
+            assert_eq!(
+                anonymous,
+                Socks5RequestContent::try_from_bytes(&bytes).unwrap()
+            );
+
+            let with_return = Socks5RequestContent::new_dns_query(
+                42,
+                "nym.com".to_string(),
+                DnsRecordType::Aaaa,
+                Some(recipient),
+            );
+            let bytes = with_return.clone().into_bytes();
+            assert_eq!(
+                with_return,
+                Socks5RequestContent::try_from_bytes(&bytes).unwrap()
+            );
+        }
+
+        #[test]
+        fn deserialization_errors() {
+            match Socks5RequestContent::try_from_bytes(&[7, 0, 0, 0, 0, 0, 0, 0, 42]).unwrap_err() {
+                RequestDeserializationError::DnsRecordTypeTooShort => {}
+                _ => unreachable!(),
+            }
+            match Socks5RequestContent::try_from_bytes(&[7, 0, 0, 0, 0, 0, 0, 0, 42, 5, 0, 1, 97])
+                .unwrap_err()
+            {
+                RequestDeserializationError::UnknownDnsRecordType { value: 5 } => {}
+                _ => unreachable!(),
+            }
+            match Socks5RequestContent::try_from_bytes(&[7, 0, 0, 0, 0, 0, 0, 0, 42, 0, 0])
+                .unwrap_err()
+            {
+                RequestDeserializationError::AddressLengthTooShort => {}
+                _ => unreachable!(),
+            }
+            match Socks5RequestContent::try_from_bytes(&[7, 0, 0, 0, 0, 0, 0, 0, 42, 0, 0, 2, 97])
+                .unwrap_err()
+            {
+                RequestDeserializationError::AddressTooShort => {}
+                _ => unreachable!(),
+            }
+        }
+    }
 }
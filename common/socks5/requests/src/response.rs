@@ -2,12 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    make_bincode_serializer, ConnectionId, InsufficientSocketDataError, SocketData,
-    Socks5ProtocolVersion, Socks5RequestError,
+    make_bincode_serializer, ConnectionId, Datagram, DnsQueryId, InsufficientSocketDataError,
+    MalformedDatagramError, SocketData, Socks5ProtocolVersion, Socks5RequestError,
 };
 use nym_exit_policy::ExitPolicy;
 use nym_service_providers_common::interface::{Serializable, ServiceProviderResponse};
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use tap::TapFallible;
 use thiserror::Error;
 
@@ -20,6 +21,9 @@ pub enum ResponseFlag {
     NetworkData = 1,
     ConnectionError = 2,
     Query = 3,
+    Datagram = 4,
+    Bind = 5,
+    DnsQuery = 6,
 }
 
 impl TryFrom<u8> for ResponseFlag {
@@ -30,6 +34,9 @@ impl TryFrom<u8> for ResponseFlag {
             _ if value == (ResponseFlag::NetworkData as u8) => Ok(Self::NetworkData),
             _ if value == (ResponseFlag::ConnectionError as u8) => Ok(Self::ConnectionError),
             _ if value == (ResponseFlag::Query as u8) => Ok(Self::Query),
+            _ if value == (ResponseFlag::Datagram as u8) => Ok(Self::Datagram),
+            _ if value == (ResponseFlag::Bind as u8) => Ok(Self::Bind),
+            _ if value == (ResponseFlag::DnsQuery as u8) => Ok(Self::DnsQuery),
             value => Err(ResponseDeserializationError::UnknownResponseFlag { value }),
         }
     }
@@ -63,6 +70,15 @@ pub enum ResponseDeserializationError {
         #[from]
         source: bincode::Error,
     },
+
+    #[error("failed to deserialize bind response: {source}")]
+    BindDeserializationError { source: bincode::Error },
+
+    #[error("failed to deserialize dns query response: {source}")]
+    DnsQueryDeserializationError { source: bincode::Error },
+
+    #[error("malformed datagram: {0}")]
+    MalformedDatagram(#[from] MalformedDatagramError),
 }
 
 #[derive(Debug, Clone)]
@@ -147,6 +163,41 @@ impl Socks5Response {
         }
     }
 
+    pub fn new_datagram(
+        protocol_version: Socks5ProtocolVersion,
+        datagram: Datagram,
+    ) -> Socks5Response {
+        Socks5Response {
+            protocol_version,
+            content: Socks5ResponseContent::Datagram(datagram),
+        }
+    }
+
+    pub fn new_bind(
+        protocol_version: Socks5ProtocolVersion,
+        connection_id: ConnectionId,
+        event: BindEvent,
+    ) -> Socks5Response {
+        Socks5Response {
+            protocol_version,
+            content: Socks5ResponseContent::Bind(BindResponse {
+                connection_id,
+                event,
+            }),
+        }
+    }
+
+    pub fn new_dns_query(
+        protocol_version: Socks5ProtocolVersion,
+        query_id: DnsQueryId,
+        result: DnsQueryResult,
+    ) -> Socks5Response {
+        Socks5Response {
+            protocol_version,
+            content: Socks5ResponseContent::DnsQuery(DnsQueryResponse { query_id, result }),
+        }
+    }
+
     pub fn new_query(
         protocol_version: Socks5ProtocolVersion,
         query_response: QueryResponse,
@@ -175,6 +226,9 @@ pub enum Socks5ResponseContent {
     NetworkData { content: SocketData },
     ConnectionError(ConnectionError),
     Query(QueryResponse),
+    Datagram(Datagram),
+    Bind(BindResponse),
+    DnsQuery(DnsQueryResponse),
 }
 
 impl Socks5ResponseContent {
@@ -220,6 +274,35 @@ impl Socks5ResponseContent {
                     .chain(query_bytes)
                     .collect()
             }
+            Socks5ResponseContent::Datagram(datagram) => {
+                std::iter::once(ResponseFlag::Datagram as u8)
+                    .chain(datagram.into_bytes_iter())
+                    .collect()
+            }
+            Socks5ResponseContent::Bind(bind) => {
+                use bincode::Options;
+                let bind_bytes: Vec<u8> = make_bincode_serializer()
+                    .serialize(&bind)
+                    .tap_err(|err| {
+                        log::error!("Failed to serialize bind response: {:?}: {err}", bind);
+                    })
+                    .unwrap_or_default();
+                std::iter::once(ResponseFlag::Bind as u8)
+                    .chain(bind_bytes)
+                    .collect()
+            }
+            Socks5ResponseContent::DnsQuery(dns) => {
+                use bincode::Options;
+                let dns_bytes: Vec<u8> = make_bincode_serializer()
+                    .serialize(&dns)
+                    .tap_err(|err| {
+                        log::error!("Failed to serialize dns query response: {:?}: {err}", dns);
+                    })
+                    .unwrap_or_default();
+                std::iter::once(ResponseFlag::DnsQuery as u8)
+                    .chain(dns_bytes)
+                    .collect()
+            }
         }
     }
 
@@ -243,6 +326,27 @@ impl Socks5ResponseContent {
                 let query = make_bincode_serializer().deserialize(&b[1..])?;
                 Ok(Socks5ResponseContent::Query(query))
             }
+            ResponseFlag::Datagram => Ok(Socks5ResponseContent::Datagram(
+                Datagram::try_from_bytes(&b[1..])?,
+            )),
+            ResponseFlag::Bind => {
+                use bincode::Options;
+                let bind = make_bincode_serializer()
+                    .deserialize(&b[1..])
+                    .map_err(
+                        |source| ResponseDeserializationError::BindDeserializationError { source },
+                    )?;
+                Ok(Socks5ResponseContent::Bind(bind))
+            }
+            ResponseFlag::DnsQuery => {
+                use bincode::Options;
+                let dns = make_bincode_serializer()
+                    .deserialize(&b[1..])
+                    .map_err(|source| {
+                        ResponseDeserializationError::DnsQueryDeserializationError { source }
+                    })?;
+                Ok(Socks5ResponseContent::DnsQuery(dns))
+            }
         }
     }
 
@@ -323,6 +427,46 @@ pub enum QueryResponse {
     },
 }
 
+/// Progress of a SOCKS5 BIND request as reported by the service provider.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BindResponse {
+    pub connection_id: ConnectionId,
+    pub event: BindEvent,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BindEvent {
+    /// The service provider has started listening for the inbound connection on the specified address.
+    Listening { address: String },
+
+    /// The inbound connection from the specified address has been accepted and it's now
+    /// going to get proxied on the associated `ConnectionId`.
+    Accepted { peer: String },
+
+    /// The service provider has failed to either start listening or to accept the inbound connection.
+    Failed { message: String },
+}
+
+/// Answer to a DNS query resolved by the service provider.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DnsQueryResponse {
+    pub query_id: DnsQueryId,
+    pub result: DnsQueryResult,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DnsQueryResult {
+    /// The name got resolved to the specified addresses. The list is empty if the name exists,
+    /// but it has no records of the requested type.
+    Resolved { addresses: Vec<IpAddr> },
+
+    /// The name is not allowed by the request filter of the service provider.
+    Refused,
+
+    /// The service provider has failed to resolve the name.
+    Failed { message: String },
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -423,4 +567,88 @@ mod tests {
             assert_eq!(exit_policy, exit_policy2);
         }
     }
+    #[cfg(test)]
+    mod serialize_datagram_response {
+        use super::*;
+
+        #[test]
+        fn serialize_there_and_back() {
+            let datagram = Socks5ResponseContent::Datagram(Datagram::new(
+                42,
+                "[2001:db8::1]:53".to_string(),
+                vec![255, 255],
+            ));
+            let bytes = datagram.clone().into_bytes();
+            assert_eq!(bytes[0], ResponseFlag::Datagram as u8);
+            assert_eq!(
+                datagram,
+                Socks5ResponseContent::try_from_bytes(&bytes).unwrap()
+            );
+
+            let err = Socks5ResponseContent::try_from_bytes(&bytes[..5]).unwrap_err();
+            assert!(matches!(
+                err,
+                ResponseDeserializationError::MalformedDatagram(
+                    MalformedDatagramError::ConnectionIdTooShort
+                )
+            ));
+        }
+    }
+    #[cfg(test)]
+    mod serialize_bind_response {
+        use super::*;
+
+        #[test]
+        fn serialize_there_and_back() {
+            let events = [
+                BindEvent::Listening {
+                    address: "1.2.3.4:5678".to_string(),
+                },
+                BindEvent::Accepted {
+                    peer: "[2001:db8::1]:20".to_string(),
+                },
+                BindEvent::Failed {
+                    message: "oh no".to_string(),
+                },
+            ];
+
+            for event in events {
+                let bind = Socks5ResponseContent::Bind(BindResponse {
+                    connection_id: 42,
+                    event,
+                });
+                let bytes = bind.clone().into_bytes();
+                assert_eq!(bytes[0], ResponseFlag::Bind as u8);
+                assert_eq!(bind, Socks5ResponseContent::try_from_bytes(&bytes).unwrap());
+            }
+        }
+    }
+    #[cfg(test)]
+    mod serialize_dns_query_response {
+        use super::*;
+
+        #[test]
+        fn serialize_there_and_back() {
+            let results = [
+                DnsQueryResult::Resolved {
+                    addresses: vec!["1.2.3.4".parse().unwrap(), "2001:db8::1".parse().unwrap()],
+                },
+                DnsQueryResult::Resolved { addresses: vec![] },
+                DnsQueryResult::Refused,
+                DnsQueryResult::Failed {
+                    message: "oh no".to_string(),
+                },
+            ];
+
+            for result in results {
+                let dns = Socks5ResponseContent::DnsQuery(DnsQueryResponse {
+                    query_id: 42,
+                    result,
+                });
+                let bytes = dns.clone().into_bytes();
+                assert_eq!(bytes[0], ResponseFlag::DnsQuery as u8);
+                assert_eq!(dns, Socks5ResponseContent::try_from_bytes(&bytes).unwrap());
+            }
+        }
+    }
 }
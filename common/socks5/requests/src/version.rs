@@ -12,10 +12,60 @@ pub const INITIAL_INTERFACE_VERSION: u8 = 3;
 /// Defines the current version of the communication interface between socks5 clients and
 /// network requesters (socks5).
 /// It has to be incremented for any breaking change.
-pub const INTERFACE_VERSION: u8 = 3;
+pub const INTERFACE_VERSION: u8 = 4;
+
+/// Defines the first version of the communication interface that supports UDP associate,
+/// BIND and DNS query requests.
+pub const EXTENDED_REQUESTS_INTERFACE_VERSION: u8 = 4;
 
 define_simple_version!(
     Socks5ProtocolVersion,
     INITIAL_INTERFACE_VERSION,
     INTERFACE_VERSION
 );
+
+impl Socks5ProtocolVersion {
+    /// Checks whether the network requester speaking this version understands UDP associate,
+    /// BIND and DNS query requests.
+    pub fn supports_extended_requests(&self) -> bool {
+        *self >= Socks5ProtocolVersion::new_versioned(EXTENDED_REQUESTS_INTERFACE_VERSION)
+    }
+
+    /// The version attached to the requests understood by all versioned network requesters,
+    /// so that the clients could keep using the ones that haven't been upgraded yet.
+    pub const fn for_basic_requests(self) -> Self {
+        match self {
+            Socks5ProtocolVersion::Versioned(version) if version > INITIAL_INTERFACE_VERSION => {
+                Socks5ProtocolVersion::new_versioned(INITIAL_INTERFACE_VERSION)
+            }
+            version => version,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended_requests_require_the_updated_interface() {
+        assert!(!Socks5ProtocolVersion::new_legacy().supports_extended_requests());
+        assert!(
+            !Socks5ProtocolVersion::new_versioned(INITIAL_INTERFACE_VERSION)
+                .supports_extended_requests()
+        );
+        assert!(Socks5ProtocolVersion::new_current().supports_extended_requests());
+    }
+
+    #[test]
+    fn basic_requests_use_the_initial_interface() {
+        assert_eq!(
+            Socks5ProtocolVersion::new_current().for_basic_requests(),
+            Socks5ProtocolVersion::new_versioned(INITIAL_INTERFACE_VERSION)
+        );
+        assert_eq!(
+            Socks5ProtocolVersion::new_legacy().for_basic_requests(),
+            Socks5ProtocolVersion::new_legacy()
+        );
+    }
+}
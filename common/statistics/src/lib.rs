@@ -36,6 +36,10 @@ pub enum StatsData {
 pub struct StatsGatewayData {
     pub gateway_id: String,
     pub inbox_count: u32,
+
+    /// Storage pressure of the gateway caused by messages kept for offline clients.
+    #[serde(default)]
+    pub inbox_storage: StatsInboxStorageData,
 }
 
 impl StatsGatewayData {
@@ -43,8 +47,30 @@ impl StatsGatewayData {
         StatsGatewayData {
             gateway_id,
             inbox_count,
+            inbox_storage: Default::default(),
         }
     }
+
+    #[must_use]
+    pub fn with_inbox_storage(mut self, inbox_storage: StatsInboxStorageData) -> Self {
+        self.inbox_storage = inbox_storage;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct StatsInboxStorageData {
+    /// Total number of messages currently stored for offline clients.
+    pub stored_messages: u64,
+
+    /// Total size of messages currently stored for offline clients.
+    pub stored_bytes: u64,
+
+    /// Number of messages removed due to exceeding their maximum age within the reporting interval.
+    pub expired_messages: u64,
+
+    /// Number of messages rejected due to exceeding client inbox quotas within the reporting interval.
+    pub rejected_messages: u64,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
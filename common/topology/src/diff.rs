@@ -0,0 +1,271 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{gateway, mix, MixLayer, NodeVersion, NymTopology};
+use nym_mixnet_contract_common::MixId;
+use nym_sphinx_addressing::nodes::NodeIdentity;
+use std::collections::HashMap;
+
+/// Mixnode that has been moved to a different layer between two topology snapshots.
+#[derive(Debug, Clone)]
+pub struct MixnodeLayerChange {
+    pub mix_id: MixId,
+    pub identity_key: NodeIdentity,
+    pub old_layer: MixLayer,
+    pub new_layer: MixLayer,
+}
+
+/// Node that has changed its reported version between two topology snapshots.
+#[derive(Debug, Clone)]
+pub struct NodeVersionChange {
+    pub identity_key: NodeIdentity,
+    pub old_version: NodeVersion,
+    pub new_version: NodeVersion,
+}
+
+/// Structured difference between two snapshots of the network topology.
+#[derive(Debug, Clone, Default)]
+pub struct NymTopologyDiff {
+    pub added_mixnodes: Vec<mix::Node>,
+    pub removed_mixnodes: Vec<mix::Node>,
+    pub mixnode_layer_changes: Vec<MixnodeLayerChange>,
+    pub mixnode_version_changes: Vec<NodeVersionChange>,
+
+    pub added_gateways: Vec<gateway::Node>,
+    pub removed_gateways: Vec<gateway::Node>,
+    pub gateway_version_changes: Vec<NodeVersionChange>,
+}
+
+impl NymTopologyDiff {
+    /// Computes the difference between the `old` and the `new` topology.
+    /// A missing topology is treated as one containing no nodes.
+    pub fn compute(old: Option<&NymTopology>, new: Option<&NymTopology>) -> Self {
+        let old_mixes = indexed_mixnodes(old);
+        let new_mixes = indexed_mixnodes(new);
+        let old_gateways = indexed_gateways(old);
+        let new_gateways = indexed_gateways(new);
+
+        let mut diff = NymTopologyDiff::default();
+
+        for (mix_id, (new_layer, new_node)) in &new_mixes {
+            let Some((old_layer, old_node)) = old_mixes.get(mix_id) else {
+                diff.added_mixnodes.push((*new_node).clone());
+                continue;
+            };
+
+            if old_layer != new_layer {
+                diff.mixnode_layer_changes.push(MixnodeLayerChange {
+                    mix_id: *mix_id,
+                    identity_key: new_node.identity_key,
+                    old_layer: *old_layer,
+                    new_layer: *new_layer,
+                })
+            }
+            if old_node.version != new_node.version {
+                diff.mixnode_version_changes.push(NodeVersionChange {
+                    identity_key: new_node.identity_key,
+                    old_version: old_node.version.clone(),
+                    new_version: new_node.version.clone(),
+                })
+            }
+        }
+        diff.removed_mixnodes = old_mixes
+            .iter()
+            .filter(|(mix_id, _)| !new_mixes.contains_key(mix_id))
+            .map(|(_, (_, node))| (*node).clone())
+            .collect();
+
+        for (identity, new_node) in &new_gateways {
+            let Some(old_node) = old_gateways.get(identity) else {
+                diff.added_gateways.push((*new_node).clone());
+                continue;
+            };
+
+            if old_node.version != new_node.version {
+                diff.gateway_version_changes.push(NodeVersionChange {
+                    identity_key: new_node.identity_key,
+                    old_version: old_node.version.clone(),
+                    new_version: new_node.version.clone(),
+                })
+            }
+        }
+        diff.removed_gateways = old_gateways
+            .iter()
+            .filter(|(identity, _)| !new_gateways.contains_key(identity))
+            .map(|(_, node)| (*node).clone())
+            .collect();
+
+        diff
+    }
+
+    /// Returns `true` if both topologies contained exactly the same nodes.
+    pub fn is_empty(&self) -> bool {
+        self.added_mixnodes.is_empty()
+            && self.removed_mixnodes.is_empty()
+            && self.mixnode_layer_changes.is_empty()
+            && self.mixnode_version_changes.is_empty()
+            && self.added_gateways.is_empty()
+            && self.removed_gateways.is_empty()
+            && self.gateway_version_changes.is_empty()
+    }
+
+    /// Checks whether the gateway with the provided identity got removed from the topology.
+    pub fn gateway_removed(&self, gateway: &NodeIdentity) -> bool {
+        self.removed_gateways
+            .iter()
+            .any(|node| &node.identity_key == gateway)
+    }
+
+    /// Checks whether the mixnode with the provided identity got removed from the topology.
+    pub fn mixnode_removed(&self, mixnode: &NodeIdentity) -> bool {
+        self.removed_mixnodes
+            .iter()
+            .any(|node| &node.identity_key == mixnode)
+    }
+}
+
+fn indexed_mixnodes(topology: Option<&NymTopology>) -> HashMap<MixId, (MixLayer, &mix::Node)> {
+    topology
+        .map(|topology| {
+            topology
+                .mixes()
+                .iter()
+                .flat_map(|(layer, nodes)| nodes.iter().map(|node| (node.mix_id, (*layer, node))))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// note: identity keys are indexed by their bytes as they don't implement `Hash`
+fn indexed_gateways(topology: Option<&NymTopology>) -> HashMap<[u8; 32], &gateway::Node> {
+    topology
+        .map(|topology| {
+            topology
+                .gateways()
+                .iter()
+                .map(|node| (node.identity_key.to_bytes(), node))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::{encryption, identity};
+    use nym_mixnet_contract_common::Layer;
+    use std::collections::BTreeMap;
+
+    fn mixnode(mix_id: MixId, layer: Layer, version: &str) -> mix::Node {
+        let mut rng = rand::thread_rng();
+        mix::Node {
+            mix_id,
+            owner: "N/A".to_string(),
+            host: "3.3.3.3".parse().unwrap(),
+            mix_host: "3.3.3.3:1789".parse().unwrap(),
+            identity_key: *identity::KeyPair::new(&mut rng).public_key(),
+            sphinx_key: *encryption::KeyPair::new(&mut rng).public_key(),
+            layer,
+            version: version.into(),
+        }
+    }
+
+    fn gateway(version: &str) -> gateway::Node {
+        let mut rng = rand::thread_rng();
+        gateway::Node {
+            owner: "N/A".to_string(),
+            host: "1.1.1.1".parse().unwrap(),
+            mix_host: "1.1.1.1:1789".parse().unwrap(),
+            clients_ws_port: 9000,
+            clients_wss_port: None,
+            identity_key: *identity::KeyPair::new(&mut rng).public_key(),
+            sphinx_key: *encryption::KeyPair::new(&mut rng).public_key(),
+            version: version.into(),
+        }
+    }
+
+    fn topology(mixes: Vec<mix::Node>, gateways: Vec<gateway::Node>) -> NymTopology {
+        let mut layered: BTreeMap<MixLayer, Vec<mix::Node>> = BTreeMap::new();
+        for mix in mixes {
+            layered.entry(mix.layer.into()).or_default().push(mix)
+        }
+        NymTopology::new(layered, gateways)
+    }
+
+    #[test]
+    fn identical_topologies_produce_empty_diff() {
+        let mix = mixnode(1, Layer::One, "1.1.0");
+        let gateway = gateway("1.1.0");
+        let topology = topology(vec![mix], vec![gateway]);
+
+        assert!(NymTopologyDiff::compute(Some(&topology), Some(&topology)).is_empty());
+        assert!(NymTopologyDiff::compute(None, None).is_empty());
+    }
+
+    #[test]
+    fn detects_added_and_removed_nodes() {
+        let mix1 = mixnode(1, Layer::One, "1.1.0");
+        let mix2 = mixnode(2, Layer::Two, "1.1.0");
+        let gateway1 = gateway("1.1.0");
+        let gateway2 = gateway("1.1.0");
+
+        let old = topology(vec![mix1.clone()], vec![gateway1.clone()]);
+        let new = topology(vec![mix2.clone()], vec![gateway2.clone()]);
+
+        let diff = NymTopologyDiff::compute(Some(&old), Some(&new));
+        assert_eq!(diff.added_mixnodes.len(), 1);
+        assert_eq!(diff.added_mixnodes[0].mix_id, 2);
+        assert_eq!(diff.removed_mixnodes.len(), 1);
+        assert_eq!(diff.removed_mixnodes[0].mix_id, 1);
+        assert!(diff.mixnode_removed(&mix1.identity_key));
+
+        assert_eq!(diff.added_gateways.len(), 1);
+        assert_eq!(diff.added_gateways[0].identity_key, gateway2.identity_key);
+        assert!(diff.gateway_removed(&gateway1.identity_key));
+        assert!(!diff.gateway_removed(&gateway2.identity_key));
+    }
+
+    #[test]
+    fn detects_layer_and_version_changes() {
+        let mix = mixnode(1, Layer::One, "1.1.0");
+        let gateway = gateway("1.1.0");
+
+        let old = topology(vec![mix.clone()], vec![gateway.clone()]);
+        let new = topology(
+            vec![mix::Node {
+                layer: Layer::Three,
+                version: "1.1.1".into(),
+                ..mix
+            }],
+            vec![gateway::Node {
+                version: "1.2.0".into(),
+                ..gateway
+            }],
+        );
+
+        let diff = NymTopologyDiff::compute(Some(&old), Some(&new));
+        assert!(diff.added_mixnodes.is_empty());
+        assert!(diff.removed_mixnodes.is_empty());
+        assert_eq!(diff.mixnode_layer_changes.len(), 1);
+        assert_eq!(diff.mixnode_layer_changes[0].old_layer, 1);
+        assert_eq!(diff.mixnode_layer_changes[0].new_layer, 3);
+        assert_eq!(diff.mixnode_version_changes.len(), 1);
+        assert_eq!(diff.gateway_version_changes.len(), 1);
+    }
+
+    #[test]
+    fn missing_topology_is_treated_as_empty() {
+        let topology = topology(
+            vec![mixnode(1, Layer::One, "1.1.0")],
+            vec![gateway("1.1.0")],
+        );
+
+        let diff = NymTopologyDiff::compute(Some(&topology), None);
+        assert_eq!(diff.removed_mixnodes.len(), 1);
+        assert_eq!(diff.removed_gateways.len(), 1);
+
+        let diff = NymTopologyDiff::compute(None, Some(&topology));
+        assert_eq!(diff.added_mixnodes.len(), 1);
+        assert_eq!(diff.added_gateways.len(), 1);
+    }
+}
@@ -10,7 +10,7 @@ use nym_sphinx_addressing::nodes::NodeIdentity;
 use nym_sphinx_types::Node as SphinxNode;
 use rand::prelude::SliceRandom;
 use rand::{CryptoRng, Rng};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
 use std::fmt::{self, Display, Formatter};
 use std::io;
@@ -19,13 +19,18 @@ use std::str::FromStr;
 
 #[cfg(feature = "serializable")]
 use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
-use nym_api_requests::models::DescribedGateway;
+use nym_api_requests::models::{DescribedGateway, MixnodeSphinxKeys};
+use nym_crypto::asymmetric::encryption;
+use time::OffsetDateTime;
 
+pub mod diff;
 pub mod error;
 pub mod filter;
 pub mod gateway;
 pub mod mix;
 pub mod random_route_provider;
+pub mod route_selection;
+pub mod sphinx_keys;
 
 #[cfg(feature = "provider-trait")]
 pub mod provider_trait;
@@ -35,13 +40,18 @@ pub(crate) mod serde;
 
 #[cfg(feature = "serializable")]
 pub use crate::serde::{
-    SerializableGateway, SerializableMixNode, SerializableNymTopology, SerializableTopologyError,
+    SerializableGateway, SerializableMixNode, SerializableNymTopology,
+    SerializableRotatingSphinxKey, SerializableTopologyError,
 };
 
 #[cfg(feature = "provider-trait")]
 pub use provider_trait::{HardcodedTopologyProvider, TopologyProvider};
 
-#[derive(Debug, Default, Clone)]
+pub use diff::NymTopologyDiff;
+pub use route_selection::WeightedRouteSelector;
+pub use sphinx_keys::RotatingSphinxKey;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum NodeVersion {
     Explicit(semver::Version),
 
@@ -115,11 +125,23 @@ pub type MixLayer = u8;
 pub struct NymTopology {
     mixes: BTreeMap<MixLayer, Vec<mix::Node>>,
     gateways: Vec<gateway::Node>,
+
+    // if specified, used for biasing the mix route selection, otherwise the nodes are chosen uniformly
+    route_selector: Option<WeightedRouteSelector>,
+
+    // keys announced by the mixnodes that rotate their sphinx keys. if there's no valid key for given node,
+    // its bonded key is going to be used instead
+    rotating_sphinx_keys: HashMap<MixId, Vec<RotatingSphinxKey>>,
 }
 
 impl NymTopology {
     pub fn new(mixes: BTreeMap<MixLayer, Vec<mix::Node>>, gateways: Vec<gateway::Node>) -> Self {
-        NymTopology { mixes, gateways }
+        NymTopology {
+            mixes,
+            gateways,
+            route_selector: None,
+            rotating_sphinx_keys: HashMap::new(),
+        }
     }
 
     pub fn new_unordered(unordered_mixes: Vec<mix::Node>, gateways: Vec<gateway::Node>) -> Self {
@@ -130,7 +152,7 @@ impl NymTopology {
             layer_entry.push(node)
         }
 
-        NymTopology { mixes, gateways }
+        NymTopology::new(mixes, gateways)
     }
 
     #[cfg(feature = "serializable")]
@@ -142,8 +164,9 @@ impl NymTopology {
     pub fn from_detailed(
         mix_details: Vec<MixNodeDetails>,
         gateway_bonds: Vec<GatewayBond>,
+        announced_sphinx_keys: &[MixnodeSphinxKeys],
     ) -> Self {
-        nym_topology_from_detailed(mix_details, gateway_bonds)
+        nym_topology_from_detailed(mix_details, gateway_bonds, announced_sphinx_keys)
     }
 
     pub fn find_mix(&self, mix_id: MixId) -> Option<&mix::Node> {
@@ -218,6 +241,59 @@ impl NymTopology {
         self.gateways = gateways
     }
 
+    /// Attaches the selector used for weighting the mixnodes during the route construction.
+    #[must_use]
+    pub fn with_route_selector(mut self, route_selector: WeightedRouteSelector) -> Self {
+        self.set_route_selector(route_selector);
+        self
+    }
+
+    pub fn set_route_selector(&mut self, route_selector: WeightedRouteSelector) {
+        self.route_selector = Some(route_selector)
+    }
+
+    pub fn route_selector(&self) -> Option<&WeightedRouteSelector> {
+        self.route_selector.as_ref()
+    }
+
+    pub fn route_selector_mut(&mut self) -> Option<&mut WeightedRouteSelector> {
+        self.route_selector.as_mut()
+    }
+
+    /// Attaches the rotated sphinx keys announced by the mixnodes.
+    #[must_use]
+    pub fn with_rotating_sphinx_keys(
+        mut self,
+        rotating_sphinx_keys: HashMap<MixId, Vec<RotatingSphinxKey>>,
+    ) -> Self {
+        self.rotating_sphinx_keys = rotating_sphinx_keys;
+        self
+    }
+
+    pub fn set_rotating_sphinx_keys(&mut self, mix_id: MixId, keys: Vec<RotatingSphinxKey>) {
+        self.rotating_sphinx_keys.insert(mix_id, keys);
+    }
+
+    pub fn rotating_sphinx_keys(&self, mix_id: MixId) -> Option<&[RotatingSphinxKey]> {
+        self.rotating_sphinx_keys
+            .get(&mix_id)
+            .map(|keys| keys.as_slice())
+    }
+
+    /// Returns the sphinx key of the specified mixnode that should be used at the provided time.
+    /// If the node doesn't rotate its keys (or none of them is valid), its bonded key is returned.
+    pub fn mix_sphinx_key_at<'a>(
+        &'a self,
+        node: &'a mix::Node,
+        unix_timestamp: i64,
+    ) -> &'a encryption::PublicKey {
+        self.rotating_sphinx_keys
+            .get(&node.mix_id)
+            .and_then(|keys| keys.iter().find(|key| key.is_valid_at(unix_timestamp)))
+            .map(|key| &key.public_key)
+            .unwrap_or(&node.sphinx_key)
+    }
+
     pub fn random_gateway<R>(&self, rng: &mut R) -> Result<&gateway::Node, NymTopologyError>
     where
         R: Rng + CryptoRng,
@@ -244,6 +320,7 @@ impl NymTopology {
             });
         }
         let mut route = Vec::with_capacity(num_mix_hops as usize);
+        let now = OffsetDateTime::now_utc().unix_timestamp();
 
         // there is no "layer 0"
         for layer in 1..=num_mix_hops {
@@ -253,12 +330,14 @@ impl NymTopology {
                 .get(&layer)
                 .ok_or(NymTopologyError::EmptyMixLayer { layer })?;
 
-            // choose a random mix from the above list
+            // choose a random mix from the above list, biased by the selector if one is specified
             // this can return a 'None' only if slice is empty
-            let random_mix = layer_mixes
-                .choose(rng)
-                .ok_or(NymTopologyError::EmptyMixLayer { layer })?;
-            route.push(random_mix.into());
+            let random_mix = match &self.route_selector {
+                Some(selector) => selector.choose(rng, layer_mixes),
+                None => layer_mixes.choose(rng),
+            }
+            .ok_or(NymTopologyError::EmptyMixLayer { layer })?;
+            route.push(random_mix.to_sphinx_node_with_key(self.mix_sphinx_key_at(random_mix, now)));
         }
 
         Ok(route)
@@ -379,6 +458,8 @@ impl NymTopology {
         NymTopology {
             mixes: self.mixes.filter_by_version(expected_mix_version),
             gateways: self.gateways.clone(),
+            route_selector: self.route_selector.clone(),
+            rotating_sphinx_keys: self.rotating_sphinx_keys.clone(),
         }
     }
 }
@@ -423,9 +504,12 @@ impl IntoGatewayNode for DescribedGateway {
     }
 }
 
+/// Constructs the topology out of the provided nodes, alongside the rotated sphinx keys announced
+/// by the mixnodes, so that the packets would be encrypted for the keys the nodes are actually using.
 pub fn nym_topology_from_detailed<G>(
     mix_details: Vec<MixNodeDetails>,
     gateway_bonds: Vec<G>,
+    announced_sphinx_keys: &[MixnodeSphinxKeys],
 ) -> NymTopology
 where
     G: IntoGatewayNode,
@@ -470,6 +554,7 @@ where
     }
 
     NymTopology::new(mixes, gateways)
+        .with_rotating_sphinx_keys(sphinx_keys::parse_all_announced_keys(announced_sphinx_keys))
 }
 
 #[cfg(test)]
@@ -80,6 +80,18 @@ impl Node {
     }
 }
 
+impl Node {
+    /// Converts this node into a sphinx node using the provided sphinx key
+    /// rather than the bonded one, for example, if the node has rotated its keys.
+    pub fn to_sphinx_node_with_key(&self, sphinx_key: &encryption::PublicKey) -> SphinxNode {
+        let node_address_bytes = NymNodeRoutingAddress::from(self.mix_host)
+            .try_into()
+            .unwrap();
+
+        SphinxNode::new(node_address_bytes, sphinx_key.into())
+    }
+}
+
 impl filter::Versioned for Node {
     fn version(&self) -> String {
         // TODO: return semver instead
@@ -89,11 +101,7 @@ impl filter::Versioned for Node {
 
 impl<'a> From<&'a Node> for SphinxNode {
     fn from(node: &'a Node) -> Self {
-        let node_address_bytes = NymNodeRoutingAddress::from(node.mix_host)
-            .try_into()
-            .unwrap();
-
-        SphinxNode::new(node_address_bytes, (&node.sphinx_key).into())
+        node.to_sphinx_node_with_key(&node.sphinx_key)
     }
 }
 
@@ -0,0 +1,204 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::mix;
+use nym_mixnet_contract_common::MixId;
+use rand::prelude::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Biases the choice of mixnodes in each layer towards the nodes that are more reliable
+/// and (optionally) closer to the client.
+///
+/// Each node is assigned a weight of `(1 - bias) + bias * score`, where `score` is the product of
+/// node's reported performance and, if it has been measured, its round-trip time relative to
+/// the fastest node in its layer.
+/// With the `performance_bias` of `0` every node is equally likely to get chosen (i.e. the
+/// behaviour is identical to the uniform selection), while with the bias of `1` the nodes are
+/// chosen purely based on their score. Any value in between trades some of the anonymity set
+/// for better reliability and latency.
+#[derive(Debug, Clone, Default)]
+pub struct WeightedRouteSelector {
+    performance: HashMap<MixId, f64>,
+    rtt: HashMap<MixId, Duration>,
+    performance_bias: f64,
+}
+
+impl WeightedRouteSelector {
+    pub fn new(performance_bias: f64) -> Self {
+        WeightedRouteSelector {
+            performance: HashMap::new(),
+            rtt: HashMap::new(),
+            performance_bias: sanitize_fraction(performance_bias),
+        }
+    }
+
+    pub fn performance_bias(&self) -> f64 {
+        self.performance_bias
+    }
+
+    pub fn set_performance_bias(&mut self, performance_bias: f64) {
+        self.performance_bias = sanitize_fraction(performance_bias)
+    }
+
+    /// Sets the performance of the specified node, expressed as a fraction in range `[0, 1]`.
+    /// Nodes without known performance are treated as fully reliable.
+    pub fn set_performance(&mut self, mix_id: MixId, performance: f64) {
+        self.performance
+            .insert(mix_id, sanitize_fraction(performance));
+    }
+
+    /// Sets the locally measured round-trip time to the specified node.
+    /// Nodes without known rtt are treated as if they were as fast as the fastest node in their layer.
+    pub fn set_rtt(&mut self, mix_id: MixId, rtt: Duration) {
+        self.rtt.insert(mix_id, rtt);
+    }
+
+    pub fn remove_rtt(&mut self, mix_id: MixId) {
+        self.rtt.remove(&mix_id);
+    }
+
+    pub fn performance(&self, mix_id: MixId) -> Option<f64> {
+        self.performance.get(&mix_id).copied()
+    }
+
+    pub fn rtt(&self, mix_id: MixId) -> Option<Duration> {
+        self.rtt.get(&mix_id).copied()
+    }
+
+    fn node_weight(&self, node: &mix::Node, layer_min_rtt: Option<Duration>) -> f64 {
+        let performance = self.performance(node.mix_id).unwrap_or(1.0);
+
+        let rtt_factor = match (layer_min_rtt, self.rtt(node.mix_id)) {
+            (Some(min_rtt), Some(rtt)) if !rtt.is_zero() => {
+                min_rtt.as_secs_f64() / rtt.as_secs_f64()
+            }
+            _ => 1.0,
+        };
+
+        (1.0 - self.performance_bias) + self.performance_bias * performance * rtt_factor
+    }
+
+    /// Chooses a node out of the provided layer according to their weights.
+    /// Returns `None` only if the layer is empty.
+    pub fn choose<'a, R>(&self, rng: &mut R, layer_mixes: &'a [mix::Node]) -> Option<&'a mix::Node>
+    where
+        R: Rng + ?Sized,
+    {
+        let layer_min_rtt = layer_mixes
+            .iter()
+            .filter_map(|node| self.rtt(node.mix_id))
+            .filter(|rtt| !rtt.is_zero())
+            .min();
+
+        // if every single node has weight of 0 (i.e. they're all reportedly down),
+        // fallback to the uniform selection rather than failing to construct any route
+        layer_mixes
+            .choose_weighted(rng, |node| self.node_weight(node, layer_min_rtt))
+            .ok()
+            .or_else(|| layer_mixes.choose(rng))
+    }
+}
+
+// treat any non-finite value as 0 and clamp everything else to [0, 1]
+fn sanitize_fraction(value: f64) -> f64 {
+    if value.is_finite() {
+        value.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::{encryption, identity};
+    use nym_mixnet_contract_common::Layer;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn mixnode(mix_id: MixId) -> mix::Node {
+        let mut rng = rand::thread_rng();
+        mix::Node {
+            mix_id,
+            owner: "N/A".to_string(),
+            host: "3.3.3.3".parse().unwrap(),
+            mix_host: "3.3.3.3:1789".parse().unwrap(),
+            identity_key: *identity::KeyPair::new(&mut rng).public_key(),
+            sphinx_key: *encryption::KeyPair::new(&mut rng).public_key(),
+            layer: Layer::One,
+            version: "1.1.0".into(),
+        }
+    }
+
+    fn selection_counts(selector: &WeightedRouteSelector, nodes: &[mix::Node]) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = vec![0; nodes.len()];
+        for _ in 0..10000 {
+            let chosen = selector.choose(&mut rng, nodes).unwrap();
+            counts[(chosen.mix_id - 1) as usize] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn zero_bias_ignores_performance() {
+        let nodes = vec![mixnode(1), mixnode(2)];
+        let mut selector = WeightedRouteSelector::new(0.0);
+        selector.set_performance(1, 0.0);
+        selector.set_performance(2, 1.0);
+
+        let counts = selection_counts(&selector, &nodes);
+        assert!(counts[0] > 4000);
+        assert!(counts[1] > 4000);
+    }
+
+    #[test]
+    fn full_bias_avoids_unreliable_nodes() {
+        let nodes = vec![mixnode(1), mixnode(2)];
+        let mut selector = WeightedRouteSelector::new(1.0);
+        selector.set_performance(1, 0.0);
+        selector.set_performance(2, 0.9);
+
+        let counts = selection_counts(&selector, &nodes);
+        assert_eq!(counts[0], 0);
+        assert_eq!(counts[1], 10000);
+    }
+
+    #[test]
+    fn rtt_is_taken_into_account() {
+        let nodes = vec![mixnode(1), mixnode(2)];
+        let mut selector = WeightedRouteSelector::new(1.0);
+        selector.set_rtt(1, Duration::from_millis(10));
+        selector.set_rtt(2, Duration::from_millis(100));
+
+        let counts = selection_counts(&selector, &nodes);
+        assert!(counts[0] > counts[1] * 5);
+    }
+
+    #[test]
+    fn falls_back_to_uniform_selection_if_all_nodes_are_down() {
+        let nodes = vec![mixnode(1), mixnode(2)];
+        let mut selector = WeightedRouteSelector::new(1.0);
+        selector.set_performance(1, 0.0);
+        selector.set_performance(2, 0.0);
+
+        let counts = selection_counts(&selector, &nodes);
+        assert!(counts[0] > 0);
+        assert!(counts[1] > 0);
+        assert!(selector.choose(&mut rand::thread_rng(), &[]).is_none());
+    }
+
+    #[test]
+    fn invalid_values_are_sanitized() {
+        let mut selector = WeightedRouteSelector::new(f64::NAN);
+        assert_eq!(selector.performance_bias(), 0.0);
+
+        selector.set_performance_bias(2.0);
+        assert_eq!(selector.performance_bias(), 1.0);
+
+        selector.set_performance(1, -1.0);
+        assert_eq!(selector.performance(1), Some(0.0));
+    }
+}
@@ -3,11 +3,11 @@
 
 use crate::gateway::GatewayConversionError;
 use crate::mix::MixnodeConversionError;
-use crate::{gateway, mix, MixLayer, NymTopology};
+use crate::{gateway, mix, MixLayer, NymTopology, RotatingSphinxKey};
 use nym_config::defaults::{DEFAULT_CLIENT_LISTENING_PORT, DEFAULT_MIX_LISTENING_PORT};
 use nym_crypto::asymmetric::{encryption, identity};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::{IpAddr, SocketAddr};
 use thiserror::Error;
 
@@ -60,12 +60,21 @@ impl TryFrom<SerializableNymTopology> for NymTopology {
 
     fn try_from(value: SerializableNymTopology) -> Result<Self, Self::Error> {
         let mut converted_mixes = BTreeMap::new();
+        let mut rotating_sphinx_keys = HashMap::new();
 
         for (layer, nodes) in value.mixnodes {
-            let layer_nodes = nodes
-                .into_iter()
-                .map(TryInto::try_into)
-                .collect::<Result<_, _>>()?;
+            let mut layer_nodes = Vec::with_capacity(nodes.len());
+            for mut node in nodes {
+                let keys = std::mem::take(&mut node.rotating_sphinx_keys);
+                if !keys.is_empty() {
+                    let keys = keys
+                        .into_iter()
+                        .map(TryInto::try_into)
+                        .collect::<Result<_, _>>()?;
+                    rotating_sphinx_keys.insert(node.mix_id, keys);
+                }
+                layer_nodes.push(node.try_into()?);
+            }
 
             converted_mixes.insert(layer, layer_nodes);
         }
@@ -76,17 +85,28 @@ impl TryFrom<SerializableNymTopology> for NymTopology {
             .map(TryInto::try_into)
             .collect::<Result<_, _>>()?;
 
-        Ok(NymTopology::new(converted_mixes, gateways))
+        Ok(NymTopology::new(converted_mixes, gateways)
+            .with_rotating_sphinx_keys(rotating_sphinx_keys))
     }
 }
 
 impl From<NymTopology> for SerializableNymTopology {
     fn from(value: NymTopology) -> Self {
+        let serialize_node = |node: &mix::Node| SerializableMixNode {
+            rotating_sphinx_keys: value
+                .rotating_sphinx_keys(node.mix_id)
+                .unwrap_or_default()
+                .iter()
+                .map(Into::into)
+                .collect(),
+            ..SerializableMixNode::from(node)
+        };
+
         SerializableNymTopology {
             mixnodes: value
                 .mixes()
                 .iter()
-                .map(|(&l, nodes)| (l, nodes.iter().map(Into::into).collect()))
+                .map(|(&l, nodes)| (l, nodes.iter().map(serialize_node).collect()))
                 .collect(),
             gateways: value.gateways().iter().map(Into::into).collect(),
         }
@@ -122,6 +142,11 @@ pub struct SerializableMixNode {
 
     #[cfg_attr(feature = "wasm-serde-types", tsify(optional))]
     pub version: Option<String>,
+
+    #[cfg_attr(feature = "wasm-serde-types", tsify(optional))]
+    #[serde(alias = "rotating_sphinx_keys")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rotating_sphinx_keys: Vec<SerializableRotatingSphinxKey>,
 }
 
 impl TryFrom<SerializableMixNode> for mix::Node {
@@ -164,6 +189,51 @@ impl<'a> From<&'a mix::Node> for SerializableMixNode {
             sphinx_key: value.sphinx_key.to_base58_string(),
             layer: value.layer.into(),
             version: Some(value.version.to_string()),
+            rotating_sphinx_keys: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm-serde-types", derive(Tsify))]
+#[cfg_attr(feature = "wasm-serde-types", tsify(into_wasm_abi, from_wasm_abi))]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct SerializableRotatingSphinxKey {
+    #[serde(alias = "rotation_id")]
+    pub rotation_id: u32,
+
+    #[serde(alias = "public_key")]
+    pub public_key: String,
+
+    #[serde(alias = "valid_from")]
+    pub valid_from: i64,
+
+    #[serde(alias = "valid_until")]
+    pub valid_until: i64,
+}
+
+impl TryFrom<SerializableRotatingSphinxKey> for RotatingSphinxKey {
+    type Error = SerializableTopologyError;
+
+    fn try_from(value: SerializableRotatingSphinxKey) -> Result<Self, Self::Error> {
+        Ok(RotatingSphinxKey {
+            rotation_id: value.rotation_id,
+            public_key: encryption::PublicKey::from_base58_string(&value.public_key)
+                .map_err(MixnodeConversionError::from)?,
+            valid_from: value.valid_from,
+            valid_until: value.valid_until,
+        })
+    }
+}
+
+impl<'a> From<&'a RotatingSphinxKey> for SerializableRotatingSphinxKey {
+    fn from(value: &'a RotatingSphinxKey) -> Self {
+        SerializableRotatingSphinxKey {
+            rotation_id: value.rotation_id,
+            public_key: value.public_key.to_base58_string(),
+            valid_from: value.valid_from,
+            valid_until: value.valid_until,
         }
     }
 }
@@ -257,3 +327,42 @@ impl<'a> From<&'a gateway::Node> for SerializableGateway {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_mixnet_contract_common::Layer;
+
+    #[test]
+    fn rotating_sphinx_keys_survive_serialization() {
+        let mut rng = rand::thread_rng();
+        let node = mix::Node {
+            mix_id: 1,
+            owner: "N/A".to_string(),
+            host: "3.3.3.3".parse().unwrap(),
+            mix_host: "3.3.3.3:1789".parse().unwrap(),
+            identity_key: *identity::KeyPair::new(&mut rng).public_key(),
+            sphinx_key: *encryption::KeyPair::new(&mut rng).public_key(),
+            layer: Layer::One,
+            version: "1.1.0".into(),
+        };
+        let key = RotatingSphinxKey {
+            rotation_id: 1,
+            public_key: *encryption::KeyPair::new(&mut rng).public_key(),
+            valid_from: 100,
+            valid_until: 200,
+        };
+        let topology = NymTopology::new(BTreeMap::from([(1, vec![node.clone()])]), Vec::new())
+            .with_rotating_sphinx_keys(HashMap::from([(1, vec![key.clone()])]));
+
+        let serialized = serde_json::to_string(&SerializableNymTopology::from(topology)).unwrap();
+        let deserialized: NymTopology =
+            serde_json::from_str::<SerializableNymTopology>(&serialized)
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+        assert_eq!(deserialized.mix_sphinx_key_at(&node, 150), &key.public_key);
+        assert_eq!(deserialized.mix_sphinx_key_at(&node, 250), &node.sphinx_key);
+    }
+}
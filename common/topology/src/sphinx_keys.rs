@@ -0,0 +1,126 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::mix::MixnodeConversionError;
+use log::warn;
+use nym_api_requests::models::MixnodeSphinxKeys;
+use nym_crypto::asymmetric::encryption;
+use nym_mixnet_contract_common::MixId;
+use std::collections::HashMap;
+
+/// Sphinx key announced by a mixnode that periodically rotates its keys.
+#[derive(Debug, Clone)]
+pub struct RotatingSphinxKey {
+    pub rotation_id: u32,
+    pub public_key: encryption::PublicKey,
+
+    /// Unix timestamp since which the key should be used.
+    pub valid_from: i64,
+
+    /// Unix timestamp after which the key should no longer be used.
+    pub valid_until: i64,
+}
+
+impl RotatingSphinxKey {
+    pub fn is_valid_at(&self, unix_timestamp: i64) -> bool {
+        self.valid_from <= unix_timestamp && unix_timestamp < self.valid_until
+    }
+}
+
+/// Attempts to parse all the keys announced by the specified mixnode.
+pub fn parse_announced_keys(
+    announced: &MixnodeSphinxKeys,
+) -> Result<Vec<RotatingSphinxKey>, MixnodeConversionError> {
+    announced
+        .keys
+        .iter()
+        .map(|key| {
+            Ok(RotatingSphinxKey {
+                rotation_id: key.rotation_id,
+                public_key: encryption::PublicKey::from_base58_string(&key.public_key)?,
+                valid_from: key.valid_from,
+                valid_until: key.valid_until,
+            })
+        })
+        .collect()
+}
+
+/// Parses the keys announced by all the provided mixnodes. Nodes that have announced malformed keys
+/// are skipped, so that their bonded keys would be used instead.
+pub fn parse_all_announced_keys<'a, I>(announced: I) -> HashMap<MixId, Vec<RotatingSphinxKey>>
+where
+    I: IntoIterator<Item = &'a MixnodeSphinxKeys>,
+{
+    announced
+        .into_iter()
+        .filter_map(|node_keys| match parse_announced_keys(node_keys) {
+            Ok(keys) => Some((node_keys.mix_id, keys)),
+            Err(err) => {
+                warn!(
+                    "mixnode {} has announced malformed sphinx keys: {err}",
+                    node_keys.mix_id
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mix, NymTopology};
+    use nym_crypto::asymmetric::identity;
+    use nym_mixnet_contract_common::Layer;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn random_sphinx_key() -> encryption::PublicKey {
+        *encryption::KeyPair::new(&mut rand::thread_rng()).public_key()
+    }
+
+    fn mixnode(mix_id: u32) -> mix::Node {
+        mix::Node {
+            mix_id,
+            owner: "N/A".to_string(),
+            host: "3.3.3.3".parse().unwrap(),
+            mix_host: "3.3.3.3:1789".parse().unwrap(),
+            identity_key: *identity::KeyPair::new(&mut rand::thread_rng()).public_key(),
+            sphinx_key: random_sphinx_key(),
+            layer: Layer::One,
+            version: "1.1.0".into(),
+        }
+    }
+
+    #[test]
+    fn key_valid_at_given_time_is_chosen() {
+        let node = mixnode(1);
+        let other_node = mixnode(2);
+        let keys = vec![
+            RotatingSphinxKey {
+                rotation_id: 1,
+                public_key: random_sphinx_key(),
+                valid_from: 100,
+                valid_until: 200,
+            },
+            RotatingSphinxKey {
+                rotation_id: 2,
+                public_key: random_sphinx_key(),
+                valid_from: 200,
+                valid_until: 300,
+            },
+        ];
+
+        let topology = NymTopology::new(BTreeMap::new(), Vec::new())
+            .with_rotating_sphinx_keys(HashMap::from([(1, keys.clone())]));
+
+        assert_eq!(topology.mix_sphinx_key_at(&node, 150), &keys[0].public_key);
+        assert_eq!(topology.mix_sphinx_key_at(&node, 200), &keys[1].public_key);
+
+        // fallback to the bonded key if there's nothing valid
+        assert_eq!(topology.mix_sphinx_key_at(&node, 300), &node.sphinx_key);
+        assert_eq!(
+            topology.mix_sphinx_key_at(&other_node, 150),
+            &other_node.sphinx_key
+        );
+    }
+}
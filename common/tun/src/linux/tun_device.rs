@@ -49,6 +49,9 @@ pub enum TunDeviceError {
 
     #[error("unable to lock peer mutex")]
     FailedToLockPeer,
+
+    #[error("failed to assign ipv6 address {address} to the tun device: {stderr}")]
+    FailedToAssignIpv6Address { address: String, stderr: String },
 }
 
 fn setup_tokio_tun_device(
@@ -152,16 +155,18 @@ impl TunDevice {
 
         let tun = setup_tokio_tun_device(&name, ipv4, netmaskv4)?;
         log::info!("Created TUN device: {}", tun.name());
-        std::process::Command::new("ip")
-            .args([
-                "-6",
-                "addr",
-                "add",
-                &format!("{}/{}", ipv6, netmaskv6),
-                "dev",
-                &tun.name(),
-            ])
+
+        let ipv6_address = format!("{ipv6}/{netmaskv6}");
+        log::info!("Assigning {ipv6_address} to TUN device: {}", tun.name());
+        let output = std::process::Command::new("ip")
+            .args(["-6", "addr", "add", &ipv6_address, "dev", &tun.name()])
             .output()?;
+        if !output.status.success() {
+            return Err(TunDeviceError::FailedToAssignIpv6Address {
+                address: ipv6_address,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
         Ok(tun)
     }
 
@@ -165,6 +165,11 @@ pub struct TrafficWasm {
 
     /// Controls whether the sent packets should use outfox as opposed to the default sphinx.
     pub use_outfox: bool,
+
+    /// Number of additional parity fragments sent alongside each message to a known recipient,
+    /// so that it could be reconstructed even if up to that many of its packets got lost.
+    #[serde(default)]
+    pub erasure_coding_parity_fragments: u8,
 }
 
 impl Default for TrafficWasm {
@@ -195,6 +200,7 @@ impl From<TrafficWasm> for ConfigTraffic {
             primary_packet_size: PacketSize::RegularPacket,
             secondary_packet_size: use_extended_packet_size,
             packet_type,
+            erasure_coding_parity_fragments: traffic.erasure_coding_parity_fragments,
         }
     }
 }
@@ -209,6 +215,7 @@ impl From<ConfigTraffic> for TrafficWasm {
                 .disable_main_poisson_packet_distribution,
             use_extended_packet_size: traffic.secondary_packet_size.is_some(),
             use_outfox: traffic.packet_type == PacketType::Outfox,
+            erasure_coding_parity_fragments: traffic.erasure_coding_parity_fragments,
         }
     }
 }
@@ -267,6 +274,11 @@ pub struct GatewayConnectionWasm {
     /// How long we're willing to wait for a response to a message sent to the gateway,
     /// before giving up on it.
     pub gateway_response_timeout_ms: u32,
+
+    /// Number of additional gateways the client is going to register with,
+    /// so that it could switch to one of them if its primary gateway became unavailable.
+    #[serde(default)]
+    pub standby_gateways: u32,
 }
 
 impl Default for GatewayConnectionWasm {
@@ -281,6 +293,7 @@ impl From<GatewayConnectionWasm> for ConfigGatewayConnection {
             gateway_response_timeout: Duration::from_millis(
                 gateway_connection.gateway_response_timeout_ms as u64,
             ),
+            standby_gateways: gateway_connection.standby_gateways as usize,
         }
     }
 }
@@ -290,6 +303,7 @@ impl From<ConfigGatewayConnection> for GatewayConnectionWasm {
         GatewayConnectionWasm {
             gateway_response_timeout_ms: gateway_connection.gateway_response_timeout.as_millis()
                 as u32,
+            standby_gateways: gateway_connection.standby_gateways as u32,
         }
     }
 }
@@ -362,6 +376,10 @@ pub struct TopologyWasm {
     /// the first valid instance.
     /// Supersedes `topology_refresh_rate_ms`.
     pub disable_refreshing: bool,
+
+    /// Specifies how strongly the mix route selection should be biased towards more reliable nodes.
+    /// The value must be in range `[0, 1]`, where `0` means uniform selection.
+    pub route_selection_performance_bias: f64,
 }
 
 impl Default for TopologyWasm {
@@ -382,6 +400,10 @@ impl From<TopologyWasm> for ConfigTopology {
                 topology.max_startup_gateway_waiting_period_ms as u64,
             ),
             topology_structure: Default::default(),
+            route_selection_performance_bias: topology.route_selection_performance_bias,
+            // neither the rtt measurement nor the network events subscription is supported in wasm
+            route_selection_measure_rtt: false,
+            subscribe_to_network_events: false,
         }
     }
 }
@@ -395,6 +417,7 @@ impl From<ConfigTopology> for TopologyWasm {
                 .max_startup_gateway_waiting_period
                 .as_millis() as u32,
             disable_refreshing: topology.disable_refreshing,
+            route_selection_performance_bias: topology.route_selection_performance_bias,
         }
     }
 }
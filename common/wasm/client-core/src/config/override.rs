@@ -89,6 +89,11 @@ pub struct TrafficWasmOverride {
     /// Controls whether the sent packets should use outfox as opposed to the default sphinx.
     #[tsify(optional)]
     pub use_outfox: Option<bool>,
+
+    /// Number of additional parity fragments sent alongside each message to a known recipient,
+    /// so that it could be reconstructed even if up to that many of its packets got lost.
+    #[tsify(optional)]
+    pub erasure_coding_parity_fragments: Option<u8>,
 }
 
 impl From<TrafficWasmOverride> for TrafficWasm {
@@ -109,6 +114,9 @@ impl From<TrafficWasmOverride> for TrafficWasm {
                 .use_extended_packet_size
                 .unwrap_or(def.use_extended_packet_size),
             use_outfox: value.use_outfox.unwrap_or(def.use_outfox),
+            erasure_coding_parity_fragments: value
+                .erasure_coding_parity_fragments
+                .unwrap_or(def.erasure_coding_parity_fragments),
         }
     }
 }
@@ -159,6 +167,11 @@ pub struct GatewayConnectionWasmOverride {
     /// before giving up on it.
     #[tsify(optional)]
     pub gateway_response_timeout_ms: Option<u32>,
+
+    /// Number of additional gateways the client is going to register with,
+    /// so that it could switch to one of them if its primary gateway became unavailable.
+    #[tsify(optional)]
+    pub standby_gateways: Option<u32>,
 }
 
 impl From<GatewayConnectionWasmOverride> for GatewayConnectionWasm {
@@ -169,6 +182,7 @@ impl From<GatewayConnectionWasmOverride> for GatewayConnectionWasm {
             gateway_response_timeout_ms: value
                 .gateway_response_timeout_ms
                 .unwrap_or(def.gateway_response_timeout_ms),
+            standby_gateways: value.standby_gateways.unwrap_or(def.standby_gateways),
         }
     }
 }
@@ -238,6 +252,11 @@ pub struct TopologyWasmOverride {
     /// Supersedes `topology_refresh_rate_ms`.
     #[tsify(optional)]
     pub disable_refreshing: Option<bool>,
+
+    /// Specifies how strongly the mix route selection should be biased towards more reliable nodes.
+    /// The value must be in range `[0, 1]`, where `0` means uniform selection.
+    #[tsify(optional)]
+    pub route_selection_performance_bias: Option<f64>,
 }
 
 impl From<TopologyWasmOverride> for TopologyWasm {
@@ -255,6 +274,9 @@ impl From<TopologyWasmOverride> for TopologyWasm {
                 .max_startup_gateway_waiting_period_ms
                 .unwrap_or(def.max_startup_gateway_waiting_period_ms),
             disable_refreshing: value.disable_refreshing.unwrap_or(def.disable_refreshing),
+            route_selection_performance_bias: value
+                .route_selection_performance_bias
+                .unwrap_or(def.route_selection_performance_bias),
         }
     }
 }
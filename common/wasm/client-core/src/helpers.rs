@@ -22,6 +22,7 @@ use rand::thread_rng;
 use url::Url;
 use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen_futures::future_to_promise;
+use wasm_utils::console_warn;
 use wasm_utils::error::PromisableResult;
 
 pub use nym_credential_storage::ephemeral_storage::EphemeralStorage as EphemeralCredentialStorage;
@@ -69,8 +70,16 @@ pub async fn current_network_topology_async(
     let api_client = NymApiClient::new(url);
     let mixnodes = api_client.get_cached_active_mixnodes().await?;
     let gateways = api_client.get_cached_gateways().await?;
+    // failure is not fatal as the bonded keys are going to be used instead
+    let announced_sphinx_keys = api_client
+        .get_cached_mixnodes_sphinx_keys()
+        .await
+        .unwrap_or_else(|err| {
+            console_warn!("failed to get rotated mixnode sphinx keys: {err}");
+            Vec::new()
+        });
 
-    Ok(NymTopology::from_detailed(mixnodes, gateways).into())
+    Ok(NymTopology::from_detailed(mixnodes, gateways, &announced_sphinx_keys).into())
 }
 
 #[wasm_bindgen(js_name = "currentNetworkTopology")]
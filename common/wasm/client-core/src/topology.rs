@@ -8,6 +8,7 @@ use wasm_utils::console_log;
 
 pub use nym_topology::{
     gateway, mix, SerializableGateway, SerializableMixNode, SerializableNymTopology,
+    SerializableRotatingSphinxKey,
 };
 
 // redeclare this as a type alias for easy of use
@@ -0,0 +1,201 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optional extensions of the client-gateway protocol.
+//!
+//! During registration (or authentication) the client advertises all extensions it supports
+//! and the gateway responds with the subset it also supports. Only the negotiated extensions
+//! can be used for the remainder of the connection. As the capabilities are sent as plain strings,
+//! either side is going to ignore any extensions it doesn't know about, while the old peers
+//! (that do not advertise anything) are going to end up with an empty set.
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("'{0}' is not a known gateway capability")]
+pub struct UnknownGatewayCapability(String);
+
+/// Single extension of the client-gateway protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GatewayCapability {
+    /// The gateway periodically pushes [`ServerResponse::BandwidthReport`](crate::ServerResponse::BandwidthReport)
+    /// with the authoritative bandwidth remaining to the client and warns it when it's running low.
+    BandwidthReports,
+
+    /// The gateway accepts [`BinaryRequest::ForwardSphinxBatch`](crate::BinaryRequest::ForwardSphinxBatch)
+    /// carrying multiple mix packets in a single websocket message.
+    ForwardSphinxBatch,
+}
+
+impl GatewayCapability {
+    /// All extensions implemented by this version of the protocol.
+    pub const ALL: &'static [GatewayCapability] = &[
+        GatewayCapability::BandwidthReports,
+        GatewayCapability::ForwardSphinxBatch,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GatewayCapability::BandwidthReports => "bandwidth_reports",
+            GatewayCapability::ForwardSphinxBatch => "forward_sphinx_batch",
+        }
+    }
+}
+
+impl Display for GatewayCapability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GatewayCapability {
+    type Err = UnknownGatewayCapability;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GatewayCapability::ALL
+            .iter()
+            .find(|capability| capability.as_str() == s)
+            .copied()
+            .ok_or_else(|| UnknownGatewayCapability(s.to_string()))
+    }
+}
+
+/// Set of protocol extensions either supported by one side of the connection
+/// or negotiated between the client and the gateway.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Vec<String>", into = "Vec<String>")]
+pub struct GatewayCapabilities {
+    capabilities: BTreeSet<GatewayCapability>,
+}
+
+impl GatewayCapabilities {
+    /// Returns all extensions supported by this version of the protocol.
+    pub fn supported() -> Self {
+        GatewayCapability::ALL.iter().copied().collect()
+    }
+
+    /// Returns the extensions supported by both sides of the connection.
+    pub fn negotiate(&self, remote: &GatewayCapabilities) -> GatewayCapabilities {
+        self.capabilities
+            .intersection(&remote.capabilities)
+            .copied()
+            .collect()
+    }
+
+    pub fn supports(&self, capability: GatewayCapability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.capabilities.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = GatewayCapability> + '_ {
+        self.capabilities.iter().copied()
+    }
+}
+
+impl FromIterator<GatewayCapability> for GatewayCapabilities {
+    fn from_iter<I: IntoIterator<Item = GatewayCapability>>(iter: I) -> Self {
+        GatewayCapabilities {
+            capabilities: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl From<Vec<String>> for GatewayCapabilities {
+    fn from(raw: Vec<String>) -> Self {
+        raw.iter()
+            .filter_map(|raw_capability| match raw_capability.parse() {
+                Ok(capability) => Some(capability),
+                Err(err) => {
+                    // the remote is probably running a newer version of the protocol
+                    debug!("{err}");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl From<GatewayCapabilities> for Vec<String> {
+    fn from(capabilities: GatewayCapabilities) -> Self {
+        capabilities
+            .iter()
+            .map(|capability| capability.as_str().to_string())
+            .collect()
+    }
+}
+
+impl Display for GatewayCapabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+        let names = self
+            .iter()
+            .map(|capability| capability.as_str())
+            .collect::<Vec<_>>();
+        f.write_str(&names.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ClientControlRequest, ServerResponse};
+
+    #[test]
+    fn unknown_capabilities_are_ignored() {
+        let raw = vec!["some_future_extension".to_string()];
+        let capabilities = GatewayCapabilities::from(raw);
+        assert!(capabilities.is_empty());
+
+        let raw = vec![
+            "some_future_extension".to_string(),
+            "bandwidth_reports".to_string(),
+        ];
+        let capabilities = GatewayCapabilities::from(raw);
+        assert!(capabilities.supports(GatewayCapability::BandwidthReports));
+        assert_eq!(capabilities.iter().count(), 1);
+    }
+
+    #[test]
+    fn negotiated_capabilities_are_supported_by_both_sides() {
+        let supported = GatewayCapabilities::supported();
+        assert_eq!(supported.negotiate(&supported), supported);
+        assert!(supported
+            .negotiate(&GatewayCapabilities::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn capabilities_survive_serialization() {
+        let supported = GatewayCapabilities::supported();
+        let serialized = serde_json::to_string(&supported).unwrap();
+        let deserialized: GatewayCapabilities = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(supported, deserialized);
+    }
+
+    #[test]
+    fn messages_of_old_peers_result_in_no_capabilities() {
+        let old_authenticate = r#"{"type":"authenticate","protocol_version":2,"address":"foo","enc_address":"bar","iv":"baz"}"#;
+        match ClientControlRequest::try_from(old_authenticate.to_string()).unwrap() {
+            ClientControlRequest::Authenticate { capabilities, .. } => {
+                assert!(capabilities.is_empty())
+            }
+            _ => unreachable!("this branch shouldn't have been reached!"),
+        }
+
+        let old_register = r#"{"type":"register","protocol_version":2,"status":true}"#;
+        match ServerResponse::try_from(old_register.to_string()).unwrap() {
+            ServerResponse::Register { capabilities, .. } => assert!(capabilities.is_empty()),
+            _ => unreachable!("this branch shouldn't have been reached!"),
+        }
+    }
+}
@@ -8,6 +8,7 @@ use nym_sphinx::params::GatewayIntegrityHmacAlgorithm;
 pub use types::*;
 
 pub mod authentication;
+pub mod capabilities;
 pub mod iv;
 pub mod models;
 pub mod registration;
@@ -1,6 +1,7 @@
 // Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::capabilities::GatewayCapabilities;
 use crate::registration::handshake::shared_key::SharedKeys;
 use crate::registration::handshake::state::State;
 use crate::registration::handshake::{error::HandshakeError, WsItem};
@@ -25,6 +26,7 @@ impl<'a> ClientHandshake<'a> {
         identity: &'a nym_crypto::asymmetric::identity::KeyPair,
         gateway_pubkey: identity::PublicKey,
         expects_credential_usage: bool,
+        capabilities: GatewayCapabilities,
     ) -> Self
     where
         S: Stream<Item = WsItem> + Sink<WsMessage> + Unpin + Send + 'a,
@@ -35,6 +37,7 @@ impl<'a> ClientHandshake<'a> {
             identity,
             Some(gateway_pubkey),
             expects_credential_usage,
+            capabilities,
         );
 
         ClientHandshake {
@@ -1,6 +1,7 @@
 // Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::capabilities::GatewayCapabilities;
 use crate::registration::handshake::shared_key::SharedKeys;
 use crate::registration::handshake::state::State;
 use crate::registration::handshake::{error::HandshakeError, WsItem};
@@ -26,7 +27,15 @@ impl<'a> GatewayHandshake<'a> {
     where
         S: Stream<Item = WsItem> + Sink<WsMessage> + Unpin + Send + 'a,
     {
-        let mut state = State::new(rng, ws_stream, identity, None, true);
+        // the gateway responds with the negotiated capabilities only after completing the handshake
+        let mut state = State::new(
+            rng,
+            ws_stream,
+            identity,
+            None,
+            true,
+            GatewayCapabilities::default(),
+        );
         GatewayHandshake {
             handshake_future: Box::pin(async move {
                 // If any step along the way failed (that are non-network related),
@@ -6,6 +6,7 @@ use self::error::HandshakeError;
 #[cfg(not(target_arch = "wasm32"))]
 use self::gateway::GatewayHandshake;
 pub use self::shared_key::{SharedKeySize, SharedKeys};
+use crate::capabilities::GatewayCapabilities;
 use futures::{Sink, Stream};
 use nym_crypto::asymmetric::identity;
 use rand::{CryptoRng, RngCore};
@@ -31,6 +32,7 @@ pub async fn client_handshake<'a, S>(
     identity: &'a identity::KeyPair,
     gateway_pubkey: identity::PublicKey,
     expects_credential_usage: bool,
+    capabilities: GatewayCapabilities,
 ) -> Result<SharedKeys, HandshakeError>
 where
     S: Stream<Item = WsItem> + Sink<WsMessage> + Unpin + Send + 'a,
@@ -41,6 +43,7 @@ where
         identity,
         gateway_pubkey,
         expects_credential_usage,
+        capabilities,
     )
     .await
 }
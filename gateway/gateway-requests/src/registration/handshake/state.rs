@@ -1,6 +1,7 @@
 // Copyright 2020-2024 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::capabilities::GatewayCapabilities;
 use crate::registration::handshake::error::HandshakeError;
 use crate::registration::handshake::shared_key::{SharedKeySize, SharedKeys};
 use crate::registration::handshake::WsItem;
@@ -48,6 +49,9 @@ pub(crate) struct State<'a, S> {
     // this field is really out of place here, however, we need to propagate this information somehow
     // in order to establish correct protocol for backwards compatibility reasons
     expects_credential_usage: bool,
+
+    /// Protocol extensions advertised by the client alongside its init message.
+    advertised_capabilities: GatewayCapabilities,
 }
 
 impl<'a, S> State<'a, S> {
@@ -57,6 +61,7 @@ impl<'a, S> State<'a, S> {
         identity: &'a identity::KeyPair,
         remote_pubkey: Option<identity::PublicKey>,
         expects_credential_usage: bool,
+        advertised_capabilities: GatewayCapabilities,
     ) -> Self {
         let ephemeral_keypair = encryption::KeyPair::new(rng);
         State {
@@ -66,6 +71,7 @@ impl<'a, S> State<'a, S> {
             remote_pubkey,
             derived_shared_keys: None,
             expects_credential_usage,
+            advertised_capabilities,
         }
     }
 
@@ -271,8 +277,12 @@ impl<'a, S> State<'a, S> {
     where
         S: Sink<WsMessage> + Unpin,
     {
-        let handshake_message =
-            types::RegistrationHandshake::new_payload(payload, self.expects_credential_usage);
+        // the capabilities only have to be attached to the first message we send
+        let handshake_message = types::RegistrationHandshake::new_payload(
+            payload,
+            self.expects_credential_usage,
+            std::mem::take(&mut self.advertised_capabilities),
+        );
         self.ws_stream
             .send(WsMessage::Text(handshake_message.try_into().unwrap()))
             .await
@@ -2,21 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::authentication::encrypted_address::EncryptedAddressBytes;
+use crate::capabilities::GatewayCapabilities;
 use crate::iv::IV;
 use crate::models::{CredentialSpendingRequest, OldV1Credential};
 use crate::registration::handshake::SharedKeys;
 use crate::{GatewayMacSize, CURRENT_PROTOCOL_VERSION, INITIAL_PROTOCOL_VERSION};
-use log::error;
 use nym_credentials::coconut::bandwidth::CredentialSpendingData;
 use nym_credentials_interface::{CoconutError, UnknownCredentialType};
 use nym_crypto::generic_array::typenum::Unsigned;
-use nym_crypto::hmac::recompute_keyed_hmac_and_verify_tag;
-use nym_crypto::symmetric::stream_cipher;
+use nym_crypto::hmac::{compute_keyed_hmac, recompute_keyed_hmac_and_verify_tag};
+use nym_crypto::symmetric::stream_cipher::{self, IvSizeUser};
 use nym_sphinx::addressing::nodes::NymNodeRoutingAddressError;
 use nym_sphinx::forwarding::packet::{MixPacket, MixPacketFormattingError};
 use nym_sphinx::params::packet_sizes::PacketSize;
 use nym_sphinx::params::{GatewayEncryptionAlgorithm, GatewayIntegrityHmacAlgorithm};
 use nym_sphinx::DestinationAddressBytes;
+use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
@@ -30,6 +31,8 @@ pub enum RegistrationHandshake {
     HandshakePayload {
         #[serde(default)]
         protocol_version: Option<u8>,
+        #[serde(default, skip_serializing_if = "GatewayCapabilities::is_empty")]
+        capabilities: GatewayCapabilities,
         data: Vec<u8>,
     },
     HandshakeError {
@@ -38,7 +41,11 @@ pub enum RegistrationHandshake {
 }
 
 impl RegistrationHandshake {
-    pub fn new_payload(data: Vec<u8>, will_use_credentials: bool) -> Self {
+    pub fn new_payload(
+        data: Vec<u8>,
+        will_use_credentials: bool,
+        capabilities: GatewayCapabilities,
+    ) -> Self {
         // if we're not going to be using credentials, advertise lower protocol version to allow connection
         // to wider range of gateways
         let protocol_version = if will_use_credentials {
@@ -49,6 +56,7 @@ impl RegistrationHandshake {
 
         RegistrationHandshake::HandshakePayload {
             protocol_version,
+            capabilities,
             data,
         }
     }
@@ -136,6 +144,12 @@ pub enum GatewayRequestsError {
 
     #[error("the provided [v1] credential has invalid number of parameters - {0}")]
     InvalidNumberOfEmbededParameters(u32),
+
+    #[error("the received batch of mix packets was malformed")]
+    MalformedMixPacketBatch,
+
+    #[error("the batch of {size} mix packets exceeds the maximum of {max}", max = MAX_MIX_PACKET_BATCH_SIZE)]
+    MixPacketBatchTooLarge { size: usize },
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -146,6 +160,8 @@ pub enum ClientControlRequest {
     Authenticate {
         #[serde(default)]
         protocol_version: Option<u8>,
+        #[serde(default, skip_serializing_if = "GatewayCapabilities::is_empty")]
+        capabilities: GatewayCapabilities,
         address: String,
         enc_address: String,
         iv: String,
@@ -154,6 +170,8 @@ pub enum ClientControlRequest {
     RegisterHandshakeInitRequest {
         #[serde(default)]
         protocol_version: Option<u8>,
+        #[serde(default, skip_serializing_if = "GatewayCapabilities::is_empty")]
+        capabilities: GatewayCapabilities,
         data: Vec<u8>,
     },
     BandwidthCredential {
@@ -173,6 +191,7 @@ impl ClientControlRequest {
         enc_address: EncryptedAddressBytes,
         iv: IV,
         uses_credentials: bool,
+        capabilities: GatewayCapabilities,
     ) -> Self {
         // if we're not going to be using credentials, advertise lower protocol version to allow connection
         // to wider range of gateways
@@ -184,6 +203,7 @@ impl ClientControlRequest {
 
         ClientControlRequest::Authenticate {
             protocol_version,
+            capabilities,
             address: address.as_base58_string(),
             enc_address: enc_address.to_base58_string(),
             iv: iv.to_base58_string(),
@@ -271,12 +291,18 @@ pub enum ServerResponse {
     Authenticate {
         #[serde(default)]
         protocol_version: Option<u8>,
+        /// Protocol extensions supported by both the client and the gateway.
+        #[serde(default, skip_serializing_if = "GatewayCapabilities::is_empty")]
+        capabilities: GatewayCapabilities,
         status: bool,
         bandwidth_remaining: i64,
     },
     Register {
         #[serde(default)]
         protocol_version: Option<u8>,
+        /// Protocol extensions supported by both the client and the gateway.
+        #[serde(default, skip_serializing_if = "GatewayCapabilities::is_empty")]
+        capabilities: GatewayCapabilities,
         status: bool,
     },
     Bandwidth {
@@ -285,6 +311,13 @@ pub enum ServerResponse {
     Send {
         remaining_bandwidth: i64,
     },
+    /// Authoritative amount of bandwidth available to the client pushed by the gateway without
+    /// a prior request. It's only ever sent if the `bandwidth_reports` capability got negotiated.
+    BandwidthReport {
+        available_total: i64,
+        /// Indicates the client should acquire more bandwidth before its traffic gets rejected.
+        running_low: bool,
+    },
     Error {
         message: String,
     },
@@ -327,13 +360,27 @@ impl TryFrom<String> for ServerResponse {
     }
 }
 
+/// Leading byte of the serialized `ForwardSphinxBatch` request. It's sent in the clear, followed by
+/// the random IV used for the encryption of the batch and the MAC over both the IV and the ciphertext.
+const FORWARD_SPHINX_BATCH_MARKER: u8 = u8::MAX;
+const BATCH_ENTRY_LENGTH_PREFIX_LEN: usize = 4;
+
+/// Maximum number of mix packets that can be sent in a single `ForwardSphinxBatch` request.
+pub const MAX_MIX_PACKET_BATCH_SIZE: usize = 64;
+
 pub enum BinaryRequest {
     ForwardSphinx(MixPacket),
+
+    /// Multiple mix packets sent under a single encryption and MAC.
+    /// Can only be used if the `forward_sphinx_batch` capability got negotiated.
+    ForwardSphinxBatch(Vec<MixPacket>),
 }
 
-// Right now the only valid `BinaryRequest` is a request to forward a sphinx packet.
-// It is encrypted using the derived shared key between client and the gateway. Thanks to
-// randomness inside the sphinx packet themselves (even via the same route), the 0s IV can be used here.
+// `ForwardSphinx` requests are encrypted using the derived shared key between client and the gateway.
+// Thanks to randomness inside the sphinx packet themselves (even via the same route), the 0s IV can
+// be used there.
+// That argument does not extend to `ForwardSphinxBatch`, as the marker and the length prefixes would
+// give known plaintext under a repeated keystream, so each batch is encrypted under a fresh random IV.
 // HOWEVER, NOTE: If we introduced another 'BinaryRequest', we must carefully examine if a 0s IV
 // would work there.
 impl BinaryRequest {
@@ -341,29 +388,138 @@ impl BinaryRequest {
         raw_req: Vec<u8>,
         shared_keys: &SharedKeys,
     ) -> Result<Self, GatewayRequestsError> {
-        let message_bytes = &shared_keys.decrypt_tagged(&raw_req, None)?;
+        // the marker is sent in the clear, so a single request could start with it by chance,
+        // in which case the MAC of the batch is not going to match
+        if let Some(batch) = raw_req.strip_prefix(&[FORWARD_SPHINX_BATCH_MARKER]) {
+            if let Some(batch_bytes) = Self::try_decrypt_batch(batch, shared_keys) {
+                return Ok(BinaryRequest::ForwardSphinxBatch(
+                    Self::recover_mix_packet_batch(&batch_bytes)?,
+                ));
+            }
+        }
 
-        // right now there's only a single option possible which significantly simplifies the logic
-        // if we decided to allow for more 'binary' messages, the API wouldn't need to change.
-        let mix_packet = MixPacket::try_from_bytes(message_bytes)?;
-        Ok(BinaryRequest::ForwardSphinx(mix_packet))
+        let message_bytes = shared_keys.decrypt_tagged(&raw_req, None)?;
+        Ok(BinaryRequest::ForwardSphinx(MixPacket::try_from_bytes(
+            &message_bytes,
+        )?))
     }
 
-    pub fn into_encrypted_tagged_bytes(self, shared_key: &SharedKeys) -> Vec<u8> {
+    // the encrypted batch is formatted as follows:
+    // MAC(IV || CIPHERTEXT) || IV || CIPHERTEXT
+    // returns `None` if the MAC doesn't match
+    fn try_decrypt_batch(b: &[u8], shared_keys: &SharedKeys) -> Option<Vec<u8>> {
+        let mac_size = GatewayMacSize::to_usize();
+        let iv_size = <GatewayEncryptionAlgorithm as IvSizeUser>::IvSize::to_usize();
+        if b.len() < mac_size + iv_size {
+            return None;
+        }
+
+        let (mac_tag, authenticated) = b.split_at(mac_size);
+        if !recompute_keyed_hmac_and_verify_tag::<GatewayIntegrityHmacAlgorithm>(
+            shared_keys.mac_key(),
+            authenticated,
+            mac_tag,
+        ) {
+            return None;
+        }
+
+        let (iv, ciphertext) = authenticated.split_at(iv_size);
+        Some(stream_cipher::decrypt::<GatewayEncryptionAlgorithm>(
+            shared_keys.encryption_key(),
+            stream_cipher::iv_from_slice::<GatewayEncryptionAlgorithm>(iv),
+            ciphertext,
+        ))
+    }
+
+    fn encrypt_batch(batch_bytes: &[u8], shared_keys: &SharedKeys) -> Vec<u8> {
+        let iv = IV::new_random(&mut OsRng);
+        let authenticated: Vec<u8> = iv
+            .as_bytes()
+            .iter()
+            .copied()
+            .chain(stream_cipher::encrypt::<GatewayEncryptionAlgorithm>(
+                shared_keys.encryption_key(),
+                iv.inner(),
+                batch_bytes,
+            ))
+            .collect();
+        let mac = compute_keyed_hmac::<GatewayIntegrityHmacAlgorithm>(
+            shared_keys.mac_key(),
+            &authenticated,
+        );
+
+        std::iter::once(FORWARD_SPHINX_BATCH_MARKER)
+            .chain(mac.into_bytes())
+            .chain(authenticated)
+            .collect()
+    }
+
+    // the decrypted batch is formatted as follows:
+    // (len(packet) as u32 BE || packet)*
+    fn recover_mix_packet_batch(mut b: &[u8]) -> Result<Vec<MixPacket>, GatewayRequestsError> {
+        let mut mix_packets = Vec::new();
+        while !b.is_empty() {
+            if mix_packets.len() == MAX_MIX_PACKET_BATCH_SIZE {
+                return Err(GatewayRequestsError::MixPacketBatchTooLarge {
+                    size: mix_packets.len() + 1,
+                });
+            }
+            if b.len() < BATCH_ENTRY_LENGTH_PREFIX_LEN {
+                return Err(GatewayRequestsError::MalformedMixPacketBatch);
+            }
+            let (len_prefix, remaining) = b.split_at(BATCH_ENTRY_LENGTH_PREFIX_LEN);
+            // the unwrap is fine as we've just split exactly `BATCH_ENTRY_LENGTH_PREFIX_LEN` bytes
+            let len = u32::from_be_bytes(len_prefix.try_into().unwrap()) as usize;
+            if len == 0 || remaining.len() < len {
+                return Err(GatewayRequestsError::MalformedMixPacketBatch);
+            }
+
+            let (packet, remaining) = remaining.split_at(len);
+            mix_packets.push(MixPacket::try_from_bytes(packet)?);
+            b = remaining;
+        }
+
+        if mix_packets.is_empty() {
+            return Err(GatewayRequestsError::MalformedMixPacketBatch);
+        }
+        Ok(mix_packets)
+    }
+
+    fn mix_packet_batch_into_bytes(
+        mix_packets: Vec<MixPacket>,
+    ) -> Result<Vec<u8>, GatewayRequestsError> {
+        if mix_packets.is_empty() {
+            return Err(GatewayRequestsError::MalformedMixPacketBatch);
+        }
+        if mix_packets.len() > MAX_MIX_PACKET_BATCH_SIZE {
+            return Err(GatewayRequestsError::MixPacketBatchTooLarge {
+                size: mix_packets.len(),
+            });
+        }
+
+        let mut batch_bytes = Vec::new();
+        for mix_packet in mix_packets {
+            let packet_bytes = mix_packet.into_bytes()?;
+            batch_bytes.extend_from_slice(&(packet_bytes.len() as u32).to_be_bytes());
+            batch_bytes.extend_from_slice(&packet_bytes);
+        }
+        Ok(batch_bytes)
+    }
+
+    pub fn into_encrypted_tagged_bytes(
+        self,
+        shared_key: &SharedKeys,
+    ) -> Result<Vec<u8>, GatewayRequestsError> {
         match self {
+            // TODO: it could be theoretically slightly more efficient if the data wasn't taken
+            // by reference because then it makes a copy for encryption rather than do it in place
             BinaryRequest::ForwardSphinx(mix_packet) => {
-                let forwarding_data = match mix_packet.into_bytes() {
-                    Ok(mix_packet) => mix_packet,
-                    Err(e) => {
-                        error!("Could not convert packet to bytes: {e}");
-                        return vec![];
-                    }
-                };
-
-                // TODO: it could be theoretically slightly more efficient if the data wasn't taken
-                // by reference because then it makes a copy for encryption rather than do it in place
-                shared_key.encrypt_and_tag(&forwarding_data, None)
+                Ok(shared_key.encrypt_and_tag(&mix_packet.into_bytes()?, None))
             }
+            BinaryRequest::ForwardSphinxBatch(mix_packets) => Ok(Self::encrypt_batch(
+                &Self::mix_packet_batch_into_bytes(mix_packets)?,
+                shared_key,
+            )),
         }
     }
 
@@ -372,8 +528,14 @@ impl BinaryRequest {
         BinaryRequest::ForwardSphinx(mix_packet)
     }
 
-    pub fn into_ws_message(self, shared_key: &SharedKeys) -> Message {
-        Message::Binary(self.into_encrypted_tagged_bytes(shared_key))
+    pub fn new_forward_batch_request(mix_packets: Vec<MixPacket>) -> BinaryRequest {
+        BinaryRequest::ForwardSphinxBatch(mix_packets)
+    }
+
+    pub fn into_ws_message(self, shared_key: &SharedKeys) -> Result<Message, GatewayRequestsError> {
+        Ok(Message::Binary(
+            self.into_encrypted_tagged_bytes(shared_key)?,
+        ))
     }
 }
 
@@ -433,12 +595,20 @@ impl BinaryResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use nym_sphinx::addressing::nodes::NymNodeRoutingAddress;
+    use nym_sphinx::params::PacketType;
+    use nym_sphinx::{
+        crypto, Delay, Destination, Node, NodeAddressBytes, NymPacket, DESTINATION_ADDRESS_LENGTH,
+        IDENTIFIER_LENGTH, NODE_ADDRESS_LENGTH,
+    };
+    use std::net::SocketAddr;
 
     #[test]
     fn handshake_payload_can_be_deserialized_into_register_handshake_init_request() {
         let handshake_data = vec![1, 2, 3, 4, 5, 6];
         let handshake_payload_with_protocol = RegistrationHandshake::HandshakePayload {
             protocol_version: Some(42),
+            capabilities: GatewayCapabilities::supported(),
             data: handshake_data.clone(),
         };
         let serialized = serde_json::to_string(&handshake_payload_with_protocol).unwrap();
@@ -447,9 +617,11 @@ mod tests {
         match deserialized {
             ClientControlRequest::RegisterHandshakeInitRequest {
                 protocol_version,
+                capabilities,
                 data,
             } => {
                 assert_eq!(protocol_version, Some(42));
+                assert_eq!(capabilities, GatewayCapabilities::supported());
                 assert_eq!(data, handshake_data)
             }
             _ => unreachable!("this branch shouldn't have been reached!"),
@@ -457,6 +629,7 @@ mod tests {
 
         let handshake_payload_without_protocol = RegistrationHandshake::HandshakePayload {
             protocol_version: None,
+            capabilities: Default::default(),
             data: handshake_data.clone(),
         };
         let serialized = serde_json::to_string(&handshake_payload_without_protocol).unwrap();
@@ -466,6 +639,7 @@ mod tests {
             ClientControlRequest::RegisterHandshakeInitRequest {
                 protocol_version,
                 data,
+                ..
             } => {
                 assert!(protocol_version.is_none());
                 assert_eq!(data, handshake_data)
@@ -473,4 +647,181 @@ mod tests {
             _ => unreachable!("this branch shouldn't have been reached!"),
         }
     }
+
+    #[test]
+    fn bandwidth_report_survives_websocket_round_trip() {
+        let report = ServerResponse::BandwidthReport {
+            available_total: 12345,
+            running_low: true,
+        };
+        let Message::Text(serialized) = Message::from(report) else {
+            panic!("the response wasn't serialized into a text message")
+        };
+
+        match ServerResponse::try_from(serialized).unwrap() {
+            ServerResponse::BandwidthReport {
+                available_total,
+                running_low,
+            } => {
+                assert_eq!(available_total, 12345);
+                assert!(running_low);
+            }
+            _ => panic!("unexpected response"),
+        }
+    }
+
+    #[test]
+    fn bandwidth_report_is_not_confused_with_bandwidth_response() {
+        let response: Message = ServerResponse::Bandwidth {
+            available_total: 42,
+        }
+        .into();
+        let Message::Text(serialized) = response else {
+            panic!("the response wasn't serialized into a text message")
+        };
+
+        assert!(matches!(
+            ServerResponse::try_from(serialized).unwrap(),
+            ServerResponse::Bandwidth {
+                available_total: 42
+            }
+        ));
+    }
+
+    #[test]
+    fn malformed_mix_packet_batches_are_rejected() {
+        // no packets at all
+        assert!(BinaryRequest::recover_mix_packet_batch(&[]).is_err());
+        // truncated length prefix
+        assert!(BinaryRequest::recover_mix_packet_batch(&[0, 0, 1]).is_err());
+        // empty packet
+        assert!(BinaryRequest::recover_mix_packet_batch(&[0, 0, 0, 0]).is_err());
+        // packet shorter than its declared length
+        assert!(BinaryRequest::recover_mix_packet_batch(&[0, 0, 0, 5, 0, 1]).is_err());
+    }
+
+    fn shared_keys() -> SharedKeys {
+        SharedKeys::try_from_bytes(&[42; 32]).unwrap()
+    }
+
+    fn mix_packet(message: &[u8]) -> MixPacket {
+        let route: Vec<_> = (1..=3)
+            .map(|i| {
+                let (_, pub_key) = crypto::keygen();
+                Node::new(
+                    NodeAddressBytes::from_bytes([i; NODE_ADDRESS_LENGTH]),
+                    pub_key,
+                )
+            })
+            .collect();
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([4; DESTINATION_ADDRESS_LENGTH]),
+            [5; IDENTIFIER_LENGTH],
+        );
+        let delays: Vec<_> = (0..3).map(|_| Delay::new_from_nanos(42)).collect();
+        let packet = NymPacket::sphinx_build(
+            PacketSize::default().payload_size(),
+            message,
+            &route,
+            &destination,
+            &delays,
+        )
+        .unwrap();
+        let next_hop = NymNodeRoutingAddress::from(SocketAddr::from(([1, 2, 3, 4], 1789)));
+
+        MixPacket::new(next_hop, packet, PacketType::default())
+    }
+
+    fn packet_bytes(mix_packet: MixPacket) -> Vec<u8> {
+        mix_packet.into_bytes().unwrap()
+    }
+
+    #[test]
+    fn single_mix_packet_survives_encryption_round_trip() {
+        let keys = shared_keys();
+        let expected = packet_bytes(mix_packet(b"foomp"));
+
+        let encrypted = BinaryRequest::ForwardSphinx(MixPacket::try_from_bytes(&expected).unwrap())
+            .into_encrypted_tagged_bytes(&keys)
+            .unwrap();
+        match BinaryRequest::try_from_encrypted_tagged_bytes(encrypted, &keys).unwrap() {
+            BinaryRequest::ForwardSphinx(recovered) => {
+                assert_eq!(packet_bytes(recovered), expected)
+            }
+            BinaryRequest::ForwardSphinxBatch(_) => panic!("recovered a batch"),
+        }
+    }
+
+    #[test]
+    fn mix_packet_batch_survives_encryption_round_trip() {
+        let keys = shared_keys();
+        let expected: Vec<_> = [b"foo".as_slice(), b"bar", b"baz"]
+            .into_iter()
+            .map(|message| packet_bytes(mix_packet(message)))
+            .collect();
+        let packets = expected
+            .iter()
+            .map(|packet| MixPacket::try_from_bytes(packet).unwrap())
+            .collect();
+
+        let encrypted = BinaryRequest::ForwardSphinxBatch(packets)
+            .into_encrypted_tagged_bytes(&keys)
+            .unwrap();
+        match BinaryRequest::try_from_encrypted_tagged_bytes(encrypted, &keys).unwrap() {
+            BinaryRequest::ForwardSphinxBatch(recovered) => assert_eq!(
+                recovered.into_iter().map(packet_bytes).collect::<Vec<_>>(),
+                expected
+            ),
+            BinaryRequest::ForwardSphinx(_) => panic!("recovered a single packet"),
+        }
+    }
+
+    #[test]
+    fn mix_packet_batches_use_fresh_ivs() {
+        let keys = shared_keys();
+        let packet = packet_bytes(mix_packet(b"foomp"));
+        let encrypt = || {
+            let batch = vec![MixPacket::try_from_bytes(&packet).unwrap()];
+            BinaryRequest::ForwardSphinxBatch(batch)
+                .into_encrypted_tagged_bytes(&keys)
+                .unwrap()
+        };
+
+        assert_ne!(encrypt(), encrypt());
+    }
+
+    #[test]
+    fn tampered_mix_packet_batch_is_rejected() {
+        let keys = shared_keys();
+        let mut encrypted = BinaryRequest::ForwardSphinxBatch(vec![mix_packet(b"foomp")])
+            .into_encrypted_tagged_bytes(&keys)
+            .unwrap();
+
+        // flip a bit of the IV, which is covered by the MAC
+        encrypted[1 + GatewayMacSize::to_usize()] ^= 1;
+        assert!(BinaryRequest::try_from_encrypted_tagged_bytes(encrypted, &keys).is_err());
+    }
+
+    #[test]
+    fn oversized_mix_packet_batches_are_rejected() {
+        let keys = shared_keys();
+        let packet = packet_bytes(mix_packet(b"foomp"));
+        let batch = (0..=MAX_MIX_PACKET_BATCH_SIZE)
+            .map(|_| MixPacket::try_from_bytes(&packet).unwrap())
+            .collect();
+
+        assert!(matches!(
+            BinaryRequest::ForwardSphinxBatch(batch).into_encrypted_tagged_bytes(&keys),
+            Err(GatewayRequestsError::MixPacketBatchTooLarge { .. })
+        ));
+
+        // the gateway doesn't accept them either, even if the client didn't enforce the limit
+        let entry = [(packet.len() as u32).to_be_bytes().as_slice(), &packet].concat();
+        let batch_bytes = entry.repeat(MAX_MIX_PACKET_BATCH_SIZE + 1);
+        let encrypted = BinaryRequest::encrypt_batch(&batch_bytes, &keys);
+        assert!(matches!(
+            BinaryRequest::try_from_encrypted_tagged_bytes(encrypted, &keys),
+            Err(GatewayRequestsError::MixPacketBatchTooLarge { .. })
+        ));
+    }
 }
@@ -0,0 +1,12 @@
+/*
+ * Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+-- unix timestamp of when the message got stored, used for evicting stale messages
+ALTER TABLE message_store ADD COLUMN stored_at INTEGER NOT NULL DEFAULT 0;
+
+-- treat all messages stored before the migration as if they have just been received
+UPDATE message_store SET stored_at = CAST(strftime('%s', 'now') AS INTEGER);
+
+CREATE INDEX `message_store_stored_at_index` ON `message_store` (`stored_at`);
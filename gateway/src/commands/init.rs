@@ -327,7 +327,7 @@ mod tests {
             None,
             identity_keys,
             sphinx_keys,
-            InMemStorage,
+            InMemStorage::default(),
         )
         .await;
     }
@@ -3,6 +3,7 @@
 
 use crate::config::persistence::paths::GatewayPaths;
 use crate::config::template::CONFIG_TEMPLATE;
+use crate::error::GatewayError;
 use log::{debug, warn};
 use nym_bin_common::logging::LoggingSettings;
 use nym_config::defaults::{DEFAULT_CLIENT_LISTENING_PORT, DEFAULT_MIX_LISTENING_PORT};
@@ -40,6 +41,12 @@ const DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE: usize = 2000;
 
 const DEFAULT_STORED_MESSAGE_FILENAME_LENGTH: u16 = 16;
 const DEFAULT_MESSAGE_RETRIEVAL_LIMIT: i64 = 100;
+const DEFAULT_MAXIMUM_CLIENT_INBOX_MESSAGES: u64 = 50_000;
+const DEFAULT_MAXIMUM_CLIENT_INBOX_BYTES: u64 = 128 * 1024 * 1024;
+const DEFAULT_STORED_MESSAGE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const DEFAULT_STORED_MESSAGES_PRUNING_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_NOISE_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_NOISE_KEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 fn de_maybe_port<'de, D>(deserializer: D) -> Result<Option<u16>, D::Error>
 where
@@ -283,6 +290,15 @@ impl Config {
     pub fn get_cosmos_mnemonic(&self) -> bip39::Mnemonic {
         self.gateway.cosmos_mnemonic.clone()
     }
+
+    /// Makes sure the values that can't be represented by the types alone are sensible.
+    pub fn validate(&self) -> Result<(), GatewayError> {
+        // a zero period would make the pruner panic on startup
+        if self.debug.stored_messages_pruning_interval.is_zero() {
+            return Err(GatewayError::ZeroStoredMessagesPruningInterval);
+        }
+        Ok(())
+    }
 }
 
 // we only really care about the mnemonic being zeroized
@@ -391,6 +407,18 @@ impl Default for IpPacketRouter {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// Sqlite database located at `storage_paths.clients_storage`.
+    #[default]
+    Sqlite,
+
+    /// Non-persistent storage that is lost once the gateway is stopped.
+    /// Useful for testing and running ephemeral gateways.
+    InMemory,
+}
+
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default)]
 pub struct Debug {
@@ -421,11 +449,52 @@ pub struct Debug {
     /// Number of messages from offline client that can be pulled at once from the storage.
     pub message_retrieval_limit: i64,
 
+    /// Maximum number of messages that can be stored for a single offline client.
+    /// Any further messages are going to be dropped. Setting it to 0 disables the limit.
+    pub maximum_client_inbox_messages: u64,
+
+    /// Maximum total size (in bytes) of messages that can be stored for a single offline client.
+    /// Any further messages are going to be dropped. Setting it to 0 disables the limit.
+    pub maximum_client_inbox_bytes: u64,
+
+    /// Maximum amount of time messages for offline clients are kept in the storage before being removed.
+    /// Setting it to 0 disables the removal.
+    #[serde(with = "humantime_serde")]
+    pub stored_message_ttl: Duration,
+
+    /// Delay between subsequent attempts at removing stale messages from the storage.
+    #[serde(with = "humantime_serde")]
+    pub stored_messages_pruning_interval: Duration,
+
     /// Specifies whether the mixnode should be using the legacy framing for the sphinx packets.
     // it's set to true by default. The reason for that decision is to preserve compatibility with the
     // existing nodes whilst everyone else is upgrading and getting the code for handling the new field.
     // It shall be disabled in the subsequent releases.
     pub use_legacy_framed_packet_version: bool,
+
+    /// Specifies the backend used for storing client data, such as shared keys, bandwidth
+    /// and messages for offline clients.
+    pub storage_backend: StorageBackend,
+
+    /// Specifies whether the gateway should attempt to establish noise encrypted and authenticated
+    /// links when forwarding packets to mixnodes, using their bonded sphinx keys.
+    /// Nodes that fail to complete the handshake, e.g. because they don't support noise yet,
+    /// are reached using the plaintext links instead, unless `require_noise_link_encryption` is set.
+    /// Note that the incoming noise links are always accepted.
+    pub use_noise_link_encryption: bool,
+
+    /// Specifies whether the gateway should refuse any plaintext mixnet links, i.e. incoming connections
+    /// not initiating the noise handshake, incoming handshakes from unknown nodes
+    /// and outgoing connections to nodes whose keys are not known.
+    pub require_noise_link_encryption: bool,
+
+    /// Timeout for completing the noise handshake with another node.
+    #[serde(with = "humantime_serde")]
+    pub noise_handshake_timeout: Duration,
+
+    /// Specifies how often the noise keys of other nodes should be refreshed from the nym-api.
+    #[serde(with = "humantime_serde")]
+    pub noise_keys_refresh_interval: Duration,
 }
 
 impl Default for Debug {
@@ -438,7 +507,16 @@ impl Default for Debug {
             maximum_connection_buffer_size: DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE,
             stored_messages_filename_length: DEFAULT_STORED_MESSAGE_FILENAME_LENGTH,
             message_retrieval_limit: DEFAULT_MESSAGE_RETRIEVAL_LIMIT,
+            maximum_client_inbox_messages: DEFAULT_MAXIMUM_CLIENT_INBOX_MESSAGES,
+            maximum_client_inbox_bytes: DEFAULT_MAXIMUM_CLIENT_INBOX_BYTES,
+            stored_message_ttl: DEFAULT_STORED_MESSAGE_TTL,
+            stored_messages_pruning_interval: DEFAULT_STORED_MESSAGES_PRUNING_INTERVAL,
             use_legacy_framed_packet_version: false,
+            storage_backend: Default::default(),
+            use_noise_link_encryption: false,
+            require_noise_link_encryption: false,
+            noise_handshake_timeout: DEFAULT_NOISE_HANDSHAKE_TIMEOUT,
+            noise_keys_refresh_interval: DEFAULT_NOISE_KEYS_REFRESH_INTERVAL,
         }
     }
 }
@@ -14,7 +14,12 @@ use std::time::Duration;
 use url::Url;
 
 use super::persistence::paths::KeysPaths;
-use super::{Config, Debug, Gateway, NetworkRequester};
+use super::{
+    Config, Debug, Gateway, NetworkRequester, DEFAULT_MAXIMUM_CLIENT_INBOX_BYTES,
+    DEFAULT_MAXIMUM_CLIENT_INBOX_MESSAGES, DEFAULT_NOISE_HANDSHAKE_TIMEOUT,
+    DEFAULT_NOISE_KEYS_REFRESH_INTERVAL, DEFAULT_STORED_MESSAGES_PRUNING_INTERVAL,
+    DEFAULT_STORED_MESSAGE_TTL,
+};
 
 const DEFAULT_GATEWAYS_DIR: &str = "gateways";
 
@@ -164,6 +169,17 @@ impl From<ConfigV1_1_31> for Config {
                 stored_messages_filename_length: value.debug.stored_messages_filename_length,
                 message_retrieval_limit: value.debug.message_retrieval_limit,
                 use_legacy_framed_packet_version: value.debug.use_legacy_framed_packet_version,
+                // \/ ADDED
+                storage_backend: Default::default(),
+                maximum_client_inbox_messages: DEFAULT_MAXIMUM_CLIENT_INBOX_MESSAGES,
+                maximum_client_inbox_bytes: DEFAULT_MAXIMUM_CLIENT_INBOX_BYTES,
+                stored_message_ttl: DEFAULT_STORED_MESSAGE_TTL,
+                stored_messages_pruning_interval: DEFAULT_STORED_MESSAGES_PRUNING_INTERVAL,
+                use_noise_link_encryption: false,
+                require_noise_link_encryption: false,
+                noise_handshake_timeout: DEFAULT_NOISE_HANDSHAKE_TIMEOUT,
+                noise_keys_refresh_interval: DEFAULT_NOISE_KEYS_REFRESH_INTERVAL,
+                // /\ ADDED
             },
         }
     }
@@ -101,6 +101,9 @@ pub(crate) enum GatewayError {
     #[error("Path to ip packet router configuration file hasn't been specified. Perhaps try to run `setup-ip-packet-router`?")]
     UnspecifiedIpPacketRouterConfig,
 
+    #[error("the interval between the attempts at pruning stale messages must be non-zero")]
+    ZeroStoredMessagesPruningInterval,
+
     #[error("there was an issue with the local network requester: {source}")]
     NetworkRequesterFailure {
         #[from]
@@ -13,13 +13,46 @@ use nym_node::error::NymNodeError;
 use nym_node::http::api::api_requests;
 use nym_node::http::api::api_requests::v1::network_requester::exit_policy::models::UsedExitPolicy;
 use nym_node::http::api::api_requests::SignedHostInformation;
-use nym_node::http::router::WireguardAppState;
+use nym_node::http::router::{ExitPolicyProvider, SharedExitPolicyProvider, WireguardAppState};
 use nym_node::wireguard::types::GatewayClientRegistry;
 use nym_sphinx::addressing::clients::Recipient;
 use nym_task::TaskClient;
+use std::fmt::{self, Debug, Formatter};
 use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
 
+/// Exposes the exit policy currently used by the request filter of the network requester,
+/// so that any reloads of the policy are reflected in the http api.
+struct RequestFilterExitPolicy {
+    request_filter: RequestFilter,
+}
+
+impl Debug for RequestFilterExitPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestFilterExitPolicy")
+            .finish_non_exhaustive()
+    }
+}
+
+impl ExitPolicyProvider for RequestFilterExitPolicy {
+    fn used_exit_policy(&self) -> UsedExitPolicy {
+        let Some(exit_policy) = self.request_filter.current_exit_policy_filter() else {
+            // this node still relies on the legacy allow lists
+            return UsedExitPolicy::default();
+        };
+
+        UsedExitPolicy {
+            enabled: true,
+            upstream_source: exit_policy
+                .upstream()
+                .map(|u| u.to_string())
+                .unwrap_or_default(),
+            last_updated: exit_policy.last_updated(),
+            policy: Some(exit_policy.policy().clone()),
+        }
+    }
+}
+
 fn load_gateway_details(
     config: &Config,
 ) -> Result<api_requests::v1::gateway::models::Gateway, GatewayError> {
@@ -145,7 +178,7 @@ fn load_ip_packet_router_details(
 pub(crate) struct HttpApiBuilder<'a> {
     gateway_config: &'a Config,
     network_requester_config: Option<&'a nym_network_requester::Config>,
-    exit_policy: Option<UsedExitPolicy>,
+    exit_policy: Option<SharedExitPolicyProvider>,
     ip_packet_router_config: Option<&'a nym_ip_packet_router::Config>,
 
     identity_keypair: &'a identity::KeyPair,
@@ -191,40 +224,7 @@ impl<'a> HttpApiBuilder<'a> {
             return self;
         };
 
-        // we can cheat here a bit since we're not refreshing the exit policy
-        // thus:
-        // - we can ignore the Arc pointer and clone the inner value
-        // - we can set the last refresh time to the current time
-        //
-        // once we start refreshing it, we'll have to change it, but at that point
-        // the allow list will be probably be completely removed and thus the pointer management
-        // will be much easier
-        let Some(exit_policy) = request_filter.current_exit_policy_filter() else {
-            warn!("this node does not use an exit policy. no changes will be made");
-            return self;
-        };
-
-        // if there's no upstream (i.e. open proxy), we couldn't have possibly updated it : )
-        let last_updated = if exit_policy.upstream().is_some() {
-            #[allow(clippy::expect_used)]
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .expect("system clock is set to before the unix epoch")
-                .as_secs()
-        } else {
-            0
-        };
-
-        self.exit_policy = Some(UsedExitPolicy {
-            enabled: true,
-            upstream_source: exit_policy
-                .upstream()
-                .map(|u| u.to_string())
-                .unwrap_or_default(),
-            last_updated,
-            policy: Some(exit_policy.policy().clone()),
-        });
-
+        self.exit_policy = Some(Arc::new(RequestFilterExitPolicy { request_filter }));
         self
     }
 
@@ -271,7 +271,7 @@ impl<'a> HttpApiBuilder<'a> {
             )?);
 
             if let Some(exit_policy) = self.exit_policy {
-                config = config.with_used_exit_policy(exit_policy)
+                config = config.with_exit_policy_provider(exit_policy)
             }
         }
 
@@ -22,12 +22,14 @@ use futures::{
 use log::*;
 use nym_credentials::coconut::bandwidth::{bandwidth_credential_params, CredentialType};
 use nym_credentials_interface::{Base58, CoconutError};
+use nym_gateway_requests::capabilities::GatewayCapability;
 use nym_gateway_requests::models::CredentialSpendingRequest;
 use nym_gateway_requests::{
     iv::{IVConversionError, IV},
     types::{BinaryRequest, ServerResponse},
     ClientControlRequest, GatewayRequestsError,
 };
+use nym_network_defaults::REMAINING_BANDWIDTH_THRESHOLD;
 use nym_sphinx::forwarding::packet::MixPacket;
 use nym_task::TaskClient;
 use nym_validator_client::coconut::CoconutApiError;
@@ -35,8 +37,12 @@ use rand::{CryptoRng, Rng};
 use std::{convert::TryFrom, process, time::Duration};
 use thiserror::Error;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::{interval_at, Instant};
 use tokio_tungstenite::tungstenite::{protocol::Message, Error as WsError};
 
+/// How often the client is told about its remaining bandwidth, if it supports such reports.
+const BANDWIDTH_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Error)]
 pub(crate) enum RequestHandlingError {
     #[error("Internal gateway storage error")]
@@ -115,6 +121,20 @@ impl IntoWSMessage for Result<ServerResponse, RequestHandlingError> {
     }
 }
 
+/// Checks whether the client's bandwidth has just dropped below the threshold,
+/// so that the client would not get warned about it again after every single packet.
+fn crossed_bandwidth_threshold(available_bandwidth: i64, remaining_bandwidth: i64) -> bool {
+    available_bandwidth >= REMAINING_BANDWIDTH_THRESHOLD
+        && remaining_bandwidth < REMAINING_BANDWIDTH_THRESHOLD
+}
+
+fn bandwidth_report(available_total: i64) -> ServerResponse {
+    ServerResponse::BandwidthReport {
+        available_total,
+        running_low: available_total < REMAINING_BANDWIDTH_THRESHOLD,
+    }
+}
+
 pub(crate) struct AuthenticatedHandler<R, S, St> {
     inner: FreshHandler<R, S, St>,
     client: ClientDetails,
@@ -124,6 +144,9 @@ pub(crate) struct AuthenticatedHandler<R, S, St> {
     // senders that are used to return the result of the ping to the handler requesting the ping.
     is_active_request_receiver: IsActiveRequestReceiver,
     is_active_ping_pending_reply: Option<(u64, IsActiveResultSender)>,
+    // Set whenever the client's bandwidth drops below the threshold, so that it could get warned
+    // about it straight away rather than at the next periodic report.
+    bandwidth_report_due: bool,
 }
 
 // explicitly remove handle from the global store upon being dropped
@@ -162,9 +185,17 @@ where
             mix_receiver,
             is_active_request_receiver,
             is_active_ping_pending_reply: None,
+            bandwidth_report_due: false,
         }
     }
 
+    /// Checks whether the client has agreed to receive unsolicited bandwidth reports.
+    fn bandwidth_reports_enabled(&self) -> bool {
+        self.inner
+            .negotiated_capabilities
+            .supports(GatewayCapability::BandwidthReports)
+    }
+
     /// Explicitly removes handle from the global store.
     fn disconnect(self) {
         self.inner
@@ -384,10 +415,28 @@ where
     ///
     /// * `mix_packet`: packet received from the client that should get forwarded into the network.
     async fn handle_forward_sphinx(
-        &self,
+        &mut self,
         mix_packet: MixPacket,
     ) -> Result<ServerResponse, RequestHandlingError> {
-        let consumed_bandwidth = mix_packet.packet().len() as i64;
+        self.handle_forward_sphinx_batch(vec![mix_packet]).await
+    }
+
+    /// Tries to handle request to forward multiple sphinx packets into the network. The request can only
+    /// succeed if the client has enough available bandwidth for all of them.
+    ///
+    /// Upon forwarding, client's bandwidth is decreased by the total size of the forwarded packets.
+    ///
+    /// # Arguments
+    ///
+    /// * `mix_packets`: packets received from the client that should get forwarded into the network.
+    async fn handle_forward_sphinx_batch(
+        &mut self,
+        mix_packets: Vec<MixPacket>,
+    ) -> Result<ServerResponse, RequestHandlingError> {
+        let consumed_bandwidth: i64 = mix_packets
+            .iter()
+            .map(|mix_packet| mix_packet.packet().len() as i64)
+            .sum();
 
         let available_bandwidth = self.get_available_bandwidth().await?;
 
@@ -398,10 +447,19 @@ where
         }
 
         self.consume_bandwidth(consumed_bandwidth).await?;
-        self.forward_packet(mix_packet);
+        for mix_packet in mix_packets {
+            self.forward_packet(mix_packet);
+        }
+
+        let remaining_bandwidth = available_bandwidth - consumed_bandwidth;
+        if self.bandwidth_reports_enabled()
+            && crossed_bandwidth_threshold(available_bandwidth, remaining_bandwidth)
+        {
+            self.bandwidth_report_due = true;
+        }
 
         Ok(ServerResponse::Send {
-            remaining_bandwidth: available_bandwidth - consumed_bandwidth,
+            remaining_bandwidth,
         })
     }
 
@@ -410,7 +468,7 @@ where
     /// # Arguments
     ///
     /// * `bin_msg`: raw message to handle.
-    async fn handle_binary(&self, bin_msg: Vec<u8>) -> Message {
+    async fn handle_binary(&mut self, bin_msg: Vec<u8>) -> Message {
         trace!("binary request");
         // this function decrypts the request and checks the MAC
         match BinaryRequest::try_from_encrypted_tagged_bytes(bin_msg, &self.client.shared_keys) {
@@ -419,11 +477,14 @@ where
                 RequestHandlingError::InvalidBinaryRequest(e).into_error_message()
             }
             Ok(request) => match request {
-                // currently only a single type exists
                 BinaryRequest::ForwardSphinx(mix_packet) => self
                     .handle_forward_sphinx(mix_packet)
                     .await
                     .into_ws_message(),
+                BinaryRequest::ForwardSphinxBatch(mix_packets) => self
+                    .handle_forward_sphinx_batch(mix_packets)
+                    .await
+                    .into_ws_message(),
             },
         }
     }
@@ -509,6 +570,25 @@ where
         }
     }
 
+    /// Sends the authoritative amount of bandwidth available to the connected client.
+    async fn send_bandwidth_report(&mut self) -> Result<(), WsError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        self.bandwidth_report_due = false;
+
+        let available_total = match self.get_available_bandwidth().await {
+            Ok(available_total) => available_total,
+            Err(err) => {
+                warn!("failed to retrieve the available bandwidth of the client: {err}");
+                return Ok(());
+            }
+        };
+        self.inner
+            .send_websocket_message(bandwidth_report(available_total).into())
+            .await
+    }
+
     /// Send a ping to the connected client and return a tag identifying the ping.
     async fn send_ping(&mut self) -> Result<u64, WsError>
     where
@@ -572,6 +652,12 @@ where
         // Ping timeout future used to check if the client responded to our ping request
         let mut ping_timeout: OptionFuture<_> = None.into();
 
+        let reports_enabled = self.bandwidth_reports_enabled();
+        let mut bandwidth_reports = interval_at(
+            Instant::now() + BANDWIDTH_REPORT_INTERVAL,
+            BANDWIDTH_REPORT_INTERVAL,
+        );
+
         while !shutdown.is_shutdown() {
             tokio::select! {
                 _ = shutdown.recv() => {
@@ -617,6 +703,23 @@ where
                             break;
                         }
                     }
+
+                    if self.bandwidth_report_due {
+                        if let Err(err) = self.send_bandwidth_report().await {
+                            warn!(
+                                "Failed to send bandwidth report over websocket: {err}. Assuming the connection is dead.",
+                            );
+                            break;
+                        }
+                    }
+                },
+                _ = bandwidth_reports.tick(), if reports_enabled => {
+                    if let Err(err) = self.send_bandwidth_report().await {
+                        warn!(
+                            "Failed to send bandwidth report over websocket: {err}. Assuming the connection is dead.",
+                        );
+                        break;
+                    }
                 },
                 mix_messages = self.mix_receiver.next() => {
                     let mix_messages = match mix_messages {
@@ -638,3 +741,45 @@ where
         trace!("The stream was closed!");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bandwidth_report_is_only_due_when_crossing_the_threshold() {
+        let threshold = REMAINING_BANDWIDTH_THRESHOLD;
+
+        assert!(crossed_bandwidth_threshold(threshold, threshold - 1));
+        assert!(crossed_bandwidth_threshold(
+            threshold + 100,
+            threshold - 100
+        ));
+
+        // still above the threshold
+        assert!(!crossed_bandwidth_threshold(threshold + 100, threshold));
+        // already below the threshold before sending the packets - the client has been warned before
+        assert!(!crossed_bandwidth_threshold(threshold - 1, threshold - 100));
+    }
+
+    #[test]
+    fn bandwidth_reports_indicate_whether_the_bandwidth_is_running_low() {
+        let threshold = REMAINING_BANDWIDTH_THRESHOLD;
+
+        match bandwidth_report(threshold - 1) {
+            ServerResponse::BandwidthReport {
+                available_total,
+                running_low,
+            } => {
+                assert_eq!(available_total, threshold - 1);
+                assert!(running_low);
+            }
+            _ => panic!("unexpected response"),
+        }
+
+        match bandwidth_report(threshold) {
+            ServerResponse::BandwidthReport { running_low, .. } => assert!(!running_low),
+            _ => panic!("unexpected response"),
+        }
+    }
+}
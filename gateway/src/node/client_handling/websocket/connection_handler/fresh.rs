@@ -10,6 +10,7 @@ use nym_crypto::asymmetric::identity;
 use nym_gateway_requests::authentication::encrypted_address::{
     EncryptedAddressBytes, EncryptedAddressConversionError,
 };
+use nym_gateway_requests::capabilities::GatewayCapabilities;
 use nym_gateway_requests::registration::handshake::shared_key::SharedKeyConversionError;
 use nym_gateway_requests::{
     iv::{IVConversionError, IV},
@@ -98,6 +99,9 @@ pub(crate) struct FreshHandler<R, S, St> {
 
     // currently unused (but populated)
     pub(crate) negotiated_protocol: Option<u8>,
+
+    /// Protocol extensions supported by both the connected client and this gateway.
+    pub(crate) negotiated_capabilities: GatewayCapabilities,
 }
 
 impl<R, S, St> FreshHandler<R, S, St>
@@ -130,6 +134,7 @@ where
             storage,
             coconut_verifier,
             negotiated_protocol: None,
+            negotiated_capabilities: GatewayCapabilities::default(),
         }
     }
 
@@ -390,6 +395,18 @@ where
         }
     }
 
+    fn negotiate_client_capabilities(
+        &mut self,
+        client_capabilities: GatewayCapabilities,
+    ) -> GatewayCapabilities {
+        let negotiated = GatewayCapabilities::supported().negotiate(&client_capabilities);
+        debug!("client capabilities: {client_capabilities}, negotiated: {negotiated}");
+
+        // populate the negotiated capabilities for future uses
+        self.negotiated_capabilities = negotiated.clone();
+        negotiated
+    }
+
     /// Using the received challenge data, i.e. client's address as well the ciphertext of it plus
     /// a fresh IV, attempts to authenticate the client by checking whether the ciphertext matches
     /// the expected value if encrypted with the shared key.
@@ -485,12 +502,14 @@ where
     ///
     /// # Arguments
     ///
+    /// * `client_capabilities`: protocol extensions supported by the client.
     /// * `client_address`: address of the client wishing to authenticate.
     /// * `encrypted_address`: ciphertext of the address of the client wishing to authenticate.
     /// * `iv`: fresh IV received with the request.
     async fn handle_authenticate(
         &mut self,
         client_protocol_version: Option<u8>,
+        client_capabilities: GatewayCapabilities,
         address: String,
         enc_address: String,
         iv: String,
@@ -525,11 +544,13 @@ where
             .unwrap_or(0);
         let client_details =
             shared_keys.map(|shared_keys| ClientDetails::new(address, shared_keys));
+        let capabilities = self.negotiate_client_capabilities(client_capabilities);
 
         Ok(InitialAuthResult::new(
             client_details,
             ServerResponse::Authenticate {
                 protocol_version: Some(negotiated_protocol),
+                capabilities,
                 status,
                 bandwidth_remaining,
             },
@@ -581,10 +602,12 @@ where
     ///
     /// # Arguments
     ///
+    /// * `client_capabilities`: protocol extensions supported by the client.
     /// * `init_data`: init payload of the registration handshake.
     async fn handle_register(
         &mut self,
         client_protocol_version: Option<u8>,
+        client_capabilities: GatewayCapabilities,
         init_data: Vec<u8>,
     ) -> Result<InitialAuthResult, InitialAuthenticationError>
     where
@@ -605,11 +628,13 @@ where
         let client_details = ClientDetails::new(remote_address, shared_keys);
 
         let status = self.register_client(&client_details).await?;
+        let capabilities = self.negotiate_client_capabilities(client_capabilities);
 
         Ok(InitialAuthResult::new(
             Some(client_details),
             ServerResponse::Register {
                 protocol_version: Some(negotiated_protocol),
+                capabilities,
                 status,
             },
         ))
@@ -632,17 +657,28 @@ where
             match request {
                 ClientControlRequest::Authenticate {
                     protocol_version,
+                    capabilities,
                     address,
                     enc_address,
                     iv,
                 } => {
-                    self.handle_authenticate(protocol_version, address, enc_address, iv)
-                        .await
+                    self.handle_authenticate(
+                        protocol_version,
+                        capabilities,
+                        address,
+                        enc_address,
+                        iv,
+                    )
+                    .await
                 }
                 ClientControlRequest::RegisterHandshakeInitRequest {
                     protocol_version,
+                    capabilities,
                     data,
-                } => self.handle_register(protocol_version, data).await,
+                } => {
+                    self.handle_register(protocol_version, capabilities, data)
+                        .await
+                }
                 // won't accept anything else (like bandwidth) without prior authentication
                 _ => Err(InitialAuthenticationError::InvalidRequest),
             }
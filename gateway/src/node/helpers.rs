@@ -1,9 +1,10 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::config::Config;
+use crate::config::{Config, StorageBackend};
 use crate::error::GatewayError;
-use crate::node::storage::PersistentStorage;
+use crate::node::storage::{GatewayStorage, InMemStorage, InboxQuota, PersistentStorage};
+use log::warn;
 use nym_crypto::asymmetric::{encryption, identity};
 use nym_pemstore::traits::{PemStorableKey, PemStorableKeyPair};
 use nym_pemstore::KeyPairPath;
@@ -150,11 +151,25 @@ pub(crate) fn load_ip_packet_router_config<P: AsRef<Path>>(
 
 pub(crate) async fn initialise_main_storage(
     config: &Config,
-) -> Result<PersistentStorage, GatewayError> {
-    let path = &config.storage_paths.clients_storage;
+) -> Result<GatewayStorage, GatewayError> {
     let retrieval_limit = config.debug.message_retrieval_limit;
+    let inbox_quota = InboxQuota {
+        max_messages: config.debug.maximum_client_inbox_messages,
+        max_bytes: config.debug.maximum_client_inbox_bytes,
+    };
 
-    Ok(PersistentStorage::init(path, retrieval_limit).await?)
+    match config.debug.storage_backend {
+        StorageBackend::Sqlite => {
+            let path = &config.storage_paths.clients_storage;
+            Ok(PersistentStorage::init(path, retrieval_limit, inbox_quota)
+                .await?
+                .into())
+        }
+        StorageBackend::InMemory => {
+            warn!("using in-memory storage backend - all client data will be lost on shutdown");
+            Ok(InMemStorage::new(retrieval_limit, inbox_quota).into())
+        }
+    }
 }
 
 pub(crate) fn load_keypair<T: PemStorableKeyPair>(
@@ -4,6 +4,7 @@
 use crate::node::client_handling::active_clients::ActiveClientsStore;
 use crate::node::client_handling::websocket::message_receiver::MixMessageSender;
 use crate::node::mixnet_handling::receiver::packet_processing::PacketProcessor;
+use crate::node::statistics::inbox::InboxStorageStatistics;
 use crate::node::storage::error::StorageError;
 use crate::node::storage::Storage;
 use futures::channel::mpsc::SendError;
@@ -11,6 +12,7 @@ use futures::StreamExt;
 use log::*;
 use nym_mixnet_client::forwarder::MixForwardingSender;
 use nym_mixnode_common::packet_processor::processor::ProcessedFinalHop;
+use nym_noise::{upgrade_noise_responder, NoiseConfig};
 use nym_sphinx::forwarding::packet::MixPacket;
 use nym_sphinx::framing::codec::NymCodec;
 use nym_sphinx::framing::packet::FramedNymPacket;
@@ -40,6 +42,8 @@ pub(crate) struct ConnectionHandler<St: Storage> {
     active_clients_store: ActiveClientsStore,
     storage: St,
     ack_sender: MixForwardingSender,
+    inbox_statistics: InboxStorageStatistics,
+    noise_config: NoiseConfig,
 }
 
 impl<St: Storage + Clone> Clone for ConnectionHandler<St> {
@@ -58,6 +62,8 @@ impl<St: Storage + Clone> Clone for ConnectionHandler<St> {
             active_clients_store: self.active_clients_store.clone(),
             storage: self.storage.clone(),
             ack_sender: self.ack_sender.clone(),
+            inbox_statistics: self.inbox_statistics.clone(),
+            noise_config: self.noise_config.clone(),
         }
     }
 }
@@ -68,6 +74,8 @@ impl<St: Storage> ConnectionHandler<St> {
         storage: St,
         ack_sender: MixForwardingSender,
         active_clients_store: ActiveClientsStore,
+        inbox_statistics: InboxStorageStatistics,
+        noise_config: NoiseConfig,
     ) -> Self {
         ConnectionHandler {
             packet_processor,
@@ -75,6 +83,8 @@ impl<St: Storage> ConnectionHandler<St> {
             storage,
             active_clients_store,
             ack_sender,
+            inbox_statistics,
+            noise_config,
         }
     }
 
@@ -163,6 +173,12 @@ impl<St: Storage> ConnectionHandler<St> {
                 .store_processed_packet_payload(client_address, unsent_plaintext)
                 .await
             {
+                Err(err @ StorageError::InboxQuotaExceeded { .. }) => {
+                    // this is expected for clients that have been offline for a long time,
+                    // so don't spam the logs
+                    debug!("Dropping the received message - {err}");
+                    self.inbox_statistics.record_rejected()
+                }
                 Err(err) => error!("Failed to store client data - {err}"),
                 Ok(_) => trace!("Stored packet for {client_address}"),
             },
@@ -205,6 +221,14 @@ impl<St: Storage> ConnectionHandler<St> {
     ) {
         debug!("Starting connection handler for {:?}", remote);
         shutdown.mark_as_success();
+
+        let conn = match upgrade_noise_responder(conn, &self.noise_config).await {
+            Ok(conn) => conn,
+            Err(err) => {
+                warn!("{remote} - failed to establish the mixnet link: {err}");
+                return;
+            }
+        };
         let mut framed_conn = Framed::new(conn, NymCodec);
         while !shutdown.is_shutdown() {
             tokio::select! {
@@ -239,7 +263,7 @@ impl<St: Storage> ConnectionHandler<St> {
             }
         }
 
-        match framed_conn.into_inner().peer_addr() {
+        match framed_conn.into_inner().get_ref().peer_addr() {
             Ok(peer_addr) => {
                 debug!("closing connection from {peer_addr}")
             }
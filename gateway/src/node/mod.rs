@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use self::helpers::load_ip_packet_router_config;
-use self::storage::PersistentStorage;
+use self::storage::GatewayStorage;
 use crate::commands::helpers::{
     override_ip_packet_router_config, override_network_requester_config,
     OverrideIpPacketRouterConfig, OverrideNetworkRequesterConfig,
@@ -17,16 +17,19 @@ use crate::node::client_handling::websocket::connection_handler::coconut::Coconu
 use crate::node::helpers::{initialise_main_storage, load_network_requester_config};
 use crate::node::mixnet_handling::receiver::connection_handler::ConnectionHandler;
 use crate::node::statistics::collector::GatewayStatisticsCollector;
-use crate::node::storage::Storage;
+use crate::node::statistics::inbox::InboxStorageStatistics;
+use crate::node::storage::{InboxPruner, Storage};
 use anyhow::bail;
 use dashmap::DashMap;
 use futures::channel::{mpsc, oneshot};
 use log::*;
 use nym_crypto::asymmetric::{encryption, identity};
 use nym_mixnet_client::forwarder::{MixForwardingSender, PacketForwarder};
+use nym_mixnode_common::noise_keys::NoiseKeysRefresher;
 use nym_network_defaults::NymNetworkDetails;
 use nym_network_requester::{LocalGateway, NRServiceProviderBuilder, RequestFilter};
 use nym_node::wireguard::types::GatewayClientRegistry;
+use nym_noise::{NoiseConfig, NoisePeerKeys};
 use nym_statistics_common::collector::StatisticsSender;
 use nym_task::{TaskClient, TaskManager};
 use nym_validator_client::{nyxd, DirectSigningHttpRpcNyxdClient};
@@ -59,6 +62,8 @@ pub(crate) async fn create_gateway(
     ip_config_override: Option<OverrideIpPacketRouterConfig>,
     custom_mixnet: Option<PathBuf>,
 ) -> Result<Gateway, GatewayError> {
+    config.validate()?;
+
     // don't attempt to read config if NR is disabled
     let network_requester_config = if config.network_requester.enabled {
         if let Some(path) = &config.storage_paths.network_requester_config {
@@ -114,7 +119,7 @@ pub struct LocalIpPacketRouterOpts {
     custom_mixnet_path: Option<PathBuf>,
 }
 
-pub(crate) struct Gateway<St = PersistentStorage> {
+pub(crate) struct Gateway<St = GatewayStorage> {
     config: Config,
 
     network_requester_opts: Option<LocalNetworkRequesterOpts>,
@@ -174,6 +179,8 @@ impl<St> Gateway<St> {
         &self,
         ack_sender: MixForwardingSender,
         active_clients_store: ActiveClientsStore,
+        inbox_statistics: InboxStorageStatistics,
+        noise_config: NoiseConfig,
         shutdown: TaskClient,
     ) where
         St: Storage + Clone + 'static,
@@ -188,6 +195,8 @@ impl<St> Gateway<St> {
             self.storage.clone(),
             ack_sender,
             active_clients_store,
+            inbox_statistics,
+            noise_config,
         );
 
         let listening_address = SocketAddr::new(
@@ -241,15 +250,55 @@ impl<St> Gateway<St> {
         );
     }
 
-    fn start_packet_forwarder(&self, shutdown: TaskClient) -> MixForwardingSender {
+    fn uses_noise_for_outgoing_links(&self) -> bool {
+        self.config.debug.use_noise_link_encryption
+            || self.config.debug.require_noise_link_encryption
+    }
+
+    fn build_noise_config(&self) -> NoiseConfig {
+        NoiseConfig::new(Arc::clone(&self.sphinx_keypair), NoisePeerKeys::new())
+            .with_handshake_timeout(self.config.debug.noise_handshake_timeout)
+            .with_required_encryption(self.config.debug.require_noise_link_encryption)
+    }
+
+    fn start_noise_keys_refresher(
+        &self,
+        peer_keys: NoisePeerKeys,
+        shutdown: TaskClient,
+    ) -> Result<(), GatewayError> {
+        info!("Starting noise keys refresher...");
+
+        let mut refresher = NoiseKeysRefresher::new(
+            peer_keys,
+            self.config.debug.noise_keys_refresh_interval,
+            self.config.get_nym_api_endpoints(),
+            shutdown,
+        )
+        .map_err(|_| GatewayError::NoNymApisAvailable)?;
+        tokio::spawn(async move { refresher.run().await });
+        Ok(())
+    }
+
+    fn start_packet_forwarder(
+        &self,
+        noise_config: &NoiseConfig,
+        shutdown: TaskClient,
+    ) -> MixForwardingSender {
         info!("Starting mix packet forwarder...");
 
+        let noise_config = if self.uses_noise_for_outgoing_links() {
+            Some(noise_config.clone())
+        } else {
+            None
+        };
+
         let (mut packet_forwarder, packet_sender) = PacketForwarder::new(
             self.config.debug.packet_forwarding_initial_backoff,
             self.config.debug.packet_forwarding_maximum_backoff,
             self.config.debug.initial_connection_timeout,
             self.config.debug.maximum_connection_buffer_size,
             self.config.debug.use_legacy_framed_packet_version,
+            noise_config,
             shutdown,
         );
 
@@ -450,21 +499,41 @@ impl<St> Gateway<St> {
             CoconutVerifier::new(nyxd_client).await
         }?;
 
-        let mix_forwarding_channel =
-            self.start_packet_forwarder(shutdown.subscribe().named("PacketForwarder"));
+        let noise_config = self.build_noise_config();
+        if self.uses_noise_for_outgoing_links() {
+            self.start_noise_keys_refresher(
+                noise_config.peer_keys().clone(),
+                shutdown.subscribe().named("NoiseKeysRefresher"),
+            )?;
+        }
+
+        let mix_forwarding_channel = self
+            .start_packet_forwarder(&noise_config, shutdown.subscribe().named("PacketForwarder"));
 
         let active_clients_store = ActiveClientsStore::new();
+        let inbox_statistics = InboxStorageStatistics::default();
         self.start_mix_socket_listener(
             mix_forwarding_channel.clone(),
             active_clients_store.clone(),
+            inbox_statistics.clone(),
+            noise_config,
             shutdown.subscribe().named("mixnet_handling::Listener"),
         );
 
+        InboxPruner::new(
+            self.storage.clone(),
+            self.config.debug.stored_message_ttl,
+            self.config.debug.stored_messages_pruning_interval,
+            inbox_statistics.clone(),
+        )
+        .start_with_shutdown(shutdown.subscribe().named("InboxPruner"));
+
         if self.config.gateway.enabled_statistics {
             let statistics_service_url = self.config.get_statistics_service_url();
             let stats_collector = GatewayStatisticsCollector::new(
                 self.identity_keypair.public_key().to_base58_string(),
                 active_clients_store.clone(),
+                inbox_statistics,
                 statistics_service_url,
             );
             let mut stats_sender = StatisticsSender::new(stats_collector);
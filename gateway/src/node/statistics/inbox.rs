@@ -0,0 +1,58 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node::storage::InboxUsage;
+use nym_statistics_common::StatsInboxStorageData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Shared view of the storage pressure caused by messages stored for offline clients.
+#[derive(Clone, Default)]
+pub(crate) struct InboxStorageStatistics {
+    inner: Arc<InboxStorageStatisticsInner>,
+}
+
+#[derive(Default)]
+struct InboxStorageStatisticsInner {
+    stored_messages: AtomicU64,
+    stored_bytes: AtomicU64,
+
+    // the below are reset after every statistics report
+    expired_messages: AtomicU64,
+    rejected_messages: AtomicU64,
+}
+
+impl InboxStorageStatistics {
+    pub(crate) fn update_usage(&self, usage: InboxUsage) {
+        self.inner
+            .stored_messages
+            .store(usage.messages, Ordering::Relaxed);
+        self.inner
+            .stored_bytes
+            .store(usage.bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_expired(&self, count: u64) {
+        self.inner
+            .expired_messages
+            .fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_rejected(&self) {
+        self.inner.rejected_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> StatsInboxStorageData {
+        StatsInboxStorageData {
+            stored_messages: self.inner.stored_messages.load(Ordering::Relaxed),
+            stored_bytes: self.inner.stored_bytes.load(Ordering::Relaxed),
+            expired_messages: self.inner.expired_messages.load(Ordering::Relaxed),
+            rejected_messages: self.inner.rejected_messages.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn reset_counters(&self) {
+        self.inner.expired_messages.store(0, Ordering::Relaxed);
+        self.inner.rejected_messages.store(0, Ordering::Relaxed);
+    }
+}
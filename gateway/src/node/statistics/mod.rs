@@ -2,3 +2,4 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 pub mod collector;
+pub(crate) mod inbox;
@@ -10,4 +10,19 @@ pub(crate) enum StorageError {
 
     #[error("Failed to perform database migration: {0}")]
     MigrationError(#[from] sqlx::migrate::MigrateError),
+
+    #[error("bandwidth entry for client {client_address_bs58} already exists")]
+    DuplicateBandwidthEntry { client_address_bs58: String },
+
+    #[error(
+        "credential with blinded serial number {blinded_serial_number_bs58} has already been spent"
+    )]
+    DuplicateSpentCredential { blinded_serial_number_bs58: String },
+
+    #[error("inbox of client {client_address_bs58} is full: it already contains {messages} messages ({bytes} bytes)")]
+    InboxQuotaExceeded {
+        client_address_bs58: String,
+        messages: u64,
+        bytes: u64,
+    },
 }
@@ -0,0 +1,465 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node::storage::error::StorageError;
+use crate::node::storage::models::{PersistedSharedKeys, StoredMessage};
+use crate::node::storage::{InboxQuota, InboxUsage, Storage};
+use async_trait::async_trait;
+use nym_credentials_interface::{Base58, BlindedSerialNumber};
+use nym_gateway_requests::registration::handshake::SharedKeys;
+use nym_sphinx::DestinationAddressBytes;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::sync::RwLock;
+
+const DEFAULT_RETRIEVAL_LIMIT: i64 = 100;
+
+#[derive(Default)]
+struct InMemStorageInner {
+    /// Derived shared keys keyed by base58-encoded client address.
+    shared_keys: HashMap<String, String>,
+
+    /// Stored messages keyed by their (monotonically increasing) ids.
+    messages: BTreeMap<i64, InMemMessage>,
+
+    /// Id that is going to get assigned to the next stored message.
+    /// Just like sqlite's `AUTOINCREMENT`, ids are never reused.
+    next_message_id: i64,
+
+    /// Available bandwidth keyed by base58-encoded client address.
+    available_bandwidth: HashMap<String, i64>,
+
+    /// Spent credentials keyed by their base58-encoded blinded serial numbers.
+    spent_credentials: HashMap<String, SpentCredentialEntry>,
+}
+
+impl InMemStorageInner {
+    fn client_usage(&self, client_address_bs58: &str) -> InboxUsage {
+        self.messages
+            .values()
+            .filter(|message| message.client_address_bs58 == client_address_bs58)
+            .fold(InboxUsage::default(), |usage, message| InboxUsage {
+                messages: usage.messages + 1,
+                bytes: usage.bytes + message.content.len() as u64,
+            })
+    }
+}
+
+struct InMemMessage {
+    client_address_bs58: String,
+    content: Vec<u8>,
+    stored_at: OffsetDateTime,
+}
+
+#[allow(dead_code)]
+struct SpentCredentialEntry {
+    was_freepass: bool,
+    client_address_bs58: String,
+}
+
+/// In-memory implementation of `Storage`. The intention is primarily in testing environments
+/// and for running ephemeral gateways that do not need to preserve any data between restarts.
+// note that clone here is fine as upon cloning the same underlying data will be used
+#[derive(Clone)]
+pub(crate) struct InMemStorage {
+    inner: Arc<RwLock<InMemStorageInner>>,
+
+    /// Maximum number of messages that can be obtained from the storage per operation.
+    /// It mirrors the semantics of the `retrieval_limit` of the persistent `InboxManager`.
+    retrieval_limit: i64,
+
+    /// Limits on the amount of data stored for a single offline client.
+    inbox_quota: InboxQuota,
+}
+
+impl Default for InMemStorage {
+    fn default() -> Self {
+        InMemStorage::new(DEFAULT_RETRIEVAL_LIMIT, InboxQuota::unlimited())
+    }
+}
+
+impl InMemStorage {
+    /// Creates new instance of the `InMemStorage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_retrieval_limit`: maximum number of stored client messages that can be retrieved at once.
+    /// * `inbox_quota`: limits on the amount of data stored for a single offline client.
+    pub(crate) fn new(mut message_retrieval_limit: i64, inbox_quota: InboxQuota) -> Self {
+        // keep it consistent with the `InboxManager`
+        if message_retrieval_limit <= 0 {
+            message_retrieval_limit = DEFAULT_RETRIEVAL_LIMIT;
+        }
+
+        InMemStorage {
+            inner: Arc::new(RwLock::new(InMemStorageInner {
+                next_message_id: 1,
+                ..Default::default()
+            })),
+            retrieval_limit: message_retrieval_limit,
+            inbox_quota,
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for InMemStorage {
+    async fn insert_shared_keys(
+        &self,
+        client_address: DestinationAddressBytes,
+        shared_keys: &SharedKeys,
+    ) -> Result<(), StorageError> {
+        self.inner.write().await.shared_keys.insert(
+            client_address.as_base58_string(),
+            shared_keys.to_base58_string(),
+        );
+        Ok(())
+    }
+
+    async fn get_shared_keys(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<Option<PersistedSharedKeys>, StorageError> {
+        let client_address_bs58 = client_address.as_base58_string();
+        let guard = self.inner.read().await;
+        Ok(guard
+            .shared_keys
+            .get(&client_address_bs58)
+            .map(|keys| PersistedSharedKeys {
+                client_address_bs58,
+                derived_aes128_ctr_blake3_hmac_keys_bs58: keys.clone(),
+            }))
+    }
+
+    async fn remove_shared_keys(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<(), StorageError> {
+        self.inner
+            .write()
+            .await
+            .shared_keys
+            .remove(&client_address.as_base58_string());
+        Ok(())
+    }
+
+    async fn store_message(
+        &self,
+        client_address: DestinationAddressBytes,
+        message: Vec<u8>,
+    ) -> Result<(), StorageError> {
+        let client_address_bs58 = client_address.as_base58_string();
+        let mut guard = self.inner.write().await;
+
+        let usage = guard.client_usage(&client_address_bs58);
+        self.inbox_quota
+            .check(&client_address_bs58, usage, message.len())?;
+
+        let id = guard.next_message_id;
+        guard.next_message_id += 1;
+        guard.messages.insert(
+            id,
+            InMemMessage {
+                client_address_bs58,
+                content: message,
+                stored_at: OffsetDateTime::now_utc(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn retrieve_messages(
+        &self,
+        client_address: DestinationAddressBytes,
+        start_after: Option<i64>,
+    ) -> Result<(Vec<StoredMessage>, Option<i64>), StorageError> {
+        let client_address_bs58 = client_address.as_base58_string();
+        let lower_bound = start_after.map(|id| id + 1).unwrap_or(i64::MIN);
+
+        let guard = self.inner.read().await;
+
+        // get 1 additional message to check whether there will be more to grab next time
+        let mut res = guard
+            .messages
+            .range(lower_bound..)
+            .filter(|(_, message)| message.client_address_bs58 == client_address_bs58)
+            .take(self.retrieval_limit as usize + 1)
+            .map(|(id, message)| StoredMessage {
+                id: *id,
+                client_address_bs58: message.client_address_bs58.clone(),
+                content: message.content.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        if res.len() > self.retrieval_limit as usize {
+            res.truncate(self.retrieval_limit as usize);
+            // given retrieval_limit > 0, unwrap will not fail
+            #[allow(clippy::unwrap_used)]
+            let start_after = res.last().unwrap().id;
+            Ok((res, Some(start_after)))
+        } else {
+            Ok((res, None))
+        }
+    }
+
+    async fn remove_messages(&self, ids: Vec<i64>) -> Result<(), StorageError> {
+        let mut guard = self.inner.write().await;
+        for id in ids {
+            guard.messages.remove(&id);
+        }
+        Ok(())
+    }
+
+    async fn remove_stale_messages(&self, cutoff: OffsetDateTime) -> Result<u64, StorageError> {
+        let mut guard = self.inner.write().await;
+        let before = guard.messages.len();
+        guard
+            .messages
+            .retain(|_, message| message.stored_at >= cutoff);
+        Ok((before - guard.messages.len()) as u64)
+    }
+
+    async fn inbox_usage(&self) -> Result<InboxUsage, StorageError> {
+        let guard = self.inner.read().await;
+        Ok(InboxUsage {
+            messages: guard.messages.len() as u64,
+            bytes: guard
+                .messages
+                .values()
+                .map(|message| message.content.len() as u64)
+                .sum(),
+        })
+    }
+
+    async fn create_bandwidth_entry(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<(), StorageError> {
+        let client_address_bs58 = client_address.as_base58_string();
+        let mut guard = self.inner.write().await;
+
+        // mimic the `UNIQUE` constraint of the persistent storage
+        if guard.available_bandwidth.contains_key(&client_address_bs58) {
+            return Err(StorageError::DuplicateBandwidthEntry {
+                client_address_bs58,
+            });
+        }
+        guard.available_bandwidth.insert(client_address_bs58, 0);
+        Ok(())
+    }
+
+    async fn get_available_bandwidth(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<Option<i64>, StorageError> {
+        Ok(self
+            .inner
+            .read()
+            .await
+            .available_bandwidth
+            .get(&client_address.as_base58_string())
+            .copied())
+    }
+
+    async fn increase_bandwidth(
+        &self,
+        client_address: DestinationAddressBytes,
+        amount: i64,
+    ) -> Result<(), StorageError> {
+        // just like the sql `UPDATE`, this is a no-op if the entry doesn't exist
+        if let Some(available) = self
+            .inner
+            .write()
+            .await
+            .available_bandwidth
+            .get_mut(&client_address.as_base58_string())
+        {
+            *available += amount
+        }
+        Ok(())
+    }
+
+    async fn consume_bandwidth(
+        &self,
+        client_address: DestinationAddressBytes,
+        amount: i64,
+    ) -> Result<(), StorageError> {
+        // just like the sql `UPDATE`, this is a no-op if the entry doesn't exist
+        if let Some(available) = self
+            .inner
+            .write()
+            .await
+            .available_bandwidth
+            .get_mut(&client_address.as_base58_string())
+        {
+            *available -= amount
+        }
+        Ok(())
+    }
+
+    async fn insert_spent_credential(
+        &self,
+        blinded_serial_number: BlindedSerialNumber,
+        was_freepass: bool,
+        client_address: DestinationAddressBytes,
+    ) -> Result<(), StorageError> {
+        let blinded_serial_number_bs58 = blinded_serial_number.to_bs58();
+        let mut guard = self.inner.write().await;
+
+        // mimic the `UNIQUE` constraint of the persistent storage
+        if guard
+            .spent_credentials
+            .contains_key(&blinded_serial_number_bs58)
+        {
+            return Err(StorageError::DuplicateSpentCredential {
+                blinded_serial_number_bs58,
+            });
+        }
+
+        guard.spent_credentials.insert(
+            blinded_serial_number_bs58,
+            SpentCredentialEntry {
+                was_freepass,
+                client_address_bs58: client_address.as_base58_string(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn contains_credential(
+        &self,
+        blinded_serial_number: &BlindedSerialNumber,
+    ) -> Result<bool, StorageError> {
+        Ok(self
+            .inner
+            .read()
+            .await
+            .spent_credentials
+            .contains_key(&blinded_serial_number.to_bs58()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(byte: u8) -> DestinationAddressBytes {
+        DestinationAddressBytes::from_bytes([byte; 32])
+    }
+
+    #[tokio::test]
+    async fn message_retrieval_respects_the_limit() {
+        let storage = InMemStorage::new(3, InboxQuota::unlimited());
+        let client_a = client(1);
+        let client_b = client(2);
+
+        for i in 0..7u8 {
+            storage.store_message(client_a, vec![i]).await.unwrap();
+            storage.store_message(client_b, vec![42]).await.unwrap();
+        }
+
+        let (first, next) = storage.retrieve_messages(client_a, None).await.unwrap();
+        assert_eq!(
+            first.iter().map(|m| m.content[0]).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(next, Some(first[2].id));
+
+        let (second, next) = storage.retrieve_messages(client_a, next).await.unwrap();
+        assert_eq!(
+            second.iter().map(|m| m.content[0]).collect::<Vec<_>>(),
+            vec![3, 4, 5]
+        );
+        assert!(next.is_some());
+
+        let (third, next) = storage.retrieve_messages(client_a, next).await.unwrap();
+        assert_eq!(
+            third.iter().map(|m| m.content[0]).collect::<Vec<_>>(),
+            vec![6]
+        );
+        assert!(next.is_none());
+
+        storage
+            .remove_messages(first.iter().map(|m| m.id).collect())
+            .await
+            .unwrap();
+        let (remaining, _) = storage.retrieve_messages(client_a, None).await.unwrap();
+        assert_eq!(remaining[0].content, vec![3]);
+    }
+
+    #[tokio::test]
+    async fn bandwidth_entries_behave_like_persistent_ones() {
+        let storage = InMemStorage::default();
+        let client = client(1);
+
+        // updating non-existent entry is a no-op
+        storage.increase_bandwidth(client, 100).await.unwrap();
+        assert!(storage
+            .get_available_bandwidth(client)
+            .await
+            .unwrap()
+            .is_none());
+
+        storage.create_bandwidth_entry(client).await.unwrap();
+        assert!(storage.create_bandwidth_entry(client).await.is_err());
+
+        storage.increase_bandwidth(client, 100).await.unwrap();
+        storage.consume_bandwidth(client, 30).await.unwrap();
+        assert_eq!(
+            storage.get_available_bandwidth(client).await.unwrap(),
+            Some(70)
+        );
+    }
+
+    #[tokio::test]
+    async fn inbox_quota_is_enforced_per_client() {
+        let quota = InboxQuota {
+            max_messages: 3,
+            max_bytes: 100,
+        };
+        let storage = InMemStorage::new(10, quota);
+        let client_a = client(1);
+        let client_b = client(2);
+
+        for _ in 0..3 {
+            storage.store_message(client_a, vec![0; 10]).await.unwrap();
+        }
+        assert!(matches!(
+            storage.store_message(client_a, vec![0; 10]).await,
+            Err(StorageError::InboxQuotaExceeded { messages: 3, .. })
+        ));
+
+        // other clients are unaffected
+        storage.store_message(client_b, vec![0; 90]).await.unwrap();
+        assert!(storage.store_message(client_b, vec![0; 11]).await.is_err());
+
+        // and freeing up the space allows storing messages again
+        let (stored, _) = storage.retrieve_messages(client_a, None).await.unwrap();
+        storage.remove_messages(vec![stored[0].id]).await.unwrap();
+        storage.store_message(client_a, vec![0; 10]).await.unwrap();
+
+        assert_eq!(
+            storage.inbox_usage().await.unwrap(),
+            InboxUsage {
+                messages: 4,
+                bytes: 120
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_messages_are_removed() {
+        let storage = InMemStorage::default();
+        let client = client(1);
+
+        storage.store_message(client, vec![1]).await.unwrap();
+        let cutoff = OffsetDateTime::now_utc() + time::Duration::SECOND;
+        assert_eq!(storage.remove_stale_messages(cutoff).await.unwrap(), 1);
+
+        storage.store_message(client, vec![2]).await.unwrap();
+        let cutoff = OffsetDateTime::now_utc() - time::Duration::HOUR;
+        assert_eq!(storage.remove_stale_messages(cutoff).await.unwrap(), 0);
+        assert_eq!(storage.inbox_usage().await.unwrap().messages, 1);
+    }
+}
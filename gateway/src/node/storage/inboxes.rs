@@ -1,7 +1,55 @@
 // Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
+use crate::node::storage::error::StorageError;
 use crate::node::storage::models::StoredMessage;
+use time::OffsetDateTime;
+
+/// Limits on the amount of data that can be stored for a single offline client.
+/// A value of 0 means the particular limit is disabled.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct InboxQuota {
+    /// Maximum number of messages stored for a single client.
+    pub(crate) max_messages: u64,
+
+    /// Maximum total size of messages (in bytes) stored for a single client.
+    pub(crate) max_bytes: u64,
+}
+
+impl InboxQuota {
+    pub(crate) fn unlimited() -> Self {
+        InboxQuota::default()
+    }
+
+    /// Checks whether the new message of the specified size could be stored
+    /// in the inbox with the provided usage.
+    pub(crate) fn check(
+        &self,
+        client_address_bs58: &str,
+        usage: InboxUsage,
+        new_message_size: usize,
+    ) -> Result<(), StorageError> {
+        let messages_exceeded = self.max_messages != 0 && usage.messages >= self.max_messages;
+        let bytes_exceeded = self.max_bytes != 0
+            && usage.bytes.saturating_add(new_message_size as u64) > self.max_bytes;
+
+        if messages_exceeded || bytes_exceeded {
+            return Err(StorageError::InboxQuotaExceeded {
+                client_address_bs58: client_address_bs58.to_string(),
+                messages: usage.messages,
+                bytes: usage.bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Amount of data stored in either a particular inbox or in all of them combined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct InboxUsage {
+    pub(crate) messages: u64,
+    pub(crate) bytes: u64,
+}
 
 #[derive(Clone)]
 pub(crate) struct InboxManager {
@@ -30,25 +78,52 @@ impl InboxManager {
         }
     }
 
-    /// Inserts new message to the storage for an offline client for future retrieval.
+    /// Inserts new message to the storage for an offline client for future retrieval,
+    /// unless it would exceed the provided quota.
+    /// The quota check is part of the insertion statement itself, so concurrent insertions
+    /// can't push the inbox over its limits.
     ///
     /// # Arguments
     ///
     /// * `client_address_bs58`: base58-encoded address of the client
     /// * `content`: raw content of the message to store.
-    pub(crate) async fn insert_message(
+    /// * `quota`: limits on the data stored for the client.
+    ///
+    /// returns whether the message got stored.
+    pub(crate) async fn insert_message_within_quota(
         &self,
         client_address_bs58: &str,
         content: Vec<u8>,
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            "INSERT INTO message_store(client_address_bs58, content) VALUES (?, ?)",
+        quota: InboxQuota,
+    ) -> Result<bool, sqlx::Error> {
+        let stored_at = OffsetDateTime::now_utc().unix_timestamp();
+        let max_messages = i64::try_from(quota.max_messages).unwrap_or(i64::MAX);
+        let max_bytes = i64::try_from(quota.max_bytes).unwrap_or(i64::MAX);
+        let message_size = content.len() as i64;
+
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO message_store(client_address_bs58, content, stored_at)
+                SELECT ?, ?, ?
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM inbox_usage
+                    WHERE client_address_bs58 = ?
+                    AND ((? != 0 AND messages >= ?) OR (? != 0 AND bytes + ? > ?))
+                )
+            "#,
             client_address_bs58,
             content,
+            stored_at,
+            client_address_bs58,
+            max_messages,
+            max_messages,
+            max_bytes,
+            message_size,
+            max_bytes,
         )
         .execute(&self.connection_pool)
         .await?;
-        Ok(())
+        Ok(res.rows_affected() == 1)
     }
 
     /// Retrieves messages stored for the particular client specified by the provided address.
@@ -133,4 +208,66 @@ impl InboxManager {
             .await?;
         Ok(())
     }
+
+    /// Obtains the number and total size of messages stored for the particular client.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_address_bs58`: base58-encoded address of the client
+    pub(crate) async fn get_client_usage(
+        &self,
+        client_address_bs58: &str,
+    ) -> Result<InboxUsage, sqlx::Error> {
+        let res = sqlx::query!(
+            r#"
+                SELECT messages as "messages!: i64", bytes as "bytes!: i64"
+                FROM inbox_usage
+                WHERE client_address_bs58 = ?
+            "#,
+            client_address_bs58
+        )
+        .fetch_optional(&self.connection_pool)
+        .await?;
+
+        Ok(res
+            .map(|res| InboxUsage {
+                messages: res.messages as u64,
+                bytes: res.bytes as u64,
+            })
+            .unwrap_or_default())
+    }
+
+    /// Obtains the number and total size of messages stored for all clients.
+    pub(crate) async fn get_total_usage(&self) -> Result<InboxUsage, sqlx::Error> {
+        let res = sqlx::query!(
+            r#"
+                SELECT
+                    COALESCE(SUM(messages), 0) as "messages!: i64",
+                    COALESCE(SUM(bytes), 0) as "bytes!: i64"
+                FROM inbox_usage
+            "#
+        )
+        .fetch_one(&self.connection_pool)
+        .await?;
+
+        Ok(InboxUsage {
+            messages: res.messages as u64,
+            bytes: res.bytes as u64,
+        })
+    }
+
+    /// Removes all messages that have been stored before the provided cutoff.
+    ///
+    /// # Arguments
+    ///
+    /// * `cutoff`: the point in time before which all messages are considered stale
+    ///
+    /// returns the number of removed messages.
+    pub(crate) async fn remove_stale(&self, cutoff: OffsetDateTime) -> Result<u64, sqlx::Error> {
+        let cutoff = cutoff.unix_timestamp();
+        let res = sqlx::query!("DELETE FROM message_store WHERE stored_at < ?", cutoff)
+            .execute(&self.connection_pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
 }
@@ -13,13 +13,20 @@ use nym_gateway_requests::registration::handshake::SharedKeys;
 use nym_sphinx::DestinationAddressBytes;
 use sqlx::ConnectOptions;
 use std::path::Path;
+use time::OffsetDateTime;
 
 mod bandwidth;
 pub(crate) mod error;
+mod in_memory;
 mod inboxes;
 mod models;
+mod pruner;
 mod shared_keys;
 
+pub(crate) use in_memory::InMemStorage;
+pub(crate) use inboxes::{InboxQuota, InboxUsage};
+pub(crate) use pruner::InboxPruner;
+
 #[async_trait]
 pub(crate) trait Storage: Send + Sync {
     /// Inserts provided derived shared keys into the database.
@@ -59,6 +66,7 @@ pub(crate) trait Storage: Send + Sync {
     ) -> Result<(), StorageError>;
 
     /// Inserts new message to the storage for an offline client for future retrieval.
+    /// It fails if storing the message would exceed the inbox quota of the client.
     ///
     /// # Arguments
     ///
@@ -92,6 +100,18 @@ pub(crate) trait Storage: Send + Sync {
     /// * `ids`: ids of the messages to remove
     async fn remove_messages(&self, ids: Vec<i64>) -> Result<(), StorageError>;
 
+    /// Removes all messages that have been stored before the provided cutoff.
+    ///
+    /// # Arguments
+    ///
+    /// * `cutoff`: the point in time before which all messages are considered stale
+    ///
+    /// returns the number of removed messages.
+    async fn remove_stale_messages(&self, cutoff: OffsetDateTime) -> Result<u64, StorageError>;
+
+    /// Obtains the number and total size of messages stored for all offline clients.
+    async fn inbox_usage(&self) -> Result<InboxUsage, StorageError>;
+
     /// Creates a new bandwidth entry for the particular client.
     ///
     /// # Arguments
@@ -166,6 +186,7 @@ pub(crate) struct PersistentStorage {
     shared_key_manager: SharedKeysManager,
     inbox_manager: InboxManager,
     bandwidth_manager: BandwidthManager,
+    inbox_quota: InboxQuota,
 }
 
 impl PersistentStorage {
@@ -175,9 +196,11 @@ impl PersistentStorage {
     ///
     /// * `database_path`: path to the database.
     /// * `message_retrieval_limit`: maximum number of stored client messages that can be retrieved at once.
+    /// * `inbox_quota`: limits on the amount of data stored for a single offline client.
     pub async fn init<P: AsRef<Path> + Send>(
         database_path: P,
         message_retrieval_limit: i64,
+        inbox_quota: InboxQuota,
     ) -> Result<Self, StorageError> {
         debug!(
             "Attempting to connect to database {:?}",
@@ -212,6 +235,7 @@ impl PersistentStorage {
             shared_key_manager: SharedKeysManager::new(connection_pool.clone()),
             inbox_manager: InboxManager::new(connection_pool.clone(), message_retrieval_limit),
             bandwidth_manager: BandwidthManager::new(connection_pool),
+            inbox_quota,
         })
     }
 }
@@ -260,10 +284,26 @@ impl Storage for PersistentStorage {
         client_address: DestinationAddressBytes,
         message: Vec<u8>,
     ) -> Result<(), StorageError> {
-        self.inbox_manager
-            .insert_message(&client_address.as_base58_string(), message)
+        let client_address_bs58 = client_address.as_base58_string();
+
+        let stored = self
+            .inbox_manager
+            .insert_message_within_quota(&client_address_bs58, message, self.inbox_quota)
             .await?;
-        Ok(())
+        if stored {
+            return Ok(());
+        }
+
+        // the insertion got rejected, so report the usage that caused it
+        let usage = self
+            .inbox_manager
+            .get_client_usage(&client_address_bs58)
+            .await?;
+        Err(StorageError::InboxQuotaExceeded {
+            client_address_bs58,
+            messages: usage.messages,
+            bytes: usage.bytes,
+        })
     }
 
     async fn retrieve_messages(
@@ -285,6 +325,16 @@ impl Storage for PersistentStorage {
         Ok(())
     }
 
+    async fn remove_stale_messages(&self, cutoff: OffsetDateTime) -> Result<u64, StorageError> {
+        let removed = self.inbox_manager.remove_stale(cutoff).await?;
+        Ok(removed)
+    }
+
+    async fn inbox_usage(&self) -> Result<InboxUsage, StorageError> {
+        let usage = self.inbox_manager.get_total_usage().await?;
+        Ok(usage)
+    }
+
     async fn create_bandwidth_entry(
         &self,
         client_address: DestinationAddressBytes,
@@ -358,107 +408,139 @@ impl Storage for PersistentStorage {
     }
 }
 
-/// In-memory implementation of `Storage`. The intention is primarily in testing environments.
-#[cfg(test)]
+/// Storage backend used by the gateway, as selected in its config.
 #[derive(Clone)]
-pub(crate) struct InMemStorage;
+pub(crate) enum GatewayStorage {
+    Persistent(PersistentStorage),
+    InMemory(InMemStorage),
+}
+
+impl From<PersistentStorage> for GatewayStorage {
+    fn from(storage: PersistentStorage) -> Self {
+        GatewayStorage::Persistent(storage)
+    }
+}
+
+impl From<InMemStorage> for GatewayStorage {
+    fn from(storage: InMemStorage) -> Self {
+        GatewayStorage::InMemory(storage)
+    }
+}
 
-//#[cfg(test)]
-//impl InMemStorage {
-//    #[allow(unused)]
-//    async fn init<P: AsRef<Path> + Send>() -> Result<Self, StorageError> {
-//        todo!()
-//    }
-//}
+impl GatewayStorage {
+    fn inner(&self) -> &dyn Storage {
+        match self {
+            GatewayStorage::Persistent(storage) => storage,
+            GatewayStorage::InMemory(storage) => storage,
+        }
+    }
+}
 
-#[cfg(test)]
 #[async_trait]
-impl Storage for InMemStorage {
+impl Storage for GatewayStorage {
     async fn insert_shared_keys(
         &self,
-        _client_address: DestinationAddressBytes,
-        _shared_keys: &SharedKeys,
+        client_address: DestinationAddressBytes,
+        shared_keys: &SharedKeys,
     ) -> Result<(), StorageError> {
-        todo!()
+        self.inner()
+            .insert_shared_keys(client_address, shared_keys)
+            .await
     }
 
     async fn get_shared_keys(
         &self,
-        _client_address: DestinationAddressBytes,
+        client_address: DestinationAddressBytes,
     ) -> Result<Option<PersistedSharedKeys>, StorageError> {
-        todo!()
+        self.inner().get_shared_keys(client_address).await
     }
 
     async fn remove_shared_keys(
         &self,
-        _client_address: DestinationAddressBytes,
+        client_address: DestinationAddressBytes,
     ) -> Result<(), StorageError> {
-        todo!()
+        self.inner().remove_shared_keys(client_address).await
     }
 
     async fn store_message(
         &self,
-        _client_address: DestinationAddressBytes,
-        _message: Vec<u8>,
+        client_address: DestinationAddressBytes,
+        message: Vec<u8>,
     ) -> Result<(), StorageError> {
-        todo!()
+        self.inner().store_message(client_address, message).await
     }
 
     async fn retrieve_messages(
         &self,
-        _client_address: DestinationAddressBytes,
-        _start_after: Option<i64>,
+        client_address: DestinationAddressBytes,
+        start_after: Option<i64>,
     ) -> Result<(Vec<StoredMessage>, Option<i64>), StorageError> {
-        todo!()
+        self.inner()
+            .retrieve_messages(client_address, start_after)
+            .await
+    }
+
+    async fn remove_messages(&self, ids: Vec<i64>) -> Result<(), StorageError> {
+        self.inner().remove_messages(ids).await
+    }
+
+    async fn remove_stale_messages(&self, cutoff: OffsetDateTime) -> Result<u64, StorageError> {
+        self.inner().remove_stale_messages(cutoff).await
     }
 
-    async fn remove_messages(&self, _ids: Vec<i64>) -> Result<(), StorageError> {
-        todo!()
+    async fn inbox_usage(&self) -> Result<InboxUsage, StorageError> {
+        self.inner().inbox_usage().await
     }
 
     async fn create_bandwidth_entry(
         &self,
-        _client_address: DestinationAddressBytes,
+        client_address: DestinationAddressBytes,
     ) -> Result<(), StorageError> {
-        todo!()
+        self.inner().create_bandwidth_entry(client_address).await
     }
 
     async fn get_available_bandwidth(
         &self,
-        _client_address: DestinationAddressBytes,
+        client_address: DestinationAddressBytes,
     ) -> Result<Option<i64>, StorageError> {
-        todo!()
+        self.inner().get_available_bandwidth(client_address).await
     }
 
     async fn increase_bandwidth(
         &self,
-        _client_address: DestinationAddressBytes,
-        _amount: i64,
+        client_address: DestinationAddressBytes,
+        amount: i64,
     ) -> Result<(), StorageError> {
-        todo!()
+        self.inner()
+            .increase_bandwidth(client_address, amount)
+            .await
     }
 
     async fn consume_bandwidth(
         &self,
-        _client_address: DestinationAddressBytes,
-        _amount: i64,
+        client_address: DestinationAddressBytes,
+        amount: i64,
     ) -> Result<(), StorageError> {
-        todo!()
+        self.inner().consume_bandwidth(client_address, amount).await
     }
 
     async fn insert_spent_credential(
         &self,
-        _blinded_serial_number: BlindedSerialNumber,
-        _was_freepass: bool,
-        _client_address: DestinationAddressBytes,
+        blinded_serial_number: BlindedSerialNumber,
+        was_freepass: bool,
+        client_address: DestinationAddressBytes,
     ) -> Result<(), StorageError> {
-        todo!()
+        self.inner()
+            .insert_spent_credential(blinded_serial_number, was_freepass, client_address)
+            .await
     }
 
     async fn contains_credential(
         &self,
-        _blinded_serial_number: &BlindedSerialNumber,
+        blinded_serial_number: &BlindedSerialNumber,
     ) -> Result<bool, StorageError> {
-        todo!()
+        self.inner()
+            .contains_credential(blinded_serial_number)
+            .await
     }
 }
@@ -0,0 +1,91 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node::statistics::inbox::InboxStorageStatistics;
+use crate::node::storage::Storage;
+use log::{debug, error, info, trace};
+use nym_task::TaskClient;
+use std::time::Duration;
+use time::OffsetDateTime;
+
+/// Task responsible for periodically removing messages that have been stored for offline clients
+/// for longer than the configured ttl and for keeping track of the storage usage.
+pub(crate) struct InboxPruner<St> {
+    storage: St,
+
+    /// Maximum age of a stored message. If set to 0, messages are never removed.
+    message_ttl: Duration,
+    pruning_interval: Duration,
+    statistics: InboxStorageStatistics,
+}
+
+impl<St> InboxPruner<St>
+where
+    St: Storage + 'static,
+{
+    pub(crate) fn new(
+        storage: St,
+        message_ttl: Duration,
+        pruning_interval: Duration,
+        statistics: InboxStorageStatistics,
+    ) -> Self {
+        InboxPruner {
+            storage,
+            message_ttl,
+            pruning_interval,
+            statistics,
+        }
+    }
+
+    async fn remove_stale_messages(&self) {
+        if self.message_ttl.is_zero() {
+            return;
+        }
+
+        let cutoff = OffsetDateTime::now_utc() - self.message_ttl;
+        match self.storage.remove_stale_messages(cutoff).await {
+            Ok(0) => trace!("there were no stale messages to remove"),
+            Ok(removed) => {
+                info!("removed {removed} messages older than {cutoff}");
+                self.statistics.record_expired(removed)
+            }
+            Err(err) => error!("failed to remove stale messages: {err}"),
+        }
+    }
+
+    async fn update_usage(&self) {
+        match self.storage.inbox_usage().await {
+            Ok(usage) => {
+                debug!(
+                    "currently storing {} messages ({} bytes) for offline clients",
+                    usage.messages, usage.bytes
+                );
+                self.statistics.update_usage(usage)
+            }
+            Err(err) => error!("failed to obtain inbox storage usage: {err}"),
+        }
+    }
+
+    pub(crate) fn start_with_shutdown(self, shutdown: TaskClient) {
+        tokio::spawn(self.run_with_shutdown(shutdown));
+    }
+
+    pub(crate) async fn run_with_shutdown(self, mut shutdown: TaskClient) {
+        debug!("Started InboxPruner with graceful shutdown support");
+
+        let mut interval = tokio::time::interval(self.pruning_interval);
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => {
+                    trace!("InboxPruner: Received shutdown");
+                }
+                _ = interval.tick() => {
+                    self.remove_stale_messages().await;
+                    self.update_usage().await;
+                }
+            }
+        }
+        debug!("InboxPruner: Exiting");
+    }
+}
@@ -46,6 +46,15 @@ const DEFAULT_PACKET_FORWARDING_INITIAL_BACKOFF: Duration = Duration::from_milli
 const DEFAULT_PACKET_FORWARDING_MAXIMUM_BACKOFF: Duration = Duration::from_millis(300_000);
 const DEFAULT_INITIAL_CONNECTION_TIMEOUT: Duration = Duration::from_millis(1_500);
 const DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE: usize = 2000;
+const DEFAULT_REPLAY_DETECTION_PARTITION_DURATION: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_REPLAY_DETECTION_EXPECTED_PACKET_RATE: u32 = 500;
+const DEFAULT_REPLAY_DETECTION_FALSE_POSITIVE_RATE: f64 = 1e-6;
+const DEFAULT_SPHINX_KEY_ROTATION_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_SPHINX_KEY_ROTATION_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_LEGACY_SPHINX_KEY_DEPRECATION_PERIOD: Duration =
+    Duration::from_secs(7 * 24 * 60 * 60);
+const DEFAULT_NOISE_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_NOISE_KEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 /// Derive default path to mixnodes's config directory.
 /// It should get resolved to `$HOME/.nym/mixnodes/<id>/config`
@@ -334,6 +343,70 @@ pub struct Debug {
     // existing nodes whilst everyone else is upgrading and getting the code for handling the new field.
     // It shall be disabled in the subsequent releases.
     pub use_legacy_framed_packet_version: bool,
+
+    /// Specifies whether the mixnode should stop rejecting packets it has already processed before.
+    pub disable_replay_detection: bool,
+
+    /// Duration of a single partition of the replay detection filter used for the packets created
+    /// for the bonded sphinx key. Since that key does not expire, the replays of such packets are
+    /// only going to be detected for at least this long. Packets created for the rotated keys
+    /// are remembered for as long as their keys are accepted.
+    #[serde(with = "humantime_serde")]
+    pub replay_detection_partition_duration: Duration,
+
+    /// Expected average number of packets processed by the mixnode per second. The replay detection
+    /// filter of each rotated sphinx key is sized to hold all packets processed at this rate for as long
+    /// as the key is accepted (and each partition of the bonded key filter for its duration).
+    /// For example, with the default rotation period, each rotated key filter takes ~170MB at 500
+    /// packets per second. If the rate gets exceeded, the false positive rate of the filters is going
+    /// to increase, i.e. honest packets might get rejected as replays.
+    pub replay_detection_expected_packet_rate: u32,
+
+    /// Desired false positive rate of the replay detection filter, i.e. the probability of
+    /// an honest packet being wrongly rejected.
+    pub replay_detection_false_positive_rate: f64,
+
+    /// Specifies whether the mixnode should periodically rotate its sphinx keys and announce them
+    /// via its http API. The bonded sphinx key is still going to be accepted for compatibility
+    /// with the clients unaware of the rotation for the duration of the `legacy_sphinx_key_deprecation_period`.
+    pub enable_sphinx_key_rotation: bool,
+
+    /// Defines how long each rotated sphinx key is going to be used for. The rotations are aligned
+    /// to the multiples of this period since unix epoch so that clients could easily determine
+    /// which key should be used at any given time.
+    #[serde(with = "humantime_serde")]
+    pub sphinx_key_rotation_period: Duration,
+
+    /// Defines for how long the previous sphinx key is still going to be accepted after the rotation
+    /// (and how long before the rotation the next key starts being accepted).
+    /// It should account for any clock skew of the clients and for packets already in transit.
+    #[serde(with = "humantime_serde")]
+    pub sphinx_key_rotation_grace_period: Duration,
+
+    /// Defines for how long, since the key rotation has first been enabled, the bonded sphinx key
+    /// is still going to be accepted. Once it passes, the rotated keys are the only ones accepted.
+    #[serde(with = "humantime_serde")]
+    pub legacy_sphinx_key_deprecation_period: Duration,
+
+    /// Specifies whether the mixnode should attempt to establish noise encrypted and authenticated
+    /// links when forwarding packets to other nodes, using their bonded sphinx keys.
+    /// Nodes that fail to complete the handshake, e.g. because they don't support noise yet,
+    /// are reached using the plaintext links instead, unless `require_noise_link_encryption` is set.
+    /// Note that the incoming noise links are always accepted.
+    pub use_noise_link_encryption: bool,
+
+    /// Specifies whether the mixnode should refuse any plaintext links, i.e. incoming connections
+    /// not initiating the noise handshake, incoming handshakes from unknown nodes
+    /// and outgoing connections to nodes whose keys are not known.
+    pub require_noise_link_encryption: bool,
+
+    /// Timeout for completing the noise handshake with another node.
+    #[serde(with = "humantime_serde")]
+    pub noise_handshake_timeout: Duration,
+
+    /// Specifies how often the noise keys of other nodes should be refreshed from the nym-api.
+    #[serde(with = "humantime_serde")]
+    pub noise_keys_refresh_interval: Duration,
 }
 
 impl Default for Debug {
@@ -346,6 +419,18 @@ impl Default for Debug {
             initial_connection_timeout: DEFAULT_INITIAL_CONNECTION_TIMEOUT,
             maximum_connection_buffer_size: DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE,
             use_legacy_framed_packet_version: false,
+            disable_replay_detection: false,
+            replay_detection_partition_duration: DEFAULT_REPLAY_DETECTION_PARTITION_DURATION,
+            replay_detection_expected_packet_rate: DEFAULT_REPLAY_DETECTION_EXPECTED_PACKET_RATE,
+            replay_detection_false_positive_rate: DEFAULT_REPLAY_DETECTION_FALSE_POSITIVE_RATE,
+            enable_sphinx_key_rotation: false,
+            sphinx_key_rotation_period: DEFAULT_SPHINX_KEY_ROTATION_PERIOD,
+            sphinx_key_rotation_grace_period: DEFAULT_SPHINX_KEY_ROTATION_GRACE_PERIOD,
+            legacy_sphinx_key_deprecation_period: DEFAULT_LEGACY_SPHINX_KEY_DEPRECATION_PERIOD,
+            use_noise_link_encryption: false,
+            require_noise_link_encryption: false,
+            noise_handshake_timeout: DEFAULT_NOISE_HANDSHAKE_TIMEOUT,
+            noise_keys_refresh_interval: DEFAULT_NOISE_KEYS_REFRESH_INTERVAL,
         }
     }
 }
@@ -44,6 +44,27 @@ pub enum MixnodeError {
         source: io::Error,
     },
 
+    #[error("failed to store rotated sphinx keys (rotation {rotation_id}) to '{}' (private key) and '{}' (public key): {err}", .paths.private_key_path.display(), .paths.public_key_path.display())]
+    SphinxKeyStoreFailure {
+        rotation_id: u32,
+        paths: nym_pemstore::KeyPairPath,
+        #[source]
+        err: io::Error,
+    },
+
+    #[error("failed to persist the time the sphinx key rotation has started at using path '{}': {err}", .path.display())]
+    SphinxKeyRotationStartPersistenceFailure {
+        path: PathBuf,
+        #[source]
+        err: io::Error,
+    },
+
+    #[error("the sphinx key rotation period must be at least one second long")]
+    InvalidSphinxKeyRotationPeriod,
+
+    #[error("there are no nym API endpoints available")]
+    NoNymApisAvailable,
+
     // TODO: in the future this should work the other way, i.e. NymNode depending on Gateway errors
     #[error(transparent)]
     NymNodeError(#[from] nym_node::error::NymNodeError),
@@ -12,6 +12,7 @@ use nym_crypto::asymmetric::{encryption, identity};
 use nym_node::error::NymNodeError;
 use nym_node::http::api::api_requests;
 use nym_node::http::api::api_requests::SignedHostInformation;
+use nym_node::http::router::SharedSphinxKeys;
 use nym_task::TaskClient;
 
 pub(crate) mod legacy;
@@ -47,6 +48,7 @@ pub(crate) struct HttpApiBuilder<'a> {
     sphinx_keypair: &'a encryption::KeyPair,
     legacy_mixnode: legacy::state::MixnodeAppState,
     legacy_descriptor: NodeDescription,
+    sphinx_keys: Option<SharedSphinxKeys>,
 }
 
 impl<'a> HttpApiBuilder<'a> {
@@ -61,6 +63,7 @@ impl<'a> HttpApiBuilder<'a> {
             sphinx_keypair,
             legacy_mixnode: legacy::state::MixnodeAppState::default(),
             legacy_descriptor: Default::default(),
+            sphinx_keys: None,
         }
     }
 
@@ -88,11 +91,17 @@ impl<'a> HttpApiBuilder<'a> {
         self
     }
 
+    #[must_use]
+    pub(crate) fn with_sphinx_keys(mut self, sphinx_keys: Option<SharedSphinxKeys>) -> Self {
+        self.sphinx_keys = sphinx_keys;
+        self
+    }
+
     pub(crate) fn start(self, task_client: TaskClient) -> Result<(), MixnodeError> {
         let bind_address = self.mixnode_config.http.bind_address;
         info!("Starting HTTP API on http://{bind_address}",);
 
-        let config = nym_node::http::Config::new(
+        let mut config = nym_node::http::Config::new(
             bin_info_owned!(),
             load_host_details(
                 self.mixnode_config,
@@ -102,6 +111,9 @@ impl<'a> HttpApiBuilder<'a> {
         )
         .with_mixnode(load_mixnode_details(self.mixnode_config)?)
         .with_landing_page_assets(self.mixnode_config.http.landing_page_assets_path.as_ref());
+        if let Some(sphinx_keys) = self.sphinx_keys {
+            config = config.with_mixnode_sphinx_keys(sphinx_keys);
+        }
 
         let router = nym_node::http::NymNodeRouter::new(config, None);
         let server = router
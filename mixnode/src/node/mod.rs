@@ -12,11 +12,17 @@ use crate::node::listener::Listener;
 use crate::node::node_description::NodeDescription;
 use crate::node::node_statistics::SharedNodeStats;
 use crate::node::packet_delayforwarder::{DelayForwarder, PacketDelayForwardSender};
+use crate::node::sphinx_keys::SphinxKeyRotation;
 use log::{error, info, warn};
 use nym_bin_common::output_format::OutputFormat;
 use nym_bin_common::version_checker::parse_version;
 use nym_crypto::asymmetric::{encryption, identity};
+use nym_mixnode_common::noise_keys::NoiseKeysRefresher;
+use nym_mixnode_common::packet_processor::key_ring::SphinxKeyRing;
+use nym_mixnode_common::replay_protection::{ReplayProtection, ReplayProtectionConfig};
 use nym_mixnode_common::verloc::{self, AtomicVerlocResult, VerlocMeasurer};
+use nym_node::http::router::SharedSphinxKeys;
+use nym_noise::{NoiseConfig, NoisePeerKeys};
 use nym_task::{TaskClient, TaskManager};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
@@ -30,6 +36,9 @@ mod listener;
 pub(crate) mod node_description;
 mod node_statistics;
 mod packet_delayforwarder;
+mod sphinx_keys;
+
+const DEFAULT_ROTATED_SPHINX_KEYS_DIR: &str = "rotated_sphinx_keys";
 
 // the MixNode will live for whole duration of this program
 pub struct MixNode {
@@ -73,6 +82,7 @@ impl MixNode {
         atomic_verloc_result: AtomicVerlocResult,
         node_stats_pointer: SharedNodeStats,
         metrics_key: Option<&String>,
+        announced_sphinx_keys: Option<SharedSphinxKeys>,
         task_client: TaskClient,
     ) -> Result<(), MixnodeError> {
         HttpApiBuilder::new(&self.config, &self.identity_keypair, &self.sphinx_keypair)
@@ -80,6 +90,7 @@ impl MixNode {
             .with_mixing_stats(node_stats_pointer)
             .with_metrics_key(metrics_key)
             .with_descriptor(self.descriptor.clone())
+            .with_sphinx_keys(announced_sphinx_keys)
             .start(task_client)
     }
 
@@ -99,18 +110,74 @@ impl MixNode {
         (node_stats_pointer, update_sender)
     }
 
+    fn build_replay_protection(&self) -> Option<ReplayProtection> {
+        if self.config.debug.disable_replay_detection {
+            warn!(
+                "replay detection is disabled - the node is going to process any replayed packets"
+            );
+            None
+        } else {
+            // each rotated key is also accepted for the grace period before and after its rotation
+            let key_lifetime = self.config.debug.sphinx_key_rotation_period
+                + 2 * self.config.debug.sphinx_key_rotation_grace_period;
+
+            Some(ReplayProtection::new(ReplayProtectionConfig {
+                expected_packet_rate: self.config.debug.replay_detection_expected_packet_rate,
+                false_positive_rate: self.config.debug.replay_detection_false_positive_rate,
+                key_lifetime,
+                legacy_partition_duration: self.config.debug.replay_detection_partition_duration,
+            }))
+        }
+    }
+
+    fn start_sphinx_key_rotation(
+        &self,
+        replay_protection: Option<ReplayProtection>,
+        shutdown: TaskClient,
+    ) -> Result<(SphinxKeyRing, SharedSphinxKeys), MixnodeError> {
+        info!("Starting sphinx key rotation...");
+
+        let storage_directory = self
+            .config
+            .storage_paths
+            .keys
+            .private_encryption_key()
+            .parent()
+            .map(|keys_dir| keys_dir.join(DEFAULT_ROTATED_SPHINX_KEYS_DIR))
+            .unwrap_or_else(|| DEFAULT_ROTATED_SPHINX_KEYS_DIR.into());
+
+        let mut rotation = SphinxKeyRotation::new(
+            self.config.debug.sphinx_key_rotation_period,
+            self.config.debug.sphinx_key_rotation_grace_period,
+            self.config.debug.legacy_sphinx_key_deprecation_period,
+            storage_directory,
+            &self.sphinx_keypair,
+            Arc::clone(&self.identity_keypair),
+            replay_protection,
+        )?;
+        let key_ring = rotation.key_ring();
+        let announced_keys = rotation.announced_keys();
+
+        tokio::spawn(async move { rotation.run(shutdown).await });
+        Ok((key_ring, announced_keys))
+    }
+
     fn start_socket_listener(
         &self,
+        sphinx_keys: SphinxKeyRing,
+        replay_protection: Option<ReplayProtection>,
         node_stats_update_sender: node_statistics::UpdateSender,
         delay_forwarding_channel: PacketDelayForwardSender,
+        noise_config: NoiseConfig,
         shutdown: TaskClient,
     ) {
         info!("Starting socket listener...");
 
         let packet_processor =
-            PacketProcessor::new(self.sphinx_keypair.private_key(), node_stats_update_sender);
+            PacketProcessor::new(sphinx_keys, replay_protection, node_stats_update_sender);
 
-        let connection_handler = ConnectionHandler::new(packet_processor, delay_forwarding_channel);
+        let connection_handler =
+            ConnectionHandler::new(packet_processor, delay_forwarding_channel, noise_config);
 
         let listening_address = SocketAddr::new(
             self.config.mixnode.listening_address,
@@ -123,17 +190,21 @@ impl MixNode {
     fn start_packet_delay_forwarder(
         &mut self,
         node_stats_update_sender: node_statistics::UpdateSender,
+        noise_config: &NoiseConfig,
         shutdown: TaskClient,
     ) -> PacketDelayForwardSender {
         info!("Starting packet delay-forwarder...");
 
-        let client_config = nym_mixnet_client::Config::new(
+        let mut client_config = nym_mixnet_client::Config::new(
             self.config.debug.packet_forwarding_initial_backoff,
             self.config.debug.packet_forwarding_maximum_backoff,
             self.config.debug.initial_connection_timeout,
             self.config.debug.maximum_connection_buffer_size,
             self.config.debug.use_legacy_framed_packet_version,
         );
+        if self.uses_noise_for_outgoing_links() {
+            client_config = client_config.with_noise(noise_config.clone());
+        }
 
         let mut packet_forwarder = DelayForwarder::new(
             nym_mixnet_client::Client::new(client_config),
@@ -187,6 +258,35 @@ impl MixNode {
         atomic_verloc_results
     }
 
+    fn uses_noise_for_outgoing_links(&self) -> bool {
+        self.config.debug.use_noise_link_encryption
+            || self.config.debug.require_noise_link_encryption
+    }
+
+    fn build_noise_config(&self) -> NoiseConfig {
+        NoiseConfig::new(Arc::clone(&self.sphinx_keypair), NoisePeerKeys::new())
+            .with_handshake_timeout(self.config.debug.noise_handshake_timeout)
+            .with_required_encryption(self.config.debug.require_noise_link_encryption)
+    }
+
+    fn start_noise_keys_refresher(
+        &self,
+        peer_keys: NoisePeerKeys,
+        shutdown: TaskClient,
+    ) -> Result<(), MixnodeError> {
+        info!("Starting noise keys refresher...");
+
+        let mut refresher = NoiseKeysRefresher::new(
+            peer_keys,
+            self.config.debug.noise_keys_refresh_interval,
+            self.config.get_nym_api_endpoints(),
+            shutdown,
+        )
+        .map_err(|_| MixnodeError::NoNymApisAvailable)?;
+        tokio::spawn(async move { refresher.run().await });
+        Ok(())
+    }
+
     fn random_api_client(&self) -> nym_validator_client::NymApiClient {
         let endpoints = self.config.get_nym_api_endpoints();
         let nym_api = endpoints
@@ -233,13 +333,41 @@ impl MixNode {
 
         let (node_stats_pointer, node_stats_update_sender) = self
             .start_node_stats_controller(shutdown.subscribe().named("node_statistics::Controller"));
+
+        let noise_config = self.build_noise_config();
+        if self.uses_noise_for_outgoing_links() {
+            self.start_noise_keys_refresher(
+                noise_config.peer_keys().clone(),
+                shutdown.subscribe().named("NoiseKeysRefresher"),
+            )?;
+        }
+
         let delay_forwarding_channel = self.start_packet_delay_forwarder(
             node_stats_update_sender.clone(),
+            &noise_config,
             shutdown.subscribe().named("DelayForwarder"),
         );
+
+        let replay_protection = self.build_replay_protection();
+        let (sphinx_keys, announced_sphinx_keys) = if self.config.debug.enable_sphinx_key_rotation {
+            let (key_ring, announced) = self.start_sphinx_key_rotation(
+                replay_protection.clone(),
+                shutdown.subscribe().named("SphinxKeyRotation"),
+            )?;
+            (key_ring, Some(announced))
+        } else {
+            (
+                SphinxKeyRing::new(self.sphinx_keypair.private_key().into()),
+                None,
+            )
+        };
+
         self.start_socket_listener(
+            sphinx_keys,
+            replay_protection,
             node_stats_update_sender,
             delay_forwarding_channel,
+            noise_config,
             shutdown.subscribe().named("Listener"),
         );
         let atomic_verloc_results =
@@ -252,6 +380,7 @@ impl MixNode {
             atomic_verloc_results,
             node_stats_pointer,
             self.config.metrics_key(),
+            announced_sphinx_keys,
             shutdown.subscribe().named("http-api"),
         )?;
 
@@ -7,7 +7,7 @@ use super::TaskClient;
 use futures::channel::mpsc;
 use futures::lock::Mutex;
 use futures::StreamExt;
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use serde::Serialize;
 use std::collections::HashMap;
 use std::ops::DerefMut;
@@ -37,7 +37,9 @@ impl SharedNodeStats {
                 packets_received_since_startup: 0.,
                 packets_sent_since_startup_all: 0.,
                 packets_dropped_since_startup_all: 0.,
+                packets_replayed_since_startup: 0.,
                 packets_received_since_last_update: 0.,
+                packets_replayed_since_last_update: 0.,
                 packets_sent_since_last_update: HashMap::new(),
                 packets_explicitly_dropped_since_last_update: HashMap::new(),
             })),
@@ -47,6 +49,7 @@ impl SharedNodeStats {
     pub(crate) async fn update(
         &self,
         new_received: f64,
+        new_replayed: f64,
         new_sent: PacketsMap,
         new_dropped: PacketsMap,
     ) {
@@ -57,6 +60,7 @@ impl SharedNodeStats {
         guard.update_time = snapshot_time;
 
         guard.packets_received_since_startup += new_received;
+        guard.packets_replayed_since_startup += new_replayed;
         for count in new_sent.values() {
             guard.packets_sent_since_startup_all += count;
         }
@@ -66,6 +70,7 @@ impl SharedNodeStats {
         }
 
         REGISTRY.inc_by("packets_received_since_startup", new_received);
+        REGISTRY.inc_by("packets_replayed_since_startup", new_replayed);
         REGISTRY.inc_by("packets_sent_since_startup_all", new_sent.values().sum());
         REGISTRY.inc_by(
             "packets_dropped_since_startup_all",
@@ -73,6 +78,7 @@ impl SharedNodeStats {
         );
 
         guard.packets_received_since_last_update = new_received;
+        guard.packets_replayed_since_last_update = new_replayed;
         guard.packets_sent_since_last_update = new_sent;
         guard.packets_explicitly_dropped_since_last_update = new_dropped;
     }
@@ -95,7 +101,10 @@ pub struct NodeStats {
     packets_received_since_startup: f64,
     packets_sent_since_startup_all: f64,
     packets_dropped_since_startup_all: f64,
+    // packets rejected by the replay detection
+    packets_replayed_since_startup: f64,
     packets_received_since_last_update: f64,
+    packets_replayed_since_last_update: f64,
     // note: sent does not imply forwarded. We don't know if it was delivered successfully
     packets_sent_since_last_update: PacketsMap,
     // we know for sure we dropped packets to those destinations
@@ -110,7 +119,9 @@ impl Default for NodeStats {
             packets_received_since_startup: 0.,
             packets_sent_since_startup_all: 0.,
             packets_dropped_since_startup_all: 0.,
+            packets_replayed_since_startup: 0.,
             packets_received_since_last_update: 0.,
+            packets_replayed_since_last_update: 0.,
             packets_sent_since_last_update: Default::default(),
             packets_explicitly_dropped_since_last_update: Default::default(),
         }
@@ -125,7 +136,9 @@ impl NodeStats {
             packets_received_since_startup: self.packets_received_since_startup,
             packets_sent_since_startup: self.packets_sent_since_startup_all,
             packets_explicitly_dropped_since_startup: self.packets_dropped_since_startup_all,
+            packets_replayed_since_startup: self.packets_replayed_since_startup,
             packets_received_since_last_update: self.packets_received_since_last_update,
+            packets_replayed_since_last_update: self.packets_replayed_since_last_update,
             packets_sent_since_last_update: self.packets_sent_since_last_update.values().sum(),
             packets_explicitly_dropped_since_last_update: self
                 .packets_explicitly_dropped_since_last_update
@@ -151,8 +164,13 @@ pub struct NodeStatsSimple {
     // we know for sure we dropped those packets
     packets_explicitly_dropped_since_startup: f64,
 
+    // packets that got rejected as they have already been processed before
+    packets_replayed_since_startup: f64,
+
     packets_received_since_last_update: f64,
 
+    packets_replayed_since_last_update: f64,
+
     // note: sent does not imply forwarded. We don't know if it was delivered successfully
     packets_sent_since_last_update: f64,
 
@@ -163,6 +181,7 @@ pub struct NodeStatsSimple {
 pub(crate) enum PacketEvent {
     Sent(String),
     Received,
+    Replayed,
     Dropped(String),
 }
 
@@ -174,6 +193,7 @@ struct CurrentPacketData {
 #[derive(Debug)]
 struct PacketDataInner {
     received: AtomicU64,
+    replayed: AtomicU64,
     sent: Mutex<PacketsMap>,
     dropped: Mutex<PacketsMap>,
 }
@@ -183,6 +203,7 @@ impl CurrentPacketData {
         CurrentPacketData {
             inner: Arc::new(PacketDataInner {
                 received: AtomicU64::new(0),
+                replayed: AtomicU64::new(0),
                 sent: Mutex::new(HashMap::new()),
                 dropped: Mutex::new(HashMap::new()),
             }),
@@ -193,6 +214,10 @@ impl CurrentPacketData {
         self.inner.received.fetch_add(1, Ordering::SeqCst);
     }
 
+    fn increment_replayed(&self) {
+        self.inner.replayed.fetch_add(1, Ordering::SeqCst);
+    }
+
     async fn increment_sent(&self, destination: String) {
         let mut unlocked = self.inner.sent.lock().await;
         let receiver_count = unlocked.entry(destination).or_insert(0.);
@@ -205,15 +230,16 @@ impl CurrentPacketData {
         *dropped_count += 1.;
     }
 
-    async fn acquire_and_reset(&self) -> (u64, PacketsMap, PacketsMap) {
+    async fn acquire_and_reset(&self) -> (u64, u64, PacketsMap, PacketsMap) {
         let mut unlocked_sent = self.inner.sent.lock().await;
         let mut unlocked_dropped = self.inner.dropped.lock().await;
         let received = self.inner.received.swap(0, Ordering::SeqCst);
+        let replayed = self.inner.replayed.swap(0, Ordering::SeqCst);
 
         let sent = std::mem::take(unlocked_sent.deref_mut());
         let dropped = std::mem::take(unlocked_dropped.deref_mut());
 
-        (received, sent, dropped)
+        (received, replayed, sent, dropped)
     }
 }
 
@@ -244,6 +270,7 @@ impl UpdateHandler {
                 Some(packet_data) = self.update_receiver.next() => {
                     match packet_data {
                         PacketEvent::Received => self.current_data.increment_received(),
+                        PacketEvent::Replayed => self.current_data.increment_replayed(),
                         PacketEvent::Sent(destination) => {
                             self.current_data.increment_sent(destination).await
                         }
@@ -288,6 +315,12 @@ impl UpdateSender {
         self.0.unbounded_send(PacketEvent::Received).unwrap()
     }
 
+    pub(crate) fn report_replayed(&self) {
+        // in unbounded_send() failed it means that the receiver channel was disconnected
+        // and hence something weird must have happened without a way of recovering
+        self.0.unbounded_send(PacketEvent::Replayed).unwrap()
+    }
+
     pub(crate) fn report_dropped(&self, destination: String) {
         // in unbounded_send() failed it means that the receiver channel was disconnected
         // and hence something weird must have happened without a way of recovering
@@ -323,9 +356,10 @@ impl StatsUpdater {
 
     async fn update_stats(&self) {
         // grab new data since last update
-        let (received, sent, dropped) = self.current_packet_data.acquire_and_reset().await;
+        let (received, replayed, sent, dropped) =
+            self.current_packet_data.acquire_and_reset().await;
         self.current_stats
-            .update(received as f64, sent, dropped)
+            .update(received as f64, replayed as f64, sent, dropped)
             .await;
     }
 
@@ -385,6 +419,14 @@ impl PacketStatsConsoleLogger {
                     difference_secs,
                 );
             }
+            if stats.packets_replayed_since_startup > 0. {
+                warn!(
+                    "Since startup rejected {} replayed packets! ({} in last {} seconds)",
+                    stats.packets_replayed_since_startup,
+                    stats.packets_replayed_since_last_update,
+                    difference_secs,
+                );
+            }
 
             debug!(
                 "Since startup received {} packets ({} in last {} seconds)",
@@ -409,6 +451,12 @@ impl PacketStatsConsoleLogger {
                     stats.packets_dropped_since_startup_all,
                 );
             }
+            if stats.packets_replayed_since_startup > 0. {
+                warn!(
+                    "Since startup rejected {} replayed packets!",
+                    stats.packets_replayed_since_startup,
+                );
+            }
 
             debug!(
                 "Since startup received {} packets",
@@ -538,6 +586,7 @@ mod tests {
         assert_eq!(&stats.packets_sent_since_last_update.len(), &1);
         assert_eq!(&stats.packets_received_since_startup, &0.);
         assert_eq!(&stats.packets_dropped_since_startup_all, &0.);
-        assert_eq!(REGISTRY.to_string(), "# HELP packets_dropped_since_startup_all packets_dropped_since_startup_all\n# TYPE packets_dropped_since_startup_all counter\npackets_dropped_since_startup_all 0\n# HELP packets_received_since_startup packets_received_since_startup\n# TYPE packets_received_since_startup counter\npackets_received_since_startup 0\n# HELP packets_sent_since_startup_all packets_sent_since_startup_all\n# TYPE packets_sent_since_startup_all counter\npackets_sent_since_startup_all 2\n")
+        assert_eq!(&stats.packets_replayed_since_startup, &0.);
+        assert_eq!(REGISTRY.to_string(), "# HELP packets_dropped_since_startup_all packets_dropped_since_startup_all\n# TYPE packets_dropped_since_startup_all counter\npackets_dropped_since_startup_all 0\n# HELP packets_received_since_startup packets_received_since_startup\n# TYPE packets_received_since_startup counter\npackets_received_since_startup 0\n# HELP packets_replayed_since_startup packets_replayed_since_startup\n# TYPE packets_replayed_since_startup counter\npackets_replayed_since_startup 0\n# HELP packets_sent_since_startup_all packets_sent_since_startup_all\n# TYPE packets_sent_since_startup_all counter\npackets_sent_since_startup_all 2\n")
     }
 }
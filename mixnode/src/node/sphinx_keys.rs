@@ -0,0 +1,439 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Scheduled rotation of the sphinx keys.
+//!
+//! The time is split into rotations of fixed length, aligned to the unix epoch, so that the
+//! rotation id valid at any given time could be computed by anyone knowing the rotation period.
+//! The node always announces the key of the current and the next rotation (so that clients with
+//! slightly outdated topology could still construct valid packets), keeps accepting the key
+//! of the previous rotation for the duration of the grace period and starts accepting the key of the
+//! next rotation the grace period before it begins. Outside those windows only a single key is accepted,
+//! so that every received packet has to be unwrapped at most once.
+//!
+//! Note that the bonded sphinx key is also accepted, so that the clients that are not aware
+//! of the rotation could continue to use the node, but only for the deprecation period
+//! since the rotation has first been enabled.
+
+use crate::error::MixnodeError;
+use log::{debug, error, info, warn};
+use nym_crypto::asymmetric::{encryption, identity};
+use nym_mixnode_common::packet_processor::key_ring::{EpochKey, KeyEpoch, SphinxKeyRing};
+use nym_mixnode_common::replay_protection::ReplayProtection;
+use nym_node::http::api::api_requests::v1::mixnode::models::{AnnouncedSphinxKey, SphinxKeys};
+use nym_node::http::api::api_requests::SignedSphinxKeys;
+use nym_node::http::router::SharedSphinxKeys;
+use nym_pemstore::KeyPairPath;
+use nym_sphinx::PrivateKey as SphinxPrivateKey;
+use nym_task::TaskClient;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const ROTATION_START_FILE: &str = "rotation_started_at";
+
+struct RotatedKey {
+    keypair: encryption::KeyPair,
+    sphinx_private: Arc<SphinxPrivateKey>,
+}
+
+impl RotatedKey {
+    fn new(keypair: encryption::KeyPair) -> Self {
+        RotatedKey {
+            sphinx_private: Arc::new(keypair.private_key().into()),
+            keypair,
+        }
+    }
+}
+
+pub(crate) struct SphinxKeyRotation {
+    rotation_period: u64,
+    grace_period: u64,
+    storage_directory: PathBuf,
+
+    legacy_key: Arc<SphinxPrivateKey>,
+    legacy_key_valid_until: i64,
+    identity_keypair: Arc<identity::KeyPair>,
+
+    key_ring: SphinxKeyRing,
+    announced: SharedSphinxKeys,
+    replay_protection: Option<ReplayProtection>,
+
+    keys: BTreeMap<u32, RotatedKey>,
+    current_rotation: Option<u32>,
+}
+
+impl SphinxKeyRotation {
+    pub(crate) fn new(
+        rotation_period: Duration,
+        grace_period: Duration,
+        legacy_key_deprecation_period: Duration,
+        storage_directory: PathBuf,
+        legacy_keypair: &encryption::KeyPair,
+        identity_keypair: Arc<identity::KeyPair>,
+        replay_protection: Option<ReplayProtection>,
+    ) -> Result<Self, MixnodeError> {
+        if rotation_period.as_secs() == 0 {
+            return Err(MixnodeError::InvalidSphinxKeyRotationPeriod);
+        }
+        let legacy_key = Arc::new(legacy_keypair.private_key().into());
+        let rotation_started_at = load_or_store_rotation_start(&storage_directory, unix_now())?;
+        let legacy_key_valid_until =
+            rotation_started_at.saturating_add(legacy_key_deprecation_period.as_secs() as i64);
+
+        // it's going to be immediately replaced with the proper data in `update_keys`
+        let announced =
+            SignedSphinxKeys::new(SphinxKeys::default(), identity_keypair.private_key())
+                .map_err(nym_node::error::NymNodeError::from)?;
+
+        let mut rotation = SphinxKeyRotation {
+            rotation_period: rotation_period.as_secs(),
+            grace_period: grace_period.as_secs(),
+            storage_directory,
+            key_ring: SphinxKeyRing::new(legacy_keypair.private_key().into()),
+            legacy_key,
+            legacy_key_valid_until,
+            identity_keypair,
+            announced: Arc::new(RwLock::new(announced)),
+            replay_protection,
+            keys: BTreeMap::new(),
+            current_rotation: None,
+        };
+        rotation.update_keys(unix_now())?;
+        Ok(rotation)
+    }
+
+    pub(crate) fn key_ring(&self) -> SphinxKeyRing {
+        self.key_ring.clone()
+    }
+
+    pub(crate) fn announced_keys(&self) -> SharedSphinxKeys {
+        Arc::clone(&self.announced)
+    }
+
+    fn key_paths(&self, rotation_id: u32) -> KeyPairPath {
+        KeyPairPath::new(
+            self.storage_directory
+                .join(format!("private_sphinx_{rotation_id}.pem")),
+            self.storage_directory
+                .join(format!("public_sphinx_{rotation_id}.pem")),
+        )
+    }
+
+    fn load_or_generate_key(&self, rotation_id: u32) -> Result<RotatedKey, MixnodeError> {
+        let paths = self.key_paths(rotation_id);
+        if let Ok(keypair) = nym_pemstore::load_keypair(&paths) {
+            return Ok(RotatedKey::new(keypair));
+        }
+
+        debug!("generating new sphinx key for rotation {rotation_id}");
+        let keypair = encryption::KeyPair::new(&mut rand::rngs::OsRng);
+        std::fs::create_dir_all(&self.storage_directory)
+            .and_then(|_| nym_pemstore::store_keypair(&keypair, &paths))
+            .map_err(|err| MixnodeError::SphinxKeyStoreFailure {
+                rotation_id,
+                paths,
+                err,
+            })?;
+        Ok(RotatedKey::new(keypair))
+    }
+
+    fn remove_key(&self, rotation_id: u32) {
+        let paths = self.key_paths(rotation_id);
+        for path in [paths.private_key_path, paths.public_key_path] {
+            if let Err(err) = std::fs::remove_file(&path) {
+                warn!(
+                    "failed to remove expired sphinx key at '{}': {err}",
+                    path.display()
+                )
+            }
+        }
+    }
+
+    fn announced_key(&self, rotation_id: u32) -> Option<AnnouncedSphinxKey> {
+        let key = self.keys.get(&rotation_id)?;
+        let (valid_from, valid_until) = rotation_validity(rotation_id, self.rotation_period);
+        Some(AnnouncedSphinxKey {
+            rotation_id,
+            public_key: key.keypair.public_key().to_base58_string(),
+            valid_from,
+            valid_until,
+        })
+    }
+
+    /// Makes sure all the required keys exist, removes the expired ones and updates both
+    /// the set of keys used for processing packets and the announced keys.
+    fn update_keys(&mut self, now: i64) -> Result<(), MixnodeError> {
+        let current = rotation_id(now, self.rotation_period);
+        let next = current.saturating_add(1);
+        let keep_previous = in_grace_period(now, current, self.rotation_period, self.grace_period);
+
+        for rotation_id in [current, next] {
+            if !self.keys.contains_key(&rotation_id) {
+                let key = self.load_or_generate_key(rotation_id)?;
+                self.keys.insert(rotation_id, key);
+            }
+        }
+
+        // if we have just started up, try to recover the previous key (if we still have it)
+        if keep_previous && self.current_rotation.is_none() {
+            if let Some(previous) = current.checked_sub(1) {
+                if let Ok(keypair) = nym_pemstore::load_keypair(&self.key_paths(previous)) {
+                    self.keys.insert(previous, RotatedKey::new(keypair));
+                }
+            }
+        }
+
+        let expired = self
+            .keys
+            .keys()
+            .copied()
+            .filter(|&id| id != current && id != next && !(keep_previous && id + 1 == current))
+            .collect::<Vec<_>>();
+        for rotation_id in expired {
+            debug!("removing expired sphinx key for rotation {rotation_id}");
+            self.keys.remove(&rotation_id);
+            self.remove_key(rotation_id);
+        }
+
+        // the current key is the most likely one to be used, then the previous one (for packets
+        // that were already in transit), the next one (for clients with skewed clocks)
+        // and finally the legacy key
+        let mut additional = Vec::new();
+        if let Some(previous) = current.checked_sub(1) {
+            if let Some(previous_key) = self.keys.get(&previous) {
+                additional.push(EpochKey::new(
+                    KeyEpoch::Rotation(previous),
+                    Arc::clone(&previous_key.sphinx_private),
+                ))
+            }
+        }
+        if accepts_next_key(now, current, self.rotation_period, self.grace_period) {
+            additional.push(EpochKey::new(
+                KeyEpoch::Rotation(next),
+                Arc::clone(&self.keys[&next].sphinx_private),
+            ));
+        }
+        if now < self.legacy_key_valid_until {
+            additional.push(EpochKey::new(
+                KeyEpoch::Legacy,
+                Arc::clone(&self.legacy_key),
+            ));
+        } else if self.key_ring.epochs().contains(&KeyEpoch::Legacy) {
+            info!("the deprecation period of the bonded sphinx key has passed - it's no longer going to be accepted")
+        }
+        self.key_ring.update(
+            EpochKey::new(
+                KeyEpoch::Rotation(current),
+                Arc::clone(&self.keys[&current].sphinx_private),
+            ),
+            additional,
+        );
+
+        // packets created for the keys that are no longer accepted could not be processed anyway,
+        // so there's no point in remembering their replay tags
+        if let Some(replay_protection) = &self.replay_protection {
+            replay_protection.retain_epochs(&self.key_ring.epochs())
+        }
+
+        let announcement = SphinxKeys {
+            keys: [current, next]
+                .into_iter()
+                .filter_map(|id| self.announced_key(id))
+                .collect(),
+        };
+        let signed = SignedSphinxKeys::new(announcement, self.identity_keypair.private_key())
+            .map_err(nym_node::error::NymNodeError::from)?;
+        match self.announced.write() {
+            Ok(mut guard) => *guard = signed,
+            Err(_) => error!("the announced sphinx keys lock got poisoned"),
+        }
+
+        if self
+            .current_rotation
+            .is_some_and(|previous| previous != current)
+        {
+            info!("rotated sphinx key: now using key for rotation {current}");
+        }
+        self.current_rotation = Some(current);
+
+        Ok(())
+    }
+
+    /// Returns the duration until the next time the set of accepted keys changes.
+    fn until_next_update(&self, now: i64) -> Duration {
+        let next_event = next_key_change(
+            now,
+            self.rotation_period,
+            self.grace_period,
+            self.legacy_key_valid_until,
+        );
+        Duration::from_secs(next_event.saturating_sub(now).max(1) as u64)
+    }
+
+    pub(crate) async fn run(&mut self, mut shutdown: TaskClient) {
+        debug!("Started SphinxKeyRotation with graceful shutdown support");
+
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                _ = tokio::time::sleep(self.until_next_update(unix_now())) => {
+                    if let Err(err) = self.update_keys(unix_now()) {
+                        error!("failed to rotate the sphinx keys: {err}")
+                    }
+                }
+                _ = shutdown.recv() => {
+                    log::trace!("SphinxKeyRotation: Received shutdown");
+                }
+            }
+        }
+    }
+}
+
+/// Retrieves the time the key rotation has first been enabled at or, if this is the first time,
+/// stores the current time.
+fn load_or_store_rotation_start(storage_directory: &Path, now: i64) -> Result<i64, MixnodeError> {
+    let path = storage_directory.join(ROTATION_START_FILE);
+    if let Ok(stored) = std::fs::read_to_string(&path) {
+        match stored.trim().parse() {
+            Ok(rotation_started_at) => return Ok(rotation_started_at),
+            Err(err) => warn!(
+                "the stored rotation start time at '{}' is malformed: {err}. It's going to be reset",
+                path.display()
+            ),
+        }
+    }
+
+    std::fs::create_dir_all(storage_directory)
+        .and_then(|_| std::fs::write(&path, now.to_string()))
+        .map_err(|err| MixnodeError::SphinxKeyRotationStartPersistenceFailure { path, err })?;
+    Ok(now)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn rotation_id(unix_timestamp: i64, rotation_period: u64) -> u32 {
+    (unix_timestamp.max(0) as u64 / rotation_period) as u32
+}
+
+fn rotation_validity(rotation_id: u32, rotation_period: u64) -> (i64, i64) {
+    let valid_from = rotation_id as i64 * rotation_period as i64;
+    (valid_from, valid_from + rotation_period as i64)
+}
+
+fn in_grace_period(now: i64, current: u32, rotation_period: u64, grace_period: u64) -> bool {
+    let (valid_from, _) = rotation_validity(current, rotation_period);
+    now < valid_from.saturating_add(grace_period as i64)
+}
+
+fn accepts_next_key(now: i64, current: u32, rotation_period: u64, grace_period: u64) -> bool {
+    let (_, valid_until) = rotation_validity(current, rotation_period);
+    now >= valid_until.saturating_sub(grace_period as i64)
+}
+
+/// Returns the unix timestamp of the next time the set of accepted keys is going to change.
+fn next_key_change(
+    now: i64,
+    rotation_period: u64,
+    grace_period: u64,
+    legacy_key_valid_until: i64,
+) -> i64 {
+    let current = rotation_id(now, rotation_period);
+    let (valid_from, valid_until) = rotation_validity(current, rotation_period);
+
+    [
+        valid_from.saturating_add(grace_period as i64),
+        valid_until.saturating_sub(grace_period as i64),
+        legacy_key_valid_until,
+    ]
+    .into_iter()
+    .filter(|&event| event > now)
+    .fold(valid_until, i64::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+    const HOUR: u64 = 60 * 60;
+
+    #[test]
+    fn rotations_are_aligned_to_the_epoch() {
+        assert_eq!(rotation_id(0, DAY), 0);
+        assert_eq!(rotation_id(DAY as i64 - 1, DAY), 0);
+        assert_eq!(rotation_id(DAY as i64, DAY), 1);
+        assert_eq!(rotation_id(-1, DAY), 0);
+
+        assert_eq!(rotation_validity(0, DAY), (0, DAY as i64));
+        assert_eq!(rotation_validity(3, DAY), (3 * DAY as i64, 4 * DAY as i64));
+    }
+
+    #[test]
+    fn grace_period_starts_at_the_rotation() {
+        let start = 10 * DAY as i64;
+        assert!(in_grace_period(start, 10, DAY, HOUR));
+        assert!(in_grace_period(start + HOUR as i64 - 1, 10, DAY, HOUR));
+        assert!(!in_grace_period(start + HOUR as i64, 10, DAY, HOUR));
+        assert!(!in_grace_period(start, 10, DAY, 0));
+    }
+
+    #[test]
+    fn next_key_is_only_accepted_shortly_before_the_rotation() {
+        let start = 10 * DAY as i64;
+        assert!(!accepts_next_key(start, 10, DAY, HOUR));
+        assert!(!accepts_next_key(
+            start + (DAY - HOUR) as i64 - 1,
+            10,
+            DAY,
+            HOUR
+        ));
+        assert!(accepts_next_key(start + (DAY - HOUR) as i64, 10, DAY, HOUR));
+        assert!(accepts_next_key(start + DAY as i64 - 1, 10, DAY, HOUR));
+    }
+
+    #[test]
+    fn key_changes_are_scheduled_at_every_window_boundary() {
+        let start = 10 * DAY as i64;
+        let never = i64::MAX;
+
+        // end of the grace period of the previous key
+        assert_eq!(
+            next_key_change(start, DAY, HOUR, never),
+            start + HOUR as i64
+        );
+        // start of the window for the next key
+        assert_eq!(
+            next_key_change(start + HOUR as i64, DAY, HOUR, never),
+            start + (DAY - HOUR) as i64
+        );
+        // the rotation itself
+        assert_eq!(
+            next_key_change(start + (DAY - HOUR) as i64, DAY, HOUR, never),
+            start + DAY as i64
+        );
+        // the legacy key deprecation
+        assert_eq!(
+            next_key_change(start + HOUR as i64, DAY, HOUR, start + 2 * HOUR as i64),
+            start + 2 * HOUR as i64
+        );
+        // legacy key that has already been deprecated does not affect anything
+        assert_eq!(
+            next_key_change(start + HOUR as i64, DAY, HOUR, start),
+            start + (DAY - HOUR) as i64
+        );
+    }
+
+    #[test]
+    fn rotation_start_is_persisted() {
+        let dir = std::env::temp_dir().join(format!("nym-sphinx-keys-{}", rand::random::<u64>()));
+        assert_eq!(load_or_store_rotation_start(&dir, 42).unwrap(), 42);
+        assert_eq!(load_or_store_rotation_start(&dir, 100).unwrap(), 42);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
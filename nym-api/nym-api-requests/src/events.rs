@@ -0,0 +1,80 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nym_mixnet_contract_common::{IdentityKey, Interval, MixId};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Interval at which the nym-api sends heartbeats (comments) on an otherwise quiet event stream,
+/// so that the subscribers could tell an idle stream apart from a dead connection.
+pub const NETWORK_EVENTS_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A change in the network topology or the epoch state, as observed by the nym-api,
+/// that is pushed to the subscribers of its event stream.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NetworkEvent {
+    /// The network has advanced to a new epoch.
+    EpochTransition { interval: Interval },
+
+    /// The rewarded set, and thus possibly the active set, has changed.
+    RewardedSetChanged {
+        rewarded_set: Vec<MixId>,
+        active_set: Vec<MixId>,
+    },
+
+    /// A new mixnode has been bonded.
+    MixnodeBonded {
+        mix_id: MixId,
+        identity_key: IdentityKey,
+    },
+
+    /// A mixnode has been unbonded.
+    MixnodeUnbonded {
+        mix_id: MixId,
+        identity_key: IdentityKey,
+    },
+
+    /// A new gateway has been bonded.
+    GatewayBonded { identity_key: IdentityKey },
+
+    /// A gateway has been unbonded.
+    GatewayUnbonded { identity_key: IdentityKey },
+
+    /// A mixnode has been added to or removed from the blacklist.
+    MixnodeBlacklistChanged { mix_id: MixId, blacklisted: bool },
+
+    /// A gateway has been added to or removed from the blacklist.
+    GatewayBlacklistChanged {
+        identity_key: IdentityKey,
+        blacklisted: bool,
+    },
+
+    /// The subscriber has not been keeping up and some events have been dropped.
+    /// It should refetch the entire network state it cares about.
+    Resync,
+
+    /// An event introduced by a newer version of the nym-api that this one does not understand.
+    /// It's never published, only deserialized into, and is meant to be ignored.
+    #[serde(other)]
+    Unknown,
+}
+
+impl NetworkEvent {
+    /// Name of the event, as used in the `event` field of the server-sent events.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NetworkEvent::EpochTransition { .. } => "epoch_transition",
+            NetworkEvent::RewardedSetChanged { .. } => "rewarded_set_changed",
+            NetworkEvent::MixnodeBonded { .. } => "mixnode_bonded",
+            NetworkEvent::MixnodeUnbonded { .. } => "mixnode_unbonded",
+            NetworkEvent::GatewayBonded { .. } => "gateway_bonded",
+            NetworkEvent::GatewayUnbonded { .. } => "gateway_unbonded",
+            NetworkEvent::MixnodeBlacklistChanged { .. } => "mixnode_blacklist_changed",
+            NetworkEvent::GatewayBlacklistChanged { .. } => "gateway_blacklist_changed",
+            NetworkEvent::Resync => "resync",
+            NetworkEvent::Unknown => "unknown",
+        }
+    }
+}
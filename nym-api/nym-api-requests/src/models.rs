@@ -10,6 +10,7 @@ use nym_mixnet_contract_common::{
     GatewayBond, IdentityKey, Interval, MixId, MixNode, Percent, RewardedSetNodeStatus,
 };
 use nym_node_requests::api::v1::gateway::models::WebSockets;
+use nym_node_requests::api::v1::mixnode::models::AnnouncedSphinxKey;
 use nym_node_requests::api::v1::node::models::{BinaryBuildInformationOwned, HostInformation};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -402,3 +403,14 @@ pub struct IpPacketRouterDetails {
     /// address of the embedded ip packet router
     pub address: String,
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MixnodeSphinxKeys {
+    pub mix_id: MixId,
+
+    /// identity key of the mixnode that has signed the announced keys
+    pub identity_key: IdentityKey,
+
+    /// the current and the upcoming sphinx keys announced by the mixnode
+    pub keys: Vec<AnnouncedSphinxKey>,
+}
@@ -0,0 +1,25 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A single page of results alongside the information required for obtaining the following one.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PaginatedResponse<T> {
+    pub pagination: CursorPagination,
+    pub data: Vec<T>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CursorPagination {
+    /// Total number of items matching the specified filters (across all pages).
+    pub total: usize,
+
+    /// Number of items included in this page.
+    pub size: usize,
+
+    /// Opaque cursor that should be sent alongside the next request in order to get the following page.
+    /// It's not set if this is the last page.
+    pub next_cursor: Option<String>,
+}
@@ -9,6 +9,7 @@ use crate::coconut::dkg::controller::keys::{
 };
 use crate::epoch_operations::RewardedSetUpdater;
 use crate::network::models::NetworkDetails;
+use crate::node_describe_cache::mixnodes::DescribedMixnodes;
 use crate::node_describe_cache::DescribedNodes;
 use crate::node_status_api::uptime_updater::HistoricalUptimeUpdater;
 use crate::support::caching::cache::SharedCache;
@@ -120,6 +121,15 @@ async fn start_nym_api_tasks(config: Config) -> anyhow::Result<ShutdownHandles>
     .named("node-self-described-data-refresher")
     .start(shutdown.subscribe_named("node-self-described-data-refresher"));
 
+    let described_mixnodes_state = rocket.state::<SharedCache<DescribedMixnodes>>().unwrap();
+    node_describe_cache::mixnodes::new_refresher_with_initial_value(
+        &config.topology_cacher,
+        nym_contract_cache_state.clone(),
+        described_mixnodes_state.to_owned(),
+    )
+    .named("mixnode-self-described-data-refresher")
+    .start(shutdown.subscribe_named("mixnode-self-described-data-refresher"));
+
     // start all the caches first
     let nym_contract_cache_listener = nym_contract_cache::start_refresher(
         &config.node_status_api,
@@ -167,6 +177,7 @@ async fn start_nym_api_tasks(config: Config) -> anyhow::Result<ShutdownHandles>
         network_monitor::start::<SphinxMessageReceiver>(
             &config.network_monitor,
             nym_contract_cache_state,
+            described_mixnodes_state,
             storage,
             nyxd_client.clone(),
             &shutdown,
@@ -11,8 +11,10 @@ use crate::network_monitor::monitor::receiver::{
 use crate::network_monitor::monitor::sender::PacketSender;
 use crate::network_monitor::monitor::summary_producer::SummaryProducer;
 use crate::network_monitor::monitor::Monitor;
+use crate::node_describe_cache::mixnodes::DescribedMixnodes;
 use crate::nym_contract_cache::cache::NymContractCache;
 use crate::storage::NymApiStorage;
+use crate::support::caching::cache::SharedCache;
 use crate::support::{config, nyxd};
 use futures::channel::mpsc;
 use nym_bandwidth_controller::BandwidthController;
@@ -34,6 +36,7 @@ pub(crate) const ROUTE_TESTING_TEST_NONCE: u64 = 0;
 pub(crate) fn setup<'a>(
     config: &'a config::NetworkMonitor,
     nym_contract_cache_state: &NymContractCache,
+    sphinx_keys_cache_state: &SharedCache<DescribedMixnodes>,
     storage: &NymApiStorage,
     nyxd_client: nyxd::Client,
 ) -> NetworkMonitorBuilder<'a> {
@@ -42,6 +45,7 @@ pub(crate) fn setup<'a>(
         nyxd_client,
         storage.to_owned(),
         nym_contract_cache_state.to_owned(),
+        sphinx_keys_cache_state.to_owned(),
     )
 }
 
@@ -50,6 +54,7 @@ pub(crate) struct NetworkMonitorBuilder<'a> {
     nyxd_client: nyxd::Client,
     node_status_storage: NymApiStorage,
     validator_cache: NymContractCache,
+    sphinx_keys_cache: SharedCache<DescribedMixnodes>,
 }
 
 impl<'a> NetworkMonitorBuilder<'a> {
@@ -58,12 +63,14 @@ impl<'a> NetworkMonitorBuilder<'a> {
         nyxd_client: nyxd::Client,
         node_status_storage: NymApiStorage,
         validator_cache: NymContractCache,
+        sphinx_keys_cache: SharedCache<DescribedMixnodes>,
     ) -> Self {
         NetworkMonitorBuilder {
             config,
             nyxd_client,
             node_status_storage,
             validator_cache,
+            sphinx_keys_cache,
         }
     }
 
@@ -85,6 +92,7 @@ impl<'a> NetworkMonitorBuilder<'a> {
 
         let packet_preparer = new_packet_preparer(
             self.validator_cache,
+            self.sphinx_keys_cache,
             self.config.debug.per_node_test_packets,
             Arc::clone(&ack_key),
             *identity_keypair.public_key(),
@@ -159,6 +167,7 @@ impl<R: MessageReceiver + Send + 'static> NetworkMonitorRunnables<R> {
 
 fn new_packet_preparer(
     validator_cache: NymContractCache,
+    sphinx_keys_cache: SharedCache<DescribedMixnodes>,
     per_node_test_packets: usize,
     ack_key: Arc<AckKey>,
     self_public_identity: identity::PublicKey,
@@ -166,6 +175,7 @@ fn new_packet_preparer(
 ) -> PacketPreparer {
     PacketPreparer::new(
         validator_cache,
+        sphinx_keys_cache,
         per_node_test_packets,
         ack_key,
         self_public_identity,
@@ -219,11 +229,18 @@ fn new_packet_receiver(
 pub(crate) async fn start<R: MessageReceiver + Send + 'static>(
     config: &config::NetworkMonitor,
     nym_contract_cache_state: &NymContractCache,
+    sphinx_keys_cache_state: &SharedCache<DescribedMixnodes>,
     storage: &NymApiStorage,
     nyxd_client: nyxd::Client,
     shutdown: &TaskManager,
 ) {
-    let monitor_builder = setup(config, nym_contract_cache_state, storage, nyxd_client);
+    let monitor_builder = setup(
+        config,
+        nym_contract_cache_state,
+        sphinx_keys_cache_state,
+        storage,
+        nyxd_client,
+    );
     info!("Starting network monitor...");
     let runnables: NetworkMonitorRunnables<R> = monitor_builder.build().await;
     runnables.spawn_tasks(shutdown);
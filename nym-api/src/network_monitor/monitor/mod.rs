@@ -130,11 +130,13 @@ impl<R: MessageReceiver + Send> Monitor<R> {
         for route in routes {
             let mut packet_preparer = self.packet_preparer.clone();
             let route = route.clone();
-            let gateway_packets = packet_preparer.prepare_test_route_viability_packets(
-                &route,
-                self.route_test_packets,
-                self.packet_type,
-            );
+            let gateway_packets = packet_preparer
+                .prepare_test_route_viability_packets(
+                    &route,
+                    self.route_test_packets,
+                    self.packet_type,
+                )
+                .await;
             packets.push(gateway_packets);
         }
 
@@ -3,17 +3,19 @@
 
 use crate::network_monitor::monitor::sender::GatewayPackets;
 use crate::network_monitor::test_route::TestRoute;
+use crate::node_describe_cache::mixnodes::DescribedMixnodes;
 use crate::nym_contract_cache::cache::NymContractCache;
+use crate::support::caching::cache::SharedCache;
 use log::info;
 use nym_crypto::asymmetric::{encryption, identity};
-use nym_mixnet_contract_common::{GatewayBond, Layer, MixNodeBond};
+use nym_mixnet_contract_common::{GatewayBond, Layer, MixId, MixNodeBond};
 use nym_node_tester_utils::node::TestableNode;
 use nym_node_tester_utils::NodeTester;
 use nym_sphinx::acknowledgements::AckKey;
 use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::forwarding::packet::MixPacket;
 use nym_sphinx::params::{PacketSize, PacketType};
-use nym_topology::{gateway, mix};
+use nym_topology::{gateway, mix, sphinx_keys, NymTopology, RotatingSphinxKey};
 use rand_07::{rngs::ThreadRng, seq::SliceRandom, thread_rng, Rng};
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
@@ -71,6 +73,10 @@ pub(crate) struct PreparedPackets {
 pub(crate) struct PacketPreparer {
     validator_cache: NymContractCache,
 
+    /// Rotated sphinx keys announced by the mixnodes. The test packets have to be encrypted
+    /// for them, otherwise the nodes would reject them once their legacy keys get deprecated.
+    sphinx_keys_cache: SharedCache<DescribedMixnodes>,
+
     /// Number of test packets sent to each node
     per_node_test_packets: usize,
 
@@ -86,6 +92,7 @@ pub(crate) struct PacketPreparer {
 impl PacketPreparer {
     pub(crate) fn new(
         validator_cache: NymContractCache,
+        sphinx_keys_cache: SharedCache<DescribedMixnodes>,
         per_node_test_packets: usize,
         ack_key: Arc<AckKey>,
         self_public_identity: identity::PublicKey,
@@ -93,6 +100,7 @@ impl PacketPreparer {
     ) -> Self {
         PacketPreparer {
             validator_cache,
+            sphinx_keys_cache,
             per_node_test_packets,
             ack_key,
             self_public_identity,
@@ -100,16 +108,38 @@ impl PacketPreparer {
         }
     }
 
+    async fn announced_sphinx_keys(&self) -> HashMap<MixId, Vec<RotatingSphinxKey>> {
+        match self.sphinx_keys_cache.get().await {
+            Ok(announced) => sphinx_keys::parse_all_announced_keys(announced.sphinx_keys.values()),
+            Err(_) => {
+                warn!("the mixnode sphinx keys cache hasn't been initialised yet. the bonded keys are going to be used instead");
+                HashMap::new()
+            }
+        }
+    }
+
+    // the keys are attached when the packets are being prepared rather than when the route is created,
+    // as the routes are reused for multiple test runs whilst the keys keep rotating
+    fn route_topology(
+        test_route: &TestRoute,
+        sphinx_keys: &HashMap<MixId, Vec<RotatingSphinxKey>>,
+    ) -> NymTopology {
+        // the topology here contains 3 mixnodes and 1 gateway so its cheap to clone it
+        test_route
+            .topology()
+            .clone()
+            .with_rotating_sphinx_keys(sphinx_keys.clone())
+    }
+
     fn ephemeral_tester(
         &self,
-        test_route: &TestRoute,
+        topology: NymTopology,
         self_address: Option<Recipient>,
     ) -> NodeTester<ThreadRng> {
         let rng = thread_rng();
         NodeTester::new(
             rng,
-            // the topology here contains 3 mixnodes and 1 gateway so its cheap to clone it
-            test_route.topology().clone(),
+            topology,
             self_address,
             PacketSize::RegularPacket,
             DEFAULT_AVERAGE_PACKET_DELAY,
@@ -119,14 +149,18 @@ impl PacketPreparer {
     }
 
     // when we're testing mixnodes, the recipient is going to stay constant, so we can specify it ahead of time
-    fn ephemeral_mix_tester(&self, test_route: &TestRoute) -> NodeTester<ThreadRng> {
+    fn ephemeral_mix_tester(
+        &self,
+        test_route: &TestRoute,
+        topology: NymTopology,
+    ) -> NodeTester<ThreadRng> {
         let self_address = self.create_packet_sender(test_route.gateway());
-        self.ephemeral_tester(test_route, Some(self_address))
+        self.ephemeral_tester(topology, Some(self_address))
     }
 
     #[allow(dead_code)]
-    fn ephemeral_gateway_tester(&self, test_route: &TestRoute) -> NodeTester<ThreadRng> {
-        self.ephemeral_tester(test_route, None)
+    fn ephemeral_gateway_tester(&self, topology: NymTopology) -> NodeTester<ThreadRng> {
+        self.ephemeral_tester(topology, None)
     }
 
     async fn topology_wait_backoff(&self, initialisation_backoff: Duration) {
@@ -295,15 +329,16 @@ impl PacketPreparer {
         )
     }
 
-    pub(crate) fn prepare_test_route_viability_packets(
+    pub(crate) async fn prepare_test_route_viability_packets(
         &mut self,
         route: &TestRoute,
         num: usize,
         // TODO: Maybe do this
         _packet_type: PacketType,
     ) -> GatewayPackets {
-        let mut tester = self.ephemeral_mix_tester(route);
-        let topology = route.topology();
+        let sphinx_keys = self.announced_sphinx_keys().await;
+        let topology = Self::route_topology(route, &sphinx_keys);
+        let mut tester = self.ephemeral_mix_tester(route, topology.clone());
         let plaintexts = route.self_test_messages(num);
 
         // the unwrap here is fine as:
@@ -312,7 +347,7 @@ impl PacketPreparer {
         // 3. the test message is not too long, i.e. when serialized it will fit in a single sphinx packet
         let mix_packets = plaintexts
             .into_iter()
-            .map(|p| tester.wrap_plaintext_data(p, topology, None).unwrap())
+            .map(|p| tester.wrap_plaintext_data(p, &topology, None).unwrap())
             .map(MixPacket::from)
             .collect();
 
@@ -381,6 +416,7 @@ impl PacketPreparer {
 
         let (mixnodes, invalid_mixnodes) = self.filter_outdated_and_malformed_mixnodes(mixnodes);
         let (gateways, invalid_gateways) = self.filter_outdated_and_malformed_gateways(gateways);
+        let sphinx_keys = self.announced_sphinx_keys().await;
 
         let tested_mixnodes = mixnodes.iter().map(|node| node.into()).collect::<Vec<_>>();
         let tested_gateways = gateways.iter().map(|node| node.into()).collect::<Vec<_>>();
@@ -397,7 +433,8 @@ impl PacketPreparer {
             let gateway_address = test_route.gateway_clients_address();
             let gateway_identity = test_route.gateway_identity();
 
-            let mut mix_tester = self.ephemeral_mix_tester(test_route);
+            let mut mix_tester = self
+                .ephemeral_mix_tester(test_route, Self::route_topology(test_route, &sphinx_keys));
 
             // generate test packets for mixnodes
             //
@@ -0,0 +1,48 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node_describe_cache::NodeDescribeCacheError;
+use nym_mixnet_contract_common::{MixId, MixNodeDetails};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const MIXNODE_DESCRIPTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+// type alias for ease of use
+pub type MixnodeLocations = HashMap<MixId, String>;
+
+// we only care about the location out of the whole self-description of the mixnode
+#[derive(Deserialize)]
+struct MixnodeDescription {
+    location: String,
+}
+
+/// Retrieves the location the mixnode has declared in its self-description.
+pub(super) async fn get_mixnode_location(
+    client: &reqwest::Client,
+    mixnode: &MixNodeDetails,
+) -> Result<String, NodeDescribeCacheError> {
+    let mix_id = mixnode.mix_id();
+    let mix_node = &mixnode.bond_information.mix_node;
+
+    // the description is only exposed by the legacy mixnode api
+    let url = format!(
+        "http://{}:{}/description",
+        mix_node.host, mix_node.http_api_port
+    );
+    let description = async {
+        client
+            .get(url)
+            .timeout(MIXNODE_DESCRIPTION_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<MixnodeDescription>()
+            .await
+    }
+    .await
+    .map_err(|source| NodeDescribeCacheError::MixnodeDescriptionFailure { mix_id, source })?;
+
+    Ok(description.location)
+}
@@ -0,0 +1,129 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node_describe_cache::mixnode_locations::{get_mixnode_location, MixnodeLocations};
+use crate::node_describe_cache::sphinx_keys::{
+    get_mixnode_sphinx_keys, AnnouncedMixnodeSphinxKeys,
+};
+use crate::node_describe_cache::NodeDescribeCacheError;
+use crate::nym_contract_cache::cache::NymContractCache;
+use crate::support::caching::cache::SharedCache;
+use crate::support::caching::refresher::{CacheItemProvider, CacheRefresher};
+use crate::support::config;
+use crate::support::config::DEFAULT_NODE_DESCRIBE_BATCH_SIZE;
+use futures::{stream, StreamExt};
+use nym_api_requests::models::MixnodeSphinxKeys;
+use nym_mixnet_contract_common::{MixId, MixNodeDetails};
+
+/// Data the mixnodes have announced about themselves through their http apis.
+#[derive(Default)]
+pub struct DescribedMixnodes {
+    /// Rotated sphinx keys of the mixnodes that have enabled the rotation.
+    pub sphinx_keys: AnnouncedMixnodeSphinxKeys,
+
+    /// Locations the mixnodes have declared in their self-descriptions.
+    pub locations: MixnodeLocations,
+}
+
+struct MixnodeDescription {
+    mix_id: MixId,
+    sphinx_keys: Option<MixnodeSphinxKeys>,
+    location: Option<String>,
+}
+
+/// Queries every bonded mixnode once per refresh for all the data it announces about itself.
+pub struct MixnodeDescriptionProvider {
+    contract_cache: NymContractCache,
+    client: reqwest::Client,
+
+    batch_size: usize,
+}
+
+impl MixnodeDescriptionProvider {
+    pub(crate) fn new(contract_cache: NymContractCache) -> MixnodeDescriptionProvider {
+        MixnodeDescriptionProvider {
+            contract_cache,
+            client: reqwest::Client::new(),
+            batch_size: DEFAULT_NODE_DESCRIBE_BATCH_SIZE,
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+}
+
+async fn describe_mixnode(client: &reqwest::Client, mixnode: MixNodeDetails) -> MixnodeDescription {
+    let (sphinx_keys, location) = tokio::join!(
+        get_mixnode_sphinx_keys(&mixnode),
+        get_mixnode_location(client, &mixnode)
+    );
+
+    MixnodeDescription {
+        mix_id: mixnode.mix_id(),
+        sphinx_keys: sphinx_keys
+            .map_err(|err| {
+                // most mixnodes simply don't rotate their keys (yet)
+                trace!("{err}")
+            })
+            .ok(),
+        location: location.map_err(|err| debug!("{err}")).ok(),
+    }
+}
+
+#[async_trait]
+impl CacheItemProvider for MixnodeDescriptionProvider {
+    type Item = DescribedMixnodes;
+    type Error = NodeDescribeCacheError;
+
+    async fn wait_until_ready(&self) {
+        self.contract_cache.wait_for_initial_values().await
+    }
+
+    async fn try_refresh(&self) -> Result<Self::Item, Self::Error> {
+        let mixnodes = self.contract_cache.mixnodes_filtered().await;
+        if mixnodes.is_empty() {
+            return Ok(DescribedMixnodes::default());
+        }
+
+        let descriptions = stream::iter(
+            mixnodes
+                .into_iter()
+                .map(|mixnode| describe_mixnode(&self.client, mixnode)),
+        )
+        .buffer_unordered(self.batch_size)
+        .collect::<Vec<_>>()
+        .await;
+
+        let mut described = DescribedMixnodes::default();
+        for description in descriptions {
+            if let Some(sphinx_keys) = description.sphinx_keys {
+                described
+                    .sphinx_keys
+                    .insert(description.mix_id, sphinx_keys);
+            }
+            if let Some(location) = description.location {
+                described.locations.insert(description.mix_id, location);
+            }
+        }
+
+        Ok(described)
+    }
+}
+
+pub(crate) fn new_refresher_with_initial_value(
+    config: &config::TopologyCacher,
+    contract_cache: NymContractCache,
+    initial: SharedCache<DescribedMixnodes>,
+) -> CacheRefresher<DescribedMixnodes, NodeDescribeCacheError> {
+    CacheRefresher::new_with_initial_value(
+        Box::new(
+            MixnodeDescriptionProvider::new(contract_cache)
+                .with_batch_size(config.debug.node_describe_batch_size),
+        ),
+        config.debug.node_describe_caching_interval,
+        initial,
+    )
+}
@@ -12,11 +12,15 @@ use nym_api_requests::models::{
 };
 use nym_config::defaults::{mainnet, DEFAULT_NYM_NODE_HTTP_PORT};
 use nym_contracts_common::IdentityKey;
-use nym_mixnet_contract_common::Gateway;
+use nym_mixnet_contract_common::{Gateway, MixId};
 use nym_node_requests::api::client::{NymNodeApiClientError, NymNodeApiClientExt};
 use std::collections::HashMap;
 use thiserror::Error;
 
+pub(crate) mod mixnode_locations;
+pub(crate) mod mixnodes;
+pub(crate) mod sphinx_keys;
+
 // type alias for ease of use
 pub type DescribedNodes = HashMap<IdentityKey, NymNodeDescription>;
 
@@ -52,6 +56,35 @@ pub enum NodeDescribeCacheError {
     // TODO: perhaps include more details here like whether key/signature/payload was malformed
     #[error("could not verify signed host information for gateway '{gateway}'")]
     MissignedHostInformation { gateway: IdentityKey },
+
+    #[error("mixnode {mix_id} has provided malformed host information ({host}: {source}")]
+    MalformedMixnodeHost {
+        host: String,
+
+        mix_id: MixId,
+
+        #[source]
+        source: NymNodeApiClientError,
+    },
+
+    #[error("failed to query mixnode {mix_id}: {source}")]
+    MixnodeApiFailure {
+        mix_id: MixId,
+
+        #[source]
+        source: NymNodeApiClientError,
+    },
+
+    #[error("could not verify signed sphinx keys of mixnode {mix_id}")]
+    MissignedSphinxKeys { mix_id: MixId },
+
+    #[error("failed to retrieve the description of mixnode {mix_id}: {source}")]
+    MixnodeDescriptionFailure {
+        mix_id: MixId,
+
+        #[source]
+        source: reqwest::Error,
+    },
 }
 
 pub struct NodeDescriptionProvider {
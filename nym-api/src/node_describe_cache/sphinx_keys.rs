@@ -0,0 +1,50 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node_describe_cache::NodeDescribeCacheError;
+use nym_api_requests::models::MixnodeSphinxKeys;
+use nym_crypto::asymmetric::identity;
+use nym_mixnet_contract_common::{MixId, MixNodeDetails};
+use nym_node_requests::api::client::NymNodeApiClientExt;
+use std::collections::HashMap;
+
+// type alias for ease of use
+pub type AnnouncedMixnodeSphinxKeys = HashMap<MixId, MixnodeSphinxKeys>;
+
+/// Retrieves the rotated sphinx keys announced by the mixnode, if it has enabled the rotation.
+pub(super) async fn get_mixnode_sphinx_keys(
+    mixnode: &MixNodeDetails,
+) -> Result<MixnodeSphinxKeys, NodeDescribeCacheError> {
+    let mix_id = mixnode.mix_id();
+    let mix_node = &mixnode.bond_information.mix_node;
+
+    let address = format!("http://{}:{}", mix_node.host, mix_node.http_api_port);
+    let client = nym_node_requests::api::Client::new_url(address, None).map_err(|err| {
+        NodeDescribeCacheError::MalformedMixnodeHost {
+            host: mix_node.host.clone(),
+            mix_id,
+            source: err,
+        }
+    })?;
+
+    let signed_keys = client.get_sphinx_keys().await.map_err(|err| {
+        NodeDescribeCacheError::MixnodeApiFailure {
+            mix_id,
+            source: err,
+        }
+    })?;
+
+    // the keys must have been signed by the bonded identity of the node
+    let Ok(identity_key) = identity::PublicKey::from_base58_string(&mix_node.identity_key) else {
+        return Err(NodeDescribeCacheError::MissignedSphinxKeys { mix_id });
+    };
+    if !signed_keys.verify(&identity_key) {
+        return Err(NodeDescribeCacheError::MissignedSphinxKeys { mix_id });
+    }
+
+    Ok(MixnodeSphinxKeys {
+        mix_id,
+        identity_key: mix_node.identity_key.clone(),
+        keys: signed_keys.data.keys,
+    })
+}
@@ -80,8 +80,13 @@ impl NodeStatusCache {
         &self,
         fn_arg: impl FnOnce(RwLockReadGuard<'_, NodeStatusCacheData>) -> Cache<T>,
     ) -> Option<Cache<T>> {
+        self.read().await.map(fn_arg)
+    }
+
+    /// Gives access to the current cache data without copying it.
+    pub(crate) async fn read(&self) -> Option<RwLockReadGuard<'_, NodeStatusCacheData>> {
         match time::timeout(Duration::from_millis(CACHE_TIMEOUT_MS), self.inner.read()).await {
-            Ok(cache) => Some(fn_arg(cache)),
+            Ok(cache) => Some(cache),
             Err(e) => {
                 error!("{e}");
                 None
@@ -20,6 +20,7 @@ pub(crate) mod local_guard;
 pub(crate) mod models;
 pub(crate) mod reward_estimate;
 pub(crate) mod routes;
+pub(crate) mod routes_v2;
 pub(crate) mod uptime_updater;
 pub(crate) mod utils;
 
@@ -69,6 +70,20 @@ pub(crate) fn node_status_routes(
     }
 }
 
+pub(crate) fn node_status_routes_v2(
+    settings: &OpenApiSettings,
+    enabled: bool,
+) -> (Vec<Route>, OpenApi) {
+    if enabled {
+        openapi_get_routes_spec![
+            settings: routes_v2::get_mixnodes_detailed_paginated,
+            routes_v2::get_gateways_detailed_paginated,
+        ]
+    } else {
+        openapi_get_routes_spec![settings: routes_v2::get_mixnodes_detailed_paginated]
+    }
+}
+
 /// Spawn the node status cache refresher.
 ///
 /// It is primarily refreshed in-sync with the nym contract cache, however provide a fallback
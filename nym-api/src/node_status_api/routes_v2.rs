@@ -0,0 +1,173 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::NodeStatusCache;
+use crate::node_describe_cache::mixnodes::DescribedMixnodes;
+use crate::node_status_api::models::ErrorResponse;
+use crate::support::caching::cache::SharedCache;
+use crate::support::http::etag::{ConditionalRequest, ETagged};
+use crate::support::http::filters::{
+    country_matches, parse_layer, parse_min_performance, version_matches,
+};
+use crate::support::http::pagination::{paginate, parse_cursor, FieldSelection};
+use nym_api_requests::pagination::PaginatedResponse;
+use nym_mixnet_contract_common::MixId;
+use rocket::http::Status;
+use rocket::State;
+use rocket_okapi::openapi;
+use serde_json::Value;
+
+/// Returns a page of the annotated mixnodes (including the blacklisted ones unless `blacklisted=false`
+/// is specified), ordered by their mix ids.
+///
+/// - `cursor`: the `next_cursor` returned alongside the previous page
+/// - `limit`: maximum number of nodes returned (100 by default, at most 1000)
+/// - `layer`: only return mixnodes assigned to this layer
+/// - `version`: only return mixnodes running this version (or version prefix, such as '1.1')
+/// - `country`: only return mixnodes whose self-described location matches this value (case-insensitive)
+/// - `min_performance`: only return mixnodes whose performance over the last 24h is at least this
+///   percentage value
+/// - `blacklisted`: only return blacklisted (or non-blacklisted) mixnodes
+/// - `fields`: comma-separated list of the top-level fields to include for each mixnode
+#[openapi(tag = "status")]
+#[allow(clippy::too_many_arguments)]
+#[get(
+    "/mixnodes/detailed?<cursor>&<limit>&<layer>&<version>&<country>&<min_performance>&<blacklisted>&<fields>"
+)]
+pub async fn get_mixnodes_detailed_paginated(
+    cache: &State<NodeStatusCache>,
+    described_mixnodes_cache: &State<SharedCache<DescribedMixnodes>>,
+    request: ConditionalRequest,
+    cursor: Option<&str>,
+    limit: Option<u32>,
+    layer: Option<u8>,
+    version: Option<&str>,
+    country: Option<&str>,
+    min_performance: Option<u8>,
+    blacklisted: Option<bool>,
+    fields: Option<&str>,
+) -> Result<ETagged<PaginatedResponse<Value>>, ErrorResponse> {
+    let cursor = parse_cursor::<MixId>(cursor)?;
+    let layer = parse_layer(layer)?;
+    let min_performance = parse_min_performance(min_performance)?;
+
+    let Some(cache) = cache.read().await else {
+        return Err(cache_unavailable());
+    };
+    let described_mixnodes = described_mixnodes_cache.get().await.ok();
+
+    // the response can only change once either of the caches gets refreshed
+    let etag = request.etag(&[
+        cache.mixnodes_annotated.timestamp(),
+        described_mixnodes
+            .as_ref()
+            .map(|d| d.timestamp())
+            .unwrap_or_default(),
+    ]);
+    if request.matches(&etag) {
+        return Ok(ETagged::NotModified { etag });
+    }
+
+    let mixnodes = cache
+        .mixnodes_annotated
+        .iter()
+        .filter(|m| {
+            layer.map_or(true, |layer| {
+                m.mixnode_details.bond_information.layer == layer
+            })
+        })
+        .filter(|m| {
+            version.map_or(true, |version| {
+                version_matches(&m.mix_node().version, version)
+            })
+        })
+        .filter(|m| {
+            country.map_or(true, |country| {
+                described_mixnodes
+                    .as_ref()
+                    .and_then(|described| described.locations.get(&m.mix_id()))
+                    .is_some_and(|location| country_matches(location, country))
+            })
+        })
+        .filter(|m| {
+            min_performance.map_or(true, |performance| {
+                m.node_performance.last_24h >= performance
+            })
+        })
+        .filter(|m| blacklisted.map_or(true, |blacklisted| m.blacklisted == blacklisted))
+        .collect();
+
+    let page = paginate(mixnodes, |m| m.mix_id(), cursor, limit);
+    ETagged::new(&FieldSelection::new(fields).apply(page)?, etag)
+}
+
+/// Returns a page of the annotated gateways (including the blacklisted ones unless `blacklisted=false`
+/// is specified), ordered by their identity keys.
+///
+/// - `cursor`: the `next_cursor` returned alongside the previous page
+/// - `limit`: maximum number of gateways returned (100 by default, at most 1000)
+/// - `version`: only return gateways running this version (or version prefix, such as '1.1')
+/// - `country`: only return gateways whose declared location matches this value (case-insensitive)
+/// - `min_performance`: only return gateways whose performance over the last 24h is at least this
+///   percentage value
+/// - `blacklisted`: only return blacklisted (or non-blacklisted) gateways
+/// - `fields`: comma-separated list of the top-level fields to include for each gateway
+#[openapi(tag = "status")]
+#[allow(clippy::too_many_arguments)]
+#[get(
+    "/gateways/detailed?<cursor>&<limit>&<version>&<country>&<min_performance>&<blacklisted>&<fields>"
+)]
+pub async fn get_gateways_detailed_paginated(
+    cache: &State<NodeStatusCache>,
+    request: ConditionalRequest,
+    cursor: Option<String>,
+    limit: Option<u32>,
+    version: Option<&str>,
+    country: Option<&str>,
+    min_performance: Option<u8>,
+    blacklisted: Option<bool>,
+    fields: Option<&str>,
+) -> Result<ETagged<PaginatedResponse<Value>>, ErrorResponse> {
+    let min_performance = parse_min_performance(min_performance)?;
+
+    let Some(cache) = cache.read().await else {
+        return Err(cache_unavailable());
+    };
+
+    // the response can only change once the cache gets refreshed
+    let etag = request.etag(&[cache.gateways_annotated.timestamp()]);
+    if request.matches(&etag) {
+        return Ok(ETagged::NotModified { etag });
+    }
+
+    let gateways = cache
+        .gateways_annotated
+        .iter()
+        .filter(|g| {
+            version.map_or(true, |version| {
+                version_matches(&g.gateway_bond.gateway.version, version)
+            })
+        })
+        .filter(|g| {
+            country.map_or(true, |country| {
+                country_matches(&g.gateway_bond.gateway.location, country)
+            })
+        })
+        .filter(|g| {
+            min_performance.map_or(true, |performance| {
+                g.node_performance.last_24h >= performance
+            })
+        })
+        .filter(|g| blacklisted.map_or(true, |blacklisted| g.blacklisted == blacklisted))
+        .collect();
+
+    let page = paginate(gateways, |g| g.identity().clone(), cursor, limit);
+    ETagged::new(&FieldSelection::new(fields).apply(page)?, etag)
+}
+
+fn cache_unavailable() -> ErrorResponse {
+    ErrorResponse::new(
+        "the node status cache is currently unavailable",
+        Status::ServiceUnavailable,
+    )
+}
@@ -0,0 +1,196 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use nym_api_requests::events::NetworkEvent;
+use nym_mixnet_contract_common::{GatewayBond, IdentityKey, Interval, MixId, MixNodeDetails};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::hash::Hash;
+
+/// Maximum number of events buffered for each subscriber before it's considered to be lagging.
+pub(crate) const NETWORK_EVENTS_CHANNEL_CAPACITY: usize = 1024;
+
+/// Minimal view of the cached contract data that's relevant for detecting the network changes.
+#[derive(Default)]
+pub(crate) struct NetworkSnapshot {
+    mixnodes: BTreeMap<MixId, IdentityKey>,
+    gateways: BTreeSet<IdentityKey>,
+    rewarded_set: BTreeSet<MixId>,
+    active_set: BTreeSet<MixId>,
+    interval: Option<Interval>,
+}
+
+impl NetworkSnapshot {
+    pub(crate) fn new(
+        mixnodes: &[MixNodeDetails],
+        gateways: &[GatewayBond],
+        rewarded_set: &[MixNodeDetails],
+        active_set: &[MixNodeDetails],
+        interval: Option<Interval>,
+    ) -> Self {
+        NetworkSnapshot {
+            mixnodes: mixnodes
+                .iter()
+                .map(|m| (m.mix_id(), m.bond_information.identity().to_owned()))
+                .collect(),
+            gateways: gateways.iter().map(|g| g.identity().clone()).collect(),
+            rewarded_set: rewarded_set.iter().map(|m| m.mix_id()).collect(),
+            active_set: active_set.iter().map(|m| m.mix_id()).collect(),
+            interval,
+        }
+    }
+
+    /// Returns all the changes that happened between the two snapshots.
+    pub(crate) fn changes_since(&self, old: &NetworkSnapshot) -> Vec<NetworkEvent> {
+        let mut events = Vec::new();
+
+        if let Some(interval) = self.interval {
+            let epoch_changed = old.interval.map_or(true, |old| {
+                old.current_epoch_absolute_id() != interval.current_epoch_absolute_id()
+            });
+            if epoch_changed {
+                events.push(NetworkEvent::EpochTransition { interval })
+            }
+        }
+
+        if self.rewarded_set != old.rewarded_set || self.active_set != old.active_set {
+            events.push(NetworkEvent::RewardedSetChanged {
+                rewarded_set: self.rewarded_set.iter().copied().collect(),
+                active_set: self.active_set.iter().copied().collect(),
+            })
+        }
+
+        for (mix_id, identity_key) in &self.mixnodes {
+            if !old.mixnodes.contains_key(mix_id) {
+                events.push(NetworkEvent::MixnodeBonded {
+                    mix_id: *mix_id,
+                    identity_key: identity_key.clone(),
+                })
+            }
+        }
+        for (mix_id, identity_key) in &old.mixnodes {
+            if !self.mixnodes.contains_key(mix_id) {
+                events.push(NetworkEvent::MixnodeUnbonded {
+                    mix_id: *mix_id,
+                    identity_key: identity_key.clone(),
+                })
+            }
+        }
+
+        for identity_key in self.gateways.difference(&old.gateways) {
+            events.push(NetworkEvent::GatewayBonded {
+                identity_key: identity_key.clone(),
+            })
+        }
+        for identity_key in old.gateways.difference(&self.gateways) {
+            events.push(NetworkEvent::GatewayUnbonded {
+                identity_key: identity_key.clone(),
+            })
+        }
+
+        events
+    }
+}
+
+/// Returns the entries that have been added to and removed from the blacklist, respectively.
+pub(crate) fn blacklist_changes<T: Eq + Hash + Clone>(
+    old: &HashSet<T>,
+    new: &HashSet<T>,
+) -> (Vec<T>, Vec<T>) {
+    (
+        new.difference(old).cloned().collect(),
+        old.difference(new).cloned().collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(absolute_epoch: u32) -> Interval {
+        serde_json::from_value(serde_json::json!({
+            "id": absolute_epoch / 720,
+            "epochs_in_interval": 720,
+            "current_epoch_start": "2024-01-01T00:00:00Z",
+            "current_epoch_id": absolute_epoch % 720,
+            "epoch_length": { "secs": 3600, "nanos": 0 },
+            "total_elapsed_epochs": absolute_epoch,
+        }))
+        .unwrap()
+    }
+
+    fn snapshot(
+        mixnodes: &[MixId],
+        gateways: &[&str],
+        rewarded_set: &[MixId],
+        active_set: &[MixId],
+        epoch: u32,
+    ) -> NetworkSnapshot {
+        NetworkSnapshot {
+            mixnodes: mixnodes
+                .iter()
+                .map(|mix_id| (*mix_id, format!("mix{mix_id}")))
+                .collect(),
+            gateways: gateways.iter().map(ToString::to_string).collect(),
+            rewarded_set: rewarded_set.iter().copied().collect(),
+            active_set: active_set.iter().copied().collect(),
+            interval: Some(interval(epoch)),
+        }
+    }
+
+    #[test]
+    fn no_changes() {
+        let old = snapshot(&[1, 2, 3], &["gw1"], &[1, 2], &[1], 42);
+        let new = snapshot(&[1, 2, 3], &["gw1"], &[1, 2], &[1], 42);
+        assert!(new.changes_since(&old).is_empty());
+    }
+
+    #[test]
+    fn epoch_transition_with_new_rewarded_set() {
+        let old = snapshot(&[1, 2, 3], &["gw1"], &[1, 2], &[1], 42);
+        let new = snapshot(&[1, 2, 3], &["gw1"], &[2, 3], &[2], 43);
+        assert_eq!(
+            new.changes_since(&old),
+            vec![
+                NetworkEvent::EpochTransition {
+                    interval: interval(43)
+                },
+                NetworkEvent::RewardedSetChanged {
+                    rewarded_set: vec![2, 3],
+                    active_set: vec![2],
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn bonded_and_unbonded_nodes() {
+        let old = snapshot(&[1, 2], &["gw1", "gw2"], &[], &[], 42);
+        let new = snapshot(&[2, 3], &["gw2", "gw3"], &[], &[], 42);
+        assert_eq!(
+            new.changes_since(&old),
+            vec![
+                NetworkEvent::MixnodeBonded {
+                    mix_id: 3,
+                    identity_key: "mix3".to_string()
+                },
+                NetworkEvent::MixnodeUnbonded {
+                    mix_id: 1,
+                    identity_key: "mix1".to_string()
+                },
+                NetworkEvent::GatewayBonded {
+                    identity_key: "gw3".to_string()
+                },
+                NetworkEvent::GatewayUnbonded {
+                    identity_key: "gw1".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_blacklist() {
+        let old = HashSet::from([1, 2]);
+        let new = HashSet::from([2, 3]);
+        assert_eq!(blacklist_changes(&old, &new), (vec![3], vec![1]));
+    }
+}
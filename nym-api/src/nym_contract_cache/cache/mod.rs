@@ -4,6 +4,8 @@
 use crate::nym_contract_cache::cache::data::CachedContractsInfo;
 use crate::support::caching::Cache;
 use data::ValidatorCacheData;
+use events::{blacklist_changes, NetworkSnapshot, NETWORK_EVENTS_CHANNEL_CAPACITY};
+use nym_api_requests::events::NetworkEvent;
 use nym_api_requests::models::MixnodeStatus;
 use nym_mixnet_contract_common::{
     families::FamilyHead, GatewayBond, IdentityKey, Interval, MixId, MixNodeBond, MixNodeDetails,
@@ -20,16 +22,20 @@ use std::{
     },
     time::Duration,
 };
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock, RwLockReadGuard};
 use tokio::time;
 
 mod data;
+mod events;
 pub(crate) mod refresher;
 
 #[derive(Clone)]
 pub struct NymContractCache {
     pub(crate) initialised: Arc<AtomicBool>,
     pub(crate) inner: Arc<RwLock<ValidatorCacheData>>,
+
+    // Notify the subscribers about any changes to the network observed while updating the cache
+    events: broadcast::Sender<NetworkEvent>,
 }
 
 impl NymContractCache {
@@ -37,6 +43,21 @@ impl NymContractCache {
         NymContractCache {
             initialised: Arc::new(AtomicBool::new(false)),
             inner: Arc::new(RwLock::new(ValidatorCacheData::new())),
+            events: broadcast::channel(NETWORK_EVENTS_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Subscribe to changes of the network, such as epoch transitions, changes to the rewarded set
+    /// or nodes getting bonded, unbonded or blacklisted.
+    pub(crate) fn subscribe_to_events(&self) -> broadcast::Receiver<NetworkEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish_events(&self, events: Vec<NetworkEvent>) {
+        for event in events {
+            debug!("network event: {event:?}");
+            // an error only means there are no subscribers at the moment
+            let _ = self.events.send(event);
         }
     }
 
@@ -60,8 +81,30 @@ impl NymContractCache {
         names: Option<Vec<RegisteredName>>,
         nym_contracts_info: CachedContractsInfo,
     ) {
+        // don't announce the entire network as new when populating the cache for the first time
+        let new_snapshot = self.initialised().then(|| {
+            NetworkSnapshot::new(
+                &mixnodes,
+                &gateways,
+                &rewarded_set,
+                &active_set,
+                Some(current_interval),
+            )
+        });
+
         match time::timeout(Duration::from_millis(100), self.inner.write()).await {
             Ok(mut cache) => {
+                let events = new_snapshot.map(|new_snapshot| {
+                    let old_snapshot = NetworkSnapshot::new(
+                        &cache.mixnodes,
+                        &cache.gateways,
+                        &cache.rewarded_set,
+                        &cache.active_set,
+                        *cache.current_interval,
+                    );
+                    new_snapshot.changes_since(&old_snapshot)
+                });
+
                 cache.mixnodes.unchecked_update(mixnodes);
                 cache.gateways.unchecked_update(gateways);
                 cache.rewarded_set.unchecked_update(rewarded_set);
@@ -80,7 +123,10 @@ impl NymContractCache {
                 cache
                     .registered_names
                     .unchecked_update(names.unwrap_or_default());
-                cache.contracts_info.unchecked_update(nym_contracts_info)
+                cache.contracts_info.unchecked_update(nym_contracts_info);
+                drop(cache);
+
+                self.publish_events(events.unwrap_or_default());
             }
             Err(err) => {
                 error!("{err}");
@@ -120,7 +166,28 @@ impl NymContractCache {
         }
         match time::timeout(Duration::from_millis(100), self.inner.write()).await {
             Ok(mut cache) => {
+                // the blacklist starts off empty, so don't announce every single blacklisted node
+                // when it's populated for the first time (the default cache has never been updated)
+                let populated = cache.mixnodes_blacklist.timestamp() != 0;
+                let (added, removed) = blacklist_changes(&cache.mixnodes_blacklist, &blacklist);
                 cache.mixnodes_blacklist.unchecked_update(blacklist);
+                drop(cache);
+                if !populated {
+                    return;
+                }
+
+                let events = added
+                    .into_iter()
+                    .map(|mix_id| (mix_id, true))
+                    .chain(removed.into_iter().map(|mix_id| (mix_id, false)))
+                    .map(
+                        |(mix_id, blacklisted)| NetworkEvent::MixnodeBlacklistChanged {
+                            mix_id,
+                            blacklisted,
+                        },
+                    )
+                    .collect();
+                self.publish_events(events);
             }
             Err(err) => {
                 error!("Failed to update mixnodes blacklist: {err}");
@@ -147,7 +214,32 @@ impl NymContractCache {
         }
         match time::timeout(Duration::from_millis(100), self.inner.write()).await {
             Ok(mut cache) => {
+                // the blacklist starts off empty, so don't announce every single blacklisted node
+                // when it's populated for the first time (the default cache has never been updated)
+                let populated = cache.gateways_blacklist.timestamp() != 0;
+                let (added, removed) = blacklist_changes(&cache.gateways_blacklist, &blacklist);
                 cache.gateways_blacklist.unchecked_update(blacklist);
+                drop(cache);
+                if !populated {
+                    return;
+                }
+
+                let events = added
+                    .into_iter()
+                    .map(|identity_key| (identity_key, true))
+                    .chain(
+                        removed
+                            .into_iter()
+                            .map(|identity_key| (identity_key, false)),
+                    )
+                    .map(
+                        |(identity_key, blacklisted)| NetworkEvent::GatewayBlacklistChanged {
+                            identity_key,
+                            blacklisted,
+                        },
+                    )
+                    .collect();
+                self.publish_events(events);
             }
             Err(err) => {
                 error!("Failed to update gateways blacklist: {err}");
@@ -216,6 +308,17 @@ impl NymContractCache {
         }
     }
 
+    /// Gives access to the current cache data without copying it.
+    pub(crate) async fn read(&self) -> Option<RwLockReadGuard<'_, ValidatorCacheData>> {
+        match time::timeout(Duration::from_millis(100), self.inner.read()).await {
+            Ok(cache) => Some(cache),
+            Err(err) => {
+                error!("{err}");
+                None
+            }
+        }
+    }
+
     pub async fn gateways_all(&self) -> Vec<GatewayBond> {
         match time::timeout(Duration::from_millis(100), self.inner.read()).await {
             Ok(cache) => cache.gateways.clone(),
@@ -32,6 +32,11 @@ pub(crate) fn nym_contract_cache_routes(settings: &OpenApiSettings) -> (Vec<Rout
     ]
 }
 
+/// Routes that can't be described by the openapi spec and thus have to be mounted separately
+pub(crate) fn nym_contract_cache_stream_routes() -> Vec<Route> {
+    routes![routes::network_events]
+}
+
 pub(crate) fn start_refresher(
     config: &config::NodeStatusAPI,
     nym_contract_cache_state: &NymContractCache,
@@ -8,6 +8,7 @@ use crate::{
     },
     nym_contract_cache::cache::NymContractCache,
 };
+use nym_api_requests::events::{NetworkEvent, NETWORK_EVENTS_HEARTBEAT_INTERVAL};
 use nym_api_requests::models::MixNodeBondAnnotated;
 use nym_mixnet_contract_common::{
     mixnode::MixNodeDetails, reward_params::RewardingParams, GatewayBond, Interval, MixId,
@@ -15,9 +16,11 @@ use nym_mixnet_contract_common::{
 
 use nym_name_service_common::response::NamesListResponse;
 use nym_service_provider_directory_common::response::ServicesListResponse;
-use rocket::{serde::json::Json, State};
+use rocket::response::stream::{Event, EventStream};
+use rocket::{serde::json::Json, Shutdown, State};
 use rocket_okapi::openapi;
 use std::collections::HashSet;
+use tokio::sync::broadcast::error::RecvError;
 
 #[openapi(tag = "contract-cache")]
 #[get("/mixnodes")]
@@ -141,3 +144,26 @@ pub async fn get_registered_names(cache: &State<NymContractCache>) -> Json<Names
     let names = cache.names().await.clone();
     Json(names.as_slice().into())
 }
+
+/// Server-sent events stream of the changes to the network topology and the epoch state,
+/// so that the consumers wouldn't have to repeatedly poll the other endpoints.
+// note: it's not part of the openapi spec as okapi can't describe event streams
+#[get("/events")]
+pub fn network_events(cache: &State<NymContractCache>, mut shutdown: Shutdown) -> EventStream![] {
+    let mut events = cache.subscribe_to_events();
+    let stream = EventStream! {
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => event,
+                    Err(RecvError::Closed) => break,
+                    // we have missed some events, so tell the subscriber to get the current state instead
+                    Err(RecvError::Lagged(_)) => NetworkEvent::Resync,
+                },
+                _ = &mut shutdown => break,
+            };
+            yield Event::json(&event).event(event.name());
+        }
+    };
+    stream.heartbeat(NETWORK_EVENTS_HEARTBEAT_INTERVAL)
+}
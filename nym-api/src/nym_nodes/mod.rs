@@ -7,10 +7,17 @@ use rocket_okapi::openapi_get_routes_spec;
 use rocket_okapi::settings::OpenApiSettings;
 
 pub(crate) mod routes;
+pub(crate) mod routes_v2;
 
 /// Merges the routes with http information and returns it to Rocket for serving
 pub(crate) fn nym_node_routes(settings: &OpenApiSettings) -> (Vec<Route>, OpenApi) {
     openapi_get_routes_spec![
-        settings: routes::get_gateways_described
+        settings: routes::get_gateways_described,
+        routes::get_mixnodes_sphinx_keys,
     ]
 }
+
+/// Merges the v2 routes with http information and returns it to Rocket for serving
+pub(crate) fn nym_node_routes_v2(settings: &OpenApiSettings) -> (Vec<Route>, OpenApi) {
+    openapi_get_routes_spec![settings: routes_v2::get_gateways_described_paginated]
+}
@@ -1,10 +1,11 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: GPL-3.0-only
 
+use crate::node_describe_cache::mixnodes::DescribedMixnodes;
 use crate::node_describe_cache::DescribedNodes;
 use crate::nym_contract_cache::cache::NymContractCache;
 use crate::support::caching::cache::SharedCache;
-use nym_api_requests::models::DescribedGateway;
+use nym_api_requests::models::{DescribedGateway, MixnodeSphinxKeys};
 use rocket::serde::json::Json;
 use rocket::State;
 use rocket_okapi::openapi;
@@ -42,3 +43,31 @@ pub async fn get_gateways_described(
             .collect(),
     )
 }
+
+/// Returns the rotated sphinx keys announced by the mixnodes. The signatures of all the keys have been
+/// verified against the bonded identities. Mixnodes that do not rotate their keys are not included.
+#[openapi(tag = "Nym Nodes")]
+#[get("/mixnodes/sphinx-keys")]
+pub async fn get_mixnodes_sphinx_keys(
+    contract_cache: &State<NymContractCache>,
+    described_mixnodes_cache: &State<SharedCache<DescribedMixnodes>>,
+) -> Json<Vec<MixnodeSphinxKeys>> {
+    let Ok(described_mixnodes) = described_mixnodes_cache.get().await else {
+        return Json(Vec::new());
+    };
+
+    // only return the keys of the nodes that are still bonded
+    Json(
+        contract_cache
+            .mixnodes_filtered()
+            .await
+            .into_iter()
+            .filter_map(|mixnode| {
+                described_mixnodes
+                    .sphinx_keys
+                    .get(&mixnode.mix_id())
+                    .cloned()
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,88 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node_describe_cache::DescribedNodes;
+use crate::node_status_api::models::ErrorResponse;
+use crate::nym_contract_cache::cache::NymContractCache;
+use crate::support::caching::cache::SharedCache;
+use crate::support::http::etag::{ConditionalRequest, ETagged};
+use crate::support::http::filters::{country_matches, version_matches};
+use crate::support::http::pagination::{paginate, FieldSelection};
+use nym_api_requests::models::DescribedGateway;
+use nym_api_requests::pagination::PaginatedResponse;
+use rocket::http::Status;
+use rocket::State;
+use rocket_okapi::openapi;
+use serde_json::Value;
+
+/// Returns a page of the (non-blacklisted) gateways alongside their self-described data,
+/// ordered by their identity keys.
+///
+/// - `cursor`: the `next_cursor` returned alongside the previous page
+/// - `limit`: maximum number of gateways returned (100 by default, at most 1000)
+/// - `version`: only return gateways running this version (or version prefix, such as '1.1')
+/// - `country`: only return gateways whose declared location matches this value (case-insensitive)
+/// - `fields`: comma-separated list of the top-level fields to include for each gateway
+#[openapi(tag = "Nym Nodes")]
+#[get("/gateways/described?<cursor>&<limit>&<version>&<country>&<fields>")]
+pub async fn get_gateways_described_paginated(
+    contract_cache: &State<NymContractCache>,
+    describe_cache: &State<SharedCache<DescribedNodes>>,
+    request: ConditionalRequest,
+    cursor: Option<String>,
+    limit: Option<u32>,
+    version: Option<&str>,
+    country: Option<&str>,
+    fields: Option<&str>,
+) -> Result<ETagged<PaginatedResponse<Value>>, ErrorResponse> {
+    let Some(contract_cache) = contract_cache.read().await else {
+        return Err(ErrorResponse::new(
+            "the contract cache is currently unavailable",
+            Status::ServiceUnavailable,
+        ));
+    };
+    let self_descriptions = describe_cache.get().await.ok();
+
+    // the response can only change once either of the caches gets refreshed
+    let etag = request.etag(&[
+        contract_cache.gateways.timestamp(),
+        contract_cache.gateways_blacklist.timestamp(),
+        self_descriptions
+            .as_ref()
+            .map(|d| d.timestamp())
+            .unwrap_or_default(),
+    ]);
+    if request.matches(&etag) {
+        return Ok(ETagged::NotModified { etag });
+    }
+
+    let gateways = contract_cache
+        .gateways
+        .iter()
+        .filter(|g| !contract_cache.gateways_blacklist.contains(g.identity()))
+        .filter(|g| version.map_or(true, |version| version_matches(&g.gateway.version, version)))
+        .filter(|g| {
+            country.map_or(true, |country| {
+                country_matches(&g.gateway.location, country)
+            })
+        })
+        .collect();
+
+    // only attach the self-described data to the gateways that are actually going to be returned
+    let page = paginate(gateways, |g| g.identity().clone(), cursor, limit);
+    let page = PaginatedResponse {
+        pagination: page.pagination,
+        data: page
+            .data
+            .into_iter()
+            .map(|bond| DescribedGateway {
+                self_described: self_descriptions
+                    .as_ref()
+                    .and_then(|descriptions| descriptions.get(bond.identity()).cloned()),
+                bond: bond.clone(),
+            })
+            .collect(),
+    };
+
+    ETagged::new(&FieldSelection::new(fields).apply(page)?, etag)
+}
@@ -0,0 +1,205 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node_status_api::models::ErrorResponse;
+use okapi::openapi3::{Object, Parameter, ParameterValue, Responses};
+use rocket::http::{ContentType, Header, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::Json;
+use rocket::Request;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+use rocket_okapi::response::OpenApiResponderInner;
+use rocket_okapi::util::ensure_status_code_exists;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::io::Cursor;
+use std::marker::PhantomData;
+
+const ETAG: &str = "ETag";
+const IF_NONE_MATCH: &str = "If-None-Match";
+
+/// Request guard extracting the entity tags from the `If-None-Match` header, if any, alongside
+/// the requested uri, i.e. the route with all of its query parameters.
+pub(crate) struct ConditionalRequest {
+    if_none_match: Option<String>,
+    uri: String,
+}
+
+impl ConditionalRequest {
+    /// Derives the ETag of the requested data from the time(s) the underlying cache(s) got last
+    /// updated, so that it could be checked before the cached data is even looked at.
+    pub(crate) fn etag(&self, cache_timestamps: &[i64]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.uri.as_bytes());
+        for timestamp in cache_timestamps {
+            hasher.update(timestamp.to_be_bytes());
+        }
+        format!("\"{}\"", bs58::encode(hasher.finalize()).into_string())
+    }
+
+    /// Checks whether the client already has the version of the data identified by the ETag.
+    pub(crate) fn matches(&self, etag: &str) -> bool {
+        let Some(header) = &self.if_none_match else {
+            return false;
+        };
+
+        // note: we're only ever producing strong tags, but as per RFC 9110 weak comparison
+        // is used for `If-None-Match`, so just strip the weak indicator
+        header
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ConditionalRequest {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let values = request.headers().get(IF_NONE_MATCH).collect::<Vec<_>>();
+        let if_none_match = if values.is_empty() {
+            None
+        } else {
+            Some(values.join(","))
+        };
+
+        Outcome::Success(ConditionalRequest {
+            if_none_match,
+            uri: request.uri().to_string(),
+        })
+    }
+}
+
+impl<'r> OpenApiFromRequest<'r> for ConditionalRequest {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(RequestHeaderInput::Parameter(Parameter {
+            name: IF_NONE_MATCH.to_owned(),
+            location: "header".to_owned(),
+            description: Some(
+                "ETag of the previously received response. If the data hasn't changed since, \
+                 an empty '304 Not Modified' response is returned instead"
+                    .to_owned(),
+            ),
+            required: false,
+            deprecated: false,
+            allow_empty_value: false,
+            value: ParameterValue::Schema {
+                style: None,
+                explode: None,
+                allow_reserved: false,
+                schema: gen.json_schema::<String>(),
+                example: None,
+                examples: None,
+            },
+            extensions: Object::default(),
+        }))
+    }
+}
+
+/// JSON response tagged with an `ETag` identifying the version of the data it has been produced from.
+/// If the client already has the current version of the data, as indicated by the `If-None-Match`
+/// header, the body is omitted and '304 Not Modified' is returned instead.
+pub(crate) enum ETagged<T> {
+    NotModified {
+        etag: String,
+    },
+    Modified {
+        etag: String,
+        body: String,
+        _typ: PhantomData<T>,
+    },
+}
+
+impl<T: Serialize> ETagged<T> {
+    pub(crate) fn new(value: &T, etag: String) -> Result<Self, ErrorResponse> {
+        let body = serde_json::to_string(value).map_err(|err| {
+            error!("failed to serialize the response: {err}");
+            ErrorResponse::new(
+                "failed to serialize the response",
+                Status::InternalServerError,
+            )
+        })?;
+
+        Ok(ETagged::Modified {
+            etag,
+            body,
+            _typ: PhantomData,
+        })
+    }
+}
+
+impl<'r, 'o: 'r, T> Responder<'r, 'o> for ETagged<T> {
+    fn respond_to(self, _req: &'r Request<'_>) -> response::Result<'o> {
+        match self {
+            ETagged::NotModified { etag } => Response::build()
+                .status(Status::NotModified)
+                .header(Header::new(ETAG, etag))
+                .ok(),
+            ETagged::Modified { etag, body, .. } => Response::build()
+                .header(ContentType::JSON)
+                .header(Header::new(ETAG, etag))
+                .sized_body(body.len(), Cursor::new(body))
+                .ok(),
+        }
+    }
+}
+
+impl<T: JsonSchema + Serialize + Send> OpenApiResponderInner for ETagged<T> {
+    fn responses(gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Json::<T>::responses(gen)?;
+        ensure_status_code_exists(&mut responses, 304);
+        Ok(responses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(if_none_match: Option<&str>, uri: &str) -> ConditionalRequest {
+        ConditionalRequest {
+            if_none_match: if_none_match.map(ToOwned::to_owned),
+            uri: uri.to_owned(),
+        }
+    }
+
+    #[test]
+    fn matching_entity_tags() {
+        let etag = "\"foo\"";
+        let matches = |if_none_match| request(if_none_match, "/").matches(etag);
+
+        assert!(!matches(None));
+        assert!(matches(Some("*")));
+        assert!(matches(Some("\"foo\"")));
+        assert!(matches(Some("W/\"foo\"")));
+        assert!(matches(Some("\"bar\", \"foo\"")));
+        assert!(!matches(Some("\"bar\"")));
+        assert!(!matches(Some("foo")));
+    }
+
+    #[test]
+    fn entity_tags_depend_on_the_query_and_cache_updates() {
+        let first_page = request(None, "/v2/gateways/described?limit=10");
+        let second_page = request(None, "/v2/gateways/described?limit=10&cursor=foo");
+
+        let etag = first_page.etag(&[1000]);
+        assert_eq!(etag, first_page.etag(&[1000]));
+        assert_ne!(etag, second_page.etag(&[1000]));
+        assert_ne!(etag, first_page.etag(&[1001]));
+        assert_ne!(
+            first_page.etag(&[1000, 2000]),
+            first_page.etag(&[1000, 2001])
+        );
+
+        assert!(request(Some(&etag), "/v2/gateways/described?limit=10").matches(&etag));
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node_status_api::models::ErrorResponse;
+use nym_mixnet_contract_common::reward_params::Performance;
+use nym_mixnet_contract_common::Layer;
+use rocket::http::Status;
+
+pub(crate) fn parse_layer(raw: Option<u8>) -> Result<Option<Layer>, ErrorResponse> {
+    raw.map(|layer| {
+        Layer::try_from(layer).map_err(|_| {
+            ErrorResponse::new(format!("invalid mixnode layer {layer}"), Status::BadRequest)
+        })
+    })
+    .transpose()
+}
+
+/// Parses the minimum performance expressed as a percentage value, i.e. between 0 and 100.
+pub(crate) fn parse_min_performance(raw: Option<u8>) -> Result<Option<Performance>, ErrorResponse> {
+    raw.map(|performance| {
+        Performance::from_percentage_value(performance as u64).map_err(|_| {
+            ErrorResponse::new(
+                format!("invalid performance threshold {performance}"),
+                Status::BadRequest,
+            )
+        })
+    })
+    .transpose()
+}
+
+/// Checks whether the node version matches the requested one. It can be either the exact version
+/// (e.g. '1.1.33') or just its prefix (e.g. '1.1' would match both '1.1.33' and '1.1.34').
+pub(crate) fn version_matches(node_version: &str, requested: &str) -> bool {
+    let node_version = node_version.trim();
+    let requested = requested.trim();
+    match node_version.strip_prefix(requested) {
+        Some(remainder) => remainder.is_empty() || remainder.starts_with('.'),
+        None => false,
+    }
+}
+
+/// Checks whether the self-declared location of the node matches the requested country.
+pub(crate) fn country_matches(location: &str, requested: &str) -> bool {
+    location.trim().eq_ignore_ascii_case(requested.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_versions() {
+        assert!(version_matches("1.1.33", "1.1.33"));
+        assert!(version_matches("1.1.33", "1.1"));
+        assert!(version_matches("1.1.33", "1"));
+        assert!(!version_matches("1.1.33", "1.1.3"));
+        assert!(!version_matches("1.1.33", "1.2"));
+        assert!(!version_matches("1.10.0", "1.1"));
+    }
+
+    #[test]
+    fn matching_countries() {
+        assert!(country_matches("Germany", "germany"));
+        assert!(country_matches(" Germany ", "Germany"));
+        assert!(!country_matches("Germany", "Ger"));
+    }
+
+    #[test]
+    fn parsing_filters() {
+        assert!(parse_layer(Some(4)).is_err());
+        assert!(matches!(parse_layer(Some(2)), Ok(Some(Layer::Two))));
+        assert!(parse_min_performance(Some(101)).is_err());
+        assert!(matches!(parse_min_performance(None), Ok(None)));
+    }
+}
@@ -6,13 +6,15 @@ use crate::coconut::client::Client;
 use crate::coconut::{self, comm::QueryCommunicationChannel};
 use crate::network::models::NetworkDetails;
 use crate::network::network_routes;
+use crate::node_describe_cache::mixnodes::DescribedMixnodes;
 use crate::node_describe_cache::DescribedNodes;
 use crate::node_status_api::{self, NodeStatusCache};
 use crate::nym_contract_cache::cache::NymContractCache;
+use crate::nym_nodes::{nym_node_routes, nym_node_routes_v2};
 use crate::support::caching::cache::SharedCache;
 use crate::support::config::Config;
 use crate::support::{nyxd, storage};
-use crate::{circulating_supply_api, nym_contract_cache, nym_nodes::nym_node_routes};
+use crate::{circulating_supply_api, nym_contract_cache};
 use anyhow::{bail, Result};
 use nym_crypto::asymmetric::identity;
 use nym_validator_client::nyxd::Coin;
@@ -22,7 +24,10 @@ use rocket_cors::{AllowedHeaders, AllowedOrigins, Cors};
 use rocket_okapi::mount_endpoints_and_merged_docs;
 use rocket_okapi::swagger_ui::make_swagger_ui;
 
+pub(crate) mod etag;
+pub(crate) mod filters;
 pub(crate) mod openapi;
+pub(crate) mod pagination;
 
 pub(crate) async fn setup_rocket(
     config: &Config,
@@ -48,9 +53,23 @@ pub(crate) async fn setup_rocket(
         "" => nym_node_routes(&openapi_settings),
     }
 
+    mount_endpoints_and_merged_docs! {
+        rocket,
+        "/v2".to_owned(),
+        openapi_settings,
+        "/" => (vec![], openapi::custom_openapi_spec_v2()),
+        "/status" => node_status_api::node_status_routes_v2(&openapi_settings, config.network_monitor.enabled),
+        "" => nym_node_routes_v2(&openapi_settings),
+    }
+
     let rocket = rocket
+        .mount(
+            "/v1",
+            nym_contract_cache::nym_contract_cache_stream_routes(),
+        )
         .manage(network_details)
         .manage(SharedCache::<DescribedNodes>::new())
+        .manage(SharedCache::<DescribedMixnodes>::new())
         .mount("/swagger", make_swagger_ui(&openapi::get_docs()))
         .attach(setup_cors()?)
         .attach(NymContractCache::stage())
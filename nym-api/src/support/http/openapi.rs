@@ -2,9 +2,18 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use okapi::openapi3::OpenApi;
+use rocket_okapi::settings::UrlObject;
 use rocket_okapi::swagger_ui::SwaggerUIConfig;
 
 pub fn custom_openapi_spec() -> OpenApi {
+    openapi_spec(get_servers("OPEN_API_BASE", "/api/v1/"))
+}
+
+pub fn custom_openapi_spec_v2() -> OpenApi {
+    openapi_spec(get_servers("OPEN_API_BASE_V2", "/api/v2/"))
+}
+
+fn openapi_spec(servers: Vec<rocket_okapi::okapi::openapi3::Server>) -> OpenApi {
     use rocket_okapi::okapi::openapi3::*;
     OpenApi {
         openapi: OpenApi::default_version(),
@@ -17,17 +26,17 @@ pub fn custom_openapi_spec() -> OpenApi {
             version: env!("CARGO_PKG_VERSION").to_owned(),
             ..Default::default()
         },
-        servers: get_servers(),
+        servers,
         ..Default::default()
     }
 }
 
-fn get_servers() -> Vec<rocket_okapi::okapi::openapi3::Server> {
+fn get_servers(base_var: &str, default_base: &str) -> Vec<rocket_okapi::okapi::openapi3::Server> {
     if std::env::var_os("CARGO").is_some() {
         return vec![];
     }
     vec![rocket_okapi::okapi::openapi3::Server {
-        url: std::env::var("OPEN_API_BASE").unwrap_or_else(|_| "/api/v1/".to_owned()),
+        url: std::env::var(base_var).unwrap_or_else(|_| default_base.to_owned()),
         description: Some("API".to_owned()),
         ..Default::default()
     }]
@@ -35,7 +44,10 @@ fn get_servers() -> Vec<rocket_okapi::okapi::openapi3::Server> {
 
 pub(crate) fn get_docs() -> SwaggerUIConfig {
     SwaggerUIConfig {
-        url: "../v1/openapi.json".to_owned(),
+        urls: vec![
+            UrlObject::new("v1", "../v1/openapi.json"),
+            UrlObject::new("v2", "../v2/openapi.json"),
+        ],
         ..SwaggerUIConfig::default()
     }
 }
@@ -0,0 +1,172 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::node_status_api::models::ErrorResponse;
+use nym_api_requests::pagination::{CursorPagination, PaginatedResponse};
+use rocket::http::Status;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+pub(crate) const DEFAULT_PAGE_SIZE: u32 = 100;
+pub(crate) const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Parses the cursor received from the client, i.e. the key of the last item it has already seen.
+pub(crate) fn parse_cursor<K: FromStr>(raw: Option<&str>) -> Result<Option<K>, ErrorResponse> {
+    raw.map(|raw| {
+        raw.parse()
+            .map_err(|_| ErrorResponse::new(format!("invalid cursor '{raw}'"), Status::BadRequest))
+    })
+    .transpose()
+}
+
+/// Returns the page of items that directly follows the provided cursor. Items are ordered by their keys,
+/// so that the pages remain stable regardless of the order in which the underlying cache stores them.
+pub(crate) fn paginate<T, K>(
+    mut items: Vec<T>,
+    key: impl Fn(&T) -> K,
+    cursor: Option<K>,
+    limit: Option<u32>,
+) -> PaginatedResponse<T>
+where
+    K: Ord + ToString,
+{
+    let total = items.len();
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE) as usize;
+
+    items.sort_by(|a, b| key(a).cmp(&key(b)));
+    let start = match cursor {
+        Some(cursor) => items.partition_point(|item| key(item) <= cursor),
+        None => 0,
+    };
+
+    // grab one more item than needed to know whether there's going to be a next page at all
+    let mut data = items
+        .into_iter()
+        .skip(start)
+        .take(limit + 1)
+        .collect::<Vec<_>>();
+    let next_cursor = if data.len() > limit {
+        data.truncate(limit);
+        data.last().map(|last| key(last).to_string())
+    } else {
+        None
+    };
+
+    PaginatedResponse {
+        pagination: CursorPagination {
+            total,
+            size: data.len(),
+            next_cursor,
+        },
+        data,
+    }
+}
+
+/// Top-level fields of the returned items requested by the client.
+/// If none were explicitly specified, all of them are returned.
+pub(crate) struct FieldSelection(Option<HashSet<String>>);
+
+impl FieldSelection {
+    pub(crate) fn new(raw: Option<&str>) -> Self {
+        FieldSelection(raw.map(|raw| {
+            raw.split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(ToOwned::to_owned)
+                .collect()
+        }))
+    }
+
+    fn select(&self, item: impl Serialize) -> Result<Value, serde_json::Error> {
+        let mut value = serde_json::to_value(item)?;
+        if let (Some(fields), Value::Object(object)) = (&self.0, &mut value) {
+            object.retain(|field, _| fields.contains(field));
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn apply<T: Serialize>(
+        &self,
+        page: PaginatedResponse<T>,
+    ) -> Result<PaginatedResponse<Value>, ErrorResponse> {
+        let data = page
+            .data
+            .into_iter()
+            .map(|item| self.select(item))
+            .collect::<Result<_, _>>()
+            .map_err(|err| {
+                error!("failed to serialize the response: {err}");
+                ErrorResponse::new(
+                    "failed to serialize the response",
+                    Status::InternalServerError,
+                )
+            })?;
+
+        Ok(PaginatedResponse {
+            pagination: page.pagination,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn paginating_through_items() {
+        let items = vec![5u32, 1, 4, 2, 3];
+
+        let first = paginate(items.clone(), |i| *i, None, Some(2));
+        assert_eq!(first.data, vec![1, 2]);
+        assert_eq!(first.pagination.total, 5);
+        assert_eq!(first.pagination.next_cursor, Some("2".to_string()));
+
+        let Ok(cursor) = parse_cursor(first.pagination.next_cursor.as_deref()) else {
+            panic!("received invalid cursor")
+        };
+        let second = paginate(items.clone(), |i| *i, cursor, Some(2));
+        assert_eq!(second.data, vec![3, 4]);
+        assert_eq!(second.pagination.next_cursor, Some("4".to_string()));
+
+        let third = paginate(items.clone(), |i| *i, Some(4), Some(2));
+        assert_eq!(third.data, vec![5]);
+        assert_eq!(third.pagination.size, 1);
+        assert!(third.pagination.next_cursor.is_none());
+
+        // the item pointed to by the cursor might have disappeared in the meantime
+        let missing = paginate(vec![1u32, 2, 5], |i| *i, Some(3), Some(2));
+        assert_eq!(missing.data, vec![5]);
+    }
+
+    #[test]
+    fn page_size_is_bounded() {
+        let items = (0..2000u32).collect::<Vec<_>>();
+        let page = paginate(items.clone(), |i| *i, None, None);
+        assert_eq!(page.data.len(), DEFAULT_PAGE_SIZE as usize);
+
+        let page = paginate(items, |i| *i, None, Some(u32::MAX));
+        assert_eq!(page.data.len(), MAX_PAGE_SIZE as usize);
+    }
+
+    #[test]
+    fn invalid_cursor() {
+        assert!(parse_cursor::<u32>(Some("foomp")).is_err());
+        assert!(matches!(parse_cursor::<u32>(None), Ok(None)));
+        assert!(matches!(parse_cursor::<u32>(Some("42")), Ok(Some(42))));
+    }
+
+    #[test]
+    fn selecting_fields() {
+        let item = json!({"a": 1, "b": 2, "c": {"d": 3}});
+
+        let all = FieldSelection::new(None);
+        assert_eq!(all.select(&item).unwrap(), item);
+
+        let some = FieldSelection::new(Some("a, c,"));
+        assert_eq!(some.select(&item).unwrap(), json!({"a": 1, "c": {"d": 3}}));
+    }
+}
@@ -65,6 +65,8 @@ pub enum BackendError {
     CoundNotSendDisconnectSignal,
     #[error("no service provider set")]
     NoServiceProviderSet,
+    #[error("the status message does not describe the gateway connectivity")]
+    NotAGatewayConnectivityStatus,
     #[error("no gateway provider set")]
     NoGatewaySet,
     #[error("initialization failed with a panic")]
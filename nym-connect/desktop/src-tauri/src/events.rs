@@ -61,6 +61,7 @@ pub async fn handle_client_status_message(
         ClientCoreStatusMessage::GatewayIsSlow | ClientCoreStatusMessage::GatewayIsVerySlow => {
             "socks5-gateway-status"
         }
+        ClientCoreStatusMessage::GatewayBandwidthRunningLow { .. } => "socks5-bandwidth-status",
     };
 
     if let Ok(connectivity) = GatewayConnectivity::try_from(client_status_message) {
@@ -45,6 +45,9 @@ impl TryFrom<&ClientCoreStatusMessage> for GatewayConnectivity {
             ClientCoreStatusMessage::GatewayIsVerySlow => GatewayConnectivity::VeryBad {
                 when: Instant::now(),
             },
+            ClientCoreStatusMessage::GatewayBandwidthRunningLow { .. } => {
+                return Err(BackendError::NotAGatewayConnectivityStatus)
+            }
         };
         Ok(conn)
     }
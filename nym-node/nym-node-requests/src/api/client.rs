@@ -4,6 +4,7 @@
 use crate::api::v1::gateway::models::WebSockets;
 use crate::api::v1::node::models::SignedHostInformation;
 use crate::api::ErrorResponse;
+use crate::api::SignedSphinxKeys;
 use crate::routes;
 use async_trait::async_trait;
 use http_api_client::{ApiClient, HttpClientError};
@@ -45,6 +46,11 @@ pub trait NymNodeApiClientExt: ApiClient {
         .await
     }
 
+    async fn get_sphinx_keys(&self) -> Result<SignedSphinxKeys, NymNodeApiClientError> {
+        self.get_json_from(routes::api::v1::mixnode::sphinx_keys_absolute())
+            .await
+    }
+
     async fn get_network_requester(&self) -> Result<NetworkRequester, NymNodeApiClientError> {
         self.get_json_from(routes::api::v1::network_requester_absolute())
             .await
@@ -1,6 +1,7 @@
 // Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::api::v1::mixnode::models::SphinxKeys;
 use crate::api::v1::node::models::HostInformation;
 use crate::error::Error;
 use nym_crypto::asymmetric::identity;
@@ -20,9 +21,12 @@ pub use client::Client;
 #[cfg(not(feature = "openapi"))]
 pub type SignedHostInformation = SignedData<HostInformation>;
 
+#[cfg(not(feature = "openapi"))]
+pub type SignedSphinxKeys = SignedData<SphinxKeys>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
-#[cfg_attr(feature = "openapi", aliases(SignedHostInformation = SignedData<HostInformation>))]
+#[cfg_attr(feature = "openapi", aliases(SignedHostInformation = SignedData<HostInformation>, SignedSphinxKeys = SignedData<SphinxKeys>))]
 pub struct SignedData<T> {
     // #[serde(flatten)]
     pub data: T,
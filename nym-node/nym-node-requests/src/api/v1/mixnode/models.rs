@@ -13,3 +13,34 @@ pub struct Mixnode {
     // /// Base58-encoded x25519 public key used for sphinx key derivation.
     // pub encoded_sphinx_key: String,
 }
+
+/// Sphinx key announced by a mixnode that rotates its keys on a schedule.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AnnouncedSphinxKey {
+    /// Sequential identifier of the rotation this key belongs to.
+    pub rotation_id: u32,
+
+    /// Base58-encoded x25519 public key used for sphinx key derivation.
+    pub public_key: String,
+
+    /// Unix timestamp since which clients should start using this key.
+    pub valid_from: i64,
+
+    /// Unix timestamp after which clients should stop using this key.
+    /// Note that the node is still going to accept it for a short grace period afterwards.
+    pub valid_until: i64,
+}
+
+impl AnnouncedSphinxKey {
+    pub fn is_valid_at(&self, unix_timestamp: i64) -> bool {
+        self.valid_from <= unix_timestamp && unix_timestamp < self.valid_until
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SphinxKeys {
+    /// The current and the upcoming sphinx keys of this node.
+    pub keys: Vec<AnnouncedSphinxKey>,
+}
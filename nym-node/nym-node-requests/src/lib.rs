@@ -84,7 +84,11 @@ pub mod routes {
             }
 
             pub mod mixnode {
-                // use super::*;
+                use super::*;
+
+                pub const SPHINX_KEYS: &str = "/sphinx-keys";
+
+                absolute_route!(sphinx_keys_absolute, mixnode_absolute(), SPHINX_KEYS);
             }
 
             pub mod network_requester {
@@ -148,6 +152,10 @@ mod tests {
         );
 
         assert_eq!("/api/v1/mixnode", routes::api::v1::mixnode_absolute());
+        assert_eq!(
+            "/api/v1/mixnode/sphinx-keys",
+            routes::api::v1::mixnode::sphinx_keys_absolute()
+        );
         assert_eq!(
             "/api/v1/network-requester",
             routes::api::v1::network_requester_absolute()
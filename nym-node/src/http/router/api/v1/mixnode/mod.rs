@@ -4,20 +4,36 @@
 use axum::routing::get;
 use axum::Router;
 use nym_node_requests::api::v1::mixnode::models;
+use nym_node_requests::api::SignedSphinxKeys;
+use nym_node_requests::routes::api::v1::mixnode;
+use std::sync::{Arc, RwLock};
 
 pub mod root;
+pub mod sphinx_keys;
+
+/// Signed announcement of the current and upcoming sphinx keys that gets updated upon every rotation.
+pub type SharedSphinxKeys = Arc<RwLock<SignedSphinxKeys>>;
 
 #[derive(Debug, Clone, Default)]
 pub struct Config {
     pub details: Option<models::Mixnode>,
+    pub sphinx_keys: Option<SharedSphinxKeys>,
 }
 
 pub(crate) fn routes<S: Send + Sync + 'static + Clone>(config: Config) -> Router<S> {
-    Router::new().route(
-        "/",
-        get({
-            let mixnode_details = config.details;
-            move |query| root::root_mixnode(mixnode_details, query)
-        }),
-    )
+    Router::new()
+        .route(
+            "/",
+            get({
+                let mixnode_details = config.details;
+                move |query| root::root_mixnode(mixnode_details, query)
+            }),
+        )
+        .route(
+            mixnode::SPHINX_KEYS,
+            get({
+                let sphinx_keys = config.sphinx_keys;
+                move |query| sphinx_keys::sphinx_keys(sphinx_keys, query)
+            }),
+        )
 }
@@ -0,0 +1,38 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::http::api::v1::mixnode::SharedSphinxKeys;
+use crate::http::router::api::{FormattedResponse, OutputParams};
+use axum::extract::Query;
+use axum::http::StatusCode;
+use nym_node_requests::api::SignedSphinxKeys;
+
+/// Returns the current and the upcoming sphinx keys of this mixnode, if it rotates them.
+#[utoipa::path(
+    get,
+    path = "/sphinx-keys",
+    context_path = "/api/v1/mixnode",
+    tag = "Mixnode",
+    responses(
+        (status = 501, description = "the node does not rotate its sphinx keys"),
+        (status = 200, content(
+            ("application/json" = SignedSphinxKeys),
+            ("application/yaml" = SignedSphinxKeys)
+        ))
+    ),
+    params(OutputParams)
+)]
+pub(crate) async fn sphinx_keys(
+    sphinx_keys: Option<SharedSphinxKeys>,
+    Query(output): Query<OutputParams>,
+) -> Result<SphinxKeysResponse, StatusCode> {
+    let sphinx_keys = sphinx_keys.ok_or(StatusCode::NOT_IMPLEMENTED)?;
+    let announced = sphinx_keys
+        .read()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .clone();
+    let output = output.output.unwrap_or_default();
+    Ok(output.to_response(announced))
+}
+
+pub type SphinxKeysResponse = FormattedResponse<SignedSphinxKeys>;
@@ -7,14 +7,30 @@ use axum::Router;
 use nym_node_requests::api::v1::network_requester::exit_policy::models::UsedExitPolicy;
 use nym_node_requests::api::v1::network_requester::models;
 use nym_node_requests::routes::api::v1::network_requester;
+use std::fmt::Debug;
+use std::sync::Arc;
 
 pub mod exit_policy;
 pub mod root;
 
+/// Provides the exit policy currently used by the node. It's queried on every request,
+/// so that any reloads of the policy would be reflected in the responses.
+pub trait ExitPolicyProvider: Debug + Send + Sync {
+    fn used_exit_policy(&self) -> UsedExitPolicy;
+}
+
+impl ExitPolicyProvider for UsedExitPolicy {
+    fn used_exit_policy(&self) -> UsedExitPolicy {
+        self.clone()
+    }
+}
+
+pub type SharedExitPolicyProvider = Arc<dyn ExitPolicyProvider>;
+
 #[derive(Debug, Clone, Default)]
 pub struct Config {
     pub details: Option<models::NetworkRequester>,
-    pub exit_policy: Option<UsedExitPolicy>,
+    pub exit_policy: Option<SharedExitPolicyProvider>,
 }
 
 pub(crate) fn routes<S: Send + Sync + 'static + Clone>(config: Config) -> Router<S> {
@@ -29,8 +45,14 @@ pub(crate) fn routes<S: Send + Sync + 'static + Clone>(config: Config) -> Router
         .route(
             network_requester::EXIT_POLICY,
             get({
-                let policy = config.exit_policy.unwrap_or_default();
-                move |query| node_exit_policy(policy, query)
+                let provider = config.exit_policy;
+                move |query| {
+                    let policy = provider
+                        .as_ref()
+                        .map(|provider| provider.used_exit_policy())
+                        .unwrap_or_default();
+                    node_exit_policy(policy, query)
+                }
             }),
         )
 }
@@ -25,6 +25,7 @@ use utoipa_swagger_ui::SwaggerUi;
         api::v1::gateway::client_interfaces::wireguard::client_registry::get_all_clients,
         api::v1::gateway::client_interfaces::wireguard::client_registry::get_client,
         api::v1::mixnode::root::root_mixnode,
+        api::v1::mixnode::sphinx_keys::sphinx_keys,
         api::v1::network_requester::root::root_network_requester,
         api::v1::network_requester::exit_policy::node_exit_policy,
         api::v1::ip_packet_router::root::root_ip_packet_router,
@@ -50,6 +51,9 @@ use utoipa_swagger_ui::SwaggerUi;
             api_requests::v1::gateway::client_interfaces::wireguard::models::GatewayClient,
             api_requests::v1::gateway::client_interfaces::wireguard::models::ClientRegistrationResponse,
             api_requests::v1::mixnode::models::Mixnode,
+            api_requests::SignedSphinxKeys,
+            api_requests::v1::mixnode::models::SphinxKeys,
+            api_requests::v1::mixnode::models::AnnouncedSphinxKey,
             api_requests::v1::network_requester::models::NetworkRequester,
             api_requests::v1::network_requester::exit_policy::models::AddressPolicy,
             api_requests::v1::network_requester::exit_policy::models::AddressPolicyRule,
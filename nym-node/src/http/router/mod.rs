@@ -3,6 +3,8 @@
 
 use crate::error::NymNodeError;
 pub use crate::http::api::v1::gateway::client_interfaces::wireguard::WireguardAppState;
+pub use crate::http::api::v1::mixnode::SharedSphinxKeys;
+pub use crate::http::api::v1::network_requester::{ExitPolicyProvider, SharedExitPolicyProvider};
 use crate::http::middleware::logging;
 use crate::http::state::AppState;
 use crate::http::NymNodeHTTPServer;
@@ -17,6 +19,7 @@ use nym_node_requests::api::SignedHostInformation;
 use nym_node_requests::routes;
 use std::net::SocketAddr;
 use std::path::Path;
+use std::sync::Arc;
 use tracing::warn;
 
 pub mod api;
@@ -84,6 +87,12 @@ impl Config {
         self
     }
 
+    #[must_use]
+    pub fn with_mixnode_sphinx_keys(mut self, sphinx_keys: SharedSphinxKeys) -> Self {
+        self.api.v1_config.mixnode.sphinx_keys = Some(sphinx_keys);
+        self
+    }
+
     #[must_use]
     pub fn with_network_requester(mut self, network_requester: NetworkRequester) -> Self {
         self.api.v1_config.node.roles.network_requester_enabled = true;
@@ -92,8 +101,13 @@ impl Config {
     }
 
     #[must_use]
-    pub fn with_used_exit_policy(mut self, exit_policy: UsedExitPolicy) -> Self {
-        self.api.v1_config.network_requester.exit_policy = Some(exit_policy);
+    pub fn with_used_exit_policy(self, exit_policy: UsedExitPolicy) -> Self {
+        self.with_exit_policy_provider(Arc::new(exit_policy))
+    }
+
+    #[must_use]
+    pub fn with_exit_policy_provider(mut self, provider: SharedExitPolicyProvider) -> Self {
+        self.api.v1_config.network_requester.exit_policy = Some(provider);
         self
     }
 
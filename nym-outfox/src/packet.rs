@@ -199,11 +199,8 @@ impl OutfoxPacket {
             .all(|x| x == &0)
     }
 
-    pub fn decode_next_layer(
-        &mut self,
-        mix_secret_key: &PrivateKey,
-    ) -> Result<[u8; 32], OutfoxError> {
-        let mix_secret_key = mix_secret_key.to_bytes();
+    /// Returns the index of the layer that is going to be decoded next.
+    fn current_layer(&self) -> usize {
         let routing_lenght_by_stage = self
             .mix_params()
             .routing_information_length_by_stage
@@ -215,6 +212,26 @@ impl OutfoxPacket {
                 break;
             }
         }
+        layer
+    }
+
+    /// Returns the public group element of the layer that is going to be decoded next.
+    /// It is unique for every packet (and hop) and thus can be used for detecting replays.
+    pub fn replay_tag(&self) -> Option<[u8; 32]> {
+        let (range, stage_params) = self.stage_params(self.current_layer());
+        self.payload()
+            .get(range)?
+            .get(stage_params.pub_element_range())?
+            .try_into()
+            .ok()
+    }
+
+    pub fn decode_next_layer(
+        &mut self,
+        mix_secret_key: &PrivateKey,
+    ) -> Result<[u8; 32], OutfoxError> {
+        let mix_secret_key = mix_secret_key.to_bytes();
+        let layer = self.current_layer();
         self.decode_mix_layer(layer, &mix_secret_key)?;
         self.update_routing_information(layer)?;
         let (range, stage_params) = self.mix_params().get_stage_params(layer);
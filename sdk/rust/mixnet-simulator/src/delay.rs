@@ -0,0 +1,83 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::channel::mpsc;
+use futures::StreamExt;
+use log::{debug, trace};
+use nym_mixnet_client::SendWithoutResponse;
+use nym_nonexhaustive_delayqueue::NonExhaustiveDelayQueue;
+use nym_sphinx::forwarding::packet::MixPacket;
+use nym_task::TaskClient;
+use tokio::time::Instant;
+
+/// Packets to forward alongside the instant they should be released at.
+/// `None` means the packet should be forwarded straight away.
+pub(crate) type DelayedPacketSender = mpsc::UnboundedSender<(MixPacket, Option<Instant>)>;
+type DelayedPacketReceiver = mpsc::UnboundedReceiver<(MixPacket, Option<Instant>)>;
+
+/// Holds the packets of a simulated node until their delays expire
+/// and then passes them onto the simulated network.
+pub(crate) struct SimulatedDelayForwarder<C> {
+    queue: NonExhaustiveDelayQueue<MixPacket>,
+    client: C,
+    receiver: DelayedPacketReceiver,
+    shutdown: TaskClient,
+}
+
+impl<C> SimulatedDelayForwarder<C>
+where
+    C: SendWithoutResponse + Send + 'static,
+{
+    /// Starts the forwarder in a background task, returning the channel for submitting the packets.
+    pub(crate) fn start(client: C, shutdown: TaskClient) -> DelayedPacketSender {
+        let (sender, receiver) = mpsc::unbounded();
+        let forwarder = SimulatedDelayForwarder {
+            queue: NonExhaustiveDelayQueue::new(),
+            client,
+            receiver,
+            shutdown,
+        };
+        tokio::spawn(forwarder.run());
+        sender
+    }
+
+    fn forward(&mut self, packet: MixPacket) {
+        let next_hop = packet.next_hop();
+        let packet_type = packet.packet_type();
+        if let Err(err) =
+            self.client
+                .send_without_response(next_hop, packet.into_packet(), packet_type)
+        {
+            debug!("failed to forward the packet to {next_hop}: {err}")
+        }
+    }
+
+    fn handle_new(&mut self, packet: MixPacket, release_at: Option<Instant>) {
+        match release_at {
+            Some(release_at) if release_at > Instant::now() => {
+                self.queue.insert_at(packet, release_at);
+            }
+            _ => self.forward(packet),
+        }
+    }
+
+    async fn run(mut self) {
+        trace!("starting SimulatedDelayForwarder");
+        while !self.shutdown.is_shutdown() {
+            tokio::select! {
+                Some(expired) = self.queue.next() => self.forward(expired.into_inner()),
+                new = self.receiver.next() => match new {
+                    Some((packet, release_at)) => self.handle_new(packet, release_at),
+                    None => {
+                        trace!("SimulatedDelayForwarder: stopping since all senders got dropped");
+                        break;
+                    }
+                },
+                _ = self.shutdown.recv() => {
+                    trace!("SimulatedDelayForwarder: received shutdown");
+                }
+            }
+        }
+        trace!("SimulatedDelayForwarder: exiting");
+    }
+}
@@ -0,0 +1,22 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SimulatorError {
+    #[error("the simulated network must contain at least a single mixnode per layer")]
+    EmptyLayer,
+
+    #[error("failed to connect the client to the simulated mixnet: {source}")]
+    ClientConnectionFailure {
+        #[from]
+        source: nym_sdk::Error,
+    },
+
+    #[error("the client did not provide its packet router to the simulated gateway")]
+    MissingPacketRouter,
+
+    #[error("the simulated gateway has already been shut down")]
+    GatewayShutdown,
+}
@@ -0,0 +1,190 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::delay::{DelayedPacketSender, SimulatedDelayForwarder};
+use crate::network::{PacketIngressReceiver, SimulatedNetwork};
+use futures::channel::mpsc;
+use futures::StreamExt;
+use log::{debug, error, trace, warn};
+use nym_crypto::asymmetric::{encryption, identity};
+use nym_mixnode_common::packet_processor::processor::{
+    MixProcessingResult, ProcessedFinalHop, SphinxPacketProcessor,
+};
+use nym_sdk::{GatewayPacketRouter, PacketRouter};
+use nym_sphinx::forwarding::packet::MixPacket;
+use nym_sphinx::framing::packet::FramedNymPacket;
+use nym_sphinx::DestinationAddressBytes;
+use nym_task::TaskClient;
+use nym_topology::{gateway, NetworkAddress, NodeVersion};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+pub(crate) type ClientPacketsSender = mpsc::UnboundedSender<MixPacket>;
+pub(crate) type ClientPacketsReceiver = mpsc::UnboundedReceiver<MixPacket>;
+
+pub(crate) type GatewayControlSender = mpsc::UnboundedSender<GatewayControlMessage>;
+type GatewayControlReceiver = mpsc::UnboundedReceiver<GatewayControlMessage>;
+
+pub(crate) enum GatewayControlMessage {
+    /// Start delivering packets destined for the specified client via the provided router.
+    RegisterClient {
+        address: DestinationAddressBytes,
+        packet_router: PacketRouter,
+    },
+}
+
+/// Gateway that delivers received messages directly to the in-process clients via their `PacketRouter`s
+/// rather than through websocket connections.
+pub(crate) struct SimulatedGateway {
+    processor: SphinxPacketProcessor,
+    ingress: PacketIngressReceiver,
+    client_packets: ClientPacketsReceiver,
+    control: GatewayControlReceiver,
+    delay_forwarding_channel: DelayedPacketSender,
+
+    /// Routers of all clients that are currently connected to this gateway.
+    clients: HashMap<DestinationAddressBytes, PacketRouter>,
+
+    /// Messages received for clients that have not (yet) connected to this gateway.
+    inbox: HashMap<DestinationAddressBytes, Vec<Vec<u8>>>,
+}
+
+impl SimulatedGateway {
+    pub(crate) fn start(
+        network: &SimulatedNetwork,
+        topology_entry: &gateway::Node,
+        encryption_key: &encryption::PrivateKey,
+        seed: u64,
+        shutdown: TaskClient,
+    ) -> (ClientPacketsSender, GatewayControlSender) {
+        let ingress = network.register_node(topology_entry.mix_host.into());
+        let (client_packets_tx, client_packets_rx) = mpsc::unbounded();
+        let (control_tx, control_rx) = mpsc::unbounded();
+
+        let delay_forwarding_channel =
+            SimulatedDelayForwarder::start(network.client(seed), shutdown.fork("delay_forwarder"));
+
+        let gateway = SimulatedGateway {
+            processor: SphinxPacketProcessor::new(encryption_key.into()),
+            ingress,
+            client_packets: client_packets_rx,
+            control: control_rx,
+            delay_forwarding_channel,
+            clients: HashMap::new(),
+            inbox: HashMap::new(),
+        };
+
+        tokio::spawn(gateway.run(shutdown));
+
+        (client_packets_tx, control_tx)
+    }
+
+    pub(crate) fn topology_entry(
+        mix_host: SocketAddr,
+        identity_key: identity::PublicKey,
+        sphinx_key: encryption::PublicKey,
+    ) -> gateway::Node {
+        gateway::Node {
+            owner: "simulated-gateway-owner".to_string(),
+            host: NetworkAddress::IpAddr(mix_host.ip()),
+            mix_host,
+            // there are no websocket listeners in the simulated network
+            clients_ws_port: 0,
+            clients_wss_port: None,
+            identity_key,
+            sphinx_key,
+            version: NodeVersion::from(env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    fn forward(&self, packet: MixPacket) {
+        // gateways do not delay packets
+        if self
+            .delay_forwarding_channel
+            .unbounded_send((packet, None))
+            .is_err()
+        {
+            trace!("the delay forwarder has already shut down")
+        }
+    }
+
+    fn deliver_to_client(&mut self, destination: DestinationAddressBytes, messages: Vec<Vec<u8>>) {
+        match self.clients.get(&destination) {
+            Some(router) => {
+                if let Err(err) = router.route_received(messages) {
+                    error!("failed to route packets to the simulated client: {err}")
+                }
+            }
+            None => {
+                trace!("client is not connected - storing the message");
+                self.inbox.entry(destination).or_default().extend(messages)
+            }
+        }
+    }
+
+    fn handle_final_hop(&mut self, final_hop: ProcessedFinalHop) {
+        self.deliver_to_client(final_hop.destination, vec![final_hop.message]);
+        if let Some(forward_ack) = final_hop.forward_ack {
+            self.forward(forward_ack)
+        }
+    }
+
+    fn handle_received(&mut self, packet: FramedNymPacket) {
+        match self.processor.process_received(packet) {
+            Err(err) => debug!("we failed to process received sphinx packet: {err}"),
+            Ok(MixProcessingResult::FinalHop(final_hop)) => self.handle_final_hop(final_hop),
+            Ok(MixProcessingResult::ForwardHop(..)) => {
+                warn!("the simulated gateway has received a forward hop packet")
+            }
+        }
+    }
+
+    fn handle_control_message(&mut self, message: GatewayControlMessage) {
+        match message {
+            GatewayControlMessage::RegisterClient {
+                address,
+                packet_router,
+            } => {
+                self.clients.insert(address, packet_router);
+                if let Some(stored) = self.inbox.remove(&address) {
+                    self.deliver_to_client(address, stored)
+                }
+            }
+        }
+    }
+
+    async fn run(mut self, mut shutdown: TaskClient) {
+        debug!("started simulated gateway");
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => {
+                    trace!("SimulatedGateway: received shutdown");
+                }
+                message = self.control.next() => match message {
+                    Some(message) => self.handle_control_message(message),
+                    None => {
+                        trace!("SimulatedGateway: stopping since the control channel closed");
+                        break;
+                    }
+                },
+                packet = self.ingress.next() => match packet {
+                    Some(packet) => self.handle_received(packet),
+                    None => {
+                        trace!("SimulatedGateway: stopping since the network channel closed");
+                        break;
+                    }
+                },
+                packet = self.client_packets.next() => match packet {
+                    Some(packet) => self.forward(packet),
+                    None => {
+                        trace!("SimulatedGateway: stopping since the client channel closed");
+                        break;
+                    }
+                },
+            }
+        }
+        shutdown.recv_timeout().await;
+        debug!("SimulatedGateway: exiting")
+    }
+}
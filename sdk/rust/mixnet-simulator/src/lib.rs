@@ -0,0 +1,305 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-process simulation of the Nym mixnet.
+//!
+//! It spins up a full set of mixnodes and a gateway as tokio tasks that exchange real sphinx
+//! packets over in-memory channels, so that applications built on top of the Rust SDK could be
+//! tested deterministically and without any network access.
+//!
+//! ```no_run
+//! use nym_mixnet_simulator::MixnetSimulator;
+//! use nym_sdk::mixnet::{self, MixnetMessageSender};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let simulator = MixnetSimulator::builder().with_seed(42).build();
+//!
+//! let mut client = simulator
+//!     .connect_client(mixnet::MixnetClientBuilder::new_ephemeral())
+//!     .await?;
+//! let our_address = *client.nym_address();
+//!
+//! client.send_plain_message(our_address, "hello there").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::gateway::{
+    ClientPacketsSender, GatewayControlMessage, GatewayControlSender, SimulatedGateway,
+};
+use crate::mixnode::SimulatedMixnode;
+use futures::channel::oneshot;
+use log::info;
+use nym_client_core::client::base_client::storage::gateway_details::GatewayDetailsStore;
+use nym_client_core::config::DebugConfig;
+use nym_crypto::asymmetric::{encryption, identity};
+use nym_sdk::mixnet::{
+    CredentialStorage, KeyStore, MixnetClient, MixnetClientBuilder, MixnetClientStorage,
+    ReplyStorageBackend,
+};
+use nym_sdk::LocalGateway;
+use nym_task::TaskManager;
+use nym_topology::provider_trait::HardcodedTopologyProvider;
+use nym_topology::{mix, NymTopology};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+pub use crate::error::SimulatorError;
+pub use crate::network::SimulatedNetwork;
+pub use nym_sphinx::addressing::nodes::NymNodeRoutingAddress;
+
+mod delay;
+mod error;
+mod gateway;
+mod mixnode;
+mod network;
+
+const DEFAULT_MIXNODES_PER_LAYER: usize = 2;
+const DEFAULT_SEED: u64 = 0;
+const SIMULATED_MIX_PORT: u16 = 1789;
+
+/// Builder for the [`MixnetSimulator`].
+#[derive(Debug, Clone, Copy)]
+pub struct MixnetSimulatorBuilder {
+    mixnodes_per_layer: usize,
+    seed: u64,
+    packet_loss: f64,
+}
+
+impl Default for MixnetSimulatorBuilder {
+    fn default() -> Self {
+        MixnetSimulatorBuilder {
+            mixnodes_per_layer: DEFAULT_MIXNODES_PER_LAYER,
+            seed: DEFAULT_SEED,
+            packet_loss: 0.,
+        }
+    }
+}
+
+impl MixnetSimulatorBuilder {
+    /// Specify the number of mixnodes in each of the mix layers.
+    #[must_use]
+    pub fn with_mixnodes_per_layer(mut self, mixnodes_per_layer: usize) -> Self {
+        self.mixnodes_per_layer = mixnodes_per_layer;
+        self
+    }
+
+    /// Seed used for deriving all the node keys and for the simulated packet loss,
+    /// so that all the runs with the same seed would be reproducible.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Probability of any packet being lost in transit between two nodes.
+    #[must_use]
+    pub fn with_packet_loss(mut self, packet_loss: f64) -> Self {
+        self.packet_loss = packet_loss;
+        self
+    }
+
+    /// Starts all the simulated nodes. Note that it must be called within a tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no mixnodes per layer or if the packet loss is not within the [0, 1] range.
+    pub fn build(self) -> MixnetSimulator {
+        match self.try_build() {
+            Ok(simulator) => simulator,
+            Err(err) => panic!("failed to build the mixnet simulator: {err}"),
+        }
+    }
+
+    /// Fallible variant of [`MixnetSimulatorBuilder::build`].
+    pub fn try_build(self) -> Result<MixnetSimulator, SimulatorError> {
+        if self.mixnodes_per_layer == 0 {
+            return Err(SimulatorError::EmptyLayer);
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let task_manager = TaskManager::new(5).named("mixnet-simulator");
+        let network = SimulatedNetwork::new();
+        network.set_packet_loss(self.packet_loss);
+
+        let mut mixes = BTreeMap::new();
+        let mut mix_id = 0;
+        for layer in [mix::Layer::One, mix::Layer::Two, mix::Layer::Three] {
+            let mut layer_nodes = Vec::with_capacity(self.mixnodes_per_layer);
+            for _ in 0..self.mixnodes_per_layer {
+                mix_id += 1;
+                let identity = identity::KeyPair::new(&mut rng);
+                let sphinx = encryption::KeyPair::new(&mut rng);
+                let node = SimulatedMixnode::topology_entry(
+                    mix_id,
+                    layer,
+                    simulated_socket_address(mix_id),
+                    *identity.public_key(),
+                    *sphinx.public_key(),
+                );
+                SimulatedMixnode::start(
+                    &network,
+                    &node,
+                    sphinx.private_key(),
+                    rng.next_u64(),
+                    task_manager.subscribe_named(format!("mixnode-{mix_id}")),
+                );
+                layer_nodes.push(node);
+            }
+            mixes.insert(layer as u8, layer_nodes);
+        }
+
+        let gateway_identity = identity::KeyPair::new(&mut rng);
+        let gateway_sphinx = encryption::KeyPair::new(&mut rng);
+        let gateway_node = SimulatedGateway::topology_entry(
+            simulated_socket_address(0),
+            *gateway_identity.public_key(),
+            *gateway_sphinx.public_key(),
+        );
+        let (client_packets, gateway_control) = SimulatedGateway::start(
+            &network,
+            &gateway_node,
+            gateway_sphinx.private_key(),
+            rng.next_u64(),
+            task_manager.subscribe_named("gateway"),
+        );
+
+        info!(
+            "started simulated mixnet with {} mixnodes per layer",
+            self.mixnodes_per_layer
+        );
+
+        Ok(MixnetSimulator {
+            topology: NymTopology::new(mixes, vec![gateway_node]),
+            network,
+            gateway_identity: *gateway_identity.public_key(),
+            client_packets,
+            gateway_control,
+            task_manager,
+        })
+    }
+}
+
+/// Deterministic, non-routable address assigned to the simulated node.
+fn simulated_socket_address(node_id: u32) -> SocketAddr {
+    let [_, b, c, d] = node_id.to_be_bytes();
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, b, c, d)), SIMULATED_MIX_PORT)
+}
+
+/// Debug config suitable for the simulated mixnet: all the cover traffic is disabled
+/// and the delays are minimised so that the tests would run quickly.
+pub fn simulation_debug_config() -> DebugConfig {
+    let mut debug_config = DebugConfig::default();
+    debug_config.traffic.average_packet_delay = Duration::from_millis(1);
+    debug_config.traffic.message_sending_average_delay = Duration::from_millis(1);
+    debug_config
+        .traffic
+        .disable_main_poisson_packet_distribution = true;
+    debug_config.cover_traffic.disable_loop_cover_traffic_stream = true;
+    debug_config.acknowledgements.average_ack_delay = Duration::from_millis(1);
+    debug_config.topology.disable_refreshing = true;
+    debug_config
+}
+
+/// Handle to the in-process mixnet consisting of three layers of mixnodes and a single gateway.
+pub struct MixnetSimulator {
+    topology: NymTopology,
+    network: SimulatedNetwork,
+    gateway_identity: identity::PublicKey,
+    client_packets: ClientPacketsSender,
+    gateway_control: GatewayControlSender,
+    task_manager: TaskManager,
+}
+
+impl MixnetSimulator {
+    pub fn builder() -> MixnetSimulatorBuilder {
+        MixnetSimulatorBuilder::default()
+    }
+
+    /// Topology of the simulated mixnet.
+    pub fn topology(&self) -> &NymTopology {
+        &self.topology
+    }
+
+    /// Handle to the simulated network allowing to, for example, introduce packet loss.
+    pub fn network(&self) -> &SimulatedNetwork {
+        &self.network
+    }
+
+    /// Identity of the sole gateway of the simulated mixnet.
+    pub fn gateway_identity(&self) -> identity::PublicKey {
+        self.gateway_identity
+    }
+
+    /// Routing addresses of all the mixnodes and the gateway of the simulated mixnet.
+    pub fn node_addresses(&self) -> Vec<NymNodeRoutingAddress> {
+        self.topology
+            .mixes()
+            .values()
+            .flatten()
+            .map(|node| node.mix_host.into())
+            .chain(
+                self.topology
+                    .gateways()
+                    .iter()
+                    .map(|node| node.mix_host.into()),
+            )
+            .collect()
+    }
+
+    /// Connects the client built with the provided builder to the simulated mixnet.
+    /// Note that the debug config of the client is always replaced with the [`simulation_debug_config`].
+    pub async fn connect_client<S>(
+        &self,
+        builder: MixnetClientBuilder<S>,
+    ) -> Result<MixnetClient, SimulatorError>
+    where
+        S: MixnetClientStorage + 'static,
+        S::ReplyStore: Send + Sync,
+        <S::ReplyStore as ReplyStorageBackend>::StorageError: Sync + Send,
+        <S::CredentialStore as CredentialStorage>::StorageError: Send + Sync,
+        <S::KeyStore as KeyStore>::StorageError: Send + Sync,
+        <S::GatewayDetailsStore as GatewayDetailsStore>::StorageError: Send + Sync,
+    {
+        let (router_tx, router_rx) = oneshot::channel();
+        let transceiver = LocalGateway::new(
+            self.gateway_identity,
+            self.client_packets.clone(),
+            router_tx,
+        );
+
+        let client = builder
+            .debug_config(simulation_debug_config())
+            .custom_topology_provider(Box::new(HardcodedTopologyProvider::new(
+                self.topology.clone(),
+            )))
+            .custom_gateway_transceiver(Box::new(transceiver))
+            .custom_shutdown(self.task_manager.subscribe_named("client"))
+            .build()?
+            .connect_to_mixnet()
+            .await?;
+
+        let packet_router = router_rx
+            .await
+            .map_err(|_| SimulatorError::MissingPacketRouter)?;
+
+        self.gateway_control
+            .unbounded_send(GatewayControlMessage::RegisterClient {
+                address: client.nym_address().identity().derive_destination_address(),
+                packet_router,
+            })
+            .map_err(|_| SimulatorError::GatewayShutdown)?;
+
+        Ok(client)
+    }
+
+    /// Signals shutdown to all the simulated nodes and waits for them to finish.
+    pub async fn shutdown(mut self) {
+        // it can only fail if all the tasks have already exited
+        let _ = self.task_manager.signal_shutdown();
+        self.task_manager.wait_for_shutdown().await;
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::delay::{DelayedPacketSender, SimulatedDelayForwarder};
+use crate::network::{PacketIngressReceiver, SimulatedNetwork};
+use futures::StreamExt;
+use log::{debug, trace, warn};
+use nym_crypto::asymmetric::{encryption, identity};
+use nym_mixnode_common::packet_processor::processor::{MixProcessingResult, SphinxPacketProcessor};
+use nym_sphinx::framing::packet::FramedNymPacket;
+use nym_task::TaskClient;
+use nym_topology::{mix, NetworkAddress, NodeVersion};
+use std::net::SocketAddr;
+use tokio::time::Instant;
+
+/// Mixnode that, rather than listening on a socket, receives its packets straight
+/// from the simulated network.
+pub(crate) struct SimulatedMixnode {
+    processor: SphinxPacketProcessor,
+    ingress: PacketIngressReceiver,
+    delay_forwarding_channel: DelayedPacketSender,
+}
+
+impl SimulatedMixnode {
+    pub(crate) fn start(
+        network: &SimulatedNetwork,
+        topology_entry: &mix::Node,
+        encryption_key: &encryption::PrivateKey,
+        seed: u64,
+        shutdown: TaskClient,
+    ) {
+        let ingress = network.register_node(topology_entry.mix_host.into());
+
+        let delay_forwarding_channel =
+            SimulatedDelayForwarder::start(network.client(seed), shutdown.fork("delay_forwarder"));
+
+        let node = SimulatedMixnode {
+            processor: SphinxPacketProcessor::new(encryption_key.into()),
+            ingress,
+            delay_forwarding_channel,
+        };
+
+        tokio::spawn(node.run(shutdown));
+    }
+
+    pub(crate) fn topology_entry(
+        mix_id: u32,
+        layer: mix::Layer,
+        mix_host: SocketAddr,
+        identity_key: identity::PublicKey,
+        sphinx_key: encryption::PublicKey,
+    ) -> mix::Node {
+        mix::Node {
+            mix_id,
+            owner: format!("simulated-mix-owner-{mix_id}"),
+            host: NetworkAddress::IpAddr(mix_host.ip()),
+            mix_host,
+            identity_key,
+            sphinx_key,
+            layer,
+            version: NodeVersion::from(env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    fn handle_received(&self, packet: FramedNymPacket) {
+        match self.processor.process_received(packet) {
+            Err(err) => debug!("we failed to process received sphinx packet: {err}"),
+            Ok(MixProcessingResult::ForwardHop(forward_packet, delay)) => {
+                // same as the real node, use the instant so that the time spent in the channel
+                // would not affect the delay
+                let forward_instant = delay.map(|delay| Instant::now() + delay.to_duration());
+                if self
+                    .delay_forwarding_channel
+                    .unbounded_send((forward_packet, forward_instant))
+                    .is_err()
+                {
+                    trace!("the delay forwarder has already shut down")
+                }
+            }
+            Ok(MixProcessingResult::FinalHop(_)) => {
+                warn!("a simulated mixnode has received a final hop packet")
+            }
+        }
+    }
+
+    async fn run(mut self, mut shutdown: TaskClient) {
+        debug!("started simulated mixnode");
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                packet = self.ingress.next() => match packet {
+                    Some(packet) => self.handle_received(packet),
+                    None => {
+                        trace!("SimulatedMixnode: stopping since the network channel closed");
+                        break;
+                    }
+                },
+                _ = shutdown.recv() => {
+                    trace!("SimulatedMixnode: received shutdown");
+                }
+            }
+        }
+        shutdown.recv_timeout().await;
+        debug!("SimulatedMixnode: exiting")
+    }
+}
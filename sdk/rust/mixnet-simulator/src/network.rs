@@ -0,0 +1,236 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::channel::mpsc;
+use log::{trace, warn};
+use nym_sphinx::addressing::nodes::NymNodeRoutingAddress;
+use nym_sphinx::framing::packet::FramedNymPacket;
+use nym_sphinx::params::PacketType;
+use nym_sphinx::NymPacket;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+pub(crate) type PacketIngressSender = mpsc::UnboundedSender<FramedNymPacket>;
+pub(crate) type PacketIngressReceiver = mpsc::UnboundedReceiver<FramedNymPacket>;
+
+#[derive(Default)]
+struct PacketLoss {
+    /// Probability of any packet getting dropped in transit.
+    probability: f64,
+
+    /// Number of upcoming packets that are going to get dropped regardless of the probability.
+    drop_next: usize,
+}
+
+impl PacketLoss {
+    fn should_drop(&mut self, rng: &mut StdRng) -> bool {
+        if self.drop_next > 0 {
+            self.drop_next -= 1;
+            return true;
+        }
+
+        self.probability > 0. && rng.gen_bool(self.probability)
+    }
+}
+
+#[derive(Default)]
+struct NetworkStats {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+struct NetworkInner {
+    /// Ingress channels of all the nodes in the simulated network.
+    nodes: RwLock<HashMap<NymNodeRoutingAddress, PacketIngressSender>>,
+
+    /// Addresses of nodes that are currently unreachable.
+    offline: RwLock<Vec<NymNodeRoutingAddress>>,
+
+    packet_loss: Mutex<PacketLoss>,
+
+    stats: NetworkStats,
+}
+
+/// In-process replacement of the TCP links between the nodes of the mixnet.
+/// Rather than going through the sockets, packets are pushed directly into the node channels.
+#[derive(Clone)]
+pub struct SimulatedNetwork {
+    inner: Arc<NetworkInner>,
+}
+
+impl SimulatedNetwork {
+    pub(crate) fn new() -> Self {
+        SimulatedNetwork {
+            inner: Arc::new(NetworkInner {
+                nodes: Default::default(),
+                offline: Default::default(),
+                packet_loss: Default::default(),
+                stats: Default::default(),
+            }),
+        }
+    }
+
+    pub(crate) fn register_node(&self, address: NymNodeRoutingAddress) -> PacketIngressReceiver {
+        let (tx, rx) = mpsc::unbounded();
+        self.inner
+            .nodes
+            .write()
+            .expect("network lock got poisoned")
+            .insert(address, tx);
+        rx
+    }
+
+    /// Sets the probability of any packet being lost between two nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided probability is not within the [0, 1] range.
+    pub fn set_packet_loss(&self, probability: f64) {
+        assert!(
+            (0. ..=1.).contains(&probability),
+            "packet loss probability must be within [0, 1] range"
+        );
+        self.inner
+            .packet_loss
+            .lock()
+            .expect("network lock got poisoned")
+            .probability = probability
+    }
+
+    /// Deterministically drop the next `n` packets sent between any two nodes.
+    pub fn drop_next_packets(&self, n: usize) {
+        self.inner
+            .packet_loss
+            .lock()
+            .expect("network lock got poisoned")
+            .drop_next = n
+    }
+
+    /// Makes the node with the provided address unreachable until `bring_online` is called.
+    pub fn take_offline(&self, address: NymNodeRoutingAddress) {
+        let mut guard = self
+            .inner
+            .offline
+            .write()
+            .expect("network lock got poisoned");
+        if !guard.contains(&address) {
+            guard.push(address)
+        }
+    }
+
+    /// Makes the previously unreachable node available again.
+    pub fn bring_online(&self, address: NymNodeRoutingAddress) {
+        self.inner
+            .offline
+            .write()
+            .expect("network lock got poisoned")
+            .retain(|offline| offline != &address)
+    }
+
+    /// Total number of packets delivered to the nodes.
+    pub fn delivered_packets(&self) -> u64 {
+        self.inner.stats.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Total number of packets that got dropped due to either simulated packet loss
+    /// or unreachable destinations.
+    pub fn dropped_packets(&self) -> u64 {
+        self.inner.stats.dropped.load(Ordering::Relaxed)
+    }
+
+    fn is_offline(&self, address: &NymNodeRoutingAddress) -> bool {
+        self.inner
+            .offline
+            .read()
+            .expect("network lock got poisoned")
+            .contains(address)
+    }
+
+    fn should_drop(&self, rng: &mut StdRng) -> bool {
+        self.inner
+            .packet_loss
+            .lock()
+            .expect("network lock got poisoned")
+            .should_drop(rng)
+    }
+
+    fn deliver(
+        &self,
+        address: NymNodeRoutingAddress,
+        packet: NymPacket,
+        packet_type: PacketType,
+        rng: &mut StdRng,
+    ) -> io::Result<()> {
+        if self.is_offline(&address) {
+            trace!("{address} is offline - dropping the packet");
+            self.inner.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                format!("{address} is offline"),
+            ));
+        }
+
+        if self.should_drop(rng) {
+            // from the sender's point of view, the packet has been sent
+            trace!("simulating packet loss on the way to {address}");
+            self.inner.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        let guard = self.inner.nodes.read().expect("network lock got poisoned");
+        let Some(ingress) = guard.get(&address) else {
+            warn!("attempted to send packet to unknown node {address}");
+            self.inner.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                format!("{address} is not part of the simulated network"),
+            ));
+        };
+
+        // we never use the legacy framing since we control all the nodes
+        let framed = FramedNymPacket::new(packet, packet_type, false);
+        if ingress.unbounded_send(framed).is_err() {
+            // the node has been shut down
+            self.inner.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                format!("{address} has been shut down"),
+            ));
+        }
+
+        self.inner.stats.delivered.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Creates the client used by a single node for sending its packets.
+    /// Each node gets its own seeded rng so that the simulated packet loss would not depend
+    /// on the order in which the node tasks happen to get scheduled.
+    pub(crate) fn client(&self, seed: u64) -> SimulatedMixnetClient {
+        SimulatedMixnetClient {
+            network: self.clone(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+/// Equivalent of the `nym_mixnet_client::Client` that pushes packets through the simulated network.
+pub(crate) struct SimulatedMixnetClient {
+    network: SimulatedNetwork,
+    rng: StdRng,
+}
+
+impl nym_mixnet_client::SendWithoutResponse for SimulatedMixnetClient {
+    fn send_without_response(
+        &mut self,
+        address: NymNodeRoutingAddress,
+        packet: NymPacket,
+        packet_type: PacketType,
+    ) -> io::Result<()> {
+        self.network
+            .deliver(address, packet, packet_type, &mut self.rng)
+    }
+}
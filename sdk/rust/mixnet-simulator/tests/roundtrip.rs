@@ -0,0 +1,132 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+// all the tests run with paused tokio time so that the packet delays, ack timeouts and
+// retransmissions happen instantly and in a reproducible order
+
+use nym_mixnet_simulator::MixnetSimulator;
+use nym_sdk::mixnet::{
+    IncludedSurbs, MixnetClient, MixnetClientBuilder, MixnetMessageSender, ReconstructedMessage,
+};
+use std::time::Duration;
+
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(60);
+
+async fn connect_clients(simulator: &MixnetSimulator) -> (MixnetClient, MixnetClient) {
+    let sender = simulator
+        .connect_client(MixnetClientBuilder::new_ephemeral())
+        .await
+        .unwrap();
+    let receiver = simulator
+        .connect_client(MixnetClientBuilder::new_ephemeral())
+        .await
+        .unwrap();
+    (sender, receiver)
+}
+
+async fn next_message(client: &mut MixnetClient) -> ReconstructedMessage {
+    loop {
+        let received = tokio::time::timeout(MESSAGE_TIMEOUT, client.wait_for_messages())
+            .await
+            .expect("timed out waiting for a message")
+            .expect("the client has shut down");
+        if let Some(message) = received.into_iter().find(|msg| !msg.message.is_empty()) {
+            return message;
+        }
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn messages_are_delivered_through_the_simulated_mixnet() {
+    let simulator = MixnetSimulator::builder().with_seed(42).build();
+    let (sender, mut receiver) = connect_clients(&simulator).await;
+
+    sender
+        .send_plain_message(*receiver.nym_address(), "hello simulated world")
+        .await
+        .unwrap();
+
+    let received = next_message(&mut receiver).await;
+    assert_eq!(received.message, b"hello simulated world");
+
+    sender.disconnect().await;
+    receiver.disconnect().await;
+    simulator.shutdown().await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn replies_are_delivered_using_surbs() {
+    let simulator = MixnetSimulator::builder().with_seed(42).build();
+    let (mut sender, mut receiver) = connect_clients(&simulator).await;
+
+    sender
+        .send_message(*receiver.nym_address(), "ping", IncludedSurbs::new(5))
+        .await
+        .unwrap();
+
+    let request = next_message(&mut receiver).await;
+    assert_eq!(request.message, b"ping");
+    let sender_tag = request
+        .sender_tag
+        .expect("the message did not contain reply surbs");
+
+    receiver.send_reply(sender_tag, "pong").await.unwrap();
+
+    let reply = next_message(&mut sender).await;
+    assert_eq!(reply.message, b"pong");
+    // the reply arrived via a surb, so it can't be attributed to anyone
+    assert!(reply.sender_tag.is_none());
+
+    sender.disconnect().await;
+    receiver.disconnect().await;
+    simulator.shutdown().await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn acknowledged_packets_are_not_retransmitted() {
+    let simulator = MixnetSimulator::builder().with_seed(42).build();
+    let (sender, mut receiver) = connect_clients(&simulator).await;
+
+    sender
+        .send_plain_message(*receiver.nym_address(), "hello")
+        .await
+        .unwrap();
+    next_message(&mut receiver).await;
+
+    // give the acks enough time to get back to the sender
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    let delivered = simulator.network().delivered_packets();
+    assert!(delivered > 0);
+
+    // had any ack gone missing, the sender would have kept on retransmitting its packets
+    tokio::time::sleep(Duration::from_secs(120)).await;
+    assert_eq!(simulator.network().delivered_packets(), delivered);
+    assert_eq!(simulator.network().dropped_packets(), 0);
+
+    sender.disconnect().await;
+    receiver.disconnect().await;
+    simulator.shutdown().await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn lost_packets_are_retransmitted() {
+    let simulator = MixnetSimulator::builder().with_seed(42).build();
+    let (sender, mut receiver) = connect_clients(&simulator).await;
+
+    // the first packet the gateway forwards into the mixnet is going to be lost,
+    // so the message can only arrive once the sender retransmits it after the ack timeout
+    simulator.network().drop_next_packets(1);
+
+    sender
+        .send_plain_message(*receiver.nym_address(), "hello again")
+        .await
+        .unwrap();
+
+    let received = next_message(&mut receiver).await;
+    assert_eq!(received.message, b"hello again");
+    assert_eq!(simulator.network().dropped_packets(), 1);
+
+    sender.disconnect().await;
+    receiver.disconnect().await;
+    simulator.shutdown().await;
+}
@@ -37,7 +37,14 @@ impl MyTopologyProvider {
 
         let gateways = self.validator_client.get_cached_gateways().await.unwrap();
 
-        nym_topology_from_detailed(filtered_mixnodes, gateways)
+        // nodes that rotate their sphinx keys announce them separately from their bonds
+        let announced_sphinx_keys = self
+            .validator_client
+            .get_cached_mixnodes_sphinx_keys()
+            .await
+            .unwrap();
+
+        nym_topology_from_detailed(filtered_mixnodes, gateways, &announced_sphinx_keys)
     }
 }
 
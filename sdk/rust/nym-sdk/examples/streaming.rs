@@ -0,0 +1,55 @@
+use nym_sdk::mixnet;
+use nym_sdk::mixnet::IncludedSurbs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::main]
+async fn main() {
+    nym_bin_common::logging::setup_logging();
+
+    // Streams are exchanged between two separate clients
+    let sender = mixnet::MixnetClient::connect_new()
+        .await
+        .unwrap()
+        .into_stream_manager();
+    let mut receiver = mixnet::MixnetClient::connect_new()
+        .await
+        .unwrap()
+        .into_stream_manager();
+    let receiver_address = *receiver.nym_address();
+
+    // Open a stream without revealing our address, the remote replies using the attached SURBs
+    let mut stream = sender
+        .open_stream(receiver_address, IncludedSurbs::Amount(20))
+        .await
+        .unwrap();
+
+    let payload = vec![42u8; 100 * 1024];
+    let writer = tokio::spawn(async move {
+        stream.write_all(&payload).await.unwrap();
+        // let the other side know we're done
+        stream.shutdown().await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        println!("Received response: {response}");
+    });
+
+    let mut incoming = receiver.accept().await.unwrap();
+    println!(
+        "Accepted stream {} from {:?}",
+        incoming.id(),
+        incoming.peer()
+    );
+
+    let mut received = Vec::new();
+    incoming.read_to_end(&mut received).await.unwrap();
+    println!("Received {} bytes", received.len());
+
+    incoming.write_all(b"thanks!").await.unwrap();
+    incoming.shutdown().await.unwrap();
+
+    writer.await.unwrap();
+
+    sender.disconnect().await;
+    receiver.disconnect().await;
+}
@@ -36,12 +36,18 @@ mod connection_state;
 mod native_client;
 mod paths;
 mod socks5_client;
+mod stream;
 mod traits;
 
 pub use client::{DisconnectedMixnetClient, IncludedSurbs, MixnetClientBuilder};
 pub use config::{Config, KeyMode};
 pub use native_client::MixnetClient;
 pub use native_client::MixnetClientSender;
+#[cfg(feature = "encrypted-surb-storage")]
+pub use nym_client_core::client::{
+    base_client::storage::EncryptedPersistent,
+    replies::reply_storage::encrypted_fs_backend::Backend as EncryptedReplyStorage,
+};
 pub use nym_client_core::{
     client::{
         base_client::storage::{Ephemeral, MixnetClientStorage, OnDiskPersistent},
@@ -72,7 +78,12 @@ pub use nym_sphinx::{
     anonymous_replies::requests::AnonymousSenderTag,
     receiver::ReconstructedMessage,
 };
-pub use nym_topology::{provider_trait::TopologyProvider, NymTopology};
+pub use nym_topology::{
+    diff::{MixnodeLayerChange, NodeVersionChange},
+    provider_trait::TopologyProvider,
+    NymTopology, NymTopologyDiff,
+};
 pub use paths::StoragePaths;
 pub use socks5_client::Socks5MixnetClient;
+pub use stream::{MixnetStream, MixnetStreamId, MixnetStreamManager, MixnetStreamPeer};
 pub use traits::MixnetMessageSender;
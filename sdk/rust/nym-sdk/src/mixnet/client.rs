@@ -14,6 +14,8 @@ use log::warn;
 use nym_client_core::client::base_client::storage::gateway_details::{
     GatewayDetailsStore, PersistedGatewayDetails,
 };
+#[cfg(feature = "encrypted-surb-storage")]
+use nym_client_core::client::base_client::storage::EncryptedPersistent;
 use nym_client_core::client::base_client::storage::{
     Ephemeral, MixnetClientStorage, OnDiskPersistent,
 };
@@ -97,6 +99,25 @@ impl MixnetClientBuilder<OnDiskPersistent> {
     }
 }
 
+#[cfg(feature = "encrypted-surb-storage")]
+impl MixnetClientBuilder<EncryptedPersistent> {
+    /// Creates a client builder with storage that persists the client keys, its gateway details
+    /// and all the reply-related data in files inside the provided directory. This allows,
+    /// for example, a service provider to keep its address across restarts and to keep replying
+    /// to anonymous senders using the reply SURBs it has already received. If the passphrase
+    /// is provided, the data is encrypted at rest.
+    pub async fn new_encrypted_persistent<P: AsRef<Path>>(
+        storage_directory: P,
+        passphrase: Option<&[u8]>,
+    ) -> Result<Self> {
+        let storage =
+            EncryptedPersistent::from_directory(storage_directory, passphrase, &Default::default())
+                .await?;
+
+        Ok(Self::new_with_storage(storage))
+    }
+}
+
 impl<S> MixnetClientBuilder<S>
 where
     S: MixnetClientStorage + 'static,
@@ -413,6 +434,21 @@ where
             .collect()
     }
 
+    /// If a custom gateway transceiver has been provided, such as one of an in-process gateway,
+    /// create gateway setup that doesn't require querying the network for available gateways.
+    fn custom_gateway_setup(&self) -> Option<GatewaySetup> {
+        let transceiver = self.custom_gateway_transceiver.as_ref()?;
+
+        Some(GatewaySetup::New {
+            specification: GatewaySelectionSpecification::Custom {
+                gateway_identity: transceiver.gateway_identity().to_base58_string(),
+                additional_data: Default::default(),
+            },
+            available_gateways: vec![],
+            overwrite_data: !self.config.key_mode.is_keep(),
+        })
+    }
+
     /// Client keys are generated at client creation if none were found. The gateway shared
     /// key, however, is created during the gateway registration handshake so it might not
     /// necessarily be available.
@@ -465,6 +501,8 @@ where
 
         let gateway_setup = if self.has_valid_gateway_info().await {
             GatewaySetup::MustLoad
+        } else if let Some(custom_setup) = self.custom_gateway_setup() {
+            custom_setup
         } else {
             let selection_spec = GatewaySelectionSpecification::new(
                 self.config.user_chosen_gateway.clone(),
@@ -528,7 +566,18 @@ where
 
         let known_gateway = self.has_valid_gateway_info().await;
 
-        let mut base_builder: BaseClientBuilder<_, _> = if !known_gateway {
+        // the custom transceiver (if any) takes care of the gateway 'connection' by itself
+        let custom_setup = if known_gateway {
+            None
+        } else {
+            self.custom_gateway_setup()
+        };
+
+        let mut base_builder: BaseClientBuilder<_, _> = if let Some(setup) = custom_setup {
+            BaseClientBuilder::new(&base_config, self.storage, self.dkg_query_client)
+                .with_wait_for_gateway(self.wait_for_gateway)
+                .with_gateway_setup(setup)
+        } else if !known_gateway {
             let selection_spec = GatewaySelectionSpecification::new(
                 self.config.user_chosen_gateway,
                 None,
@@ -1,4 +1,5 @@
 use crate::mixnet::client::MixnetClientBuilder;
+use crate::mixnet::stream::MixnetStreamManager;
 use crate::mixnet::traits::MixnetMessageSender;
 use crate::{Error, Result};
 use async_trait::async_trait;
@@ -16,9 +17,11 @@ use nym_task::{
     connections::{ConnectionCommandSender, LaneQueueLengths},
     TaskHandle,
 };
-use nym_topology::NymTopology;
+use nym_topology::{NymTopology, NymTopologyDiff};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use tokio::sync::broadcast;
 
 /// Client connected to the Nym mixnet.
 pub struct MixnetClient {
@@ -139,6 +142,14 @@ impl MixnetClient {
         self.client_state.topology_accessor.current_topology().await
     }
 
+    /// Subscribe to the changes of the network topology used by this client.
+    /// Every time the topology changes, the receiver gets the difference between the old and
+    /// the new one, so that the application could react, for example, when its own gateway
+    /// or any node it relies on disappears from the network.
+    pub fn topology_changes(&self) -> broadcast::Receiver<Arc<NymTopologyDiff>> {
+        self.client_state.topology_accessor.subscribe_to_changes()
+    }
+
     /// Restore default topology refreshing behaviour of this client.
     pub fn restore_automatic_topology_refreshing(&self) {
         self.client_state.topology_accessor.release_manual_control()
@@ -161,6 +172,23 @@ impl MixnetClient {
         }
     }
 
+    /// Convert this client into a [`MixnetStreamManager`] allowing to exchange data via
+    /// [`MixnetStream`](crate::mixnet::MixnetStream)s rather than as complete messages.
+    ///
+    /// Note that it must be called from within a tokio runtime.
+    pub fn into_stream_manager(self) -> MixnetStreamManager {
+        MixnetStreamManager::new(
+            self.nym_address,
+            self.client_input,
+            self.client_output,
+            self.client_state,
+            self.reconstructed_receiver,
+            self._buffered,
+            self.task_handle,
+            self.packet_type,
+        )
+    }
+
     /// Disconnect from the mixnet. Currently it is not supported to reconnect a disconnected
     /// client.
     pub async fn disconnect(mut self) {
@@ -0,0 +1,141 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::mixnet::Recipient;
+
+/// Prefix used to distinguish stream frames from any other messages received from the mixnet.
+const STREAM_FRAME_MAGIC: [u8; 4] = *b"NYMS";
+
+const STREAM_FRAME_VERSION: u8 = 2;
+
+/// magic (4) || version (1) || kind (1) || stream id (8) || sequence (8)
+const STREAM_FRAME_HEADER_LEN: usize = 22;
+
+/// Identifier of a [`MixnetStream`](super::MixnetStream) shared by both of its ends.
+pub type MixnetStreamId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum StreamFrameKind {
+    /// Announces new stream. It optionally contains the address of the opener
+    /// if it chose to reveal it rather than to rely on reply SURBs.
+    Open = 0,
+
+    /// Contains the next chunk of the stream data.
+    Data = 1,
+
+    /// Indicates no more data is going to be sent. The sequence of the frame
+    /// is equal to the total number of data frames sent.
+    Close = 2,
+
+    /// Allows the remote to send data frames with sequence numbers lower than
+    /// the sequence of this frame.
+    WindowUpdate = 3,
+}
+
+impl TryFrom<u8> for StreamFrameKind {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            _ if value == StreamFrameKind::Open as u8 => Ok(StreamFrameKind::Open),
+            _ if value == StreamFrameKind::Data as u8 => Ok(StreamFrameKind::Data),
+            _ if value == StreamFrameKind::Close as u8 => Ok(StreamFrameKind::Close),
+            _ if value == StreamFrameKind::WindowUpdate as u8 => Ok(StreamFrameKind::WindowUpdate),
+            value => Err(value),
+        }
+    }
+}
+
+/// The unit of data exchanged between the two ends of a stream. Each frame is sent as a separate
+/// mixnet message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StreamFrame {
+    pub(crate) stream_id: MixnetStreamId,
+    pub(crate) kind: StreamFrameKind,
+    pub(crate) sequence: u64,
+    pub(crate) payload: Vec<u8>,
+}
+
+impl StreamFrame {
+    pub(crate) fn open(stream_id: MixnetStreamId, reply_address: Option<Recipient>) -> Self {
+        StreamFrame {
+            stream_id,
+            kind: StreamFrameKind::Open,
+            sequence: 0,
+            payload: reply_address
+                .map(|address| address.to_bytes().to_vec())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub(crate) fn data(stream_id: MixnetStreamId, sequence: u64, payload: Vec<u8>) -> Self {
+        StreamFrame {
+            stream_id,
+            kind: StreamFrameKind::Data,
+            sequence,
+            payload,
+        }
+    }
+
+    pub(crate) fn close(stream_id: MixnetStreamId, final_sequence: u64) -> Self {
+        StreamFrame {
+            stream_id,
+            kind: StreamFrameKind::Close,
+            sequence: final_sequence,
+            payload: Vec::new(),
+        }
+    }
+
+    pub(crate) fn window_update(stream_id: MixnetStreamId, sequence_limit: u64) -> Self {
+        StreamFrame {
+            stream_id,
+            kind: StreamFrameKind::WindowUpdate,
+            sequence: sequence_limit,
+            payload: Vec::new(),
+        }
+    }
+
+    /// Attempts to recover the reply address included in the `Open` frame.
+    pub(crate) fn reply_address(&self) -> Option<Recipient> {
+        if self.kind != StreamFrameKind::Open {
+            return None;
+        }
+        let bytes = self.payload.as_slice().try_into().ok()?;
+        Recipient::try_from_bytes(bytes).ok()
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(STREAM_FRAME_HEADER_LEN + self.payload.len());
+        bytes.extend_from_slice(&STREAM_FRAME_MAGIC);
+        bytes.push(STREAM_FRAME_VERSION);
+        bytes.push(self.kind as u8);
+        bytes.extend_from_slice(&self.stream_id.to_be_bytes());
+        bytes.extend_from_slice(&self.sequence.to_be_bytes());
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+
+    /// Attempts to parse the received message as a stream frame.
+    /// Returns `None` if the message is not a (valid) stream frame.
+    pub(crate) fn try_from_bytes(b: &[u8]) -> Option<Self> {
+        if b.len() < STREAM_FRAME_HEADER_LEN || b[..4] != STREAM_FRAME_MAGIC {
+            return None;
+        }
+        if b[4] != STREAM_FRAME_VERSION {
+            return None;
+        }
+        let kind = StreamFrameKind::try_from(b[5]).ok()?;
+
+        // the unwraps are fine as we've checked the length of the slice
+        let stream_id = MixnetStreamId::from_be_bytes(b[6..14].try_into().unwrap());
+        let sequence = u64::from_be_bytes(b[14..22].try_into().unwrap());
+
+        Some(StreamFrame {
+            stream_id,
+            kind,
+            sequence,
+            payload: b[STREAM_FRAME_HEADER_LEN..].to_vec(),
+        })
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::frame::{MixnetStreamId, StreamFrame, StreamFrameKind};
+use super::{MixnetStream, MixnetStreamPeer, StreamContext, RECEIVE_WINDOW};
+use crate::mixnet::{AnonymousSenderTag, IncludedSurbs, Recipient};
+use crate::{Error, Result};
+use futures::channel::mpsc;
+use futures::StreamExt;
+use log::{debug, trace, warn};
+use nym_client_core::client::base_client::{ClientInput, ClientOutput, ClientState};
+use nym_client_core::client::received_buffer::ReconstructedMessagesReceiver;
+use nym_sphinx::params::PacketType;
+use nym_sphinx::receiver::ReconstructedMessage;
+use nym_task::{TaskClient, TaskHandle};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Maximum number of frames kept for a stream whose `Open` frame has not yet been received.
+/// The remote is not allowed to send more than the receive window before we open the stream anyway.
+const MAX_PENDING_FRAMES_PER_STREAM: usize = RECEIVE_WINDOW as usize;
+
+/// Maximum number of not yet opened streams for which the received frames are kept around.
+const MAX_PENDING_STREAMS: usize = 64;
+
+/// Maximum duration for which frames of a not yet opened stream are kept around.
+const PENDING_FRAMES_TTL: Duration = Duration::from_secs(60);
+
+/// Client connected to the Nym mixnet that exchanges data via [`MixnetStream`]s.
+///
+/// Any received message that is not part of a stream is still available via
+/// [`MixnetStreamManager::wait_for_messages`].
+pub struct MixnetStreamManager {
+    nym_address: Recipient,
+    context: StreamContext,
+
+    incoming_streams: mpsc::UnboundedReceiver<MixnetStream>,
+    other_messages: mpsc::UnboundedReceiver<Vec<ReconstructedMessage>>,
+
+    // note: we have to keep the client input and output alive as otherwise the client would have
+    // been shut down
+    _client_input: ClientInput,
+    _client_output: ClientOutput,
+    _client_state: ClientState,
+    task_handle: TaskHandle,
+}
+
+impl MixnetStreamManager {
+    pub(crate) fn new(
+        nym_address: Recipient,
+        client_input: ClientInput,
+        client_output: ClientOutput,
+        client_state: ClientState,
+        reconstructed_receiver: ReconstructedMessagesReceiver,
+        buffered: Vec<ReconstructedMessage>,
+        task_handle: TaskHandle,
+        packet_type: Option<PacketType>,
+    ) -> Self {
+        let context = StreamContext {
+            input_sender: client_input.input_sender.clone(),
+            lane_queue_lengths: client_state.shared_lane_queue_lengths.clone(),
+            packet_type,
+            registry: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        let (incoming_tx, incoming_streams) = mpsc::unbounded();
+        let (other_tx, other_messages) = mpsc::unbounded();
+
+        let mut router = StreamRouter {
+            context: context.clone(),
+            reconstructed_receiver,
+            incoming_streams: incoming_tx,
+            other_messages: other_tx,
+            pending: HashMap::new(),
+        };
+        if !buffered.is_empty() {
+            router.handle_messages(buffered);
+        }
+        router.start_with_shutdown(task_handle.fork("stream_router"));
+
+        MixnetStreamManager {
+            nym_address,
+            context,
+            incoming_streams,
+            other_messages,
+            _client_input: client_input,
+            _client_output: client_output,
+            _client_state: client_state,
+            task_handle,
+        }
+    }
+
+    /// Get the nym address of the underlying client.
+    pub fn nym_address(&self) -> &Recipient {
+        &self.nym_address
+    }
+
+    fn new_stream_id(&self) -> MixnetStreamId {
+        let registry = self
+            .context
+            .registry
+            .lock()
+            .expect("stream registry lock got poisoned");
+        loop {
+            let id = rand::random();
+            if !registry.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
+    async fn open(
+        &self,
+        stream: MixnetStream,
+        reply_address: Option<Recipient>,
+    ) -> Result<MixnetStream> {
+        let open_frame = StreamFrame::open(stream.id, reply_address);
+        self.context
+            .input_sender
+            .send(stream.input_message(open_frame))
+            .await
+            .map_err(|_| Error::MessageSendingFailure)?;
+        Ok(stream)
+    }
+
+    /// Opens a new stream to the provided recipient. Depending on the `surbs` argument, either
+    /// our address is revealed to the remote or the specified number of reply SURBs is sent
+    /// alongside the initial frame.
+    pub async fn open_stream(
+        &self,
+        recipient: Recipient,
+        surbs: IncludedSurbs,
+    ) -> Result<MixnetStream> {
+        let (reply_surbs, reply_address) = match surbs {
+            IncludedSurbs::Amount(amount) => (Some(amount), None),
+            IncludedSurbs::ExposeSelfAddress => (None, Some(self.nym_address)),
+        };
+
+        let stream = MixnetStream::new(
+            self.new_stream_id(),
+            MixnetStreamPeer::Recipient(recipient),
+            reply_surbs,
+            self.context.clone(),
+        );
+        self.open(stream, reply_address).await
+    }
+
+    /// Opens a new stream to the anonymous client that has previously sent us reply SURBs.
+    pub async fn open_reply_stream(
+        &self,
+        recipient_tag: AnonymousSenderTag,
+    ) -> Result<MixnetStream> {
+        let stream = MixnetStream::new(
+            self.new_stream_id(),
+            MixnetStreamPeer::AnonymousSender(recipient_tag),
+            None,
+            self.context.clone(),
+        );
+        self.open(stream, None).await
+    }
+
+    /// Wait for the next stream opened by a remote client.
+    pub async fn accept(&mut self) -> Option<MixnetStream> {
+        self.incoming_streams.next().await
+    }
+
+    /// Wait for messages from the mixnet that are not part of any stream.
+    pub async fn wait_for_messages(&mut self) -> Option<Vec<ReconstructedMessage>> {
+        self.other_messages.next().await
+    }
+
+    /// Disconnect from the mixnet. Any existing streams are going to be terminated.
+    pub async fn disconnect(mut self) {
+        if let TaskHandle::Internal(task_manager) = &mut self.task_handle {
+            task_manager.signal_shutdown().ok();
+            task_manager.wait_for_shutdown().await;
+        }
+    }
+}
+
+/// Frames received before the `Open` frame of their stream.
+struct PendingFrames {
+    first_received: Instant,
+    frames: Vec<StreamFrame>,
+}
+
+/// Routes all received stream frames to their respective streams.
+struct StreamRouter {
+    context: StreamContext,
+    reconstructed_receiver: ReconstructedMessagesReceiver,
+    incoming_streams: mpsc::UnboundedSender<MixnetStream>,
+    other_messages: mpsc::UnboundedSender<Vec<ReconstructedMessage>>,
+
+    /// The mixnet does not preserve ordering of messages, so we might receive data frames of
+    /// a stream before it has been announced.
+    pending: HashMap<MixnetStreamId, PendingFrames>,
+}
+
+impl StreamRouter {
+    fn handle_open_frame(&mut self, frame: StreamFrame, sender_tag: Option<AnonymousSenderTag>) {
+        let stream_id = frame.stream_id;
+        if self
+            .context
+            .registry
+            .lock()
+            .expect("stream registry lock got poisoned")
+            .contains_key(&stream_id)
+        {
+            debug!("received duplicate open frame for stream {stream_id}");
+            return;
+        }
+
+        let peer = match (frame.reply_address(), sender_tag) {
+            (Some(address), _) => MixnetStreamPeer::Recipient(address),
+            (None, Some(sender_tag)) => MixnetStreamPeer::AnonymousSender(sender_tag),
+            (None, None) => {
+                warn!("received open frame for stream {stream_id} without any means of replying");
+                return;
+            }
+        };
+
+        let stream = MixnetStream::new(stream_id, peer, None, self.context.clone());
+        if let Some(pending) = self.pending.remove(&stream_id) {
+            for frame in pending.frames {
+                // the stream has just been registered so the frame can't be given back
+                let _ = self.route_frame(frame);
+            }
+        }
+
+        if self.incoming_streams.unbounded_send(stream).is_err() {
+            debug!("nobody is accepting incoming streams anymore");
+        }
+    }
+
+    fn buffer_pending_frame(&mut self, frame: StreamFrame) {
+        let now = Instant::now();
+        self.pending
+            .retain(|_, pending| now.duration_since(pending.first_received) < PENDING_FRAMES_TTL);
+
+        if !self.pending.contains_key(&frame.stream_id) && self.pending.len() >= MAX_PENDING_STREAMS
+        {
+            warn!(
+                "too many unknown streams with pending frames - dropping the frame for stream {}",
+                frame.stream_id
+            );
+            return;
+        }
+
+        let pending = self
+            .pending
+            .entry(frame.stream_id)
+            .or_insert_with(|| PendingFrames {
+                first_received: now,
+                frames: Vec::new(),
+            });
+        if pending.frames.len() >= MAX_PENDING_FRAMES_PER_STREAM {
+            warn!(
+                "too many frames received for unknown stream {} - dropping the frame",
+                frame.stream_id
+            );
+            return;
+        }
+        pending.frames.push(frame)
+    }
+
+    /// Forward the frame to its stream. If the stream does not exist, the frame is given back.
+    fn route_frame(&mut self, frame: StreamFrame) -> Result<(), StreamFrame> {
+        let mut registry = self
+            .context
+            .registry
+            .lock()
+            .expect("stream registry lock got poisoned");
+
+        let Some(stream_sender) = registry.get_mut(&frame.stream_id) else {
+            return Err(frame);
+        };
+        let stream_id = frame.stream_id;
+        if let Err(err) = stream_sender.try_send(frame) {
+            if err.is_disconnected() {
+                trace!("stream {stream_id} has already been dropped");
+                registry.remove(&stream_id);
+            } else {
+                // the remote must not send more than what fits in the receive window
+                warn!("the remote has overflown the buffer of stream {stream_id} - dropping the frame");
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_messages(&mut self, messages: Vec<ReconstructedMessage>) {
+        let mut other_messages = Vec::new();
+        for message in messages {
+            let Some(frame) = StreamFrame::try_from_bytes(&message.message) else {
+                other_messages.push(message);
+                continue;
+            };
+
+            if frame.kind == StreamFrameKind::Open {
+                self.handle_open_frame(frame, message.sender_tag);
+            } else if let Err(frame) = self.route_frame(frame) {
+                self.buffer_pending_frame(frame)
+            }
+        }
+
+        if !other_messages.is_empty() && self.other_messages.unbounded_send(other_messages).is_err()
+        {
+            trace!("nobody is listening for non-stream messages anymore");
+        }
+    }
+
+    async fn run_with_shutdown(mut self, mut shutdown: TaskClient) {
+        debug!("Started StreamRouter with graceful shutdown support");
+
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => {
+                    trace!("StreamRouter: Received shutdown");
+                }
+                messages = self.reconstructed_receiver.next() => match messages {
+                    Some(messages) => self.handle_messages(messages),
+                    None => {
+                        debug!("the reconstructed messages channel has been closed");
+                        break
+                    }
+                }
+            }
+        }
+        shutdown.recv_timeout().await;
+        debug!("StreamRouter: Exiting");
+    }
+
+    fn start_with_shutdown(self, shutdown: TaskClient) {
+        tokio::spawn(async move { self.run_with_shutdown(shutdown).await });
+    }
+}
@@ -0,0 +1,637 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Byte streams multiplexed over the mixnet.
+//!
+//! Each [`MixnetStream`] implements [`AsyncRead`] and [`AsyncWrite`] so that arbitrarily large
+//! payloads could be sent without having to hold them in memory as a single message.
+//! The written data is split into frames, each sent as a separate mixnet message on its own
+//! transmission lane. The remote end reorders the frames before making them available for reading.
+//! The amount of data buffered by the receiver is bounded by its receive window: the sender
+//! is only allowed to send data frames the receiver has explicitly made room for.
+
+use self::frame::{StreamFrame, StreamFrameKind};
+use crate::mixnet::{AnonymousSenderTag, Recipient};
+use futures::channel::mpsc;
+use futures::{ready, Future, StreamExt};
+use log::{debug, warn};
+use nym_client_core::client::inbound_messages::{InputMessage, InputMessageSender};
+use nym_ordered_buffer::OrderedMessageBuffer;
+use nym_sphinx::params::PacketType;
+use nym_task::connections::{LaneQueueLengths, TransmissionLane};
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::mpsc::OwnedPermit;
+
+pub use self::frame::MixnetStreamId;
+pub use self::manager::MixnetStreamManager;
+
+mod frame;
+mod manager;
+
+/// Maximum amount of data put into a single frame (i.e. a single mixnet message).
+const MAX_FRAME_PAYLOAD: usize = 8 * 1024;
+
+/// Number of packets queued up on the stream lane after which the writes are going to be
+/// suspended until the client catches up.
+const LANE_QUEUE_THRESHOLD: usize = 30;
+
+/// How often the lane queue length is checked while the writer is suspended.
+const BACKPRESSURE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of data frames the remote is allowed to send beyond the data we have already read.
+/// It bounds the amount of memory used for buffering the received data of a single stream.
+pub(crate) const RECEIVE_WINDOW: u64 = 128;
+
+/// Capacity of the channel of frames routed to a stream. On top of the receive window,
+/// it leaves some room for the control frames.
+const INBOUND_FRAMES_BUFFER: usize = RECEIVE_WINDOW as usize + 16;
+
+type PermitFuture = Pin<
+    Box<
+        dyn Future<
+                Output = Result<OwnedPermit<InputMessage>, tokio::sync::mpsc::error::SendError<()>>,
+            > + Send,
+    >,
+>;
+
+pub(crate) type StreamRegistry = Arc<Mutex<HashMap<MixnetStreamId, mpsc::Sender<StreamFrame>>>>;
+
+/// The other end of a [`MixnetStream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixnetStreamPeer {
+    /// The peer has revealed its address.
+    Recipient(Recipient),
+
+    /// The peer is anonymous and the data is sent back using the reply SURBs it has provided.
+    AnonymousSender(AnonymousSenderTag),
+}
+
+/// Shared state required by all the streams created by the same client.
+#[derive(Clone)]
+pub(crate) struct StreamContext {
+    pub(crate) input_sender: InputMessageSender,
+    pub(crate) lane_queue_lengths: LaneQueueLengths,
+    pub(crate) packet_type: Option<PacketType>,
+    pub(crate) registry: StreamRegistry,
+}
+
+/// A bidirectional, ordered byte stream to another mixnet client.
+///
+/// Writes are suspended whenever there are too many packets queued up for the stream,
+/// so that the producer could not outrun the rate at which the client sends packets into the
+/// mixnet, and whenever the remote has not made room for more data in its receive window,
+/// so that the producer could not outrun the consumer either. Calling `shutdown` notifies
+/// the remote end that no more data is going to be written, after which reads on the other side
+/// return EOF once all the preceding data has been received.
+pub struct MixnetStream {
+    id: MixnetStreamId,
+    peer: MixnetStreamPeer,
+
+    /// Number of reply SURBs attached to the messages sent to the `Recipient` peer.
+    /// `None` indicates our address is exposed instead.
+    reply_surbs: Option<u32>,
+
+    context: StreamContext,
+
+    // write half
+    next_write_sequence: u64,
+    /// Sequence of the first data frame the remote has not made room for yet.
+    send_limit: u64,
+    pending_permit: Option<PermitFuture>,
+    backpressure_delay: Option<Pin<Box<tokio::time::Sleep>>>,
+    write_closed: bool,
+    write_waker: Option<Waker>,
+
+    // read half
+    inbound: mpsc::Receiver<StreamFrame>,
+    reorder_buffer: OrderedMessageBuffer,
+    next_read_sequence: u64,
+    /// Sequence of the first data frame we have not made room for yet.
+    receive_limit: u64,
+    pending_window_permit: Option<PermitFuture>,
+    remote_close_sequence: Option<u64>,
+    read_buffer: Vec<u8>,
+    read_offset: usize,
+    read_waker: Option<Waker>,
+}
+
+impl MixnetStream {
+    pub(crate) fn new(
+        id: MixnetStreamId,
+        peer: MixnetStreamPeer,
+        reply_surbs: Option<u32>,
+        context: StreamContext,
+    ) -> Self {
+        let (inbound_tx, inbound) = mpsc::channel(INBOUND_FRAMES_BUFFER);
+        context
+            .registry
+            .lock()
+            .expect("stream registry lock got poisoned")
+            .insert(id, inbound_tx);
+
+        MixnetStream {
+            id,
+            peer,
+            reply_surbs,
+            context,
+            next_write_sequence: 0,
+            send_limit: RECEIVE_WINDOW,
+            pending_permit: None,
+            backpressure_delay: None,
+            write_closed: false,
+            write_waker: None,
+            inbound,
+            reorder_buffer: OrderedMessageBuffer::new(),
+            next_read_sequence: 0,
+            receive_limit: RECEIVE_WINDOW,
+            pending_window_permit: None,
+            remote_close_sequence: None,
+            read_buffer: Vec::new(),
+            read_offset: 0,
+            read_waker: None,
+        }
+    }
+
+    /// Identifier of this stream.
+    pub fn id(&self) -> MixnetStreamId {
+        self.id
+    }
+
+    /// The remote end of this stream.
+    pub fn peer(&self) -> MixnetStreamPeer {
+        self.peer
+    }
+
+    fn lane(&self) -> TransmissionLane {
+        TransmissionLane::ConnectionId(self.id)
+    }
+
+    pub(crate) fn input_message(&self, frame: StreamFrame) -> InputMessage {
+        let is_open = frame.kind == StreamFrameKind::Open;
+        let data = frame.into_bytes();
+        match (self.peer, self.reply_surbs) {
+            (MixnetStreamPeer::Recipient(recipient), None) => {
+                InputMessage::new_regular(recipient, data, self.lane(), self.context.packet_type)
+            }
+            (MixnetStreamPeer::Recipient(recipient), Some(reply_surbs)) => {
+                // the remote will request more surbs if it runs out of them, so we only need to
+                // attach them to the initial frame
+                let reply_surbs = if is_open { reply_surbs } else { 0 };
+                InputMessage::new_anonymous(
+                    recipient,
+                    data,
+                    reply_surbs,
+                    self.lane(),
+                    self.context.packet_type,
+                )
+            }
+            (MixnetStreamPeer::AnonymousSender(recipient_tag), _) => {
+                InputMessage::new_reply(recipient_tag, data, self.lane(), self.context.packet_type)
+            }
+        }
+    }
+
+    /// Wait until the number of packets queued on the stream lane is not greater than `threshold`.
+    fn poll_lane_below(&mut self, cx: &mut Context<'_>, threshold: usize) -> Poll<()> {
+        loop {
+            if let Some(delay) = self.backpressure_delay.as_mut() {
+                ready!(delay.as_mut().poll(cx));
+                self.backpressure_delay = None;
+            }
+
+            match self.context.lane_queue_lengths.get(&self.lane()) {
+                Some(queued) if queued > threshold => {
+                    self.backpressure_delay =
+                        Some(Box::pin(tokio::time::sleep(BACKPRESSURE_CHECK_INTERVAL)))
+                }
+                _ => return Poll::Ready(()),
+            }
+        }
+    }
+
+    /// Reserve space in the client input channel, so that the frame could be sent immediately.
+    fn poll_reserve(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<OwnedPermit<InputMessage>>> {
+        poll_permit(&self.context.input_sender, &mut self.pending_permit, cx)
+    }
+
+    /// Wait until the remote makes room for another data frame in its receive window.
+    fn poll_send_window(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.next_write_sequence >= self.send_limit {
+            self.write_waker = Some(cx.waker().clone());
+            ready!(self.poll_inbound(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Once at least half of the receive window has been read, let the remote know
+    /// it can send more data. It must only be called once all the received data has been read.
+    fn poll_grant_window(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let new_limit = self.next_read_sequence + RECEIVE_WINDOW;
+        if self.remote_close_sequence.is_some()
+            || new_limit - self.receive_limit < RECEIVE_WINDOW / 2
+        {
+            return Poll::Ready(Ok(()));
+        }
+
+        let permit = ready!(poll_permit(
+            &self.context.input_sender,
+            &mut self.pending_window_permit,
+            cx
+        ))?;
+        permit.send(self.input_message(StreamFrame::window_update(self.id, new_limit)));
+        self.receive_limit = new_limit;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Process the next frame received from the remote.
+    fn poll_inbound(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match ready!(self.inbound.poll_next_unpin(cx)) {
+            Some(frame) => {
+                self.handle_inbound_frame(frame);
+                Poll::Ready(Ok(()))
+            }
+            None => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "the mixnet client has been shut down",
+            ))),
+        }
+    }
+
+    fn handle_inbound_frame(&mut self, frame: StreamFrame) {
+        // the reading and the writing might happen in separate tasks, so whichever is processing
+        // the frames has to wake up the other one if the frame is relevant to it
+        match frame.kind {
+            // we might receive a duplicate if the remote has retransmitted it
+            StreamFrameKind::Open => (),
+            StreamFrameKind::Data => {
+                if frame.sequence >= self.receive_limit {
+                    warn!(
+                        "stream {} received data frame {} outside of its receive window - dropping it",
+                        self.id, frame.sequence
+                    );
+                    return;
+                }
+                if let Err(err) = self.reorder_buffer.write(frame.sequence, frame.payload) {
+                    warn!("stream {} received invalid data frame: {err}", self.id)
+                }
+                wake(&mut self.read_waker)
+            }
+            StreamFrameKind::Close => {
+                debug!("stream {} has been closed by the remote", self.id);
+                self.remote_close_sequence = Some(frame.sequence);
+                wake(&mut self.read_waker)
+            }
+            StreamFrameKind::WindowUpdate => {
+                if frame.sequence > self.send_limit {
+                    self.send_limit = frame.sequence;
+                    wake(&mut self.write_waker)
+                }
+            }
+        }
+    }
+
+    fn is_remote_done(&self) -> bool {
+        self.remote_close_sequence
+            .map(|final_sequence| self.next_read_sequence >= final_sequence)
+            .unwrap_or_default()
+    }
+}
+
+fn poll_permit(
+    input_sender: &InputMessageSender,
+    pending_permit: &mut Option<PermitFuture>,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<OwnedPermit<InputMessage>>> {
+    let permit_fut =
+        pending_permit.get_or_insert_with(|| Box::pin(input_sender.clone().reserve_owned()));
+
+    let res = ready!(permit_fut.as_mut().poll(cx));
+    *pending_permit = None;
+    Poll::Ready(res.map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::BrokenPipe,
+            "the mixnet client has been shut down",
+        )
+    }))
+}
+
+fn wake(waker: &mut Option<Waker>) {
+    if let Some(waker) = waker.take() {
+        waker.wake()
+    }
+}
+
+impl AsyncRead for MixnetStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            if self.read_offset < self.read_buffer.len() {
+                let available = &self.read_buffer[self.read_offset..];
+                let n = usize::min(available.len(), buf.remaining());
+                buf.put_slice(&available[..n]);
+                self.read_offset += n;
+                return Poll::Ready(Ok(()));
+            }
+
+            // everything received so far has been read, so we can make room for more data
+            ready!(self.poll_grant_window(cx))?;
+
+            if let Some(contiguous) = self.reorder_buffer.read() {
+                self.next_read_sequence = contiguous.last_sequence + 1;
+                self.read_buffer = contiguous.data;
+                self.read_offset = 0;
+                continue;
+            }
+
+            if self.is_remote_done() {
+                // EOF
+                return Poll::Ready(Ok(()));
+            }
+
+            self.read_waker = Some(cx.waker().clone());
+            ready!(self.poll_inbound(cx))?;
+        }
+    }
+}
+
+impl AsyncWrite for MixnetStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.write_closed {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the stream has already been shut down",
+            )));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        ready!(self.poll_send_window(cx))?;
+        ready!(self.poll_lane_below(cx, LANE_QUEUE_THRESHOLD));
+        let permit = ready!(self.poll_reserve(cx))?;
+
+        let n = usize::min(buf.len(), MAX_FRAME_PAYLOAD);
+        let frame = StreamFrame::data(self.id, self.next_write_sequence, buf[..n].to_vec());
+        permit.send(self.input_message(frame));
+        self.next_write_sequence += 1;
+
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // all written data has been handed over to the client, so the only thing left
+        // is to wait until it actually gets sent into the mixnet
+        ready!(self.poll_lane_below(cx, 0));
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if !self.write_closed {
+            let permit = ready!(self.poll_reserve(cx))?;
+            let frame = StreamFrame::close(self.id, self.next_write_sequence);
+            permit.send(self.input_message(frame));
+            self.write_closed = true;
+        }
+
+        self.poll_flush(cx)
+    }
+}
+
+impl Drop for MixnetStream {
+    fn drop(&mut self) {
+        if !self.write_closed {
+            // best effort attempt to let the remote know we're gone
+            let frame = StreamFrame::close(self.id, self.next_write_sequence);
+            if self
+                .context
+                .input_sender
+                .try_send(self.input_message(frame))
+                .is_err()
+            {
+                debug!(
+                    "failed to notify the remote about closing stream {}",
+                    self.id
+                )
+            }
+        }
+
+        if let Ok(mut registry) = self.context.registry.lock() {
+            registry.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::{encryption, identity};
+    use rand::rngs::OsRng;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::mpsc::Receiver;
+
+    const STREAM_ID: MixnetStreamId = 42;
+
+    fn test_stream() -> (MixnetStream, Receiver<InputMessage>) {
+        let mut rng = OsRng;
+        let recipient = Recipient::new(
+            *identity::KeyPair::new(&mut rng).public_key(),
+            *encryption::KeyPair::new(&mut rng).public_key(),
+            *identity::KeyPair::new(&mut rng).public_key(),
+        );
+
+        let (input_sender, input_receiver) = tokio::sync::mpsc::channel(1024);
+        let context = StreamContext {
+            input_sender,
+            lane_queue_lengths: Default::default(),
+            packet_type: None,
+            registry: Default::default(),
+        };
+        let stream = MixnetStream::new(
+            STREAM_ID,
+            MixnetStreamPeer::Recipient(recipient),
+            None,
+            context,
+        );
+        (stream, input_receiver)
+    }
+
+    fn deliver(stream: &MixnetStream, frame: StreamFrame) {
+        stream
+            .context
+            .registry
+            .lock()
+            .unwrap()
+            .get_mut(&frame.stream_id)
+            .unwrap()
+            .try_send(frame)
+            .unwrap()
+    }
+
+    fn sent_frames(input_receiver: &mut Receiver<InputMessage>) -> Vec<StreamFrame> {
+        let mut frames = Vec::new();
+        while let Ok(message) = input_receiver.try_recv() {
+            let InputMessage::Regular { data, .. } = message else {
+                panic!("unexpected input message")
+            };
+            frames.push(StreamFrame::try_from_bytes(&data).unwrap());
+        }
+        frames
+    }
+
+    async fn read_to_end(stream: &mut MixnetStream) -> Vec<u8> {
+        let mut received = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut received))
+            .await
+            .expect("the stream has not been closed")
+            .unwrap();
+        received
+    }
+
+    #[tokio::test]
+    async fn data_is_read_in_order() {
+        let (mut stream, _input) = test_stream();
+
+        deliver(&stream, StreamFrame::data(STREAM_ID, 2, b"baz".to_vec()));
+        deliver(&stream, StreamFrame::data(STREAM_ID, 0, b"foo".to_vec()));
+        deliver(&stream, StreamFrame::data(STREAM_ID, 1, b"bar".to_vec()));
+        deliver(&stream, StreamFrame::close(STREAM_ID, 3));
+
+        assert_eq!(read_to_end(&mut stream).await, b"foobarbaz");
+    }
+
+    #[tokio::test]
+    async fn remote_close_waits_for_all_preceding_data() {
+        let (mut stream, _input) = test_stream();
+
+        // the close frame overtook the data in the mixnet
+        deliver(&stream, StreamFrame::close(STREAM_ID, 2));
+        deliver(&stream, StreamFrame::data(STREAM_ID, 1, b"bar".to_vec()));
+
+        let mut buf = [0u8; 16];
+        let pending = tokio::time::timeout(Duration::from_millis(100), stream.read(&mut buf)).await;
+        assert!(
+            pending.is_err(),
+            "EOF has been reported before receiving all the data"
+        );
+
+        deliver(&stream, StreamFrame::data(STREAM_ID, 0, b"foo".to_vec()));
+        assert_eq!(read_to_end(&mut stream).await, b"foobar");
+    }
+
+    #[tokio::test]
+    async fn data_outside_of_receive_window_is_dropped() {
+        let (mut stream, _input) = test_stream();
+
+        deliver(
+            &stream,
+            StreamFrame::data(STREAM_ID, RECEIVE_WINDOW, b"too far".to_vec()),
+        );
+        deliver(&stream, StreamFrame::data(STREAM_ID, 0, b"foo".to_vec()));
+        deliver(&stream, StreamFrame::close(STREAM_ID, 1));
+
+        assert_eq!(read_to_end(&mut stream).await, b"foo");
+        assert!(stream.reorder_buffer.read().is_none());
+    }
+
+    #[tokio::test]
+    async fn window_is_granted_once_half_of_it_is_read() {
+        let (mut stream, mut input) = test_stream();
+
+        let frames = RECEIVE_WINDOW / 2;
+        for sequence in 0..frames {
+            deliver(&stream, StreamFrame::data(STREAM_ID, sequence, vec![1]));
+        }
+        let mut buf = vec![0u8; frames as usize];
+        stream.read_exact(&mut buf).await.unwrap();
+
+        // the window is only granted once all the received data has been read
+        assert!(sent_frames(&mut input).is_empty());
+        let pending = tokio::time::timeout(Duration::from_millis(100), stream.read(&mut buf)).await;
+        assert!(pending.is_err());
+
+        assert_eq!(
+            sent_frames(&mut input),
+            vec![StreamFrame::window_update(
+                STREAM_ID,
+                frames + RECEIVE_WINDOW
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_are_suspended_until_the_remote_grants_window() {
+        let (mut stream, mut input) = test_stream();
+
+        for _ in 0..RECEIVE_WINDOW {
+            stream.write_all(&[1]).await.unwrap();
+        }
+        let suspended = tokio::time::timeout(Duration::from_millis(100), stream.write(&[2])).await;
+        assert!(suspended.is_err(), "wrote beyond the receive window");
+        assert_eq!(sent_frames(&mut input).len() as u64, RECEIVE_WINDOW);
+
+        deliver(
+            &stream,
+            StreamFrame::window_update(STREAM_ID, RECEIVE_WINDOW + 1),
+        );
+        stream.write_all(&[2]).await.unwrap();
+        assert_eq!(
+            sent_frames(&mut input),
+            vec![StreamFrame::data(STREAM_ID, RECEIVE_WINDOW, vec![2])]
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_notifies_the_remote() {
+        let (mut stream, mut input) = test_stream();
+
+        stream.write_all(b"foo").await.unwrap();
+        stream.shutdown().await.unwrap();
+        assert!(stream.write(b"bar").await.is_err());
+
+        assert_eq!(
+            sent_frames(&mut input),
+            vec![
+                StreamFrame::data(STREAM_ID, 0, b"foo".to_vec()),
+                StreamFrame::close(STREAM_ID, 1),
+            ]
+        );
+
+        // nothing more is sent once the stream is dropped
+        drop(stream);
+        assert!(sent_frames(&mut input).is_empty());
+    }
+
+    #[tokio::test]
+    async fn dropping_the_stream_closes_it() {
+        let (mut stream, mut input) = test_stream();
+        let registry = stream.context.registry.clone();
+
+        stream.write_all(b"foo").await.unwrap();
+        drop(stream);
+
+        assert!(registry.lock().unwrap().is_empty());
+        assert_eq!(
+            sent_frames(&mut input),
+            vec![
+                StreamFrame::data(STREAM_ID, 0, b"foo".to_vec()),
+                StreamFrame::close(STREAM_ID, 1),
+            ]
+        );
+    }
+}
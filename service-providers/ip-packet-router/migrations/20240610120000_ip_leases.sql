@@ -0,0 +1,18 @@
+/*
+ * Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+ * SPDX-License-Identifier: GPL-3.0-only
+ */
+
+CREATE TABLE ip_lease
+(
+    nym_address   TEXT    NOT NULL PRIMARY KEY,
+    ipv4          TEXT    NOT NULL UNIQUE,
+    ipv6          TEXT    NOT NULL UNIQUE,
+    mix_hops      INTEGER,
+
+    -- unix timestamp of when the lease expires, NULL while the client is connected
+    expires_at    INTEGER,
+
+    -- unix timestamp of the last time the client has sent us anything
+    last_activity INTEGER NOT NULL
+);
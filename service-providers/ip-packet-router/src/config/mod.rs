@@ -1,5 +1,6 @@
 pub use nym_client_core::config::Config as BaseClientConfig;
 
+use ipnetwork::{Ipv4Network, Ipv6Network};
 use nym_bin_common::logging::LoggingSettings;
 use nym_client_core::{
     cli_helpers::client_init::ClientConfig, config::disk_persistence::CommonClientPaths,
@@ -9,16 +10,22 @@ use nym_config::{
     serde_helpers::de_maybe_stringified, NymConfigTemplate, OptionalSet, DEFAULT_CONFIG_DIR,
     DEFAULT_CONFIG_FILENAME, DEFAULT_DATA_DIR, NYM_DIR,
 };
+use nym_sdk::mixnet::Recipient;
 use nym_service_providers_common::DEFAULT_SERVICE_PROVIDERS_DIR;
 use serde::{Deserialize, Serialize};
 use std::{
     io,
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 use url::Url;
 
 use crate::config::persistence::IpPacketRouterPaths;
+use crate::constants::{
+    DEFAULT_CLIENT_LEASE_EXPIRY, DEFAULT_IPV4_ADDRESS_POOL, DEFAULT_IPV6_ADDRESS_POOL,
+};
+use crate::error::IpPacketRouterError;
 
 use self::template::CONFIG_TEMPLATE;
 
@@ -193,6 +200,87 @@ pub struct IpPacketRouter {
     /// Specifies the url for an upstream source of the exit policy used by this node.
     #[serde(deserialize_with = "de_maybe_stringified")]
     pub upstream_exit_policy_url: Option<Url>,
+
+    /// The IPv4 network out of which addresses are assigned to the connected clients.
+    /// Its first host address is assigned to the TUN device.
+    pub ipv4_address_pool: Ipv4Network,
+
+    /// The IPv6 network out of which addresses are assigned to the connected clients.
+    /// Its first host address is assigned to the TUN device.
+    pub ipv6_address_pool: Ipv6Network,
+
+    /// Specifies whether the router should set up masquerading (NAT44 and NAT66) of the traffic
+    /// of the connected clients itself. If disabled, the address pools have to be routed manually.
+    pub configure_nat: bool,
+
+    /// Specifies for how long the addresses of a disconnected client are kept reserved for it,
+    /// so that it would get the same addresses back if it reconnects.
+    #[serde(with = "humantime_serde")]
+    pub client_lease_expiry: Duration,
+
+    /// Nym addresses that are allowed to query the addresses leased to the clients.
+    pub admin_addresses: Vec<String>,
+
+    /// The default limits applied to the traffic of each connected client.
+    pub client_rate_limit: ClientRateLimit,
+
+    /// Limits applied to the traffic of particular clients instead of the default ones.
+    pub client_rate_limit_overrides: Vec<ClientRateLimitOverride>,
+}
+
+impl IpPacketRouter {
+    /// Get the limits that should be applied to the traffic of the specified client.
+    pub fn rate_limit_for(&self, nym_address: &Recipient) -> ClientRateLimit {
+        self.client_rate_limit_overrides
+            .iter()
+            .find(|o| Recipient::try_from_base58_string(&o.nym_address).ok() == Some(*nym_address))
+            .map(|o| o.limit)
+            .unwrap_or(self.client_rate_limit)
+    }
+
+    /// Parse the nym addresses of the admins, rejecting the config if any of them is malformed.
+    pub fn parsed_admin_addresses(&self) -> Result<Vec<Recipient>, IpPacketRouterError> {
+        self.admin_addresses
+            .iter()
+            .map(|address| {
+                Recipient::try_from_base58_string(address).map_err(|source| {
+                    IpPacketRouterError::InvalidAdminAddress {
+                        address: address.clone(),
+                        source,
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Limits applied to the traffic of a single client, separately in each direction.
+/// Packets exceeding the limits get dropped.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientRateLimit {
+    /// Maximum sustained bandwidth in bytes per second. Set to 0 to disable the limit.
+    pub bytes_per_second: u64,
+
+    /// Maximum number of bytes that can be sent in a single burst.
+    /// If set to 0, it defaults to a second worth of traffic.
+    pub burst_bytes: u64,
+
+    /// Maximum sustained number of packets per second. Set to 0 to disable the limit.
+    pub packets_per_second: u64,
+
+    /// Maximum number of packets that can be sent in a single burst.
+    /// If set to 0, it defaults to a second worth of traffic.
+    pub burst_packets: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientRateLimitOverride {
+    /// Nym address of the client the limits apply to.
+    pub nym_address: String,
+
+    pub limit: ClientRateLimit,
 }
 
 impl Default for IpPacketRouter {
@@ -204,6 +292,17 @@ impl Default for IpPacketRouter {
                     .parse()
                     .expect("invalid default exit policy URL"),
             ),
+            ipv4_address_pool: DEFAULT_IPV4_ADDRESS_POOL
+                .parse()
+                .expect("invalid default IPv4 address pool"),
+            ipv6_address_pool: DEFAULT_IPV6_ADDRESS_POOL
+                .parse()
+                .expect("invalid default IPv6 address pool"),
+            configure_nat: true,
+            client_lease_expiry: DEFAULT_CLIENT_LEASE_EXPIRY,
+            admin_addresses: Vec::new(),
+            client_rate_limit: Default::default(),
+            client_rate_limit_overrides: Vec::new(),
         }
     }
 }
@@ -2,10 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use nym_client_core::config::disk_persistence::CommonClientPaths;
+use nym_config::serde_helpers::de_maybe_path;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
 pub const DEFAULT_DESCRIPTION_FILENAME: &str = "description.toml";
+pub const DEFAULT_IP_LEASE_DATABASE_FILENAME: &str = "ip_leases.sqlite";
 
 #[derive(Debug, Deserialize, PartialEq, Eq, Serialize, Clone)]
 pub struct IpPacketRouterPaths {
@@ -14,6 +16,11 @@ pub struct IpPacketRouterPaths {
 
     /// Location of the file containing our description
     pub ip_packet_router_description: PathBuf,
+
+    /// Path to the database containing the addresses leased to the clients, so that they
+    /// would survive restarts of the router. If not set, it's kept in the data directory.
+    #[serde(default, deserialize_with = "de_maybe_path")]
+    pub ip_lease_database: Option<PathBuf>,
 }
 
 impl IpPacketRouterPaths {
@@ -23,6 +30,46 @@ impl IpPacketRouterPaths {
         Self {
             common_paths: CommonClientPaths::new_base(base_dir),
             ip_packet_router_description: base_dir.join(DEFAULT_DESCRIPTION_FILENAME),
+            ip_lease_database: Some(base_dir.join(DEFAULT_IP_LEASE_DATABASE_FILENAME)),
         }
     }
+
+    /// Location of the lease database. Configs created before it got introduced don't specify it,
+    /// in which case it's placed next to the description file, i.e. in the data directory.
+    pub fn ip_lease_database(&self) -> PathBuf {
+        match &self.ip_lease_database {
+            Some(path) => path.clone(),
+            None => self
+                .ip_packet_router_description
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .join(DEFAULT_IP_LEASE_DATABASE_FILENAME),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_lease_database_defaults_to_the_data_directory() {
+        let mut paths = IpPacketRouterPaths::new_base("/data/ipr");
+        assert_eq!(
+            paths.ip_lease_database(),
+            PathBuf::from("/data/ipr").join(DEFAULT_IP_LEASE_DATABASE_FILENAME)
+        );
+
+        paths.ip_lease_database = None;
+        assert_eq!(
+            paths.ip_lease_database(),
+            PathBuf::from("/data/ipr").join(DEFAULT_IP_LEASE_DATABASE_FILENAME)
+        );
+
+        paths.ip_lease_database = Some(PathBuf::from("/elsewhere/leases.sqlite"));
+        assert_eq!(
+            paths.ip_lease_database(),
+            PathBuf::from("/elsewhere/leases.sqlite")
+        );
+    }
 }
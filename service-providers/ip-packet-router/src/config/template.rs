@@ -78,6 +78,63 @@ unknown_list_location = '{{ storage_paths.unknown_list_location }}'
 # Path to file containing description of this network-requester.
 ip_packet_router_description = '{{ storage_paths.ip_packet_router_description }}'
 
+# Path to the database containing the addresses leased to the clients, so that they
+# would survive restarts of the router.
+ip_lease_database = '{{ storage_paths.ip_lease_database }}'
+
+
+##### ip packet router config options #####
+
+[ip_packet_router]
+
+# Disable Poisson sending rate.
+disable_poisson_rate = {{ ip_packet_router.disable_poisson_rate }}
+
+# Specifies the url for an upstream source of the exit policy used by this node.
+upstream_exit_policy_url = '{{ ip_packet_router.upstream_exit_policy_url }}'
+
+# The IPv4 network out of which addresses are assigned to the connected clients.
+# Its first host address is assigned to the TUN device.
+ipv4_address_pool = '{{ ip_packet_router.ipv4_address_pool }}'
+
+# The IPv6 network out of which addresses are assigned to the connected clients.
+# Its first host address is assigned to the TUN device.
+ipv6_address_pool = '{{ ip_packet_router.ipv6_address_pool }}'
+
+# Specifies whether the router should set up masquerading (NAT44 and NAT66) of the traffic
+# of the connected clients itself. If disabled, the address pools have to be routed manually.
+configure_nat = {{ ip_packet_router.configure_nat }}
+
+# Specifies for how long the addresses of a disconnected client are kept reserved for it,
+# so that it would get the same addresses back if it reconnects.
+client_lease_expiry = '{{ ip_packet_router.client_lease_expiry }}'
+
+# Nym addresses that are allowed to query the addresses leased to the clients.
+admin_addresses = [
+    {{#each ip_packet_router.admin_addresses }}
+        '{{this}}',
+    {{/each}}
+]
+
+# The default limits applied to the traffic of each connected client, separately in each direction.
+# Packets exceeding the limits get dropped. Set the rates to 0 to disable the limits. If the bursts
+# are set to 0, they default to a second worth of traffic.
+[ip_packet_router.client_rate_limit]
+bytes_per_second = {{ ip_packet_router.client_rate_limit.bytes_per_second }}
+burst_bytes = {{ ip_packet_router.client_rate_limit.burst_bytes }}
+packets_per_second = {{ ip_packet_router.client_rate_limit.packets_per_second }}
+burst_packets = {{ ip_packet_router.client_rate_limit.burst_packets }}
+
+# Limits applied to the traffic of particular clients instead of the default ones.
+{{#each ip_packet_router.client_rate_limit_overrides }}
+[[ip_packet_router.client_rate_limit_overrides]]
+nym_address = '{{this.nym_address}}'
+limit.bytes_per_second = {{this.limit.bytes_per_second}}
+limit.burst_bytes = {{this.limit.burst_bytes}}
+limit.packets_per_second = {{this.limit.packets_per_second}}
+limit.burst_packets = {{this.limit.burst_packets}}
+{{/each}}
+
 
 ##### logging configuration options #####
 
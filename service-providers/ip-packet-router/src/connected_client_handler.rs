@@ -8,7 +8,7 @@ use nym_sdk::mixnet::{MixnetMessageSender, Recipient};
 use crate::{
     constants::CLIENT_HANDLER_ACTIVITY_TIMEOUT,
     error::{IpPacketRouterError, Result},
-    util::create_message::create_input_message,
+    util::{create_message::create_input_message, rate_limit::ClientRateLimiter},
 };
 
 // Data flow
@@ -25,6 +25,7 @@ pub(crate) struct ConnectedClientHandler {
     close_rx: tokio::sync::oneshot::Receiver<()>,
     activity_timeout: tokio::time::Interval,
     encoder: MultiIpPacketCodec,
+    rate_limiter: ClientRateLimiter,
 }
 
 impl ConnectedClientHandler {
@@ -33,6 +34,7 @@ impl ConnectedClientHandler {
         reply_to_hops: Option<u8>,
         buffer_timeout: std::time::Duration,
         mixnet_client_sender: nym_sdk::mixnet::MixnetClientSender,
+        rate_limiter: ClientRateLimiter,
     ) -> (
         tokio::sync::mpsc::UnboundedSender<Vec<u8>>,
         tokio::sync::oneshot::Sender<()>,
@@ -55,6 +57,7 @@ impl ConnectedClientHandler {
             close_rx,
             activity_timeout,
             encoder,
+            rate_limiter,
         };
 
         let handle = tokio::spawn(async move {
@@ -89,6 +92,14 @@ impl ConnectedClientHandler {
     async fn handle_packet(&mut self, packet: Vec<u8>) -> Result<()> {
         self.activity_timeout.reset();
 
+        if !self
+            .rate_limiter
+            .allow(packet.len(), std::time::Instant::now())
+        {
+            log::trace!("client handler: dropping packet exceeding the rate limit");
+            return Ok(());
+        }
+
         if let Some(bundled_packets) = self.encoder.append_packet(packet.into()) {
             self.send_packets_to_mixnet(bundled_packets).await
         } else {
@@ -126,6 +137,14 @@ impl ConnectedClientHandler {
             }
         }
 
+        let (throttled_packets, throttled_bytes) = self.rate_limiter.throttled();
+        if throttled_packets > 0 {
+            log::info!(
+                "client handler: throttled {throttled_packets} packets ({throttled_bytes} bytes) sent to {}",
+                self.nym_address
+            );
+        }
+
         log::debug!("ConnectedClientHandler: exiting");
         Ok(())
     }
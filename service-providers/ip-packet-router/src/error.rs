@@ -81,6 +81,18 @@ pub enum IpPacketRouterError {
 
     #[error("failed to update client activity")]
     FailedToUpdateClientActivity,
+
+    #[error("the address pool {pool} is too small to assign addresses to any clients")]
+    AddressPoolTooSmall { pool: String },
+
+    #[error("the admin address '{address}' is malformed: {source}")]
+    InvalidAdminAddress {
+        address: String,
+        source: nym_sphinx::addressing::clients::RecipientFormattingError,
+    },
+
+    #[error("lease storage failure: {0}")]
+    LeaseStorageError(#[from] crate::storage::error::IpLeaseStorageError),
 }
 
 pub type Result<T> = std::result::Result<T, IpPacketRouterError>;
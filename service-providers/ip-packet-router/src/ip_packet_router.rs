@@ -15,6 +15,8 @@ use crate::{
     config::Config,
     error::IpPacketRouterError,
     request_filter::{self, RequestFilter},
+    storage::IpLeaseStorage,
+    util::{ip_pool::IpPool, nat},
 };
 
 pub struct OnStartData {
@@ -117,6 +119,9 @@ impl IpPacketRouter {
         use crate::{mixnet_listener, tun_listener};
         let task_handle: TaskHandle = self.shutdown.map(Into::into).unwrap_or_default();
 
+        // Reject malformed admin addresses upfront rather than silently ignoring them
+        let admin_addresses = self.config.ip_packet_router.parsed_admin_addresses()?;
+
         // Connect to the mixnet
         let mixnet_client = crate::mixnet_client::create_mixnet_client(
             &self.config.base,
@@ -130,20 +135,40 @@ impl IpPacketRouter {
 
         let self_address = *mixnet_client.nym_address();
 
+        // The pools of addresses we assign to the connected clients
+        let ip_pool = IpPool::new(
+            self.config.ip_packet_router.ipv4_address_pool,
+            self.config.ip_packet_router.ipv6_address_pool,
+            self.config.ip_packet_router.client_lease_expiry,
+        )?;
+
         // Create the TUN device that we interact with the rest of the world with
+        let tun_ips = ip_pool.tun_ips();
         let config = nym_tun::tun_device::TunDeviceConfig {
             base_name: crate::constants::TUN_BASE_NAME.to_string(),
-            ipv4: crate::constants::TUN_DEVICE_ADDRESS_V4,
-            netmaskv4: crate::constants::TUN_DEVICE_NETMASK_V4,
-            ipv6: crate::constants::TUN_DEVICE_ADDRESS_V6,
-            netmaskv6: crate::constants::TUN_DEVICE_NETMASK_V6.to_string(),
+            ipv4: tun_ips.ipv4,
+            netmaskv4: ip_pool.ipv4_network().mask(),
+            ipv6: tun_ips.ipv6,
+            netmaskv6: ip_pool.ipv6_network().prefix().to_string(),
         };
-        let (tun_reader, tun_writer) =
-            tokio::io::split(nym_tun::tun_device::TunDevice::new_device_only(config)?);
+        let tun = nym_tun::tun_device::TunDevice::new_device_only(config)?;
+        check_ip_forwarding();
+        if nat::is_documentation_network(ip_pool.ipv6_network()) {
+            log::warn!(
+                "the IPv6 address pool {} is within the documentation prefix and can't be routed",
+                ip_pool.ipv6_network()
+            );
+        }
+        // the rules are kept for as long as the router is running
+        let _masquerade_rules = self.config.ip_packet_router.configure_nat.then(|| {
+            nat::setup_masquerade(tun.name(), ip_pool.ipv4_network(), ip_pool.ipv6_network())
+        });
+        let (tun_reader, tun_writer) = tokio::io::split(tun);
 
         // Channel used by the IpPacketRouter to signal connected and disconnected clients to the
         // TunListener
-        let (connected_clients, connected_clients_rx) = mixnet_listener::ConnectedClients::new();
+        let (connected_clients, connected_clients_rx) =
+            mixnet_listener::ConnectedClients::new(ip_pool);
 
         let tun_listener = tun_listener::TunListener {
             tun_reader,
@@ -155,14 +180,22 @@ impl IpPacketRouter {
         let request_filter = request_filter::RequestFilter::new(&self.config).await?;
         request_filter.start_update_tasks().await;
 
-        let mixnet_listener = mixnet_listener::MixnetListener {
-            _config: self.config,
+        let lease_storage =
+            IpLeaseStorage::init(self.config.storage_paths.ip_lease_database()).await?;
+
+        let mut mixnet_listener = mixnet_listener::MixnetListener {
+            config: self.config,
+            admin_addresses,
+            lease_storage,
+            persisted_activity: Default::default(),
+            pending_lease_expiries: Default::default(),
             request_filter: request_filter.clone(),
             tun_writer,
             mixnet_client,
             task_handle,
             connected_clients,
         };
+        mixnet_listener.restore_leases().await?;
 
         log::info!("The address of this client is: {self_address}");
         log::info!("All systems go. Press CTRL-C to stop the server.");
@@ -180,3 +213,21 @@ impl IpPacketRouter {
         mixnet_listener.run().await
     }
 }
+
+// Packets are only going to be routed between the TUN device and the outside world if the kernel is
+// configured to forward them, so let the operator know if that's not the case
+#[cfg(target_os = "linux")]
+fn check_ip_forwarding() {
+    for (family, path) in [
+        ("IPv4", "/proc/sys/net/ipv4/ip_forward"),
+        ("IPv6", "/proc/sys/net/ipv6/conf/all/forwarding"),
+    ] {
+        match std::fs::read_to_string(path) {
+            Ok(value) if value.trim() == "1" => {}
+            Ok(_) => log::warn!(
+                "{family} forwarding is disabled ({path}): {family} traffic of the connected clients won't be routed"
+            ),
+            Err(err) => log::warn!("failed to check whether {family} forwarding is enabled: {err}"),
+        }
+    }
+}
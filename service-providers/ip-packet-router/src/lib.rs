@@ -12,5 +12,6 @@ mod ip_packet_router;
 mod mixnet_client;
 mod mixnet_listener;
 pub mod request_filter;
+mod storage;
 mod tun_listener;
 mod util;
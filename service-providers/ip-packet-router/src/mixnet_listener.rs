@@ -1,6 +1,10 @@
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
-use std::{collections::HashMap, net::SocketAddr};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    net::SocketAddr,
+};
 
 use bytes::{Bytes, BytesMut};
 use futures::StreamExt;
@@ -9,7 +13,7 @@ use nym_ip_packet_requests::{
     codec::MultiIpPacketCodec,
     request::{IpPacketRequest, IpPacketRequestData},
     response::{
-        DynamicConnectFailureReason, InfoResponseReply, IpPacketResponse,
+        DynamicConnectFailureReason, InfoResponseReply, IpPacketResponse, LeaseQueryFailureReason,
         StaticConnectFailureReason,
     },
     IpPair,
@@ -24,16 +28,21 @@ use tokio::sync::RwLock;
 use tokio_util::codec::Decoder;
 
 use crate::{
-    config::Config,
+    config::{ClientRateLimit, Config},
     connected_client_handler,
-    constants::{CLIENT_MIXNET_INACTIVITY_TIMEOUT, DISCONNECT_TIMER_INTERVAL},
+    constants::{
+        CLIENT_MIXNET_INACTIVITY_TIMEOUT, DISCONNECT_TIMER_INTERVAL,
+        LEASE_ACTIVITY_PERSISTENCE_RESOLUTION,
+    },
     error::{IpPacketRouterError, Result},
     request_filter::{self},
+    storage::{self, ClientLease, IpLeaseStorage},
     tun_listener,
-    util::generate_new_ip,
+    util::ip_pool::IpPool,
     util::{
         create_message::create_input_message,
         parse_ip::{parse_packet, ParsedPacket},
+        rate_limit::{ClientRateLimiter, ThrottleStats},
     },
 };
 
@@ -42,17 +51,29 @@ pub(crate) struct ConnectedClients {
     clients_ipv4_mapping: HashMap<Ipv4Addr, ConnectedClient>,
     clients_ipv6_mapping: HashMap<Ipv6Addr, ConnectedClient>,
 
+    // The addresses leased to the clients, including the ones that have recently disconnected
+    ip_pool: IpPool<Recipient>,
+
+    // The traffic dropped due to the rate limits, across all the clients
+    throttle_stats: Arc<ThrottleStats>,
+    reported_throttle_stats: (u64, u64),
+
     // Notify the tun listener when a new client connects or disconnects
     tun_listener_connected_client_tx: tokio::sync::mpsc::UnboundedSender<ConnectedClientEvent>,
 }
 
 impl ConnectedClients {
-    pub(crate) fn new() -> (Self, tun_listener::ConnectedClientsListener) {
+    pub(crate) fn new(
+        ip_pool: IpPool<Recipient>,
+    ) -> (Self, tun_listener::ConnectedClientsListener) {
         let (connected_client_tx, connected_client_rx) = tokio::sync::mpsc::unbounded_channel();
         (
             Self {
                 clients_ipv4_mapping: Default::default(),
                 clients_ipv6_mapping: Default::default(),
+                ip_pool,
+                throttle_stats: Default::default(),
+                reported_throttle_stats: (0, 0),
                 tun_listener_connected_client_tx: connected_client_tx,
             },
             tun_listener::ConnectedClientsListener::new(connected_client_rx),
@@ -106,6 +127,7 @@ impl ConnectedClients {
         ips: IpPair,
         nym_address: Recipient,
         mix_hops: Option<u8>,
+        rate_limit: ClientRateLimit,
         forward_from_tun_tx: tokio::sync::mpsc::UnboundedSender<Vec<u8>>,
         close_tx: tokio::sync::oneshot::Sender<()>,
         handle: tokio::task::JoinHandle<()>,
@@ -117,6 +139,7 @@ impl ConnectedClients {
             ipv6: ips.ipv6,
             mix_hops,
             last_activity: Arc::new(RwLock::new(std::time::Instant::now())),
+            rate_limiter: Arc::new(std::sync::Mutex::new(self.new_rate_limiter(rate_limit))),
             _close_tx: Arc::new(CloseTx {
                 nym_address,
                 inner: Some(close_tx),
@@ -148,6 +171,17 @@ impl ConnectedClients {
         }
     }
 
+    async fn get_last_activity(&self) -> Vec<(Recipient, std::time::Instant)> {
+        let mut ret = vec![];
+        for connected_client in self.clients_ipv4_mapping.values() {
+            ret.push((
+                connected_client.nym_address,
+                *connected_client.last_activity.read().await,
+            ))
+        }
+        ret
+    }
+
     // Identify connected client handlers that have stopped without being told to stop
     fn get_finished_client_handlers(&mut self) -> Vec<(IpPair, Recipient)> {
         self.clients_ipv4_mapping
@@ -182,10 +216,12 @@ impl ConnectedClients {
     }
 
     fn disconnect_stopped_client_handlers(&mut self, stopped_clients: Vec<(IpPair, Recipient)>) {
+        let now = std::time::Instant::now();
         for (ips, _) in &stopped_clients {
             log::info!("Disconnect stopped client: {ips}");
             self.clients_ipv4_mapping.remove(&ips.ipv4);
             self.clients_ipv6_mapping.remove(&ips.ipv6);
+            self.ip_pool.release(*ips, now);
             self.tun_listener_connected_client_tx
                 .send(ConnectedClientEvent::Disconnect(DisconnectEvent(*ips)))
                 .tap_err(|err| {
@@ -196,10 +232,12 @@ impl ConnectedClients {
     }
 
     fn disconnect_inactive_clients(&mut self, inactive_clients: Vec<(IpPair, Recipient)>) {
+        let now = std::time::Instant::now();
         for (ips, _) in &inactive_clients {
             log::info!("Disconnect inactive client: {ips}");
             self.clients_ipv4_mapping.remove(&ips.ipv4);
             self.clients_ipv6_mapping.remove(&ips.ipv6);
+            self.ip_pool.release(*ips, now);
             self.tun_listener_connected_client_tx
                 .send(ConnectedClientEvent::Disconnect(DisconnectEvent(*ips)))
                 .tap_err(|err| {
@@ -209,8 +247,55 @@ impl ConnectedClients {
         }
     }
 
-    fn find_new_ip(&self) -> Option<IpPair> {
-        generate_new_ip::find_new_ips(&self.clients_ipv4_mapping, &self.clients_ipv6_mapping)
+    // Lease new addresses for the client. If it has only recently disconnected, it gets the same
+    // addresses it had before.
+    fn find_new_ip(&mut self, nym_address: Recipient) -> Option<IpPair> {
+        self.ip_pool
+            .allocate(nym_address, std::time::Instant::now())
+    }
+
+    // Lease the specific addresses requested by the client, if they're available
+    fn reserve_ips(&mut self, ips: IpPair, nym_address: Recipient) -> bool {
+        self.ip_pool
+            .reserve(nym_address, ips, std::time::Instant::now())
+    }
+
+    fn is_ip_in_pool(&self, ips: &IpPair) -> bool {
+        self.ip_pool.contains(*ips)
+    }
+
+    // Restore the lease persisted before the restart. If `expires_at` is `None`, the client was
+    // still connected at the time.
+    fn restore_lease(
+        &mut self,
+        ips: IpPair,
+        nym_address: Recipient,
+        expires_at: Option<std::time::Instant>,
+    ) -> bool {
+        self.ip_pool.restore(nym_address, ips, expires_at)
+    }
+
+    fn lease_expiry(&self) -> std::time::Duration {
+        self.ip_pool.lease_expiry()
+    }
+
+    // Create a rate limiter for a single direction of the traffic of a client
+    fn new_rate_limiter(&self, rate_limit: ClientRateLimit) -> ClientRateLimiter {
+        ClientRateLimiter::new(
+            rate_limit,
+            self.throttle_stats.clone(),
+            std::time::Instant::now(),
+        )
+    }
+
+    // Log the traffic dropped due to the rate limits, if there was any since the last time
+    fn report_throttled_traffic(&mut self) {
+        let totals = self.throttle_stats.totals();
+        if totals != self.reported_throttle_stats {
+            let (packets, bytes) = totals;
+            log::info!("Throttled {packets} packets ({bytes} bytes) in total due to rate limits");
+            self.reported_throttle_stats = totals;
+        }
     }
 }
 
@@ -236,6 +321,10 @@ pub(crate) struct ConnectedClient {
     // Keep track of last activity so we can disconnect inactive clients
     pub(crate) last_activity: Arc<RwLock<std::time::Instant>>,
 
+    // Limits the traffic the client sends through us. The traffic sent back to the client is
+    // limited by its connected client handler.
+    pub(crate) rate_limiter: Arc<std::sync::Mutex<ClientRateLimiter>>,
+
     pub(crate) _close_tx: Arc<CloseTx>,
 
     // Handle for the connected client handler
@@ -246,6 +335,13 @@ impl ConnectedClient {
     async fn update_activity(&self) {
         *self.last_activity.write().await = std::time::Instant::now();
     }
+
+    fn is_within_rate_limit(&self, packet_size: usize) -> bool {
+        self.rate_limiter
+            .lock()
+            .expect("rate limiter mutex poisoned")
+            .allow(packet_size, std::time::Instant::now())
+    }
 }
 
 impl Drop for CloseTx {
@@ -262,7 +358,21 @@ type PacketHandleResult = Result<Option<IpPacketResponse>>;
 #[cfg(target_os = "linux")]
 pub(crate) struct MixnetListener {
     // The configuration for the mixnet listener
-    pub(crate) _config: Config,
+    pub(crate) config: Config,
+
+    // The nym addresses that are allowed to query the leases
+    pub(crate) admin_addresses: Vec<Recipient>,
+
+    // The persistent storage of the addresses leased to the clients
+    pub(crate) lease_storage: IpLeaseStorage,
+
+    // The last activity of each connected client as it was last written to the lease storage, so
+    // that we'd only write it again once it has changed
+    pub(crate) persisted_activity: HashMap<Recipient, std::time::Instant>,
+
+    // The expiry times of the leases released by the disconnected clients, so that we'd only
+    // remove the expired leases from the storage once any of them is due
+    pub(crate) pending_lease_expiries: BinaryHeap<Reverse<i64>>,
 
     // The request filter that we use to check if a packet should be forwarded
     pub(crate) request_filter: request_filter::RequestFilter,
@@ -324,15 +434,36 @@ impl MixnetListener {
                 )))
             }
             (false, false) => {
+                if !self.connected_clients.is_ip_in_pool(&requested_ips) {
+                    log::info!("Requested IP is outside of the address pool");
+                    return Ok(Some(IpPacketResponse::new_static_connect_failure(
+                        request_id,
+                        reply_to,
+                        StaticConnectFailureReason::Other(
+                            "requested ip address is outside of the address pool".to_string(),
+                        ),
+                    )));
+                }
+                if !self.connected_clients.reserve_ips(requested_ips, reply_to) {
+                    log::info!("Requested IP is still leased to another client");
+                    return Ok(Some(IpPacketResponse::new_static_connect_failure(
+                        request_id,
+                        reply_to,
+                        StaticConnectFailureReason::RequestedIpAlreadyInUse,
+                    )));
+                }
+
                 log::info!("Connecting a new client");
 
                 // Spawn the ConnectedClientHandler for the new client
+                let rate_limit = self.config.ip_packet_router.rate_limit_for(&reply_to);
                 let (forward_from_tun_tx, close_tx, handle) =
                     connected_client_handler::ConnectedClientHandler::start(
                         reply_to,
                         reply_to_hops,
                         buffer_timeout,
                         self.mixnet_client.split_sender(),
+                        self.connected_clients.new_rate_limiter(rate_limit),
                     );
 
                 // Register the new client in the set of connected clients
@@ -340,10 +471,13 @@ impl MixnetListener {
                     requested_ips,
                     reply_to,
                     reply_to_hops,
+                    rate_limit,
                     forward_from_tun_tx,
                     close_tx,
                     handle,
                 );
+                self.persist_lease(reply_to, requested_ips, reply_to_hops)
+                    .await;
                 Ok(Some(IpPacketResponse::new_static_connect_success(
                     request_id, reply_to,
                 )))
@@ -404,7 +538,7 @@ impl MixnetListener {
             )));
         }
 
-        let Some(new_ips) = self.connected_clients.find_new_ip() else {
+        let Some(new_ips) = self.connected_clients.find_new_ip(reply_to) else {
             log::info!("No available IP address");
             return Ok(Some(IpPacketResponse::new_dynamic_connect_failure(
                 request_id,
@@ -414,12 +548,14 @@ impl MixnetListener {
         };
 
         // Spawn the ConnectedClientHandler for the new client
+        let rate_limit = self.config.ip_packet_router.rate_limit_for(&reply_to);
         let (forward_from_tun_tx, close_tx, handle) =
             connected_client_handler::ConnectedClientHandler::start(
                 reply_to,
                 reply_to_hops,
                 buffer_timeout,
                 self.mixnet_client.split_sender(),
+                self.connected_clients.new_rate_limiter(rate_limit),
             );
 
         // Register the new client in the set of connected clients
@@ -427,15 +563,56 @@ impl MixnetListener {
             new_ips,
             reply_to,
             reply_to_hops,
+            rate_limit,
             forward_from_tun_tx,
             close_tx,
             handle,
         );
+        self.persist_lease(reply_to, new_ips, reply_to_hops).await;
         Ok(Some(IpPacketResponse::new_dynamic_connect_success(
             request_id, reply_to, new_ips,
         )))
     }
 
+    // Only the operators of the router, as specified in the config, are allowed to see the leases
+    fn is_admin(&self, nym_address: &Recipient) -> bool {
+        self.admin_addresses.contains(nym_address)
+    }
+
+    async fn on_lease_query_request(
+        &self,
+        lease_query_request: nym_ip_packet_requests::request::LeaseQueryRequest,
+    ) -> PacketHandleResult {
+        let request_id = lease_query_request.request_id;
+        let reply_to = lease_query_request.reply_to;
+        log::info!("Received lease query request from {reply_to}");
+
+        if !self.is_admin(&reply_to) {
+            log::info!("Lease query request from {reply_to} is not authorized");
+            return Ok(Some(IpPacketResponse::new_lease_query_failure(
+                request_id,
+                reply_to,
+                LeaseQueryFailureReason::NotAuthorized,
+            )));
+        }
+
+        match self.lease_storage.load_leases().await {
+            Ok(leases) => Ok(Some(IpPacketResponse::new_lease_query_success(
+                request_id,
+                reply_to,
+                leases.into_iter().map(Into::into).collect(),
+            ))),
+            Err(err) => {
+                log::error!("Failed to load the stored leases: {err}");
+                Ok(Some(IpPacketResponse::new_lease_query_failure(
+                    request_id,
+                    reply_to,
+                    LeaseQueryFailureReason::Other(err.to_string()),
+                )))
+            }
+        }
+    }
+
     fn on_disconnect_request(
         &self,
         _disconnect_request: nym_ip_packet_requests::request::DisconnectRequest,
@@ -468,6 +645,11 @@ impl MixnetListener {
             // Keep track of activity so we can disconnect inactive clients
             connected_client.update_activity().await;
 
+            if !connected_client.is_within_rate_limit(ip_packet.len()) {
+                log::trace!("dropping packet from {src_addr}: exceeded the rate limit");
+                return Ok(None);
+            }
+
             // For packets without a port, use 0.
             let dst = dst.unwrap_or_else(|| SocketAddr::new(dst_addr, 0));
 
@@ -575,6 +757,9 @@ impl MixnetListener {
                 log::info!("Received health request: not implemented, dropping");
                 Ok(vec![])
             }
+            IpPacketRequestData::LeaseQuery(lease_query_request) => {
+                Ok(vec![self.on_lease_query_request(lease_query_request).await])
+            }
         }
     }
 
@@ -590,10 +775,178 @@ impl MixnetListener {
         //    }
         //}
 
+        let disconnected = stopped_clients
+            .iter()
+            .chain(inactive_clients.iter())
+            .map(|(_, nym_address)| *nym_address)
+            .collect::<Vec<_>>();
+
         self.connected_clients
             .disconnect_stopped_client_handlers(stopped_clients);
         self.connected_clients
             .disconnect_inactive_clients(inactive_clients);
+
+        self.update_stored_leases(disconnected).await;
+        self.connected_clients.report_throttled_traffic();
+    }
+
+    async fn persist_lease(&mut self, nym_address: Recipient, ips: IpPair, mix_hops: Option<u8>) {
+        let lease = ClientLease {
+            nym_address,
+            ips,
+            mix_hops,
+            expires_at: None,
+            last_activity: storage::unix_now(),
+        };
+        match self.lease_storage.store_lease(&lease).await {
+            Ok(()) => {
+                self.persisted_activity
+                    .insert(nym_address, std::time::Instant::now());
+            }
+            Err(err) => log::error!("Failed to persist the lease of {nym_address}: {err}"),
+        }
+    }
+
+    // Keep the stored leases in sync with the connected clients
+    async fn update_stored_leases(&mut self, disconnected: Vec<Recipient>) {
+        let now = storage::unix_now();
+        let expires_at = now + self.connected_clients.lease_expiry().as_secs() as i64;
+        for nym_address in disconnected {
+            self.persisted_activity.remove(&nym_address);
+            if let Err(err) = self
+                .lease_storage
+                .release_lease(&nym_address, expires_at)
+                .await
+            {
+                log::error!("Failed to release the stored lease of {nym_address}: {err}");
+            }
+            self.pending_lease_expiries.push(Reverse(expires_at));
+        }
+
+        // Only write the activity of the clients that have been active since we last stored it
+        let changed = self
+            .connected_clients
+            .get_last_activity()
+            .await
+            .into_iter()
+            .filter(
+                |(nym_address, last_activity)| match self.persisted_activity.get(nym_address) {
+                    Some(persisted) => {
+                        last_activity.saturating_duration_since(*persisted)
+                            >= LEASE_ACTIVITY_PERSISTENCE_RESOLUTION
+                    }
+                    None => true,
+                },
+            )
+            .collect::<Vec<_>>();
+        if !changed.is_empty() {
+            let activity = changed
+                .iter()
+                .map(|(nym_address, last_activity)| {
+                    (*nym_address, storage::to_unix_timestamp(*last_activity))
+                })
+                .collect();
+            match self.lease_storage.update_last_activity(activity).await {
+                Ok(()) => self.persisted_activity.extend(changed),
+                Err(err) => {
+                    log::error!("Failed to update the last activity of the stored leases: {err}")
+                }
+            }
+        }
+
+        // Only touch the storage once some of the released leases are due to expire
+        let mut expired = false;
+        while let Some(Reverse(expires_at)) = self.pending_lease_expiries.peek() {
+            if *expires_at > now {
+                break;
+            }
+            self.pending_lease_expiries.pop();
+            expired = true;
+        }
+        if expired {
+            if let Err(err) = self.lease_storage.remove_expired_leases(now).await {
+                log::error!("Failed to remove the expired leases: {err}");
+            }
+        }
+    }
+
+    // Restore the leases persisted before the restart. The clients that were still active get
+    // their connected client handlers started again, so that their tunnels survive the restart.
+    pub(crate) async fn restore_leases(&mut self) -> Result<()> {
+        let lease_storage = &self.lease_storage;
+        let now = storage::unix_now();
+        lease_storage.remove_expired_leases(now).await?;
+        let leases = lease_storage.load_leases().await?;
+        let lease_expiry = self.connected_clients.lease_expiry();
+
+        let (mut restored_clients, mut restored_leases) = (0, 0);
+        for lease in leases {
+            let ClientLease {
+                nym_address,
+                ips,
+                mix_hops,
+                expires_at,
+                last_activity,
+            } = lease;
+
+            let was_active = now.saturating_sub(last_activity)
+                < CLIENT_MIXNET_INACTIVITY_TIMEOUT.as_secs() as i64;
+            let expires_at = match expires_at {
+                Some(expires_at) => expires_at,
+                None if was_active => {
+                    if !self.connected_clients.restore_lease(ips, nym_address, None) {
+                        log::warn!("Failed to restore the lease of {nym_address} for {ips}");
+                        continue;
+                    }
+
+                    let rate_limit = self.config.ip_packet_router.rate_limit_for(&nym_address);
+                    let (forward_from_tun_tx, close_tx, handle) =
+                        connected_client_handler::ConnectedClientHandler::start(
+                            nym_address,
+                            mix_hops,
+                            nym_ip_packet_requests::codec::BUFFER_TIMEOUT,
+                            self.mixnet_client.split_sender(),
+                            self.connected_clients.new_rate_limiter(rate_limit),
+                        );
+                    self.connected_clients.connect(
+                        ips,
+                        nym_address,
+                        mix_hops,
+                        rate_limit,
+                        forward_from_tun_tx,
+                        close_tx,
+                        handle,
+                    );
+                    restored_clients += 1;
+                    continue;
+                }
+                // the client went quiet before the restart, so treat it as disconnected
+                None => {
+                    let expires_at = now + lease_expiry.as_secs() as i64;
+                    lease_storage
+                        .release_lease(&nym_address, expires_at)
+                        .await?;
+                    expires_at
+                }
+            };
+
+            self.pending_lease_expiries.push(Reverse(expires_at));
+            let remaining = std::time::Duration::from_secs(expires_at.saturating_sub(now) as u64);
+            if self.connected_clients.restore_lease(
+                ips,
+                nym_address,
+                Some(std::time::Instant::now() + remaining),
+            ) {
+                restored_leases += 1;
+            } else {
+                log::warn!("Failed to restore the lease of {nym_address} for {ips}");
+            }
+        }
+
+        log::info!(
+            "Restored {restored_clients} connected clients and {restored_leases} leases of disconnected clients"
+        );
+        Ok(())
     }
 
     // When an incoming mixnet message triggers a response that we send back, such as during
@@ -0,0 +1,14 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+#[derive(Debug, thiserror::Error)]
+pub enum IpLeaseStorageError {
+    #[error("File system error - {0}")]
+    FSError(#[from] std::io::Error),
+
+    #[error("SQL error - {0}")]
+    InternalDatabaseError(#[from] sqlx::Error),
+
+    #[error("SQL migrate error - {0}")]
+    DatabaseMigrateError(#[from] sqlx::migrate::MigrateError),
+}
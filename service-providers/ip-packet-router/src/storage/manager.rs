@@ -0,0 +1,119 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::storage::models::StoredIpLease;
+
+#[derive(Clone)]
+pub(crate) struct StorageManager {
+    pub(crate) connection_pool: sqlx::SqlitePool,
+}
+
+// all SQL goes here
+impl StorageManager {
+    /// Returns all the leases currently stored.
+    pub(super) async fn get_leases(&self) -> Result<Vec<StoredIpLease>, sqlx::Error> {
+        sqlx::query_as!(StoredIpLease, "SELECT * FROM ip_lease")
+            .fetch_all(&self.connection_pool)
+            .await
+    }
+
+    /// Inserts the lease of the client, replacing its previous lease (if any) alongside
+    /// any stale leases of other clients for the same addresses.
+    ///
+    /// # Arguments
+    ///
+    /// * `lease`: the lease to store.
+    pub(super) async fn upsert_lease(&self, lease: &StoredIpLease) -> Result<(), sqlx::Error> {
+        let mut tx = self.connection_pool.begin().await?;
+
+        sqlx::query!(
+            "DELETE FROM ip_lease WHERE (ipv4 = ? OR ipv6 = ?) AND nym_address != ?",
+            lease.ipv4,
+            lease.ipv6,
+            lease.nym_address,
+        )
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO ip_lease(nym_address, ipv4, ipv6, mix_hops, expires_at, last_activity)
+                VALUES (?, ?, ?, ?, ?, ?)
+                ON CONFLICT(nym_address) DO UPDATE SET
+                    ipv4 = excluded.ipv4,
+                    ipv6 = excluded.ipv6,
+                    mix_hops = excluded.mix_hops,
+                    expires_at = excluded.expires_at,
+                    last_activity = excluded.last_activity
+            "#,
+            lease.nym_address,
+            lease.ipv4,
+            lease.ipv6,
+            lease.mix_hops,
+            lease.expires_at,
+            lease.last_activity,
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    /// Sets the expiration of the lease of the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `nym_address`: address of the client.
+    /// * `expires_at`: unix timestamp of when the lease expires or `None` if it's active again.
+    pub(super) async fn set_lease_expiry(
+        &self,
+        nym_address: &str,
+        expires_at: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE ip_lease SET expires_at = ? WHERE nym_address = ?",
+            expires_at,
+            nym_address,
+        )
+        .execute(&self.connection_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Updates the last activity of the specified clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `activity`: addresses of the clients alongside the unix timestamps of their last activity.
+    pub(super) async fn update_last_activity(
+        &self,
+        activity: &[(String, i64)],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.connection_pool.begin().await?;
+        for (nym_address, last_activity) in activity {
+            sqlx::query!(
+                "UPDATE ip_lease SET last_activity = ? WHERE nym_address = ?",
+                last_activity,
+                nym_address,
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Removes all the leases that have expired before the provided timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `now`: the current unix timestamp.
+    pub(super) async fn remove_expired_leases(&self, now: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM ip_lease WHERE expires_at IS NOT NULL AND expires_at <= ?",
+            now
+        )
+        .execute(&self.connection_pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
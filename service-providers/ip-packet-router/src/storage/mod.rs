@@ -0,0 +1,135 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use log::*;
+use nym_sdk::mixnet::Recipient;
+use sqlx::ConnectOptions;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::storage::error::IpLeaseStorageError;
+use crate::storage::manager::StorageManager;
+use crate::storage::models::StoredIpLease;
+
+pub(crate) use crate::storage::models::ClientLease;
+
+pub(crate) mod error;
+mod manager;
+mod models;
+
+// note that clone here is fine as upon cloning the same underlying pool will be used
+#[derive(Clone)]
+pub(crate) struct IpLeaseStorage {
+    manager: StorageManager,
+}
+
+impl IpLeaseStorage {
+    pub async fn init<P: AsRef<Path>>(database_path: P) -> Result<Self, IpLeaseStorageError> {
+        let database_path = database_path.as_ref();
+        if let Some(parent) = database_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut opts = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(database_path)
+            .create_if_missing(true);
+
+        opts.disable_statement_logging();
+
+        let connection_pool = sqlx::SqlitePool::connect_with(opts).await?;
+
+        sqlx::migrate!("./migrations").run(&connection_pool).await?;
+        info!("Database migration finished!");
+
+        Ok(IpLeaseStorage {
+            manager: StorageManager { connection_pool },
+        })
+    }
+
+    /// Returns all the stored leases, skipping over any that could not be parsed.
+    pub(crate) async fn load_leases(&self) -> Result<Vec<ClientLease>, IpLeaseStorageError> {
+        Ok(self
+            .manager
+            .get_leases()
+            .await?
+            .into_iter()
+            .filter_map(|stored| match ClientLease::try_from(stored) {
+                Ok(lease) => Some(lease),
+                Err(err) => {
+                    warn!("ignoring malformed stored lease: {err}");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Stores the lease of the client, replacing any previous lease for it or for its addresses.
+    ///
+    /// # Arguments
+    ///
+    /// * `lease`: the lease to store.
+    pub(crate) async fn store_lease(&self, lease: &ClientLease) -> Result<(), IpLeaseStorageError> {
+        Ok(self
+            .manager
+            .upsert_lease(&StoredIpLease::from(lease))
+            .await?)
+    }
+
+    /// Marks the lease of the now disconnected client as expiring at the provided time.
+    ///
+    /// # Arguments
+    ///
+    /// * `nym_address`: address of the client.
+    /// * `expires_at`: unix timestamp of when the lease expires.
+    pub(crate) async fn release_lease(
+        &self,
+        nym_address: &Recipient,
+        expires_at: i64,
+    ) -> Result<(), IpLeaseStorageError> {
+        Ok(self
+            .manager
+            .set_lease_expiry(&nym_address.to_string(), Some(expires_at))
+            .await?)
+    }
+
+    /// Updates the last activity of the connected clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `activity`: the clients alongside the unix timestamps of their last activity.
+    pub(crate) async fn update_last_activity(
+        &self,
+        activity: Vec<(Recipient, i64)>,
+    ) -> Result<(), IpLeaseStorageError> {
+        let activity = activity
+            .into_iter()
+            .map(|(nym_address, last_activity)| (nym_address.to_string(), last_activity))
+            .collect::<Vec<_>>();
+        Ok(self.manager.update_last_activity(&activity).await?)
+    }
+
+    /// Removes all the leases that have already expired.
+    ///
+    /// # Arguments
+    ///
+    /// * `now`: the current unix timestamp.
+    pub(crate) async fn remove_expired_leases(&self, now: i64) -> Result<(), IpLeaseStorageError> {
+        let removed = self.manager.remove_expired_leases(now).await?;
+        if removed > 0 {
+            debug!("removed {removed} expired leases");
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+// Convert the (monotonic) instant into the corresponding unix timestamp
+pub(crate) fn to_unix_timestamp(instant: Instant) -> i64 {
+    unix_now() - instant.elapsed().as_secs() as i64
+}
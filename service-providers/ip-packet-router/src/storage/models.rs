@@ -0,0 +1,77 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use nym_ip_packet_requests::{response::IpLease, IpPair};
+use nym_sdk::mixnet::Recipient;
+
+pub(crate) struct StoredIpLease {
+    pub(crate) nym_address: String,
+    pub(crate) ipv4: String,
+    pub(crate) ipv6: String,
+    pub(crate) mix_hops: Option<i64>,
+    pub(crate) expires_at: Option<i64>,
+    pub(crate) last_activity: i64,
+}
+
+/// Addresses leased to a client, as persisted across restarts of the router.
+#[derive(Debug, Clone)]
+pub(crate) struct ClientLease {
+    pub(crate) nym_address: Recipient,
+    pub(crate) ips: IpPair,
+    pub(crate) mix_hops: Option<u8>,
+
+    /// Unix timestamp of when the lease expires, `None` while the client is connected.
+    pub(crate) expires_at: Option<i64>,
+
+    /// Unix timestamp of the last time the client has sent us anything.
+    pub(crate) last_activity: i64,
+}
+
+impl TryFrom<StoredIpLease> for ClientLease {
+    type Error = String;
+
+    fn try_from(value: StoredIpLease) -> Result<Self, Self::Error> {
+        let nym_address = Recipient::try_from_base58_string(&value.nym_address)
+            .map_err(|err| format!("malformed nym address '{}': {err}", value.nym_address))?;
+        let ipv4 = value
+            .ipv4
+            .parse()
+            .map_err(|err| format!("malformed IPv4 address '{}': {err}", value.ipv4))?;
+        let ipv6 = value
+            .ipv6
+            .parse()
+            .map_err(|err| format!("malformed IPv6 address '{}': {err}", value.ipv6))?;
+
+        Ok(ClientLease {
+            nym_address,
+            ips: IpPair::new(ipv4, ipv6),
+            mix_hops: value.mix_hops.and_then(|hops| u8::try_from(hops).ok()),
+            expires_at: value.expires_at,
+            last_activity: value.last_activity,
+        })
+    }
+}
+
+impl From<&ClientLease> for StoredIpLease {
+    fn from(value: &ClientLease) -> Self {
+        StoredIpLease {
+            nym_address: value.nym_address.to_string(),
+            ipv4: value.ips.ipv4.to_string(),
+            ipv6: value.ips.ipv6.to_string(),
+            mix_hops: value.mix_hops.map(Into::into),
+            expires_at: value.expires_at,
+            last_activity: value.last_activity,
+        }
+    }
+}
+
+impl From<ClientLease> for IpLease {
+    fn from(value: ClientLease) -> Self {
+        IpLease {
+            nym_address: value.nym_address,
+            ips: value.ips,
+            expires_at: value.expires_at,
+            last_activity: value.last_activity,
+        }
+    }
+}
@@ -0,0 +1,363 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, Instant};
+
+use ipnetwork::{Ipv4Network, Ipv6Network};
+use nym_ip_packet_requests::IpPair;
+
+use crate::error::{IpPacketRouterError, Result};
+
+// The first address of each pool is the network address and the second one is assigned to the
+// TUN device, so the first address we can hand out to a client is at this offset
+const FIRST_CLIENT_OFFSET: u128 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LeaseState {
+    // The client is connected and the addresses are in use
+    Active,
+
+    // The client has disconnected, but the addresses stay reserved for it until the lease expires.
+    // If it reconnects in the meantime it gets the same addresses back, and otherwise we make sure
+    // any return traffic still in flight is not delivered to somebody else.
+    Released { expires_at: Instant },
+}
+
+struct Lease<C> {
+    client: C,
+    ips: IpPair,
+    state: LeaseState,
+}
+
+impl<C> Lease<C> {
+    fn is_expired(&self, now: Instant) -> bool {
+        matches!(self.state, LeaseState::Released { expires_at } if expires_at <= now)
+    }
+}
+
+// Hands out addresses from the configured IPv4 and IPv6 pools to the connected clients, where
+// `C` identifies the client, i.e. its nym address.
+// The allocation is deterministic: a new client always gets the lowest addresses that are free.
+pub(crate) struct IpPool<C> {
+    ipv4_network: Ipv4Network,
+    ipv6_network: Ipv6Network,
+    lease_expiry: Duration,
+
+    leases: HashMap<Ipv4Addr, Lease<C>>,
+    leased_ipv6: HashMap<Ipv6Addr, Ipv4Addr>,
+}
+
+impl<C: Copy + PartialEq> IpPool<C> {
+    pub(crate) fn new(
+        ipv4_network: Ipv4Network,
+        ipv6_network: Ipv6Network,
+        lease_expiry: Duration,
+    ) -> Result<Self> {
+        // we need at least the network address, the TUN device, a client and (for IPv4) the
+        // broadcast address
+        if ipv4_network.size() < 4 {
+            return Err(IpPacketRouterError::AddressPoolTooSmall {
+                pool: ipv4_network.to_string(),
+            });
+        }
+        if ipv6_network.size() < 3 {
+            return Err(IpPacketRouterError::AddressPoolTooSmall {
+                pool: ipv6_network.to_string(),
+            });
+        }
+
+        Ok(IpPool {
+            ipv4_network,
+            ipv6_network,
+            lease_expiry,
+            leases: HashMap::new(),
+            leased_ipv6: HashMap::new(),
+        })
+    }
+
+    fn ipv4_at(&self, offset: u128) -> Ipv4Addr {
+        Ipv4Addr::from(u32::from(self.ipv4_network.network()) + offset as u32)
+    }
+
+    fn ipv6_at(&self, offset: u128) -> Ipv6Addr {
+        Ipv6Addr::from(u128::from(self.ipv6_network.network()) + offset)
+    }
+
+    // The addresses assigned to the TUN device
+    pub(crate) fn tun_ips(&self) -> IpPair {
+        IpPair::new(self.ipv4_at(1), self.ipv6_at(1))
+    }
+
+    pub(crate) fn ipv4_network(&self) -> Ipv4Network {
+        self.ipv4_network
+    }
+
+    pub(crate) fn ipv6_network(&self) -> Ipv6Network {
+        self.ipv6_network
+    }
+
+    // Check that the addresses could be assigned to a client
+    pub(crate) fn contains(&self, ips: IpPair) -> bool {
+        let tun_ips = self.tun_ips();
+        self.ipv4_network.contains(ips.ipv4)
+            && ips.ipv4 != self.ipv4_network.network()
+            && ips.ipv4 != self.ipv4_network.broadcast()
+            && ips.ipv4 != tun_ips.ipv4
+            && self.ipv6_network.contains(ips.ipv6)
+            && ips.ipv6 != self.ipv6_network.network()
+            && ips.ipv6 != tun_ips.ipv6
+    }
+
+    fn remove_lease(&mut self, ipv4: &Ipv4Addr) {
+        if let Some(lease) = self.leases.remove(ipv4) {
+            self.leased_ipv6.remove(&lease.ips.ipv6);
+        }
+    }
+
+    fn remove_expired_leases(&mut self, now: Instant) {
+        let expired = self
+            .leases
+            .iter()
+            .filter(|(_, lease)| lease.is_expired(now))
+            .map(|(ipv4, _)| *ipv4)
+            .collect::<Vec<_>>();
+        for ipv4 in expired {
+            log::debug!("lease for {ipv4} has expired");
+            self.remove_lease(&ipv4);
+        }
+    }
+
+    fn insert_lease(&mut self, client: C, ips: IpPair) {
+        self.leased_ipv6.insert(ips.ipv6, ips.ipv4);
+        self.leases.insert(
+            ips.ipv4,
+            Lease {
+                client,
+                ips,
+                state: LeaseState::Active,
+            },
+        );
+    }
+
+    // Find the lowest address in the pool that is not leased to anybody
+    fn find_free_ips(&self) -> Option<IpPair> {
+        let ipv4 = (FIRST_CLIENT_OFFSET..self.ipv4_network.size() as u128 - 1)
+            .map(|offset| self.ipv4_at(offset))
+            .find(|ip| !self.leases.contains_key(ip))?;
+        let ipv6 = (FIRST_CLIENT_OFFSET..self.ipv6_network.size())
+            .map(|offset| self.ipv6_at(offset))
+            .find(|ip| !self.leased_ipv6.contains_key(ip))?;
+        Some(IpPair::new(ipv4, ipv6))
+    }
+
+    // Lease a pair of addresses to the client. If the client already holds a lease, including one
+    // that has been released but not yet expired, the same addresses are handed out again.
+    pub(crate) fn allocate(&mut self, client: C, now: Instant) -> Option<IpPair> {
+        self.remove_expired_leases(now);
+
+        if let Some(lease) = self
+            .leases
+            .values_mut()
+            .find(|lease| lease.client == client)
+        {
+            lease.state = LeaseState::Active;
+            return Some(lease.ips);
+        }
+
+        let ips = self.find_free_ips()?;
+        self.insert_lease(client, ips);
+        Some(ips)
+    }
+
+    // Lease the specific addresses requested by the client. Fails if they're outside of the pool
+    // or if any of them is leased to somebody else.
+    pub(crate) fn reserve(&mut self, client: C, ips: IpPair, now: Instant) -> bool {
+        self.remove_expired_leases(now);
+
+        if !self.contains(ips) {
+            return false;
+        }
+
+        let leased_to_other = |lease: &Lease<C>| lease.client != client || lease.ips != ips;
+        if self.leases.get(&ips.ipv4).is_some_and(leased_to_other) {
+            return false;
+        }
+        if let Some(ipv4) = self.leased_ipv6.get(&ips.ipv6) {
+            if self.leases.get(ipv4).is_some_and(leased_to_other) {
+                return false;
+            }
+        }
+
+        // the client might have held a lease for different addresses before
+        let previous = self
+            .leases
+            .iter()
+            .filter(|(_, lease)| lease.client == client)
+            .map(|(ipv4, _)| *ipv4)
+            .collect::<Vec<_>>();
+        for ipv4 in previous {
+            self.remove_lease(&ipv4);
+        }
+
+        self.insert_lease(client, ips);
+        true
+    }
+
+    // Restore a lease persisted before the router has been restarted. `expires_at` is `None` if the
+    // client was still connected at the time.
+    pub(crate) fn restore(&mut self, client: C, ips: IpPair, expires_at: Option<Instant>) -> bool {
+        if !self.contains(ips)
+            || self.leases.contains_key(&ips.ipv4)
+            || self.leased_ipv6.contains_key(&ips.ipv6)
+            || self.leases.values().any(|lease| lease.client == client)
+        {
+            return false;
+        }
+
+        self.insert_lease(client, ips);
+        if let Some(expires_at) = expires_at {
+            self.release_until(ips, expires_at)
+        }
+        true
+    }
+
+    fn release_until(&mut self, ips: IpPair, expires_at: Instant) {
+        if let Some(lease) = self.leases.get_mut(&ips.ipv4) {
+            if lease.ips == ips {
+                lease.state = LeaseState::Released { expires_at };
+            }
+        }
+    }
+
+    // The client has disconnected: keep its addresses reserved until the lease expires
+    pub(crate) fn release(&mut self, ips: IpPair, now: Instant) {
+        self.release_until(ips, now + self.lease_expiry)
+    }
+
+    pub(crate) fn lease_expiry(&self) -> Duration {
+        self.lease_expiry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPIRY: Duration = Duration::from_secs(60);
+
+    fn pool(ipv4: &str, ipv6: &str) -> IpPool<u32> {
+        IpPool::new(ipv4.parse().unwrap(), ipv6.parse().unwrap(), EXPIRY).unwrap()
+    }
+
+    fn ips(ipv4: &str, ipv6: &str) -> IpPair {
+        IpPair::new(ipv4.parse().unwrap(), ipv6.parse().unwrap())
+    }
+
+    #[test]
+    fn tun_device_gets_the_first_host_addresses() {
+        let pool = pool("10.0.0.0/24", "fd00:a160::/120");
+        assert_eq!(pool.tun_ips(), ips("10.0.0.1", "fd00:a160::1"));
+    }
+
+    #[test]
+    fn pools_must_fit_at_least_one_client() {
+        assert!(IpPool::<u32>::new(
+            "10.0.0.0/31".parse().unwrap(),
+            "fd00::/120".parse().unwrap(),
+            EXPIRY
+        )
+        .is_err());
+        assert!(IpPool::<u32>::new(
+            "10.0.0.0/24".parse().unwrap(),
+            "fd00::/127".parse().unwrap(),
+            EXPIRY
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn allocation_is_deterministic() {
+        let now = Instant::now();
+        let mut pool = pool("10.0.0.0/24", "fd00:a160::/120");
+
+        assert_eq!(pool.allocate(1, now), Some(ips("10.0.0.2", "fd00:a160::2")));
+        assert_eq!(pool.allocate(2, now), Some(ips("10.0.0.3", "fd00:a160::3")));
+        assert_eq!(pool.allocate(3, now), Some(ips("10.0.0.4", "fd00:a160::4")));
+
+        // allocating again for the same client returns the existing lease
+        assert_eq!(pool.allocate(2, now), Some(ips("10.0.0.3", "fd00:a160::3")));
+    }
+
+    #[test]
+    fn released_addresses_are_reused_only_after_expiry() {
+        let now = Instant::now();
+        let mut pool = pool("10.0.0.0/24", "fd00:a160::/120");
+
+        let first = pool.allocate(1, now).unwrap();
+        pool.allocate(2, now).unwrap();
+        pool.release(first, now);
+
+        // the lease is still held for the disconnected client
+        assert_eq!(pool.allocate(3, now), Some(ips("10.0.0.4", "fd00:a160::4")));
+
+        // and it gets it back if it reconnects
+        assert_eq!(pool.allocate(1, now), Some(first));
+        pool.release(first, now);
+
+        // but once it expires it's given to whoever asks first
+        let later = now + EXPIRY;
+        assert_eq!(pool.allocate(4, later), Some(first));
+    }
+
+    #[test]
+    fn exhausted_pool_fails_allocation() {
+        let now = Instant::now();
+        let mut pool = pool("10.0.0.0/30", "fd00:a160::/120");
+
+        assert_eq!(pool.allocate(1, now), Some(ips("10.0.0.2", "fd00:a160::2")));
+        assert_eq!(pool.allocate(2, now), None);
+    }
+
+    #[test]
+    fn restoring_persisted_leases() {
+        let now = Instant::now();
+        let mut pool = pool("10.0.0.0/24", "fd00:a160::/120");
+
+        let connected = ips("10.0.0.2", "fd00:a160::2");
+        let disconnected = ips("10.0.0.5", "fd00:a160::5");
+        assert!(pool.restore(1, connected, None));
+        assert!(pool.restore(2, disconnected, Some(now + EXPIRY)));
+
+        // conflicting or no longer valid leases are rejected
+        assert!(!pool.restore(3, connected, None));
+        assert!(!pool.restore(1, ips("10.0.0.6", "fd00:a160::6"), None));
+        assert!(!pool.restore(4, ips("10.0.1.2", "fd00:a160::7"), None));
+
+        assert_eq!(pool.allocate(2, now), Some(disconnected));
+        assert_eq!(pool.allocate(5, now), Some(ips("10.0.0.3", "fd00:a160::3")));
+    }
+
+    #[test]
+    fn reserving_specific_addresses() {
+        let now = Instant::now();
+        let mut pool = pool("10.0.0.0/24", "fd00:a160::/120");
+
+        // outside of the pool or reserved addresses
+        assert!(!pool.reserve(1, ips("10.0.1.2", "fd00:a160::2"), now));
+        assert!(!pool.reserve(1, ips("10.0.0.1", "fd00:a160::2"), now));
+        assert!(!pool.reserve(1, ips("10.0.0.255", "fd00:a160::2"), now));
+        assert!(!pool.reserve(1, ips("10.0.0.2", "fd00:a160::1"), now));
+
+        let requested = ips("10.0.0.10", "fd00:a160::10");
+        assert!(pool.reserve(1, requested, now));
+        assert!(pool.reserve(1, requested, now));
+        assert!(!pool.reserve(2, requested, now));
+        assert!(!pool.reserve(2, ips("10.0.0.11", "fd00:a160::10"), now));
+
+        // dynamic allocation skips over the reserved addresses
+        assert_eq!(pool.allocate(2, now), Some(ips("10.0.0.2", "fd00:a160::2")));
+        assert_eq!(pool.allocate(1, now), Some(requested));
+    }
+}
@@ -1,3 +1,5 @@
 pub(crate) mod create_message;
-pub(crate) mod generate_new_ip;
+pub(crate) mod ip_pool;
+pub(crate) mod nat;
 pub(crate) mod parse_ip;
+pub(crate) mod rate_limit;
@@ -0,0 +1,128 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use ipnetwork::{IpNetwork, Ipv4Network, Ipv6Network};
+use std::process::{Command, Output};
+
+// The addresses assigned to the clients are private, so their traffic has to be masqueraded behind
+// the addresses of the host before it could reach the internet, i.e. NAT44 and NAT66 respectively.
+// The rules are removed again once the returned guard is dropped, i.e. when the router stops.
+pub(crate) fn setup_masquerade(
+    tun_name: &str,
+    ipv4_pool: Ipv4Network,
+    ipv6_pool: Ipv6Network,
+) -> MasqueradeRules {
+    let mut rules = MasqueradeRules {
+        tun_name: tun_name.to_string(),
+        added: Vec::new(),
+    };
+
+    for (command, pool) in [
+        ("iptables", IpNetwork::V4(ipv4_pool)),
+        ("ip6tables", IpNetwork::V6(ipv6_pool)),
+    ] {
+        match add_masquerade_rule(command, tun_name, pool) {
+            Ok(()) => {
+                log::info!("Masquerading the traffic of {pool} leaving through other interfaces than {tun_name}");
+                rules.added.push((command, pool));
+            }
+            Err(err) => log::warn!(
+                "failed to set up masquerading for {pool} using {command}: {err}. The traffic of the connected clients won't be routed unless it's configured manually"
+            ),
+        }
+    }
+
+    rules
+}
+
+/// Masquerading rules set up by the router that get removed when dropped.
+pub(crate) struct MasqueradeRules {
+    tun_name: String,
+    added: Vec<(&'static str, IpNetwork)>,
+}
+
+impl Drop for MasqueradeRules {
+    fn drop(&mut self) {
+        for (command, pool) in self.added.drain(..) {
+            match run_rule(command, "-D", &self.tun_name, pool) {
+                Ok(output) if output.status.success() => {
+                    log::info!("Stopped masquerading the traffic of {pool}")
+                }
+                Ok(output) => log::warn!(
+                    "failed to remove the masquerading rule for {pool} using {command}: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Err(err) => log::warn!(
+                    "failed to remove the masquerading rule for {pool} using {command}: {err}"
+                ),
+            }
+        }
+    }
+}
+
+fn add_masquerade_rule(command: &str, tun_name: &str, pool: IpNetwork) -> Result<(), String> {
+    // the rule might have already been added before the router got killed without cleaning up,
+    // in which case we take it over so that it's removed on shutdown
+    if run_rule(command, "-C", tun_name, pool)?.status.success() {
+        return Ok(());
+    }
+
+    let output = run_rule(command, "-A", tun_name, pool)?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+fn run_rule(
+    command: &str,
+    action: &str,
+    tun_name: &str,
+    pool: IpNetwork,
+) -> Result<Output, String> {
+    Command::new(command)
+        .args(rule_args(action, tun_name, &pool.to_string()))
+        .output()
+        .map_err(|err| err.to_string())
+}
+
+fn rule_args<'a>(action: &'a str, tun_name: &'a str, pool: &'a str) -> [&'a str; 11] {
+    [
+        "-t",
+        "nat",
+        action,
+        "POSTROUTING",
+        "-s",
+        pool,
+        "!",
+        "-o",
+        tun_name,
+        "-j",
+        "MASQUERADE",
+    ]
+}
+
+// Addresses out of the documentation prefix are never routed, regardless of NAT
+pub(crate) fn is_documentation_network(network: Ipv6Network) -> bool {
+    let documentation =
+        Ipv6Network::new(std::net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32)
+            .expect("invalid documentation prefix");
+    documentation.contains(network.network())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEFAULT_IPV6_ADDRESS_POOL;
+
+    #[test]
+    fn default_pool_is_not_the_documentation_prefix() {
+        let default_pool: Ipv6Network = DEFAULT_IPV6_ADDRESS_POOL.parse().unwrap();
+        assert!(!is_documentation_network(default_pool));
+        // it's a unique local network, i.e. fc00::/7
+        assert_eq!(default_pool.network().segments()[0] & 0xfe00, 0xfc00);
+        assert!(is_documentation_network(
+            "2001:db8:a160::/120".parse().unwrap()
+        ));
+    }
+}
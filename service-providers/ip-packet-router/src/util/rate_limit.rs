@@ -0,0 +1,224 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::config::ClientRateLimit;
+
+// A bucket that is refilled at a constant rate up to its capacity. Each forwarded unit of traffic
+// consumes a token and if there aren't enough tokens left, the traffic gets throttled.
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    // If the burst is not set, allow for up to a second worth of traffic
+    fn new(rate: u64, burst: u64, now: Instant) -> Self {
+        let capacity = if burst == 0 { rate } else { burst } as f64;
+        TokenBucket {
+            rate: rate as f64,
+            capacity,
+            tokens: capacity,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    fn has(&self, amount: f64) -> bool {
+        self.tokens >= amount
+    }
+
+    fn consume(&mut self, amount: f64) {
+        self.tokens -= amount;
+    }
+}
+
+// Traffic throttled across all the connected clients
+#[derive(Debug, Default)]
+pub(crate) struct ThrottleStats {
+    throttled_packets: AtomicU64,
+    throttled_bytes: AtomicU64,
+}
+
+impl ThrottleStats {
+    fn record(&self, bytes: usize) {
+        self.throttled_packets.fetch_add(1, Ordering::Relaxed);
+        self.throttled_bytes
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    // Returns the total number of throttled packets and bytes
+    pub(crate) fn totals(&self) -> (u64, u64) {
+        (
+            self.throttled_packets.load(Ordering::Relaxed),
+            self.throttled_bytes.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// Enforces the bandwidth and packet rate limits of the traffic of a single client, in a single
+// direction.
+pub(crate) struct ClientRateLimiter {
+    bytes: Option<TokenBucket>,
+    packets: Option<TokenBucket>,
+
+    throttled_packets: u64,
+    throttled_bytes: u64,
+    stats: Arc<ThrottleStats>,
+}
+
+impl ClientRateLimiter {
+    pub(crate) fn new(limit: ClientRateLimit, stats: Arc<ThrottleStats>, now: Instant) -> Self {
+        ClientRateLimiter {
+            bytes: (limit.bytes_per_second > 0)
+                .then(|| TokenBucket::new(limit.bytes_per_second, limit.burst_bytes, now)),
+            packets: (limit.packets_per_second > 0)
+                .then(|| TokenBucket::new(limit.packets_per_second, limit.burst_packets, now)),
+            throttled_packets: 0,
+            throttled_bytes: 0,
+            stats,
+        }
+    }
+
+    // Check whether the packet of the provided size is within the limits. If it's not, it should
+    // be dropped.
+    pub(crate) fn allow(&mut self, packet_size: usize, now: Instant) -> bool {
+        let size = packet_size as f64;
+
+        if let Some(bytes) = &mut self.bytes {
+            bytes.refill(now);
+        }
+        if let Some(packets) = &mut self.packets {
+            packets.refill(now);
+        }
+
+        let within_limits = self.bytes.as_ref().map_or(true, |bytes| {
+            // a packet bigger than the whole bucket would never get through,
+            // so let it pass whenever the bucket is full instead
+            bytes.has(size.min(bytes.capacity))
+        }) && self
+            .packets
+            .as_ref()
+            .map_or(true, |packets| packets.has(1.));
+
+        if !within_limits {
+            self.throttled_packets += 1;
+            self.throttled_bytes += packet_size as u64;
+            self.stats.record(packet_size);
+            return false;
+        }
+
+        if let Some(bytes) = &mut self.bytes {
+            bytes.consume(size);
+        }
+        if let Some(packets) = &mut self.packets {
+            packets.consume(1.);
+        }
+        true
+    }
+
+    // Returns the number of packets and bytes throttled by this limiter
+    pub(crate) fn throttled(&self) -> (u64, u64) {
+        (self.throttled_packets, self.throttled_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn limiter(limit: ClientRateLimit) -> (ClientRateLimiter, Arc<ThrottleStats>, Instant) {
+        let stats = Arc::new(ThrottleStats::default());
+        let now = Instant::now();
+        (
+            ClientRateLimiter::new(limit, stats.clone(), now),
+            stats,
+            now,
+        )
+    }
+
+    #[test]
+    fn unlimited_by_default() {
+        let (mut limiter, stats, now) = limiter(ClientRateLimit::default());
+        for _ in 0..10_000 {
+            assert!(limiter.allow(1500, now));
+        }
+        assert_eq!(stats.totals(), (0, 0));
+    }
+
+    #[test]
+    fn bandwidth_is_limited() {
+        let (mut limiter, stats, now) = limiter(ClientRateLimit {
+            bytes_per_second: 1000,
+            burst_bytes: 2000,
+            ..Default::default()
+        });
+
+        assert!(limiter.allow(1000, now));
+        assert!(limiter.allow(1000, now));
+        assert!(!limiter.allow(100, now));
+        assert_eq!(limiter.throttled(), (1, 100));
+        assert_eq!(stats.totals(), (1, 100));
+
+        // half a second later we got enough tokens for 500 bytes
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.allow(500, later));
+        assert!(!limiter.allow(1, later));
+    }
+
+    #[test]
+    fn packet_rate_is_limited() {
+        let (mut limiter, _, now) = limiter(ClientRateLimit {
+            packets_per_second: 2,
+            ..Default::default()
+        });
+
+        assert!(limiter.allow(10, now));
+        assert!(limiter.allow(10, now));
+        assert!(!limiter.allow(10, now));
+        assert!(limiter.allow(10, now + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn both_limits_must_be_satisfied() {
+        let (mut limiter, _, now) = limiter(ClientRateLimit {
+            bytes_per_second: 100,
+            packets_per_second: 10,
+            ..Default::default()
+        });
+
+        // throttled on bandwidth
+        assert!(limiter.allow(100, now));
+        assert!(!limiter.allow(100, now));
+
+        // throttled on packet rate
+        let later = now + Duration::from_secs(1);
+        for _ in 0..10 {
+            assert!(limiter.allow(1, later));
+        }
+        assert!(!limiter.allow(1, later));
+    }
+
+    #[test]
+    fn oversized_packets_pass_when_bucket_is_full() {
+        let (mut limiter, _, now) = limiter(ClientRateLimit {
+            bytes_per_second: 100,
+            ..Default::default()
+        });
+
+        assert!(limiter.allow(1500, now));
+        assert!(!limiter.allow(1500, now + Duration::from_millis(500)));
+        assert!(limiter.allow(1500, now + Duration::from_secs(20)));
+    }
+}
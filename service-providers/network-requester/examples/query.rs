@@ -36,14 +36,14 @@ async fn main() -> anyhow::Result<()> {
     let open_proxy_request = Request::new_provider_data(
         ProviderInterfaceVersion::new_current(),
         Socks5Request::new_query(
-            Socks5ProtocolVersion::new_current(),
+            Socks5ProtocolVersion::new_current().for_basic_requests(),
             QueryRequest::OpenProxy,
         ),
     );
     let description_request = Request::new_provider_data(
         ProviderInterfaceVersion::new_current(),
         Socks5Request::new_query(
-            Socks5ProtocolVersion::new_current(),
+            Socks5ProtocolVersion::new_current().for_basic_requests(),
             QueryRequest::Description,
         ),
     );
@@ -16,6 +16,7 @@ use nym_service_providers_common::DEFAULT_SERVICE_PROVIDERS_DIR;
 use nym_sphinx::params::PacketSize;
 use serde::{Deserialize, Serialize};
 use std::io;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
@@ -32,6 +33,8 @@ mod template;
 const DEFAULT_NETWORK_REQUESTERS_DIR: &str = "network-requester";
 
 pub const DEFAULT_STANDARD_LIST_UPDATE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+pub const DEFAULT_EXIT_POLICY_UPDATE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+pub const DEFAULT_DNS_CACHE_TTL: Duration = Duration::from_secs(60);
 
 /// Derive default path to network requester's config directory.
 /// It should get resolved to `$HOME/.nym/service-providers/network-requester/<id>/config`
@@ -253,6 +256,21 @@ pub struct NetworkRequester {
     /// Specifies the url for an upstream source of the exit policy used by this node.
     #[serde(deserialize_with = "de_maybe_stringified")]
     pub upstream_exit_policy_url: Option<Url>,
+
+    /// Specifies whether this network requester is allowed to connect to private, local and otherwise
+    /// reserved addresses (such as 10.0.0.0/8 or ::1). They are refused by default regardless of
+    /// the exit policy or the allow list, so that remotes couldn't resolve to any internal services.
+    pub allow_reserved_addresses: bool,
+
+    /// Specifies whether this network requester accepts SOCKS5 BIND requests, i.e. whether it opens
+    /// listening sockets on behalf of its clients for inbound connections.
+    pub enable_bind: bool,
+
+    /// Specifies the address reported to the clients as the one their BIND peers should connect to.
+    /// It has to be set if this network requester is behind NAT. If unspecified, the address of
+    /// the local interface used for reaching the peer (or the internet in general) is reported instead.
+    #[serde(deserialize_with = "de_maybe_stringified")]
+    pub bind_announced_address: Option<IpAddr>,
 }
 
 impl Default for NetworkRequester {
@@ -268,6 +286,9 @@ impl Default for NetworkRequester {
                     .parse()
                     .expect("invalid default exit policy URL"),
             ),
+            allow_reserved_addresses: false,
+            enable_bind: false,
+            bind_announced_address: None,
         }
     }
 }
@@ -279,12 +300,22 @@ pub struct Debug {
     /// Deprecated
     #[serde(with = "humantime_serde")]
     pub standard_list_update_interval: Duration,
+
+    /// Defines how often the exit policy should get refetched from the upstream source
+    #[serde(with = "humantime_serde")]
+    pub exit_policy_update_interval: Duration,
+
+    /// Defines for how long the resolved addresses of the requested remotes are cached
+    #[serde(with = "humantime_serde")]
+    pub dns_cache_ttl: Duration,
 }
 
 impl Default for Debug {
     fn default() -> Self {
         Debug {
             standard_list_update_interval: DEFAULT_STANDARD_LIST_UPDATE_INTERVAL,
+            exit_policy_update_interval: DEFAULT_EXIT_POLICY_UPDATE_INTERVAL,
+            dns_cache_ttl: DEFAULT_DNS_CACHE_TTL,
         }
     }
 }
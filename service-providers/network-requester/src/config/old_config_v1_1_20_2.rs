@@ -115,6 +115,7 @@ impl From<DebugV1_1_20_2> for Debug {
     fn from(value: DebugV1_1_20_2) -> Self {
         Debug {
             standard_list_update_interval: value.standard_list_update_interval,
+            ..Default::default()
         }
     }
 }
@@ -103,6 +103,20 @@ use_deprecated_allow_list = {{ network_requester.use_deprecated_allow_list }}
 # Specifies the url for an upstream source of the exit policy used by this node.
 upstream_exit_policy_url = '{{ network_requester.upstream_exit_policy_url }}'
 
+# Specifies whether this network requester is allowed to connect to private, local and otherwise
+# reserved addresses (such as 10.0.0.0/8 or ::1). They are refused by default regardless of
+# the exit policy or the allow list, so that remotes couldn't resolve to any internal services.
+allow_reserved_addresses = {{ network_requester.allow_reserved_addresses }}
+
+# Specifies whether this network requester accepts SOCKS5 BIND requests, i.e. whether it opens
+# listening sockets on behalf of its clients for inbound connections.
+enable_bind = {{ network_requester.enable_bind }}
+
+# Specifies the address reported to the clients as the one their BIND peers should connect to.
+# It has to be set if this network requester is behind NAT. If unspecified, the address of
+# the local interface used for reaching the peer (or the internet in general) is reported instead.
+bind_announced_address = '{{ network_requester.bind_announced_address }}'
+
 ##### logging configuration options #####
 
 [logging]
@@ -26,9 +26,9 @@ use nym_socks5_proxy_helpers::connection_controller::{
 };
 use nym_socks5_proxy_helpers::proxy_runner::{MixProxyReader, MixProxySender};
 use nym_socks5_requests::{
-    ConnectRequest, ConnectionId, QueryRequest, QueryResponse, SendRequest, SocketData,
-    Socks5ProtocolVersion, Socks5ProviderRequest, Socks5Request, Socks5RequestContent,
-    Socks5Response,
+    BindEvent, ConnectRequest, ConnectionId, Datagram, DnsQueryRequest, QueryRequest,
+    QueryResponse, SendRequest, SocketData, Socks5ProtocolVersion, Socks5ProviderRequest,
+    Socks5Request, Socks5RequestContent, Socks5Response, UdpAssociateRequest,
 };
 use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
@@ -38,6 +38,7 @@ use nym_statistics_common::collector::StatisticsSender;
 use nym_task::connections::LaneQueueLengths;
 use nym_task::manager::TaskHandle;
 use nym_task::TaskClient;
+use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
@@ -88,6 +89,8 @@ pub struct NRServiceProvider {
 
     mix_input_sender: MixProxySender<MixnetMessage>,
     stats_collector: Option<ServiceStatisticsCollector>,
+    bind_listeners: socks5::bind::BindListeners,
+    udp_associations: socks5::udp::Associations,
     shutdown: TaskHandle,
 }
 
@@ -173,6 +176,17 @@ impl ServiceProvider<Socks5Request> for NRServiceProvider {
                 self.handle_proxy_send(req)
             }
             Socks5RequestContent::Query(query) => return self.handle_query(query),
+            Socks5RequestContent::UdpAssociate(req) => {
+                self.handle_udp_associate(request_version, sender, req)
+            }
+            Socks5RequestContent::Datagram(datagram) => self.handle_datagram(datagram),
+            Socks5RequestContent::UdpDisassociate(connection_id) => {
+                self.handle_udp_disassociate(connection_id)
+            }
+            Socks5RequestContent::Bind(req) => self.handle_bind(request_version, sender, req),
+            Socks5RequestContent::DnsQuery(req) => {
+                self.handle_dns_query(request_version, sender, req)
+            }
         }
 
         Ok(None)
@@ -338,6 +352,8 @@ impl NRServiceProviderBuilder {
             controller_sender,
             mix_input_sender,
             stats_collector,
+            bind_listeners: Default::default(),
+            udp_associations: Default::default(),
             shutdown,
         };
 
@@ -442,6 +458,7 @@ impl NRServiceProvider {
         remote_version: RequestVersion<Socks5Request>,
         connection_id: ConnectionId,
         remote_addr: String,
+        resolved: Vec<SocketAddr>,
         return_address: reply::MixnetAddress,
         biggest_packet_size: PacketSize,
         controller_sender: ControllerSender,
@@ -449,9 +466,10 @@ impl NRServiceProvider {
         lane_queue_lengths: LaneQueueLengths,
         shutdown: TaskClient,
     ) {
-        let mut conn = match socks5::tcp::Connection::new(
+        let conn = match socks5::tcp::Connection::new(
             connection_id,
             remote_addr.clone(),
+            &resolved,
             return_address.clone(),
         )
         .await
@@ -477,7 +495,33 @@ impl NRServiceProvider {
             }
         };
 
-        // Connect implies it's a fresh connection - register it with our controller
+        Self::run_connection_proxy(
+            conn,
+            remote_version,
+            connection_id,
+            remote_addr,
+            biggest_packet_size,
+            controller_sender,
+            mix_input_sender,
+            lane_queue_lengths,
+            shutdown,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_connection_proxy(
+        mut conn: socks5::tcp::Connection,
+        remote_version: RequestVersion<Socks5Request>,
+        connection_id: ConnectionId,
+        remote_addr: String,
+        biggest_packet_size: PacketSize,
+        controller_sender: ControllerSender,
+        mix_input_sender: MixProxySender<MixnetMessage>,
+        lane_queue_lengths: LaneQueueLengths,
+        shutdown: TaskClient,
+    ) {
+        // it's a fresh connection - register it with our controller
         let (mix_sender, mix_receiver) = mpsc::unbounded();
         controller_sender
             .unbounded_send(ControllerCommand::Insert {
@@ -549,7 +593,14 @@ impl NRServiceProvider {
         // because we might have to resolve the underlying address and it can take some time
         // during which we don't want to block other incoming requests
         tokio::spawn(async move {
-            if !request_filter.check_address(&remote_addr).await {
+            let resolved = request_filter
+                .resolve_allowed(&remote_addr)
+                .await
+                .unwrap_or_else(|err| {
+                    log::warn!("failed to resolve {remote_addr}: {err}");
+                    None
+                });
+            let Some(resolved) = resolved else {
                 let log_msg = format!("Domain {remote_addr:?} failed filter check");
                 log::info!("{log_msg}");
                 let error_msg = MixnetMessage::new_connection_error(
@@ -565,13 +616,14 @@ impl NRServiceProvider {
                     .expect("InputMessageReceiver has stopped receiving!");
                 shutdown.mark_as_success();
                 return;
-            }
+            };
 
             // if all is good, start the proxy for this connection
             Self::start_proxy(
                 remote_version,
                 conn_id,
                 remote_addr,
+                resolved,
                 return_address,
                 packet_size,
                 controller_sender_clone,
@@ -583,6 +635,262 @@ impl NRServiceProvider {
         });
     }
 
+    fn handle_udp_associate(
+        &self,
+        remote_version: RequestVersion<Socks5Request>,
+        sender_tag: Option<AnonymousSenderTag>,
+        associate_req: Box<UdpAssociateRequest>,
+    ) {
+        let Some(return_address) =
+            reply::MixnetAddress::new(associate_req.return_address, sender_tag)
+        else {
+            log::warn!(
+                "attempted to start udp association with no way of returning data back to the sender"
+            );
+            return;
+        };
+
+        let connection_id = associate_req.conn_id;
+        let controller_sender = self.controller_sender.clone();
+        let mix_input_sender = self.mix_input_sender.clone();
+        let request_filter = self.request_filter.clone();
+        let udp_associations = self.udp_associations.clone();
+        let mut shutdown = self.shutdown.get_handle();
+
+        tokio::spawn(async move {
+            let association = async {
+                let permit = udp_associations
+                    .try_acquire(&return_address)
+                    .map_err(|err| err.to_string())?;
+                socks5::udp::Association::new(
+                    connection_id,
+                    return_address.clone(),
+                    request_filter,
+                    permit,
+                )
+                .await
+                .map_err(|err| format!("failed to create udp association: {err}"))
+            }
+            .await;
+
+            let association = match association {
+                Ok(association) => association,
+                Err(message) => {
+                    log::warn!("{message}");
+                    let error_msg = MixnetMessage::new_connection_error(
+                        return_address,
+                        remote_version,
+                        connection_id,
+                        message,
+                    );
+                    mix_input_sender
+                        .send(error_msg)
+                        .await
+                        .expect("InputMessageReceiver has stopped receiving!");
+                    shutdown.mark_as_success();
+                    return;
+                }
+            };
+
+            let (datagram_sender, datagram_receiver) = mpsc::unbounded();
+            controller_sender
+                .unbounded_send(ControllerCommand::InsertAssociation {
+                    connection_id,
+                    datagram_sender,
+                })
+                .unwrap();
+
+            log::info!("Starting udp association {connection_id}");
+            association
+                .run(
+                    remote_version,
+                    datagram_receiver,
+                    mix_input_sender,
+                    shutdown,
+                )
+                .await;
+
+            // association is done - remove the access channel from the controller
+            controller_sender
+                .unbounded_send(ControllerCommand::Remove { connection_id })
+                .unwrap();
+            log::info!("Udp association {connection_id} is finished");
+        });
+    }
+
+    fn handle_bind(
+        &self,
+        remote_version: RequestVersion<Socks5Request>,
+        sender_tag: Option<AnonymousSenderTag>,
+        bind_req: Box<ConnectRequest>,
+    ) {
+        let Some(return_address) = reply::MixnetAddress::new(bind_req.return_address, sender_tag)
+        else {
+            log::warn!(
+                "attempted to start bind listener with no way of returning data back to the sender"
+            );
+            return;
+        };
+
+        let expected_peer = bind_req.remote_addr;
+        let connection_id = bind_req.conn_id;
+        let traffic_config = self.config.base.debug.traffic;
+        let packet_size = traffic_config
+            .secondary_packet_size
+            .unwrap_or(traffic_config.primary_packet_size);
+
+        let controller_sender = self.controller_sender.clone();
+        let mix_input_sender = self.mix_input_sender.clone();
+        let lane_queue_lengths = self.mixnet_client.shared_lane_queue_lengths();
+        let request_filter = self.request_filter.clone();
+        let bind_listeners = self.bind_listeners.clone();
+        let bind_enabled = self.config.network_requester.enable_bind;
+        let announced_ip = self.config.network_requester.bind_announced_address;
+        let mut shutdown = self.shutdown.get_handle();
+
+        tokio::spawn(async move {
+            let send_bind_event = |event| {
+                let mix_input_sender = mix_input_sender.clone();
+                let msg = MixnetMessage::new_bind_response(
+                    return_address.clone(),
+                    remote_version.clone(),
+                    connection_id,
+                    event,
+                );
+                async move {
+                    mix_input_sender
+                        .send(msg)
+                        .await
+                        .expect("InputMessageReceiver has stopped receiving!");
+                }
+            };
+
+            let accepted = async {
+                if !bind_enabled {
+                    return Err(
+                        "BIND requests are not enabled on this network requester".to_string()
+                    );
+                }
+
+                // if the expected peer has been specified, make sure it's allowed
+                // before even starting the listener
+                if !socks5::bind::is_unspecified(&expected_peer)
+                    && !request_filter.check_address(&expected_peer).await
+                {
+                    return Err(format!("Address {expected_peer:?} failed filter check"));
+                }
+
+                let permit = bind_listeners
+                    .try_acquire(&return_address)
+                    .map_err(|err| err.to_string())?;
+                let listener =
+                    socks5::bind::BindListener::new(&expected_peer, announced_ip, permit)
+                        .await
+                        .map_err(|err| format!("failed to start the bind listener: {err}"))?;
+                let address = listener.announced_address();
+
+                log::info!("Listening on {address} for connection from {expected_peer}");
+                send_bind_event(BindEvent::Listening {
+                    address: address.to_string(),
+                })
+                .await;
+
+                listener
+                    .accept(&request_filter, &mut shutdown)
+                    .await
+                    .map_err(|err| format!("failed to accept the inbound connection: {err}"))
+            }
+            .await;
+
+            let (stream, peer) = match accepted {
+                Ok(accepted) => accepted,
+                Err(message) => {
+                    log::info!("{message}");
+                    send_bind_event(BindEvent::Failed { message }).await;
+                    shutdown.mark_as_success();
+                    return;
+                }
+            };
+
+            send_bind_event(BindEvent::Accepted {
+                peer: peer.to_string(),
+            })
+            .await;
+
+            let conn = socks5::tcp::Connection::from_stream(
+                connection_id,
+                peer.to_string(),
+                stream,
+                return_address.clone(),
+            );
+            Self::run_connection_proxy(
+                conn,
+                remote_version,
+                connection_id,
+                peer.to_string(),
+                packet_size,
+                controller_sender,
+                mix_input_sender,
+                lane_queue_lengths,
+                shutdown,
+            )
+            .await
+        });
+    }
+
+    fn handle_dns_query(
+        &self,
+        remote_version: RequestVersion<Socks5Request>,
+        sender_tag: Option<AnonymousSenderTag>,
+        query_req: Box<DnsQueryRequest>,
+    ) {
+        let Some(return_address) = reply::MixnetAddress::new(query_req.return_address, sender_tag)
+        else {
+            log::warn!("received dns query with no way of returning the answer back to the sender");
+            return;
+        };
+
+        let mix_input_sender = self.mix_input_sender.clone();
+        let request_filter = self.request_filter.clone();
+
+        // resolving might take a while, so don't block the processing of other requests
+        tokio::spawn(async move {
+            let DnsQueryRequest {
+                query_id,
+                name,
+                record_type,
+                ..
+            } = *query_req;
+            debug!("resolving {record_type:?} records of {name} for query {query_id}");
+
+            let result = socks5::dns::resolve(&name, record_type, &request_filter).await;
+            let response = MixnetMessage::new_dns_query_response(
+                return_address,
+                remote_version,
+                query_id,
+                result,
+            );
+            mix_input_sender
+                .send(response)
+                .await
+                .expect("InputMessageReceiver has stopped receiving!");
+        });
+    }
+
+    fn handle_datagram(&mut self, datagram: Datagram) {
+        self.controller_sender
+            .unbounded_send(ControllerCommand::new_send_datagram(datagram))
+            .unwrap()
+    }
+
+    fn handle_udp_disassociate(&mut self, connection_id: ConnectionId) {
+        // removing the association from the controller closes its channel,
+        // which in turn terminates the association itself
+        self.controller_sender
+            .unbounded_send(ControllerCommand::Remove { connection_id })
+            .unwrap()
+    }
+
     fn handle_proxy_send(&mut self, req: SendRequest) {
         self.controller_sender
             .unbounded_send(ControllerCommand::new_send(req.data))
@@ -6,8 +6,9 @@ use nym_service_providers_common::interface::{
     ControlRequest, ControlResponse, ProviderInterfaceVersion, RequestVersion,
 };
 use nym_socks5_requests::{
-    ConnectionId, SocketData, Socks5ProviderRequest, Socks5ProviderResponse, Socks5Request,
-    Socks5RequestContent, Socks5Response, Socks5ResponseContent,
+    BindEvent, ConnectionId, Datagram, DnsQueryId, DnsQueryResult, SocketData,
+    Socks5ProviderRequest, Socks5ProviderResponse, Socks5Request, Socks5RequestContent,
+    Socks5Response, Socks5ResponseContent,
 };
 use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
@@ -92,6 +93,47 @@ impl MixnetMessage {
         Self::new_provider_response(address, connection_id, msg)
     }
 
+    pub(crate) fn new_datagram_response(
+        address: MixnetAddress,
+        request_version: RequestVersion<Socks5Request>,
+        datagram: Datagram,
+    ) -> Self {
+        let connection_id = datagram.connection_id;
+        let res = Socks5Response::new_datagram(request_version.provider_protocol, datagram);
+        let msg =
+            Socks5ProviderResponse::new_provider_data(request_version.provider_interface, res);
+
+        Self::new_provider_response(address, connection_id, msg)
+    }
+
+    pub(crate) fn new_bind_response(
+        address: MixnetAddress,
+        request_version: RequestVersion<Socks5Request>,
+        connection_id: ConnectionId,
+        event: BindEvent,
+    ) -> Self {
+        let res = Socks5Response::new_bind(request_version.provider_protocol, connection_id, event);
+        let msg =
+            Socks5ProviderResponse::new_provider_data(request_version.provider_interface, res);
+
+        Self::new_provider_response(address, connection_id, msg)
+    }
+
+    pub(crate) fn new_dns_query_response(
+        address: MixnetAddress,
+        request_version: RequestVersion<Socks5Request>,
+        query_id: DnsQueryId,
+        result: DnsQueryResult,
+    ) -> Self {
+        let res =
+            Socks5Response::new_dns_query(request_version.provider_protocol, query_id, result);
+        let msg =
+            Socks5ProviderResponse::new_provider_data(request_version.provider_interface, res);
+
+        // each query gets its own lane, akin to connections
+        Self::new_provider_response(address, query_id, msg)
+    }
+
     #[allow(dead_code)]
     pub(crate) fn new_control_request<A: Into<MixnetAddress>>(
         address: A,
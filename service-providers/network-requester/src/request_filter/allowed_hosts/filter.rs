@@ -9,6 +9,7 @@ use publicsuffix::Psl;
 use std::{
     net::{IpAddr, SocketAddr},
     str::FromStr,
+    sync::Arc,
 };
 use tokio::sync::Mutex;
 use url::Url;
@@ -36,8 +37,8 @@ enum RequestHost {
 pub(crate) struct OutboundRequestFilter {
     pub(super) allowed_hosts: StoredAllowedHosts,
     pub(super) standard_list: StandardList,
-    root_domain_list: publicsuffix::List,
-    unknown_hosts: Mutex<HostsStore>,
+    root_domain_list: Arc<publicsuffix::List>,
+    unknown_hosts: Arc<Mutex<HostsStore>>,
 }
 
 /// The official URL of the list
@@ -75,8 +76,19 @@ impl OutboundRequestFilter {
         OutboundRequestFilter {
             allowed_hosts,
             standard_list,
-            root_domain_list: domain_list,
-            unknown_hosts: Mutex::new(unknown_hosts),
+            root_domain_list: Arc::new(domain_list),
+            unknown_hosts: Arc::new(Mutex::new(unknown_hosts)),
+        }
+    }
+
+    /// Create a new `OutboundRequestFilter` with the provided `allowed_hosts` list
+    /// that shares the rest of its state, such as the standard list, with this one.
+    pub(crate) fn with_allowed_hosts(&self, allowed_hosts: StoredAllowedHosts) -> Self {
+        OutboundRequestFilter {
+            allowed_hosts,
+            standard_list: self.standard_list.clone(),
+            root_domain_list: Arc::clone(&self.root_domain_list),
+            unknown_hosts: Arc::clone(&self.unknown_hosts),
         }
     }
 
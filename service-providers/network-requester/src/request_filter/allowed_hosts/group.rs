@@ -33,6 +33,18 @@ impl HostsGroup {
         HostsGroup { domains, ip_nets }
     }
 
+    /// Returns all the hosts in this group in their textual representation, sorted.
+    pub(crate) fn rules(&self) -> Vec<String> {
+        let mut rules: Vec<_> = self
+            .domains
+            .iter()
+            .cloned()
+            .chain(self.ip_nets.iter().map(ToString::to_string))
+            .collect();
+        rules.sort();
+        rules
+    }
+
     pub(crate) fn contains_domain(&self, host: &str) -> bool {
         self.domains.contains(&host.to_string())
     }
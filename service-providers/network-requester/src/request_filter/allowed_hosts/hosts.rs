@@ -44,10 +44,18 @@ impl HostsStore {
         }
     }
 
-    pub(crate) fn try_reload(&mut self) -> io::Result<()> {
-        let hosts = Self::load_from_storefile(&self.storefile)?;
-        self.data = HostsGroup::new(hosts);
-        Ok(())
+    /// Attempts to load the HostsStore from an existing storefile.
+    pub(crate) fn try_load<P: AsRef<Path>>(storefile: P) -> io::Result<HostsStore> {
+        let storefile = storefile.as_ref().to_path_buf();
+        let hosts = Self::load_from_storefile(&storefile)?;
+        Ok(HostsStore {
+            storefile,
+            data: HostsGroup::new(hosts),
+        })
+    }
+
+    pub(crate) fn rules(&self) -> Vec<String> {
+        self.data.rules()
     }
 
     pub(crate) fn contains_domain(&self, host: &str) -> bool {
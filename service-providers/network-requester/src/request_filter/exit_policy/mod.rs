@@ -2,17 +2,19 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use crate::error::NetworkRequesterError;
-use log::trace;
 use nym_exit_policy::client::get_exit_policy;
 use nym_exit_policy::ExitPolicy;
-use nym_socks5_requests::RemoteAddress;
 use reqwest::IntoUrl;
-use tokio::net::lookup_host;
+use std::net::SocketAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
 use url::Url;
 
 pub struct ExitPolicyRequestFilter {
     upstream: Option<Url>,
     policy: ExitPolicy,
+
+    /// Unix timestamp of when the policy got retrieved from the upstream (if any).
+    last_updated: u64,
 }
 
 impl ExitPolicyRequestFilter {
@@ -21,9 +23,16 @@ impl ExitPolicyRequestFilter {
             .into_url()
             .map_err(|source| NetworkRequesterError::MalformedExitPolicyUpstreamUrl { source })?;
 
+        let policy = get_exit_policy(url.clone()).await?;
+        let last_updated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or_default();
+
         Ok(ExitPolicyRequestFilter {
-            upstream: Some(url.clone()),
-            policy: get_exit_policy(url).await?,
+            upstream: Some(url),
+            policy,
+            last_updated,
         })
     }
 
@@ -31,6 +40,7 @@ impl ExitPolicyRequestFilter {
         ExitPolicyRequestFilter {
             upstream: None,
             policy,
+            last_updated: 0,
         }
     }
 
@@ -42,32 +52,26 @@ impl ExitPolicyRequestFilter {
         self.upstream.as_ref()
     }
 
-    pub(crate) async fn check(
-        &self,
-        remote: &RemoteAddress,
-    ) -> Result<bool, NetworkRequesterError> {
-        // try to convert the remote to a proper socket address
-        let addrs = lookup_host(remote)
-            .await
-            .map_err(|source| NetworkRequesterError::CouldNotResolveHost {
-                remote: remote.to_string(),
-                source,
-            })?
-            .collect::<Vec<_>>();
-
-        // I'm honestly not sure if it's possible to return an Ok with an empty iterator,
-        // but might as well guard against that
-        if addrs.is_empty() {
-            return Err(NetworkRequesterError::EmptyResolvedAddresses {
-                remote: remote.to_string(),
-            });
-        }
+    /// Unix timestamp of when the policy got retrieved from the upstream.
+    /// It's always 0 if there's no upstream.
+    pub fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
 
-        trace!("{remote} has been resolved to {addrs:?}");
+    /// Returns the rules of the policy in their torrc representation.
+    pub(crate) fn rules(&self) -> Vec<String> {
+        self.policy
+            .format_as_torrc()
+            .lines()
+            .map(ToString::to_string)
+            .collect()
+    }
 
+    /// Checks the addresses the remote has been resolved to against the exit policy.
+    pub(crate) fn check(&self, addrs: &[SocketAddr]) -> Result<bool, NetworkRequesterError> {
         // if the remote decided to give us an address that can resolve to multiple socket addresses,
         // they'd better make sure all of them are allowed by the exit policy.
-        for addr in addrs {
+        for &addr in addrs {
             if !self
                 .policy
                 .allows_sockaddr(&addr)
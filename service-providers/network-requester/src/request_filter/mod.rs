@@ -4,20 +4,28 @@
 use crate::config::{self, Config};
 use crate::error::NetworkRequesterError;
 use crate::request_filter::allowed_hosts::standard_list::StandardListUpdater;
-use crate::request_filter::allowed_hosts::stored_allowed_hosts::{
-    start_allowed_list_reloader, StoredAllowedHosts,
-};
+use crate::request_filter::allowed_hosts::stored_allowed_hosts::StoredAllowedHosts;
 use crate::request_filter::allowed_hosts::{OutboundRequestFilter, StandardList};
 use crate::request_filter::exit_policy::ExitPolicyRequestFilter;
-use log::{info, warn};
-use nym_exit_policy::ExitPolicy;
+use crate::request_filter::reloader::{ReloadTrigger, RequestFilterReloader, RulesDiff};
+use crate::request_filter::resolver::{system_nameservers, CachingResolver};
+use log::{debug, info, warn};
+use nym_exit_policy::{is_reserved_address, ExitPolicy};
 use nym_socks5_requests::RemoteAddress;
 use nym_task::TaskHandle;
-use std::sync::Arc;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
 
 /// Old request filtering based on the allowed.list files.
 pub mod allowed_hosts;
 pub mod exit_policy;
+mod reloader;
+mod resolver;
+
+/// Port of the DNS service, used for checking whether the exit policy allows resolving names.
+const DNS_PORT: u16 = 53;
 
 enum RequestFilterInner {
     AllowList {
@@ -25,16 +33,94 @@ enum RequestFilterInner {
         filter: OutboundRequestFilter,
     },
     ExitPolicy {
-        policy_filter: ExitPolicyRequestFilter,
+        policy_filter: Arc<ExitPolicyRequestFilter>,
     },
 }
 
+impl RequestFilterInner {
+    /// Attempts to create a fresh copy of this filter by reloading all of its rules
+    /// from their sources. Returns `None` if there's nothing that could be reloaded.
+    async fn reloaded(
+        &self,
+        allowed_list_location: &Path,
+    ) -> Result<Option<Self>, NetworkRequesterError> {
+        match self {
+            RequestFilterInner::AllowList { open_proxy, filter } => {
+                if *open_proxy {
+                    return Ok(None);
+                }
+                let allowed_hosts = StoredAllowedHosts::try_load(allowed_list_location)?;
+                Ok(Some(RequestFilterInner::AllowList {
+                    open_proxy: false,
+                    filter: filter.with_allowed_hosts(allowed_hosts),
+                }))
+            }
+            RequestFilterInner::ExitPolicy { policy_filter } => {
+                let Some(upstream) = policy_filter.upstream() else {
+                    return Ok(None);
+                };
+                let policy_filter = ExitPolicyRequestFilter::new_upstream(upstream.clone()).await?;
+                Ok(Some(RequestFilterInner::ExitPolicy {
+                    policy_filter: Arc::new(policy_filter),
+                }))
+            }
+        }
+    }
+
+    async fn rules(&self) -> Vec<String> {
+        match self {
+            RequestFilterInner::AllowList { filter, .. } => {
+                filter.allowed_hosts().get().await.rules()
+            }
+            RequestFilterInner::ExitPolicy { policy_filter } => policy_filter.rules(),
+        }
+    }
+}
+
+struct SharedRequestFilter {
+    current: RwLock<Arc<RequestFilterInner>>,
+    allowed_list_location: PathBuf,
+
+    // shared between all versions of the filter so that reloads wouldn't flush the cache
+    resolver: CachingResolver,
+    allow_reserved_addresses: bool,
+
+    // nameservers the system resolver forwards the dns queries of the clients to
+    nameservers: Vec<IpAddr>,
+
+    // makes sure we're not running multiple reloads at the same time
+    reload_lock: Mutex<()>,
+}
+
 #[derive(Clone)]
 pub struct RequestFilter {
-    inner: Arc<RequestFilterInner>,
+    inner: Arc<SharedRequestFilter>,
 }
 
 impl RequestFilter {
+    fn from_inner(inner: RequestFilterInner, config: &Config) -> Self {
+        RequestFilter {
+            inner: Arc::new(SharedRequestFilter {
+                current: RwLock::new(Arc::new(inner)),
+                allowed_list_location: config.storage_paths.allowed_list_location.clone(),
+                resolver: CachingResolver::new(config.network_requester_debug.dns_cache_ttl),
+                allow_reserved_addresses: config.network_requester.allow_reserved_addresses,
+                nameservers: system_nameservers(),
+                reload_lock: Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Returns the currently used version of the filter.
+    /// Note that it might get replaced at any point if the filter gets reloaded.
+    fn current(&self) -> Arc<RequestFilterInner> {
+        self.inner
+            .current
+            .read()
+            .expect("request filter lock is poisoned")
+            .clone()
+    }
+
     pub(crate) async fn new(config: &Config) -> Result<Self, NetworkRequesterError> {
         if config.network_requester.use_deprecated_allow_list {
             info!("setting up allow-list based 'OutboundRequestFilter'...");
@@ -45,11 +131,43 @@ impl RequestFilter {
         }
     }
 
-    pub fn current_exit_policy_filter(&self) -> Option<&ExitPolicyRequestFilter> {
-        match &*self.inner {
+    pub fn current_exit_policy_filter(&self) -> Option<Arc<ExitPolicyRequestFilter>> {
+        match &*self.current() {
             RequestFilterInner::AllowList { .. } => None,
-            RequestFilterInner::ExitPolicy { policy_filter } => Some(policy_filter),
+            RequestFilterInner::ExitPolicy { policy_filter } => Some(Arc::clone(policy_filter)),
+        }
+    }
+
+    /// Reloads all the filter rules from their sources, i.e. the allowed.list file
+    /// or the upstream exit policy, and atomically replaces the currently used filter.
+    // used by the processes embedding the network requester
+    #[allow(unused)]
+    pub async fn request_reload(&self) -> Result<(), NetworkRequesterError> {
+        self.reload(ReloadTrigger::Requested).await
+    }
+
+    async fn reload(&self, trigger: ReloadTrigger) -> Result<(), NetworkRequesterError> {
+        let _reload_guard = self.inner.reload_lock.lock().await;
+
+        let current = self.current();
+        let Some(reloaded) = current.reloaded(&self.inner.allowed_list_location).await? else {
+            debug!("there's nothing to reload in the request filter ({trigger})");
+            return Ok(());
+        };
+
+        let diff = RulesDiff::new(&current.rules().await, &reloaded.rules().await);
+        if diff.is_empty() {
+            debug!("reloaded the request filter after {trigger}: {diff}");
+        } else {
+            info!("reloaded the request filter after {trigger}: {diff}");
         }
+
+        *self
+            .inner
+            .current
+            .write()
+            .expect("request filter lock is poisoned") = Arc::new(reloaded);
+        Ok(())
     }
 
     pub(crate) async fn start_update_tasks(
@@ -57,7 +175,14 @@ impl RequestFilter {
         config: &config::Debug,
         task_handle: &TaskHandle,
     ) {
-        match &*self.inner {
+        let reloader = || {
+            RequestFilterReloader::new(
+                self.clone(),
+                task_handle.get_handle().named("RequestFilterReloader"),
+            )
+        };
+
+        match &*self.current() {
             RequestFilterInner::AllowList { open_proxy, filter } => {
                 // if we're running in open proxy, we don't have to spawn any refreshers,
                 // after all, we're going to be accepting all requests regardless
@@ -74,17 +199,19 @@ impl RequestFilter {
                 )
                 .start();
 
-                // start the allowed.list watcher and updater
-                start_allowed_list_reloader(
-                    filter.allowed_hosts(),
-                    task_handle
-                        .get_handle()
-                        .named("stored_allowed_hosts_reloader"),
-                )
-                .await;
+                // start the allowed.list watcher and the reloader
+                reloader()
+                    .with_allowed_list_watcher(self.inner.allowed_list_location.clone())
+                    .start();
             }
-            RequestFilterInner::ExitPolicy { .. } => {
-                // nothing to do for the exit policy (yet; we might add a refresher at some point)
+            RequestFilterInner::ExitPolicy { policy_filter } => {
+                // without an upstream source there's nothing that could ever change
+                if policy_filter.upstream().is_none() {
+                    return;
+                }
+                reloader()
+                    .with_upstream_refresh(config.exit_policy_update_interval)
+                    .start();
             }
         }
     }
@@ -96,13 +223,11 @@ impl RequestFilter {
             allowed_hosts::HostsStore::new(&config.storage_paths.unknown_list_location);
 
         // TODO: technically if we're running open proxy, we don't have to be loading anything here
-        RequestFilter {
-            inner: Arc::new(RequestFilterInner::AllowList {
-                open_proxy: config.network_requester.open_proxy,
-                filter: OutboundRequestFilter::new(allowed_hosts, standard_list, unknown_hosts)
-                    .await,
-            }),
-        }
+        let inner = RequestFilterInner::AllowList {
+            open_proxy: config.network_requester.open_proxy,
+            filter: OutboundRequestFilter::new(allowed_hosts, standard_list, unknown_hosts).await,
+        };
+        Self::from_inner(inner, config)
     }
 
     async fn new_exit_policy_filter(config: &Config) -> Result<Self, NetworkRequesterError> {
@@ -116,27 +241,86 @@ impl RequestFilter {
                 .ok_or(NetworkRequesterError::NoUpstreamExitPolicy)?;
             ExitPolicyRequestFilter::new_upstream(upstream_url.clone()).await?
         };
-        Ok(RequestFilter {
-            inner: Arc::new(RequestFilterInner::ExitPolicy { policy_filter }),
-        })
+        let inner = RequestFilterInner::ExitPolicy {
+            policy_filter: Arc::new(policy_filter),
+        };
+        Ok(Self::from_inner(inner, config))
+    }
+
+    fn contains_reserved_address<'a>(
+        &self,
+        mut addresses: impl Iterator<Item = &'a IpAddr>,
+    ) -> Option<&'a IpAddr> {
+        if self.inner.allow_reserved_addresses {
+            return None;
+        }
+        addresses.find(|address| is_reserved_address(address))
+    }
+
+    /// Resolves the remote and checks it against the filter. If it's allowed, returns the addresses
+    /// that should be used for reaching it, so that it wouldn't get resolved again
+    /// (possibly to something else) when connecting.
+    pub(crate) async fn resolve_allowed(
+        &self,
+        address: &RemoteAddress,
+    ) -> Result<Option<Vec<SocketAddr>>, NetworkRequesterError> {
+        let current = self.current();
+        if let RequestFilterInner::AllowList { open_proxy, filter } = &*current {
+            if !*open_proxy && !filter.check(address).await {
+                return Ok(None);
+            }
+        }
+
+        let resolved = self.inner.resolver.resolve(address).await?;
+
+        let resolved_ips: Vec<_> = resolved.iter().map(SocketAddr::ip).collect();
+        if let Some(reserved) = self.contains_reserved_address(resolved_ips.iter()) {
+            warn!("'{address}' has been resolved to a reserved address {reserved}");
+            return Ok(None);
+        }
+
+        if let RequestFilterInner::ExitPolicy { policy_filter } = &*current {
+            if !policy_filter.check(&resolved)? {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(resolved))
     }
 
     pub(crate) async fn check_address(&self, address: &RemoteAddress) -> bool {
-        match &*self.inner {
+        match self.resolve_allowed(address).await {
+            Err(err) => {
+                warn!("failed to validate '{address}' against the request filter: {err}");
+                false
+            }
+            Ok(res) => res.is_some(),
+        }
+    }
+
+    /// Checks whether the remote client is allowed to learn the addresses the name resolves to.
+    pub(crate) async fn check_dns_query(&self, name: &str, addresses: &[IpAddr]) -> bool {
+        if let Some(reserved) = self.contains_reserved_address(addresses.iter()) {
+            warn!("'{name}' has been resolved to a reserved address {reserved}");
+            return false;
+        }
+
+        match &*self.current() {
             RequestFilterInner::AllowList { open_proxy, filter } => {
                 if *open_proxy {
                     return true;
                 }
-                filter.check(address).await
+                filter.check(name).await
             }
+            // the only traffic resolving the name generates is the query sent to the upstream
+            // nameservers, so the policy has to explicitly accept the DNS port for all of them
             RequestFilterInner::ExitPolicy { policy_filter } => {
-                match policy_filter.check(address).await {
-                    Err(err) => {
-                        warn!("failed to validate '{address}' against the exit policy: {err}");
-                        false
-                    }
-                    Ok(res) => res,
-                }
+                self.inner.nameservers.iter().all(|nameserver| {
+                    policy_filter
+                        .policy()
+                        .allows(nameserver, DNS_PORT)
+                        .unwrap_or(false)
+                })
             }
         }
     }
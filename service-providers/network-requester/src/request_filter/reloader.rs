@@ -0,0 +1,318 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::request_filter::RequestFilter;
+use async_file_watcher::{AsyncFileWatcher, FileWatcherEventReceiver};
+use futures::channel::mpsc;
+use futures::StreamExt;
+use nym_task::TaskClient;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::future::pending;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::time::{interval_at, Instant, Interval};
+
+/// The reason for reloading the request filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReloadTrigger {
+    /// The local allowed.list file has been modified.
+    AllowedListChanged,
+
+    /// It's time to refetch the exit policy from its upstream source.
+    UpstreamRefresh,
+
+    /// The process has received SIGHUP.
+    HangupSignal,
+
+    /// The reload has been explicitly requested, for example by the process embedding
+    /// the network requester.
+    Requested,
+}
+
+impl Display for ReloadTrigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReloadTrigger::AllowedListChanged => write!(f, "allowed list change"),
+            ReloadTrigger::UpstreamRefresh => write!(f, "upstream refresh"),
+            ReloadTrigger::HangupSignal => write!(f, "SIGHUP"),
+            ReloadTrigger::Requested => write!(f, "explicit request"),
+        }
+    }
+}
+
+/// Difference between the rules of two versions of the request filter.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct RulesDiff {
+    pub(crate) added: Vec<String>,
+    pub(crate) removed: Vec<String>,
+
+    /// Indicates whether the rules that are present in both versions are now applied in different
+    /// order. That matters for the exit policy, where the first matching rule wins.
+    pub(crate) reordered: bool,
+}
+
+impl RulesDiff {
+    pub(crate) fn new(old: &[String], new: &[String]) -> Self {
+        let old_set: HashSet<_> = old.iter().collect();
+        let new_set: HashSet<_> = new.iter().collect();
+
+        let added = new
+            .iter()
+            .filter(|rule| !old_set.contains(rule))
+            .cloned()
+            .collect();
+        let removed = old
+            .iter()
+            .filter(|rule| !new_set.contains(rule))
+            .cloned()
+            .collect();
+
+        let retained_old = old.iter().filter(|rule| new_set.contains(rule));
+        let retained_new = new.iter().filter(|rule| old_set.contains(rule));
+        let reordered = !retained_old.eq(retained_new);
+
+        RulesDiff {
+            added,
+            removed,
+            reordered,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && !self.reordered
+    }
+}
+
+impl Display for RulesDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes");
+        }
+        write!(
+            f,
+            "{} added, {} removed",
+            self.added.len(),
+            self.removed.len()
+        )?;
+        if self.reordered {
+            write!(f, ", existing rules reordered")?;
+        }
+        for rule in &self.added {
+            write!(f, "\n\t+ {rule}")?;
+        }
+        for rule in &self.removed {
+            write!(f, "\n\t- {rule}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reloads the request filter whenever any of the reload triggers fires.
+pub(crate) struct RequestFilterReloader {
+    request_filter: RequestFilter,
+    allowed_list_events: Option<FileWatcherEventReceiver>,
+    upstream_refresh: Option<Interval>,
+
+    // Listens to shutdown commands from higher up
+    shutdown_listener: TaskClient,
+}
+
+impl RequestFilterReloader {
+    pub(crate) fn new(request_filter: RequestFilter, shutdown_listener: TaskClient) -> Self {
+        RequestFilterReloader {
+            request_filter,
+            allowed_list_events: None,
+            upstream_refresh: None,
+            shutdown_listener,
+        }
+    }
+
+    /// Reload the filter whenever the provided allowed.list file gets modified.
+    #[must_use]
+    pub(crate) fn with_allowed_list_watcher(mut self, allowed_list: PathBuf) -> Self {
+        let (events_sender, events_receiver) = mpsc::unbounded();
+        let watcher = AsyncFileWatcher::new_file_changes_watcher(allowed_list, events_sender)
+            .expect("failed to create file watcher");
+
+        start_watcher(watcher, self.shutdown_listener.clone());
+        self.allowed_list_events = Some(events_receiver);
+        self
+    }
+
+    /// Periodically refetch the exit policy from its upstream source.
+    #[must_use]
+    pub(crate) fn with_upstream_refresh(mut self, refresh_interval: Duration) -> Self {
+        // we've just fetched the policy, so there's no point in doing it again immediately
+        self.upstream_refresh = Some(interval_at(
+            Instant::now() + refresh_interval,
+            refresh_interval,
+        ));
+        self
+    }
+
+    async fn reload(&self, trigger: ReloadTrigger) {
+        if let Err(err) = self.request_filter.reload(trigger).await {
+            log::error!("failed to reload the request filter after {trigger}: {err}");
+        }
+    }
+
+    pub(crate) async fn run(&mut self) {
+        let mut hangup = HangupListener::new();
+
+        while !self.shutdown_listener.is_shutdown() {
+            tokio::select! {
+                biased;
+                _ = self.shutdown_listener.recv() => {
+                    log::trace!("RequestFilterReloader: Received shutdown");
+                }
+                event = next_file_event(&mut self.allowed_list_events) => {
+                    let Some(event) = event else {
+                        log::trace!("RequestFilterReloader: file watcher channel has terminated");
+                        self.allowed_list_events = None;
+                        continue
+                    };
+                    log::debug!("the allowed list has changed - {event:?}");
+                    self.reload(ReloadTrigger::AllowedListChanged).await
+                }
+                _ = next_tick(&mut self.upstream_refresh) => {
+                    self.reload(ReloadTrigger::UpstreamRefresh).await
+                }
+                _ = hangup.recv() => {
+                    self.reload(ReloadTrigger::HangupSignal).await
+                }
+            }
+        }
+
+        log::debug!("RequestFilterReloader: Exiting");
+    }
+
+    pub(crate) fn start(mut self) {
+        tokio::spawn(async move { self.run().await });
+    }
+}
+
+async fn next_file_event<T>(events: &mut Option<mpsc::UnboundedReceiver<T>>) -> Option<T> {
+    match events {
+        Some(events) => events.next().await,
+        None => pending().await,
+    }
+}
+
+async fn next_tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => pending().await,
+    }
+}
+
+struct HangupListener {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl HangupListener {
+    #[cfg(unix)]
+    fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let signal = signal(SignalKind::hangup())
+            .map_err(|err| log::warn!("failed to register the SIGHUP listener: {err}"))
+            .ok();
+        HangupListener { signal }
+    }
+
+    #[cfg(not(unix))]
+    fn new() -> Self {
+        HangupListener {}
+    }
+
+    #[cfg(unix)]
+    async fn recv(&mut self) {
+        match &mut self.signal {
+            Some(signal) => {
+                if signal.recv().await.is_none() {
+                    self.signal = None;
+                    pending().await
+                }
+            }
+            None => pending().await,
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn recv(&mut self) {
+        pending().await
+    }
+}
+
+async fn run_watcher(mut watcher: AsyncFileWatcher, mut shutdown: TaskClient) {
+    tokio::select! {
+        biased;
+        _ = shutdown.recv() => {
+            log::trace!("AsyncFileWatcher: Received shutdown");
+        }
+        res = watcher.watch() => {
+            log::trace!("AsyncFileWatcher: finished with {res:?}");
+        }
+    }
+    log::debug!("AsyncFileWatcher: Exiting");
+}
+
+fn start_watcher(watcher: AsyncFileWatcher, shutdown: TaskClient) {
+    tokio::spawn(async move { run_watcher(watcher, shutdown).await });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn identical_rules() {
+        let old = rules(&["accept *:80", "reject *:*"]);
+        let diff = RulesDiff::new(&old, &old);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "no changes");
+    }
+
+    #[test]
+    fn added_and_removed_rules() {
+        let old = rules(&["nymtech.net", "1.2.3.0/24", "example.com"]);
+        let new = rules(&["nymtech.net", "example.com", "nym.com"]);
+        let diff = RulesDiff::new(&old, &new);
+        assert_eq!(
+            diff,
+            RulesDiff {
+                added: rules(&["nym.com"]),
+                removed: rules(&["1.2.3.0/24"]),
+                reordered: false,
+            }
+        );
+        assert_eq!(
+            diff.to_string(),
+            "1 added, 1 removed\n\t+ nym.com\n\t- 1.2.3.0/24"
+        );
+    }
+
+    #[test]
+    fn reordered_rules() {
+        let old = rules(&["accept *:80", "reject 1.2.3.4:*", "reject *:*"]);
+        let new = rules(&[
+            "reject 1.2.3.4:*",
+            "accept *:80",
+            "accept *:443",
+            "reject *:*",
+        ]);
+        let diff = RulesDiff::new(&old, &new);
+        assert_eq!(diff.added, rules(&["accept *:443"]));
+        assert!(diff.removed.is_empty());
+        assert!(diff.reordered);
+        assert!(!diff.is_empty());
+    }
+}
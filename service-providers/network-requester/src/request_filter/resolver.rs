@@ -0,0 +1,193 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::error::NetworkRequesterError;
+use log::{trace, warn};
+use nym_socks5_requests::RemoteAddress;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::net::lookup_host;
+
+/// The maximum number of lookups we're willing to keep in the cache.
+const MAX_CACHED_LOOKUPS: usize = 4096;
+
+/// Configuration of the system resolver used for the lookups.
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Nameserver used by the system resolver if none has been configured.
+const DEFAULT_NAMESERVER: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+struct CachedLookup {
+    addresses: Vec<SocketAddr>,
+    expires_at: Instant,
+}
+
+/// Resolves the remote addresses requested by the clients, caching the results for the configured
+/// duration. The addresses returned from here are the ones that get checked against the request
+/// filter and are then used for the actual connection, so that the remote couldn't get resolved
+/// to something else in between (i.e. DNS rebinding).
+pub(crate) struct CachingResolver {
+    ttl: Duration,
+    cache: Mutex<HashMap<RemoteAddress, CachedLookup>>,
+}
+
+impl CachingResolver {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        CachingResolver {
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cached(&self, remote: &RemoteAddress, now: Instant) -> Option<Vec<SocketAddr>> {
+        let cache = self.cache.lock().expect("resolver cache lock is poisoned");
+        cache
+            .get(remote)
+            .filter(|lookup| lookup.expires_at > now)
+            .map(|lookup| lookup.addresses.clone())
+    }
+
+    fn insert(&self, remote: &RemoteAddress, addresses: Vec<SocketAddr>, now: Instant) {
+        if self.ttl.is_zero() {
+            return;
+        }
+
+        let mut cache = self.cache.lock().expect("resolver cache lock is poisoned");
+        if cache.len() >= MAX_CACHED_LOOKUPS {
+            cache.retain(|_, lookup| lookup.expires_at > now);
+            if cache.len() >= MAX_CACHED_LOOKUPS {
+                cache.clear();
+            }
+        }
+        cache.insert(
+            remote.clone(),
+            CachedLookup {
+                addresses,
+                expires_at: now + self.ttl,
+            },
+        );
+    }
+
+    pub(crate) async fn resolve(
+        &self,
+        remote: &RemoteAddress,
+    ) -> Result<Vec<SocketAddr>, NetworkRequesterError> {
+        // there's nothing to resolve (or cache) if we already got an ip address
+        if let Ok(address) = remote.parse::<SocketAddr>() {
+            return Ok(vec![address]);
+        }
+
+        if let Some(addresses) = self.cached(remote, Instant::now()) {
+            trace!("using cached resolution of {remote}: {addresses:?}");
+            return Ok(addresses);
+        }
+
+        let addresses = lookup_host(remote)
+            .await
+            .map_err(|source| NetworkRequesterError::CouldNotResolveHost {
+                remote: remote.to_string(),
+                source,
+            })?
+            .collect::<Vec<_>>();
+
+        // I'm honestly not sure if it's possible to return an Ok with an empty iterator,
+        // but might as well guard against that
+        if addresses.is_empty() {
+            return Err(NetworkRequesterError::EmptyResolvedAddresses {
+                remote: remote.to_string(),
+            });
+        }
+
+        trace!("{remote} has been resolved to {addresses:?}");
+        self.insert(remote, addresses.clone(), Instant::now());
+        Ok(addresses)
+    }
+}
+
+/// Returns the addresses of the nameservers the system resolver sends its queries to.
+pub(crate) fn system_nameservers() -> Vec<IpAddr> {
+    let nameservers = match std::fs::read_to_string(RESOLV_CONF) {
+        Ok(conf) => parse_nameservers(&conf),
+        Err(err) => {
+            warn!("failed to read the resolver configuration from {RESOLV_CONF}: {err}");
+            Vec::new()
+        }
+    };
+
+    if nameservers.is_empty() {
+        vec![DEFAULT_NAMESERVER]
+    } else {
+        nameservers
+    }
+}
+
+fn parse_nameservers(conf: &str) -> Vec<IpAddr> {
+    conf.lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace();
+            if tokens.next()? != "nameserver" {
+                return None;
+            }
+            // link-local ipv6 nameservers might include the scope, i.e. 'fe80::1%eth0'
+            tokens.next()?.split('%').next()?.parse().ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nameservers_are_parsed_from_resolv_conf() {
+        let conf = "# generated by NetworkManager\n\
+                    search example.com\n\
+                    nameserver 1.1.1.1\n\
+                    ;nameserver 9.9.9.9\n\
+                    nameserver fe80::1%eth0\n\
+                    nameserver not-an-ip\n\
+                    options edns0\n";
+        assert_eq!(
+            parse_nameservers(conf),
+            vec![
+                "1.1.1.1".parse::<IpAddr>().unwrap(),
+                "fe80::1".parse::<IpAddr>().unwrap()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn ip_addresses_are_not_cached() {
+        let resolver = CachingResolver::new(Duration::from_secs(60));
+        let addresses = resolver.resolve(&"1.2.3.4:80".to_string()).await.unwrap();
+        assert_eq!(addresses, vec!["1.2.3.4:80".parse().unwrap()]);
+        assert!(resolver.cache.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn cached_lookups_expire() {
+        let resolver = CachingResolver::new(Duration::from_secs(60));
+        let remote = "nymtech.net:443".to_string();
+        let addresses = vec!["1.2.3.4:443".parse().unwrap()];
+        let now = Instant::now();
+
+        resolver.insert(&remote, addresses.clone(), now);
+        assert_eq!(resolver.cached(&remote, now), Some(addresses));
+        assert_eq!(
+            resolver.cached(&remote, now + Duration::from_secs(61)),
+            None
+        );
+    }
+
+    #[test]
+    fn zero_ttl_disables_caching() {
+        let resolver = CachingResolver::new(Duration::ZERO);
+        let remote = "nymtech.net:443".to_string();
+        let now = Instant::now();
+
+        resolver.insert(&remote, vec!["1.2.3.4:443".parse().unwrap()], now);
+        assert_eq!(resolver.cached(&remote, now), None);
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::reply::MixnetAddress;
+use crate::request_filter::RequestFilter;
+use crate::socks5::limits::{ClientLimiter, ClientPermit, LimitError};
+use log::debug;
+use nym_socks5_requests::RemoteAddress;
+use nym_task::TaskClient;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+/// How long we're willing to wait for the inbound connection before giving up.
+const ACCEPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Maximum number of listeners a single client is allowed to have open at any given time.
+pub(crate) const MAX_BIND_LISTENERS_PER_CLIENT: usize = 8;
+
+/// Maximum number of listeners open at any given time across all clients.
+/// Unlike the per-client limit, it does not rely on the identity chosen by the client.
+pub(crate) const MAX_BIND_LISTENERS: usize = 128;
+
+/// Well-known public addresses used for determining which local interface is used
+/// for reaching the internet. No packets are actually sent to them.
+const PUBLIC_PROBE_V4: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 53);
+const PUBLIC_PROBE_V6: SocketAddr = SocketAddr::new(
+    IpAddr::V6(Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111)),
+    53,
+);
+
+/// A listener for a single inbound TCP connection created on behalf of a SOCKS5 BIND request.
+pub(crate) struct BindListener {
+    listener: TcpListener,
+
+    /// The address reported to the client as the one the peer should connect to.
+    announced_address: SocketAddr,
+
+    /// The address of the peer we're expecting the connection from.
+    /// If it's not specified, we're accepting the first connection that passes the request filter.
+    expected_peer: Option<IpAddr>,
+
+    /// Makes sure the client does not exceed its limit of listeners for as long as this one exists.
+    _permit: ClientPermit,
+}
+
+impl BindListener {
+    pub(crate) async fn new(
+        expected_peer: &RemoteAddress,
+        announced_ip: Option<IpAddr>,
+        permit: ClientPermit,
+    ) -> io::Result<Self> {
+        let expected_peer = tokio::net::lookup_host(expected_peer)
+            .await?
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("could not resolve {expected_peer}"),
+                )
+            })?;
+
+        let (local_ip, reachable_ip, expected_peer) = if expected_peer.ip().is_unspecified() {
+            // we don't know where the connection is going to come from, so listen on all interfaces
+            // and announce the one used for reaching the internet
+            let probe = match expected_peer {
+                SocketAddr::V4(_) => PUBLIC_PROBE_V4,
+                SocketAddr::V6(_) => PUBLIC_PROBE_V6,
+            };
+            (
+                expected_peer.ip(),
+                local_address_towards(probe).await?,
+                None,
+            )
+        } else {
+            let local_ip = local_address_towards(expected_peer).await?;
+            (local_ip, local_ip, Some(expected_peer.ip()))
+        };
+
+        let listener = TcpListener::bind((local_ip, 0)).await?;
+        let announced_address = SocketAddr::new(
+            announced_ip.unwrap_or(reachable_ip),
+            listener.local_addr()?.port(),
+        );
+
+        Ok(BindListener {
+            listener,
+            announced_address,
+            expected_peer,
+            _permit: permit,
+        })
+    }
+
+    /// The address the peer should connect to.
+    pub(crate) fn announced_address(&self) -> SocketAddr {
+        self.announced_address
+    }
+
+    /// Waits for the expected peer to connect, rejecting any other inbound connections.
+    pub(crate) async fn accept(
+        self,
+        request_filter: &RequestFilter,
+        shutdown: &mut TaskClient,
+    ) -> io::Result<(TcpStream, SocketAddr)> {
+        let accept_expected = async {
+            loop {
+                let (stream, peer) = self.listener.accept().await?;
+                if let Some(expected) = self.expected_peer {
+                    if peer.ip() != expected {
+                        debug!("rejecting inbound connection from unexpected peer {peer}");
+                        continue;
+                    }
+                }
+                if !request_filter.check_address(&peer.to_string()).await {
+                    debug!(
+                        "rejecting inbound connection from {peer} as it failed the filter check"
+                    );
+                    continue;
+                }
+                return Ok((stream, peer));
+            }
+        };
+
+        tokio::select! {
+            biased;
+            _ = shutdown.recv() => Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "received shutdown while waiting for the inbound connection",
+            )),
+            accepted = tokio::time::timeout(ACCEPT_TIMEOUT, accept_expected) => accepted.map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "timed out while waiting for the inbound connection",
+                )
+            })?,
+        }
+    }
+}
+
+/// Keeps track of the number of bind listeners opened by each client and in total.
+#[derive(Clone)]
+pub(crate) struct BindListeners(ClientLimiter);
+
+impl Default for BindListeners {
+    fn default() -> Self {
+        BindListeners(ClientLimiter::new(
+            "bind listeners",
+            MAX_BIND_LISTENERS_PER_CLIENT,
+            MAX_BIND_LISTENERS,
+        ))
+    }
+}
+
+impl BindListeners {
+    /// Attempts to reserve a new listener for the client.
+    pub(crate) fn try_acquire(&self, client: &MixnetAddress) -> Result<ClientPermit, LimitError> {
+        self.0.try_acquire(client)
+    }
+
+    #[cfg(test)]
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Checks whether the client has left the expected peer unspecified, i.e. `0.0.0.0:0`,
+/// in which case we'll accept connection from anyone that passes the request filter.
+pub(crate) fn is_unspecified(expected_peer: &RemoteAddress) -> bool {
+    expected_peer
+        .parse::<SocketAddr>()
+        .map(|addr| addr.ip().is_unspecified())
+        .unwrap_or_default()
+}
+
+/// Finds out which local address would be used for reaching the specified remote,
+/// so that the listener would be bound on an address the remote could actually connect to.
+async fn local_address_towards(remote: SocketAddr) -> io::Result<IpAddr> {
+    let unspecified: IpAddr = match remote {
+        SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    // connecting an udp socket does not send any packets
+    let probe = UdpSocket::bind((unspecified, 0)).await?;
+    probe.connect(remote).await?;
+    Ok(probe.local_addr()?.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+    use nym_task::TaskManager;
+    use tokio::net::TcpSocket;
+
+    fn client(tag: u8) -> MixnetAddress {
+        MixnetAddress::Anonymous(AnonymousSenderTag::from_bytes([tag; 16]))
+    }
+
+    async fn open_request_filter() -> RequestFilter {
+        // loopback addresses are reserved, so they have to be explicitly allowed
+        let mut config = Config::new("bind-listener-test")
+            .with_open_proxy(true)
+            .with_old_allow_list(false);
+        config.network_requester.allow_reserved_addresses = true;
+        RequestFilter::new(&config).await.unwrap()
+    }
+
+    #[test]
+    fn unspecified_peers() {
+        assert!(is_unspecified(&"0.0.0.0:0".to_string()));
+        assert!(is_unspecified(&"[::]:0".to_string()));
+        assert!(!is_unspecified(&"1.2.3.4:0".to_string()));
+        assert!(!is_unspecified(&"example.com:80".to_string()));
+    }
+
+    #[test]
+    fn listeners_are_capped() {
+        let listeners = BindListeners::default();
+
+        let permits: Vec<_> = (0..MAX_BIND_LISTENERS_PER_CLIENT)
+            .map(|_| listeners.try_acquire(&client(1)).unwrap())
+            .collect();
+        assert!(matches!(
+            listeners.try_acquire(&client(1)),
+            Err(LimitError::ClientLimitReached { .. })
+        ));
+
+        drop(permits);
+        assert!(listeners.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unspecified_peer_listener_does_not_announce_unspecified_address() {
+        let listeners = BindListeners::default();
+        let permit = listeners.try_acquire(&client(1)).unwrap();
+
+        let listener = match BindListener::new(&"0.0.0.0:0".to_string(), None, permit).await {
+            Ok(listener) => listener,
+            // the test environment might not have any route towards the internet
+            Err(err) => {
+                eprintln!("skipping the test as no route is available: {err}");
+                return;
+            }
+        };
+        assert!(!listener.announced_address().ip().is_unspecified());
+        assert_ne!(listener.announced_address().port(), 0);
+    }
+
+    #[tokio::test]
+    async fn configured_address_is_announced() {
+        let listeners = BindListeners::default();
+        let permit = listeners.try_acquire(&client(1)).unwrap();
+        let announced: IpAddr = "203.0.113.7".parse().unwrap();
+
+        let listener = BindListener::new(&"127.0.0.1:0".to_string(), Some(announced), permit)
+            .await
+            .unwrap();
+        assert_eq!(listener.announced_address().ip(), announced);
+        assert_eq!(
+            listener.announced_address().port(),
+            listener.listener.local_addr().unwrap().port()
+        );
+    }
+
+    #[tokio::test]
+    async fn only_the_expected_peer_is_accepted() {
+        let request_filter = open_request_filter().await;
+        let listeners = BindListeners::default();
+        let permit = listeners.try_acquire(&client(1)).unwrap();
+
+        let listener = BindListener::new(&"127.0.0.1:0".to_string(), None, permit)
+            .await
+            .unwrap();
+        let address = listener.announced_address();
+        assert_eq!(address.ip(), IpAddr::from(Ipv4Addr::LOCALHOST));
+
+        let task_manager = TaskManager::default();
+        let mut shutdown = task_manager.subscribe();
+        let accepted =
+            tokio::spawn(async move { listener.accept(&request_filter, &mut shutdown).await });
+
+        // a connection from a different address gets rejected
+        let unexpected = TcpSocket::new_v4().unwrap();
+        unexpected.bind("127.0.0.2:0".parse().unwrap()).unwrap();
+        let _unexpected = unexpected.connect(address).await.unwrap();
+
+        let expected = TcpStream::connect(address).await.unwrap();
+        let (_, peer) = accepted.await.unwrap().unwrap();
+        assert_eq!(peer, expected.local_addr().unwrap());
+
+        // the listener is gone, so its permit has been released
+        assert!(listeners.is_empty());
+    }
+
+    #[tokio::test]
+    async fn accepting_stops_on_shutdown() {
+        let request_filter = open_request_filter().await;
+        let listeners = BindListeners::default();
+        let permit = listeners.try_acquire(&client(1)).unwrap();
+
+        let listener = BindListener::new(&"127.0.0.1:0".to_string(), None, permit)
+            .await
+            .unwrap();
+
+        let task_manager = TaskManager::default();
+        let mut shutdown = task_manager.subscribe();
+        let accepted =
+            tokio::spawn(async move { listener.accept(&request_filter, &mut shutdown).await });
+
+        task_manager.signal_shutdown().unwrap();
+        let err = tokio::time::timeout(Duration::from_secs(5), accepted)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        assert!(listeners.is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::request_filter::RequestFilter;
+use log::{debug, info};
+use nym_socks5_requests::{DnsQueryResult, DnsRecordType};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// How long we're willing to wait for the system resolver before giving up.
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resolves the DNS query on behalf of a remote client using the system resolver,
+/// making sure both the name and the resulting addresses pass the request filter.
+pub(crate) async fn resolve(
+    name: &str,
+    record_type: DnsRecordType,
+    request_filter: &RequestFilter,
+) -> DnsQueryResult {
+    // the port is irrelevant here, but the resolver requires one
+    let lookup = tokio::net::lookup_host((name, 0));
+    let resolved = match tokio::time::timeout(RESOLVE_TIMEOUT, lookup).await {
+        Ok(Ok(resolved)) => resolved,
+        Ok(Err(err)) => {
+            debug!("failed to resolve {name}: {err}");
+            return DnsQueryResult::Failed {
+                message: err.to_string(),
+            };
+        }
+        Err(_) => {
+            debug!("timed out while resolving {name}");
+            return DnsQueryResult::Failed {
+                message: "timed out while resolving the name".to_string(),
+            };
+        }
+    };
+
+    let mut addresses: Vec<IpAddr> = resolved
+        .map(|addr| addr.ip())
+        .filter(|ip| match record_type {
+            DnsRecordType::A => ip.is_ipv4(),
+            DnsRecordType::Aaaa => ip.is_ipv6(),
+        })
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+
+    if !request_filter.check_dns_query(name, &addresses).await {
+        info!("Dns query for {name} failed filter check");
+        return DnsQueryResult::Refused;
+    }
+
+    DnsQueryResult::Resolved { addresses }
+}
@@ -0,0 +1,155 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::reply::MixnetAddress;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub(crate) enum LimitError {
+    #[error("the limit of {max} concurrent {resource} per client has been reached")]
+    ClientLimitReached { resource: &'static str, max: usize },
+
+    #[error("the limit of {max} concurrent {resource} has been reached")]
+    GlobalLimitReached { resource: &'static str, max: usize },
+}
+
+#[derive(Default)]
+struct Active {
+    per_client: HashMap<String, usize>,
+    total: usize,
+}
+
+/// Keeps track of the number of long-lived resources, such as listening or UDP sockets,
+/// held by each client and in total.
+/// The global limit does not rely on the identity chosen by the client, so a single sender
+/// can't go over it by rotating its identity.
+#[derive(Clone)]
+pub(crate) struct ClientLimiter {
+    resource: &'static str,
+    max_per_client: usize,
+    max_total: usize,
+    active: Arc<Mutex<Active>>,
+}
+
+impl ClientLimiter {
+    pub(crate) fn new(resource: &'static str, max_per_client: usize, max_total: usize) -> Self {
+        ClientLimiter {
+            resource,
+            max_per_client,
+            max_total,
+            active: Default::default(),
+        }
+    }
+
+    /// Attempts to reserve a new resource for the client. Fails if either the client
+    /// or this network requester as a whole has already reached its limit.
+    pub(crate) fn try_acquire(&self, client: &MixnetAddress) -> Result<ClientPermit, LimitError> {
+        let client = client_key(client);
+        let mut active = self.active.lock().expect("client limiter lock is poisoned");
+        if active.total >= self.max_total {
+            return Err(LimitError::GlobalLimitReached {
+                resource: self.resource,
+                max: self.max_total,
+            });
+        }
+        let count = active.per_client.entry(client.clone()).or_default();
+        if *count >= self.max_per_client {
+            return Err(LimitError::ClientLimitReached {
+                resource: self.resource,
+                max: self.max_per_client,
+            });
+        }
+        *count += 1;
+        active.total += 1;
+
+        Ok(ClientPermit {
+            limiter: self.clone(),
+            client,
+        })
+    }
+
+    fn release(&self, client: &str) {
+        let mut active = self.active.lock().expect("client limiter lock is poisoned");
+        if let Some(count) = active.per_client.get_mut(client) {
+            *count -= 1;
+            if *count == 0 {
+                active.per_client.remove(client);
+            }
+            active.total -= 1;
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_empty(&self) -> bool {
+        let active = self.active.lock().unwrap();
+        active.total == 0 && active.per_client.is_empty()
+    }
+}
+
+/// Reservation of a single resource of a client that's released when dropped.
+pub(crate) struct ClientPermit {
+    limiter: ClientLimiter,
+    client: String,
+}
+
+impl Drop for ClientPermit {
+    fn drop(&mut self) {
+        self.limiter.release(&self.client)
+    }
+}
+
+fn client_key(client: &MixnetAddress) -> String {
+    match client {
+        MixnetAddress::Known(recipient) => recipient.to_string(),
+        MixnetAddress::Anonymous(tag) => tag.to_base58_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+
+    fn client(tag: u8) -> MixnetAddress {
+        MixnetAddress::Anonymous(AnonymousSenderTag::from_bytes([tag; 16]))
+    }
+
+    #[test]
+    fn resources_are_capped_per_client() {
+        let limiter = ClientLimiter::new("things", 4, 16);
+
+        let permits: Vec<_> = (0..4)
+            .map(|_| limiter.try_acquire(&client(1)).unwrap())
+            .collect();
+        assert!(matches!(
+            limiter.try_acquire(&client(1)),
+            Err(LimitError::ClientLimitReached { max: 4, .. })
+        ));
+
+        // other clients are not affected
+        assert!(limiter.try_acquire(&client(2)).is_ok());
+
+        drop(permits);
+        assert!(limiter.try_acquire(&client(1)).is_ok());
+        assert!(limiter.is_empty());
+    }
+
+    #[test]
+    fn resources_are_capped_globally() {
+        let limiter = ClientLimiter::new("things", 4, 16);
+
+        // a single sender rotating its identity can't go over the global limit
+        let permits: Vec<_> = (0..16)
+            .map(|i| limiter.try_acquire(&client(i)).unwrap())
+            .collect();
+        assert!(matches!(
+            limiter.try_acquire(&client(255)),
+            Err(LimitError::GlobalLimitReached { max: 16, .. })
+        ));
+
+        drop(permits);
+        assert!(limiter.try_acquire(&client(255)).is_ok());
+        assert!(limiter.is_empty());
+    }
+}
@@ -11,6 +11,7 @@ use nym_sphinx::params::PacketSize;
 use nym_task::connections::LaneQueueLengths;
 use nym_task::TaskClient;
 use std::io;
+use std::net::SocketAddr;
 use tokio::net::TcpStream;
 
 /// An outbound TCP connection between the Socks5 service provider, which makes
@@ -25,12 +26,15 @@ pub(crate) struct Connection {
 }
 
 impl Connection {
+    /// Connects to the remote using the addresses it has been resolved to (and checked against
+    /// the request filter) beforehand, rather than resolving it again.
     pub(crate) async fn new(
         id: ConnectionId,
         address: RemoteAddress,
+        resolved: &[SocketAddr],
         return_address: reply::MixnetAddress,
     ) -> io::Result<Self> {
-        let conn = TcpStream::connect(&address).await?;
+        let conn = TcpStream::connect(resolved).await?;
 
         Ok(Connection {
             id,
@@ -40,6 +44,20 @@ impl Connection {
         })
     }
 
+    pub(crate) fn from_stream(
+        id: ConnectionId,
+        address: RemoteAddress,
+        conn: TcpStream,
+        return_address: reply::MixnetAddress,
+    ) -> Self {
+        Connection {
+            id,
+            address,
+            conn: Some(conn),
+            return_address,
+        }
+    }
+
     pub(crate) async fn run_proxy(
         &mut self,
         remote_version: RequestVersion<Socks5Request>,
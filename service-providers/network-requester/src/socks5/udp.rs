@@ -0,0 +1,521 @@
+// Copyright 2024 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::error::NetworkRequesterError;
+use crate::reply::{self, MixnetMessage};
+use crate::request_filter::RequestFilter;
+use crate::socks5::limits::{ClientLimiter, ClientPermit, LimitError};
+use futures::StreamExt;
+use log::{debug, info, trace, warn};
+use nym_service_providers_common::interface::RequestVersion;
+use nym_socks5_proxy_helpers::connection_controller::DatagramReceiver;
+use nym_socks5_proxy_helpers::proxy_runner::MixProxySender;
+use nym_socks5_requests::{ConnectionId, Datagram, RemoteAddress, Socks5Request};
+use nym_task::TaskClient;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+/// The biggest possible UDP datagram we might receive from a remote.
+const MAX_UDP_DATAGRAM_SIZE: usize = 65535;
+
+/// If no datagrams are exchanged within this period, the association is considered abandoned
+/// and gets closed.
+const ASSOCIATION_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Maximum number of allowed destinations remembered by a single association. Once it's reached,
+/// the least recently used destination gets evicted and stops being accepted as a source.
+const MAX_ALLOWED_DESTINATIONS: usize = 1024;
+
+/// Maximum number of rejected destinations remembered by a single association before they're
+/// forgotten (and thus checked against the request filter again).
+const MAX_REJECTED_DESTINATIONS: usize = 1024;
+
+/// Maximum number of UDP associations a single client is allowed to have open at any given time.
+pub(crate) const MAX_ASSOCIATIONS_PER_CLIENT: usize = 16;
+
+/// Maximum number of UDP associations open at any given time across all clients.
+pub(crate) const MAX_ASSOCIATIONS: usize = 512;
+
+/// Keeps track of the number of UDP associations opened by each client and in total.
+#[derive(Clone)]
+pub(crate) struct Associations(ClientLimiter);
+
+impl Default for Associations {
+    fn default() -> Self {
+        Associations(ClientLimiter::new(
+            "udp associations",
+            MAX_ASSOCIATIONS_PER_CLIENT,
+            MAX_ASSOCIATIONS,
+        ))
+    }
+}
+
+impl Associations {
+    /// Attempts to reserve a new association for the client.
+    pub(crate) fn try_acquire(
+        &self,
+        client: &reply::MixnetAddress,
+    ) -> Result<ClientPermit, LimitError> {
+        self.0.try_acquire(client)
+    }
+}
+
+/// Allowed destinations alongside their resolved addresses, evicting the least recently used ones.
+#[derive(Default)]
+struct AllowedDestinations {
+    /// Resolved address of each destination and when it has been last used.
+    resolved: HashMap<RemoteAddress, (SocketAddr, u64)>,
+
+    /// Number of destinations resolved to each of the addresses. We're only accepting datagrams
+    /// coming from any of them.
+    sources: HashMap<SocketAddr, usize>,
+
+    /// Monotonically increasing counter used for determining the least recently used destination.
+    uses: u64,
+}
+
+impl AllowedDestinations {
+    fn get(&mut self, destination: &RemoteAddress) -> Option<SocketAddr> {
+        self.uses += 1;
+        let (resolved, last_used) = self.resolved.get_mut(destination)?;
+        *last_used = self.uses;
+        Some(*resolved)
+    }
+
+    fn insert(&mut self, destination: RemoteAddress, resolved: SocketAddr) {
+        if self.resolved.len() >= MAX_ALLOWED_DESTINATIONS {
+            self.evict_least_recently_used();
+        }
+        self.uses += 1;
+        if let Some((previous, _)) = self.resolved.insert(destination, (resolved, self.uses)) {
+            self.remove_source(previous);
+        }
+        *self.sources.entry(resolved).or_default() += 1;
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let Some(oldest) = self
+            .resolved
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(destination, _)| destination.clone())
+        else {
+            return;
+        };
+        if let Some((resolved, _)) = self.resolved.remove(&oldest) {
+            self.remove_source(resolved);
+        }
+    }
+
+    fn remove_source(&mut self, source: SocketAddr) {
+        if let Some(count) = self.sources.get_mut(&source) {
+            *count -= 1;
+            if *count == 0 {
+                self.sources.remove(&source);
+            }
+        }
+    }
+
+    fn is_allowed_source(&self, source: &SocketAddr) -> bool {
+        self.sources.contains_key(source)
+    }
+}
+
+/// An outbound UDP socket used by the Socks5 service provider for relaying datagrams
+/// on behalf of a single UDP association of a remote client.
+pub(crate) struct Association {
+    id: ConnectionId,
+    socket: UdpSocket,
+    return_address: reply::MixnetAddress,
+    request_filter: RequestFilter,
+
+    /// Destinations that have passed the request filter alongside their resolved addresses.
+    allowed_destinations: AllowedDestinations,
+
+    /// Destinations that have failed the request filter check, so that the remote client
+    /// would only get notified about it once.
+    rejected_destinations: HashSet<RemoteAddress>,
+
+    /// Makes sure the client does not exceed its limit of associations for as long as this one exists.
+    _permit: ClientPermit,
+}
+
+impl Association {
+    pub(crate) async fn new(
+        id: ConnectionId,
+        return_address: reply::MixnetAddress,
+        request_filter: RequestFilter,
+        permit: ClientPermit,
+    ) -> io::Result<Self> {
+        // attempt to get a dual-stack socket and fallback to IPv4-only one if that's not possible
+        let socket = match UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0)).await {
+            Ok(socket) => socket,
+            Err(err) => {
+                debug!("failed to bind IPv6 UDP socket: {err}. falling back to IPv4");
+                UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?
+            }
+        };
+
+        Ok(Association {
+            id,
+            socket,
+            return_address,
+            request_filter,
+            allowed_destinations: Default::default(),
+            rejected_destinations: HashSet::new(),
+            _permit: permit,
+        })
+    }
+
+    fn is_dual_stack(&self) -> bool {
+        self.socket
+            .local_addr()
+            .map(|addr| addr.is_ipv6())
+            .unwrap_or_default()
+    }
+
+    /// Checks the destination against the request filter and resolves it.
+    /// Returns `Ok(None)` if the destination is not allowed.
+    async fn resolve_destination(
+        &mut self,
+        remote_addr: &RemoteAddress,
+    ) -> Result<Option<SocketAddr>, NetworkRequesterError> {
+        if let Some(resolved) = self.allowed_destinations.get(remote_addr) {
+            return Ok(Some(resolved));
+        }
+        if self.rejected_destinations.contains(remote_addr) {
+            return Ok(None);
+        }
+
+        // use the very same address that has been checked against the filter
+        let Some(resolved) = self
+            .request_filter
+            .resolve_allowed(remote_addr)
+            .await?
+            .and_then(|resolved| resolved.first().copied())
+        else {
+            // forgetting the rejections only means they're going to be checked again
+            if self.rejected_destinations.len() >= MAX_REJECTED_DESTINATIONS {
+                self.rejected_destinations.clear();
+            }
+            self.rejected_destinations.insert(remote_addr.clone());
+            return Ok(None);
+        };
+
+        self.allowed_destinations
+            .insert(remote_addr.clone(), resolved);
+        Ok(Some(resolved))
+    }
+
+    async fn handle_outbound(
+        &mut self,
+        datagram: Datagram,
+        remote_version: &RequestVersion<Socks5Request>,
+        mix_sender: &MixProxySender<MixnetMessage>,
+    ) {
+        let destination = match self.resolve_destination(&datagram.remote_addr).await {
+            Ok(Some(destination)) => destination,
+            Ok(None) => {
+                let log_msg = format!(
+                    "Datagram destination {:?} failed filter check",
+                    datagram.remote_addr
+                );
+                info!("{log_msg}");
+                let error_msg = MixnetMessage::new_connection_error(
+                    self.return_address.clone(),
+                    remote_version.clone(),
+                    self.id,
+                    log_msg,
+                );
+                mix_sender
+                    .send(error_msg)
+                    .await
+                    .expect("InputMessageReceiver has stopped receiving!");
+                return;
+            }
+            Err(err) => {
+                warn!("failed to resolve {}: {err}", datagram.remote_addr);
+                return;
+            }
+        };
+
+        // dual-stack sockets require using IPv4-mapped addresses for IPv4 destinations
+        let target = match destination {
+            SocketAddr::V4(v4) if self.is_dual_stack() => {
+                SocketAddr::new(IpAddr::V6(v4.ip().to_ipv6_mapped()), v4.port())
+            }
+            other => other,
+        };
+
+        trace!(
+            "sending {} bytes to {} on association {}",
+            datagram.data.len(),
+            datagram.remote_addr,
+            self.id
+        );
+        if let Err(err) = self.socket.send_to(&datagram.data, target).await {
+            warn!("failed to send datagram to {}: {err}", datagram.remote_addr)
+        }
+    }
+
+    /// Relays the datagram back to the client if it came from one of the contacted destinations.
+    /// Returns whether the datagram has been accepted.
+    async fn handle_inbound(
+        &self,
+        data: &[u8],
+        source: SocketAddr,
+        remote_version: &RequestVersion<Socks5Request>,
+        mix_sender: &MixProxySender<MixnetMessage>,
+    ) -> bool {
+        let source = canonical_address(source);
+        if !self.allowed_destinations.is_allowed_source(&source) {
+            debug!(
+                "dropping datagram from {source} on association {} as it has never been contacted",
+                self.id
+            );
+            return false;
+        }
+
+        let datagram = Datagram::new(self.id, source.to_string(), data.to_vec());
+        let msg = MixnetMessage::new_datagram_response(
+            self.return_address.clone(),
+            remote_version.clone(),
+            datagram,
+        );
+        mix_sender
+            .send(msg)
+            .await
+            .expect("InputMessageReceiver has stopped receiving!");
+        true
+    }
+
+    pub(crate) async fn run(
+        mut self,
+        remote_version: RequestVersion<Socks5Request>,
+        mut datagram_receiver: DatagramReceiver,
+        mix_sender: MixProxySender<MixnetMessage>,
+        mut shutdown: TaskClient,
+    ) {
+        let mut buf = vec![0u8; MAX_UDP_DATAGRAM_SIZE];
+
+        // only the traffic we actually relay keeps the association alive,
+        // so that strangers couldn't do it by spamming the socket
+        let idle_timeout = tokio::time::sleep(ASSOCIATION_IDLE_TIMEOUT);
+        tokio::pin!(idle_timeout);
+
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                _ = shutdown.recv() => {
+                    trace!("Association {}: Received shutdown", self.id);
+                }
+                _ = &mut idle_timeout => {
+                    debug!("Association {} has been idle for too long", self.id);
+                    break;
+                }
+                datagram = datagram_receiver.next() => match datagram {
+                    Some(datagram) => {
+                        idle_timeout.as_mut().reset(Instant::now() + ASSOCIATION_IDLE_TIMEOUT);
+                        self.handle_outbound(datagram, &remote_version, &mix_sender).await
+                    }
+                    None => {
+                        trace!("Association {}: Stopping since channel closed", self.id);
+                        break;
+                    }
+                },
+                received = self.socket.recv_from(&mut buf) => match received {
+                    Ok((n, source)) => {
+                        if self.handle_inbound(&buf[..n], source, &remote_version, &mix_sender).await {
+                            idle_timeout.as_mut().reset(Instant::now() + ASSOCIATION_IDLE_TIMEOUT);
+                        }
+                    }
+                    // this might happen if we received an ICMP error for a previously sent datagram
+                    Err(err) => debug!("failed to receive datagram on association {}: {err}", self.id),
+                }
+            }
+        }
+        shutdown.mark_as_success();
+    }
+}
+
+/// Converts IPv4-mapped IPv6 addresses, as returned by dual-stack sockets, back into IPv4 addresses.
+fn canonical_address(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V6(v6) => match v6.ip().to_ipv4_mapped() {
+            Some(v4) => SocketAddr::new(IpAddr::V4(v4), v6.port()),
+            None => addr,
+        },
+        addr => addr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use nym_service_providers_common::interface::ProviderInterfaceVersion;
+    use nym_socks5_requests::Socks5ProtocolVersion;
+    use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+    use nym_task::TaskManager;
+    use tokio::sync::mpsc;
+
+    struct RunningAssociation {
+        id: ConnectionId,
+        datagram_sender: nym_socks5_proxy_helpers::connection_controller::DatagramSender,
+        mix_receiver: mpsc::Receiver<MixnetMessage>,
+        _task_manager: TaskManager,
+    }
+
+    async fn start_association(id: ConnectionId) -> RunningAssociation {
+        // loopback addresses are reserved, so they have to be explicitly allowed
+        let mut config = Config::new("udp-association-test")
+            .with_open_proxy(true)
+            .with_old_allow_list(false);
+        config.network_requester.allow_reserved_addresses = true;
+        let request_filter = RequestFilter::new(&config).await.unwrap();
+
+        let return_address =
+            reply::MixnetAddress::Anonymous(AnonymousSenderTag::from_bytes(Default::default()));
+        let permit = Associations::default()
+            .try_acquire(&return_address)
+            .unwrap();
+        let association = Association::new(id, return_address, request_filter, permit)
+            .await
+            .unwrap();
+
+        let remote_version = RequestVersion::new(
+            ProviderInterfaceVersion::new_current(),
+            Socks5ProtocolVersion::new_current(),
+        );
+        let (datagram_sender, datagram_receiver) = futures::channel::mpsc::unbounded();
+        let (mix_sender, mix_receiver) = mpsc::channel(16);
+        let task_manager = TaskManager::default();
+        tokio::spawn(association.run(
+            remote_version,
+            datagram_receiver,
+            mix_sender,
+            task_manager.subscribe(),
+        ));
+
+        RunningAssociation {
+            id,
+            datagram_sender,
+            mix_receiver,
+            _task_manager: task_manager,
+        }
+    }
+
+    #[tokio::test]
+    async fn datagrams_are_relayed_in_both_directions() {
+        let remote = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let remote_addr = remote.local_addr().unwrap();
+
+        let mut association = start_association(42).await;
+        association
+            .datagram_sender
+            .unbounded_send(Datagram::new(
+                association.id,
+                remote_addr.to_string(),
+                b"ping".to_vec(),
+            ))
+            .unwrap();
+
+        let mut buf = [0u8; 64];
+        let (n, source) = remote.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+
+        remote.send_to(b"pong", source).await.unwrap();
+        let response =
+            tokio::time::timeout(Duration::from_secs(5), association.mix_receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(response.connection_id, association.id);
+        assert!(response.data.ends_with(b"pong"));
+    }
+
+    #[tokio::test]
+    async fn datagrams_from_sources_that_were_never_contacted_are_dropped() {
+        let remote = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let remote_addr = remote.local_addr().unwrap();
+        let stranger = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+
+        let mut association = start_association(42).await;
+        association
+            .datagram_sender
+            .unbounded_send(Datagram::new(
+                association.id,
+                remote_addr.to_string(),
+                b"ping".to_vec(),
+            ))
+            .unwrap();
+
+        let mut buf = [0u8; 64];
+        let (_, association_addr) = remote.recv_from(&mut buf).await.unwrap();
+
+        stranger
+            .send_to(b"spoofed", association_addr)
+            .await
+            .unwrap();
+        remote.send_to(b"pong", association_addr).await.unwrap();
+
+        // only the datagram from the contacted remote is relayed back
+        let response =
+            tokio::time::timeout(Duration::from_secs(5), association.mix_receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+        assert!(response.data.ends_with(b"pong"));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), association.mix_receiver.recv())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn least_recently_used_destinations_are_evicted() {
+        let address = |i: usize| -> SocketAddr {
+            format!("10.0.{}.{}:53", i / 256, i % 256).parse().unwrap()
+        };
+        let mut destinations = AllowedDestinations::default();
+        for i in 0..MAX_ALLOWED_DESTINATIONS {
+            destinations.insert(address(i).to_string(), address(i));
+        }
+
+        // use the oldest destination so that the second one becomes the least recently used
+        assert_eq!(destinations.get(&address(0).to_string()), Some(address(0)));
+        destinations.insert("new".to_string(), address(MAX_ALLOWED_DESTINATIONS));
+
+        assert!(destinations.is_allowed_source(&address(0)));
+        assert!(!destinations.is_allowed_source(&address(1)));
+        assert!(destinations.get(&address(1).to_string()).is_none());
+        assert!(destinations.is_allowed_source(&address(2)));
+        assert!(destinations.is_allowed_source(&address(MAX_ALLOWED_DESTINATIONS)));
+    }
+
+    #[test]
+    fn sources_shared_by_destinations_are_kept_until_all_are_evicted() {
+        let shared: SocketAddr = "10.0.0.1:53".parse().unwrap();
+        let mut destinations = AllowedDestinations::default();
+        destinations.insert("a.example:53".to_string(), shared);
+        destinations.insert("b.example:53".to_string(), shared);
+
+        destinations.evict_least_recently_used();
+        assert!(destinations.is_allowed_source(&shared));
+        destinations.evict_least_recently_used();
+        assert!(!destinations.is_allowed_source(&shared));
+    }
+
+    #[test]
+    fn ipv4_mapped_addresses_are_converted_back() {
+        let mapped: SocketAddr = "[::ffff:1.2.3.4]:53".parse().unwrap();
+        assert_eq!(canonical_address(mapped), "1.2.3.4:53".parse().unwrap());
+
+        let v6: SocketAddr = "[2001:db8::1]:53".parse().unwrap();
+        assert_eq!(canonical_address(v6), v6);
+    }
+}
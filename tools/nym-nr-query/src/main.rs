@@ -142,7 +142,7 @@ fn new_supported_request_versions_request() -> Request {
 
 fn new_open_proxy_request() -> Request<Socks5Request> {
     let request_open_proxy = Socks5Request::new_query(
-        Socks5ProtocolVersion::new_current(),
+        Socks5ProtocolVersion::new_current().for_basic_requests(),
         QueryRequest::OpenProxy,
     );
     Request::new_provider_data(ProviderInterfaceVersion::new_current(), request_open_proxy)
@@ -150,7 +150,7 @@ fn new_open_proxy_request() -> Request<Socks5Request> {
 
 fn new_exit_policy_request() -> Request<Socks5Request> {
     let request_exit_policy = Socks5Request::new_query(
-        Socks5ProtocolVersion::new_current(),
+        Socks5ProtocolVersion::new_current().for_basic_requests(),
         QueryRequest::ExitPolicy,
     );
     Request::new_provider_data(ProviderInterfaceVersion::new_current(), request_exit_policy)
@@ -70,6 +70,15 @@ impl RequestWriter {
                     Socks5ResponseContent::NetworkData { content } => {
                         self.requests.try_send_data_to_go(content).await;
                     }
+                    Socks5ResponseContent::Datagram(datagram) => {
+                        console_error!("received a datagram even though we never opened any udp associations! - {datagram:?}")
+                    }
+                    Socks5ResponseContent::Bind(bind) => {
+                        console_error!("received a bind response even though we never sent any bind requests! - {bind:?}")
+                    }
+                    Socks5ResponseContent::DnsQuery(dns) => {
+                        console_error!("received a dns query response even though we never sent any dns queries! - {dns:?}")
+                    }
                 },
             },
         }
@@ -10,7 +10,7 @@ use wasm_client_core::Recipient;
 pub(crate) const PROVIDER_INTERFACE_VERSION: ProviderInterfaceVersion =
     ProviderInterfaceVersion::new_current();
 pub(crate) const SOCKS5_PROTOCOL_VERSION: Socks5ProtocolVersion =
-    Socks5ProtocolVersion::new_current();
+    Socks5ProtocolVersion::new_current().for_basic_requests();
 
 // for now explicitly attach return address, we can worry about surbs later
 pub(crate) fn socks5_connect_request(